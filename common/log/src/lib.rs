@@ -20,29 +20,258 @@
     unused_results
 )]
 
+use std::{ffi::CStr, fmt, io, path::PathBuf, str::FromStr};
+
+use opentelemetry::{
+    sdk::{trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig as _;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt as tfmt, layer::SubscriberExt as _,
+};
+
 pub use slog::{self, Drain};
 pub use slog_scope::{self as log, logger};
 
+/// Configuration of logging targets.
+#[derive(Clone, Debug)]
+pub struct LogConfig {
+    /// Verbosity level of logs ([`Info`] by default, if [`None`]).
+    ///
+    /// [`Info`]: slog::Level::Info
+    pub level: Option<slog::Level>,
+
+    /// Indicator whether logs should be written to STDOUT.
+    pub stdout: bool,
+
+    /// Path to a file logs should be written to (if any).
+    pub file: Option<PathBuf>,
+
+    /// [`LogRotation`] of the [`LogConfig::file`].
+    pub rotation: LogRotation,
+
+    /// Indicator whether logs should be sent to the local syslog.
+    pub syslog: bool,
+
+    /// Endpoint of an [OTLP] collector (gRPC) to export [`tracing`] spans
+    /// to (if any).
+    ///
+    /// [OTLP]: https://opentelemetry.io/docs/specs/otlp
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Default for LogConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            level: None,
+            stdout: true,
+            file: None,
+            rotation: LogRotation::default(),
+            syslog: false,
+            otlp_endpoint: None,
+        }
+    }
+}
+
+/// Period of rotating a log file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogRotation {
+    /// Log file is rotated every minute.
+    Minutely,
+
+    /// Log file is rotated every hour.
+    Hourly,
+
+    /// Log file is rotated every day.
+    #[default]
+    Daily,
+
+    /// Log file is never rotated.
+    Never,
+}
+
+impl FromStr for LogRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "minutely" => Self::Minutely,
+            "hourly" => Self::Hourly,
+            "daily" => Self::Daily,
+            "never" => Self::Never,
+            _ => return Err(format!("Unknown log rotation: {}", s)),
+        })
+    }
+}
+
+impl fmt::Display for LogRotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Minutely => "minutely",
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Never => "never",
+        })
+    }
+}
+
+impl From<LogRotation> for rolling::Rotation {
+    #[inline]
+    fn from(r: LogRotation) -> Self {
+        match r {
+            LogRotation::Minutely => Self::MINUTELY,
+            LogRotation::Hourly => Self::HOURLY,
+            LogRotation::Daily => Self::DAILY,
+            LogRotation::Never => Self::NEVER,
+        }
+    }
+}
+
+/// Guard of the global logger, that should be held as long as program runs.
+#[derive(Debug)]
+pub struct LogGuard {
+    /// Guard of the global [`slog`] logger.
+    _logger: slog_scope::GlobalLoggerGuard,
+
+    /// Guard flushing the non-blocking writer of the [`LogConfig::file`].
+    _file: Option<WorkerGuard>,
+
+    /// Guard flushing the spans exported to the [`LogConfig::otlp_endpoint`].
+    _otlp: Option<OtlpGuard>,
+}
+
+/// Guard flushing the [`tracing`] spans not exported yet to an [OTLP]
+/// collector, once dropped.
+///
+/// [OTLP]: https://opentelemetry.io/docs/specs/otlp
+#[derive(Debug)]
+struct OtlpGuard;
+
+impl Drop for OtlpGuard {
+    #[inline]
+    fn drop(&mut self) {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
 /// Initializes global logger with the given verbosity `level` ([`Info`] by
-/// default, if [`None`]), returning its guard that should be held as long as
-/// program runs.
+/// default, if [`None`]), writing to STDOUT only, and returning its guard
+/// that should be held as long as program runs.
 ///
 /// # Panics
 ///
 /// If failed to initialize logger.
 ///
 /// [`Info`]: slog::Level::Info
-pub fn init(level: Option<slog::Level>) -> slog_scope::GlobalLoggerGuard {
-    let guard = slog_scope::set_global_logger(main_logger(
-        level.unwrap_or(slog::Level::Info),
-    ));
+pub fn init(level: Option<slog::Level>) -> LogGuard {
+    init_with(&LogConfig {
+        level,
+        ..LogConfig::default()
+    })
+}
+
+/// Initializes global logger with the given [`LogConfig`], returning its guard
+/// that should be held as long as program runs.
+///
+/// Logs are passed to a layered [`tracing_subscriber`] registry, having a
+/// layer for each of the configured targets.
+///
+/// # Panics
+///
+/// If failed to initialize logger.
+pub fn init_with(cfg: &LogConfig) -> LogGuard {
+    let level = cfg.level.unwrap_or(slog::Level::Info);
+
+    let stdout = cfg
+        .stdout
+        .then(|| tfmt::layer().with_target(false).with_writer(io::stdout));
+
+    let (file, file_guard) = cfg
+        .file
+        .as_ref()
+        .map(|path| {
+            let dir = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or_else(|| ".".as_ref());
+            let name = path.file_name().unwrap_or_else(|| {
+                panic!("Invalid log file path: {}", path.display())
+            });
+            let (writer, guard) = tracing_appender::non_blocking(
+                rolling::RollingFileAppender::new(
+                    cfg.rotation.into(),
+                    dir,
+                    name,
+                ),
+            );
+            let layer = tfmt::layer()
+                .with_ansi(false)
+                .with_target(false)
+                .with_writer(writer);
+            (layer, guard)
+        })
+        .unzip();
+
+    let syslog = cfg.syslog.then(|| {
+        let identity = CStr::from_bytes_with_nul(b"ephyr\0").unwrap();
+        let (options, facility) = Default::default();
+        let writer = syslog_tracing::Syslog::new(identity, options, facility)
+            .unwrap_or_else(|| panic!("Failed to connect to syslog"));
+        tfmt::layer()
+            .with_ansi(false)
+            .with_target(false)
+            .without_time()
+            .with_writer(writer)
+    });
+
+    let otlp = cfg.otlp_endpoint.as_ref().map(|endpoint| {
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(trace::config().with_resource(Resource::new(
+                [KeyValue::new("service.name", "ephyr")],
+            )))
+            // Runs exporting in its own thread, as no `tokio` runtime exists
+            // yet.
+            .install_batch(opentelemetry::runtime::TokioCurrentThread)
+            .unwrap_or_else(|e| {
+                panic!("Failed to initialize OTLP exporter: {}", e)
+            });
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    });
+
+    let otlp_guard = otlp.is_some().then_some(OtlpGuard);
+
+    let subscriber = tracing_subscriber::registry()
+        .with(level_filter(level))
+        .with(stdout)
+        .with(file)
+        .with(syslog)
+        .with(otlp);
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        panic!("Failed to initialize logger: {}", e);
+    }
+
+    let logger = slog_scope::set_global_logger(main_logger(level));
     if let Err(e) = slog_stdlog::init() {
         panic!("Failed to initialize logger: {}", e);
     };
-    guard
+    LogGuard {
+        _logger: logger,
+        _file: file_guard,
+        _otlp: otlp_guard,
+    }
 }
 
-/// Creates, configures and returns main [`Logger`] of the application.
+/// Creates, configures and returns main [`Logger`] of the application,
+/// passing its records to the global [`tracing`] subscriber.
 ///
 /// [`Logger`]: slog::Logger
 #[must_use]
@@ -50,10 +279,7 @@ pub fn main_logger(level: slog::Level) -> slog::Logger {
     use slog::Drain as _;
     use slog_async::OverflowStrategy::Drop;
 
-    let decorator = slog_term::TermDecorator::new().build();
-    let drain = slog_term::CompactFormat::new(decorator).build().fuse();
-
-    let drain = drain
+    let drain = TracingDrain
         .filter_level(level)
         .filter(|rec| {
             // Disable annoying DEBUG logs from `hyper` crate.
@@ -69,3 +295,42 @@ pub fn main_logger(level: slog::Level) -> slog::Logger {
 
     slog::Logger::root(drain, slog::o!())
 }
+
+/// Converts the given [`slog::Level`] into a [`LevelFilter`].
+fn level_filter(level: slog::Level) -> LevelFilter {
+    match level {
+        slog::Level::Critical | slog::Level::Error => LevelFilter::ERROR,
+        slog::Level::Warning => LevelFilter::WARN,
+        slog::Level::Info => LevelFilter::INFO,
+        slog::Level::Debug => LevelFilter::DEBUG,
+        slog::Level::Trace => LevelFilter::TRACE,
+    }
+}
+
+/// [`slog::Drain`] passing all the records to the global [`tracing`]
+/// subscriber.
+#[derive(Clone, Copy, Debug)]
+struct TracingDrain;
+
+impl slog::Drain for TracingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(
+        &self,
+        rec: &slog::Record<'_>,
+        _: &slog::OwnedKVList,
+    ) -> Result<Self::Ok, Self::Err> {
+        let msg = rec.msg();
+        match rec.level() {
+            slog::Level::Critical | slog::Level::Error => {
+                tracing::error!("{}", msg);
+            }
+            slog::Level::Warning => tracing::warn!("{}", msg),
+            slog::Level::Info => tracing::info!("{}", msg),
+            slog::Level::Debug => tracing::debug!("{}", msg),
+            slog::Level::Trace => tracing::trace!("{}", msg),
+        }
+        Ok(())
+    }
+}
@@ -127,3 +127,11 @@ export const isFailoverInput = (input: any) => {
 export const escapeRegExp = (str: string) => {
   return str.replace(/[.*+?^${}()|[\]\\]/g, '\\$&'); // $& means the whole matched string
 };
+
+export const DEFAULT_RTMP_PORT = 1935;
+
+// Returns host to be used in RTMP URLs, omitting the port if it's a default
+// one.
+export const rtmpHost = (host: string, port?: number): string => {
+  return !port || port === DEFAULT_RTMP_PORT ? host : `${host}:${port}`;
+};
@@ -2,23 +2,45 @@
 //!
 //! [GraphQL]: https://graphql.com
 
-use std::collections::HashSet;
+use std::{collections::HashSet, convert::TryFrom, time::Duration};
 
 use actix_web::http::StatusCode;
 use anyhow::anyhow;
-use futures::{stream::BoxStream, StreamExt};
+use ephyr_log::log;
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
 use futures_signals::signal::SignalExt as _;
-use juniper::{graphql_object, graphql_subscription, GraphQLObject, RootNode};
+use juniper::{
+    graphql_object, graphql_subscription, EmptyMutation, GraphQLObject,
+    Nullable, RootNode,
+};
 use once_cell::sync::Lazy;
 use rand::Rng as _;
+use regex::Regex;
+use serde_json::Value as Json;
+use tokio::time::{self, Instant};
 
 use crate::{
-    api::graphql,
-    dvr, spec,
+    api::graphql::{self, ErrorCode},
+    audit::{self, AuditEntry},
+    backup::{self, Backup},
+    dvr::{self, DvrFile},
+    ffmpeg::{self, RestreamerKind},
+    json_patch,
+    server::statistics,
+    spec,
     state::{
-        Delay, InputEndpointKind, InputId, InputKey, InputSrcUrl, Label,
-        MixinId, MixinSrcUrl, OutputDstUrl, OutputId, PasswordKind, Restream,
-        RestreamId, RestreamKey, Volume,
+        Delay, FailoverHysteresis, FallbackKind, FfmpegLogLevel,
+        HlsVariantSelector, Input, InputEndpointKind, InputId, InputKey,
+        InputSrc, InputSrcUrl, InputTuning, IpCidr, Label, MixinId,
+        MixinLimits, MixinLimitsError, MixinSidechainParams, MixinSrcUrl,
+        Output, OutputDstUrl, OutputIcecast, OutputId, OutputLoudnorm,
+        OutputReconnect, OutputTls, PasswordKind, PublishToken, Restream,
+        RestreamFallback, RestreamId, RestreamKey, RestreamSrsTuning,
+        RestreamTemplate, StateError, Status, TenantKey, Title, Volume,
     },
     Spec,
 };
@@ -26,10 +48,23 @@ use crate::{
 use super::Context;
 use crate::{
     spec::v1::BackupInput,
-    state::{EndpointId, ServerInfo, VolumeLevel},
+    state::{
+        ClientId, EndpointId, EndpointKey, OutputAudioLevels, ServerInfo,
+        ServerInfoSample, StreamStatistics, VolumeLevel,
+    },
+    stream_probe::stream_probe_once,
 };
 use url::Url;
 
+/// Interval to sample live audio levels with in `audioLevels` subscription.
+const AUDIO_LEVELS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Timeout of each step of `setOutput` destination preflight check.
+const DST_PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout of probing a live stream in `probeInputSrc` mutation.
+const INPUT_PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
 /// Schema of `Restreamer` app.
 pub type Schema =
     RootNode<'static, QueriesRoot, MutationsRoot, SubscriptionsRoot>;
@@ -41,6 +76,22 @@ pub fn schema() -> Schema {
     Schema::new(QueriesRoot, MutationsRoot, SubscriptionsRoot)
 }
 
+/// Schema of `Restreamer` app served to tenants, having read-only access, so
+/// no mutations at all.
+pub type TenantSchema =
+    RootNode<'static, QueriesRoot, EmptyMutation<Context>, SubscriptionsRoot>;
+
+/// Constructs and returns new [`TenantSchema`], ready for use.
+#[inline]
+#[must_use]
+pub fn tenant_schema() -> TenantSchema {
+    TenantSchema::new(QueriesRoot, EmptyMutation::new(), SubscriptionsRoot)
+}
+
+/// [`Schema`] to execute selections of `allRestreamsPatch` subscription
+/// against.
+static SCHEMA: Lazy<Schema> = Lazy::new(schema);
+
 /// Root of all [GraphQL mutations][1] in the [`Schema`].
 ///
 /// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
@@ -55,6 +106,10 @@ impl MutationsRoot {
     /// the one defined by the `spec`. Otherwise, merges the `spec` with
     /// existing `Restream`s.
     ///
+    /// If the server runs with `--spec-expand-env` option, then
+    /// `${EPHYR_SPEC_*}` placeholders in URLs and labels of the `spec` are
+    /// replaced with values of the server's environment variables.
+    ///
     /// ### Result
     ///
     /// Returns `null` if a `Restream` with the given `id` doesn't exist,
@@ -75,35 +130,75 @@ impl MutationsRoot {
         restream_id: Option<RestreamId>,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
-        let spec = serde_json::from_str::<Spec>(&spec)?.into_v1();
-
-        Ok(if let Some(id) = restream_id {
-            let spec = (spec.restreams.len() == 1)
-                .then(|| spec.restreams.into_iter().next())
-                .flatten()
-                .ok_or_else(|| {
-                    graphql::Error::new("INVALID_SPEC")
-                        .status(StatusCode::BAD_REQUEST)
-                        .message(
-                            "JSON spec should contain exactly one Restream",
-                        )
-                })?;
-            #[allow(clippy::manual_find_map)]
-            // due to moving `spec` inside closure
-            context
-                .state()
-                .restreams
-                .lock_mut()
-                .iter_mut()
-                .find(|r| r.id == id)
-                .map(|r| {
-                    r.apply(spec, replace);
-                    true
-                })
-        } else {
-            context.state().apply(spec, replace);
-            Some(true)
-        })
+        context.ensure_not_in_maintenance()?;
+        let expand_env = context.config().spec_expand_env;
+        let spec = Spec::from_json(&spec, expand_env)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidSpecJson)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?
+            .into_v1();
+
+        Ok(context
+            .state()
+            .import(spec, replace, restream_id)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?
+            .map(|()| true))
+    }
+
+    /// Restores the server's state from the backup with the specified `name`,
+    /// as the `import` mutation does.
+    ///
+    /// The current state is backed up before being restored over, so this
+    /// mutation may be reverted by restoring that backup.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` once the backup is restored.
+    ///
+    /// Errors with `UNKNOWN_BACKUP` if there is no backup with the given
+    /// `name`, or with `INVALID_SPEC` if the backup cannot be applied.
+    async fn restore_backup(
+        #[graphql(description = "Name of the backup to be restored.")]
+        name: String,
+        #[graphql(
+            description = "Indicator whether the backup should replace \
+                           existing definitions.",
+            default = false
+        )]
+        replace: bool,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let json = backup::Storage::global()
+            .read(&name)
+            .await
+            .map_err(|e| anyhow!("Failed to read {name} backup: {e}"))?
+            .ok_or_else(|| {
+                graphql::Error::new(ErrorCode::UnknownBackup)
+                    .status(StatusCode::NOT_FOUND)
+                    .message(&format!("Backup {name} doesn't exist"))
+            })?;
+        let spec = Spec::from_json(&json, false)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidSpecJson)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?
+            .into_v1();
+
+        backup::store(context.state(), backup::Reason::Restore).await;
+        let _ = context.state().import(spec, replace, None).map_err(|e| {
+            graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+        Ok(true)
     }
 
     /// Sets a new `Restream` or updates an existing one (if `id` is specified).
@@ -117,12 +212,25 @@ impl MutationsRoot {
     ///
     /// Returns `null` if a `Restream` with the given `id` doesn't exist,
     /// otherwise always returns `true`.
+    ///
+    /// Errors with `DUPLICATE_RESTREAM_KEY` if another `Restream` has such
+    /// `key` already, or with `CONFLICT` if it has such `label` already
+    /// (ignoring case).
     #[allow(clippy::too_many_arguments)]
     fn set_restream(
         #[graphql(description = "Unique key to set the `Restream` with.")]
         key: RestreamKey,
         #[graphql(description = "Optional label to set the `Restream` with.")]
         label: Option<Label>,
+        #[graphql(description = "Optional tenant to isolate the `Restream` \
+                                 under, prefixing the SRS app of its \
+                                 endpoints.\
+                                 \n\n\
+                                 If not specified for an existing \
+                                 `Restream`, then its current tenant is \
+                                 preserved, while an explicit `null` \
+                                 removes it.")]
+        tenant: Nullable<TenantKey>,
         #[graphql(description = "URL to pull a live stream from.\
                            \n\n\
                            If not specified then `Restream` will await for a \
@@ -138,24 +246,235 @@ impl MutationsRoot {
             default = false
         )]
         with_hls: bool,
+        #[graphql(description = "Indicator whether the HLS endpoint of the \
+                           `Restream` should additionally serve a live stream \
+                           as LL-HLS (Low-Latency HLS).\
+                           \n\n\
+                           Requires `withHls` to be `true`.\
+                           \n\n\
+                           If not specified for an existing `Restream`, \
+                           then its current LL-HLS options are preserved.")]
+        ll_hls: Option<bool>,
+        #[graphql(description = "Target latency (in milliseconds) of LL-HLS \
+                                 on the client side.\
+                                 \n\n\
+                                 Defaults to 3000 milliseconds (or the \
+                                 current one of an existing `Restream`), if \
+                                 not specified.")]
+        ll_hls_target_latency: Option<i32>,
+        #[graphql(description = "Ladder of `HlsRendition`s the HLS endpoint \
+                                 of the `Restream` should additionally serve \
+                                 a live stream with, listed in its master \
+                                 playlist.\
+                                 \n\n\
+                                 Requires `withHls` to be `true`.\
+                                 \n\n\
+                                 If not specified for an existing \
+                                 `Restream`, then its current ladder is \
+                                 preserved.")]
+        hls_renditions: Option<Vec<spec::v1::HlsRendition>>,
+        #[graphql(
+            description = "Indicator whether the `Restream` should have an \
+                           additional endpoint for accepting a live stream \
+                           via WHIP (WebRTC) from browsers.\
+                           \n\n\
+                           Cannot be used along with `src` without \
+                           `backupInputs`.\
+                           \n\n\
+                           If not specified for an existing `Restream`, \
+                           then its current WHIP endpoint (if any) is \
+                           preserved."
+        )]
+        with_whip: Option<bool>,
+        #[graphql(
+            description = "Indicator whether the `Restream` should have an \
+                           additional endpoint for accepting a live stream \
+                           from encoders connecting via SRT in caller mode.\
+                           \n\n\
+                           Cannot be used along with `src` without \
+                           `backupInputs`.\
+                           \n\n\
+                           If not specified for an existing `Restream`, \
+                           then its current SRT endpoint (if any) is \
+                           preserved along with its allocated port."
+        )]
+        with_srt: Option<bool>,
+        #[graphql(description = "Number of seconds a restored `Input` \
+                                 should stay online before the `Restream` \
+                                 switches back to it.\
+                                 \n\n\
+                                 Requires `backupInputs`. If not specified \
+                                 for an existing `Restream`, then its \
+                                 current value is preserved, while an \
+                                 explicit `null` makes it switch back as \
+                                 soon as the `Input` goes online.")]
+        failover_stable_secs: Nullable<i32>,
+        #[graphql(
+            description = "Indicator whether a restored `Input` should be \
+                           probed with FFprobe for a valid video stream \
+                           before the `Restream` switches back to it.\
+                           \n\n\
+                           Requires `failoverStableSecs`.\
+                           \n\n\
+                           If not specified for an existing `Restream`, \
+                           then its current value is preserved."
+        )]
+        failover_probe_video: Option<bool>,
         #[graphql(description = "ID of the `Restream` to be updated \
                                  rather than creating a new one.")]
         id: Option<RestreamId>,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let existing_restream =
+            id.and_then(|id| context.state().get_restream(id));
+        let has_endpoint = |kind: InputEndpointKind| {
+            existing_restream.as_ref().map_or(false, |r| {
+                r.input.endpoints.iter().any(|e| e.kind == kind)
+            })
+        };
+        let with_whip =
+            with_whip.unwrap_or_else(|| has_endpoint(InputEndpointKind::Whip));
+        let with_srt =
+            with_srt.unwrap_or_else(|| has_endpoint(InputEndpointKind::Srt));
+        let existing_hls = existing_restream.as_ref().and_then(|r| {
+            r.input
+                .endpoints
+                .iter()
+                .find(|e| e.kind == InputEndpointKind::Hls)
+        });
+
+        if ll_hls == Some(true) && !with_hls {
+            return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message("LL-HLS cannot be used without HLS endpoint"));
+        }
+        let ll_hls = match ll_hls {
+            Some(true) => Some(spec::v1::LlHls {
+                target_latency: ll_hls_target_latency
+                    .unwrap_or(spec::v1::LlHls::DEFAULT_TARGET_LATENCY),
+            }),
+            Some(false) => None,
+            None => existing_hls
+                .filter(|_| with_hls)
+                .and_then(|e| e.ll_hls)
+                .map(|l| spec::v1::LlHls {
+                    target_latency: ll_hls_target_latency
+                        .unwrap_or(l.export().target_latency),
+                }),
+        };
+        if ll_hls.map_or(false, |l| !l.is_valid()) {
+            return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!(
+                    "LL-HLS target latency should be in {}..={} range",
+                    spec::v1::LlHls::MIN_TARGET_LATENCY,
+                    spec::v1::LlHls::MAX_TARGET_LATENCY,
+                )));
+        }
+        if let Some(ladder) = &hls_renditions {
+            if !with_hls {
+                return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(
+                        "HLS renditions cannot be used without HLS endpoint",
+                    ));
+            }
+            spec::v1::HlsRendition::validate_ladder(ladder).map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&format!("HLS renditions ladder {e}"))
+            })?;
+        }
+        if with_whip && src.is_some() && backup_inputs.is_none() {
+            return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message("WHIP endpoint cannot be used along with `src`"));
+        }
+        if with_srt && src.is_some() && backup_inputs.is_none() {
+            return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message("SRT endpoint cannot be used along with `src`"));
+        }
+
+        let existing_hysteresis = existing_restream
+            .as_ref()
+            .and_then(|r| match &r.input.src {
+                Some(InputSrc::Failover(s)) => s.hysteresis,
+                _ => None,
+            })
+            .filter(|_| backup_inputs.is_some());
+        let stable_secs = match failover_stable_secs {
+            Nullable::ImplicitNull => {
+                existing_hysteresis.map(|h| h.stable_secs)
+            }
+            Nullable::ExplicitNull => None,
+            Nullable::Some(stable_secs) => Some(stable_secs),
+        };
+        let hysteresis = match stable_secs {
+            Some(stable_secs) => Some(FailoverHysteresis {
+                stable_secs,
+                probe_video: failover_probe_video.unwrap_or_else(|| {
+                    existing_hysteresis.map_or(false, |h| h.probe_video)
+                }),
+            }),
+            None if failover_probe_video == Some(true) => {
+                return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(
+                        "`failoverProbeVideo` cannot be used without \
+                         `failoverStableSecs`",
+                    ));
+            }
+            None => None,
+        };
+        if let Some(h) = hysteresis {
+            if backup_inputs.is_none() {
+                return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(
+                        "`failoverStableSecs` cannot be used without \
+                         `backupInputs`",
+                    ));
+            }
+            if !h.is_valid() {
+                return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&format!(
+                        "`failoverStableSecs` should be in {}..={} range",
+                        FailoverHysteresis::MIN_STABLE_SECS,
+                        FailoverHysteresis::MAX_STABLE_SECS,
+                    )));
+            }
+        }
+
+        // `HlsVariantSelector`s set via `setInputHlsVariant` are preserved for
+        // the URLs which haven't changed.
+        let remote_src = |url: InputSrcUrl| spec::v1::InputSrc::Remote {
+            hls_variant: existing_restream
+                .as_ref()
+                .and_then(|r| remote_hls_variant(&r.input, &url)),
+            url,
+        };
         let (input_key, input_src) = if let Some(backups) = backup_inputs {
             (
                 InputKey::new("playback").unwrap(),
-                Some(spec::v1::InputSrc::FailoverInputs(
-                    vec![spec::v1::Input {
+                Some(spec::v1::InputSrc::Failover {
+                    inputs: vec![spec::v1::Input {
                         id: None,
                         key: InputKey::new("primary").unwrap(),
                         endpoints: vec![spec::v1::InputEndpoint {
                             kind: InputEndpointKind::Rtmp,
                             label: None,
+                            secret_key: None,
+                            ll_hls: None,
+                            hls_renditions: vec![],
                         }],
-                        src: src.map(spec::v1::InputSrc::RemoteUrl),
+                        src: src.map(remote_src),
                         enabled: true,
+                        tuning: None,
+                        log_level: None,
+                        priority: 0,
                     }]
                     .into_iter()
                     .chain(backups.into_iter().map(|b| spec::v1::Input {
@@ -164,34 +483,73 @@ impl MutationsRoot {
                         endpoints: vec![spec::v1::InputEndpoint {
                             kind: InputEndpointKind::Rtmp,
                             label: None,
+                            secret_key: None,
+                            ll_hls: None,
+                            hls_renditions: vec![],
                         }],
-                        src: b.src.map(spec::v1::InputSrc::RemoteUrl),
+                        src: b.src.map(remote_src),
                         enabled: true,
+                        tuning: None,
+                        log_level: None,
+                        priority: 0,
                     }))
                     .collect(),
-                )),
+                    hysteresis,
+                }),
             )
         } else {
-            (
-                InputKey::new("primary").unwrap(),
-                src.map(spec::v1::InputSrc::RemoteUrl),
-            )
+            (InputKey::new("primary").unwrap(), src.map(remote_src))
         };
 
         let mut endpoints = vec![spec::v1::InputEndpoint {
             kind: InputEndpointKind::Rtmp,
             label: None,
+            secret_key: None,
+            ll_hls: None,
+            hls_renditions: vec![],
         }];
         if with_hls {
             endpoints.push(spec::v1::InputEndpoint {
                 kind: InputEndpointKind::Hls,
                 label: None,
+                secret_key: None,
+                ll_hls,
+                hls_renditions: hls_renditions
+                    .or_else(|| existing_hls.map(|e| e.export().hls_renditions))
+                    .unwrap_or_default(),
             });
         }
+        if with_whip {
+            endpoints.push(spec::v1::InputEndpoint {
+                kind: InputEndpointKind::Whip,
+                label: None,
+                secret_key: None,
+                ll_hls: None,
+                hls_renditions: vec![],
+            });
+        }
+        if with_srt {
+            endpoints.push(spec::v1::InputEndpoint {
+                kind: InputEndpointKind::Srt,
+                label: None,
+                secret_key: None,
+                ll_hls: None,
+                hls_renditions: vec![],
+            });
+        }
+
+        let tenant = match tenant {
+            Nullable::ImplicitNull => {
+                existing_restream.as_ref().and_then(|r| r.tenant.clone())
+            }
+            Nullable::ExplicitNull => None,
+            Nullable::Some(tenant) => Some(tenant),
+        };
 
         let spec = spec::v1::Restream {
             id: None,
             key,
+            tenant,
             label,
             input: spec::v1::Input {
                 id: None,
@@ -199,8 +557,17 @@ impl MutationsRoot {
                 endpoints,
                 src: input_src,
                 enabled: true,
+                tuning: None,
+                log_level: None,
+                priority: 0,
             },
             outputs: vec![],
+            webhook: None,
+            fallback: None,
+            ip_filter: None,
+            srs_tuning: None,
+            publish_token: None,
+            idle_timeout: None,
         };
 
         #[allow(clippy::option_if_let_else)] // due to consuming `spec`
@@ -209,27 +576,495 @@ impl MutationsRoot {
         } else {
             context.state().add_restream(spec).map(Some)
         }
-        .map_err(|e| {
-            graphql::Error::new("DUPLICATE_RESTREAM_KEY")
+        .map_err(|e| match e.downcast::<StateError>() {
+            Ok(e) => e.into(),
+            Err(e) => graphql::Error::new(ErrorCode::DuplicateRestreamKey)
                 .status(StatusCode::CONFLICT)
-                .message(&e)
+                .message(&e),
         })?
         .map(|_| true))
     }
 
+    /// Clones a `Restream` with all its `Input`s and `Output`s under a new
+    /// `key`, assigning new IDs to them.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if a `Restream` with the given `id` doesn't exist,
+    /// otherwise returns ID of the newly created `Restream`.
+    ///
+    /// Errors with `DUPLICATE_RESTREAM_KEY` if another `Restream` has such
+    /// `key` already, or with `CONFLICT` if it has such `label` already
+    /// (ignoring case).
+    fn clone_restream(
+        #[graphql(description = "ID of the `Restream` to be cloned.")]
+        id: RestreamId,
+        #[graphql(description = "Unique key to create a new `Restream` with.")]
+        new_key: RestreamKey,
+        #[graphql(description = "Optional label to create a new `Restream` \
+                                 with.")]
+        new_label: Option<Label>,
+        context: &Context,
+    ) -> Result<Option<RestreamId>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context
+            .state()
+            .clone_restream(id, new_key, new_label)
+            .map_err(|e| match e.downcast::<StateError>() {
+                Ok(e) => e.into(),
+                Err(e) => graphql::Error::new(ErrorCode::DuplicateRestreamKey)
+                    .status(StatusCode::CONFLICT)
+                    .message(&e),
+            })
+    }
+
+    /// Sets or removes the label of a `Restream`, without touching anything
+    /// else of it (unlike `setRestream` does).
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the label is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist, or with `CONFLICT` if another `Restream` has such label already
+    /// (ignoring case).
+    fn rename_restream(
+        #[graphql(description = "ID of the `Restream` to set label of.")]
+        id: RestreamId,
+        #[graphql(description = "Label to set the `Restream` with.\
+                                 \n\n\
+                                 If not specified, then the label is \
+                                 removed.")]
+        label: Option<Label>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().rename_restream(id, label)?)
+    }
+
+    /// Changes the unique key of a `Restream`, without touching anything else
+    /// of it (unlike `setRestream` does).
+    ///
+    /// The current external publishers (if any) are kicked, as they publish
+    /// under the previous key, so should re-publish under the new one.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the key is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist, or with `DUPLICATE_RESTREAM_KEY` if another `Restream` has the
+    /// given `newKey` already.
+    fn change_restream_key(
+        #[graphql(description = "ID of the `Restream` to change key of.")]
+        id: RestreamId,
+        #[graphql(description = "Unique key to set the `Restream` with.")]
+        new_key: RestreamKey,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context
+            .state()
+            .change_restream_key(id, new_key)
+            .map_err(|e| match e {
+                StateError::Conflict(_) => {
+                    graphql::Error::new(ErrorCode::DuplicateRestreamKey)
+                        .status(StatusCode::CONFLICT)
+                        .message(&e)
+                }
+                e => e.into(),
+            })
+    }
+
+    /// Sets or removes the outbound webhook of a `Restream`, called when its
+    /// `Input` goes online or offline.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the webhook is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_webhook(
+        #[graphql(description = "ID of the `Restream` to set webhook of.")]
+        id: RestreamId,
+        #[graphql(description = "URL to send `POST` requests with events to.\
+                                 \n\n\
+                                 If not specified, then the webhook is \
+                                 removed.")]
+        url: Option<String>,
+        #[graphql(description = "Secret to sign requests with HMAC-SHA256 \
+                                 in `X-Ephyr-Signature` header.\
+                                 \n\n\
+                                 If not specified, then requests are not \
+                                 signed.")]
+        secret: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let webhook = url
+            .map(|url| {
+                Url::parse(&url)
+                    .ok()
+                    .filter(|u| matches!(u.scheme(), "http" | "https"))
+                    .ok_or_else(|| {
+                        graphql::Error::new(ErrorCode::InvalidWebhookUrl)
+                            .status(StatusCode::BAD_REQUEST)
+                            .message("Webhook URL should be HTTP(S) URL")
+                    })
+                    .map(|url| spec::v1::RestreamWebhook {
+                        url,
+                        secret: secret.filter(|s| !s.is_empty()),
+                    })
+            })
+            .transpose()?;
+        Ok(context.state().set_restream_webhook(id, webhook)?)
+    }
+
+    /// Sets or removes the fallback source of a `Restream`, broadcast to its
+    /// `Output`s while its `Input` is offline.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the fallback is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_fallback(
+        #[graphql(description = "ID of the `Restream` to set fallback of.")]
+        id: RestreamId,
+        #[graphql(description = "Kind of the fallback source.\
+                                 \n\n\
+                                 If not specified, then the fallback is \
+                                 removed.")]
+        kind: Option<FallbackKind>,
+        #[graphql(description = "Path to a local file to be broadcast, \
+                                 relative to the server's media directory.\
+                                 \n\n\
+                                 Required for `IMAGE` and `FILE` kinds.")]
+        file: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let fallback = kind
+            .map(|kind| {
+                let file = file.filter(|f| !f.is_empty());
+                if kind.requires_file() && file.is_none() {
+                    return Err(graphql::Error::new(
+                        ErrorCode::InvalidFallback,
+                    )
+                    .status(StatusCode::BAD_REQUEST)
+                    .message("Fallback of this kind requires a file"));
+                }
+                if let Some(f) = file.as_deref() {
+                    if !RestreamFallback::is_valid_file(f) {
+                        return Err(graphql::Error::new(
+                            ErrorCode::InvalidFallback,
+                        )
+                        .status(StatusCode::BAD_REQUEST)
+                        .message(
+                            "Fallback file should be a relative path inside \
+                             the media directory",
+                        ));
+                    }
+                }
+                Ok(spec::v1::RestreamFallback {
+                    kind,
+                    file: kind.requires_file().then_some(file).flatten(),
+                })
+            })
+            .transpose()?;
+        Ok(context.state().set_restream_fallback(id, fallback)?)
+    }
+
+    /// Sets or removes the filter of IP addresses allowed to publish a live
+    /// stream to a `Restream`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the filter is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_ip_filter(
+        #[graphql(description = "ID of the `Restream` to set filter of.")]
+        id: RestreamId,
+        #[graphql(description = "Networks allowed to publish a live stream.\
+                                 \n\n\
+                                 If empty, then any network not listed in \
+                                 `deny` is allowed.")]
+        allow: Option<Vec<IpCidr>>,
+        #[graphql(description = "Networks denied to connect to the \
+                                 `Restream` at all.")]
+        deny: Option<Vec<IpCidr>>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let filter = spec::v1::RestreamIpFilter {
+            allow: allow.unwrap_or_default(),
+            deny: deny.unwrap_or_default(),
+        };
+        let filter = (!filter.allow.is_empty() || !filter.deny.is_empty())
+            .then_some(filter);
+        Ok(context.state().set_restream_ip_filter(id, filter)?)
+    }
+
+    /// Sets or removes the tuning of the SRS server for a `Restream`.
+    ///
+    /// The SRS server is reloaded to apply the changes, and HLS endpoints of
+    /// the `Restream` are restarted, if necessary.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the tuning is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_srs_tuning(
+        #[graphql(description = "ID of the `Restream` to tune SRS for.")]
+        id: RestreamId,
+        #[graphql(
+            description = "Indicator whether live streams should be recorded \
+                           by SRS into FLV files.",
+            default = false
+        )]
+        dvr: bool,
+        #[graphql(description = "Duration (in seconds) of HLS fragments.\
+                                 \n\n\
+                                 If not specified, then the SRS default is \
+                                 used.")]
+        hls_fragment: Option<i32>,
+        #[graphql(description = "Indicator whether the latest GOP is cached \
+                                 for HLS endpoints.\
+                                 \n\n\
+                                 If not specified, then the SRS default is \
+                                 used.")]
+        gop_cache: Option<bool>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let range = RestreamSrsTuning::HLS_FRAGMENT_RANGE;
+        if hls_fragment.map_or(false, |f| !range.contains(&f)) {
+            return Err(graphql::Error::new(ErrorCode::InvalidSrsTuning)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!(
+                    "HLS fragment duration should be in {}..={} range",
+                    range.start(),
+                    range.end(),
+                )));
+        }
+        let tuning = spec::v1::RestreamSrsTuning {
+            dvr,
+            hls_fragment,
+            gop_cache,
+        };
+        let tuning = (tuning.dvr
+            || tuning.hls_fragment.is_some()
+            || tuning.gop_cache.is_some())
+        .then_some(tuning);
+        Ok(context.state().set_restream_srs_tuning(id, tuning)?)
+    }
+
+    /// Sets or removes the token required to publish a live stream to a
+    /// `Restream` by an external client.
+    ///
+    /// The current external publishers (if any) are kicked.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the token is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_publish_token(
+        #[graphql(description = "ID of the `Restream` to set token of.")]
+        id: RestreamId,
+        #[graphql(description = "Token to be passed by external clients as \
+                                 `?token=<value>` query parameter.\
+                                 \n\n\
+                                 If not specified, then the token is \
+                                 removed.")]
+        token: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let token = token
+            .map(|t| {
+                PublishToken::new(t).ok_or_else(|| {
+                    graphql::Error::new(ErrorCode::InvalidPublishToken)
+                        .status(StatusCode::BAD_REQUEST)
+                        .message(
+                            "Publish token should consist of 8-64 latin \
+                             letters, digits, `_` or `-` symbols",
+                        )
+                })
+            })
+            .transpose()?;
+        Ok(context.state().set_restream_publish_token(id, token)?)
+    }
+
+    /// Rotates the token required to publish a live stream to a `Restream`
+    /// by an external client, so it may be published only with the new
+    /// token.
+    ///
+    /// The current external publishers (if any) are kicked.
+    ///
+    /// ### Result
+    ///
+    /// Returns the new token, or `null` if `Restream` with the given `id`
+    /// doesn't exist.
+    fn rotate_restream_publish_token(
+        #[graphql(description = "ID of the `Restream` to rotate token of.")]
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<PublishToken>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().rotate_restream_publish_token(id))
+    }
+
+    /// Sets or removes the duration a `Restream`'s `Input` may stay offline
+    /// while being enabled, before it's disabled automatically.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the timeout is set to the same value already,
+    /// otherwise `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
+    fn set_restream_idle_timeout(
+        #[graphql(description = "ID of the `Restream` to set timeout of.")]
+        id: RestreamId,
+        #[graphql(description = "Duration (in minutes) of the `Input` being \
+                                 offline, after which it's disabled.\
+                                 \n\n\
+                                 `0` means never disabling the `Input`, \
+                                 while `null` means using the server-wide \
+                                 setting.")]
+        timeout: Option<i32>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if let Some(t) = timeout {
+            if !Restream::is_valid_idle_timeout(t) {
+                return Err(idle_timeout_error(0, t));
+            }
+        }
+        Ok(context.state().set_restream_idle_timeout(id, timeout)?)
+    }
+
+    /// Saves a named partial JSON `spec` of a `Restream` on this server as a
+    /// `RestreamTemplate`, to be merged into existing `Restream`s later.
+    ///
+    /// The existing `RestreamTemplate` with the same `name` (if any) is
+    /// replaced.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a new `RestreamTemplate` has been saved, or `false`
+    /// if the existing one has been replaced.
+    fn save_template(
+        #[graphql(description = "Unique name of the `RestreamTemplate`.")]
+        name: Label,
+        #[graphql(description = "Partial JSON spec of a `Restream`, \
+                                 containing any of its `outputs`, \
+                                 `fallback` and `ip_filter` fields in the \
+                                 same format as `export` query returns \
+                                 them.")]
+        spec: String,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let spec: spec::v1::RestreamTemplate = serde_json::from_str(&spec)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidSpecJson)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        if spec.is_empty() {
+            return Err(graphql::Error::new(ErrorCode::InvalidSpec)
+                .status(StatusCode::BAD_REQUEST)
+                .message("RestreamTemplate should define something to merge"));
+        }
+
+        Ok(context
+            .state()
+            .save_template(RestreamTemplate { name, spec }))
+    }
+
+    /// Deletes a `RestreamTemplate` by its `name`.
+    ///
+    /// ### Result
+    ///
+    /// Always returns `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `RestreamTemplate` with the given `name`
+    /// doesn't exist.
+    fn delete_template(
+        #[graphql(description = "Name of the `RestreamTemplate` to be \
+                                 deleted.")]
+        name: Label,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context.state().delete_template(&name)?;
+        Ok(true)
+    }
+
+    /// Merges a `RestreamTemplate` into an existing `Restream`.
+    ///
+    /// `Output`s of the `RestreamTemplate` are merged with the existing ones
+    /// by their `dst`, while the rest of the existing `Output`s are left
+    /// untouched.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if `Restream` with the given `restreamId` doesn't
+    /// exist, otherwise always returns `true`.
+    fn apply_template(
+        #[graphql(description = "ID of the `Restream` to merge the \
+                                 `RestreamTemplate` into.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Name of the `RestreamTemplate` to be \
+                                 merged.")]
+        template_name: Label,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
+            .state()
+            .apply_template(restream_id, &template_name)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::UnknownTemplate)
+                    .status(StatusCode::NOT_FOUND)
+                    .message(&e)
+            })?
+            .map(|()| true))
+    }
+
     /// Removes a `Restream` by its `id`.
     ///
     /// ### Result
     ///
-    /// Returns `null` if `Restream` with the given `id` doesn't exist,
-    /// otherwise always returns `true`.
+    /// Always returns `true`.
+    ///
+    /// Errors with `NOT_FOUND` if `Restream` with the given `id` doesn't
+    /// exist.
     fn remove_restream(
         #[graphql(description = "ID of the `Restream` to be removed.")]
         id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
         context.state().remove_restream(id)?;
-        Some(true)
+        Ok(true)
     }
 
     /// Enables a `Restream` by its `id`.
@@ -239,13 +1074,16 @@ impl MutationsRoot {
     /// ### Result
     ///
     /// Returns `true` if a `Restream` with the given `id` has been enabled,
-    /// `false` if it has been enabled already, and `null` if it doesn't exist.
+    /// or `false` if it has been enabled already.
+    ///
+    /// Errors with `NOT_FOUND` if it doesn't exist.
     fn enable_restream(
         #[graphql(description = "ID of the `Restream` to be enabled.")]
         id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_restream(id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().enable_restream(id)?)
     }
 
     /// Disables a `Restream` by its `id`.
@@ -256,13 +1094,16 @@ impl MutationsRoot {
     /// ### Result
     ///
     /// Returns `true` if a `Restream` with the given `id` has been disabled,
-    /// `false` if it has been disabled already, and `null` if it doesn't exist.
+    /// or `false` if it has been disabled already.
+    ///
+    /// Errors with `NOT_FOUND` if it doesn't exist.
     fn disable_restream(
         #[graphql(description = "ID of the `Restream` to be disabled.")]
         id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_restream(id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().disable_restream(id)?)
     }
 
     /// Enables an `Input` by its `id`.
@@ -271,18 +1112,213 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if an `Input` with the given `id` has been enabled,
-    /// `false` if it has been enabled already, and `null` if it doesn't exist.
-    fn enable_input(
-        #[graphql(description = "ID of the `Input` to be enabled.")]
-        id: InputId,
-        #[graphql(
-            description = "ID of the `Restream` to enable the `Input` in."
-        )]
+    /// Returns `true` if an `Input` with the given `id` has been enabled,
+    /// or `false` if it has been enabled already.
+    ///
+    /// Errors with `NOT_FOUND` if it doesn't exist.
+    fn enable_input(
+        #[graphql(description = "ID of the `Input` to be enabled.")]
+        id: InputId,
+        #[graphql(
+            description = "ID of the `Restream` to enable the `Input` in."
+        )]
+        restream_id: RestreamId,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().enable_input(id, restream_id)?)
+    }
+
+    /// Sets tuning options of pulling a live stream by an `Input` from its
+    /// remote source.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if tuning options have been changed, or `false` if they
+    /// are the same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` doesn't exist.
+    fn set_input_tuning(
+        #[graphql(description = "ID of the `Input` to be tuned.")] id: InputId,
+        #[graphql(
+            description = "ID of the `Restream` to tune the `Input` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "Tuning options to be set.\
+                                 \n\n\
+                                 If not specified, then the defaults of FFmpeg \
+                                 are used.")]
+        tuning: Option<spec::v1::InputTuning>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if let Some(tuning) = &tuning {
+            tuning.validate().map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidInputTuning)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+        Ok(context.state().set_input_tuning(
+            id,
+            restream_id,
+            tuning.map(InputTuning::new),
+        )?)
+    }
+
+    /// Sets the priority of an `Input` among other `Input`s of its
+    /// `FailoverInputSrc`.
+    ///
+    /// `Input`s with higher priority are preferred, while the ones with the
+    /// same priority are preferred in their order in the `FailoverInputSrc`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the priority has been changed, or `false` if it is
+    /// the same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` doesn't exist.
+    fn set_input_priority(
+        #[graphql(description = "ID of the `Input` to set priority of.")]
+        id: InputId,
+        #[graphql(description = "ID of the `Restream` owning the `Input`.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Priority to be set.")] priority: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
+            .state()
+            .set_input_priority(id, restream_id, priority)?)
+    }
+
+    /// Moves an `Input` to the given `position` among other `Input`s of the
+    /// `FailoverInputSrc` of its `Restream`, changing the order they're
+    /// preferred in (among the ones with the same priority).
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the `Input` has been moved, or `false` if it is at
+    /// the given `position` already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` doesn't exist, or with
+    /// `CONFLICT` if the `Restream` doesn't pull from a `FailoverInputSrc`.
+    fn move_failover_input(
+        #[graphql(description = "ID of the `Input` to be moved.")] id: InputId,
+        #[graphql(description = "ID of the `Restream` owning the `Input`.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Zero-based position to move the `Input` to.\
+                                 \n\n\
+                                 Negative values move it to the beginning, \
+                                 while values exceeding the number of \
+                                 `Input`s move it to the end.")]
+        position: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let position = usize::try_from(position).unwrap_or(0);
+        Ok(context
+            .state()
+            .move_failover_input(id, restream_id, position)?)
+    }
+
+    /// Sets limits to select a variant stream with, when an `Input` pulls a
+    /// live stream from a remote HLS master playlist.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the limits have been changed, or `false` if they are
+    /// the same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` doesn't exist, or with
+    /// `CONFLICT` if it doesn't pull a live stream from a remote source.
+    fn set_input_hls_variant(
+        #[graphql(description = "ID of the `Input` to set limits of.")]
+        id: InputId,
+        #[graphql(description = "ID of the `Restream` the `Input` is in.")]
+        restream_id: RestreamId,
+        #[graphql(
+            description = "Maximum height (in pixels) of the variant's \
+                                 video."
+        )]
+        max_height: Option<i32>,
+        #[graphql(description = "Maximum bandwidth (in bits per second) of \
+                                 the variant.\
+                                 \n\n\
+                                 If neither this nor `maxHeight` is \
+                                 specified, then the variant is chosen by \
+                                 FFmpeg.")]
+        max_bandwidth: Option<i32>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if max_height.map_or(false, |v| v <= 0)
+            || max_bandwidth.map_or(false, |v| v <= 0)
+        {
+            return Err(graphql::Error::new(ErrorCode::InvalidHlsVariant)
+                .status(StatusCode::BAD_REQUEST)
+                .message("HLS variant limits should be positive"));
+        }
+        let selector = (max_height.is_some() || max_bandwidth.is_some())
+            .then_some(HlsVariantSelector {
+                max_height,
+                max_bandwidth,
+            });
+        Ok(context
+            .state()
+            .set_input_hls_variant(id, restream_id, selector)?)
+    }
+
+    /// Sets a verbosity level of FFmpeg processes serving an `Input`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the level has been changed, or `false` if it is the
+    /// same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` doesn't exist.
+    fn set_input_log_level(
+        #[graphql(description = "ID of the `Input` to set level of.")]
+        id: InputId,
+        #[graphql(description = "ID of the `Restream` the `Input` is in.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Log level to be set.\
+                                 \n\n\
+                                 If not specified, then the level is defined \
+                                 by the server's log level.")]
+        log_level: Option<FfmpegLogLevel>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
+            .state()
+            .set_input_log_level(id, restream_id, log_level)?)
+    }
+
+    /// Sets a verbosity level of FFmpeg process serving an `Output`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the level has been changed, or `false` if it is the
+    /// same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `Output` doesn't exist.
+    fn set_output_log_level(
+        #[graphql(description = "ID of the `Output` to set level of.")]
+        id: OutputId,
+        #[graphql(description = "ID of the `Restream` the `Output` is in.")]
         restream_id: RestreamId,
+        #[graphql(description = "Log level to be set.\
+                                 \n\n\
+                                 If not specified, then the level is defined \
+                                 by the server's log level.")]
+        log_level: Option<FfmpegLogLevel>,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_input(id, restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
+            .state()
+            .set_output_log_level(id, restream_id, log_level)?)
     }
 
     /// Disables an `Input` by its `id`.
@@ -293,7 +1329,9 @@ impl MutationsRoot {
     /// ### Result
     ///
     /// Returns `true` if an `Input` with the given `id` has been disabled,
-    /// `false` if it has been disabled already, and `null` if it doesn't exist.
+    /// or `false` if it has been disabled already.
+    ///
+    /// Errors with `NOT_FOUND` if it doesn't exist.
     fn disable_input(
         #[graphql(description = "ID of the `Input` to be disabled.")]
         id: InputId,
@@ -302,17 +1340,50 @@ impl MutationsRoot {
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_input(id, restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().disable_input(id, restream_id)?)
+    }
+
+    /// Kicks the publisher of an `InputEndpoint` by its `id`, along with all
+    /// its players, without disabling the `Input`.
+    ///
+    /// Useful to free an endpoint hogged by a stale publisher. Mind that the
+    /// publisher is free to reconnect afterwards.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the publisher has been kicked, or `false` if there is
+    /// no publisher.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream`/`Input`/
+    /// `InputEndpoint` doesn't exist.
+    fn kick_publisher(
+        #[graphql(description = "ID of the `InputEndpoint` to kick the \
+                                 publisher of.")]
+        endpoint_id: EndpointId,
+        #[graphql(description = "ID of the `Input` owning the endpoint.")]
+        input_id: InputId,
+        #[graphql(description = "ID of the `Restream` owning the `Input`.")]
+        restream_id: RestreamId,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().kick_publisher(
+            endpoint_id,
+            input_id,
+            restream_id,
+        )?)
     }
 
     /// Sets an `Input`'s endpoint label by `Input` and `Endpoint` `id`.
     ///
     /// ### Result
     ///
-    /// Returns `true` if the label has been set with the given `label`,
-    /// `false` if it was not
-    /// `null` if the `Input` or `Endpoint` doesn't exist.
+    /// Returns `true` if the label has been set with the given `label`, or
+    /// `false` if it was not.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` or `Endpoint` doesn't exist.
     fn set_endpoint_label(
         #[graphql(description = "ID of the `Input` to be changed.")]
         id: InputId,
@@ -321,10 +1392,101 @@ impl MutationsRoot {
         endpoint_id: EndpointId,
         label: Option<Label>,
         context: &Context,
-    ) -> Option<bool> {
-        context
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().set_endpoint_label(
+            id,
+            restream_id,
+            endpoint_id,
+            label,
+        )?)
+    }
+
+    /// Rotates the secret key of an `InputEndpoint`, so a live stream may be
+    /// published to it by an external client only with the new key.
+    ///
+    /// The current external publisher (if any) is kicked.
+    ///
+    /// ### Result
+    ///
+    /// Returns the new secret key, or `null` if the `Input` or the
+    /// `InputEndpoint` doesn't exist.
+    fn rotate_endpoint_key(
+        #[graphql(description = "ID of the `Input` to be changed.")]
+        id: InputId,
+        #[graphql(description = "ID of the `Restream` to change.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `InputEndpoint` to rotate the \
+                                  secret key of.")]
+        endpoint_id: EndpointId,
+        context: &Context,
+    ) -> Result<Option<EndpointKey>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
+            .state()
+            .rotate_endpoint_key(id, restream_id, endpoint_id))
+    }
+
+    /// Removes the secret key of an `InputEndpoint`, so a live stream may be
+    /// published to it without any key.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the secret key has been removed, or `false` if the
+    /// `InputEndpoint` has no secret key.
+    ///
+    /// Errors with `NOT_FOUND` if the `Input` or the `InputEndpoint` doesn't
+    /// exist.
+    fn remove_endpoint_key(
+        #[graphql(description = "ID of the `Input` to be changed.")]
+        id: InputId,
+        #[graphql(description = "ID of the `Restream` to change.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `InputEndpoint` to remove the \
+                                  secret key of.")]
+        endpoint_id: EndpointId,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context
             .state()
-            .set_endpoint_label(id, restream_id, endpoint_id, label)
+            .remove_endpoint_key(id, restream_id, endpoint_id)?)
+    }
+
+    /// Probes the given candidate source URL of an `Input` with FFprobe,
+    /// allowing to verify a remote source before saving a `Restream` pulling
+    /// from it.
+    ///
+    /// ### Result
+    ///
+    /// Returns codecs and bitrate of the probed live stream, or an `error`
+    /// describing why it cannot be probed.
+    ///
+    /// Errors with `CONFLICT` if the given URL is being probed already.
+    async fn probe_input_src(
+        #[graphql(description = "RTMP or HLS URL of the live stream to be \
+                                 probed.")]
+        url: InputSrcUrl,
+        context: &Context,
+    ) -> Result<StreamStatistics, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let probed = time::timeout(
+            INPUT_PROBE_TIMEOUT,
+            stream_probe_once(url.clone().into()),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Some(Err(anyhow!(
+                "Timed out probing after {}",
+                humantime::format_duration(INPUT_PROBE_TIMEOUT),
+            )))
+        })
+        .ok_or_else(|| {
+            graphql::Error::new(ErrorCode::Conflict)
+                .status(StatusCode::CONFLICT)
+                .message(&format!("'{url}' is being probed already"))
+        })?;
+        Ok(StreamStatistics::new(probed))
     }
 
     /// Sets a new `Output` or updates an existing one (if `id` is specified).
@@ -340,7 +1502,13 @@ impl MutationsRoot {
     /// Returns `null` if a `Restream` with the given `restreamId` doesn't
     /// exist, or an `Output` with the given `id` doesn't exist, otherwise
     /// always returns `true`.
-    fn set_output(
+    ///
+    /// ### Validation
+    ///
+    /// If `validate` is `true`, then the `dst` host is resolved via DNS and
+    /// connected to via TCP before accepting it, returning `DST_UNREACHABLE`
+    /// error if it fails.
+    async fn set_output(
         #[graphql(
             description = "ID of the `Restream` to add a new `Output` to."
         )]
@@ -367,34 +1535,112 @@ impl MutationsRoot {
         #[graphql(description = "ID of the `Output` to be updated \
                                  rather than creating a new one.")]
         id: Option<OutputId>,
+        #[graphql(description = "Optional TLS options to publish onto an \
+                                 `rtmps://` destination with.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current TLS options are preserved, \
+                                 while an explicit `null` removes them.")]
+        tls: Nullable<spec::v1::OutputTls>,
+        #[graphql(description = "Optional options to survive short network \
+                                 failures of the destination with, without \
+                                 restarting the re-streaming process.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current options are preserved.")]
+        reconnect: Option<spec::v1::OutputReconnect>,
+        #[graphql(description = "Optional metadata to describe the stream \
+                                 with on an `icecast://` destination.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current metadata is preserved.")]
+        icecast: Option<spec::v1::OutputIcecast>,
+        #[graphql(description = "Optional options to normalize loudness of \
+                                 the `Output`'s audio with (EBU R128), \
+                                 re-encoding it.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current options are preserved.")]
+        loudnorm: Option<spec::v1::OutputLoudnorm>,
+        #[graphql(description = "Offset (in milliseconds) of the `Output`'s \
+                                 audio relatively to its video, applied when \
+                                 mixing. Positive value delays the audio, \
+                                 while negative one delays the video.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current offset is preserved.")]
+        av_offset: Option<i32>,
+        #[graphql(description = "Priority of the `Output` to be kept \
+                                 running when the server is overloaded.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current priority is preserved.")]
+        priority: Option<i32>,
+        #[graphql(description = "Extra arguments to pass to FFmpeg process \
+                                 serving the `Output`, like `-bufsize` or \
+                                 `-tune`. Only options tuning encoding and \
+                                 muxing are allowed.\
+                                 \n\n\
+                                 If not specified for an existing `Output`, \
+                                 then its current arguments are preserved.")]
+        extra_ffmpeg_args: Option<Vec<String>>,
+        #[graphql(
+            description = "Whether to check the `dst` to be reachable \
+                           before accepting it.",
+            default = false
+        )]
+        validate: bool,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
-        if mixins.len() > 5 {
-            return Err(graphql::Error::new("TOO_MUCH_MIXIN_URLS")
+        context.ensure_not_in_maintenance()?;
+        if let Some(args) = extra_ffmpeg_args.as_ref() {
+            Output::validate_extra_ffmpeg_args(args).map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidFfmpegArgs)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
+        if let Some(r) = reconnect.as_ref().filter(|r| !r.is_valid()) {
+            return Err(graphql::Error::new(ErrorCode::InvalidOutputReconnect)
                 .status(StatusCode::BAD_REQUEST)
-                .message("Maximum 5 mixing URLs are allowed"));
+                .message(&format!(
+                    "Output reconnect options should be non-negative: {:?}",
+                    r,
+                )));
         }
-        if !mixins.is_empty() {
-            let mut unique = HashSet::with_capacity(mixins.len());
-            for m in &mixins {
-                if let Some(dup) = unique.replace(m) {
-                    return Err(graphql::Error::new("DUPLICATE_MIXIN_URL")
-                        .status(StatusCode::BAD_REQUEST)
-                        .message(&format!(
-                            "Duplicate Output.mixin.src: {}",
-                            dup,
-                        )));
-                }
-            }
-            if mixins.iter().filter(|u| u.scheme() == "ts").take(4).count() > 3
-            {
-                return Err(graphql::Error::new(
-                    "TOO_MUCH_TEAMSPEAK_MIXIN_URLS",
-                )
+        if icecast.as_ref().map_or(false, |i| !i.is_valid()) {
+            return Err(graphql::Error::new(ErrorCode::InvalidOutputIcecast)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!(
+                    "Output Icecast metadata values should have at most {} \
+                     characters and no control ones",
+                    spec::v1::OutputIcecast::MAX_LEN,
+                )));
+        }
+        if let Some(l) = loudnorm.filter(|l| !l.is_valid()) {
+            return Err(graphql::Error::new(ErrorCode::InvalidOutputLoudnorm)
                 .status(StatusCode::BAD_REQUEST)
-                .message("Maximum 3 TeamSpeak URLs are allowed"));
+                .message(&format!(
+                    "Output loudness target should be in {}..={} LUFS, but {} \
+                     provided",
+                    spec::v1::OutputLoudnorm::MIN_TARGET_LUFS,
+                    spec::v1::OutputLoudnorm::MAX_TARGET_LUFS,
+                    l.target_lufs,
+                )));
+        }
+        if let Some(offset) = av_offset {
+            if !Output::is_valid_av_offset(offset) {
+                return Err(av_offset_error(offset));
             }
         }
+        validate_mixin_srcs(&mixins, restream_id, context)?;
+        if validate {
+            dst.preflight(DST_PREFLIGHT_TIMEOUT).await.map_err(|e| {
+                graphql::Error::new(ErrorCode::DstUnreachable)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        }
 
         let existing_output = if let Some(&id_unwrap) = id.as_ref() {
             context.state().get_output(restream_id, id_unwrap)
@@ -409,6 +1655,44 @@ impl MutationsRoot {
             }
         }
 
+        let tls = match tls {
+            Nullable::ImplicitNull => existing_output
+                .as_ref()
+                .and_then(|o| o.tls.as_ref().map(OutputTls::export)),
+            Nullable::ExplicitNull => None,
+            Nullable::Some(tls) => Some(tls),
+        };
+        let reconnect = reconnect.or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.reconnect.as_ref().map(OutputReconnect::export))
+        });
+        let icecast = icecast.or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.icecast.as_ref().map(OutputIcecast::export))
+        });
+        let loudnorm = loudnorm.or_else(|| {
+            existing_output
+                .as_ref()
+                .and_then(|o| o.loudnorm.map(OutputLoudnorm::export))
+        });
+
+        let av_offset = av_offset
+            .or_else(|| existing_output.as_ref().map(|o| o.av_offset))
+            .unwrap_or_default();
+        let priority = priority
+            .or_else(|| existing_output.as_ref().map(|o| o.priority))
+            .unwrap_or_default();
+        let log_level = existing_output.as_ref().and_then(|o| o.log_level);
+        let extra_ffmpeg_args = extra_ffmpeg_args
+            .or_else(|| {
+                existing_output
+                    .as_ref()
+                    .map(|o| o.extra_ffmpeg_args.clone())
+            })
+            .unwrap_or_default();
+
         let spec = spec::v1::Output {
             id: None,
             dst,
@@ -421,6 +1705,7 @@ impl MutationsRoot {
                     let delay;
                     let volume;
                     let sidechain;
+                    let sidechain_params;
                     if let Some(orig_mixin) =
                         existing_output.as_ref().and_then(|val| {
                             val.mixins.iter().find(|val| val.src == src)
@@ -429,6 +1714,7 @@ impl MutationsRoot {
                         volume = orig_mixin.volume.export();
                         delay = orig_mixin.delay;
                         sidechain = orig_mixin.sidechain;
+                        sidechain_params = orig_mixin.sidechain_params.export();
                     } else {
                         volume = Volume::ORIGIN.export();
                         delay = (src.scheme() == "ts")
@@ -436,16 +1722,27 @@ impl MutationsRoot {
                             .flatten()
                             .unwrap_or_default();
                         sidechain = false;
+                        sidechain_params =
+                            spec::v1::MixinSidechainParams::default();
                     }
                     spec::v1::Mixin {
                         src,
                         volume,
                         delay,
                         sidechain,
+                        sidechain_params,
                     }
                 })
                 .collect(),
+            av_offset,
             enabled: false,
+            priority,
+            log_level,
+            tls,
+            reconnect,
+            icecast,
+            loudnorm,
+            extra_ffmpeg_args,
         };
 
         #[allow(clippy::option_if_let_else)] // due to consuming `spec`
@@ -455,19 +1752,154 @@ impl MutationsRoot {
             context.state().add_output(restream_id, spec)
         }
         .map_err(|e| {
-            graphql::Error::new("DUPLICATE_OUTPUT_URL")
+            graphql::Error::new(ErrorCode::DuplicateOutputUrl)
                 .status(StatusCode::CONFLICT)
                 .message(&e)
         })?
         .map(|_| true))
     }
 
+    /// Clones an `Output` with all its `Mixin`s in the specified `Restream`
+    /// to re-stream onto a new `dst`, assigning new IDs to them.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if a `Restream` with the given `restreamId` doesn't
+    /// exist, or an `Output` with the given `outputId` doesn't exist,
+    /// otherwise returns ID of the newly created `Output`.
+    fn clone_output(
+        #[graphql(description = "ID of the `Restream` to clone the `Output` \
+                                 in.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` to be cloned.")]
+        output_id: OutputId,
+        #[graphql(description = "Destination URL to create a new `Output` \
+                                 with.")]
+        new_dst: OutputDstUrl,
+        context: &Context,
+    ) -> Result<Option<OutputId>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context
+            .state()
+            .clone_output(restream_id, output_id, new_dst)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::DuplicateOutputUrl)
+                    .status(StatusCode::CONFLICT)
+                    .message(&e)
+            })
+    }
+
+    /// Adds a new `Output` to the specified `Restream`, relaying its live
+    /// stream to a `Restream` on another ephyr (or plain SRS) server.
+    ///
+    /// Optionally, registers the downstream server as a `Client` to be
+    /// monitored by this dashboard, simplifying multi-region distribution
+    /// setups.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if a `Restream` with the given `restreamId` doesn't
+    /// exist, otherwise always returns `true`.
+    ///
+    /// Errors with `INVALID_CLIENT_URL` if the `targetServerUrl` is invalid,
+    /// or with `DUPLICATE_OUTPUT_URL` if the `Restream` relays to the same
+    /// destination already.
+    #[allow(clippy::too_many_arguments)]
+    fn relay_to_server(
+        #[graphql(description = "ID of the `Restream` to relay.")]
+        restream_id: RestreamId,
+        #[graphql(description = "HTTP(S) URL of the downstream server, as \
+                                 used for registering it as a `Client`.")]
+        target_server_url: ClientId,
+        #[graphql(description = "Key of the `Restream` on the downstream \
+                                 server to relay onto.")]
+        target_restream_key: RestreamKey,
+        #[graphql(
+            description = "Port that the downstream server accepts RTMP live \
+                           streams via.",
+            default = 1935
+        )]
+        target_rtmp_port: i32,
+        #[graphql(description = "Token required by the downstream \
+                                 `Restream` for publishing, if any.")]
+        target_publish_token: Option<PublishToken>,
+        #[graphql(
+            description = "Indicator whether the downstream server should be \
+                           registered as a `Client` of this dashboard \
+                           (unless it's registered already).",
+            default = false
+        )]
+        register_client: bool,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let invalid_url = || {
+            graphql::Error::new(ErrorCode::InvalidClientUrl)
+                .status(StatusCode::BAD_REQUEST)
+                .message(
+                    "Target server URL should be an HTTP(S) URL of a host \
+                     without credentials, query or fragment, and RTMP port \
+                     should be a valid one",
+                )
+        };
+        let host = target_server_url
+            .host_str()
+            .filter(|_| target_server_url.is_valid())
+            .ok_or_else(invalid_url)?;
+        let port = u16::try_from(target_rtmp_port)
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(invalid_url)?;
+        let dst = OutputDstUrl::relay(
+            host,
+            port,
+            &target_restream_key,
+            target_publish_token.as_ref(),
+        )
+        .map_err(|_| invalid_url())?;
+
+        let spec = spec::v1::Output {
+            id: None,
+            dst,
+            label: Label::new(format!("Relay to {host}")).ok(),
+            preview_url: None,
+            volume: Volume::ORIGIN.export(),
+            mixins: vec![],
+            av_offset: 0,
+            enabled: false,
+            priority: 0,
+            log_level: None,
+            tls: None,
+            reconnect: None,
+            icecast: None,
+            loudnorm: None,
+            extra_ffmpeg_args: vec![],
+        };
+        let added = context
+            .state()
+            .add_output(restream_id, spec)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::DuplicateOutputUrl)
+                    .status(StatusCode::CONFLICT)
+                    .message(&e)
+            })?
+            .map(|()| true);
+
+        if added.is_some() && register_client {
+            // Already registered `Client` is fine here.
+            let _ = context.state().add_client(&target_server_url);
+        }
+        Ok(added)
+    }
+
     /// Removes an `Output` by its `id` from the specified `Restream`.
     ///
     /// ### Result
     ///
-    /// Returns `null` if the specified `Restream`/`Output` doesn't exist,
-    /// otherwise always returns `true`.
+    /// Always returns `true`.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream`/`Output` doesn't
+    /// exist.
     fn remove_output(
         #[graphql(description = "ID of the `Output` to be removed.")]
         id: OutputId,
@@ -476,8 +1908,10 @@ impl MutationsRoot {
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().remove_output(id, restream_id).map(|_| true)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context.state().remove_output(id, restream_id)?;
+        Ok(true)
     }
 
     /// Enables an `Output` by its `id` in the specified `Restream`.
@@ -487,8 +1921,10 @@ impl MutationsRoot {
     /// ### Result
     ///
     /// Returns `true` if an `Output` with the given `id` has been enabled,
-    /// `false` if it has been enabled already, and `null` if the specified
-    /// `Restream`/`Output` doesn't exist.
+    /// or `false` if it has been enabled already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream`/`Output` doesn't
+    /// exist.
     fn enable_output(
         #[graphql(description = "ID of the `Output` to be enabled.")]
         id: OutputId,
@@ -497,8 +1933,59 @@ impl MutationsRoot {
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_output(id, restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().enable_output(id, restream_id)?)
+    }
+
+    /// Tests the destination of an `Output` by its `id` in the specified
+    /// `Restream`, pushing a generated test pattern with silence onto it for
+    /// the given `duration`.
+    ///
+    /// Allows to validate the destination (and its stream key) before
+    /// enabling the `Output`. Mind that the test stream is visible on the
+    /// destination while being pushed.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if the specified `Restream`/`Output` doesn't exist,
+    /// otherwise always returns the result of the test.
+    async fn test_output(
+        #[graphql(description = "ID of the `Output` to be tested.")]
+        id: OutputId,
+        #[graphql(
+            description = "ID of the `Restream` to test the `Output` of."
+        )]
+        restream_id: RestreamId,
+        #[graphql(
+            description = "Duration (in seconds) to push the test stream \
+                           for. Should be in `1..=30` range.",
+            default = 5
+        )]
+        duration: i32,
+        context: &Context,
+    ) -> Result<Option<ffmpeg::OutputTestResult>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let duration = u64::try_from(duration)
+            .ok()
+            .filter(|d| (1..=30).contains(d))
+            .ok_or_else(|| {
+                graphql::Error::new(ErrorCode::InvalidOutputTestDuration)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message("Test duration should be in 1..=30 seconds range")
+            })?;
+        let output = match context.state().get_output(restream_id, id) {
+            Some(o) => o,
+            None => return Ok(None),
+        };
+        Ok(Some(
+            ffmpeg::test_output(
+                ffmpeg::Binary::global(),
+                &output,
+                Duration::from_secs(duration),
+            )
+            .await,
+        ))
     }
 
     /// Disables an `Output` by its `id` in the specified `Restream`.
@@ -508,8 +1995,10 @@ impl MutationsRoot {
     /// ### Result
     ///
     /// Returns `true` if an `Output` with the given `id` has been disabled,
-    /// `false` if it has been disabled already, and `null` if the specified
-    /// `Restream`/`Output` doesn't exist.
+    /// or `false` if it has been disabled already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream`/`Output` doesn't
+    /// exist.
     fn disable_output(
         #[graphql(description = "ID of the `Output` to be disabled.")]
         id: OutputId,
@@ -518,8 +2007,9 @@ impl MutationsRoot {
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_output(id, restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().disable_output(id, restream_id)?)
     }
 
     /// Enables all `Output`s in the specified `Restream`.
@@ -529,17 +2019,19 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if at least one `Output` has been enabled, `false` if all
-    /// `Output`s have been enabled already, and `null` if the specified
-    /// `Restream` doesn't exist.
+    /// Returns `true` if at least one `Output` has been enabled, or `false` if
+    /// all `Output`s have been enabled already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream` doesn't exist.
     fn enable_all_outputs(
         #[graphql(
             description = "ID of the `Restream` to enable all `Output`s in."
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_all_outputs(restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().enable_all_outputs(restream_id)?)
     }
 
     /// Disables all `Output`s in the specified `Restream`.
@@ -549,17 +2041,19 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if at least one `Output` has been disabled, `false` if
-    /// all `Output`s have been disabled already, and `null` if the specified
-    /// `Restream` doesn't exist.
+    /// Returns `true` if at least one `Output` has been disabled, or `false`
+    /// if all `Output`s have been disabled already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream` doesn't exist.
     fn disable_all_outputs(
         #[graphql(
             description = "ID of the `Restream` to disable all `Output`s in."
         )]
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_all_outputs(restream_id)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().disable_all_outputs(restream_id)?)
     }
 
     /// Disables all `Output`s in all `Restream`s.
@@ -592,9 +2086,11 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if a `Volume` rate has been changed, `false` if it has
-    /// the same value already, or `null` if the specified `Output` or `Mixin`
-    /// doesn't exist.
+    /// Returns `true` if a `Volume` rate has been changed, or `false` if it
+    /// has the same value already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` or `Mixin` doesn't
+    /// exist.
     fn tune_volume(
         #[graphql(
             description = "ID of the `Restream` to tune the `Output` in."
@@ -610,14 +2106,77 @@ impl MutationsRoot {
         #[graphql(description = "Volume rate in percents to be set.")]
         level: VolumeLevel,
         muted: bool,
+        #[graphql(description = "Duration (in milliseconds) to fade the \
+                                 audio into the new `Volume` rate over, \
+                                 capped to 10 seconds.\
+                                 \n\n\
+                                 If not specified, then the rate is changed \
+                                 instantly.")]
+        fade: Option<Delay>,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_volume(
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_volume(
             restream_id,
             output_id,
             mixin_id,
             Volume { level, muted },
-        )
+            fade.unwrap_or_default(),
+        )?)
+    }
+
+    /// Tunes a `Volume` rate of all the `Output`s of the specified `Restream`,
+    /// or of all their `Mixin`s matching the given `mixinSrcPattern`, at once.
+    ///
+    /// Useful for muting all the translation `Mixin`s during a live show.
+    ///
+    /// ### Result
+    ///
+    /// Returns the number of `Output`s and `Mixin`s whose `Volume` rate has
+    /// been changed.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Restream` doesn't exist, or
+    /// with `INVALID_MIXIN_SRC_PATTERN` if the `mixinSrcPattern` is not a valid
+    /// regular expression.
+    fn tune_volume_all(
+        #[graphql(
+            description = "ID of the `Restream` to tune the `Output`s in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "Regular expression to match URLs of the \
+                                 tuned `Mixin`s against.\
+                                 \n\n\
+                                 If not set, then tunes the `Output`s rather \
+                                 than their `Mixin`s.")]
+        mixin_src_pattern: Option<String>,
+        #[graphql(description = "Volume rate in percents to be set.")]
+        level: VolumeLevel,
+        muted: bool,
+        #[graphql(description = "Duration (in milliseconds) to fade the \
+                                 audio into the new `Volume` rate over, \
+                                 capped to 10 seconds.\
+                                 \n\n\
+                                 If not specified, then the rate is changed \
+                                 instantly.")]
+        fade: Option<Delay>,
+        context: &Context,
+    ) -> Result<i32, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let pattern = mixin_src_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidMixinSrcPattern)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        Ok(context.state().tune_volume_all(
+            restream_id,
+            pattern.as_ref(),
+            &Volume { level, muted },
+            fade.unwrap_or_default(),
+        )?)
     }
 
     /// Tunes a `Delay` of the specified `Mixin` before mix it into its
@@ -625,8 +2184,10 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if a `Delay` has been changed, `false` if it has the same
-    /// value already, or `null` if the specified `Output` or `Mixin` doesn't
+    /// Returns `true` if a `Delay` has been changed, or `false` if it has the
+    /// same value already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` or `Mixin` doesn't
     /// exist.
     fn tune_delay(
         #[graphql(
@@ -640,10 +2201,69 @@ impl MutationsRoot {
                                  the `Mixin` before mix it into its `Output`.")]
         delay: Delay,
         context: &Context,
-    ) -> Option<bool> {
-        context
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_delay(
+            restream_id,
+            output_id,
+            mixin_id,
+            delay,
+        )?)
+    }
+
+    /// Sets a new source URL of the specified `Mixin`, preserving its
+    /// `Volume`, `Delay` and side-chain settings.
+    ///
+    /// Only the FFmpeg process mixing the `Output` of the `Mixin` is
+    /// restarted.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the source URL has been changed, or `false` if it
+    /// has the same value already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` or `Mixin` doesn't
+    /// exist.
+    fn set_mixin_src(
+        #[graphql(
+            description = "ID of the `Restream` to change the `Mixin` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` of the changed `Mixin`.")]
+        output_id: OutputId,
+        #[graphql(description = "ID of the changed `Mixin`.")]
+        mixin_id: MixinId,
+        #[graphql(description = "New `MixinSrcUrl` of the `Mixin`.")]
+        new_src: MixinSrcUrl,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let output = context
             .state()
-            .tune_delay(restream_id, output_id, mixin_id, delay)
+            .get_output(restream_id, output_id)
+            .ok_or(StateError::NotFound("Output"))?;
+        if !output.mixins.iter().any(|m| m.id == mixin_id) {
+            return Err(StateError::NotFound("Mixin").into());
+        }
+        let srcs: Vec<_> = output
+            .mixins
+            .iter()
+            .map(|m| {
+                if m.id == mixin_id {
+                    new_src.clone()
+                } else {
+                    m.src.clone()
+                }
+            })
+            .collect();
+        validate_mixin_srcs(&srcs, restream_id, context)?;
+
+        Ok(context.state().set_mixin_src(
+            restream_id,
+            output_id,
+            mixin_id,
+            new_src,
+        )?)
     }
 
     /// Tunes a `Sidechain` of the specified `Mixin` before mix it into its
@@ -651,9 +2271,11 @@ impl MutationsRoot {
     ///
     /// ### Result
     ///
-    /// Returns `true` if a `Sidechain` has been changed, `false` if it has
-    /// the same value already, or `null` if the specified `Output`
-    /// or `Mixin` doesn't exist.
+    /// Returns `true` if a `Sidechain` has been changed, or `false` if it has
+    /// the same value already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` or `Mixin` doesn't
+    /// exist.
     fn tune_sidechain(
         #[graphql(
             description = "ID of the `Restream` to tune the the `Mixin` in."
@@ -664,13 +2286,86 @@ impl MutationsRoot {
         #[graphql(description = "ID of the tuned `Mixin`.")] mixin_id: MixinId,
         sidechain: bool,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_sidechain(
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_sidechain(
             restream_id,
             output_id,
             mixin_id,
             sidechain,
-        )
+        )?)
+    }
+
+    /// Tunes parameters of side-chaining the specified `Mixin` with its
+    /// `Output`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the parameters have been changed, or `false` if they
+    /// are the same already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` or `Mixin` doesn't
+    /// exist.
+    fn tune_sidechain_params(
+        #[graphql(
+            description = "ID of the `Restream` to tune the the `Mixin` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` of the tuned `Mixin`.")]
+        output_id: OutputId,
+        #[graphql(description = "ID of the tuned `Mixin`.")] mixin_id: MixinId,
+        #[graphql(description = "New side-chain parameters of the `Mixin`.")]
+        params: spec::v1::MixinSidechainParams,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if !params.is_valid() {
+            return Err(graphql::Error::new(ErrorCode::InvalidSidechainParams)
+                .status(StatusCode::BAD_REQUEST)
+                .message("Side-chain parameters are out of allowed ranges"));
+        }
+        Ok(context.state().tune_sidechain_params(
+            restream_id,
+            output_id,
+            mixin_id,
+            MixinSidechainParams::new(params),
+        )?)
+    }
+
+    /// Tunes an offset of the specified `Output`'s audio relatively to its
+    /// video in real-time, correcting their desynchronization introduced by
+    /// mixing.
+    ///
+    /// Changing the sign of a negative offset (which delays the video)
+    /// restarts the mixing process.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the offset has been changed, or `false` if it has
+    /// the same value already.
+    ///
+    /// Errors with `NOT_FOUND` if the specified `Output` doesn't exist.
+    fn tune_av_offset(
+        #[graphql(
+            description = "ID of the `Restream` to tune the `Output` in."
+        )]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the tuned `Output`.")]
+        output_id: OutputId,
+        #[graphql(description = "Number of milliseconds to offset the \
+                                 `Output`'s audio by. Positive value delays \
+                                 the audio, while negative one delays the \
+                                 video.")]
+        av_offset: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if !Output::is_valid_av_offset(av_offset) {
+            return Err(av_offset_error(av_offset));
+        }
+        Ok(context
+            .state()
+            .tune_av_offset(restream_id, output_id, av_offset)?)
     }
 
     /// Removes the specified recorded file.
@@ -686,9 +2381,11 @@ impl MutationsRoot {
                            Use the exact value returned by `Query.dvrFiles`."
         )]
         path: String,
+        context: &Context,
     ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
         if path.starts_with('/') || path.contains("../") {
-            return Err(graphql::Error::new("INVALID_DVR_FILE_PATH")
+            return Err(graphql::Error::new(ErrorCode::InvalidDvrFilePath)
                 .status(StatusCode::BAD_REQUEST)
                 .message(&format!("Invalid DVR file path: {path}")));
         }
@@ -702,6 +2399,9 @@ impl MutationsRoot {
     /// perform [HTTP Basic auth][1], where any username is allowed, but the
     /// password should match the one being set.
     ///
+    /// Once password is changed, all the subscriptions established before are
+    /// terminated, so should be re-established with the new password.
+    ///
     /// ### Result
     ///
     /// Returns `true` if password has been changed or unset, otherwise `false`
@@ -718,6 +2418,7 @@ impl MutationsRoot {
         kind: Option<PasswordKind>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
         static HASH_CFG: Lazy<argon2::Config<'static>> =
             Lazy::new(argon2::Config::default);
 
@@ -730,15 +2431,17 @@ impl MutationsRoot {
         if let Some(hash) = &hash {
             match old {
                 None => {
-                    return Err(graphql::Error::new("NO_OLD_PASSWORD")
+                    return Err(graphql::Error::new(ErrorCode::NoOldPassword)
                         .status(StatusCode::FORBIDDEN)
                         .message("Old password required for this action"))
                 }
                 Some(pass) => {
                     if !argon2::verify_encoded(hash, pass.as_bytes()).unwrap() {
-                        return Err(graphql::Error::new("WRONG_OLD_PASSWORD")
-                            .status(StatusCode::FORBIDDEN)
-                            .message("Wrong old password specified"));
+                        return Err(graphql::Error::new(
+                            ErrorCode::WrongOldPassword,
+                        )
+                        .status(StatusCode::FORBIDDEN)
+                        .message("Wrong old password specified"));
                     }
                 }
             }
@@ -766,16 +2469,58 @@ impl MutationsRoot {
                 settings.password_output_hash = new_hash;
             }
         };
+        settings.auth_generation += 1;
 
         Ok(true)
     }
 
+    /// Sets or unsets the password granting read-only access to this GraphQL
+    /// API for the given tenant.
+    ///
+    /// Once set, requests performing [HTTP Basic auth][1] with the tenant as
+    /// username and this password are allowed to query and subscribe to the
+    /// `Restream`s of the tenant only, while any mutations are rejected.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if password has been changed or unset, otherwise `false`
+    /// if nothing changes.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Basic_access_authentication
+    fn set_tenant_password(
+        #[graphql(description = "Tenant to set the password for.")]
+        tenant: TenantKey,
+        #[graphql(description = "New password to be set. \
+                                 In `null` then unsets the current password.")]
+        new: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        static HASH_CFG: Lazy<argon2::Config<'static>> =
+            Lazy::new(argon2::Config::default);
+
+        let mut settings = context.state().settings.lock_mut();
+        Ok(match new {
+            Some(pass) => {
+                let hash = argon2::hash_encoded(
+                    pass.as_bytes(),
+                    &rand::thread_rng().gen::<[u8; 32]>(),
+                    &HASH_CFG,
+                )
+                .unwrap();
+                let _ = settings.tenant_password_hashes.insert(tenant, hash);
+                true
+            }
+            None => settings.tenant_password_hashes.remove(&tenant).is_some(),
+        })
+    }
+
     /// Sets settings of the server
     ///
     /// ### Result
     ///
-    /// Returns `false` if title does not pass validation for max allowed
-    /// characters length. Otherwise returns `true`
+    /// Errors with `INVALID_TITLE` if title exceeds 70 characters or contains
+    /// control characters. Otherwise returns `true`.
     fn set_settings(
         #[graphql(description = "Title for the server")] title: Option<String>,
         #[graphql(description = "Whether do we need to confirm deletion \
@@ -786,24 +2531,443 @@ impl MutationsRoot {
                            of inputs or outputs"
         )]
         enable_confirmation: Option<bool>,
+        #[graphql(description = "Duration (in minutes) of an enabled \
+                                 `Input` being offline, after which it's \
+                                 disabled automatically.\
+                                 \n\n\
+                                 If not specified, then `Input`s are never \
+                                 disabled automatically, unless their \
+                                 `Restream` says otherwise.")]
+        idle_timeout: Option<i32>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
-        // Validate title
-        let value = title.unwrap_or_default();
-        if value.len() > 70 {
-            return Err(graphql::Error::new("WRONG_TITLE_LENGTH")
-                .status(StatusCode::BAD_REQUEST)
-                .message("Title exceeds max allowed length of 70 characters"));
+        context.ensure_not_in_maintenance()?;
+        let title = title
+            .filter(|t| !t.trim().is_empty())
+            .map(Title::new)
+            .transpose()
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidTitle)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&format!("Title {e}"))
+            })?;
+        if let Some(t) = idle_timeout {
+            if t == 0 || !Restream::is_valid_idle_timeout(t) {
+                return Err(idle_timeout_error(1, t));
+            }
         }
 
         let mut settings = context.state().settings.lock_mut();
-        settings.title = Some(value);
+        settings.title = title;
         settings.delete_confirmation = delete_confirmation;
         settings.enable_confirmation = enable_confirmation;
+        settings.idle_timeout = idle_timeout;
+        Ok(true)
+    }
+
+    /// Sets limits of `Mixin`s that a single `Output` may have on this server.
+    ///
+    /// Limits cannot exceed 16 `Mixin`s and 8 TeamSpeak ones.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the limits have been changed, or `false` if they are
+    /// the same already.
+    ///
+    /// Errors with `INVALID_MIXIN_LIMITS` if the limits are out of range, or
+    /// some existing `Output` exceeds them.
+    fn set_mixin_limits(
+        #[graphql(description = "Maximum number of `Mixin`s of an `Output`.")]
+        max_mixins: i32,
+        #[graphql(description = "Maximum number of TeamSpeak `Mixin`s of an \
+                                 `Output`.")]
+        max_teamspeak_mixins: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        let limits = MixinLimits {
+            max_mixins,
+            max_teamspeak_mixins,
+        };
+        if !limits.is_valid() {
+            return Err(graphql::Error::new(ErrorCode::InvalidMixinLimits)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!(
+                    "Mixin limits should not exceed {} mixins and {} \
+                     TeamSpeak ones, with the latter not exceeding the former",
+                    MixinLimits::CEILING.max_mixins,
+                    MixinLimits::CEILING.max_teamspeak_mixins,
+                )));
+        }
+        context.state().set_mixin_limits(limits).map_err(|e| {
+            graphql::Error::new(ErrorCode::InvalidMixinLimits)
+                .status(StatusCode::CONFLICT)
+                .message(&e)
+        })
+    }
+
+    /// Switches maintenance mode of the server.
+    ///
+    /// In maintenance mode, all the mutations (except this one) are rejected
+    /// with `MAINTENANCE_MODE` error, while queries and subscriptions keep
+    /// working. Running re-streaming processes are not affected.
+    ///
+    /// ### Result
+    ///
+    /// Returns `false` if the maintenance mode has been switched this way
+    /// already. Otherwise returns `true`.
+    fn set_maintenance_mode(
+        #[graphql(description = "Indicator whether the maintenance mode \
+                                 should be enabled.")]
+        enabled: bool,
+        context: &Context,
+    ) -> bool {
+        let mut mode = context.state().maintenance_mode.lock_mut();
+        if *mode == enabled {
+            return false;
+        }
+        *mode = enabled;
+        true
+    }
+
+    /// Abruptly kills FFmpeg process serving an `Output` or an
+    /// `InputEndpoint`, simulating its crash.
+    ///
+    /// Available in debug mode only.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the process has been killed, or `false` if there is
+    /// no such process running.
+    fn debug_kill_ffmpeg(
+        #[graphql(description = "ID of the `Output` to kill process of.")]
+        output_id: Option<OutputId>,
+        #[graphql(description = "ID of the `InputEndpoint` to kill \
+                                 process of.")]
+        endpoint_id: Option<EndpointId>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        ensure_debug_mode(context)?;
+        let id = output_id
+            .map(Into::into)
+            .or_else(|| endpoint_id.map(Into::into))
+            .ok_or_else(|| {
+                graphql::Error::new(ErrorCode::NoProcessSpecified)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message("Either `outputId` or `endpointId` is required")
+            })?;
+        Ok(RestreamerKind::kill_process(id))
+    }
+
+    /// Forcibly sets a status of an `InputEndpoint`, simulating its failure.
+    ///
+    /// The status is overwritten once the real one changes.
+    ///
+    /// Available in debug mode only.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the status has been changed, or `false` if it is the
+    /// same already.
+    ///
+    /// Errors with `NOT_FOUND` if the `InputEndpoint` doesn't exist.
+    fn debug_set_endpoint_status(
+        #[graphql(description = "ID of the `InputEndpoint` to set status of.")]
+        id: EndpointId,
+        #[graphql(description = "ID of the `Restream` the `InputEndpoint` \
+                                 is in.")]
+        restream_id: RestreamId,
+        #[graphql(description = "Status to be set.")] status: Status,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        ensure_debug_mode(context)?;
+        Ok(context
+            .state()
+            .set_endpoint_status(id, restream_id, status)?)
+    }
+
+    /// Injects a fake CPU usage of the server, simulating a high load.
+    ///
+    /// Available in debug mode only.
+    ///
+    /// ### Result
+    ///
+    /// Always returns `true`.
+    fn debug_inject_cpu_usage(
+        #[graphql(description = "CPU usage (in percents) to be reported.\
+                                 \n\n\
+                                 If not specified, then the real one is \
+                                 reported again.")]
+        usage: Option<f64>,
+        #[graphql(description = "Duration (in seconds) to report the fake \
+                                 CPU usage during.\
+                                 \n\n\
+                                 If not specified, then 60 seconds is used.")]
+        duration: Option<i32>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        ensure_debug_mode(context)?;
+        let duration = u64::try_from(duration.unwrap_or(60)).map_err(|_| {
+            graphql::Error::new(ErrorCode::WrongDuration)
+                .status(StatusCode::BAD_REQUEST)
+                .message("Duration should be non-negative")
+        })?;
+        statistics::inject_cpu_usage(usage, Duration::from_secs(duration));
         Ok(true)
     }
 }
 
+/// Ensures that the server runs in debug mode, as required by simulating
+/// mutations.
+///
+/// # Errors
+///
+/// If the server doesn't run in debug mode.
+fn ensure_debug_mode(context: &Context) -> Result<(), graphql::Error> {
+    if context.config().debug {
+        Ok(())
+    } else {
+        Err(graphql::Error::new(ErrorCode::DebugModeRequired)
+            .status(StatusCode::FORBIDDEN)
+            .message("Available in debug mode only"))
+    }
+}
+
+/// Converts the given `min_interval` (in milliseconds) argument of a
+/// subscription into a [`Duration`].
+///
+/// # Errors
+///
+/// If the given `min_interval` is negative.
+fn to_min_interval(
+    min_interval: Option<i32>,
+) -> Result<Option<Duration>, graphql::Error> {
+    min_interval
+        .map(|ms| {
+            u64::try_from(ms).map(Duration::from_millis).map_err(|_| {
+                graphql::Error::new(ErrorCode::WrongDuration)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message("Minimum interval should be non-negative")
+            })
+        })
+        .transpose()
+}
+
+/// Converts the given `seconds` of a `ServerInfo` history into a
+/// [`Duration`].
+///
+/// # Errors
+///
+/// If the given `seconds` are negative.
+fn to_history_period(seconds: i32) -> Result<Duration, graphql::Error> {
+    u64::try_from(seconds)
+        .map(Duration::from_secs)
+        .map_err(|_| {
+            graphql::Error::new(ErrorCode::WrongDuration)
+                .status(StatusCode::BAD_REQUEST)
+                .message("History period should be non-negative")
+        })
+}
+
+/// Throttles the given [`Stream`] of signal changes, so its items are polled
+/// not more often than once per the given `min_interval` (if any).
+///
+/// As signal streams yield only the latest value, the changes happened
+/// within the `min_interval` are coalesced.
+fn throttle<S>(
+    changes: S,
+    min_interval: Option<Duration>,
+) -> BoxStream<'static, S::Item>
+where
+    S: Stream + Send + Unpin + 'static,
+{
+    let min_interval = match min_interval {
+        Some(i) if !i.is_zero() => i,
+        _ => return changes.boxed(),
+    };
+    stream::unfold((changes, None), move |(mut changes, last)| async move {
+        if let Some(last) = last {
+            time::sleep_until(last + min_interval).await;
+        }
+        let item = changes.next().await?;
+        Some((item, (changes, Some(Instant::now()))))
+    })
+    .boxed()
+}
+
+/// Checks whether the given GraphQL `selection` set has balanced braces and
+/// no comments, so cannot escape the query it's embedded into.
+fn is_valid_selection(selection: &str) -> bool {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in selection.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '#' => return false,
+            '{' => depth += 1,
+            '}' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0 && !in_string && !selection.trim().is_empty()
+}
+
+/// Executes the given GraphQL `query` against the [`SCHEMA`] with the given
+/// [`Context`], returning the `allRestreams` field of its result as JSON.
+///
+/// # Errors
+///
+/// If the `query` fails to be executed.
+async fn execute_restreams_query(
+    query: &str,
+    context: &Context,
+) -> Result<Json, graphql::Error> {
+    let invalid = |msg: &dyn std::fmt::Display| {
+        graphql::Error::new(ErrorCode::InvalidSelection)
+            .status(StatusCode::BAD_REQUEST)
+            .message(msg)
+    };
+    let (data, errors) = juniper::execute(
+        query,
+        None,
+        &*SCHEMA,
+        &juniper::Variables::new(),
+        context,
+    )
+    .await
+    .map_err(|e| invalid(&e))?;
+    if let Some(e) = errors.first() {
+        return Err(invalid(&e.error().message()));
+    }
+    let mut data = serde_json::to_value(&data).map_err(|e| invalid(&e))?;
+    Ok(data
+        .get_mut("allRestreams")
+        .map(Json::take)
+        .unwrap_or_default())
+}
+
+/// Validates the given `mixins` sources of an `Output` in the `Restream` with
+/// the given `restream_id`.
+///
+/// # Errors
+///
+/// If there are too many `mixins`, some of them are duplicated, or refer to a
+/// non-existent `Restream` or the `Restream` itself.
+fn validate_mixin_srcs(
+    mixins: &[MixinSrcUrl],
+    restream_id: RestreamId,
+    context: &Context,
+) -> Result<(), graphql::Error> {
+    let limits = context.state().settings.lock_ref().mixin_limits;
+    if let Err(e) = limits.check(mixins) {
+        let code = match e {
+            MixinLimitsError::TooMuchMixins(_) => ErrorCode::TooMuchMixinUrls,
+            MixinLimitsError::TooMuchTeamspeakMixins(_) => {
+                ErrorCode::TooMuchTeamspeakMixinUrls
+            }
+        };
+        return Err(graphql::Error::new(code)
+            .status(StatusCode::BAD_REQUEST)
+            .message(&e));
+    }
+    if mixins.is_empty() {
+        return Ok(());
+    }
+
+    let mut unique = HashSet::with_capacity(mixins.len());
+    for m in mixins {
+        if let Some(dup) = unique.replace(m) {
+            return Err(graphql::Error::new(ErrorCode::DuplicateMixinUrl)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!("Duplicate Output.mixin.src: {}", dup,)));
+        }
+    }
+
+    let restreams = context.state().restreams.get_cloned();
+    for key in mixins.iter().filter_map(MixinSrcUrl::restream_key) {
+        match restreams.iter().find(|r| r.key == key) {
+            Some(r) if r.id == restream_id => {
+                return Err(graphql::Error::new(ErrorCode::SelfMixinRestream)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&format!(
+                        "Restream '{}' cannot be mixed into itself",
+                        key,
+                    )));
+            }
+            Some(_) => {}
+            None => {
+                return Err(graphql::Error::new(
+                    ErrorCode::UnknownMixinRestream,
+                )
+                .status(StatusCode::NOT_FOUND)
+                .message(&format!(
+                    "Restream '{}' referred by Output.mixin.src doesn't exist",
+                    key,
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Looks up the [`HlsVariantSelector`] of the remote source with the given
+/// `url` among the sources of the given [`Input`] (including its failover
+/// ones).
+fn remote_hls_variant(
+    input: &Input,
+    url: &InputSrcUrl,
+) -> Option<HlsVariantSelector> {
+    match input.src.as_ref()? {
+        InputSrc::Remote(r) => {
+            (r.url == *url).then_some(r.hls_variant).flatten()
+        }
+        InputSrc::Failover(s) => {
+            s.inputs.iter().find_map(|i| remote_hls_variant(i, url))
+        }
+        InputSrc::Playlist(_) => None,
+    }
+}
+
+/// Creates a [`graphql::Error`] of the given `offset` being out of the range
+/// allowed for [`Output::av_offset`].
+fn av_offset_error(offset: i32) -> graphql::Error {
+    graphql::Error::new(ErrorCode::InvalidAvOffset)
+        .status(StatusCode::BAD_REQUEST)
+        .message(&format!(
+            "Output audio/video offset should be in -{max}..={max} range, \
+             but {offset} provided",
+            max = Output::MAX_AV_OFFSET,
+        ))
+}
+
+/// Creates a [`graphql::Error`] of the given `timeout` being out of the range
+/// allowed for an idle timeout of `Input`s, starting from the given `min`.
+fn idle_timeout_error(min: i32, timeout: i32) -> graphql::Error {
+    graphql::Error::new(ErrorCode::InvalidIdleTimeout)
+        .status(StatusCode::BAD_REQUEST)
+        .message(&format!(
+            "Idle timeout should be in {min}..={max} range (in minutes), \
+             but {timeout} provided",
+            max = Restream::MAX_IDLE_TIMEOUT,
+        ))
+}
+
 /// Root of all [GraphQL queries][1] in the [`Schema`].
 ///
 /// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
@@ -812,16 +2976,31 @@ pub struct QueriesRoot;
 
 #[graphql_object(name = "Query", context = Context)]
 impl QueriesRoot {
+    /// Returns all the `ErrorCode`s which may be returned by this API in
+    /// `errors.extensions.code` field of GraphQL response.
+    fn error_codes() -> Vec<ErrorCode> {
+        ErrorCode::ALL.to_vec()
+    }
+
     /// Returns the current `Info` parameters of this server.
     fn info(context: &Context) -> Info {
         let settings = context.state().settings.get_cloned();
         Info {
             public_host: context.config().public_host.clone().unwrap(),
+            public_rtmp_port: i32::from(
+                context
+                    .config()
+                    .public_rtmp_port
+                    .unwrap_or(context.config().srs_rtmp_port),
+            ),
             password_hash: settings.password_hash,
             password_output_hash: settings.password_output_hash,
-            title: settings.title,
+            title: settings.title.map(Into::into),
             delete_confirmation: settings.delete_confirmation,
             enable_confirmation: settings.enable_confirmation,
+            idle_timeout: settings.idle_timeout,
+            mixin_limits: settings.mixin_limits,
+            maintenance_mode: context.state().maintenance_mode.get(),
         }
     }
 
@@ -835,13 +3014,79 @@ impl QueriesRoot {
             ram_free: info.ram_free,
             tx_delta: info.tx_delta,
             rx_delta: info.rx_delta,
+            disk_total: info.disk_total,
+            disk_free: info.disk_free,
             error_msg: info.error_msg,
+            state_persistence_error: info.state_persistence_error,
         }
     }
 
+    /// Returns `ServerInfoSample`s taken during the last `seconds`, ordered
+    /// from the oldest to the newest, allowing to draw charts of the server
+    /// resources usage.
+    ///
+    /// Only the samples of the last 15 minutes are kept.
+    fn server_info_history(
+        #[graphql(
+            description = "Period (in seconds) to return samples for.",
+            default = 900
+        )]
+        seconds: i32,
+        context: &Context,
+    ) -> Result<Vec<ServerInfoSample>, graphql::Error> {
+        let period = to_history_period(seconds)?;
+        Ok(context.state().server_info_since(period))
+    }
+
+    /// Returns version of the FFmpeg binary currently used for spawning
+    /// re-streaming processes.
+    ///
+    /// Reflects the binary replacement once it's detected. Returns `null` if
+    /// the version cannot be detected.
+    fn ffmpeg_version() -> Option<String> {
+        ffmpeg::Binary::global().version()
+    }
+
     /// Returns all the `Restream`s happening on this server.
+    ///
+    /// Only the `Restream`s of the tenant are returned, if authorized with
+    /// its credentials.
     fn all_restreams(context: &Context) -> Vec<Restream> {
-        context.state().restreams.get_cloned()
+        let tenant = context.tenant();
+        context
+            .state()
+            .restreams
+            .get_cloned()
+            .into_iter()
+            .filter(|r| r.is_visible_to(tenant.as_ref()))
+            .collect()
+    }
+
+    /// Returns all the `RestreamTemplate`s stored on this server.
+    fn templates(context: &Context) -> Vec<RestreamTemplate> {
+        context.state().settings.get_cloned().templates
+    }
+
+    /// Returns entries of the audit log, describing mutations requested via
+    /// GraphQL APIs of this server, ordered from the oldest to the newest.
+    ///
+    /// Only the latest 1000 entries are kept.
+    fn audit_log(
+        #[graphql(
+            description = "Maximum number of entries to return.",
+            default = 100
+        )]
+        limit: i32,
+        #[graphql(description = "ID of the entry to return the ones \
+                                 following it.\
+                                 \n\n\
+                                 If not specified, then the latest entries \
+                                 are returned.")]
+        after: Option<i32>,
+        context: &Context,
+    ) -> Vec<AuditEntry> {
+        let limit = usize::try_from(limit).unwrap_or_default();
+        audit::list(context.state(), limit, after)
     }
 
     /// Returns list of recorded files of the specified `Output`.
@@ -849,24 +3094,67 @@ impl QueriesRoot {
     /// If returned list is empty, the there is no recorded files for the
     /// specified `Output`.
     ///
-    /// Each recorded file is represented as a relative path on [SRS] HTTP
-    /// server in `dvr/` directory, so the download link should look like this:
+    /// Each recorded file should be downloaded via its `url`, requiring the
+    /// same authorization as this API does. Its thumbnail and markers are
+    /// represented as relative paths, which may be downloaded the same way:
     /// ```ignore
-    /// http://my.host:8080/dvr/returned/file/path.flv
-    /// http://my.host:8080/dvr/returned/file/path.wav
-    /// http://my.host:8080/dvr/returned/file/path.mp3
+    /// /dvr/returned/file/path.flv
+    /// /dvr/returned/thumbnail/path.jpg
     /// ```
     ///
-    /// [SRS]: https://github.com/ossrs/srs
+    /// Thumbnails of recordings are generated in background once their
+    /// recording finishes, so may appear in subsequent queries only.
     async fn dvr_files(
         #[graphql(
             description = "ID of the `Output` to return recorded files of."
         )]
         id: OutputId,
-    ) -> Vec<String> {
+    ) -> Vec<DvrFile> {
         dvr::Storage::global().list_files(id).await
     }
 
+    /// Returns details (codecs, resolution, frame rate, audio channels layout
+    /// and bitrate) of the live stream received by the specified
+    /// `InputEndpoint`.
+    ///
+    /// The live stream is probed once per its publishing session, and the
+    /// result is cached until the stream is re-published.
+    ///
+    /// Returns `null` if there is no such `InputEndpoint`, it doesn't receive
+    /// a live stream at the moment, or its live stream is being probed
+    /// already.
+    async fn stream_info(
+        #[graphql(description = "ID of the `Restream` to probe stream of.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Input` to probe stream of.")]
+        input_id: InputId,
+        #[graphql(
+            description = "ID of the `InputEndpoint` to probe stream of."
+        )]
+        endpoint_id: EndpointId,
+        context: &Context,
+    ) -> Option<StreamStatistics> {
+        let tenant = context.tenant();
+        let (cached, url, publisher) = context.state().stream_info_of(
+            restream_id,
+            input_id,
+            endpoint_id,
+            tenant.as_ref(),
+        )?;
+        if cached.is_some() {
+            return cached;
+        }
+
+        let stat = StreamStatistics::new(stream_probe_once(url).await?);
+        // Outdated results are still valid for this request.
+        let _ = context.state().cache_stream_info(
+            endpoint_id,
+            &publisher,
+            stat.clone(),
+        );
+        Some(stat)
+    }
+
     /// Returns `Restream`s happening on this server and identifiable by the
     /// given `ids` in an exportable JSON format.
     ///
@@ -882,20 +3170,26 @@ impl QueriesRoot {
         ids: Vec<RestreamId>,
         context: &Context,
     ) -> Result<Option<String>, graphql::Error> {
-        let settings = context.state().settings.get_cloned().export();
+        let tenant = context.tenant();
+        // Tenants are not allowed to see the server-wide settings.
+        let settings = tenant
+            .is_none()
+            .then(|| context.state().settings.get_cloned().export());
         let restreams = context
             .state()
             .restreams
             .get_cloned()
             .into_iter()
             .filter_map(|r| {
-                (ids.is_empty() || ids.contains(&r.id)).then(|| r.export())
+                ((ids.is_empty() || ids.contains(&r.id))
+                    && r.is_visible_to(tenant.as_ref()))
+                .then(|| r.export())
             })
             .collect::<Vec<_>>();
         (!restreams.is_empty())
             .then(|| {
                 let spec: Spec = spec::v1::Spec {
-                    settings: Some(settings),
+                    settings,
                     restreams,
                 }
                 .into();
@@ -905,6 +3199,18 @@ impl QueriesRoot {
             })
             .transpose()
     }
+
+    /// Returns automatic backups of the server's state, the newest first.
+    ///
+    /// Any of them may be restored with the `restoreBackup` mutation.
+    ///
+    /// Always returns an empty list if authorized as a tenant.
+    async fn backups(context: &Context) -> Vec<Backup> {
+        if context.tenant().is_some() {
+            return vec![];
+        }
+        backup::Storage::global().list().await
+    }
 }
 
 /// Root of all [GraphQL subscriptions][1] in the [`Schema`].
@@ -927,7 +3233,7 @@ impl SubscriptionsRoot {
                 public_host: public_host.clone(),
                 password_hash: h.password_hash,
                 password_output_hash: h.password_output_hash,
-                title: h.title,
+                title: h.title.map(Into::into),
                 delete_confirmation: h.delete_confirmation,
                 enable_confirmation: h.enable_confirmation,
             })
@@ -936,28 +3242,262 @@ impl SubscriptionsRoot {
     }
 
     /// Subscribes to updates of `ServerInfo` parameters of this server.
-    async fn server_info(context: &Context) -> BoxStream<'static, ServerInfo> {
+    ///
+    /// If `backfill` is specified, then the `ServerInfo` samples of the last
+    /// `backfill` seconds (see `serverInfoHistory`) are emitted first, so
+    /// charts may be drawn right away.
+    async fn server_info(
+        #[graphql(description = "Period (in seconds) of the latest samples \
+                                 to be emitted before the updates.")]
+        backfill: Option<i32>,
+        context: &Context,
+    ) -> Result<BoxStream<'static, ServerInfo>, graphql::Error> {
+        let mut history = backfill
+            .map(to_history_period)
+            .transpose()?
+            .map(|p| context.state().server_info_since(p))
+            .unwrap_or_default();
+        // The latest sample is the current `ServerInfo`, emitted by the
+        // signal anyway.
+        let _ = history.pop();
+        Ok(stream::iter(history.into_iter().map(|s| s.info))
+            .chain(
+                context
+                    .state()
+                    .server_info
+                    .signal_cloned()
+                    .dedupe_cloned()
+                    .to_stream(),
+            )
+            .boxed())
+    }
+
+    /// Subscribes to updates of all `Restream`s happening on this server.
+    async fn all_restreams(
+        #[graphql(description = "Minimum interval (in milliseconds) between \
+                                 emissions. Changes happened within it are \
+                                 coalesced into the latest snapshot.\
+                                 \n\n\
+                                 If not specified, then every change is \
+                                 emitted.")]
+        min_interval: Option<i32>,
+        context: &Context,
+    ) -> Result<BoxStream<'static, Vec<Restream>>, graphql::Error> {
+        let min_interval = to_min_interval(min_interval)?;
+        let tenant = context.tenant();
+        Ok(throttle(
+            context
+                .state()
+                .restreams
+                .signal_cloned()
+                .map(move |restreams| {
+                    restreams
+                        .into_iter()
+                        .filter(|r| r.is_visible_to(tenant.as_ref()))
+                        .collect::<Vec<_>>()
+                })
+                .dedupe_cloned()
+                .to_stream(),
+            min_interval,
+        ))
+    }
+
+    /// Subscribes to updates of all `Restream`s happening on this server,
+    /// emitting them as [JSON Patch][1] documents relative to the previous
+    /// emission, rather than full snapshots.
+    ///
+    /// The first emission replaces the whole document (`null` initially) with
+    /// the full snapshot. Emissions with no changes are omitted.
+    ///
+    /// Each emission is a JSON-encoded array of JSON Patch operations, applied
+    /// to an array of `Restream`s, containing the fields of the provided
+    /// `selection` only.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc6902
+    async fn all_restreams_patch(
+        #[graphql(description = "GraphQL selection set of `Restream` fields \
+                                 to be emitted, like \
+                                 `id key input { endpoints { id status } }`.")]
+        selection: String,
+        #[graphql(description = "Minimum interval (in milliseconds) between \
+                                 emissions. Changes happened within it are \
+                                 coalesced into a single patch.\
+                                 \n\n\
+                                 If not specified, then every change is \
+                                 emitted.")]
+        min_interval: Option<i32>,
+        context: &Context,
+    ) -> Result<BoxStream<'static, String>, graphql::Error> {
+        let min_interval = to_min_interval(min_interval)?;
+        if !is_valid_selection(&selection) {
+            return Err(graphql::Error::new(ErrorCode::InvalidSelection)
+                .status(StatusCode::BAD_REQUEST)
+                .message("Selection set should have balanced braces"));
+        }
+        let query = format!(
+            "query AllRestreamsPatch {{ allRestreams {{ {selection} }} }}",
+        );
+        // Ensure the selection is executable, so the subscription doesn't
+        // silently emit nothing.
+        let _ = execute_restreams_query(&query, context).await?;
+
+        let context = context.clone();
+        let changes = context
+            .state()
+            .restreams
+            .signal_cloned()
+            .dedupe_cloned()
+            .to_stream();
+        Ok(throttle(changes, min_interval)
+            .then(move |_| {
+                let (query, context) = (query.clone(), context.clone());
+                async move {
+                    execute_restreams_query(&query, &context)
+                        .await
+                        .map_err(|e| {
+                            log::error!(
+                                "Failed to execute `allRestreamsPatch` \
+                                 selection: {}",
+                                e,
+                            );
+                        })
+                        .ok()
+                }
+            })
+            .filter_map(future::ready)
+            .scan(Json::Null, |prev, data| {
+                let ops = json_patch::diff(prev, &data);
+                *prev = data;
+                future::ready(Some(ops))
+            })
+            .filter_map(|ops| {
+                future::ready(
+                    (!ops.is_empty())
+                        .then(|| serde_json::to_string(&ops).ok())
+                        .flatten(),
+                )
+            })
+            .boxed())
+    }
+
+    /// Subscribes to `Status` updates of the specified `Mixin`.
+    ///
+    /// For `ts://` sources the `Status` reflects the connection with the
+    /// TeamSpeak server, so a dropped translation channel is seen as
+    /// `UNSTABLE` or `OFFLINE`.
+    ///
+    /// Emits `null` if there is no such `Mixin`.
+    async fn mixin_status(
+        #[graphql(description = "ID of the `Output` the `Mixin` belongs to.")]
+        output_id: OutputId,
+        #[graphql(description = "ID of the `Mixin` to subscribe to.")]
+        mixin_id: MixinId,
+        context: &Context,
+    ) -> BoxStream<'static, Option<Status>> {
         context
             .state()
-            .server_info
+            .restreams
             .signal_cloned()
+            .map(move |restreams| {
+                restreams
+                    .iter()
+                    .flat_map(|r| r.outputs.iter())
+                    .find(|o| o.id == output_id)?
+                    .mixins
+                    .iter()
+                    .find_map(|m| (m.id == mixin_id).then_some(m.status))
+            })
+            .dedupe()
+            .to_stream()
+            .boxed()
+    }
+
+    /// Subscribes to updates of the specified `Restream`.
+    ///
+    /// Emits only when this `Restream` changes, unlike `allRestreams`, which
+    /// emits on any change of any `Restream`.
+    ///
+    /// Emits `null` if there is no `Restream` with such `id`.
+    async fn restream(
+        #[graphql(description = "ID of the `Restream` to subscribe to.")]
+        id: RestreamId,
+        context: &Context,
+    ) -> BoxStream<'static, Option<Restream>> {
+        let tenant = context.tenant();
+        context
+            .state()
+            .restream_signal(id)
+            .map(move |r| r.filter(|r| r.is_visible_to(tenant.as_ref())))
             .dedupe_cloned()
             .to_stream()
             .boxed()
     }
 
-    /// Subscribes to updates of all `Restream`s happening on this server.
-    async fn all_restreams(
+    /// Subscribes to updates of the specified `Output` of the specified
+    /// `Restream`.
+    ///
+    /// Emits only when this `Output` changes.
+    ///
+    /// Emits `null` if there is no such `Output` or `Restream`.
+    async fn output(
+        #[graphql(description = "ID of the `Restream` to subscribe to the \
+                                 `Output` of.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` to subscribe to.")]
+        output_id: OutputId,
         context: &Context,
-    ) -> BoxStream<'static, Vec<Restream>> {
+    ) -> BoxStream<'static, Option<Output>> {
+        let tenant = context.tenant();
         context
             .state()
-            .restreams
-            .signal_cloned()
+            .restream_signal(restream_id)
+            .map(move |r| {
+                r.filter(|r| r.is_visible_to(tenant.as_ref()))?
+                    .outputs
+                    .into_iter()
+                    .find(|o| o.id == output_id)
+            })
             .dedupe_cloned()
             .to_stream()
             .boxed()
     }
+
+    /// Subscribes to new entries of the audit log, describing mutations
+    /// requested via GraphQL APIs of this server.
+    async fn audit_log(context: &Context) -> BoxStream<'static, AuditEntry> {
+        audit::watch(context.state()).boxed()
+    }
+
+    /// Subscribes to live audio levels of the specified `Output` and its
+    /// `Mixin`s, allowing to balance their volumes.
+    ///
+    /// Levels are sampled 5 times per second, and emitted only on changes.
+    ///
+    /// Emits `null` if there is no such `Output` or `Restream`.
+    async fn audio_levels(
+        #[graphql(description = "ID of the `Restream` to subscribe to the \
+                                 `Output` audio levels of.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` to subscribe to the \
+                                 audio levels of.")]
+        output_id: OutputId,
+        context: &Context,
+    ) -> BoxStream<'static, Option<OutputAudioLevels>> {
+        let state = context.state().clone();
+        stream::unfold((state, None), move |(state, prev)| async move {
+            loop {
+                if prev.is_some() {
+                    time::sleep(AUDIO_LEVELS_INTERVAL).await;
+                }
+                let levels =
+                    OutputAudioLevels::collect(&state, restream_id, output_id);
+                if prev.as_ref() != Some(&levels) {
+                    return Some((levels.clone(), (state, Some(levels))));
+                }
+            }
+        })
+        .boxed()
+    }
 }
 
 /// Information about parameters that this server operates with.
@@ -968,6 +3508,11 @@ pub struct Info {
     /// Use it for constructing URLs to this server.
     pub public_host: String,
 
+    /// Port that this server accepts RTMP live streams via in public.
+    ///
+    /// Use it for constructing RTMP URLs to this server.
+    pub public_rtmp_port: i32,
+
     /// Title of the server
     pub title: Option<String>,
 
@@ -977,6 +3522,17 @@ pub struct Info {
     /// Whether do we need to confirm enabling/disabling of inputs or outputs
     pub enable_confirmation: Option<bool>,
 
+    /// Duration (in minutes) of an enabled `Input` being offline, after which
+    /// it's disabled automatically, unless its `Restream` says otherwise.
+    pub idle_timeout: Option<i32>,
+
+    /// Limits of `Mixin`s that a single `Output` may have on this server.
+    pub mixin_limits: MixinLimits,
+
+    /// Indicator whether this server runs in maintenance mode, rejecting any
+    /// mutations except `setMaintenanceMode`.
+    pub maintenance_mode: bool,
+
     /// [Argon2] hash of the password that this server's GraphQL API is
     /// protected with, if any.
     ///
@@ -4,11 +4,18 @@
 
 use super::Context;
 use crate::{
-    api::graphql,
-    state::{Client, ClientId},
+    api::graphql::{self, ErrorCode},
+    federation::{self, RemoteRestream},
+    state::{
+        Alert, AlertEvent, AlertKind, AlertRule, Client, ClientId, OutputId,
+        RestreamId,
+    },
 };
 use actix_web::http::StatusCode;
-use futures::{stream::BoxStream, StreamExt};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use futures_signals::signal::SignalExt;
 use juniper::{graphql_object, graphql_subscription, RootNode};
 
@@ -31,9 +38,36 @@ pub struct QueriesRoot;
 
 #[graphql_object(name = "Query", context = Context)]
 impl QueriesRoot {
+    /// Returns all the `ErrorCode`s which may be returned by this API in
+    /// `errors.extensions.code` field of GraphQL response.
+    fn error_codes() -> Vec<ErrorCode> {
+        ErrorCode::ALL.to_vec()
+    }
+
     fn statistics(context: &Context) -> Vec<Client> {
         context.state().clients.lock_mut().clone()
     }
+
+    /// Returns the current `AlertRule`s of raising `Alert`s about `Client`s.
+    fn alert_rules(context: &Context) -> Vec<AlertRule> {
+        context.state().settings.get_cloned().alert_rules
+    }
+
+    /// Returns the currently active `Alert`s about `Client`s.
+    fn alerts(context: &Context) -> Vec<Alert> {
+        context.state().alerts.get_cloned()
+    }
+
+    /// Fetches all `Restream`s of the specified remote `Client` via its
+    /// client API.
+    async fn client_restreams(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        context: &Context,
+    ) -> Result<Vec<RemoteRestream>, graphql::Error> {
+        federation::restreams(context.state(), &client_id)
+            .await
+            .map_err(remote_error)
+    }
 }
 
 /// Root of all [GraphQL mutations][1] in the [`Schema`].
@@ -44,37 +78,196 @@ pub struct MutationsRoot;
 
 #[graphql_object(name = "Mutation", context = Context)]
 impl MutationsRoot {
-    /// Add a new [`Client`]
+    /// Adds a new `Client` to be monitored by this dashboard.
     ///
-    /// Returns [`graphql::Error`] if there is already [`Client`] in this
-    /// [`State`].
+    /// The URL should be an HTTP(S) URL of the remote client's host, without
+    /// any credentials, query or fragment.
+    ///
+    /// Returns an error if the URL is invalid, or there is a `Client` with
+    /// such URL already.
     fn add_client(
-        #[graphql(description = "Ulr of remote client")] client_id: ClientId,
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if !client_id.is_valid() {
+            return Err(graphql::Error::new(ErrorCode::InvalidClientUrl)
+                .status(StatusCode::BAD_REQUEST)
+                .message(
+                    "Client URL should be an HTTP(S) URL of a host without \
+                     credentials, query or fragment",
+                ));
+        }
         match context.state().add_client(&client_id) {
             Ok(_) => Ok(Some(true)),
-            Err(e) => Err(graphql::Error::new("DUPLICATE_CLIENT")
+            Err(e) => Err(graphql::Error::new(ErrorCode::DuplicateClient)
                 .status(StatusCode::CONFLICT)
                 .message(&e)),
         }
     }
 
-    /// Remove [`Client`]
+    /// Removes the `Client` from being monitored by this dashboard.
     ///
-    /// Returns [`None`] if there is no [`Client`] in this
-    /// [`State`].
+    /// Errors with `NOT_FOUND` if there is no such `Client`.
     fn remove_client(
-        #[graphql(description = "Ulr of remote client")] client_id: ClientId,
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context.state().remove_client(&client_id)?;
+        Ok(true)
+    }
+
+    /// Sets or removes the password to operate the specified remote `Client`
+    /// via its client API with.
+    ///
+    /// Returns `false` if nothing changed.
+    ///
+    /// Errors with `NOT_FOUND` if there is no such `Client`.
+    fn set_client_password(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        #[graphql(description = "Password of the client API of the remote \
+                                 client.\
+                                 \n\n\
+                                 If not specified, then the password is \
+                                 removed.")]
+        password: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().set_client_password(
+            &client_id,
+            password.filter(|p| !p.is_empty()),
+        )?)
+    }
+
+    /// Generates a new token for the specified `Client` to report its
+    /// statistics with (see `reportStatistics` mutation of the report API),
+    /// so it's not polled for them anymore.
+    ///
+    /// The previous token, if any, is invalidated.
+    ///
+    /// Returns `null` if there is no such `Client`.
+    fn rotate_client_report_token(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        context: &Context,
+    ) -> Result<Option<String>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().rotate_client_report_token(&client_id))
+    }
+
+    /// Removes the token of the specified `Client` to report its statistics
+    /// with, so it's polled for them again.
+    ///
+    /// Returns `false` if it has no token.
+    ///
+    /// Errors with `NOT_FOUND` if there is no such `Client`.
+    fn remove_client_report_token(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().remove_client_report_token(&client_id)?)
+    }
+
+    /// Enables an `Output` in the specified `Restream` of the remote `Client`
+    /// via its client API.
+    ///
+    /// Returns the result of `enableOutput` mutation of the remote `Client`.
+    async fn enable_client_output(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        #[graphql(description = "ID of the `Restream` on the remote client.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` to be enabled.")]
+        output_id: OutputId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        federation::toggle_output(
+            context.state(),
+            &client_id,
+            restream_id,
+            output_id,
+            true,
+        )
+        .await
+        .map_err(remote_error)
+    }
+
+    /// Disables an `Output` in the specified `Restream` of the remote `Client`
+    /// via its client API.
+    ///
+    /// Returns the result of `disableOutput` mutation of the remote `Client`.
+    async fn disable_client_output(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        #[graphql(description = "ID of the `Restream` on the remote client.")]
+        restream_id: RestreamId,
+        #[graphql(description = "ID of the `Output` to be disabled.")]
+        output_id: OutputId,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
-        match context.state().remove_client(&client_id) {
-            Some(_) => Ok(Some(true)),
-            None => Ok(None),
+        context.ensure_not_in_maintenance()?;
+        federation::toggle_output(
+            context.state(),
+            &client_id,
+            restream_id,
+            output_id,
+            false,
+        )
+        .await
+        .map_err(remote_error)
+    }
+
+    /// Sets an `AlertRule` of the given `kind`, replacing the existing one, if
+    /// any.
+    ///
+    /// Returns `false` if nothing changed.
+    fn set_alert_rule(
+        #[graphql(description = "Kind of the condition to check.")]
+        kind: AlertKind,
+        #[graphql(description = "Value which should be exceeded to satisfy \
+                                 the condition.")]
+        threshold: i32,
+        #[graphql(description = "Duration (in seconds) the condition should \
+                                 last for before raising an `Alert`.")]
+        duration: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if threshold < 0 || duration < 0 {
+            return Err(graphql::Error::new(ErrorCode::InvalidAlertRule)
+                .status(StatusCode::BAD_REQUEST)
+                .message("Threshold and duration cannot be negative"));
         }
+        Ok(context.state().set_alert_rule(AlertRule {
+            kind,
+            threshold,
+            duration,
+        }))
+    }
+
+    /// Removes an `AlertRule` of the given `kind`.
+    ///
+    /// Errors with `NOT_FOUND` if there is no `AlertRule` of such `kind`.
+    fn remove_alert_rule(
+        #[graphql(description = "Kind of the `AlertRule` to be removed.")]
+        kind: AlertKind,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        context.state().remove_alert_rule(kind)?;
+        Ok(true)
     }
 }
 
+/// Converts the given error of operating a remote `Client` into a
+/// [`graphql::Error`].
+fn remote_error(e: anyhow::Error) -> graphql::Error {
+    graphql::Error::new(ErrorCode::RemoteClientError)
+        .status(StatusCode::BAD_GATEWAY)
+        .message(&e)
+}
+
 /// Root of all [GraphQL subscriptions][1] in the [`Schema`].
 ///
 /// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
@@ -92,4 +285,40 @@ impl SubscriptionsRoot {
             .to_stream()
             .boxed()
     }
+
+    /// Subscribes to the list of URLs of the `Client`s monitored by this
+    /// dashboard.
+    ///
+    /// Emits only when a `Client` is added or removed, unlike `statistics`
+    /// subscription, which emits on every statistics update as well.
+    async fn clients(context: &Context) -> BoxStream<'static, Vec<ClientId>> {
+        context
+            .state()
+            .clients
+            .signal_ref(|cs| cs.iter().map(|c| c.id.clone()).collect())
+            .dedupe_cloned()
+            .to_stream()
+            .boxed()
+    }
+
+    /// Emits `AlertEvent`s on raising and clearing of `Alert`s about
+    /// `Client`s.
+    ///
+    /// The currently active `Alert`s are emitted as raised ones right after
+    /// subscribing.
+    async fn alerts(context: &Context) -> BoxStream<'static, AlertEvent> {
+        let mut prev = vec![];
+        context
+            .state()
+            .alerts
+            .signal_cloned()
+            .dedupe_cloned()
+            .to_stream()
+            .flat_map(move |actual| {
+                let events = AlertEvent::diff(&prev, &actual);
+                prev = actual;
+                stream::iter(events)
+            })
+            .boxed()
+    }
 }
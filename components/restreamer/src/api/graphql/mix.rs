@@ -2,16 +2,36 @@
 //!
 //! [GraphQL]: https://graphql.com
 
-use futures::{stream::BoxStream, StreamExt};
+use std::{convert::TryFrom, time::Duration};
+
+use actix_web::http::StatusCode;
+use ephyr_log::log;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use futures_signals::signal::SignalExt as _;
-use juniper::{graphql_object, graphql_subscription, RootNode};
+use juniper::{graphql_object, graphql_subscription, GraphQLObject, RootNode};
+use tokio::time;
 
-use crate::state::{
-    Delay, MixinId, Output, OutputId, RestreamId, Volume, VolumeLevel,
+use crate::{
+    api::{
+        graphql::{self, ErrorCode},
+        srs,
+    },
+    spec,
+    state::{
+        Delay, Mixin, MixinId, MixinSidechainParams, Output, OutputId,
+        RestreamId, Status, Volume, VolumeLevel,
+    },
+    State,
 };
 
 use super::Context;
 
+/// Interval of re-checking an [`OutputHealth`].
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Schema of `Mix` app.
 pub type Schema =
     RootNode<'static, QueriesRoot, MutationsRoot, SubscriptionsRoot>;
@@ -38,14 +58,17 @@ impl MutationsRoot {
         mixin_id: Option<MixinId>,
         level: VolumeLevel,
         muted: bool,
+        fade: Option<Delay>,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_volume(
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_volume(
             restream_id,
             output_id,
             mixin_id,
             Volume { level, muted },
-        )
+            fade.unwrap_or_default(),
+        )?)
     }
 
     /// Tunes a `Delay` of the specified `Mixin` before mix it into its
@@ -55,10 +78,14 @@ impl MutationsRoot {
         mixin_id: MixinId,
         delay: Delay,
         context: &Context,
-    ) -> Option<bool> {
-        context
-            .state()
-            .tune_delay(restream_id, output_id, mixin_id, delay)
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_delay(
+            restream_id,
+            output_id,
+            mixin_id,
+            delay,
+        )?)
     }
 
     /// Tunes a the specified [`Mixin.sidechain`] in this [`State`]
@@ -68,13 +95,37 @@ impl MutationsRoot {
         mixin_id: MixinId,
         sidechain: bool,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().tune_sidechain(
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        Ok(context.state().tune_sidechain(
             restream_id,
             output_id,
             mixin_id,
             sidechain,
-        )
+        )?)
+    }
+
+    /// Tunes parameters of side-chaining the specified `Mixin` with its
+    /// `Output`.
+    fn tune_sidechain_params(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        params: spec::v1::MixinSidechainParams,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.ensure_not_in_maintenance()?;
+        if !params.is_valid() {
+            return Err(graphql::Error::new(ErrorCode::InvalidSidechainParams)
+                .status(StatusCode::BAD_REQUEST)
+                .message("Side-chain parameters are out of allowed ranges"));
+        }
+        Ok(context.state().tune_sidechain_params(
+            restream_id,
+            output_id,
+            mixin_id,
+            MixinSidechainParams::new(params),
+        )?)
     }
 }
 
@@ -94,6 +145,152 @@ impl QueriesRoot {
     ) -> Option<Output> {
         context.state().get_output(restream_id, output_id)
     }
+
+    /// Returns the current mixing state of the specified `Output` along with
+    /// its `mixRevision`.
+    ///
+    /// Intended for re-syncing a client after reconnection, so it may check
+    /// whether its last changes have been applied.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if no `Output` with the given `outputId` exists.
+    fn mix_state(output_id: OutputId, context: &Context) -> Option<MixState> {
+        context
+            .state()
+            .find_output(output_id)
+            .as_ref()
+            .map(MixState::new)
+    }
+}
+
+/// Current mixing state of an `Output`.
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct MixState {
+    /// ID of the `Output` this `MixState` belongs to.
+    pub output_id: OutputId,
+
+    /// Revision of the `Output`'s mixing parameters this `MixState`
+    /// corresponds to.
+    pub mix_revision: i32,
+
+    /// `Volume` rate of the `Output`'s original audio.
+    pub volume: Volume,
+
+    /// Mixing state of each `Mixin` of the `Output`.
+    pub mixins: Vec<MixinState>,
+}
+
+impl MixState {
+    /// Creates a new [`MixState`] of the given [`Output`].
+    #[must_use]
+    pub fn new(output: &Output) -> Self {
+        Self {
+            output_id: output.id,
+            mix_revision: output.mix_revision,
+            volume: output.volume.clone(),
+            mixins: output.mixins.iter().map(MixinState::new).collect(),
+        }
+    }
+}
+
+/// Current mixing state of a `Mixin`.
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct MixinState {
+    /// ID of the `Mixin` this `MixinState` belongs to.
+    pub id: MixinId,
+
+    /// `Volume` rate of the `Mixin`'s audio.
+    pub volume: Volume,
+
+    /// `Delay` of the `Mixin` before being mixed.
+    pub delay: Delay,
+
+    /// Indicator whether the `Mixin` is side-chained with its `Output`.
+    pub sidechain: bool,
+
+    /// Parameters of side-chaining the `Mixin` with its `Output`.
+    pub sidechain_params: MixinSidechainParams,
+}
+
+impl MixinState {
+    /// Creates a new [`MixinState`] of the given [`Mixin`].
+    #[must_use]
+    pub fn new(mixin: &Mixin) -> Self {
+        Self {
+            id: mixin.id,
+            volume: mixin.volume.clone(),
+            delay: mixin.delay,
+            sidechain: mixin.sidechain,
+            sidechain_params: mixin.sidechain_params,
+        }
+    }
+}
+
+/// Health of an `Output` and the program it re-streams, allowing to tell
+/// whether a silent mix is caused by an upstream outage.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct OutputHealth {
+    /// `Status` of the `Output`.
+    pub status: Status,
+
+    /// Indicator whether the program (the `Restream`'s input) is received by
+    /// the server.
+    ///
+    /// `false` means an upstream outage.
+    pub input_online: bool,
+
+    /// Bitrate (in kbps) the program is received with, averaged over the
+    /// last 30 seconds.
+    ///
+    /// `null` if unknown.
+    pub bitrate: Option<i32>,
+
+    /// Indicator whether the program has audio flowing.
+    pub audio_flowing: bool,
+}
+
+impl OutputHealth {
+    /// Checks the actual [`OutputHealth`] of the specified [`Output`] in the
+    /// given [`State`].
+    ///
+    /// Returns [`None`] if no such [`Output`] exists.
+    async fn check(
+        state: &State,
+        restream_id: RestreamId,
+        output_id: OutputId,
+    ) -> Option<Self> {
+        let (app, stream, status, input_online) = {
+            let restreams = state.restreams.lock_ref();
+            let restream = restreams.iter().find(|r| r.id == restream_id)?;
+            let output = restream.outputs.iter().find(|o| o.id == output_id)?;
+            (
+                restream.srs_app(),
+                restream.input.key.to_string(),
+                output.status,
+                restream.input.is_ready_to_serve(),
+            )
+        };
+        let stream = srs::Client::streams()
+            .await
+            .map_err(|e| log::warn!("Failed to retrieve SRS streams: {}", e))
+            .ok()
+            .and_then(|streams| {
+                streams
+                    .into_iter()
+                    .find(|s| s.app == app && s.name == stream)
+            });
+        Some(Self {
+            status,
+            input_online,
+            bitrate: stream
+                .as_ref()
+                .map(|s| i32::try_from(s.kbps.recv_30s).unwrap_or(i32::MAX)),
+            audio_flowing: stream.map_or(false, |s| {
+                s.publish.active && s.audio.is_some() && s.kbps.recv_30s > 0
+            }),
+        })
+    }
 }
 
 /// Root of all [GraphQL subscriptions][1] in the [`Schema`].
@@ -112,18 +309,40 @@ impl SubscriptionsRoot {
     ) -> BoxStream<'static, Option<Output>> {
         context
             .state()
-            .restreams
-            .signal_cloned()
+            .restream_signal(restream_id)
+            .map(move |r| r?.outputs.into_iter().find(|o| o.id == output_id))
             .dedupe_cloned()
-            .map(move |restreams| {
-                restreams
-                    .into_iter()
-                    .find(|r| r.id == restream_id)?
-                    .outputs
-                    .into_iter()
-                    .find(|o| o.id == output_id)
-            })
             .to_stream()
             .boxed()
     }
+
+    /// Returns `OutputHealth` of the specified `Output`, re-checking it
+    /// periodically.
+    ///
+    /// Exposes nothing but the `Output` status and the program it re-streams,
+    /// so is suitable for translators.
+    ///
+    /// ### Result
+    ///
+    /// Emits `null` if no such `Output` exists.
+    async fn output_health(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        context: &Context,
+    ) -> BoxStream<'static, Option<OutputHealth>> {
+        let state = context.state().clone();
+        stream::unfold((state, None), move |(state, prev)| async move {
+            loop {
+                if prev.is_some() {
+                    time::sleep(HEALTH_CHECK_INTERVAL).await;
+                }
+                let health =
+                    OutputHealth::check(&state, restream_id, output_id).await;
+                if prev.as_ref() != Some(&health) {
+                    return Some((health.clone(), (state, Some(health))));
+                }
+            }
+        })
+        .boxed()
+    }
 }
@@ -10,16 +10,21 @@
 pub mod client;
 pub mod dashboard;
 pub mod mix;
+pub mod report;
 pub mod statistics;
 
-use std::{borrow::Cow, convert::Infallible, fmt, ops::Deref};
+use std::{borrow::Cow, convert::Infallible, fmt, ops::Deref, str::FromStr};
 
 use actix_web::{http, HttpRequest};
 use derive_more::{Display, Error};
-use juniper::{graphql_value, FieldError, IntoFieldError, ScalarValue};
+use juniper::{
+    graphql_value, FieldError, GraphQLEnum, IntoFieldError, ScalarValue,
+};
 use send_wrapper::SendWrapper;
 use smart_default::SmartDefault;
 
+use crate::state::StateError;
+
 /// Context containing [`HttpRequest`] for providing additional information when
 /// executing GraphQL operations.
 #[derive(Clone, Debug)]
@@ -62,6 +67,42 @@ impl Context {
     pub fn state(&self) -> &crate::State {
         self.app_data::<crate::State>().unwrap()
     }
+
+    /// Returns [`TenantKey`] whose credentials the [`HttpRequest`] has been
+    /// authorized with, if any.
+    ///
+    /// [`TenantKey`]: crate::state::TenantKey
+    #[inline]
+    #[must_use]
+    pub fn tenant(&self) -> Option<crate::state::TenantKey> {
+        self.extensions().get::<crate::state::TenantKey>().cloned()
+    }
+
+    /// Returns [`ReportToken`] the [`HttpRequest`] has been authorized with,
+    /// if any.
+    ///
+    /// [`ReportToken`]: report::ReportToken
+    #[inline]
+    #[must_use]
+    pub fn report_token(&self) -> Option<report::ReportToken> {
+        self.extensions().get::<report::ReportToken>().cloned()
+    }
+
+    /// Ensures that the server doesn't run in maintenance mode, so a mutation
+    /// is allowed to be performed.
+    ///
+    /// # Errors
+    ///
+    /// With `MAINTENANCE_MODE` [`Error`] if the server runs in maintenance
+    /// mode.
+    pub fn ensure_not_in_maintenance(&self) -> Result<(), Error> {
+        if !self.state().maintenance_mode.get() {
+            return Ok(());
+        }
+        Err(Error::new(ErrorCode::MaintenanceMode)
+            .status(http::StatusCode::SERVICE_UNAVAILABLE)
+            .message("Server runs in maintenance mode, so rejects any changes"))
+    }
 }
 
 impl Deref for Context {
@@ -76,13 +117,288 @@ impl Deref for Context {
 // To make our context usable by Juniper, we have to implement a marker trait.
 impl juniper::Context for Context {}
 
+/// Unique literal code of an [`Error`](struct@Error) returned by GraphQL API.
+///
+/// Goes as `errors.extensions.code` field of GraphQL response, having the
+/// same literal value as before introducing this enum, so clients matching
+/// on plain strings keep working.
+#[derive(
+    Clone, Copy, Debug, Display, Eq, GraphQLEnum, Hash, PartialEq, SmartDefault,
+)]
+#[display(fmt = "{}", "self.as_str()")]
+pub enum ErrorCode {
+    /// Operation conflicts with the current state of the mutated element.
+    Conflict,
+
+    /// Operation is available in debug mode only.
+    DebugModeRequired,
+
+    /// `Client` with such ID exists already.
+    DuplicateClient,
+
+    /// `Mixin` with such source URL exists already in the `Output`.
+    DuplicateMixinUrl,
+
+    /// `Output` with such destination URL exists already in the `Restream`.
+    DuplicateOutputUrl,
+
+    /// `Restream` with such key exists already.
+    DuplicateRestreamKey,
+
+    /// Destination of the `Output` cannot be resolved or connected to.
+    DstUnreachable,
+
+    /// Unexpected failure has happened on the server.
+    InternalServerError,
+
+    /// Provided `AlertRule` is invalid.
+    InvalidAlertRule,
+
+    /// Provided `Output` audio/video offset is out of the allowed range.
+    InvalidAvOffset,
+
+    /// Provided `Client` URL is not an HTTP(S) URL of a host.
+    InvalidClientUrl,
+
+    /// Provided path of a DVR file is invalid.
+    InvalidDvrFilePath,
+
+    /// Provided `Restream` fallback is invalid.
+    InvalidFallback,
+
+    /// Provided extra FFmpeg arguments are invalid.
+    InvalidFfmpegArgs,
+
+    /// Provided limits of selecting a HLS variant stream are invalid.
+    InvalidHlsVariant,
+
+    /// Provided idle timeout of `Input`s is invalid.
+    InvalidIdleTimeout,
+
+    /// Provided `Input` tuning is invalid.
+    InvalidInputTuning,
+
+    /// Provided limits of `Mixin`s are invalid or exceeded by existing
+    /// `Output`s.
+    InvalidMixinLimits,
+
+    /// Provided pattern of `Mixin` sources is not a valid regular expression.
+    InvalidMixinSrcPattern,
+
+    /// Provided `Output` Icecast metadata is invalid.
+    InvalidOutputIcecast,
+
+    /// Provided `Output` loudness normalization options are invalid.
+    InvalidOutputLoudnorm,
+
+    /// Provided `Output` reconnect options are invalid.
+    InvalidOutputReconnect,
+
+    /// Provided duration of testing an `Output` is invalid.
+    InvalidOutputTestDuration,
+
+    /// Provided `Restream` publish token is invalid.
+    InvalidPublishToken,
+
+    /// Provided `Client` report token is missing or doesn't match.
+    InvalidReportToken,
+
+    /// Provided GraphQL selection set is invalid.
+    InvalidSelection,
+
+    /// Provided side-chain parameters of a `Mixin` are invalid.
+    InvalidSidechainParams,
+
+    /// Provided spec is invalid.
+    InvalidSpec,
+
+    /// Provided spec is not a valid JSON.
+    InvalidSpecJson,
+
+    /// Provided SRS tuning of a `Restream` is invalid.
+    InvalidSrsTuning,
+
+    /// Provided statistics report of a `Client` is invalid.
+    InvalidStatisticsReport,
+
+    /// Provided title is invalid.
+    InvalidTitle,
+
+    /// Provided webhook URL is invalid.
+    InvalidWebhookUrl,
+
+    /// Server runs in maintenance mode, so rejects any changes.
+    MaintenanceMode,
+
+    /// Old password is required to change the password.
+    NoOldPassword,
+
+    /// No process to be restarted is specified.
+    NoProcessSpecified,
+
+    /// Mutated element (or one of its parents) doesn't exist.
+    NotFound,
+
+    /// Request to a remote `Client` has failed.
+    RemoteClientError,
+
+    /// `Restream` cannot be mixed into its own `Output`.
+    SelfMixinRestream,
+
+    /// Too many `Mixin`s are specified for an `Output`.
+    TooMuchMixinUrls,
+
+    /// Too many TeamSpeak `Mixin`s are specified for an `Output`.
+    TooMuchTeamspeakMixinUrls,
+
+    /// Unknown error has happened.
+    #[default]
+    Unknown,
+
+    /// Backup to be restored doesn't exist.
+    UnknownBackup,
+
+    /// `Restream` to be mixed in doesn't exist.
+    UnknownMixinRestream,
+
+    /// `RestreamTemplate` to be applied doesn't exist.
+    UnknownTemplate,
+
+    /// Provided duration is invalid.
+    WrongDuration,
+
+    /// Provided old password is wrong.
+    WrongOldPassword,
+}
+
+impl ErrorCode {
+    /// All the existing [`ErrorCode`]s.
+    pub const ALL: &'static [Self] = &[
+        Self::Conflict,
+        Self::DebugModeRequired,
+        Self::DuplicateClient,
+        Self::DuplicateMixinUrl,
+        Self::DuplicateOutputUrl,
+        Self::DuplicateRestreamKey,
+        Self::DstUnreachable,
+        Self::InternalServerError,
+        Self::InvalidAlertRule,
+        Self::InvalidAvOffset,
+        Self::InvalidClientUrl,
+        Self::InvalidDvrFilePath,
+        Self::InvalidFallback,
+        Self::InvalidFfmpegArgs,
+        Self::InvalidHlsVariant,
+        Self::InvalidIdleTimeout,
+        Self::InvalidInputTuning,
+        Self::InvalidMixinLimits,
+        Self::InvalidMixinSrcPattern,
+        Self::InvalidOutputIcecast,
+        Self::InvalidOutputLoudnorm,
+        Self::InvalidOutputReconnect,
+        Self::InvalidOutputTestDuration,
+        Self::InvalidPublishToken,
+        Self::InvalidReportToken,
+        Self::InvalidSelection,
+        Self::InvalidSidechainParams,
+        Self::InvalidSpec,
+        Self::InvalidSpecJson,
+        Self::InvalidSrsTuning,
+        Self::InvalidStatisticsReport,
+        Self::InvalidTitle,
+        Self::InvalidWebhookUrl,
+        Self::MaintenanceMode,
+        Self::NoOldPassword,
+        Self::NoProcessSpecified,
+        Self::NotFound,
+        Self::RemoteClientError,
+        Self::SelfMixinRestream,
+        Self::TooMuchMixinUrls,
+        Self::TooMuchTeamspeakMixinUrls,
+        Self::Unknown,
+        Self::UnknownBackup,
+        Self::UnknownMixinRestream,
+        Self::UnknownTemplate,
+        Self::WrongDuration,
+        Self::WrongOldPassword,
+    ];
+
+    /// Returns the literal value of this [`ErrorCode`], as it goes in
+    /// `errors.extensions.code` field of GraphQL response.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Conflict => "CONFLICT",
+            Self::DebugModeRequired => "DEBUG_MODE_REQUIRED",
+            Self::DuplicateClient => "DUPLICATE_CLIENT",
+            Self::DuplicateMixinUrl => "DUPLICATE_MIXIN_URL",
+            Self::DuplicateOutputUrl => "DUPLICATE_OUTPUT_URL",
+            Self::DuplicateRestreamKey => "DUPLICATE_RESTREAM_KEY",
+            Self::DstUnreachable => "DST_UNREACHABLE",
+            Self::InternalServerError => "INTERNAL_SERVER_ERROR",
+            Self::InvalidAlertRule => "INVALID_ALERT_RULE",
+            Self::InvalidAvOffset => "INVALID_AV_OFFSET",
+            Self::InvalidClientUrl => "INVALID_CLIENT_URL",
+            Self::InvalidDvrFilePath => "INVALID_DVR_FILE_PATH",
+            Self::InvalidFallback => "INVALID_FALLBACK",
+            Self::InvalidFfmpegArgs => "INVALID_FFMPEG_ARGS",
+            Self::InvalidHlsVariant => "INVALID_HLS_VARIANT",
+            Self::InvalidIdleTimeout => "INVALID_IDLE_TIMEOUT",
+            Self::InvalidInputTuning => "INVALID_INPUT_TUNING",
+            Self::InvalidMixinLimits => "INVALID_MIXIN_LIMITS",
+            Self::InvalidMixinSrcPattern => "INVALID_MIXIN_SRC_PATTERN",
+            Self::InvalidOutputIcecast => "INVALID_OUTPUT_ICECAST",
+            Self::InvalidOutputLoudnorm => "INVALID_OUTPUT_LOUDNORM",
+            Self::InvalidOutputReconnect => "INVALID_OUTPUT_RECONNECT",
+            Self::InvalidOutputTestDuration => "INVALID_OUTPUT_TEST_DURATION",
+            Self::InvalidPublishToken => "INVALID_PUBLISH_TOKEN",
+            Self::InvalidReportToken => "INVALID_REPORT_TOKEN",
+            Self::InvalidSelection => "INVALID_SELECTION",
+            Self::InvalidSidechainParams => "INVALID_SIDECHAIN_PARAMS",
+            Self::InvalidSpec => "INVALID_SPEC",
+            Self::InvalidSpecJson => "INVALID_SPEC_JSON",
+            Self::InvalidSrsTuning => "INVALID_SRS_TUNING",
+            Self::InvalidStatisticsReport => "INVALID_STATISTICS_REPORT",
+            Self::InvalidTitle => "INVALID_TITLE",
+            Self::InvalidWebhookUrl => "INVALID_WEBHOOK_URL",
+            Self::MaintenanceMode => "MAINTENANCE_MODE",
+            Self::NoOldPassword => "NO_OLD_PASSWORD",
+            Self::NoProcessSpecified => "NO_PROCESS_SPECIFIED",
+            Self::NotFound => "NOT_FOUND",
+            Self::RemoteClientError => "REMOTE_CLIENT_ERROR",
+            Self::SelfMixinRestream => "SELF_MIXIN_RESTREAM",
+            Self::TooMuchMixinUrls => "TOO_MUCH_MIXIN_URLS",
+            Self::TooMuchTeamspeakMixinUrls => "TOO_MUCH_TEAMSPEAK_MIXIN_URLS",
+            Self::Unknown => "UNKNOWN",
+            Self::UnknownBackup => "UNKNOWN_BACKUP",
+            Self::UnknownMixinRestream => "UNKNOWN_MIXIN_RESTREAM",
+            Self::UnknownTemplate => "UNKNOWN_TEMPLATE",
+            Self::WrongDuration => "WRONG_DURATION",
+            Self::WrongOldPassword => "WRONG_OLD_PASSWORD",
+        }
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = anyhow::Error;
+
+    /// Parses an [`ErrorCode`] from its literal value, for compatibility with
+    /// the code still operating plain string codes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown error code: {}", s))
+    }
+}
+
 /// Error returned to the client by GraphQL API.
 #[derive(Clone, Debug, Display, Error, SmartDefault)]
 #[display(fmt = "{message}")]
 pub struct Error {
     /// Unique literal code of this [`Error`](struct@Error).
-    #[default = "UNKNOWN"]
-    pub code: Cow<'static, str>,
+    pub code: ErrorCode,
 
     /// HTTP status code of this [`Error`](struct@Error).
     #[default(http::StatusCode::INTERNAL_SERVER_ERROR)]
@@ -98,17 +414,15 @@ pub struct Error {
 }
 
 impl Error {
-    /// Creates new default [`Error`](struct@Error) with a given unique literal
-    /// code applied.
-    ///
-    /// Code is usually upper-cased, like `USER_NOT_FOUND`.
+    /// Creates new default [`Error`](struct@Error) with a given unique
+    /// [`ErrorCode`] applied.
     ///
     /// Goes as `errors.extensions.code` field of GraphQL response.
     #[inline]
     #[must_use]
-    pub fn new<C: Into<Cow<'static, str>>>(code: C) -> Self {
+    pub fn new(code: ErrorCode) -> Self {
         Self {
-            code: code.into(),
+            code,
             ..Self::default()
         }
     }
@@ -179,8 +493,8 @@ impl<S: ScalarValue> IntoFieldError<S> for Error {
     fn into_field_error(self) -> FieldError<S> {
         let size = if self.backtrace.is_some() { 3 } else { 2 };
         let mut extensions = juniper::Object::with_capacity(size);
-        let _added_code = extensions
-            .add_field("code", graphql_value!(self.code.into_owned()));
+        let _added_code =
+            extensions.add_field("code", graphql_value!(self.code.as_str()));
         let _added_status = extensions.add_field(
             "status",
             graphql_value!(i32::from(self.status.as_u16())),
@@ -207,16 +521,31 @@ impl From<Infallible> for Error {
 impl From<anyhow::Error> for Error {
     #[inline]
     fn from(err: anyhow::Error) -> Self {
-        Self::new("INTERNAL_SERVER_ERROR")
+        Self::new(ErrorCode::InternalServerError)
             .status(http::StatusCode::INTERNAL_SERVER_ERROR)
             .message(&err)
     }
 }
 
+impl From<StateError> for Error {
+    #[inline]
+    fn from(err: StateError) -> Self {
+        let (code, status) = match &err {
+            StateError::NotFound(_) => {
+                (ErrorCode::NotFound, http::StatusCode::NOT_FOUND)
+            }
+            StateError::Conflict(_) => {
+                (ErrorCode::Conflict, http::StatusCode::CONFLICT)
+            }
+        };
+        Self::new(code).status(status).message(&err)
+    }
+}
+
 impl From<serde_json::Error> for Error {
     #[inline]
     fn from(err: serde_json::Error) -> Self {
-        Self::new("INVALID_SPEC_JSON")
+        Self::new(ErrorCode::InvalidSpecJson)
             .status(http::StatusCode::BAD_REQUEST)
             .message(&err)
     }
@@ -0,0 +1,111 @@
+//! Report [GraphQL] API for remote `Client`s reporting their statistics to a
+//! dashboard by themselves.
+//!
+//! [GraphQL]: https://graphql.com
+
+use actix_web::http::StatusCode;
+use juniper::{graphql_object, EmptySubscription, RootNode};
+
+use super::Context;
+use crate::{
+    api::graphql::{self, ErrorCode},
+    client_stat::SUPPORTED_API_VERSIONS,
+    state::{ClientId, ClientStatistics},
+};
+
+/// Schema of `Report` API.
+pub type Schema =
+    RootNode<'static, QueriesRoot, MutationsRoot, EmptySubscription<Context>>;
+
+/// Constructs and returns new [`Schema`], ready for use.
+#[inline]
+#[must_use]
+pub fn schema() -> Schema {
+    Schema::new(QueriesRoot, MutationsRoot, EmptySubscription::new())
+}
+
+/// Token of a remote `Client` to report its statistics with, passed as a
+/// [Bearer token][1].
+///
+/// [1]: https://datatracker.ietf.org/doc/html/rfc6750
+#[derive(Clone, Debug)]
+pub struct ReportToken(pub String);
+
+/// Root of all [GraphQL queries][1] in the [`Schema`].
+///
+/// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
+#[derive(Clone, Copy, Debug)]
+pub struct QueriesRoot;
+
+#[graphql_object(name = "Query", context = Context)]
+impl QueriesRoot {
+    /// Returns all the `ErrorCode`s which may be returned by this API in
+    /// `errors.extensions.code` field of GraphQL response.
+    fn error_codes() -> Vec<ErrorCode> {
+        ErrorCode::ALL.to_vec()
+    }
+}
+
+/// Root of all [GraphQL mutations][1] in the [`Schema`].
+///
+/// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
+#[derive(Clone, Copy, Debug)]
+pub struct MutationsRoot;
+
+#[graphql_object(name = "Mutation", context = Context)]
+impl MutationsRoot {
+    /// Reports statistics of the specified `Client` by the `Client` itself.
+    ///
+    /// Should be authorized with the token issued by
+    /// `rotateClientReportToken` mutation of the dashboard API, passed as a
+    /// Bearer token.
+    ///
+    /// Errors with `NOT_FOUND` if there is no such `Client`, or with
+    /// `INVALID_REPORT_TOKEN` if the token is not the one of the `Client`.
+    fn report_statistics(
+        #[graphql(description = "Url of remote client")] client_id: ClientId,
+        #[graphql(description = "JSON-serialized `ClientStatistics` of the \
+                                 remote client.")]
+        statistics: String,
+        #[graphql(description = "Statistics API version of remote client.")]
+        api_version: i32,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        let token = context.report_token().ok_or_else(|| {
+            graphql::Error::new(ErrorCode::InvalidReportToken)
+                .status(StatusCode::UNAUTHORIZED)
+                .message("Report token is required")
+        })?;
+        if !SUPPORTED_API_VERSIONS.contains(&api_version) {
+            return Err(graphql::Error::new(
+                ErrorCode::InvalidStatisticsReport,
+            )
+            .status(StatusCode::BAD_REQUEST)
+            .message(&format!(
+                "Incompatible statistics API version {}, supported are {}-{}",
+                api_version,
+                SUPPORTED_API_VERSIONS.start(),
+                SUPPORTED_API_VERSIONS.end(),
+            )));
+        }
+        let statistics = serde_json::from_str::<ClientStatistics>(&statistics)
+            .map_err(|e| {
+                graphql::Error::new(ErrorCode::InvalidStatisticsReport)
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+
+        let reported = context.state().report_client_statistics(
+            &client_id,
+            &token.0,
+            statistics,
+            api_version,
+        )?;
+        if !reported {
+            return Err(graphql::Error::new(ErrorCode::InvalidReportToken)
+                .status(StatusCode::UNAUTHORIZED)
+                .message("Report token doesn't match"));
+        }
+        Ok(true)
+    }
+}
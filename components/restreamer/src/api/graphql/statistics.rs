@@ -25,6 +25,11 @@ pub fn schema() -> Schema {
     Schema::new(QueriesRoot, EmptyMutation::new(), EmptySubscription::new())
 }
 
+/// Version of the [`Schema`].
+///
+/// Must be incremented on every breaking change of the [`Schema`].
+pub const API_VERSION: i32 = 1;
+
 /// Root of all [GraphQL queries][1] in the [`Schema`].
 ///
 /// [1]: https://spec.graphql.org/June2018/#sec-Root-Operation-Types
@@ -33,6 +38,14 @@ pub struct QueriesRoot;
 
 #[graphql_object(name = "Query", context = Context)]
 impl QueriesRoot {
+    /// Version of this `Statistics` API.
+    ///
+    /// Incremented on every breaking change of the API, so clients may
+    /// check their compatibility before querying statistics.
+    fn api_version() -> i32 {
+        API_VERSION
+    }
+
     fn statistics(context: &Context) -> FieldResult<ClientStatistics> {
         Ok(context.state().get_statistics())
     }
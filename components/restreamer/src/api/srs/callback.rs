@@ -45,6 +45,11 @@ pub struct Request {
     /// [SRS]: https://github.com/ossrs/srs
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stream: Option<String>,
+
+    /// Query parameters (like `?key=value`) of RTMP stream URL that happened
+    /// event is related to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub param: Option<String>,
 }
 
 /// Possible [SRS] events in [HTTP Callback API][1] that this application reacts
@@ -5,7 +5,12 @@
 
 pub mod callback;
 
+use std::net::IpAddr;
+
 use derive_more::{Display, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::srs;
 
 /// Client for performing requests to [HTTP API][1] of locally spawned [SRS].
 ///
@@ -15,12 +20,69 @@ use derive_more::{Display, Error};
 pub struct Client;
 
 impl Client {
-    /// [URL] of v1 [HTTP API][1] hosted by local [SRS].
+    /// Returns [URL] of v1 [HTTP API][1] hosted by local [SRS].
     ///
     /// [SRS]: https://github.com/ossrs/srs
     /// [URL]: https://en.wikipedia.org/wiki/URL
     /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
-    const V1_URL: &'static str = "http://127.0.0.1:8002/api/v1";
+    fn v1_url() -> String {
+        format!("http://127.0.0.1:{}/api/v1", srs::api_port())
+    }
+
+    /// Returns [URL] of [WebRTC] [HTTP API][1] hosted by local [SRS].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [URL]: https://en.wikipedia.org/wiki/URL
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_WebRTC
+    fn rtc_url() -> String {
+        format!("http://127.0.0.1:{}/rtc/v1", srs::api_port())
+    }
+
+    /// Checks whether the local [SRS] server is alive by requesting its
+    /// [versions][1].
+    ///
+    /// # Errors
+    ///
+    /// If API request cannot be performed, or fails. See [`Error`](enum@Error)
+    /// for details.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi#versions
+    pub async fn ping() -> Result<(), Error> {
+        let resp = reqwest::Client::new()
+            .get(&format!("{}/versions", Self::v1_url()))
+            .timeout(std::time::Duration::from_secs(3))
+            .send()
+            .await
+            .map_err(Error::RequestFailed)?;
+        if !resp.status().is_success() {
+            return Err(Error::BadStatus(resp.status()));
+        }
+        Ok(())
+    }
+
+    /// [Reloads][1] configuration of the local [SRS] server.
+    ///
+    /// # Errors
+    ///
+    /// If API request cannot be performed, or fails. See [`Error`](enum@Error)
+    /// for details.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi#raw-reload
+    pub async fn reload() -> Result<(), Error> {
+        let resp = reqwest::Client::new()
+            .get(&format!("{}/raw?rpc=reload", Self::v1_url()))
+            .timeout(std::time::Duration::from_secs(3))
+            .send()
+            .await
+            .map_err(Error::RequestFailed)?;
+        if !resp.status().is_success() {
+            return Err(Error::BadStatus(resp.status()));
+        }
+        Ok(())
+    }
 
     /// [Kicks off][1] a client connected to [SRS] server by its `id`.
     ///
@@ -33,7 +95,7 @@ impl Client {
     /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi#kickoff-client
     pub async fn kickoff_client(id: String) -> Result<(), Error> {
         let resp = reqwest::Client::new()
-            .delete(&format!("{}/clients/{id}", Self::V1_URL))
+            .delete(&format!("{}/clients/{id}", Self::v1_url()))
             .send()
             .await
             .map_err(Error::RequestFailed)?;
@@ -42,6 +104,190 @@ impl Client {
         }
         Ok(())
     }
+
+    /// Starts [publishing][1] a [WebRTC] stream to [SRS] server with the given
+    /// `offer` [SDP] of the publishing client with the given `client_ip`.
+    ///
+    /// The `stream_url` should be a `webrtc://` URL identifying the published
+    /// stream (with its `vhost` and params).
+    ///
+    /// # Errors
+    ///
+    /// If API request cannot be performed, or fails. See [`Error`](enum@Error)
+    /// for details.
+    ///
+    /// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_WebRTC
+    pub async fn rtc_publish(
+        stream_url: String,
+        client_ip: IpAddr,
+        offer: String,
+    ) -> Result<RtcSession, Error> {
+        let url = format!("{}/publish/", Self::rtc_url());
+        let resp = reqwest::Client::new()
+            .post(&url)
+            .timeout(std::time::Duration::from_secs(10))
+            .json(&RtcPublishRequest {
+                api: url.clone(),
+                clientip: client_ip.to_string(),
+                sdp: offer,
+                streamurl: stream_url,
+            })
+            .send()
+            .await
+            .map_err(Error::RequestFailed)?;
+        if !resp.status().is_success() {
+            return Err(Error::BadStatus(resp.status()));
+        }
+        let resp = resp
+            .json::<RtcPublishResponse>()
+            .await
+            .map_err(Error::RequestFailed)?;
+        if resp.code != 0 {
+            return Err(Error::Rejected(resp.code));
+        }
+        Ok(RtcSession {
+            id: resp.sessionid,
+            answer: resp.sdp,
+        })
+    }
+
+    /// Retrieves [information about streams][1] served by [SRS] server.
+    ///
+    /// # Errors
+    ///
+    /// If API request cannot be performed, or fails. See [`Error`](enum@Error)
+    /// for details.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi#http-api-streams
+    pub async fn streams() -> Result<Vec<Stream>, Error> {
+        let resp = reqwest::Client::new()
+            .get(&format!("{}/streams?count=1000", Self::v1_url()))
+            .timeout(std::time::Duration::from_secs(3))
+            .send()
+            .await
+            .map_err(Error::RequestFailed)?;
+        if !resp.status().is_success() {
+            return Err(Error::BadStatus(resp.status()));
+        }
+        Ok(resp
+            .json::<StreamsResponse>()
+            .await
+            .map_err(Error::RequestFailed)?
+            .streams)
+    }
+}
+
+/// Request of [SRS WebRTC API][1] to start publishing a stream.
+///
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_WebRTC
+#[derive(Clone, Debug, Serialize)]
+struct RtcPublishRequest {
+    /// URL of the API being requested.
+    api: String,
+
+    /// IP address of the publishing client.
+    clientip: String,
+
+    /// Offer [SDP] of the publishing client.
+    ///
+    /// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+    sdp: String,
+
+    /// `webrtc://` URL of the published stream.
+    streamurl: String,
+}
+
+/// Response of [SRS WebRTC API][1] to start publishing a stream.
+///
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_WebRTC
+#[derive(Clone, Debug, Deserialize)]
+struct RtcPublishResponse {
+    /// Code of the result, being non-zero on failure.
+    code: i32,
+
+    /// Answer [SDP] of [SRS].
+    ///
+    /// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default)]
+    sdp: String,
+
+    /// ID of the started session.
+    #[serde(default)]
+    sessionid: String,
+}
+
+/// [WebRTC] session started on [SRS].
+///
+/// [SRS]: https://github.com/ossrs/srs
+/// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+#[derive(Clone, Debug)]
+pub struct RtcSession {
+    /// ID of this [`RtcSession`].
+    pub id: String,
+
+    /// Answer [SDP] of [SRS] to be returned to the client.
+    ///
+    /// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+    /// [SRS]: https://github.com/ossrs/srs
+    pub answer: String,
+}
+
+/// Response of [SRS HTTP API][1] listing streams.
+///
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi#http-api-streams
+#[derive(Clone, Debug, Deserialize)]
+struct StreamsResponse {
+    /// Streams served by [SRS].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default)]
+    streams: Vec<Stream>,
+}
+
+/// Information about a stream served by [SRS].
+///
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(Clone, Debug, Deserialize)]
+pub struct Stream {
+    /// [SRS] `app` of this [`Stream`].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub app: String,
+
+    /// [SRS] `stream` name of this [`Stream`].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub name: String,
+
+    /// Bitrate of this [`Stream`].
+    pub kbps: StreamKbps,
+
+    /// Information about publishing of this [`Stream`].
+    pub publish: StreamPublish,
+
+    /// Audio track of this [`Stream`], if any has been received.
+    #[serde(default)]
+    pub audio: Option<serde_json::Value>,
+}
+
+/// Bitrate of a [`Stream`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct StreamKbps {
+    /// Bitrate (in kbps) the [`Stream`] is received with, averaged over the
+    /// last 30 seconds.
+    pub recv_30s: u64,
+}
+
+/// Information about publishing of a [`Stream`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct StreamPublish {
+    /// Indicator whether the [`Stream`] is being published at the moment.
+    pub active: bool,
 }
 
 /// Possible errors of performing requests to [SRS HTTP API][1].
@@ -59,4 +305,10 @@ pub enum Error {
     /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
     #[display(fmt = "SRS HTTP API responded with bad status: {_0}")]
     BadStatus(#[error(not(source))] reqwest::StatusCode),
+
+    /// [SRS HTTP API][1] rejected the request with a non-zero code.
+    ///
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+    #[display(fmt = "SRS HTTP API rejected request with code: {_0}")]
+    Rejected(#[error(not(source))] i32),
 }
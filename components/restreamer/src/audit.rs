@@ -0,0 +1,810 @@
+//! Audit log of changes made via [GraphQL] API mutations.
+//!
+//! [GraphQL]: https://graphql.com
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use ephyr_log::log;
+use futures::future;
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use tokio::{fs, io};
+
+use crate::{state::PasswordKind, State};
+
+/// Maximum number of [`AuditEntry`]s kept in the audit log.
+///
+/// The oldest entries are dropped once it's exceeded.
+pub const CAPACITY: usize = 1000;
+
+/// Maximum number of characters kept in an [`AuditEntry::args`].
+const MAX_ARGS_LEN: usize = 512;
+
+/// [`AuditEntry::api`] of the changes made by the server itself, rather than
+/// requested by a client.
+pub const INTERNAL_API: &str = "internal";
+
+/// Placeholder replacing values of sensitive arguments in an
+/// [`AuditEntry::args`].
+const MASK: &str = "\"***\"";
+
+/// Maximum depth of nested fragments being resolved while extracting
+/// mutations, so a maliciously deep document cannot overflow the stack.
+const MAX_FRAGMENTS_DEPTH: usize = 32;
+
+/// Entry of the audit log, describing a single mutation requested via GraphQL
+/// API.
+#[derive(Clone, Debug, Deserialize, GraphQLObject, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// Sequential number of this `AuditEntry`.
+    ///
+    /// Increases monotonically, so may be used as a cursor.
+    pub id: i32,
+
+    /// Time the mutation has been requested at.
+    pub at: DateTime<Utc>,
+
+    /// IP address of the client requested the mutation.
+    pub client_ip: Option<String>,
+
+    /// Kind of the password the client has been authorized with.
+    ///
+    /// `null` if the API is not protected with any password.
+    pub auth: Option<PasswordKind>,
+
+    /// Path of the GraphQL API the mutation has been requested via.
+    pub api: String,
+
+    /// Name of the requested mutation.
+    pub mutation: String,
+
+    /// Summary of the mutation arguments, with values of sensitive ones (like
+    /// passwords) masked.
+    pub args: String,
+}
+
+/// Information about a client requesting mutations, recorded into
+/// [`AuditEntry`]s.
+#[derive(Clone, Debug)]
+pub struct Requester {
+    /// IP address of the client.
+    pub client_ip: Option<String>,
+
+    /// Kind of the password the client has been authorized with, if any.
+    pub auth: Option<PasswordKind>,
+
+    /// Path of the GraphQL API requested by the client.
+    pub api: String,
+}
+
+/// Single GraphQL request, as sent by a client.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    /// GraphQL document of this [`Request`].
+    pub query: String,
+
+    /// Name of the operation to be executed from the [`Request::query`].
+    #[serde(default)]
+    pub operation_name: Option<String>,
+
+    /// Values of the variables used in the [`Request::query`].
+    #[serde(default)]
+    pub variables: Option<Json>,
+}
+
+impl Request {
+    /// Parses a JSON `body` of a GraphQL HTTP request, being either a single
+    /// [`Request`] or a batch of them.
+    ///
+    /// Returns no [`Request`]s if the `body` cannot be parsed, as the request
+    /// will be rejected on execution anyway.
+    #[must_use]
+    pub fn parse_batch(body: &[u8]) -> Vec<Self> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Batch {
+            Single(Request),
+            Many(Vec<Request>),
+        }
+
+        match serde_json::from_slice(body) {
+            Ok(Batch::Single(r)) => vec![r],
+            Ok(Batch::Many(rs)) => rs,
+            Err(_) => vec![],
+        }
+    }
+}
+
+/// Loads the audit log from the given `file` (if any) into the [`State`], and
+/// persists it there on any changes.
+///
+/// # Errors
+///
+/// If the `file` exists, but fails to be read or parsed.
+pub async fn init<P: AsRef<Path>>(
+    state: &State,
+    file: P,
+) -> anyhow::Result<()> {
+    let file = file.as_ref().to_owned();
+
+    match fs::read(&file).await {
+        Ok(contents) if !contents.is_empty() => {
+            let entries: VecDeque<AuditEntry> =
+                serde_json::from_slice(&contents).map_err(|e| {
+                    anyhow!(
+                        "Failed to deserialize audit log from '{}' file: {}",
+                        file.display(),
+                        e,
+                    )
+                })?;
+            state.audit_log.set(entries);
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(anyhow!(
+                "Failed to read '{}' file: {}",
+                file.display(),
+                e,
+            ))
+        }
+    }
+
+    State::on_change("persist_audit_log", &state.audit_log, move |log| {
+        let file = file.clone();
+        async move {
+            let contents = serde_json::to_vec(&log)
+                .expect("Failed to serialize audit log");
+            if let Err(e) = fs::write(&file, contents).await {
+                log::error!("Failed to persist audit log: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Records [`AuditEntry`]s for all the mutations of the given [`Request`]s
+/// into the [`State`].
+///
+/// Mutations are recorded once requested, regardless of their results.
+pub fn record(state: &State, requester: &Requester, requests: &[Request]) {
+    let mutations = requests
+        .iter()
+        .flat_map(|r| {
+            mutations(&r.query, r.operation_name.as_deref(), &r.variables)
+        })
+        .collect::<Vec<_>>();
+    if mutations.is_empty() {
+        return;
+    }
+
+    for (mutation, args) in mutations {
+        log::info!(
+            "Audit: `{}` requested by {} via {}",
+            mutation,
+            requester.client_ip.as_deref().unwrap_or("unknown client"),
+            requester.api,
+        );
+        push(state, requester, mutation, args);
+    }
+}
+
+/// Records an [`AuditEntry`] of the `mutation` with the given `args`,
+/// requested by the given client via non-GraphQL API, into the [`State`].
+pub fn record_mutation(
+    state: &State,
+    requester: &Requester,
+    mutation: &str,
+    args: String,
+) {
+    log::info!(
+        "Audit: `{}` requested by {} via {}",
+        mutation,
+        requester.client_ip.as_deref().unwrap_or("unknown client"),
+        requester.api,
+    );
+    push(state, requester, mutation.into(), args);
+}
+
+/// Records an [`AuditEntry`] of the `mutation` with the given `args`, being
+/// performed by the server itself, into the [`State`].
+pub fn record_internal(state: &State, mutation: &str, args: String) {
+    log::info!("Audit: `{}` performed by server", mutation);
+    let requester = Requester {
+        client_ip: None,
+        auth: None,
+        api: INTERNAL_API.into(),
+    };
+    push(state, &requester, mutation.into(), args);
+}
+
+/// Pushes a new [`AuditEntry`] into the [`State`], evicting the oldest ones
+/// beyond the [`CAPACITY`].
+fn push(state: &State, requester: &Requester, mutation: String, args: String) {
+    let mut entries = state.audit_log.lock_mut();
+    let id = entries
+        .back()
+        .map_or(0, |e| e.id)
+        .checked_add(1)
+        .unwrap_or(1);
+    entries.push_back(AuditEntry {
+        id,
+        at: Utc::now(),
+        client_ip: requester.client_ip.clone(),
+        auth: requester.auth,
+        api: requester.api.clone(),
+        mutation,
+        args,
+    });
+    while entries.len() > CAPACITY {
+        drop(entries.pop_front());
+    }
+}
+
+/// Returns the latest `limit` [`AuditEntry`]s from the [`State`], or the
+/// first `limit` ones following the [`AuditEntry`] with the `after` ID, if
+/// it's specified.
+#[must_use]
+pub fn list(
+    state: &State,
+    limit: usize,
+    after: Option<i32>,
+) -> Vec<AuditEntry> {
+    let entries = state.audit_log.lock_ref();
+    if let Some(after) = after {
+        entries
+            .iter()
+            .filter(|e| e.id > after)
+            .take(limit)
+            .cloned()
+            .collect()
+    } else {
+        let skip = entries.len().saturating_sub(limit);
+        entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Returns a stream of new [`AuditEntry`]s recorded into the [`State`].
+pub fn watch(state: &State) -> impl futures::Stream<Item = AuditEntry> {
+    use futures::{stream, StreamExt as _};
+    use futures_signals::signal::SignalExt as _;
+
+    let last = state.audit_log.lock_ref().back().map(|e| e.id);
+    state
+        .audit_log
+        .signal_cloned()
+        .to_stream()
+        .scan(last, |last, entries| {
+            let new = entries
+                .into_iter()
+                .filter(|e| last.map_or(true, |l| e.id > l))
+                .collect::<Vec<_>>();
+            if let Some(e) = new.last() {
+                *last = Some(e.id);
+            }
+            future::ready(Some(stream::iter(new)))
+        })
+        .flatten()
+}
+
+/// Lexical token of a GraphQL document, sufficient for extracting mutations
+/// out of it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token<'a> {
+    /// Punctuator, like `{` or `:`.
+    Punct(char),
+
+    /// Spread operator `...`.
+    Spread,
+
+    /// Name of a field, an argument, a type, etc.
+    Name(&'a str),
+
+    /// Literal value (string or number) as is.
+    Value(&'a str),
+}
+
+/// Splits the given GraphQL document `src` into [`Token`]s.
+///
+/// Ignored tokens (whitespaces, commas and comments) are omitted.
+///
+/// Scanning is done over bytes, and only ASCII bytes delimit [`Token`]s, so
+/// any [`Token`] is sliced on [`char`] boundaries, even if an escape sequence
+/// is followed by a multi-byte [`char`].
+fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let bytes = src.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'"' if bytes[i..].starts_with(b"\"\"\"") => {
+                i += 3;
+                while i < bytes.len() && !bytes[i..].starts_with(b"\"\"\"") {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 3).min(bytes.len());
+                tokens.push(Token::Value(&src[start..i]));
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                tokens.push(Token::Value(&src[start..i]));
+            }
+            b'.' if bytes[i..].starts_with(b"...") => {
+                i += 3;
+                tokens.push(Token::Spread);
+            }
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                while i < bytes.len()
+                    && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric())
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Name(&src[start..i]));
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || matches!(bytes[i], b'.' | b'+' | b'-'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Value(&src[start..i]));
+            }
+            b @ (b'{' | b'}' | b'(' | b')' | b'[' | b']' | b':' | b'$'
+            | b'@' | b'!' | b'=' | b'|' | b'&') => {
+                i += 1;
+                tokens.push(Token::Punct(char::from(b)));
+            }
+            // Whitespaces, commas, BOM and anything invalid.
+            _ => i += 1,
+        }
+    }
+    tokens
+}
+
+/// Returns index of the [`Token`] following the bracket closing the one at
+/// the `open` index.
+fn skip_brackets(tokens: &[Token<'_>], open: usize) -> usize {
+    let mut depth = 0_usize;
+    for (i, t) in tokens.iter().enumerate().skip(open) {
+        match t {
+            Token::Punct('{' | '(' | '[') => depth += 1,
+            Token::Punct('}' | ')' | ']') => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Returns index of the [`Token`] following the directives starting at the
+/// `i` index (if any).
+fn skip_directives(tokens: &[Token<'_>], mut i: usize) -> usize {
+    while tokens.get(i) == Some(&Token::Punct('@')) {
+        i += 2;
+        if tokens.get(i) == Some(&Token::Punct('(')) {
+            i = skip_brackets(tokens, i);
+        }
+    }
+    i
+}
+
+/// Named fragments of a GraphQL document, resolved while extracting fields of
+/// a selection set.
+#[derive(Debug)]
+struct Fragments<'a> {
+    /// Selection sets of the defined fragments by their names.
+    defined: HashMap<&'a str, &'a [Token<'a>]>,
+
+    /// Names of the fragments resolved already.
+    ///
+    /// Each fragment is resolved only once, so neither cyclic nor repeatedly
+    /// spread fragments can blow up the extraction.
+    resolved: HashSet<&'a str>,
+}
+
+impl<'a> Fragments<'a> {
+    /// Collects the fragments defined in the given `tokens` of a GraphQL
+    /// document.
+    fn collect(tokens: &'a [Token<'a>]) -> Self {
+        let mut defined = HashMap::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i] {
+                Token::Name("fragment") => {
+                    i += 1;
+                    let name = match tokens.get(i) {
+                        Some(Token::Name(n)) => *n,
+                        _ => continue,
+                    };
+                    // Skip type condition and directives.
+                    while i < tokens.len() && tokens[i] != Token::Punct('{') {
+                        i = if tokens[i] == Token::Punct('(') {
+                            skip_brackets(tokens, i)
+                        } else {
+                            i + 1
+                        };
+                    }
+                    let end = skip_brackets(tokens, i);
+                    let selection = tokens
+                        .get(i + 1..end.saturating_sub(1))
+                        .unwrap_or_default();
+                    let _ = defined.insert(name, selection);
+                    i = end;
+                }
+                Token::Punct('{' | '(') => i = skip_brackets(tokens, i),
+                _ => i += 1,
+            }
+        }
+        Self {
+            defined,
+            resolved: HashSet::new(),
+        }
+    }
+
+    /// Resolves the selection set of the fragment with the given `name`.
+    ///
+    /// Returns an empty selection set if there is no such fragment, or it has
+    /// been resolved already.
+    fn resolve(&mut self, name: &'a str) -> &'a [Token<'a>] {
+        if !self.resolved.insert(name) {
+            return &[];
+        }
+        self.defined.get(name).copied().unwrap_or_default()
+    }
+}
+
+/// Extracts names of the mutations requested by the given GraphQL `query`,
+/// along with summaries of their arguments.
+///
+/// If `operation` is specified, then only the operation with such name is
+/// considered.
+fn mutations(
+    query: &str,
+    operation: Option<&str>,
+    variables: &Option<Json>,
+) -> Vec<(String, String)> {
+    let tokens = tokenize(query);
+    let mut fragments = Fragments::collect(&tokens);
+    let mut mutations = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Name("mutation") => {
+                i += 1;
+                let name = match tokens.get(i) {
+                    Some(Token::Name(n)) => {
+                        i += 1;
+                        Some(*n)
+                    }
+                    _ => None,
+                };
+                // Skip variables definitions and directives.
+                while i < tokens.len() && tokens[i] != Token::Punct('{') {
+                    i = if tokens[i] == Token::Punct('(') {
+                        skip_brackets(&tokens, i)
+                    } else {
+                        i + 1
+                    };
+                }
+                let end = skip_brackets(&tokens, i);
+                if operation.map_or(true, |op| name == Some(op)) {
+                    let selection = tokens.get(i + 1..end).unwrap_or_default();
+                    mutations.extend(fields(
+                        selection,
+                        &mut fragments,
+                        variables,
+                        0,
+                    ));
+                }
+                i = end;
+            }
+            Token::Punct('{' | '(') => i = skip_brackets(&tokens, i),
+            _ => i += 1,
+        }
+    }
+    mutations
+}
+
+/// Extracts top-level fields of the given `selection` set, along with
+/// summaries of their arguments.
+///
+/// Fields of the spread `fragments` (both named and inline ones) are
+/// extracted too, as they belong to the same selection set.
+fn fields<'a>(
+    selection: &'a [Token<'a>],
+    fragments: &mut Fragments<'a>,
+    variables: &Option<Json>,
+    depth: usize,
+) -> Vec<(String, String)> {
+    let mut found = vec![];
+    let mut i = 0;
+    while i < selection.len() {
+        let mut field = match selection[i] {
+            Token::Name(n) => n,
+            Token::Spread => {
+                i += 1;
+                let named = match selection.get(i) {
+                    Some(Token::Name("on")) => {
+                        i += 2;
+                        None
+                    }
+                    Some(Token::Name(n)) => {
+                        i += 1;
+                        Some(*n)
+                    }
+                    _ => None,
+                };
+                i = skip_directives(selection, i);
+                let inner = if let Some(name) = named {
+                    fragments.resolve(name)
+                } else if selection.get(i) == Some(&Token::Punct('{')) {
+                    let end = skip_brackets(selection, i);
+                    let inner = selection
+                        .get(i + 1..end.saturating_sub(1))
+                        .unwrap_or_default();
+                    i = end;
+                    inner
+                } else {
+                    &[]
+                };
+                if depth < MAX_FRAGMENTS_DEPTH {
+                    found.extend(fields(
+                        inner,
+                        fragments,
+                        variables,
+                        depth + 1,
+                    ));
+                }
+                continue;
+            }
+            Token::Punct('{' | '(') => {
+                i = skip_brackets(selection, i);
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        i += 1;
+        if selection.get(i) == Some(&Token::Punct(':')) {
+            if let Some(Token::Name(n)) = selection.get(i + 1) {
+                field = *n;
+                i += 2;
+            }
+        }
+        let mut args = String::new();
+        if selection.get(i) == Some(&Token::Punct('(')) {
+            let end = skip_brackets(selection, i);
+            let inner = selection
+                .get(i + 1..end.saturating_sub(1))
+                .unwrap_or_default();
+            args = if is_sensitive(field) {
+                "***".to_owned()
+            } else {
+                summarize(inner, variables)
+            };
+            i = end;
+        }
+        // Skip directives and sub-selection.
+        i = skip_directives(selection, i);
+        if selection.get(i) == Some(&Token::Punct('{')) {
+            i = skip_brackets(selection, i);
+        }
+        if !field.starts_with("__") {
+            found.push((field.to_owned(), args));
+        }
+    }
+    found
+}
+
+/// Summarizes the given `args` tokens of a field, substituting values of the
+/// used `variables` and masking values of sensitive arguments.
+fn summarize(args: &[Token<'_>], variables: &Option<Json>) -> String {
+    /// Separates the next value in the `out`put, unless it starts a new one.
+    fn separate(out: &mut String) {
+        if !out.is_empty() && !out.ends_with(['{', '[', ' ']) {
+            out.push_str(", ");
+        }
+    }
+
+    let mut out = String::new();
+    let mut masked = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i] {
+            Token::Name(n) if args.get(i + 1) == Some(&Token::Punct(':')) => {
+                separate(&mut out);
+                masked = is_sensitive(n);
+                out.push_str(n);
+                out.push_str(": ");
+                i += 1;
+            }
+            Token::Punct('$') => {
+                if let Some(Token::Name(var)) = args.get(i + 1) {
+                    separate(&mut out);
+                    if masked {
+                        out.push_str(MASK);
+                    } else {
+                        let val = variables
+                            .as_ref()
+                            .and_then(|v| v.get(var))
+                            .cloned()
+                            .unwrap_or(Json::Null);
+                        out.push_str(&mask_json(val).to_string());
+                    }
+                    i += 1;
+                }
+            }
+            Token::Value(v) | Token::Name(v) => {
+                separate(&mut out);
+                out.push_str(if masked { MASK } else { v });
+            }
+            Token::Punct(c @ ('{' | '[')) => {
+                separate(&mut out);
+                out.push(c);
+            }
+            Token::Punct(c @ ('}' | ']')) => out.push(c),
+            Token::Punct(_) | Token::Spread => {}
+        }
+        i += 1;
+    }
+    if out.chars().count() > MAX_ARGS_LEN {
+        out = out.chars().take(MAX_ARGS_LEN).collect();
+        out.push('…');
+    }
+    out
+}
+
+/// Masks values of sensitive keys in the given JSON `val`ue.
+fn mask_json(val: Json) -> Json {
+    match val {
+        Json::Object(obj) => Json::Object(
+            obj.into_iter()
+                .map(|(k, v)| {
+                    let v = if is_sensitive(&k) {
+                        Json::String("***".into())
+                    } else {
+                        mask_json(v)
+                    };
+                    (k, v)
+                })
+                .collect(),
+        ),
+        Json::Array(arr) => {
+            Json::Array(arr.into_iter().map(mask_json).collect())
+        }
+        v => v,
+    }
+}
+
+/// Indicates whether the given `name` of a mutation or an argument refers to
+/// a sensitive value, which should not be recorded.
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["password", "secret", "token", "hash"]
+        .iter()
+        .any(|s| name.contains(s))
+}
+
+#[cfg(test)]
+mod audit_spec {
+    use serde_json::json;
+
+    use super::{mutations, tokenize};
+
+    #[test]
+    fn extracts_mutations_with_args() {
+        let query = r#"
+            query Info { info { publicHost } }
+            mutation EnableOutput($id: OutputID!, $restreamId: RestreamId!) {
+                enableOutput(id: $id, restreamId: $restreamId)
+                other: disableAllOutputs(restreamId: "r1") { __typename }
+            }
+        "#;
+        let vars = Some(json!({ "id": "o1", "restreamId": "r1" }));
+
+        assert_eq!(
+            mutations(query, Some("EnableOutput"), &vars),
+            vec![
+                (
+                    "enableOutput".to_owned(),
+                    r#"id: "o1", restreamId: "r1""#.to_owned(),
+                ),
+                (
+                    "disableAllOutputs".to_owned(),
+                    r#"restreamId: "r1""#.to_owned(),
+                ),
+            ],
+        );
+        assert!(mutations(query, Some("Info"), &vars).is_empty());
+    }
+
+    #[test]
+    fn masks_sensitive_args() {
+        let query = r#"mutation {
+            setPassword(new: "qwerty", old: "asdf")
+            setOutput(restreamId: "r1", tls: $tls, dst: "rtmp://a/b")
+        }"#;
+        let vars = Some(json!({ "tls": { "password": "x", "verify": true } }));
+
+        assert_eq!(
+            mutations(query, None, &vars),
+            vec![
+                ("setPassword".to_owned(), "***".to_owned()),
+                (
+                    "setOutput".to_owned(),
+                    concat!(
+                        r#"restreamId: "r1", "#,
+                        r#"tls: {"password":"***","verify":true}, "#,
+                        r#"dst: "rtmp://a/b""#,
+                    )
+                    .to_owned(),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn extracts_mutations_from_fragments() {
+        let query = r#"
+            mutation {
+                ... on Mutation { removeRestream(id: "r1") }
+                ...Disable
+                ... @include(if: true) { setMaintenanceMode(enabled: true) }
+            }
+            fragment Disable on Mutation {
+                disableAllOutputs(restreamId: "r2")
+                ...Disable
+            }
+        "#;
+
+        assert_eq!(
+            mutations(query, None, &None),
+            vec![
+                ("removeRestream".to_owned(), r#"id: "r1""#.to_owned()),
+                (
+                    "disableAllOutputs".to_owned(),
+                    r#"restreamId: "r2""#.to_owned(),
+                ),
+                ("setMaintenanceMode".to_owned(), "enabled: true".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn tokenizes_escaped_multibyte_chars() {
+        for query in [
+            "mutation { a(v: \"\"\"\\é\"\"\") }",
+            "mutation { a(v: \"\\é\") }",
+            "mutation { a(v: \"\"\"é\\",
+            "\"\\",
+        ] {
+            let _ = tokenize(query);
+            let _ = mutations(query, None, &None);
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Automatic backups of the server's state, exported as JSON [`Spec`]s into
+//! a directory on a schedule and on changes.
+//!
+//! [`Spec`]: crate::Spec
+
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use ephyr_log::log;
+use futures::future;
+use juniper::GraphQLObject;
+use once_cell::sync::OnceCell;
+use tokio::{fs, io::AsyncWriteExt as _, time};
+
+use crate::{
+    spec,
+    state::{Restream, State},
+    Spec,
+};
+
+/// Extension of backup files.
+const EXTENSION: &str = "json";
+
+/// Global instance of backups [`Storage`].
+static STORAGE: OnceCell<Storage> = OnceCell::new();
+
+/// Reason of making a [`Backup`], suffixing its name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reason {
+    /// Regular backup made on a schedule.
+    Scheduled,
+
+    /// Backup made after the configured number of changes.
+    Changes,
+
+    /// Backup of `Restream`s preceding removal of several of them at once.
+    BulkDelete,
+
+    /// Backup of `Restream`s preceding restoring another [`Backup`] over
+    /// them.
+    Restore,
+}
+
+impl Reason {
+    /// Returns the name suffix of a [`Backup`] made for this [`Reason`].
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Changes => "changes",
+            Self::BulkDelete => "bulk-delete",
+            Self::Restore => "restore",
+        }
+    }
+}
+
+/// Stored backup of the server's state.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct Backup {
+    /// Name of this backup file, identifying it for restoring.
+    pub name: String,
+
+    /// Moment this backup has been made at.
+    pub created_at: DateTime<Utc>,
+
+    /// Size of this backup file (in bytes).
+    pub size: f64,
+}
+
+/// Storage of [`Backup`]s, rotating them.
+#[derive(Debug)]
+pub struct Storage {
+    /// Path of the directory where [`Backup`]s are stored.
+    pub dir: PathBuf,
+
+    /// Maximum number of stored [`Backup`]s, after which the oldest ones are
+    /// removed.
+    pub max: usize,
+
+    /// JSON of the last stored [`Backup`], to not store the same one again.
+    last: Mutex<Option<String>>,
+}
+
+impl Storage {
+    /// Creates a new [`Storage`] of [`Backup`]s in the given `dir`, keeping
+    /// at most `max` of them.
+    #[inline]
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(dir: P, max: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Returns the global instance of [`Storage`].
+    ///
+    /// # Panics
+    ///
+    /// If the global instance hasn't been initialized yet via
+    /// [`Storage::set_global()`].
+    #[inline]
+    #[must_use]
+    pub fn global() -> &'static Storage {
+        STORAGE.get().expect("backup::Storage is not initialized")
+    }
+
+    /// Sets the global instance of [`Storage`].
+    ///
+    /// # Errors
+    ///
+    /// If the global instance has been set already.
+    #[inline]
+    pub fn set_global(self) -> anyhow::Result<()> {
+        STORAGE.set(self).map_err(|_| {
+            anyhow!("backup::Storage has been initialized already")
+        })
+    }
+
+    /// Resolves the given `name` of a stored [`Backup`] into its path.
+    ///
+    /// Returns [`None`] if the `name` is not a plain file name of a
+    /// [`Backup`], so cannot escape this [`Storage::dir`].
+    #[must_use]
+    pub fn file_path(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        let mut components = path.components();
+        let is_valid = matches!(components.next(), Some(Component::Normal(_)))
+            && components.next().is_none()
+            && path.extension().map_or(false, |ext| ext == EXTENSION);
+        is_valid.then(|| self.dir.join(path))
+    }
+
+    /// Lists stored [`Backup`]s, the newest first.
+    pub async fn list(&self) -> Vec<Backup> {
+        let mut backups = match self.list_files().await {
+            Ok(backups) => backups,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::error!("Failed to list backups: {e}");
+                }
+                return vec![];
+            }
+        };
+        backups.sort_by(|a, b| b.name.cmp(&a.name));
+        backups
+    }
+
+    /// Reads all the [`Backup`] files in this [`Storage::dir`].
+    #[allow(clippy::cast_precision_loss)]
+    async fn list_files(&self) -> io::Result<Vec<Backup>> {
+        let mut backups = vec![];
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if self.file_path(&name).is_none() {
+                continue;
+            }
+            let meta = entry.metadata().await?;
+            if !meta.is_file() {
+                continue;
+            }
+            backups.push(Backup {
+                name,
+                created_at: meta.modified()?.into(),
+                size: meta.len() as f64,
+            });
+        }
+        Ok(backups)
+    }
+
+    /// Reads the JSON [`Spec`] of the stored [`Backup`] with the given
+    /// `name`.
+    ///
+    /// Returns [`None`] if there is no such [`Backup`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`Backup`] file cannot be read.
+    pub async fn read(&self, name: &str) -> io::Result<Option<String>> {
+        let path = match self.file_path(name) {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        match fs::read_to_string(path).await {
+            Ok(json) => Ok(Some(json)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stores the given [`Spec`] as a new [`Backup`] made for the given
+    /// [`Reason`], and removes the oldest [`Backup`]s exceeding the
+    /// [`Storage::max`].
+    ///
+    /// Does nothing if the [`Spec`] is the same as the last stored one.
+    ///
+    /// As [`Backup`]s contain secrets (like stream keys of `Output`s), their
+    /// files are readable and writable by their owner only (on Unix
+    /// platforms).
+    ///
+    /// # Errors
+    ///
+    /// If the [`Spec`] cannot be serialized, or the [`Backup`] file cannot be
+    /// written.
+    pub async fn store(
+        &self,
+        spec: &Spec,
+        reason: Reason,
+    ) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(spec)
+            .map_err(|e| anyhow!("Failed to JSON-serialize spec: {e}"))?;
+        if self.last.lock().unwrap().as_ref() == Some(&json) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir).await?;
+        let name = format!(
+            "{}-{}.{EXTENSION}",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            reason.as_str(),
+        );
+        let mut opts = fs::OpenOptions::new();
+        let _ = opts.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        let _ = opts.mode(0o600);
+        opts.open(self.dir.join(&name))
+            .await?
+            .write_all(json.as_bytes())
+            .await?;
+        log::info!("Stored {name} backup");
+        *self.last.lock().unwrap() = Some(json);
+
+        self.rotate().await;
+        Ok(())
+    }
+
+    /// Removes the oldest [`Backup`]s exceeding the [`Storage::max`].
+    async fn rotate(&self) {
+        let outdated = self.list().await.into_iter().skip(self.max);
+        let _ = future::join_all(outdated.map(|b| async move {
+            if let Err(e) = fs::remove_file(self.dir.join(&b.name)).await {
+                log::error!("Failed to remove {} backup: {e}", b.name);
+            }
+        }))
+        .await;
+    }
+}
+
+/// Backs up the given [`State`] into the global [`Storage`] for the given
+/// [`Reason`].
+///
+/// Errors are logged only, as a failed backup shouldn't fail anything else.
+pub async fn store(state: &State, reason: Reason) {
+    if let Err(e) = Storage::global().store(&state.export(), reason).await {
+        log::error!("Failed to store {} backup: {e}", reason.as_str());
+    }
+}
+
+/// Runs making [`Backup`]s of the given [`State`] every `interval` and after
+/// every `every_changes` changes of its `Restream`s.
+///
+/// Additionally, backs up `Restream`s before several of them are removed at
+/// once, so an accidental bulk removal may be reverted.
+///
+/// Zero `interval` disables scheduled backups.
+pub fn run(state: State, interval: Duration, every_changes: Option<u32>) {
+    if !interval.is_zero() {
+        let state = state.clone();
+        drop(tokio::spawn(async move {
+            let mut interval = time::interval(interval);
+            loop {
+                let _ = interval.tick().await;
+                store(&state, Reason::Scheduled).await;
+            }
+        }));
+    }
+
+    let mut previous: Option<Vec<Restream>> = None;
+    let mut changes = 0;
+    State::on_change("store_backups", &state.restreams.clone(), move |now| {
+        let bulk_deleted: Option<Spec> = previous
+            .as_ref()
+            .filter(|prev| {
+                let removed = prev
+                    .iter()
+                    .filter(|r| !now.iter().any(|n| n.id == r.id))
+                    .count();
+                removed > 1
+            })
+            .map(|prev| {
+                spec::v1::Spec {
+                    settings: Some(state.settings.get_cloned().export()),
+                    restreams: prev.iter().map(Restream::export).collect(),
+                }
+                .into()
+            });
+        let is_first = previous.is_none();
+        previous = Some(now);
+
+        let nth_changed = match every_changes {
+            Some(n) if n > 0 && !is_first => {
+                changes += 1;
+                let reached = changes >= n;
+                if reached {
+                    changes = 0;
+                }
+                reached
+            }
+            _ => false,
+        };
+
+        let state = state.clone();
+        async move {
+            let storage = Storage::global();
+            if let Some(spec) = bulk_deleted {
+                if let Err(e) = storage.store(&spec, Reason::BulkDelete).await {
+                    log::error!("Failed to store bulk-delete backup: {e}");
+                }
+            }
+            if nth_changed {
+                store(&state, Reason::Changes).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod backup_spec {
+    use std::{env, path::PathBuf};
+
+    use tokio::fs;
+    use uuid::Uuid;
+
+    use crate::spec;
+
+    use super::{Reason, Storage};
+
+    /// Creates a new [`Storage`] in a unique temporary directory, keeping at
+    /// most `max` [`Backup`]s.
+    ///
+    /// [`Backup`]: super::Backup
+    fn temp_storage(max: usize) -> Storage {
+        Storage::new(
+            env::temp_dir().join(format!("ephyr_backups_{}", Uuid::new_v4())),
+            max,
+        )
+    }
+
+    #[test]
+    fn resolves_plain_backup_names_only() {
+        let storage = Storage::new("/backups", 3);
+
+        assert_eq!(
+            storage.file_path("20220101T101010.000Z-scheduled.json"),
+            Some(PathBuf::from(
+                "/backups/20220101T101010.000Z-scheduled.json",
+            )),
+        );
+        for name in [
+            "",
+            ".",
+            "..",
+            ".json",
+            "backup",
+            "backup.txt",
+            "../state.json",
+            "../../etc/passwd.json",
+            "/etc/state.json",
+            "./backup.json",
+            "dir/backup.json",
+            "dir/../backup.json",
+        ] {
+            assert_eq!(storage.file_path(name), None, "accepted {name:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rotates_oldest_backups() {
+        let storage = temp_storage(2);
+        fs::create_dir_all(&storage.dir).await.unwrap();
+        for name in [
+            "20220101T101010.000Z-scheduled.json",
+            "20220102T101010.000Z-changes.json",
+            "20220103T101010.000Z-restore.json",
+            "notes.txt",
+        ] {
+            fs::write(storage.dir.join(name), "{}").await.unwrap();
+        }
+
+        storage.rotate().await;
+
+        let names: Vec<_> =
+            storage.list().await.into_iter().map(|b| b.name).collect();
+        assert_eq!(
+            names,
+            [
+                "20220103T101010.000Z-restore.json",
+                "20220102T101010.000Z-changes.json",
+            ],
+        );
+        assert!(
+            storage.dir.join("notes.txt").exists(),
+            "non-backup file is removed",
+        );
+
+        fs::remove_dir_all(&storage.dir).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stores_backups_accessible_by_owner_only() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let storage = temp_storage(1);
+        let spec = spec::v1::Spec {
+            settings: None,
+            restreams: vec![],
+        }
+        .into();
+        storage.store(&spec, Reason::Scheduled).await.unwrap();
+
+        let backups = storage.list().await;
+        assert_eq!(backups.len(), 1);
+        let mode = fs::metadata(storage.dir.join(&backups[0].name))
+            .await
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(&storage.dir).await.unwrap();
+    }
+}
@@ -44,6 +44,12 @@ fn main() -> anyhow::Result<()> {
             juniper::IntrospectionFormat::default(),
         )
         .map_err(err_fn)?,
+        Api::Report => juniper::introspect(
+            &api::graphql::report::schema(),
+            &api::graphql::Context::fake(),
+            juniper::IntrospectionFormat::default(),
+        )
+        .map_err(err_fn)?,
     };
 
     let json = serde_json::to_string_pretty(&res)
@@ -108,6 +114,9 @@ enum Api {
     /// [`api::graphql::statistics`].
     #[display(fmt = "statistics")]
     Statistics,
+    /// [`api::graphql::report`].
+    #[display(fmt = "report")]
+    Report,
 }
 
 impl FromStr for Api {
@@ -119,6 +128,7 @@ impl FromStr for Api {
             "mix" => Ok(Self::Mix),
             "dashboard" => Ok(Self::Dashboard),
             "statistics" => Ok(Self::Statistics),
+            "report" => Ok(Self::Report),
             _ => Err(anyhow!("Unknown backend API '{}'", s)),
         }
     }
@@ -1,10 +1,18 @@
 //! CLI (command line interface).
 
-use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr as _};
+use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr as _, time::Duration};
 
 use anyhow::anyhow;
-use ephyr_log::slog;
+use ephyr_log::{slog, LogRotation};
 use structopt::StructOpt;
+use url::Url;
+
+use crate::{
+    ffmpeg::{HwAccel, MixinPipeKind, RestartWindow},
+    notifier::Severity,
+    srt,
+    state::StorageKind,
+};
 
 /// CLI (command line interface) of the re-streamer server.
 #[derive(Clone, Debug, StructOpt)]
@@ -14,6 +22,18 @@ pub struct Opts {
     #[structopt(short, long, help = "Enables debug mode")]
     pub debug: bool,
 
+    /// Indicator whether the server should start in maintenance mode,
+    /// rejecting any changes requested via API.
+    #[structopt(
+        long,
+        help = "Starts in maintenance mode",
+        long_help = "Starts in maintenance mode, rejecting any changes \
+                     requested via API, while queries and subscriptions keep \
+                     working. May be switched off via `setMaintenanceMode` \
+                     GraphQL mutation."
+    )]
+    pub maintenance_mode: bool,
+
     /// IP address for the server to listen client HTTP requests on.
     #[structopt(
         long,
@@ -35,6 +55,87 @@ pub struct Opts {
     )]
     pub client_http_port: u16,
 
+    /// Path to a PEM file with a [TLS] certificate (chain) to serve client
+    /// HTTP requests over HTTPS with.
+    ///
+    /// [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TLS_CERT_PATH",
+        help = "Path to TLS certificate to serve client HTTPS with",
+        long_help = "Path to a PEM file with a TLS certificate (chain) to \
+                     serve client HTTP requests over HTTPS with"
+    )]
+    pub tls_cert_path: Option<PathBuf>,
+
+    /// Path to a PEM file with a private key of the
+    /// [`Opts::tls_cert_path`] certificate.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TLS_KEY_PATH",
+        help = "Path to TLS private key to serve client HTTPS with",
+        long_help = "Path to a PEM file with a private key of the TLS \
+                     certificate to serve client HTTP requests over HTTPS \
+                     with"
+    )]
+    pub tls_key_path: Option<PathBuf>,
+
+    /// Indicator whether a self-signed [TLS] certificate should be generated
+    /// to serve client HTTP requests over HTTPS with.
+    ///
+    /// [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+    #[structopt(
+        long,
+        help = "Generates self-signed TLS certificate for client HTTPS",
+        long_help = "Generates a self-signed TLS certificate to serve client \
+                     HTTP requests over HTTPS with. If --tls-cert-path and \
+                     --tls-key-path are specified, then the generated \
+                     certificate is stored there and reused on restarts"
+    )]
+    pub tls_self_signed: bool,
+
+    /// Number of failed authorization attempts from a single IP address,
+    /// after which it's locked out of client HTTP server.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_AUTH_MAX_FAILURES",
+        default_value = "5",
+        help = "Number of failed auth attempts to lock out an IP after",
+        long_help = "Number of failed authorization attempts from a single IP \
+                     address, after which it's locked out of client HTTP \
+                     server for --auth-lockout duration. Zero disables the \
+                     lockout."
+    )]
+    pub auth_max_failures: u32,
+
+    /// Duration of the first lockout of an IP address exceeding
+    /// [`Opts::auth_max_failures`], doubled on every next one.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_AUTH_LOCKOUT",
+        default_value = "1m",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Duration to lock out an IP after failed auth attempts for",
+        long_help = "Duration to lock out an IP address exceeding \
+                     --auth-max-failures for. Doubled on every next lockout \
+                     of the same IP address, up to --auth-max-lockout."
+    )]
+    pub auth_lockout: Duration,
+
+    /// Maximum duration of a lockout of an IP address exceeding
+    /// [`Opts::auth_max_failures`].
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_AUTH_MAX_LOCKOUT",
+        default_value = "1h",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Maximum duration to lock out an IP for",
+        long_help = "Maximum duration to lock out an IP address exceeding \
+                     --auth-max-failures for. IP addresses having no failed \
+                     authorization attempts during it are forgotten."
+    )]
+    pub auth_max_lockout: Duration,
+
     /// IP address for the server to listen RTMP callback HTTP requests on.
     #[structopt(
         long,
@@ -68,6 +169,128 @@ pub struct Opts {
     )]
     pub state_path: PathBuf,
 
+    /// [`StorageKind`] to persist the server's state with.
+    ///
+    /// On switching to `sqlite`, the state is migrated from the
+    /// [`Opts::state_path`] file.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_STATE_BACKEND",
+        default_value = "json",
+        help = "Backend to persist state with: json | sqlite",
+        long_help = "Backend to persist the server's state with: json | \
+                     sqlite. On switching to sqlite, the state is migrated \
+                     from the file specified by --state-path"
+    )]
+    pub state_backend: StorageKind,
+
+    /// Path to a [SQLite] database to persist the server's state in, if the
+    /// `sqlite` [`Opts::state_backend`] is used.
+    ///
+    /// [SQLite]: https://sqlite.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_STATE_DB_PATH",
+        default_value = "state.db",
+        help = "Path to a SQLite database to persist state in",
+        long_help = "Path to a SQLite database to persist the server's state \
+                     in, if the sqlite state backend is used"
+    )]
+    pub state_db_path: PathBuf,
+
+    /// Interval of no changes to wait for before persisting the server's
+    /// state, so bursts of changes are persisted at once.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_STATE_PERSIST_DEBOUNCE",
+        default_value = "200ms",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval to debounce state persisting with",
+        long_help = "Interval of no changes to wait for before persisting the \
+                     server's state, so bursts of changes are persisted at \
+                     once"
+    )]
+    pub state_persist_debounce: Duration,
+
+    /// Indicator whether `${EPHYR_SPEC_*}` placeholders in URLs and labels of
+    /// imported specs should be expanded with values of the server's
+    /// environment variables.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SPEC_EXPAND_ENV",
+        help = "Expand ${EPHYR_SPEC_*} placeholders in imported specs",
+        long_help = "Expand ${EPHYR_SPEC_*} placeholders in URLs and labels \
+                     of imported specs with values of the server's \
+                     environment variables, failing on unset ones. \
+                     Environment variables without EPHYR_SPEC_ prefix are \
+                     never expanded."
+    )]
+    pub spec_expand_env: bool,
+
+    /// Path to a file to persist the audit log of GraphQL API mutations in.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_AUDIT_LOG_PATH",
+        default_value = "audit.json",
+        help = "Path to a file to persist audit log in",
+        long_help = "Path to a file to persist the audit log of GraphQL API \
+                     mutations in"
+    )]
+    pub audit_log_path: PathBuf,
+
+    /// Path to a directory to store automatic backups of the server's state
+    /// in.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_BACKUPS_DIR",
+        default_value = "backups",
+        help = "Path to a directory to store state backups in",
+        long_help = "Path to a directory to store automatic backups of the \
+                     server's state in, as JSON specs restorable via GraphQL \
+                     API"
+    )]
+    pub backups_dir: PathBuf,
+
+    /// Interval of making scheduled backups of the server's state.
+    ///
+    /// Zero disables scheduled backups.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_BACKUP_INTERVAL",
+        default_value = "1h",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval of making state backups",
+        long_help = "Interval of making scheduled backups of the server's \
+                     state. A backup is skipped if nothing has changed since \
+                     the previous one. Zero disables scheduled backups."
+    )]
+    pub backup_interval: Duration,
+
+    /// Number of `Restream`s changes to make a backup of the server's state
+    /// after.
+    ///
+    /// If [`None`], then backups are not made on changes.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_BACKUP_EVERY_CHANGES",
+        help = "Number of changes to make a state backup after",
+        long_help = "Number of changes of restreams to make a backup of the \
+                     server's state after (disabled by default)"
+    )]
+    pub backup_every_changes: Option<u32>,
+
+    /// Maximum number of stored backups of the server's state, after which
+    /// the oldest ones are removed.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_BACKUPS_MAX",
+        default_value = "48",
+        help = "Maximum number of stored state backups",
+        long_help = "Maximum number of stored backups of the server's state, \
+                     after which the oldest ones are removed"
+    )]
+    pub backups_max: usize,
+
     /// Path to [SRS] installation directory.
     ///
     /// [SRS]: https://github.com/ossrs/srs
@@ -100,6 +323,135 @@ pub struct Opts {
     )]
     pub srs_http_dir: PathBuf,
 
+    /// Path to the directory to store [DVR] recordings in.
+    ///
+    /// Should be outside of [`Opts::srs_http_dir`], as the recordings are
+    /// served only via the client HTTP server requiring authorization.
+    ///
+    /// Relative path will use [`Opts::srs_path`] as its base path, not the
+    /// current working directory.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_DVR_DIR",
+        default_value = "dvr",
+        help = "Path to DVR recordings",
+        long_help = "Path to the directory to store DVR recordings in. \
+                     Should be outside of --srs-http-dir, as SRS serves it \
+                     without any authorization.\
+                     \n\n\
+                     Relative path will use --srs-path as its base path, not \
+                     the current working directory."
+    )]
+    pub dvr_dir: PathBuf,
+
+    /// Path to the directory with media files to be broadcast as fallback
+    /// sources of `Restream`s.
+    ///
+    /// Fallback files are resolved relatively to it, and cannot escape it.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_MEDIA_DIR",
+        default_value = "media",
+        help = "Path to media files of fallback sources",
+        long_help = "Path to the directory with media files (images and \
+                     videos) to be broadcast as fallback sources of \
+                     restreams. Files of fallback sources are specified \
+                     relatively to it, and cannot escape it."
+    )]
+    pub media_dir: PathBuf,
+
+    /// Port for [SRS] to accept [RTMP] connections on.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRS]: https://github.com/ossrs/srs
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRS_RTMP_PORT",
+        default_value = "1935",
+        help = "Port for SRS to accept RTMP on",
+        long_help = "Port for SRS to accept RTMP connections on. Changing it \
+                     allows multiple servers to co-exist on the same host."
+    )]
+    pub srs_rtmp_port: u16,
+
+    /// Port for [SRS] to serve [HLS] live streams on.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRS_HTTP_PORT",
+        default_value = "8000",
+        help = "Port for SRS to serve HLS on",
+        long_help = "Port for SRS HTTP server to serve HLS live streams on. \
+                     Changing it allows multiple servers to co-exist on the \
+                     same host."
+    )]
+    pub srs_http_port: u16,
+
+    /// UDP port for [SRS] to accept [WebRTC] media on.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRS_RTC_PORT",
+        default_value = "8000",
+        help = "UDP port for SRS to accept WebRTC on",
+        long_help = "UDP port for SRS to accept WebRTC media on (published \
+                     via WHIP endpoints). Changing it allows multiple servers \
+                     to co-exist on the same host."
+    )]
+    pub srs_rtc_port: u16,
+
+    /// Port for [SRS] to expose its [HTTP API][1] on `127.0.0.1`.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRS_API_PORT",
+        default_value = "8002",
+        help = "Port for SRS to expose its HTTP API on",
+        long_help = "Port for SRS to expose its HTTP API on 127.0.0.1 \
+                     interface. Changing it allows multiple servers to \
+                     co-exist on the same host."
+    )]
+    pub srs_api_port: u16,
+
+    /// Port to ingest [RTMP] live streams via in public networks.
+    ///
+    /// If [`None`], then [`Opts::srs_rtmp_port`] is used.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_PUBLIC_RTMP_PORT",
+        help = "Port to ingest RTMP via in public networks",
+        long_help = "Port to ingest RTMP live streams via in public networks, \
+                     if it differs from --srs-rtmp-port (behind NAT or proxy, \
+                     for example)"
+    )]
+    pub public_rtmp_port: Option<u16>,
+
+    /// Range of UDP ports to assign to [SRT] endpoints of `Input`s, so
+    /// encoders may connect to them in caller mode.
+    ///
+    /// If [`None`], then no [SRT] endpoint is listened on.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRT_LISTEN_PORTS",
+        help = "Range of UDP ports to listen for SRT on",
+        long_help = "Range of UDP ports (like 10000-10099) to assign to SRT \
+                     endpoints of Inputs, each listening for a single SRT \
+                     caller. SRT endpoints are not listened on, if omitted."
+    )]
+    pub srt_listen_ports: Option<srt::PortRange>,
+
     /// Path to [FFmpeg] binary.
     ///
     /// [FFmpeg]: https://ffmpeg.org
@@ -113,6 +465,206 @@ pub struct Opts {
     )]
     pub ffmpeg_path: PathBuf,
 
+    /// Interval to check the [FFmpeg] binary for being replaced with.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_CHECK_INTERVAL",
+        default_value = "1m",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval to check FFmpeg binary for replacement",
+        long_help = "Interval to check FFmpeg binary for being replaced in \
+                     place (upgraded, for example)"
+    )]
+    pub ffmpeg_check_interval: Duration,
+
+    /// Daily time window (in UTC) to progressively restart [FFmpeg] processes
+    /// onto a replaced binary during.
+    ///
+    /// If [`None`], then processes switch onto a replaced binary on their
+    /// next natural restart only.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_RESTART_WINDOW",
+        help = "UTC time window to restart FFmpeg processes onto a replaced \
+                binary, like `02:00-04:00`",
+        long_help = "Daily time window (in UTC, `HH:MM-HH:MM` format) to \
+                     progressively restart FFmpeg processes onto a replaced \
+                     binary during. If not specified, processes switch onto \
+                     the replaced binary on their next natural restart only."
+    )]
+    pub ffmpeg_restart_window: Option<RestartWindow>,
+
+    /// Duration of [FFmpeg] processes not advancing their output, after which
+    /// they're considered frozen and restarted.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_STALL_TIMEOUT",
+        default_value = "30s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Duration of FFmpeg output not advancing to restart it after",
+        long_help = "Duration of FFmpeg process not advancing its output \
+                     (staying alive without forwarding frames), after which \
+                     it's considered frozen and restarted. Zero disables \
+                     the detection."
+    )]
+    pub ffmpeg_stall_timeout: Duration,
+
+    /// Interval of capturing preview images of online `InputEndpoint`s.
+    ///
+    /// Zero disables capturing.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_PREVIEW_INTERVAL",
+        default_value = "10s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval of capturing preview images of online inputs",
+        long_help = "Interval of capturing a JPEG preview image of every \
+                     online input endpoint with FFmpeg, served on \
+                     `/preview/{endpointId}.jpg`. Zero disables capturing."
+    )]
+    pub preview_interval: Duration,
+
+    /// Directory for runtime artifacts, like [FIFO]s, playlist files and
+    /// preview images.
+    ///
+    /// If [`None`], then the system temporary directory is used.
+    ///
+    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_RUNTIME_DIR",
+        help = "Directory for runtime artifacts (FIFOs, previews, etc)",
+        long_help = "Directory for runtime artifacts, like FIFOs, playlist \
+                     files and preview images. It's created if not exists, \
+                     and cleaned up from the artifacts of previous runs on \
+                     startup. If not specified, then the system temporary \
+                     directory is used (and not cleaned up)."
+    )]
+    pub runtime_dir: Option<PathBuf>,
+
+    /// [`HwAccel`] to be used by [FFmpeg] for transcoding.
+    ///
+    /// If [`None`], then it's auto-detected on startup.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_HW_ACCEL",
+        help = "Hardware acceleration of FFmpeg transcoding: \
+                none | nvenc | vaapi | vaapi:<device>",
+        long_help = "Hardware acceleration to be used by FFmpeg for \
+                     transcoding: none | nvenc | vaapi | vaapi:<device>. \
+                     If not specified, then it's auto-detected on startup \
+                     via `ffmpeg -encoders` output."
+    )]
+    pub hw_accel: Option<HwAccel>,
+
+    /// Kind of pipes feeding [TeamSpeak] audio into [FFmpeg] mixing
+    /// processes.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [TeamSpeak]: https://teamspeak.com
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_MIXIN_PIPE",
+        default_value = "anonymous",
+        help = "Kind of pipes feeding TeamSpeak audio into FFmpeg: \
+                anonymous | fifo",
+        long_help = "Kind of pipes feeding TeamSpeak audio into FFmpeg \
+                     mixing processes: `anonymous` passes inherited file \
+                     descriptors via `/dev/fd`, while `fifo` keeps the \
+                     legacy behavior of creating named pipes in the \
+                     --runtime-dir directory."
+    )]
+    pub mixin_pipe: MixinPipeKind,
+
+    /// Resident memory (in megabytes) of a single [FFmpeg] process to
+    /// terminate it on exceeding, so it's restarted afresh.
+    ///
+    /// If [`None`], then memory of [FFmpeg] processes is not limited.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_RSS_LIMIT",
+        help = "Memory (in MB) to restart a single FFmpeg process on \
+                exceeding",
+        long_help = "Resident memory (in megabytes) of a single FFmpeg \
+                     process to terminate it on exceeding, so it's restarted \
+                     afresh (disabled by default)"
+    )]
+    pub ffmpeg_rss_limit: Option<u64>,
+
+    /// Interval of sampling the server resources usage.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_STATISTICS_INTERVAL",
+        default_value = "1s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval of sampling server statistics",
+        long_help = "Interval of sampling the server resources usage (CPU, \
+                     RAM, network and disk)"
+    )]
+    pub statistics_interval: Duration,
+
+    /// URL of a remote dashboard to report statistics of this server to.
+    ///
+    /// If [`None`], then statistics are not reported, and the dashboard is
+    /// expected to poll them.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_REPORT_TO",
+        requires_all = &["report-as", "report-token"],
+        help = "Dashboard URL to report statistics to",
+        long_help = "URL of a remote dashboard to report statistics of this \
+                     server to, instead of the dashboard polling them \
+                     (disabled by default)"
+    )]
+    pub report_to: Option<Url>,
+
+    /// URL this server is registered with on the remote dashboard
+    /// ([`Opts::report_to`]).
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_REPORT_AS",
+        help = "URL this server is registered with on the dashboard",
+        long_help = "URL this server is registered with on the remote \
+                     dashboard it reports statistics to"
+    )]
+    pub report_as: Option<Url>,
+
+    /// Token issued by the remote dashboard ([`Opts::report_to`]) to report
+    /// statistics with.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_REPORT_TOKEN",
+        hide_env_values = true,
+        help = "Token to report statistics to the dashboard with",
+        long_help = "Token issued by the remote dashboard (see \
+                     `rotateClientReportToken` mutation) to report statistics \
+                     to it with"
+    )]
+    pub report_token: Option<String>,
+
+    /// Interval of reporting statistics to the remote dashboard
+    /// ([`Opts::report_to`]).
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_REPORT_INTERVAL",
+        default_value = "5s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval of reporting statistics to the dashboard",
+        long_help = "Interval of reporting statistics of this server to the \
+                     remote dashboard"
+    )]
+    pub report_interval: Duration,
+
     /// Host to access the re-streamer server in public networks.
     ///
     /// If [`None`], then it will be auto-detected.
@@ -125,6 +677,107 @@ pub struct Opts {
     )]
     pub public_host: Option<String>,
 
+    /// Indicator whether public viewer statistics should be served without
+    /// authorization.
+    #[structopt(
+        long,
+        help = "Enables public viewer statistics",
+        long_help = "Serves coarse viewer counts and online status of \
+                     restreams as JSON on `/api-public-stats` endpoint \
+                     without authorization, for embedding into public \
+                     websites"
+    )]
+    pub public_stats: bool,
+
+    /// CPU usage (in percents) considered as the server overload, when
+    /// `Output`s should be stopped in order of their priority.
+    ///
+    /// If [`None`], then no `Output`s are stopped on overload.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_CPU_SHEDDING_THRESHOLD",
+        help = "CPU usage % to stop low-priority outputs on",
+        long_help = "CPU usage (in percents) considered as the server \
+                     overload, when outputs should be stopped in order of \
+                     their priority (disabled by default)"
+    )]
+    pub cpu_shedding_threshold: Option<f64>,
+
+    /// Duration of the server overload (or normal load), after which the next
+    /// `Output` is stopped (or resumed).
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_CPU_SHEDDING_PERIOD",
+        default_value = "30s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Duration of overload to stop an output after",
+        long_help = "Duration of the server overload (or normal load), after \
+                     which the next output is stopped (or resumed)"
+    )]
+    pub cpu_shedding_period: Duration,
+
+    /// URL of a [Slack] incoming webhook to send notifications to.
+    ///
+    /// [Slack]: https://api.slack.com/messaging/webhooks
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SLACK_WEBHOOK_URL",
+        help = "Slack webhook URL to send notifications to",
+        long_help = "URL of a Slack incoming webhook to send notifications to"
+    )]
+    pub slack_webhook_url: Option<Url>,
+
+    /// Minimal [`Severity`] of events to be sent to [Slack].
+    ///
+    /// [Slack]: https://api.slack.com/messaging/webhooks
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SLACK_MIN_SEVERITY",
+        default_value = "warning",
+        help = "Minimal severity of Slack notifications: \
+                info | warning | critical"
+    )]
+    pub slack_min_severity: Severity,
+
+    /// URL of a [Microsoft Teams] incoming webhook to send notifications to.
+    ///
+    /// [Microsoft Teams]: https://learn.microsoft.com/en-us/microsoftteams/platform/webhooks-and-connectors/how-to/add-incoming-webhook
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TEAMS_WEBHOOK_URL",
+        help = "Teams webhook URL to send notifications to",
+        long_help = "URL of a Microsoft Teams incoming webhook to send \
+                     notifications to"
+    )]
+    pub teams_webhook_url: Option<Url>,
+
+    /// Minimal [`Severity`] of events to be sent to [Microsoft Teams].
+    ///
+    /// [Microsoft Teams]: https://learn.microsoft.com/en-us/microsoftteams/platform/webhooks-and-connectors/how-to/add-incoming-webhook
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TEAMS_MIN_SEVERITY",
+        default_value = "warning",
+        help = "Minimal severity of Teams notifications: \
+                info | warning | critical"
+    )]
+    pub teams_min_severity: Severity,
+
+    /// Template of notification messages.
+    ///
+    /// `{severity}`, `{host}`, `{title}` and `{message}` placeholders are
+    /// substituted with the appropriate event fields.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_NOTIFICATION_TEMPLATE",
+        default_value = "[{severity}] {host}: {title}\n{message}",
+        help = "Template of notification messages",
+        long_help = "Template of notification messages, where {severity}, \
+                     {host}, {title} and {message} placeholders are \
+                     substituted with the appropriate event fields"
+    )]
+    pub notification_template: String,
+
     /// Verbosity level of the server logs.
     #[structopt(
         short,
@@ -134,6 +787,99 @@ pub struct Opts {
                 OFF | CRIT | ERRO | WARN | INFO | DEBG | TRCE"
     )]
     pub verbose: Option<slog::Level>,
+
+    /// Indicator whether the server logs should not be written to STDOUT.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_NO_LOG_STDOUT",
+        help = "Disables writing logs to STDOUT"
+    )]
+    pub no_log_stdout: bool,
+
+    /// Path to a file to write the server logs to.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_LOG_FILE",
+        help = "Path to a file to write logs to",
+        long_help = "Path to a file to write logs to. The file is rotated \
+                     according to --log-rotation, having the rotation date \
+                     appended to its name"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    /// [`LogRotation`] of the [`Opts::log_file`].
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_LOG_ROTATION",
+        default_value = "daily",
+        help = "Period of log file rotation: \
+                minutely | hourly | daily | never"
+    )]
+    pub log_rotation: LogRotation,
+
+    /// Indicator whether the server logs should be sent to the local syslog.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_LOG_SYSLOG",
+        help = "Enables sending logs to the local syslog"
+    )]
+    pub log_syslog: bool,
+
+    /// Endpoint of an [OTLP] collector to export traces of GraphQL requests,
+    /// [SRS] callbacks and [FFmpeg] processes lifecycle to.
+    ///
+    /// If [`None`], then no traces are exported.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [OTLP]: https://opentelemetry.io/docs/specs/otlp
+    /// [SRS]: https://github.com/ossrs/srs
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_OTLP_ENDPOINT",
+        help = "OTLP collector endpoint to export traces to",
+        long_help = "Endpoint of an OTLP collector (gRPC, like \
+                     http://localhost:4317) to export traces of GraphQL \
+                     requests, SRS callbacks and FFmpeg processes lifecycle \
+                     to. No traces are exported, if omitted."
+    )]
+    pub otlp_endpoint: Option<Url>,
+
+    /// Optional [`Command`] to be executed instead of running the server.
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+/// Commands of the re-streamer server, to be executed instead of running it.
+#[derive(Clone, Debug, StructOpt)]
+pub enum Command {
+    /// Validates the given [`Spec`] file without running the server.
+    ///
+    /// [`Spec`]: crate::Spec
+    #[structopt(
+        about = "Validates a spec file",
+        long_about = "Validates a JSON spec file (as exported by `export` \
+                      GraphQL query) without running the server, printing \
+                      the found errors along with their JSON paths"
+    )]
+    Validate {
+        /// Path to the [`Spec`] file to be validated.
+        ///
+        /// [`Spec`]: crate::Spec
+        #[structopt(help = "Path to a JSON spec file")]
+        spec: PathBuf,
+
+        /// Indicator whether `${ENV_VAR}` placeholders in the [`Spec`] file
+        /// should be expanded with values of environment variables.
+        ///
+        /// [`Spec`]: crate::Spec
+        #[structopt(
+            long,
+            help = "Expand ${EPHYR_SPEC_*} placeholders in URLs and labels \
+                    of the spec file with values of environment variables, \
+                    failing on unset ones"
+        )]
+        expand_env: bool,
+    },
 }
 
 impl Opts {
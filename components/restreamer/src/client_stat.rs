@@ -4,25 +4,42 @@
 // graphql query without documentation and that causes warning messages
 #![allow(missing_docs)]
 
-use std::{collections::HashMap, panic::AssertUnwindSafe, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::{TryFrom, TryInto as _},
+    ops::RangeInclusive,
+    panic::AssertUnwindSafe,
+    time::Duration,
+};
 
 use crate::{
+    api::graphql::statistics::API_VERSION,
+    cli::Opts,
     display_panic,
+    notifier::{self, Event, Severity},
     state::{
-        Client, ClientId, ClientStatistics, ClientStatisticsResponse, Status,
-        StatusStatistics,
+        Alert, AlertEvent, AlertEventKind, AlertKind, Client, ClientId,
+        ClientStatistics, ClientStatisticsResponse, ClientStatus,
+        EndpointsUsage, FlappingOutput, Status, StatusStatistics,
     },
     types::DroppableAbortHandle,
     State,
 };
 
+use anyhow::anyhow;
 use ephyr_log::log;
 use futures::{future, FutureExt as _, TryFutureExt};
+use serde_json::{json, Value as Json};
 use tokio::time;
-
-use crate::client_stat::statistics_query::{
-    StatisticsQueryStatisticsInputs, StatisticsQueryStatisticsOutputs,
-    StatisticsQueryStatisticsServerInfo,
+use url::Url;
+
+use crate::client_stat::{
+    endpoints_usage_query::EndpointsUsageQueryStatisticsEndpointsUsage,
+    flapping_outputs_query::FlappingOutputsQueryStatisticsFlappingOutputs,
+    statistics_query::{
+        StatisticsQueryStatisticsInputs, StatisticsQueryStatisticsOutputs,
+        StatisticsQueryStatisticsServerInfo,
+    },
 };
 
 use crate::state::ServerInfo;
@@ -53,10 +70,12 @@ impl ClientJobsPool {
 
     /// Creates new [`ClientJob`] for added [`Client`] and removes for
     /// deleted [`Client`]
+    ///
+    /// [`Client`]s reporting their statistics by themselves are not polled.
     pub fn apply(&mut self, clients: &[Client]) {
         let mut new_pool = HashMap::with_capacity(self.pool.len() + 1);
 
-        for c in clients {
+        for c in clients.iter().filter(|c| !c.is_reporting()) {
             let client_id = c.id.clone();
             let job = self.pool.remove(&client_id).unwrap_or_else(|| {
                 ClientJob::run(c.id.clone(), self.state.clone())
@@ -69,8 +88,298 @@ impl ClientJobsPool {
     }
 }
 
+/// Duration after which a [`Client`] reporting its statistics by itself is
+/// considered [`ClientStatus::Stale`], if it hasn't reported them.
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Runs marking [`Client`]s of the given [`State`], which report their
+/// statistics by themselves, as [`ClientStatus::Stale`] once they stop doing
+/// so for [`STALE_AFTER`].
+pub fn run_stale_detection(state: State) {
+    let started_at = Utc::now();
+    drop(tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(5));
+        loop {
+            let _ = interval.tick().await;
+            mark_stale(&state, started_at);
+        }
+    }));
+}
+
+/// Marks [`Client`]s of the given [`State`], which haven't reported their
+/// statistics for [`STALE_AFTER`], as [`ClientStatus::Stale`].
+///
+/// [`Client`]s not reported since this server has `started_at` are counted
+/// since that moment.
+fn mark_stale(state: &State, started_at: DateTime<Utc>) {
+    let stale_after = chrono::Duration::from_std(STALE_AFTER).unwrap();
+    let now = Utc::now();
+    let is_stale = |c: &Client| {
+        c.is_reporting()
+            && c.statistics
+                .as_ref()
+                .map_or(true, |s| s.status != ClientStatus::Stale)
+            && now - c.last_seen_at.unwrap_or(started_at) >= stale_after
+    };
+
+    if !state.clients.lock_ref().iter().any(is_stale) {
+        return;
+    }
+    let mut clients = state.clients.lock_mut();
+    for c in clients.iter_mut().filter(|c| is_stale(c)) {
+        let msg = c.last_seen_at.map_or_else(
+            || "No statistics have been reported yet".to_owned(),
+            |at| format!("No statistics have been reported since {at}"),
+        );
+        log::warn!("Client {} is stale: {}", c.id, msg);
+        c.statistics = Some(ClientStatisticsResponse {
+            data: None,
+            errors: Some(vec![msg]),
+            status: ClientStatus::Stale,
+            api_version: c.statistics.as_ref().and_then(|s| s.api_version),
+        });
+    }
+}
+
+/// Reporter of statistics of this server to a remote dashboard, so the
+/// dashboard doesn't need to poll this server for them.
+#[derive(Clone, Debug)]
+pub struct Reporter {
+    /// GraphQL API endpoint of the remote dashboard.
+    endpoint: Url,
+
+    /// [`ClientId`] this server is registered with on the remote dashboard.
+    client_id: ClientId,
+
+    /// Token to report statistics with.
+    token: String,
+
+    /// Interval of reporting statistics.
+    interval: Duration,
+}
+
+impl Reporter {
+    /// GraphQL mutation reporting statistics to a remote dashboard.
+    const MUTATION: &'static str = "\
+        mutation ReportStatistics(\
+            $clientId: ClientId!, $statistics: String!, $apiVersion: Int!\
+        ) {\
+            reportStatistics(\
+                clientId: $clientId,\
+                statistics: $statistics,\
+                apiVersion: $apiVersion\
+            )\
+        }";
+
+    /// Creates a new [`Reporter`] out of the given CLI [`Opts`].
+    ///
+    /// Returns [`None`] if reporting is not configured.
+    ///
+    /// # Errors
+    ///
+    /// If the configured dashboard URL cannot be used as a base URL.
+    pub fn new(cfg: &Opts) -> anyhow::Result<Option<Self>> {
+        let (dashboard, client_id, token) = match (
+            cfg.report_to.as_ref(),
+            cfg.report_as.clone(),
+            cfg.report_token.clone(),
+        ) {
+            (Some(d), Some(id), Some(t)) => (d, ClientId::new(id), t),
+            _ => return Ok(None),
+        };
+        let endpoint = dashboard.join("api-report").map_err(|e| {
+            anyhow!("Invalid dashboard URL `{}`: {}", dashboard, e)
+        })?;
+        Ok(Some(Self {
+            endpoint,
+            client_id,
+            token,
+            interval: cfg.report_interval,
+        }))
+    }
+
+    /// Runs reporting statistics of the given [`State`] periodically.
+    pub fn run(self, state: State) {
+        drop(tokio::spawn(async move {
+            let mut interval = time::interval(self.interval);
+            loop {
+                let _ = interval.tick().await;
+                if let Err(e) = self.report(&state).await {
+                    log::error!(
+                        "Failed to report statistics to {}: {}",
+                        self.endpoint,
+                        e,
+                    );
+                }
+            }
+        }));
+    }
+
+    /// Reports the current statistics of the given [`State`] to the remote
+    /// dashboard.
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the dashboard rejects the statistics.
+    async fn report(&self, state: &State) -> anyhow::Result<()> {
+        let statistics = serde_json::to_string(&state.get_statistics())?;
+        let body = json!({
+            "query": Self::MUTATION,
+            "variables": {
+                "clientId": self.client_id.to_string(),
+                "statistics": statistics,
+                "apiVersion": API_VERSION,
+            },
+        });
+        let resp: Response<Json> = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()?
+            .post(self.endpoint.clone())
+            .bearer_auth(&self.token)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(errs) = resp.errors.filter(|e| !e.is_empty()) {
+            return Err(anyhow!(
+                "Dashboard responded with errors: {}",
+                errs.iter()
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Evaluator of `AlertRule`s against statistics of [`Client`]s, raising and
+/// clearing [`Alert`]s in a [`State`].
+#[derive(Debug, Default)]
+pub struct AlertsEvaluator {
+    /// Moments since when conditions of `AlertRule`s are satisfied for
+    /// [`Client`]s.
+    pending: HashMap<(ClientId, AlertKind), DateTime<Utc>>,
+}
+
+impl AlertsEvaluator {
+    /// Runs this [`AlertsEvaluator`] tracking [`Client`]s changes of the given
+    /// [`State`].
+    pub fn run(mut self, state: State) {
+        let st = state.clone();
+        State::on_change("evaluate_alerts", &state.clients, move |clients| {
+            self.evaluate(&clients, &st);
+            future::ready(())
+        });
+    }
+
+    /// Evaluates `AlertRule`s against the given [`Client`]s, raising and
+    /// clearing [`Alert`]s in the [`State`].
+    fn evaluate(&mut self, clients: &[Client], state: &State) {
+        let rules = state.settings.get_cloned().alert_rules;
+        let prev = state.alerts.get_cloned();
+        let now = Utc::now();
+
+        let mut pending = HashMap::new();
+        let mut actual = vec![];
+        for client in clients {
+            let stats = match client.statistics.as_ref() {
+                Some(s) => s,
+                None => continue,
+            };
+            for rule in &rules {
+                let value = match rule.check(stats.status, stats.data.as_ref())
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let key = (client.id.clone(), rule.kind);
+                let since = self.pending.get(&key).copied().unwrap_or(now);
+                let _ = pending.insert(key, since);
+                if now - since < chrono::Duration::seconds(rule.duration.into())
+                {
+                    continue;
+                }
+                actual.push(
+                    prev.iter()
+                        .find(|a| {
+                            a.client_id == client.id && a.kind == rule.kind
+                        })
+                        .cloned()
+                        .unwrap_or_else(|| Alert {
+                            client_id: client.id.clone(),
+                            kind: rule.kind,
+                            value,
+                            raised_at: now,
+                        }),
+                );
+            }
+        }
+        self.pending = pending;
+
+        for e in AlertEvent::diff(&prev, &actual) {
+            let (severity, title) = match e.kind {
+                AlertEventKind::Raised => (Severity::Warning, "Alert raised"),
+                AlertEventKind::Cleared => (Severity::Info, "Alert cleared"),
+            };
+            let msg = format!(
+                "{} alert on client {} (value {})",
+                e.alert.kind, e.alert.client_id, e.alert.value,
+            );
+            log::warn!("{}: {}", title, msg);
+            notifier::notify(Event::new(severity, title, msg));
+        }
+        state.alerts.set_neq(actual);
+    }
+}
+
 type DateTimeUtc = DateTime<Utc>;
 
+/// Versions of the statistics API of [`Client`]s supported by this server.
+///
+/// [`Client`]s not reporting their API version at all are considered as
+/// supported, as they run old servers compatible with [`StatisticsQuery`].
+pub const SUPPORTED_API_VERSIONS: RangeInclusive<i32> = 1..=API_VERSION;
+
+/// GraphQL query for getting version of client's statistics API.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "statistics.graphql.schema.json",
+    query_path = "src/api/graphql/queries/api_version.graphql",
+    response_derives = "Debug"
+)]
+#[derive(Debug)]
+pub struct ApiVersionQuery;
+
+/// GraphQL query for getting outputs of client being restarted repeatedly.
+///
+/// Queried separately from [`StatisticsQuery`], as old clients don't support
+/// it.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "statistics.graphql.schema.json",
+    query_path = "src/api/graphql/queries/flapping_outputs.graphql",
+    response_derives = "Debug"
+)]
+#[derive(Debug)]
+pub struct FlappingOutputsQuery;
+
+/// GraphQL query for getting usage of client's input endpoints by publishers
+/// and players.
+///
+/// Queried separately from [`StatisticsQuery`], as old clients don't support
+/// it.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "statistics.graphql.schema.json",
+    query_path = "src/api/graphql/queries/endpoints_usage.graphql",
+    response_derives = "Debug"
+)]
+#[derive(Debug)]
+pub struct EndpointsUsageQuery;
+
 /// GraphQL query for getting client statistics
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -93,42 +402,79 @@ impl From<StatisticsQueryStatisticsServerInfo> for ServerInfo {
             ram_free: item.ram_free,
             rx_delta: item.rx_delta,
             tx_delta: item.tx_delta,
+            disk_total: None,
+            disk_free: None,
             error_msg: item.error_msg,
+            state_persistence_error: None,
         }
     }
 }
 
 #[allow(clippy::cast_possible_truncation)]
-impl From<StatisticsQueryStatisticsInputs> for StatusStatistics {
-    fn from(item: StatisticsQueryStatisticsInputs) -> Self {
-        StatusStatistics {
-            status: item.status.into(),
+impl TryFrom<StatisticsQueryStatisticsInputs> for StatusStatistics {
+    type Error = String;
+
+    fn try_from(
+        item: StatisticsQueryStatisticsInputs,
+    ) -> Result<Self, Self::Error> {
+        Ok(StatusStatistics {
+            status: item.status.try_into()?,
             count: item.count as i32,
-        }
+        })
     }
 }
 
 #[allow(clippy::cast_possible_truncation)]
-impl From<StatisticsQueryStatisticsOutputs> for StatusStatistics {
-    fn from(item: StatisticsQueryStatisticsOutputs) -> Self {
-        StatusStatistics {
-            status: item.status.into(),
+impl TryFrom<StatisticsQueryStatisticsOutputs> for StatusStatistics {
+    type Error = String;
+
+    fn try_from(
+        item: StatisticsQueryStatisticsOutputs,
+    ) -> Result<Self, Self::Error> {
+        Ok(StatusStatistics {
+            status: item.status.try_into()?,
             count: item.count as i32,
+        })
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+impl From<FlappingOutputsQueryStatisticsFlappingOutputs> for FlappingOutput {
+    fn from(item: FlappingOutputsQueryStatisticsFlappingOutputs) -> Self {
+        FlappingOutput {
+            label: item.label,
+            dst: item.dst,
+            restarts: item.restarts as i32,
+            last_error: item.last_error,
         }
     }
 }
 
-impl From<statistics_query::Status> for Status {
-    fn from(status: statistics_query::Status) -> Self {
-        match status {
+#[allow(clippy::cast_possible_truncation)]
+impl From<EndpointsUsageQueryStatisticsEndpointsUsage> for EndpointsUsage {
+    fn from(item: EndpointsUsageQueryStatisticsEndpointsUsage) -> Self {
+        EndpointsUsage {
+            publishers_count: item.publishers_count as i32,
+            players_count: item.players_count as i32,
+        }
+    }
+}
+
+impl TryFrom<statistics_query::Status> for Status {
+    type Error = String;
+
+    /// Converts the given [`statistics_query::Status`] into a [`Status`].
+    ///
+    /// Unknown statuses (reported by newer clients) are returned as errors,
+    /// so may be skipped rather than break the whole statistics.
+    fn try_from(status: statistics_query::Status) -> Result<Self, Self::Error> {
+        Ok(match status {
             statistics_query::Status::ONLINE => Status::Online,
             statistics_query::Status::OFFLINE => Status::Offline,
             statistics_query::Status::INITIALIZING => Status::Initializing,
             statistics_query::Status::UNSTABLE => Status::Unstable,
-            statistics_query::Status::Other(other) => {
-                panic!("Unknown status {}", other)
-            }
-        }
+            statistics_query::Status::Other(other) => return Err(other),
+        })
     }
 }
 
@@ -205,13 +551,26 @@ impl ClientJob {
 
         log::info!("Getting statistics from client: {}", client_id);
 
-        let request_body = StatisticsQuery::build_query(Vars {});
         let request = reqwest::Client::builder()
             .timeout(Duration::from_secs(5))
             .build()
             .unwrap();
-
         let url = format!("{client_id}api-statistics");
+
+        let api_version = Self::fetch_api_version(&request, &url).await?;
+        if let Some(ver) =
+            api_version.filter(|v| !SUPPORTED_API_VERSIONS.contains(v))
+        {
+            log::warn!(
+                "Client {} has incompatible statistics API version {}",
+                client_id,
+                ver,
+            );
+            Self::save_client_incompatible(client_id, ver, state);
+            return Ok(());
+        }
+
+        let request_body = StatisticsQuery::build_query(Vars {});
         let res = request
             .post(url.as_str())
             .json(&request_body)
@@ -219,10 +578,135 @@ impl ClientJob {
             .await?;
 
         let response: Response<ResponseData> = res.json().await?;
-        Self::save_client_stat(client_id, response, state);
+        let flapping = Self::fetch_flapping_outputs(&request, &url).await;
+        let usage = Self::fetch_endpoints_usage(&request, &url).await;
+        Self::save_client_stat(
+            client_id,
+            response,
+            flapping,
+            usage,
+            api_version,
+            state,
+        );
         Ok(())
     }
 
+    /// Fetches outputs being restarted repeatedly of the client by the given
+    /// `url`.
+    ///
+    /// Returns nothing if the client doesn't support it.
+    async fn fetch_flapping_outputs(
+        request: &reqwest::Client,
+        url: &str,
+    ) -> Vec<FlappingOutput> {
+        type Vars = <FlappingOutputsQuery as GraphQLQuery>::Variables;
+        type ResponseData =
+            <FlappingOutputsQuery as GraphQLQuery>::ResponseData;
+
+        let response: Option<Response<ResponseData>> = async {
+            request
+                .post(url)
+                .json(&FlappingOutputsQuery::build_query(Vars {}))
+                .send()
+                .await?
+                .json()
+                .await
+        }
+        .await
+        .ok();
+
+        // Old clients respond with an error about unknown `flappingOutputs`
+        // field.
+        response
+            .and_then(|r| r.data)
+            .map(|d| {
+                d.statistics
+                    .flapping_outputs
+                    .into_iter()
+                    .map(Into::into)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Fetches usage of input endpoints of the client by the given `url`.
+    ///
+    /// Returns nothing if the client doesn't support it.
+    async fn fetch_endpoints_usage(
+        request: &reqwest::Client,
+        url: &str,
+    ) -> EndpointsUsage {
+        type Vars = <EndpointsUsageQuery as GraphQLQuery>::Variables;
+        type ResponseData = <EndpointsUsageQuery as GraphQLQuery>::ResponseData;
+
+        let response: Option<Response<ResponseData>> = async {
+            request
+                .post(url)
+                .json(&EndpointsUsageQuery::build_query(Vars {}))
+                .send()
+                .await?
+                .json()
+                .await
+        }
+        .await
+        .ok();
+
+        // Old clients respond with an error about unknown `endpointsUsage`
+        // field.
+        response
+            .and_then(|r| r.data)
+            .map(|d| d.statistics.endpoints_usage.into())
+            .unwrap_or_default()
+    }
+
+    /// Fetches version of the statistics API of the client by the given
+    /// `url`.
+    ///
+    /// Returns [`None`] if the client doesn't support API versioning at all.
+    async fn fetch_api_version(
+        request: &reqwest::Client,
+        url: &str,
+    ) -> anyhow::Result<Option<i32>> {
+        type Vars = <ApiVersionQuery as GraphQLQuery>::Variables;
+        type ResponseData = <ApiVersionQuery as GraphQLQuery>::ResponseData;
+
+        let response: Response<ResponseData> = request
+            .post(url)
+            .json(&ApiVersionQuery::build_query(Vars {}))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // Old clients respond with an error about unknown `apiVersion` field.
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(response.data.map(|d| d.api_version as i32))
+    }
+
+    fn save_client_incompatible(
+        client_id: &ClientId,
+        api_version: i32,
+        state: &State,
+    ) {
+        let mut clients = state.clients.lock_mut();
+        let client = match clients.iter_mut().find(|r| r.id == *client_id) {
+            Some(c) => c,
+            None => panic!("Client with id = {} was not found", client_id),
+        };
+
+        client.statistics = Some(ClientStatisticsResponse {
+            data: None,
+            errors: Some(vec![format!(
+                "Incompatible statistics API version {}, supported are {}-{}",
+                api_version,
+                SUPPORTED_API_VERSIONS.start(),
+                SUPPORTED_API_VERSIONS.end(),
+            )]),
+            status: ClientStatus::Incompatible,
+            api_version: Some(api_version),
+        });
+    }
+
     fn save_client_error(
         client_id: &ClientId,
         error_message: String,
@@ -237,21 +721,33 @@ impl ClientJob {
         client.statistics = Some(ClientStatisticsResponse {
             data: None,
             errors: Some(vec![error_message]),
+            status: ClientStatus::Failed,
+            api_version: None,
         });
     }
 
     fn save_client_stat(
         client_id: &ClientId,
         response: Response<<StatisticsQuery as GraphQLQuery>::ResponseData>,
+        flapping_outputs: Vec<FlappingOutput>,
+        endpoints_usage: EndpointsUsage,
+        api_version: Option<i32>,
         state: &State,
     ) {
-        let response_errors: Vec<String> = response
+        let mut response_errors: Vec<String> = response
             .errors
             .unwrap_or_default()
             .into_iter()
             .map(|e| e.message)
             .collect();
 
+        let mut tolerate = |res: Result<StatusStatistics, String>| {
+            res.map_err(|status| {
+                response_errors.push(format!("Unknown status {}", status));
+            })
+            .ok()
+        };
+
         let mut clients = state.clients.lock_mut();
         let client = match clients.iter_mut().find(|r| r.id == *client_id) {
             Some(c) => c,
@@ -259,27 +755,46 @@ impl ClientJob {
         };
 
         client.statistics = match response.data {
-            Some(data) => Some(ClientStatisticsResponse {
-                data: Some(ClientStatistics::new(
-                    data.statistics.client_title,
-                    data.statistics
-                        .inputs
-                        .into_iter()
-                        .map(Into::into)
-                        .collect(),
-                    data.statistics
-                        .outputs
-                        .into_iter()
-                        .map(Into::into)
-                        .collect(),
-                    data.statistics.server_info.into(),
-                )),
-                errors: Some(response_errors),
-            }),
+            Some(data) => {
+                let inputs = data
+                    .statistics
+                    .inputs
+                    .into_iter()
+                    .filter_map(|i| tolerate(i.try_into()))
+                    .collect();
+                let outputs = data
+                    .statistics
+                    .outputs
+                    .into_iter()
+                    .filter_map(|o| tolerate(o.try_into()))
+                    .collect();
+                Some(ClientStatisticsResponse {
+                    data: Some(ClientStatistics::new(
+                        data.statistics.client_title,
+                        inputs,
+                        outputs,
+                        data.statistics.server_info.into(),
+                        flapping_outputs,
+                        endpoints_usage,
+                    )),
+                    errors: Some(response_errors),
+                    status: ClientStatus::Ok,
+                    api_version,
+                })
+            }
             None => Some(ClientStatisticsResponse {
                 data: None,
                 errors: Some(response_errors),
+                status: ClientStatus::Failed,
+                api_version,
             }),
         };
+        if client
+            .statistics
+            .as_ref()
+            .map_or(false, |s| s.status == ClientStatus::Ok)
+        {
+            client.last_seen_at = Some(Utc::now());
+        }
     }
 }
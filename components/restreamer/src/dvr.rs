@@ -3,22 +3,89 @@
 //! [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
 
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsString,
     io,
-    path::{Path, PathBuf},
-    time::SystemTime,
+    path::{Component, Path, PathBuf},
+    process::Stdio,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::anyhow;
 use ephyr_log::log;
 use futures::{future, stream::TryStreamExt};
-use once_cell::sync::OnceCell;
-use tokio::fs;
+use juniper::GraphQLObject;
+use once_cell::sync::{Lazy, OnceCell};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt as _,
+    process::Command,
+    sync::Semaphore,
+    time,
+};
 use tokio_stream::wrappers::ReadDirStream;
 use url::Url;
 use uuid::Uuid;
 
-use crate::state;
+use crate::{ffmpeg, state};
+
+/// Path prefix of the client HTTP server's route serving [DVR] files.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+pub const ROUTE: &str = "/dvr/";
+
+/// Name of the directory (inside an `Output`'s [DVR] directory) where
+/// thumbnails of its [DVR] files are stored.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+const THUMBNAILS_DIR: &str = ".thumbnails";
+
+/// Name of the directory (inside an `Output`'s [DVR] directory) where switch
+/// markers of its chained [DVR] recordings are stored.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+const MARKERS_DIR: &str = ".markers";
+
+/// Name of the directory (inside [`Storage::root_path`]) where [SRS] records
+/// live streams by itself.
+///
+/// [SRS]: https://github.com/ossrs/srs
+const SRS_DIR: &str = "srs";
+
+/// Period after a chained [DVR] recording has been stopped, during which its
+/// restart continues the same recording rather than starting a new one.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+const CHAIN_GAP: Duration = Duration::from_secs(30);
+
+/// Maximum number of [FFmpeg] processes generating thumbnails concurrently.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const THUMBNAILS_CONCURRENCY: usize = 2;
+
+/// Period after the last modification of a [DVR] file, when it's considered as
+/// a completed recording.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+const COMPLETED_AFTER: Duration = Duration::from_secs(10);
+
+/// [`Semaphore`] bounding concurrency of thumbnails generation.
+static THUMBNAILS_PERMITS: Lazy<Semaphore> =
+    Lazy::new(|| Semaphore::new(THUMBNAILS_CONCURRENCY));
+
+/// Relative paths of [DVR] files being processed for thumbnails at the moment.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+static THUMBNAILS_IN_PROGRESS: Lazy<Mutex<HashSet<String>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Chained [DVR] recordings in progress, identified by their DVR file [`Url`]
+/// (formed by [`Storage::file_url()`]).
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+static CHAINS: Lazy<Mutex<HashMap<Url, Chain>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Global instance of a [DVR] files [`Storage`] used by this application.
 ///
@@ -72,16 +139,35 @@ impl Storage {
         let mut full = self.root_path.clone();
         full.push(output.id.to_string());
         full.push(output.dst.path().trim_start_matches('/'));
-        Url::from_file_path(full)
-            .map_err(|e| anyhow!("Failed convert path to URL: {:?}", e))
+        let mut url = Url::from_file_path(full)
+            .map_err(|e| anyhow!("Failed convert path to URL: {:?}", e))?;
+        // Preserve recording options (like `segment`) for re-streamers.
+        url.set_query(output.dst.query());
+        Ok(url)
+    }
+
+    /// Resolves the given `path` (relative to this [`Storage::root_path`]) of
+    /// a stored [DVR] file into the absolute one.
+    ///
+    /// Returns [`None`] if the `path` is not a relative one, or tries to escape
+    /// this [`Storage::root_path`].
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    #[must_use]
+    pub fn file_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        let path = path.as_ref();
+        let is_valid = path.components().next().is_some()
+            && path.components().all(|c| matches!(c, Component::Normal(_)));
+        is_valid.then(|| self.root_path.join(path))
     }
 
     /// Lists stored [DVR] files of the given [`state::Output`].
     ///
-    /// Returns them as relative paths to this [`Storage::root_path`].
+    /// Returns them with paths relative to this [`Storage::root_path`], sorted
+    /// by their paths, so segments of the same recording go one after another.
     ///
     /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
-    pub async fn list_files(&self, id: state::OutputId) -> Vec<String> {
+    pub async fn list_files(&self, id: state::OutputId) -> Vec<DvrFile> {
         let dir = &self.root_path;
 
         let mut output_dir = dir.clone();
@@ -90,18 +176,50 @@ impl Storage {
         if let Ok(read_dir) = fs::read_dir(output_dir).await {
             return ReadDirStream::new(read_dir)
                 .try_filter_map(|i| async move {
-                    Ok(i.file_type()
+                    if !i.file_type().await?.is_file() {
+                        return Ok(None);
+                    }
+                    let path = i.path();
+                    let completed = i
+                        .metadata()
                         .await?
-                        .is_file()
-                        .then(|| i.path())
+                        .modified()?
+                        .elapsed()
+                        .map_or(false, |d| d >= COMPLETED_AFTER);
+                    let thumbnail = fs::metadata(thumbnail_path(&path))
+                        .await
+                        .is_ok()
+                        .then(|| thumbnail_path(&path))
+                        .and_then(|p| {
+                            Some(
+                                p.strip_prefix(dir).ok()?.display().to_string(),
+                            )
+                        });
+                    let recording = recording_of(&path);
+                    let markers = fs::metadata(markers_path(&path, &recording))
+                        .await
+                        .is_ok()
+                        .then(|| markers_path(&path, &recording))
                         .and_then(|p| {
                             Some(
                                 p.strip_prefix(dir).ok()?.display().to_string(),
                             )
-                        }))
+                        });
+                    Ok(path.strip_prefix(dir).ok().map(|p| DvrFile {
+                        url: format!("{ROUTE}{}", p.display()),
+                        path: p.display().to_string(),
+                        recording,
+                        thumbnail,
+                        markers,
+                        completed,
+                    }))
                 })
                 .try_collect()
                 .await
+                .map(|mut files: Vec<DvrFile>| {
+                    files.sort_by(|a, b| a.path.cmp(&b.path));
+                    files
+                })
                 .unwrap_or_else(|e| {
                     if e.kind() != io::ErrorKind::NotFound {
                         log::error!("Failed to list {} DVR files: {}", id, e);
@@ -112,17 +230,94 @@ impl Storage {
         vec![]
     }
 
+    /// Spawns background [FFmpeg] jobs generating thumbnails for the given
+    /// completed video [DVR] `files` not having them yet.
+    ///
+    /// Number of concurrently running jobs is bounded, so the rest of them
+    /// await in a queue.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn generate_thumbnails<P: AsRef<Path>>(
+        &self,
+        ffmpeg_path: P,
+        files: &[DvrFile],
+    ) {
+        for file in files {
+            if file.thumbnail.is_some()
+                || !file.completed
+                || !Path::new(&file.path)
+                    .extension()
+                    .map_or(false, |ext| ext == "flv" || ext == "mp4")
+                || !THUMBNAILS_IN_PROGRESS
+                    .lock()
+                    .unwrap()
+                    .insert(file.path.clone())
+            {
+                continue;
+            }
+
+            let ffmpeg_path = ffmpeg_path.as_ref().to_owned();
+            let rel_path = file.path.clone();
+            let mut src = self.root_path.clone();
+            src.push(&rel_path);
+
+            drop(tokio::spawn(async move {
+                if let Ok(_permit) = THUMBNAILS_PERMITS.acquire().await {
+                    if let Err(e) = generate_thumbnail(&ffmpeg_path, &src).await
+                    {
+                        log::error!(
+                            "Failed to generate thumbnail for {} DVR file: {}",
+                            rel_path,
+                            e,
+                        );
+                    }
+                }
+                let _ =
+                    THUMBNAILS_IN_PROGRESS.lock().unwrap().remove(&rel_path);
+            }));
+        }
+    }
+
+    /// Spawns a background job generating thumbnails for the completed video
+    /// [DVR] files of the [`state::Output`] with the given `id`, once its
+    /// recording [FFmpeg] process has finished.
+    ///
+    /// Awaits the last recorded file to be considered as a completed one
+    /// beforehand (see [`COMPLETED_AFTER`]).
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn generate_thumbnails_on_finish(&'static self, id: state::OutputId) {
+        drop(tokio::spawn(async move {
+            time::sleep(COMPLETED_AFTER + Duration::from_secs(1)).await;
+            let files = self.list_files(id).await;
+            self.generate_thumbnails(ffmpeg::Binary::global().path(), &files);
+        }));
+    }
+
     /// Removes a [DVR] file from this [`Storage`] identified by its relative
     /// `path` to this [`Storage::root_path`].
     ///
-    /// Returns `true` if the file has been removed, otherwise `false`.
+    /// Returns `true` if the file has been removed, otherwise `false`
+    /// (including the case of the `path` trying to escape this
+    /// [`Storage::root_path`]).
     ///
     /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
     pub async fn remove_file<P: AsRef<Path>>(&self, path: P) -> bool {
         let path = path.as_ref();
+        let path = path.strip_prefix("/").unwrap_or(path);
 
-        let mut full = self.root_path.clone();
-        full.push(path.strip_prefix("/").unwrap_or(path));
+        let (full, thumb) = match (
+            self.file_path(path),
+            self.file_path(thumbnail_path(path)),
+        ) {
+            (Some(full), Some(thumb)) => (full, thumb),
+            _ => return false,
+        };
+
+        // Thumbnail may not exist, so we're not interested in the result.
+        let _ = fs::remove_file(thumb).await;
 
         if let Err(e) = fs::remove_file(full).await {
             if e.kind() != io::ErrorKind::NotFound {
@@ -147,15 +342,16 @@ impl Storage {
             ReadDirStream::new(read_dir)
                 .try_filter(|i| {
                     future::ready(
-                        i.file_name()
-                            .to_str()
-                            .and_then(|n| Uuid::parse_str(n).ok())
-                            .map_or(true, |id| {
-                                let id = id.into();
-                                !restreams.iter().any(|r| {
-                                    r.outputs.iter().any(|o| o.id == id)
-                                })
-                            }),
+                        i.file_name() != SRS_DIR
+                            && i.file_name()
+                                .to_str()
+                                .and_then(|n| Uuid::parse_str(n).ok())
+                                .map_or(true, |id| {
+                                    let id = id.into();
+                                    !restreams.iter().any(|r| {
+                                        r.outputs.iter().any(|o| o.id == id)
+                                    })
+                                }),
                     )
                 })
                 .try_for_each_concurrent(4, |i| async move {
@@ -173,16 +369,278 @@ impl Storage {
     }
 }
 
+/// Recorded [DVR] file of an `Output`.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct DvrFile {
+    /// Path of this recorded file relative to the [`Storage::root_path`].
+    pub path: String,
+
+    /// URL of this recorded file on the client HTTP server, relative to its
+    /// origin.
+    ///
+    /// Requires the same authorization as GraphQL API does, and supports
+    /// partial downloads via HTTP `Range` requests.
+    pub url: String,
+
+    /// Identifier of the recording this file belongs to.
+    ///
+    /// Segments of the same segmented recording share the same value of it,
+    /// so may be grouped by it.
+    pub recording: String,
+
+    /// Path of a thumbnail image of this recorded file relative to the
+    /// [`Storage::root_path`].
+    ///
+    /// Served by the client HTTP server under [`ROUTE`], requiring the same
+    /// authorization as GraphQL API does.
+    ///
+    /// `null` if the thumbnail hasn't been generated yet, or the recorded file
+    /// is an audio-only one.
+    pub thumbnail: Option<String>,
+
+    /// Path of a text file with switch markers of the chained recording this
+    /// file belongs to, relative to the [`Storage::root_path`].
+    ///
+    /// Served by the client HTTP server under [`ROUTE`], requiring the same
+    /// authorization as GraphQL API does.
+    ///
+    /// Each line of it contains a UNIX timestamp (in seconds) when the
+    /// recording has been (re)started (due to a failover switch, for
+    /// example), and the name of the file continuing the recording since
+    /// then, separated by a tab.
+    ///
+    /// `null` if the recording is not a chained one.
+    pub markers: Option<String>,
+
+    /// Indicator whether the recording of this file has been completed.
+    pub completed: bool,
+}
+
+/// Suffix of a segmented [DVR] file name, formatted by [FFmpeg] with a
+/// segment's start time.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
+const SEGMENT_SUFFIX: &str = "-%Y%m%dT%H%M%S";
+
+/// Prefix of a chained [DVR] file name suffix, followed by a sequence number of
+/// the file in its chained recording.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+const CHAIN_PREFIX: &str = "-c";
+
+/// Chained [DVR] recording, continued by new files each time its recording
+/// [FFmpeg] process is restarted (due to a failover switch, for example)
+/// rather than starting a new recording.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug)]
+struct Chain {
+    /// Identifier of this [`Chain`] recording.
+    recording: OsString,
+
+    /// Sequence number of the last file in this [`Chain`].
+    seq: u32,
+
+    /// Moment when this [`Chain`] recording has been stopped the last time.
+    ///
+    /// [`None`] if it's being recorded at the moment.
+    stopped_at: Option<Instant>,
+}
+
+impl Chain {
+    /// Indicates whether this [`Chain`] recording may be continued.
+    fn is_alive(&self) -> bool {
+        self.stopped_at.map_or(true, |at| at.elapsed() < CHAIN_GAP)
+    }
+}
+
+/// Indicates whether the given DVR file [`Url`] (formed by
+/// [`Storage::file_url()`]) requires a chained recording, following the
+/// active `Input` of a failover source across its switches.
+#[must_use]
+pub fn is_chained(url: &Url) -> bool {
+    url.query_pairs()
+        .any(|(k, v)| k == "chain" && (v == "1" || v == "true"))
+}
+
+/// Marks the chained recording into the given DVR file [`Url`] (formed by
+/// [`Storage::file_url()`]) as stopped, so it's continued only if restarted
+/// within [`CHAIN_GAP`].
+pub fn stop_chain(url: &Url) {
+    if let Some(chain) = CHAINS.lock().unwrap().get_mut(url) {
+        chain.stopped_at = Some(Instant::now());
+    }
+}
+
+/// Returns duration (in seconds) of segments to split the recording into,
+/// if the given DVR file [`Url`] (formed by [`Storage::file_url()`]) requires
+/// a segmented recording.
+#[must_use]
+pub fn segment_duration(url: &Url) -> Option<u32> {
+    url.query_pairs()
+        .find_map(|(k, v)| (k == "segment").then(|| v.parse().ok()).flatten())
+}
+
+/// Returns identifier of the recording the given [DVR] file `path` belongs
+/// to.
+///
+/// For segmented recordings it's the file name without the segment's suffix,
+/// and for regular ones it's the file name without extension.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+fn recording_of(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = stem
+        .rsplit_once('-')
+        .filter(|(_, time)| is_segment_time(time))
+        .map_or(stem.as_str(), |(name, _)| name);
+    name.rsplit_once(CHAIN_PREFIX)
+        .filter(|(_, seq)| {
+            !seq.is_empty() && seq.bytes().all(|b| b.is_ascii_digit())
+        })
+        .map_or(name, |(name, _)| name)
+        .to_owned()
+}
+
+/// Indicates whether the given string is a segment's start time formatted
+/// by [`SEGMENT_SUFFIX`] (like `20220101T101010`).
+fn is_segment_time(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 15
+        && bytes.iter().enumerate().all(|(n, b)| {
+            if n == 8 {
+                *b == b'T'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
+/// Returns a path of a switch markers file of the given chained `recording`
+/// for the given absolute [DVR] file `path` belonging to it.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+fn markers_path(path: &Path, recording: &str) -> PathBuf {
+    let mut markers = path.to_path_buf();
+    markers.set_file_name(MARKERS_DIR);
+    markers.push(format!("{recording}.txt"));
+    markers
+}
+
+/// Appends a switch marker of the given new `file` of a chained `recording`
+/// to its markers file.
+///
+/// # Errors
+///
+/// If the markers directory cannot be created, or the markers file fails to
+/// be written.
+async fn write_marker(
+    file: &Path,
+    recording: &str,
+    now: Duration,
+) -> io::Result<()> {
+    let path = markers_path(file, recording);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?
+        .write_all(format!("{}\t{name}\n", now.as_secs()).as_bytes())
+        .await
+}
+
+/// Returns a path of a thumbnail image for the given absolute [DVR] file
+/// `path`.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+fn thumbnail_path(path: &Path) -> PathBuf {
+    let mut thumb = path.to_path_buf();
+    let mut file_name = OsString::new();
+    if let Some(name) = path.file_name() {
+        file_name.push(name);
+    }
+    file_name.push(".jpg");
+    thumb.set_file_name(THUMBNAILS_DIR);
+    thumb.push(file_name);
+    thumb
+}
+
+/// Generates a thumbnail image of the first keyframe of the given absolute
+/// [DVR] file `path` with [FFmpeg].
+///
+/// # Errors
+///
+/// If the thumbnails directory cannot be created, or [FFmpeg] process fails.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
+async fn generate_thumbnail(ffmpeg_path: &Path, path: &Path) -> io::Result<()> {
+    let thumb = thumbnail_path(path);
+    if let Some(dir) = thumb.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let out = Command::new(ffmpeg_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .args(["-loglevel", "error"])
+        .args(["-skip_frame", "nokey"])
+        .arg("-i")
+        .arg(path)
+        .args(["-frames:v", "1"])
+        .args(["-vf", "scale=320:-2"])
+        .arg("-y")
+        .arg(&thumb)
+        .output()
+        .await?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "FFmpeg exited with code: {}\n{}",
+                out.status,
+                String::from_utf8_lossy(&out.stderr),
+            ),
+        ))
+    }
+}
+
 /// Creates a new recording file path from the given DVR file [`Url`] (formed by
 /// [`Storage::file_url()`]) appended with the current timestamp in microseconds
 /// to ensure its uniqueness.
 ///
+/// If the [`Url`] requires a segmented recording (see [`segment_duration()`]),
+/// then the returned path is a pattern for [FFmpeg] `segment` muxer, having
+/// each segment's start time in its file name.
+///
+/// If the [`Url`] requires a chained recording (see [`is_chained()`]), then
+/// the returned path continues the recording stopped less than [`CHAIN_GAP`]
+/// ago (if any), having the next sequence number in its file name, and the
+/// switch marker is written for it.
+///
 /// Also, ensures that the appropriate parent directory for the file exists.
 ///
 /// # Errors
 ///
 /// If cannot create a file path from the given [`Url`], or fails to create its
 /// parent directory.
+///
+/// [FFmpeg]: https://ffmpeg.org
 #[allow(clippy::missing_panics_doc)]
 pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
     let mut path = url.to_file_path().map_err(|_| {
@@ -201,11 +659,136 @@ pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
     if let Some(name) = path.file_stem() {
         file_name.push(name);
     }
-    file_name.push(format!("_{}.", now.as_micros()));
+    file_name.push(format!("_{}", now.as_micros()));
+    let chain = is_chained(url).then(|| {
+        let mut chains = CHAINS.lock().unwrap();
+        chains.retain(|_, c| c.is_alive());
+        let chain = chains.entry(url.clone()).or_insert_with(|| Chain {
+            recording: file_name.clone(),
+            seq: 0,
+            stopped_at: None,
+        });
+        chain.seq += 1;
+        chain.stopped_at = None;
+        chain.clone()
+    });
+    if let Some(chain) = &chain {
+        file_name = chain.recording.clone();
+        file_name.push(format!("{CHAIN_PREFIX}{:03}", chain.seq));
+    }
+    if segment_duration(url).is_some() {
+        file_name.push(SEGMENT_SUFFIX);
+    }
+    file_name.push(".");
     if let Some(ext) = path.extension() {
         file_name.push(ext);
     }
     path.set_file_name(file_name);
 
+    if let Some(chain) = chain {
+        let recording = chain.recording.to_string_lossy();
+        if let Err(e) = write_marker(&path, &recording, now).await {
+            log::error!(
+                "Failed to write switch marker of {} DVR recording: {}",
+                recording,
+                e,
+            );
+        }
+    }
+
     Ok(path)
 }
+
+/// Properly setups the given [FFmpeg] [`Command`] for a segmented recording,
+/// if the given DVR file [`Url`] requires it (see [`segment_duration()`]).
+///
+/// Must be called right before specifying the output file path.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub fn setup_segmenting(cmd: &mut Command, url: &Url) {
+    let secs = if let Some(secs) = segment_duration(url) {
+        secs
+    } else {
+        return;
+    };
+    let format =
+        match Path::new(url.path()).extension().and_then(|e| e.to_str()) {
+            Some("wav") => "wav",
+            Some("mp3") => "mp3",
+            Some("mp4") => "mp4",
+            _ => "flv",
+        };
+    let _ = cmd
+        .args(["-f", "segment"])
+        .args(["-segment_time", &secs.to_string()])
+        .args(["-segment_format", format])
+        .args(["-reset_timestamps", "1"])
+        .args(["-strftime", "1"]);
+}
+
+#[cfg(test)]
+mod storage_spec {
+    use std::env;
+
+    use tokio::fs;
+    use uuid::Uuid;
+
+    use super::Storage;
+
+    #[tokio::test]
+    async fn does_not_remove_files_outside_root() {
+        let dir = env::temp_dir().join(format!("ephyr_dvr_{}", Uuid::new_v4()));
+        let storage = Storage {
+            root_path: dir.join("dvr"),
+        };
+        fs::create_dir_all(storage.root_path.join("id"))
+            .await
+            .unwrap();
+        fs::write(dir.join("state.json"), "{}").await.unwrap();
+        fs::write(storage.root_path.join("id/rec.flv"), "")
+            .await
+            .unwrap();
+
+        for path in ["../state.json", "/../state.json", "id/../../state.json"] {
+            assert!(!storage.remove_file(path).await, "removed {path:?}");
+        }
+        assert!(dir.join("state.json").exists(), "file outside is removed");
+
+        assert!(storage.remove_file("/id/rec.flv").await);
+        assert!(!storage.root_path.join("id/rec.flv").exists());
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
+
+#[cfg(test)]
+mod recording_spec {
+    use std::path::Path;
+
+    use super::recording_of;
+
+    #[test]
+    fn groups_segments_by_recording() {
+        for (input, expected) in &[
+            ("/dvr/id/rec_1650000000.flv", "rec_1650000000"),
+            (
+                "/dvr/id/rec_1650000000-20220101T101010.mp4",
+                "rec_1650000000",
+            ),
+            (
+                "/dvr/id/my-rec_1650000000-20220101T101010.flv",
+                "my-rec_1650000000",
+            ),
+            ("/dvr/id/my-rec_1650000000.flv", "my-rec_1650000000"),
+            ("/dvr/id/rec_1650000000-2022.flv", "rec_1650000000-2022"),
+            ("/dvr/id/rec_1650000000-c002.flv", "rec_1650000000"),
+            (
+                "/dvr/id/rec_1650000000-c012-20220101T101010.mp4",
+                "rec_1650000000",
+            ),
+            ("/dvr/id/rec-c_1650000000.flv", "rec-c_1650000000"),
+        ] {
+            assert_eq!(&recording_of(Path::new(input)), expected, "{input}");
+        }
+    }
+}
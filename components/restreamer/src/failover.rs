@@ -0,0 +1,103 @@
+//! Health-checking of failover `Input`s, so a `FailoverInputSrc` switches back
+//! to a restored `Input` only once it's proven to be stable.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use ephyr_log::log;
+use tokio::time;
+use url::Url;
+
+use crate::{
+    state::{EndpointId, InputSrc, Status},
+    stream_probe::stream_probe,
+    State,
+};
+
+/// Interval of checking failover `Input`s for being ready to be switched back
+/// to.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Runs checking `InputEndpoint`s of failover `Input`s in the given [`State`]
+/// periodically, marking them as `InputEndpoint::is_failover_ready` once they
+/// satisfy the `FailoverHysteresis` of their `FailoverInputSrc`.
+///
+/// Marking changes the [`State`], so the `RestreamersPool` re-applies it and
+/// switches the failover source back, if required.
+pub fn run(state: State) {
+    drop(tokio::spawn(async move {
+        let mut interval = time::interval(CHECK_INTERVAL);
+        loop {
+            let _ = interval.tick().await;
+            check(&state).await;
+        }
+    }));
+}
+
+/// Checks `InputEndpoint`s of failover `Input`s in the given [`State`],
+/// marking the ones being online long enough (and having a valid video
+/// stream, if required) as `InputEndpoint::is_failover_ready`.
+async fn check(state: &State) {
+    let now = Utc::now();
+
+    let mut candidates = vec![];
+    for r in state.restreams.lock_ref().iter() {
+        let s = match &r.input.src {
+            Some(InputSrc::Failover(s)) => s,
+            _ => continue,
+        };
+        let hysteresis = match s.hysteresis {
+            Some(h) => h,
+            None => continue,
+        };
+        let stable = chrono::Duration::seconds(hysteresis.stable_secs.into());
+        for i in &s.inputs {
+            for e in &i.endpoints {
+                if e.is_rtmp()
+                    && e.status == Status::Online
+                    && !e.is_failover_ready
+                    && e.counters
+                        .online_since
+                        .map_or(false, |t| t + stable <= now)
+                {
+                    candidates.push((
+                        e.id,
+                        e.kind.rtmp_url(&r.srs_app(), &i.key),
+                        hysteresis.probe_video,
+                    ));
+                }
+            }
+        }
+    }
+
+    for (id, url, probe_video) in candidates {
+        if probe_video && !has_video(url).await {
+            continue;
+        }
+        mark_ready(state, id);
+    }
+}
+
+/// Probes the live stream at the given `url` for having a valid video stream.
+async fn has_video(url: Url) -> bool {
+    match stream_probe(url.clone()).await {
+        Ok(info) => info.find_stream("video").is_some(),
+        Err(e) => {
+            log::debug!("Failed to probe failover Input at {url}: {e}");
+            false
+        }
+    }
+}
+
+/// Marks the `InputEndpoint` with the given `id` in the [`State`] as
+/// `InputEndpoint::is_failover_ready`, if it's still online.
+fn mark_ready(state: &State, id: EndpointId) {
+    let mut restreams = state.restreams.lock_mut();
+    if let Some(e) =
+        restreams.iter_mut().find_map(|r| r.input.find_endpoint(id))
+    {
+        if e.status == Status::Online {
+            e.is_failover_ready = true;
+        }
+    }
+}
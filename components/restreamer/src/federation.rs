@@ -0,0 +1,376 @@
+//! Federation of remote `Client`s, allowing to operate them via their
+//! [GraphQL] APIs from a single dashboard.
+//!
+//! [GraphQL]: https://graphql.com
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use graphql_client::{GraphQLQuery, Response};
+use juniper::GraphQLObject;
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    state::{ClientId, OutputId, RestreamId, Status},
+    State,
+};
+
+use self::queries::{
+    remote_restreams_query, RemoteDisableOutputMutation,
+    RemoteEnableOutputMutation, RemoteRestreamsQuery,
+};
+
+/// Login to authorize on remote `Client`s with.
+///
+/// Any login is accepted by `Client`s, only their password matters.
+const LOGIN: &str = "ephyr";
+
+/// Timeout of requests to remote `Client`s.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `Restream` of a remote `Client`.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct RemoteRestream {
+    /// ID of this `Restream` on the remote `Client`.
+    pub id: RestreamId,
+
+    /// Key of this `Restream`.
+    pub key: String,
+
+    /// Optional label of this `Restream`.
+    pub label: Option<String>,
+
+    /// `Output`s of this `Restream`.
+    pub outputs: Vec<RemoteOutput>,
+}
+
+/// `Output` of a `Restream` on a remote `Client`.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct RemoteOutput {
+    /// ID of this `Output` on the remote `Client`.
+    pub id: OutputId,
+
+    /// Destination URL this `Output` re-streams onto.
+    pub dst: String,
+
+    /// Optional label of this `Output`.
+    pub label: Option<String>,
+
+    /// Indicator whether this `Output` is enabled.
+    pub enabled: bool,
+
+    /// `Status` of this `Output`.
+    ///
+    /// `null` if the remote `Client` reports a status unknown to this server.
+    pub status: Option<Status>,
+}
+
+/// Fetches all the `Restream`s of the remote `Client` with the given `id`.
+///
+/// # Errors
+///
+/// If there is no such `Client` in the given [`State`], or the request to
+/// it fails.
+pub async fn restreams(
+    state: &State,
+    id: &ClientId,
+) -> anyhow::Result<Vec<RemoteRestream>> {
+    type Vars = <RemoteRestreamsQuery as GraphQLQuery>::Variables;
+
+    let data = request::<RemoteRestreamsQuery>(state, id, Vars {}).await?;
+    data.all_restreams
+        .into_iter()
+        .map(|r| {
+            Ok(RemoteRestream {
+                id: parse_id(&r.id)?.into(),
+                key: r.key,
+                label: r.label,
+                outputs: r
+                    .outputs
+                    .into_iter()
+                    .map(|o| {
+                        Ok(RemoteOutput {
+                            id: parse_id(&o.id)?.into(),
+                            dst: o.dst,
+                            label: o.label,
+                            enabled: o.enabled,
+                            status: remote_status(o.status),
+                        })
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            })
+        })
+        .collect()
+}
+
+/// Enables or disables the specified `Output` on the remote `Client` with
+/// the given `id`.
+///
+/// Returns the result of the mutation reported by the remote `Client` as
+/// is.
+///
+/// # Errors
+///
+/// If there is no such `Client` in the given [`State`], or the request to
+/// it fails.
+pub async fn toggle_output(
+    state: &State,
+    id: &ClientId,
+    restream_id: RestreamId,
+    output_id: OutputId,
+    enabled: bool,
+) -> anyhow::Result<Option<bool>> {
+    log::info!(
+        "{} Output {} of Restream {} on client {}",
+        if enabled { "Enabling" } else { "Disabling" },
+        output_id,
+        restream_id,
+        id,
+    );
+    let (restream_id, output_id) =
+        (restream_id.to_string(), output_id.to_string());
+    Ok(if enabled {
+        request::<RemoteEnableOutputMutation>(
+            state,
+            id,
+            queries::remote_enable_output_mutation::Variables {
+                restream_id,
+                id: output_id,
+            },
+        )
+        .await?
+        .enable_output
+    } else {
+        request::<RemoteDisableOutputMutation>(
+            state,
+            id,
+            queries::remote_disable_output_mutation::Variables {
+                restream_id,
+                id: output_id,
+            },
+        )
+        .await?
+        .disable_output
+    })
+}
+
+/// Performs the given [`GraphQLQuery`] against the client API of the remote
+/// `Client` with the given `id`, authorizing with its stored password.
+///
+/// # Errors
+///
+/// If there is no such `Client` in the given [`State`], the request fails,
+/// or the remote `Client` responds with errors.
+async fn request<Q>(
+    state: &State,
+    id: &ClientId,
+    vars: Q::Variables,
+) -> anyhow::Result<Q::ResponseData>
+where
+    Q: GraphQLQuery,
+    Q::Variables: Serialize,
+    Q::ResponseData: DeserializeOwned,
+{
+    let client = state
+        .clients
+        .lock_ref()
+        .iter()
+        .find(|c| c.id == *id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Client '{}' doesn't exist", id))?;
+
+    let mut req = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()?
+        .post(format!("{id}api"))
+        .json(&Q::build_query(vars));
+    if let Some(pass) = client.password.as_ref() {
+        req = req.basic_auth(LOGIN, Some(pass));
+    }
+    let resp = req.send().await?.error_for_status()?;
+
+    let resp: Response<Q::ResponseData> = resp.json().await?;
+    if let Some(errs) = resp.errors.filter(|e| !e.is_empty()) {
+        return Err(anyhow!(
+            "Client '{}' responded with errors: {}",
+            id,
+            errs.iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+        ));
+    }
+    resp.data
+        .ok_or_else(|| anyhow!("Client '{}' responded with no data", id))
+}
+
+/// Parses an ID reported by a remote `Client`.
+fn parse_id(id: &str) -> anyhow::Result<Uuid> {
+    id.parse()
+        .map_err(|e| anyhow!("Invalid ID '{}' reported by client: {}", id, e))
+}
+
+/// Converts the given [`remote_restreams_query::Status`] into a [`Status`].
+///
+/// Returns [`None`] for unknown statuses (reported by newer clients).
+fn remote_status(status: remote_restreams_query::Status) -> Option<Status> {
+    use remote_restreams_query::Status as S;
+
+    Some(match status {
+        S::ONLINE => Status::Online,
+        S::OFFLINE => Status::Offline,
+        S::INITIALIZING => Status::Initializing,
+        S::UNSTABLE => Status::Unstable,
+        S::Other(_) => return None,
+    })
+}
+
+/// [GraphQL] queries to the client API of remote `Client`s.
+///
+/// [GraphQL]: https://graphql.com
+mod queries {
+    // This is required because of `graphql_client` crate generate module for
+    // graphql query without documentation and that causes warning messages
+    #![allow(missing_docs)]
+
+    use graphql_client::GraphQLQuery;
+
+    // Custom scalars of the client API are received as is.
+    type Label = String;
+    type OutputDstUrl = String;
+    type OutputId = String;
+    type RestreamId = String;
+    type RestreamKey = String;
+
+    /// GraphQL query for getting all `Restream`s of a remote client.
+    #[derive(GraphQLQuery)]
+    #[graphql(
+        schema_path = "client.graphql.schema.json",
+        query_path = "src/api/graphql/queries/remote_restreams.graphql",
+        response_derives = "Debug"
+    )]
+    #[derive(Debug)]
+    pub struct RemoteRestreamsQuery;
+
+    /// GraphQL mutation for enabling an `Output` of a remote client.
+    #[derive(GraphQLQuery)]
+    #[graphql(
+        schema_path = "client.graphql.schema.json",
+        query_path = "src/api/graphql/queries/remote_enable_output.graphql",
+        response_derives = "Debug"
+    )]
+    #[derive(Debug)]
+    pub struct RemoteEnableOutputMutation;
+
+    /// GraphQL mutation for disabling an `Output` of a remote client.
+    #[derive(GraphQLQuery)]
+    #[graphql(
+        schema_path = "client.graphql.schema.json",
+        query_path = "src/api/graphql/queries/remote_disable_output.graphql",
+        response_derives = "Debug"
+    )]
+    #[derive(Debug)]
+    pub struct RemoteDisableOutputMutation;
+}
+
+#[cfg(test)]
+mod federation_spec {
+    use actix_web::test::TestRequest;
+    use graphql_client::{GraphQLQuery, Response};
+    use juniper::http::GraphQLRequest;
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::json;
+
+    use crate::{
+        api::graphql::{client, Context},
+        State,
+    };
+
+    use super::queries::{
+        remote_disable_output_mutation, remote_enable_output_mutation,
+        remote_restreams_query, RemoteDisableOutputMutation,
+        RemoteEnableOutputMutation, RemoteRestreamsQuery,
+    };
+
+    /// Executes the given [`GraphQLQuery`] against the current client API of
+    /// this server, the same way a federating dashboard does, so the
+    /// [`GraphQLQuery`] is checked against the actual schema rather than the
+    /// exported one it has been generated from.
+    async fn execute<Q>(state: &State, vars: Q::Variables) -> Q::ResponseData
+    where
+        Q: GraphQLQuery,
+        Q::Variables: Serialize,
+        Q::ResponseData: DeserializeOwned,
+    {
+        let req: GraphQLRequest = serde_json::from_value(
+            serde_json::to_value(Q::build_query(vars)).unwrap(),
+        )
+        .unwrap();
+        let context = Context::new(
+            TestRequest::default()
+                .app_data(state.clone())
+                .to_http_request(),
+        );
+        let resp = req.execute(&client::schema(), &context).await;
+
+        let resp: Response<Q::ResponseData> =
+            serde_json::from_value(serde_json::to_value(&resp).unwrap())
+                .unwrap();
+        if let Some(errs) = resp.errors.filter(|e| !e.is_empty()) {
+            panic!("Query is rejected: {errs:?}");
+        }
+        resp.data.unwrap()
+    }
+
+    #[tokio::test]
+    async fn queries_match_client_api() {
+        let state = State::default();
+        state
+            .add_restream(
+                serde_json::from_value(json!({
+                    "key": "origin",
+                    "label": "Origin",
+                    "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                    "outputs": [{"dst": "rtmp://example.com/live/stream"}],
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+
+        let data = execute::<RemoteRestreamsQuery>(
+            &state,
+            remote_restreams_query::Variables {},
+        )
+        .await;
+        assert_eq!(data.all_restreams.len(), 1);
+        let restream = &data.all_restreams[0];
+        assert_eq!(restream.key, "origin");
+        assert_eq!(restream.label.as_deref(), Some("Origin"));
+        assert_eq!(restream.outputs.len(), 1);
+        let output = &restream.outputs[0];
+        assert_eq!(output.dst, "rtmp://example.com/live/stream");
+        assert!(!output.enabled);
+
+        let data = execute::<RemoteEnableOutputMutation>(
+            &state,
+            remote_enable_output_mutation::Variables {
+                restream_id: restream.id.clone(),
+                id: output.id.clone(),
+            },
+        )
+        .await;
+        assert_eq!(data.enable_output, Some(true));
+
+        let data = execute::<RemoteDisableOutputMutation>(
+            &state,
+            remote_disable_output_mutation::Variables {
+                restream_id: restream.id.clone(),
+                id: output.id.clone(),
+            },
+        )
+        .await;
+        assert_eq!(data.disable_output, Some(true));
+    }
+}
@@ -2,18 +2,41 @@
 //!
 //! [FFmpeg]: https://ffmpeg.org
 
+mod audio_meter;
+mod binary;
+mod command;
 mod copy_restreamer;
+#[cfg(test)]
+mod fake;
+mod fallback_restreamer;
+mod hw_accel;
+mod local_port;
+mod mixin_pipe;
 mod mixing_restreamer;
+mod output_test;
+mod playlist_restreamer;
+mod progress;
 mod restreamer;
 mod restreamer_kind;
 mod restreamers_pool;
+mod traffic;
 mod transcoding_restreamer;
 
 pub use self::{
+    binary::{Binary, RestartWindow},
+    command::CommandFactory,
     copy_restreamer::CopyRestreamer,
+    fallback_restreamer::FallbackRestreamer,
+    hw_accel::HwAccel,
+    local_port::LocalPort,
+    mixin_pipe::{set_mixin_pipe_kind, MixinPipeKind},
     mixing_restreamer::{Mixin, MixingRestreamer},
+    output_test::{test_output, OutputTestResult},
+    playlist_restreamer::PlaylistRestreamer,
+    progress::set_stall_timeout,
     restreamer::Restreamer,
     restreamer_kind::RestreamerKind,
     restreamers_pool::RestreamersPool,
+    traffic::run_traffic_accounting,
     transcoding_restreamer::TranscodingRestreamer,
 };
@@ -0,0 +1,98 @@
+//! Measuring of live audio levels inside [FFmpeg] filter graphs.
+//!
+//! [FFmpeg] prints the measured levels as `astats` filter metadata into a
+//! local UDP socket, which is read and reported into the [`State`].
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{future::Future, net::Ipv4Addr};
+
+use ephyr_log::log;
+use futures::{future, pin_mut};
+use tokio::net::UdpSocket;
+use uuid::Uuid;
+
+use crate::state::{AudioLevel, State};
+
+/// Returns [FFmpeg] filters measuring audio levels of a filter chain they're
+/// inserted into, and sending them to the UDP `port` on `127.0.0.1`.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[must_use]
+pub(crate) fn filters(port: u16) -> String {
+    format!(
+        "astats=metadata=1\
+               :reset=1\
+               :measure_perchannel=none\
+               :measure_overall=Peak_level+RMS_level,\
+         ametadata=mode=print\
+                  :file=udp\\\\\\://127.0.0.1\\\\\\:{port}"
+    )
+}
+
+/// Awaits the given `running` [FFmpeg] process, meanwhile reporting audio
+/// levels measured by its [`filters()`] into the [`State`].
+///
+/// `meters` are pairs of an ID of the measured element in the [`State`] and
+/// the UDP port its [`filters()`] send levels to. Once the process finishes,
+/// their levels are removed from the [`State`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) async fn measure_while<F: Future>(
+    running: F,
+    meters: &[(Uuid, u16)],
+    state: &State,
+) -> F::Output {
+    // Sockets should be bound before FFmpeg starts sending levels.
+    let measuring = future::join_all(meters.iter().filter_map(|(id, port)| {
+        let socket = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, *port))
+            .and_then(|s| s.set_nonblocking(true).map(|()| s))
+            .and_then(UdpSocket::from_std)
+            .map_err(|e| {
+                log::error!("Failed to bind audio meter of {}: {}", id, e);
+            })
+            .ok()?;
+        Some(report(*id, socket, state))
+    }));
+    pin_mut!(running, measuring);
+
+    let out = match future::select(running, measuring).await {
+        future::Either::Left((out, _)) => out,
+        future::Either::Right((_, running)) => running.await,
+    };
+
+    let mut levels = state.audio_levels.lock_mut();
+    for (id, _) in meters {
+        let _ = levels.remove(id);
+    }
+    out
+}
+
+/// Reads [FFmpeg] `astats` metadata from the given `socket`, and reports it as
+/// an [`AudioLevel`] of the element with the given `id` into the [`State`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+async fn report(id: Uuid, socket: UdpSocket, state: &State) {
+    let mut buf = vec![0; 4096];
+    let mut pending = String::new();
+    let mut level = AudioLevel::default();
+    loop {
+        let n = match socket.recv(&mut buf).await {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("Failed to read audio levels of {}: {}", id, e);
+                return;
+            }
+        };
+        // Metadata lines may be split between datagrams.
+        pending.push_str(&String::from_utf8_lossy(&buf[..n]));
+        let mut changed = false;
+        while let Some(pos) = pending.find('\n') {
+            changed |= level.apply_metadata(&pending[..pos]);
+            drop(pending.drain(..=pos));
+        }
+        if changed {
+            let _ = state.audio_levels.lock_mut().insert(id, level);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! [FFmpeg] binary used for spawning re-streaming processes, tracking its
+//! in-place replacements.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt as _;
+use std::{
+    fmt,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use chrono::{NaiveTime, Utc};
+use ephyr_log::log;
+use once_cell::sync::OnceCell;
+use tokio::{fs, process::Command, time};
+
+use crate::{
+    ffmpeg::{CommandFactory, RestreamerKind},
+    process::{self, Pid},
+};
+
+/// Pause between restarts of [FFmpeg] processes onto a new binary, so they're
+/// restarted progressively rather than all at once.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const RESTART_PACE: Duration = Duration::from_secs(5);
+
+/// Global instance of the [FFmpeg] [`Binary`] used by this application.
+///
+/// [FFmpeg]: https://ffmpeg.org
+static BINARY: OnceCell<Binary> = OnceCell::new();
+
+/// [FFmpeg] binary used for spawning re-streaming processes.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Debug)]
+pub struct Binary {
+    /// Path to the [FFmpeg] binary as configured, possibly being a symlink.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    path: PathBuf,
+
+    /// Actual information about the resolved [FFmpeg] binary.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    actual: Mutex<BinaryInfo>,
+}
+
+impl CommandFactory for Binary {
+    #[inline]
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(self.path());
+        process::setup(&mut cmd);
+        cmd
+    }
+}
+
+/// Information about a resolved [FFmpeg] binary.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BinaryInfo {
+    /// Canonical path of the [FFmpeg] binary.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    path: PathBuf,
+
+    /// Device ID of the [FFmpeg] binary file.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    dev: u64,
+
+    /// Inode of the [FFmpeg] binary file.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    ino: u64,
+
+    /// Version reported by the [FFmpeg] binary.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    version: Option<String>,
+}
+
+impl Binary {
+    /// Resolves the [FFmpeg] binary by the given `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the binary cannot be resolved.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub async fn try_new<P: Into<PathBuf>>(path: P) -> anyhow::Result<Self> {
+        let path = path.into();
+        let actual = BinaryInfo::resolve(&path, None).await?;
+        Ok(Self {
+            path,
+            actual: Mutex::new(actual),
+        })
+    }
+
+    /// Returns the global instance of [`Binary`].
+    ///
+    /// # Panics
+    ///
+    /// If the global instance hasn't been initialized yet via
+    /// [`Binary::set_global()`].
+    #[inline]
+    #[must_use]
+    pub fn global() -> &'static Binary {
+        BINARY.get().expect("ffmpeg::Binary is not initialized")
+    }
+
+    /// Sets the global instance of [`Binary`].
+    ///
+    /// # Errors
+    ///
+    /// If the global instance has been set already.
+    #[inline]
+    pub fn set_global(self) -> anyhow::Result<()> {
+        BINARY
+            .set(self)
+            .map_err(|_| anyhow!("ffmpeg::Binary has been initialized already"))
+    }
+
+    /// Returns the actual canonical path of this [`Binary`] to spawn new
+    /// processes with.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.actual.lock().unwrap().path.clone()
+    }
+
+    /// Returns the version reported by this [`Binary`], if it could be
+    /// detected.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    #[must_use]
+    pub fn version(&self) -> Option<String> {
+        self.actual.lock().unwrap().version.clone()
+    }
+
+    /// Spawns a background job re-resolving this [`Binary`] every `interval`
+    /// to detect its replacement.
+    ///
+    /// If `window` is specified, then the processes still running on a
+    /// replaced binary are progressively restarted onto the new one during
+    /// this [`RestartWindow`]. Otherwise, they switch onto the new binary on
+    /// their next natural restart only.
+    pub fn watch(
+        &'static self,
+        interval: Duration,
+        window: Option<RestartWindow>,
+    ) {
+        drop(tokio::spawn(async move {
+            loop {
+                time::sleep(interval).await;
+
+                let prev = self.actual.lock().unwrap().clone();
+                match BinaryInfo::resolve(&self.path, Some(prev)).await {
+                    Ok(new) => {
+                        let mut actual = self.actual.lock().unwrap();
+                        if new != *actual {
+                            log::info!(
+                                "FFmpeg binary has been replaced: {} ({}) -> \
+                                 {} ({})",
+                                actual.path.display(),
+                                actual.version.as_deref().unwrap_or("unknown"),
+                                new.path.display(),
+                                new.version.as_deref().unwrap_or("unknown"),
+                            );
+                            *actual = new;
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to resolve FFmpeg binary: {}", e);
+                        continue;
+                    }
+                }
+
+                if let Some(window) = window {
+                    self.restart_stale(window).await;
+                }
+            }
+        }));
+    }
+
+    /// Progressively restarts (stopping gracefully) the running [FFmpeg]
+    /// processes spawned from a replaced binary, while the given
+    /// [`RestartWindow`] lasts.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    async fn restart_stale(&self, window: RestartWindow) {
+        for (id, pid) in RestreamerKind::running_processes() {
+            if !window.contains(Utc::now().time()) {
+                return;
+            }
+            if !self.is_stale(pid).await {
+                continue;
+            }
+            log::info!(
+                "Restarting FFmpeg process {} of {} onto the new binary",
+                pid,
+                id,
+            );
+            let _ = process::stop(pid);
+            time::sleep(RESTART_PACE).await;
+        }
+    }
+
+    /// Indicates whether the process with the given `pid` runs on a binary
+    /// different from the actual one of this [`Binary`].
+    async fn is_stale(&self, pid: Pid) -> bool {
+        let (dev, ino) = {
+            let actual = self.actual.lock().unwrap();
+            (actual.dev, actual.ino)
+        };
+        // Executable of a running process remains accessible via `/proc` even
+        // if it has been replaced or removed.
+        fs::metadata(format!("/proc/{pid}/exe"))
+            .await
+            .map_or(false, |m| file_id(&m) != (dev, ino))
+    }
+}
+
+impl BinaryInfo {
+    /// Resolves the [FFmpeg] binary by the given `path` along with its
+    /// version.
+    ///
+    /// Version is taken from the `prev` [`BinaryInfo`] if the binary is still
+    /// the same, so is detected only once per binary.
+    ///
+    /// # Errors
+    ///
+    /// If the binary cannot be resolved.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    async fn resolve(path: &Path, prev: Option<Self>) -> anyhow::Result<Self> {
+        let path = fs::canonicalize(path).await?;
+        let meta = fs::metadata(&path).await?;
+        if let Some(prev) =
+            prev.filter(|p| (p.dev, p.ino) == file_id(&meta) && p.path == path)
+        {
+            return Ok(prev);
+        }
+        let version = Command::new(&path)
+            .arg("-version")
+            .output()
+            .await
+            .map_err(|e| {
+                log::warn!("Failed to detect FFmpeg version: {}", e);
+            })
+            .ok()
+            .and_then(|out| {
+                parse_version(&String::from_utf8_lossy(&out.stdout))
+            });
+        let (dev, ino) = file_id(&meta);
+        Ok(Self {
+            dev,
+            ino,
+            path,
+            version,
+        })
+    }
+}
+
+/// Returns the device ID and the inode of the file with the given
+/// [`Metadata`], identifying it on the filesystem.
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> (u64, u64) {
+    (meta.dev(), meta.ino())
+}
+
+/// Returns the size and the modification time (in nanoseconds) of the file
+/// with the given [`Metadata`], as there are no inodes to identify it with.
+#[cfg(not(unix))]
+fn file_id(meta: &Metadata) -> (u64, u64) {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX));
+    (meta.len(), modified)
+}
+
+/// Parses [FFmpeg] version out of its `-version` output (like
+/// `ffmpeg version 6.0 Copyright (c) ...`).
+///
+/// [FFmpeg]: https://ffmpeg.org
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .next()?
+        .split_whitespace()
+        .skip_while(|w| *w != "version")
+        .nth(1)
+        .map(ToOwned::to_owned)
+}
+
+/// Daily time window (in UTC) during which [FFmpeg] processes may be restarted
+/// onto a replaced binary.
+///
+/// Parsed from `HH:MM-HH:MM` format. May wrap around midnight (like
+/// `23:00-01:00`).
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RestartWindow {
+    /// Time this [`RestartWindow`] starts at.
+    start: NaiveTime,
+
+    /// Time this [`RestartWindow`] ends at.
+    end: NaiveTime,
+}
+
+impl RestartWindow {
+    /// Indicates whether the given time is inside this [`RestartWindow`].
+    #[must_use]
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl FromStr for RestartWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Expected `HH:MM-HH:MM` format"))?;
+        Ok(Self {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M")?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M")?,
+        })
+    }
+}
+
+impl fmt::Display for RestartWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
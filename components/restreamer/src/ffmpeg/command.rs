@@ -0,0 +1,22 @@
+//! Factory of [FFmpeg] [`Command`]s to spawn re-streaming processes with.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::fmt;
+
+use tokio::process::Command;
+
+/// Factory of [FFmpeg] [`Command`]s to spawn re-streaming processes with.
+///
+/// Allows to substitute the actual [FFmpeg] binary with a fake one, so the
+/// [`Restreamer`] and [`RestreamersPool`] logic may be tested without it.
+///
+/// [`Restreamer`]: crate::ffmpeg::Restreamer
+/// [`RestreamersPool`]: crate::ffmpeg::RestreamersPool
+/// [FFmpeg]: https://ffmpeg.org
+pub trait CommandFactory: fmt::Debug + Send + Sync {
+    /// Creates a new [`Command`] to spawn a single [FFmpeg] process with.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    fn command(&self) -> Command;
+}
@@ -10,7 +10,13 @@ use tokio::{io, process::Command};
 use url::Url;
 use uuid::Uuid;
 
-use crate::dvr;
+use crate::{
+    dvr, m3u8,
+    state::{
+        FfmpegLogLevel, HlsVariantSelector, InputTuning, OutputIcecast,
+        OutputLoudnorm, OutputReconnect, OutputTls,
+    },
+};
 
 /// Kind of a [FFmpeg] re-streaming process that re-streams a live stream from
 /// one URL endpoint to another one "as is", without performing any live stream
@@ -28,8 +34,46 @@ pub struct CopyRestreamer {
     /// [`Url`] to pull a live stream from.
     pub from_url: Url,
 
+    /// [`InputTuning`] options to pull a live stream from the
+    /// [`CopyRestreamer::from_url`] with.
+    pub tuning: Option<InputTuning>,
+
+    /// [`HlsVariantSelector`] to choose a variant stream with, if the
+    /// [`CopyRestreamer::from_url`] is a [HLS] master playlist.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    pub hls_variant: Option<HlsVariantSelector>,
+
     /// [`Url`] to publish the pulled live stream onto.
     pub to_url: Url,
+
+    /// [`OutputTls`] options to publish onto an encrypted
+    /// [`CopyRestreamer::to_url`] with.
+    pub tls: Option<OutputTls>,
+
+    /// [`OutputReconnect`] options to survive short network failures of the
+    /// [`CopyRestreamer::to_url`] with.
+    pub reconnect: Option<OutputReconnect>,
+
+    /// [`OutputIcecast`] metadata to describe the stream with on an
+    /// `icecast://` [`CopyRestreamer::to_url`].
+    pub icecast: Option<OutputIcecast>,
+
+    /// [`OutputLoudnorm`] options to normalize loudness of the audio with,
+    /// re-encoding it rather than copying "as is".
+    pub loudnorm: Option<OutputLoudnorm>,
+
+    /// [`FfmpegLogLevel`] to run [FFmpeg] process with, overriding the
+    /// server's one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub log_level: Option<FfmpegLogLevel>,
+
+    /// Extra arguments to pass to [FFmpeg] right before the
+    /// [`CopyRestreamer::to_url`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub extra_args: Vec<String>,
 }
 
 impl CopyRestreamer {
@@ -39,7 +83,16 @@ impl CopyRestreamer {
     #[inline]
     #[must_use]
     pub fn needs_restart(&self, actual: &Self) -> bool {
-        self.from_url != actual.from_url || self.to_url != actual.to_url
+        self.from_url != actual.from_url
+            || self.tuning != actual.tuning
+            || self.hls_variant != actual.hls_variant
+            || self.to_url != actual.to_url
+            || self.tls != actual.tls
+            || self.reconnect != actual.reconnect
+            || self.icecast != actual.icecast
+            || self.loudnorm != actual.loudnorm
+            || self.log_level != actual.log_level
+            || self.extra_args != actual.extra_args
     }
 
     /// Properly setups the given [FFmpeg] [`Command`] for this
@@ -47,40 +100,96 @@ impl CopyRestreamer {
     ///
     /// # Errors
     ///
-    /// If the given [FFmpeg] [`Command`] fails to be setup.
+    /// If the given [FFmpeg] [`Command`] fails to be setup, or a variant
+    /// stream of a [HLS] master playlist fails to be resolved.
     ///
     /// [FFmpeg]: https://ffmpeg.org
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
     pub(crate) async fn setup_ffmpeg(
         &self,
         cmd: &mut Command,
     ) -> io::Result<()> {
+        if let Some(level) = self.log_level {
+            level.setup_ffmpeg(cmd);
+        }
+
+        if let Some(tuning) = self.tuning.as_ref() {
+            tuning.setup_ffmpeg(cmd);
+        }
+
         let _ = match self.from_url.scheme() {
             "http" | "https"
                 if Path::new(self.from_url.path()).extension()
                     == Some("m3u8".as_ref()) =>
             {
-                cmd.arg("-re")
+                let url = match self.hls_variant.as_ref() {
+                    Some(selector) => {
+                        m3u8::resolve_variant(&self.from_url, selector)
+                            .await
+                            .map_err(|e| {
+                                io::Error::new(
+                                    io::ErrorKind::Other,
+                                    format!(
+                                        "Failed to resolve HLS variant of \
+                                         {}: {}",
+                                        self.from_url, e,
+                                    ),
+                                )
+                            })?
+                    }
+                    None => self.from_url.clone(),
+                };
+                cmd.arg("-re").args(["-i", url.as_str()])
             }
 
-            "rtmp" | "rtmps" => cmd,
+            "rtmp" | "rtmps" => cmd.args(["-i", self.from_url.as_str()]),
+
+            "srt" => cmd.args(["-i", self.from_url.as_str()]),
 
             _ => unimplemented!(),
+        };
+
+        if self.to_url.scheme() == "rtmps" {
+            if let Some(tls) = self.tls.as_ref() {
+                tls.setup_ffmpeg(cmd);
+            }
         }
-        .args(["-i", self.from_url.as_str()]);
+        if let Some(reconnect) = self.reconnect.as_ref() {
+            reconnect.setup_ffmpeg(cmd, &self.to_url);
+        }
+        if self.to_url.scheme() == "icecast" {
+            if let Some(icecast) = self.icecast.as_ref() {
+                icecast.setup_ffmpeg(cmd);
+            }
+        }
+
+        if self.to_url.scheme() == "file" {
+            dvr::setup_segmenting(cmd, &self.to_url);
+        }
+
+        // Audio cannot be normalized without re-encoding it.
+        let copy_args: &[&str] = if let Some(loudnorm) = self.loudnorm {
+            let _ = cmd.args(["-af", &loudnorm.filters()]);
+            &["-c:v", "copy", "-c:a", "libfdk_aac"]
+        } else {
+            &["-c", "copy"]
+        };
 
         let _ = match self.to_url.scheme() {
             "file" => match Path::new(self.to_url.path())
                 .extension()
                 .and_then(OsStr::to_str)
             {
-                Some("flv") => cmd
-                    .args(["-c", "copy"])
+                Some("flv" | "mp4") => cmd
+                    .args(copy_args)
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 Some("wav") => cmd
                     .arg("-vn")
                     .args(["-acodec", "pcm_s16le"])
                     .args(["-ar", "48000"])
                     .args(["-ac", "2"])
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 Some("mp3") => cmd
                     .arg("-vn")
@@ -88,6 +197,7 @@ impl CopyRestreamer {
                     .args(["-b:a", "64k"])
                     .args(["-ar", "48000"])
                     .args(["-ac", "2"])
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 _ => unimplemented!(),
             },
@@ -95,16 +205,19 @@ impl CopyRestreamer {
                 .arg("-vn")
                 .args(["-acodec", "libmp3lame", "-b:a", "64k"])
                 .args(["-f", "mp3", "-content_type", "audio/mpeg"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             "rtmp" | "rtmps" => cmd
-                .args(["-c", "copy"])
+                .args(copy_args)
                 .args(["-f", "flv"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             "srt" => cmd
-                .args(["-c", "copy"])
+                .args(copy_args)
                 .args(["-strict", "-2", "-y", "-f", "mpegts"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             _ => unimplemented!(),
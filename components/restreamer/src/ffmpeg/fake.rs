@@ -0,0 +1,69 @@
+//! Fake [FFmpeg] allowing to test re-streaming processes management without
+//! the real one.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::{process::Command, time};
+
+use crate::ffmpeg::CommandFactory;
+
+/// Fake [FFmpeg] spawning a shell process, which ignores all the given
+/// arguments and runs until being terminated.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Debug, Default)]
+pub struct FakeFfmpeg {
+    /// Number of processes spawned with this [`FakeFfmpeg`].
+    spawns: AtomicUsize,
+}
+
+impl FakeFfmpeg {
+    /// Shell script run by the spawned processes.
+    ///
+    /// Sleeps in short steps, so exits gracefully on `SIGTERM` shortly.
+    const SCRIPT: &'static str = "trap 'exit 0' TERM; \
+                                  while :; do sleep 0.1; done";
+
+    /// Creates a new [`FakeFfmpeg`] living until the program ends, as
+    /// required by a [`RestreamersPool`].
+    ///
+    /// [`RestreamersPool`]: crate::ffmpeg::RestreamersPool
+    #[must_use]
+    pub fn leaked() -> &'static Self {
+        Box::leak(Box::default())
+    }
+
+    /// Returns number of processes spawned with this [`FakeFfmpeg`].
+    #[must_use]
+    pub fn spawns(&self) -> usize {
+        self.spawns.load(Ordering::SeqCst)
+    }
+}
+
+impl CommandFactory for FakeFfmpeg {
+    fn command(&self) -> Command {
+        let _ = self.spawns.fetch_add(1, Ordering::SeqCst);
+        let mut cmd = Command::new("sh");
+        let _ = cmd.args(["-c", Self::SCRIPT, "ffmpeg"]);
+        cmd
+    }
+}
+
+/// Awaits the given `condition` to become `true`, re-checking it
+/// periodically.
+///
+/// Returns `false` if it doesn't happen within 5 seconds.
+pub async fn eventually<F: FnMut() -> bool>(mut condition: F) -> bool {
+    for _ in 0..100 {
+        if condition() {
+            return true;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
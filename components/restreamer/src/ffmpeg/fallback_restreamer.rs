@@ -0,0 +1,144 @@
+//! Kind of a [FFmpeg] re-streaming process that broadcasts a fallback source
+//! (slate) of a `Restream` to an `Output` while its `Input` is offline.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio::process::Command;
+use url::Url;
+use uuid::Uuid;
+
+use crate::state::{FallbackKind, FfmpegLogLevel, OutputTls, RestreamFallback};
+
+/// [FFmpeg] `lavfi` source generating a silent audio to accompany sources
+/// without their own one.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const SILENCE: &str = "anullsrc=channel_layout=stereo:sample_rate=48000";
+
+/// Kind of a [FFmpeg] re-streaming process that broadcasts a fallback source
+/// (slate) of a `Restream` to an `Output` while its `Input` is offline.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FallbackRestreamer {
+    /// ID of an element in a [`State`] this [`FallbackRestreamer`] process is
+    /// related to.
+    ///
+    /// [`State`]: crate::state::State
+    pub id: Uuid,
+
+    /// [`RestreamFallback`] source to be broadcast.
+    pub fallback: RestreamFallback,
+
+    /// Path of the [`RestreamFallback::file`] resolved inside the server's
+    /// media directory.
+    pub file: Option<PathBuf>,
+
+    /// [`Url`] to publish the fallback live stream onto.
+    pub to_url: Url,
+
+    /// [`OutputTls`] options to publish onto an encrypted
+    /// [`FallbackRestreamer::to_url`] with.
+    pub tls: Option<OutputTls>,
+
+    /// [`FfmpegLogLevel`] to run [FFmpeg] process with, overriding the
+    /// server's one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub log_level: Option<FfmpegLogLevel>,
+
+    /// Extra arguments to pass to [FFmpeg] right before the
+    /// [`FallbackRestreamer::to_url`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub extra_args: Vec<String>,
+}
+
+impl FallbackRestreamer {
+    /// Checks whether this [`FallbackRestreamer`] process must be restarted,
+    /// as cannot apply the new `actual` params on itself correctly, without
+    /// interruptions.
+    #[inline]
+    #[must_use]
+    pub fn needs_restart(&self, actual: &Self) -> bool {
+        self != actual
+    }
+
+    /// Returns [`Url`] of the source broadcast by this [`FallbackRestreamer`].
+    ///
+    /// Generated sources are represented with a `lavfi:` [`Url`].
+    #[must_use]
+    pub fn src_url(&self) -> Url {
+        self.file
+            .as_deref()
+            .filter(|_| self.fallback.kind.requires_file())
+            .and_then(|f| Url::from_file_path(f).ok())
+            .unwrap_or_else(|| Url::parse("lavfi:smptehdbars").unwrap())
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] for this
+    /// [`FallbackRestreamer`] before running it.
+    ///
+    /// # Errors
+    ///
+    /// If the [`FallbackRestreamer::to_url`] has a scheme unsupported for
+    /// broadcasting a fallback slate (normally, it's never constructed with
+    /// such one).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) -> io::Result<()> {
+        if let Some(level) = self.log_level {
+            level.setup_ffmpeg(cmd);
+        }
+
+        let file = self.file.as_deref().unwrap_or_else(|| Path::new(""));
+        let _ = match self.fallback.kind {
+            FallbackKind::Image => cmd
+                .args(["-re", "-loop", "1", "-framerate", "30"])
+                .arg("-i")
+                .arg(file)
+                .args(["-f", "lavfi", "-i", SILENCE]),
+            FallbackKind::File => {
+                cmd.args(["-re", "-stream_loop", "-1"]).arg("-i").arg(file)
+            }
+            FallbackKind::ColorBars => cmd
+                .args(["-re", "-f", "lavfi"])
+                .args(["-i", "smptehdbars=size=1280x720:rate=30"])
+                .args(["-f", "lavfi", "-i", SILENCE]),
+        };
+
+        // Slate is always re-encoded, as it should match the usual live
+        // stream parameters expected by downstream platforms.
+        let _ = cmd
+            .args(["-c:v", "libx264", "-preset", "veryfast"])
+            .args(["-pix_fmt", "yuv420p", "-r", "30", "-g", "60"])
+            .args(["-c:a", "libfdk_aac", "-b:a", "128k"]);
+
+        if self.to_url.scheme() == "rtmps" {
+            if let Some(tls) = self.tls.as_ref() {
+                tls.setup_ffmpeg(cmd);
+            }
+        }
+
+        let format = match self.to_url.scheme() {
+            "rtmp" | "rtmps" => "flv",
+            "srt" => "mpegts",
+            scheme => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Fallback cannot be broadcast onto `{scheme}`"),
+                ))
+            }
+        };
+        let _ = cmd
+            .args(["-f", format])
+            .args(&self.extra_args)
+            .arg(self.to_url.as_str());
+        Ok(())
+    }
+}
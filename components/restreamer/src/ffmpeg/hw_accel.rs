@@ -0,0 +1,178 @@
+//! Hardware acceleration of [FFmpeg] video encoding.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use tokio::process::Command;
+
+use crate::ffmpeg::CommandFactory;
+
+/// Default [VAAPI] render device.
+///
+/// [VAAPI]: https://trac.ffmpeg.org/wiki/Hardware/VAAPI
+const DEFAULT_VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+/// Device file of the first [NVIDIA] GPU, indicating that [NVENC] is usable.
+///
+/// [NVENC]: https://trac.ffmpeg.org/wiki/HWAccelIntro#NVENC
+/// [NVIDIA]: https://www.nvidia.com
+const NVIDIA_DEVICE: &str = "/dev/nvidia0";
+
+/// Hardware acceleration to be used by [FFmpeg] for encoding H.264 video.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HwAccel {
+    /// No hardware acceleration, so software encoders are used.
+    None,
+
+    /// [NVENC] encoder of [NVIDIA] GPUs.
+    ///
+    /// [NVENC]: https://trac.ffmpeg.org/wiki/HWAccelIntro#NVENC
+    /// [NVIDIA]: https://www.nvidia.com
+    Nvenc,
+
+    /// [VAAPI] encoder using the render device at the given path.
+    ///
+    /// [VAAPI]: https://trac.ffmpeg.org/wiki/Hardware/VAAPI
+    Vaapi(PathBuf),
+}
+
+impl HwAccel {
+    /// Detects the [`HwAccel`] available on this machine by inspecting
+    /// `ffmpeg -encoders` output of the given [FFmpeg] binary and the present
+    /// devices.
+    ///
+    /// Falls back to [`HwAccel::None`] if nothing is detected.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub async fn detect(ffmpeg: &dyn CommandFactory) -> Self {
+        let out = match ffmpeg
+            .command()
+            .args(["-hide_banner", "-encoders"])
+            .output()
+            .await
+        {
+            Ok(out) => String::from_utf8_lossy(&out.stdout).into_owned(),
+            Err(e) => {
+                log::warn!("Failed to detect FFmpeg encoders: {}", e);
+                return Self::None;
+            }
+        };
+        let has_encoder =
+            |name: &str| out.split_whitespace().any(|word| word == name);
+
+        let accel =
+            if has_encoder("h264_nvenc") && Path::new(NVIDIA_DEVICE).exists() {
+                Self::Nvenc
+            } else if has_encoder("h264_vaapi")
+                && Path::new(DEFAULT_VAAPI_DEVICE).exists()
+            {
+                Self::Vaapi(DEFAULT_VAAPI_DEVICE.into())
+            } else {
+                Self::None
+            };
+        log::info!("Using FFmpeg hardware acceleration: {}", accel);
+        accel
+    }
+
+    /// Returns the hardware H.264 encoder of this [`HwAccel`], if any.
+    #[must_use]
+    pub fn h264_encoder(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Nvenc => Some("h264_nvenc"),
+            Self::Vaapi(_) => Some("h264_vaapi"),
+        }
+    }
+
+    /// Converts the given H.264 `profile` of a software encoder into the one
+    /// supported by the encoder of this [`HwAccel`].
+    #[must_use]
+    pub fn h264_profile<'p>(&self, profile: &'p str) -> &'p str {
+        match (self, profile) {
+            (Self::Vaapi(_), "baseline") => "constrained_baseline",
+            _ => profile,
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] for this [`HwAccel`]
+    /// before specifying its inputs.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg_device(&self, cmd: &mut Command) {
+        if let Self::Vaapi(device) = self {
+            let _ = cmd.arg("-vaapi_device").arg(device);
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] for this [`HwAccel`]
+    /// before specifying its video encoder, uploading decoded frames to the
+    /// hardware device, if required.
+    ///
+    /// The given `filter` (if any) is applied to the decoded frames before
+    /// uploading them.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg_filter(
+        &self,
+        cmd: &mut Command,
+        filter: Option<&str>,
+    ) {
+        let upload = match self {
+            Self::Vaapi(_) => Some("format=nv12,hwupload"),
+            Self::None | Self::Nvenc => None,
+        };
+        let filters = match (filter, upload) {
+            (Some(f), Some(u)) => format!("{f},{u}"),
+            (Some(f), None) | (None, Some(f)) => f.to_owned(),
+            (None, None) => return,
+        };
+        let _ = cmd.arg("-vf").arg(filters);
+    }
+}
+
+impl Default for HwAccel {
+    #[inline]
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl FromStr for HwAccel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.split_once(':') {
+            None if s == "none" => Self::None,
+            None if s == "nvenc" => Self::Nvenc,
+            None if s == "vaapi" => Self::Vaapi(DEFAULT_VAAPI_DEVICE.into()),
+            Some(("vaapi", device)) if !device.is_empty() => {
+                Self::Vaapi(device.into())
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Expected `none`, `nvenc`, `vaapi` or \
+                     `vaapi:<device>` value",
+                ))
+            }
+        })
+    }
+}
+
+impl fmt::Display for HwAccel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Nvenc => write!(f, "nvenc"),
+            Self::Vaapi(device) => write!(f, "vaapi:{}", device.display()),
+        }
+    }
+}
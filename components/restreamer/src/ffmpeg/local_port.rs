@@ -0,0 +1,180 @@
+//! Local ports allocated for [ZeroMQ] listeners and audio meters of [FFmpeg]
+//! processes.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+//! [ZeroMQ]: https://zeromq.org
+
+use std::{
+    collections::HashSet,
+    fmt,
+    net::{Ipv4Addr, TcpListener, UdpSocket},
+    ops::{Deref, RangeInclusive},
+    sync::{Arc, Mutex},
+};
+
+use ephyr_log::log;
+use once_cell::sync::Lazy;
+
+/// Range of ports to allocate [`LocalPort`]s from.
+const RANGE: RangeInclusive<u16> = 20000..=65535;
+
+/// Registry of the allocated [`LocalPort`]s.
+static PORTS: Lazy<Mutex<Registry>> = Lazy::new(|| {
+    Mutex::new(Registry {
+        in_use: HashSet::new(),
+        next: *RANGE.start(),
+    })
+});
+
+/// Registry of the allocated [`LocalPort`]s.
+#[derive(Debug)]
+struct Registry {
+    /// Ports being in use by the alive [`LocalPort`]s.
+    in_use: HashSet<u16>,
+
+    /// Port to start looking for a free one from on the next allocation.
+    next: u16,
+}
+
+/// Port on the `127.0.0.1` interface allocated exclusively for an [FFmpeg]
+/// process, being usable both for TCP ([ZeroMQ]) and UDP listeners.
+///
+/// The port is released once all the clones of its [`LocalPort`] are
+/// dropped.
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [ZeroMQ]: https://zeromq.org
+#[derive(Clone, Debug)]
+pub struct LocalPort(Arc<Lease>);
+
+impl LocalPort {
+    /// Allocates a new [`LocalPort`], which is neither used by other
+    /// [`LocalPort`]s, nor by other processes on the host at the moment.
+    ///
+    /// Availability is confirmed by binding test TCP and UDP sockets to the
+    /// port. If no port is available at all, then a port being used by
+    /// nothing known is returned, as the best effort.
+    ///
+    /// # Panics
+    ///
+    /// If the registry lock is poisoned.
+    #[must_use]
+    pub fn allocate() -> Self {
+        let mut ports = PORTS.lock().unwrap();
+
+        let start = ports.next;
+        let mut port = start;
+        loop {
+            let next = if port == *RANGE.end() {
+                *RANGE.start()
+            } else {
+                port + 1
+            };
+            if !ports.in_use.contains(&port) && is_free(port) {
+                ports.next = next;
+                let _ = ports.in_use.insert(port);
+                return Self(Arc::new(Lease {
+                    port,
+                    tracked: true,
+                }));
+            }
+            port = next;
+            if port == start {
+                break;
+            }
+        }
+
+        log::error!("No free local ports left in {:?} range", RANGE);
+        ports.next = if start == *RANGE.end() {
+            *RANGE.start()
+        } else {
+            start + 1
+        };
+        Self(Arc::new(Lease {
+            port: start,
+            tracked: false,
+        }))
+    }
+}
+
+impl Deref for LocalPort {
+    type Target = u16;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0.port
+    }
+}
+
+impl fmt::Display for LocalPort {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0.port, f)
+    }
+}
+
+/// Lease of a port in the registry, releasing it on [`Drop`].
+#[derive(Debug)]
+struct Lease {
+    /// Leased port.
+    port: u16,
+
+    /// Indicator whether the [`Lease::port`] is tracked in the registry, so
+    /// should be released there.
+    tracked: bool,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if self.tracked {
+            if let Ok(mut ports) = PORTS.lock() {
+                let _ = ports.in_use.remove(&self.port);
+            }
+        }
+    }
+}
+
+/// Checks whether the given `port` is not used by any TCP or UDP listener on
+/// the `127.0.0.1` interface, by binding test sockets to it.
+fn is_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+        && UdpSocket::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+}
+
+#[cfg(test)]
+mod local_port_spec {
+    use std::net::{Ipv4Addr, TcpListener};
+
+    use super::{LocalPort, PORTS};
+
+    #[test]
+    fn allocates_distinct_ports() {
+        let a = LocalPort::allocate();
+        let b = LocalPort::allocate();
+
+        assert_ne!(*a, *b);
+    }
+
+    #[test]
+    fn skips_ports_used_by_host() {
+        let busy = PORTS.lock().unwrap().next;
+        let _listener = TcpListener::bind((Ipv4Addr::LOCALHOST, busy));
+
+        let port = LocalPort::allocate();
+
+        assert_ne!(*port, busy);
+    }
+
+    #[test]
+    fn releases_port_once_dropped() {
+        let port = LocalPort::allocate();
+        let value = *port;
+        let clone = port.clone();
+
+        drop(port);
+        assert!(PORTS.lock().unwrap().in_use.contains(&value));
+
+        drop(clone);
+        assert!(!PORTS.lock().unwrap().in_use.contains(&value));
+    }
+}
@@ -0,0 +1,271 @@
+//! Pipes feeding live audio captured from [TeamSpeak] into [FFmpeg] mixing
+//! processes.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+//! [TeamSpeak]: https://teamspeak.com
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd as _, FromRawFd as _, OwnedFd};
+use std::{
+    fmt,
+    fs::File as StdFile,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use ephyr_log::log;
+#[cfg(unix)]
+use interprocess::os::unix::fifo_file::create_fifo;
+#[cfg(unix)]
+use nix::{
+    fcntl::{self, FcntlArg, FdFlag, OFlag},
+    unistd,
+};
+use once_cell::sync::OnceCell;
+use tokio::{fs::File, io, process::Command, sync::Notify};
+
+/// Kind of [`MixinPipe`]s to be used for feeding [FFmpeg] mixing processes.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MixinPipeKind {
+    /// Anonymous pipe, passed to [FFmpeg] as an inherited `/dev/fd/N` file
+    /// descriptor, so nothing is created on the filesystem.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    Anonymous,
+
+    /// Named pipe ([FIFO]) file created in the runtime directory.
+    ///
+    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+    Fifo,
+}
+
+impl Default for MixinPipeKind {
+    #[inline]
+    fn default() -> Self {
+        Self::Anonymous
+    }
+}
+
+impl FromStr for MixinPipeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "anonymous" => Ok(Self::Anonymous),
+            "fifo" => Ok(Self::Fifo),
+            _ => Err(anyhow!("Expected `anonymous` or `fifo` value")),
+        }
+    }
+}
+
+impl fmt::Display for MixinPipeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Anonymous => write!(f, "anonymous"),
+            Self::Fifo => write!(f, "fifo"),
+        }
+    }
+}
+
+/// Global [`MixinPipeKind`] to create [`MixinPipe`]s of.
+static KIND: OnceCell<MixinPipeKind> = OnceCell::new();
+
+/// Sets the global [`MixinPipeKind`] to feed [FFmpeg] mixing processes with.
+///
+/// [`MixinPipeKind::Anonymous`] is used, if not set.
+///
+/// # Errors
+///
+/// If the global [`MixinPipeKind`] has been set already.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub fn set_mixin_pipe_kind(kind: MixinPipeKind) -> anyhow::Result<()> {
+    KIND.set(kind)
+        .map_err(|_| anyhow!("Mixin pipe kind has been set already"))
+}
+
+/// Pipe feeding live audio of a single `Mixin` into its [FFmpeg] mixing
+/// process, surviving the process restarts.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Debug)]
+pub(crate) struct MixinPipe {
+    /// [`MixinPipeKind`] of this [`MixinPipe`].
+    kind: MixinPipeKind,
+
+    /// Path of the [FIFO] file, if this [`MixinPipe`] is a
+    /// [`MixinPipeKind::Fifo`].
+    ///
+    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+    fifo_path: PathBuf,
+
+    /// Read end of the anonymous pipe inherited by the latest spawned
+    /// [FFmpeg] process.
+    ///
+    /// Kept open until the next process is spawned, so the feeding of the
+    /// exited process is interrupted (with `EPIPE`) once it's replaced.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[cfg(unix)]
+    reader: Mutex<Option<OwnedFd>>,
+
+    /// Write end of the anonymous pipe to be fed next.
+    writer: Mutex<Option<StdFile>>,
+
+    /// Notification about a new [`MixinPipe::writer`] being available.
+    writer_ready: Notify,
+}
+
+impl MixinPipe {
+    /// Creates a new [`MixinPipe`] of the global [`MixinPipeKind`].
+    ///
+    /// The given `fifo_path` is used for [`MixinPipeKind::Fifo`] only.
+    #[must_use]
+    pub(crate) fn new(fifo_path: PathBuf) -> Self {
+        Self {
+            kind: KIND.get().copied().unwrap_or_default(),
+            fifo_path,
+            #[cfg(unix)]
+            reader: Mutex::new(None),
+            writer: Mutex::new(None),
+            writer_ready: Notify::new(),
+        }
+    }
+
+    /// Prepares this [`MixinPipe`] for a new [FFmpeg] process, adding it as
+    /// an input of the given [`Command`].
+    ///
+    /// # Errors
+    ///
+    /// If the pipe cannot be created.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[cfg(unix)]
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) -> io::Result<()> {
+        if self.kind == MixinPipeKind::Fifo {
+            // FIFO should be created before open.
+            ensure_fifo(&self.fifo_path)?;
+            let _ = cmd.arg("-i").arg(&self.fifo_path);
+            return Ok(());
+        }
+
+        let (reader, writer) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        // Both descriptors are just created and owned exclusively here.
+        #[allow(unsafe_code)]
+        let (reader, writer) = unsafe {
+            (OwnedFd::from_raw_fd(reader), StdFile::from_raw_fd(writer))
+        };
+        let fd = reader.as_raw_fd();
+
+        // Only the spawned FFmpeg process should inherit the read end, so
+        // it's not leaked into any other processes spawned concurrently.
+        #[allow(unsafe_code)]
+        unsafe {
+            let _ = cmd.pre_exec(move || {
+                let _ = fcntl::fcntl(fd, FcntlArg::F_SETFD(FdFlag::empty()))?;
+                Ok(())
+            });
+        }
+        let _ = cmd.arg("-i").arg(format!("/dev/fd/{fd}"));
+
+        *self.reader.lock().unwrap() = Some(reader);
+        *self.writer.lock().unwrap() = Some(writer);
+        self.writer_ready.notify_one();
+        Ok(())
+    }
+
+    /// Errors always, as neither anonymous pipes inheritable by [FFmpeg] via
+    /// `/dev/fd/N`, nor [FIFO]s are available on non-Unix platforms.
+    ///
+    /// # Errors
+    ///
+    /// Always.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+    #[cfg(not(unix))]
+    pub(crate) fn setup_ffmpeg(&self, _: &mut Command) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    /// Feeds the given `src` into this [`MixinPipe`] until the [FFmpeg]
+    /// process reading it exits.
+    ///
+    /// Awaits for the [FFmpeg] process to be spawned, if it's not yet.
+    ///
+    /// # Errors
+    ///
+    /// If the pipe fails to be written.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) async fn feed<R>(&self, src: &mut R) -> io::Result<u64>
+    where
+        R: io::AsyncRead + Unpin + ?Sized,
+    {
+        let mut file = if self.kind == MixinPipeKind::Fifo {
+            // FIFO may be removed by a previous feeding.
+            ensure_fifo(&self.fifo_path)?;
+            // Blocks until FFmpeg opens FIFO for reading.
+            File::create(&self.fifo_path).await?
+        } else {
+            loop {
+                let writer = self.writer.lock().unwrap().take();
+                if let Some(w) = writer {
+                    break File::from_std(w);
+                }
+                self.writer_ready.notified().await;
+            }
+        };
+        io::copy(src, &mut file).await
+    }
+
+    /// Cleans up the filesystem artifacts of this [`MixinPipe`], if any.
+    pub(crate) fn cleanup(&self) {
+        if self.kind == MixinPipeKind::Fifo {
+            if let Err(e) = std::fs::remove_file(&self.fifo_path) {
+                log::error!("Failed to remove FIFO: {e}");
+            }
+        }
+    }
+}
+
+/// Creates a [FIFO] file by the given `path`, unless it exists already.
+///
+/// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+#[cfg(unix)]
+fn ensure_fifo(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        create_fifo(path, 0o777)?;
+    }
+    Ok(())
+}
+
+/// Errors always, as there are no [FIFO]s on non-Unix platforms.
+///
+/// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+#[cfg(not(unix))]
+fn ensure_fifo(_: &Path) -> io::Result<()> {
+    Err(unsupported())
+}
+
+/// Returns an error of [`MixinPipe`]s being unsupported on non-Unix
+/// platforms.
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Mixin pipes are supported on Unix platforms only",
+    )
+}
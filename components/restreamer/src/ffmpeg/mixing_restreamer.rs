@@ -7,22 +7,31 @@
 use std::{
     borrow::Cow,
     collections::HashMap,
+    convert::{TryFrom as _, TryInto as _},
     ffi::OsStr,
     fmt::Write as _,
+    iter,
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     process::Stdio,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use ephyr_log::{log, Drain as _};
-use futures::{FutureExt as _, TryFutureExt as _};
-use interprocess::os::unix::fifo_file::create_fifo;
+use futures::{
+    future::{self, AbortHandle},
+    FutureExt as _, TryFutureExt as _,
+};
+use once_cell::sync::Lazy;
 use tokio::{
-    fs::File,
-    io, pin,
+    io,
     process::Command,
     sync::{watch, Mutex},
+    time,
 };
 use tsclientlib::Identity;
 use url::Url;
@@ -31,8 +40,16 @@ use zeromq::ZmqMessage;
 
 use crate::{
     display_panic, dvr,
-    ffmpeg::{restreamer::RestreamerStatus, RestreamerKind},
-    state::{self, Delay, MixinId, MixinSrcUrl, State, Volume},
+    ffmpeg::{
+        audio_meter, mixin_pipe::MixinPipe, restreamer::RestreamerStatus,
+        LocalPort, RestreamerKind,
+    },
+    runtime_dir,
+    state::{
+        self, Delay, FfmpegLogLevel, MixinId, MixinSidechainParams,
+        MixinSrcUrl, OutputIcecast, OutputLoudnorm, OutputReconnect, OutputTls,
+        State, Status, Volume, VolumeLevel,
+    },
     teamspeak,
 };
 
@@ -53,15 +70,59 @@ pub struct MixingRestreamer {
     /// [`Url`] to publish the mixed live stream onto.
     pub to_url: Url,
 
+    /// [`OutputTls`] options to publish onto an encrypted
+    /// [`MixingRestreamer::to_url`] with.
+    pub tls: Option<OutputTls>,
+
+    /// [`OutputReconnect`] options to survive short network failures of the
+    /// [`MixingRestreamer::to_url`] with.
+    pub reconnect: Option<OutputReconnect>,
+
+    /// [`OutputIcecast`] metadata to describe the stream with on an
+    /// `icecast://` [`MixingRestreamer::to_url`].
+    pub icecast: Option<OutputIcecast>,
+
+    /// [`OutputLoudnorm`] options to normalize loudness of the mixed audio
+    /// with.
+    pub loudnorm: Option<OutputLoudnorm>,
+
+    /// [`FfmpegLogLevel`] to run [FFmpeg] process with, overriding the
+    /// server's one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub log_level: Option<FfmpegLogLevel>,
+
+    /// Extra arguments to pass to [FFmpeg] right before the
+    /// [`MixingRestreamer::to_url`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub extra_args: Vec<String>,
+
     /// [`Volume`] rate to mix an audio of the original pulled live stream with.
     pub orig_volume: Volume,
 
+    /// Duration to fade the audio of the original pulled live stream into the
+    /// [`MixingRestreamer::orig_volume`] over, once it's tuned.
+    pub orig_volume_fade: Delay,
+
+    /// Offset (in milliseconds) of the mixed audio relatively to the video.
+    ///
+    /// Positive value delays the audio (tunable in real-time), while negative
+    /// one delays the video (requires restart).
+    pub av_offset: i32,
+
     /// [ZeroMQ] port of a spawned [FFmpeg] process listening to a real-time
     /// filter updates of the original pulled live stream during mixing process.
     ///
     /// [FFmpeg]: https://ffmpeg.org
     /// [ZeroMQ]: https://zeromq.org
-    pub orig_zmq_port: u16,
+    pub orig_zmq_port: LocalPort,
+
+    /// UDP port to receive audio levels of the mixed live stream on, measured
+    /// by a spawned [FFmpeg] process.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub levels_port: LocalPort,
 
     /// Additional live streams to be mixed with the original one before being
     /// re-streamed to the [`MixingRestreamer::to_url`].
@@ -90,8 +151,17 @@ impl MixingRestreamer {
             id: output.id.into(),
             from_url: from_url.clone(),
             to_url: RestreamerKind::dst_url(output),
+            tls: output.tls.clone(),
+            reconnect: output.reconnect.clone(),
+            icecast: output.icecast.clone(),
+            loudnorm: output.loudnorm,
+            log_level: output.log_level,
+            extra_args: output.extra_ffmpeg_args.clone(),
             orig_volume: output.volume.clone(),
-            orig_zmq_port: new_unique_zmq_port(),
+            orig_volume_fade: output.volume_fade,
+            av_offset: output.av_offset,
+            orig_zmq_port: LocalPort::allocate(),
+            levels_port: LocalPort::allocate(),
             mixins: output
                 .mixins
                 .iter()
@@ -114,7 +184,14 @@ impl MixingRestreamer {
     pub fn needs_restart(&mut self, actual: &Self) -> bool {
         if self.from_url != actual.from_url
             || self.to_url != actual.to_url
+            || self.tls != actual.tls
+            || self.reconnect != actual.reconnect
+            || self.icecast != actual.icecast
+            || self.loudnorm != actual.loudnorm
+            || self.log_level != actual.log_level
+            || self.extra_args != actual.extra_args
             || self.mixins.len() != actual.mixins.len()
+            || self.av_offset.min(0) != actual.av_offset.min(0)
         {
             return true;
         }
@@ -126,17 +203,37 @@ impl MixingRestreamer {
         }
 
         if self.orig_volume != actual.orig_volume {
+            tune_volume(
+                self.id,
+                *self.orig_zmq_port,
+                &self.orig_volume,
+                &actual.orig_volume,
+                actual.orig_volume_fade,
+            );
             self.orig_volume = actual.orig_volume.clone();
-            tune_volume(self.id, self.orig_zmq_port, self.orig_volume.clone());
+        }
+        if self.av_offset != actual.av_offset {
+            self.av_offset = actual.av_offset;
+            tune_delay(
+                self.id,
+                *self.orig_zmq_port,
+                Delay::from_millis(self.av_offset).unwrap_or_default(),
+            );
         }
         for (curr, actual) in self.mixins.iter_mut().zip(actual.mixins.iter()) {
             if curr.volume != actual.volume {
+                tune_volume(
+                    curr.id.into(),
+                    *curr.zmq_port,
+                    &curr.volume,
+                    &actual.volume,
+                    actual.volume_fade,
+                );
                 curr.volume = actual.volume.clone();
-                tune_volume(curr.id.into(), curr.zmq_port, curr.volume.clone());
             }
             if curr.delay != actual.delay {
                 curr.delay = actual.delay;
-                tune_delay(curr.id.into(), curr.zmq_port, curr.delay);
+                tune_delay(curr.id.into(), *curr.zmq_port, curr.delay);
             }
         }
 
@@ -146,9 +243,9 @@ impl MixingRestreamer {
     /// Properly setups the given [FFmpeg] [`Command`] for this
     /// [`MixingRestreamer`] before running it.
     ///
-    /// The specified [`State`] is used to retrieve up-to-date [`Volume`]s, as
-    /// their changes don't trigger re-creation of the whole [FFmpeg]
-    /// re-streaming process.
+    /// The specified [`State`] is used to retrieve up-to-date [`Volume`]s and
+    /// [`Delay`]s, as their changes don't trigger re-creation of the whole
+    /// [FFmpeg] re-streaming process.
     ///
     /// # Errors
     ///
@@ -163,19 +260,25 @@ impl MixingRestreamer {
     ) -> io::Result<()> {
         let my_id = self.id.into();
 
-        // We need up-to-date values of `Volume` here, right from the `State`,
-        // as they won't be updated in a closured `self` value.
+        // We need up-to-date values of `Volume` and `Delay` here, right from
+        // the `State`, as they won't be updated in a closured `self` value.
         let output =
             state.restreams.lock_ref().iter().find_map(|r| {
                 r.outputs.iter().find(|o| o.id == my_id).cloned()
             });
 
-        if ephyr_log::logger().is_debug_enabled() {
+        if let Some(level) = self.log_level {
+            level.setup_ffmpeg(cmd);
+        } else if ephyr_log::logger().is_debug_enabled() {
             let _ = cmd.stderr(Stdio::inherit()).args(["-loglevel", "debug"]);
         } else {
             let _ = cmd.stderr(Stdio::null());
         }
 
+        for m in &self.mixins {
+            m.report_status(state);
+        }
+
         let orig_volume = output
             .as_ref()
             .map_or(self.orig_volume.clone(), |o| o.volume.clone());
@@ -204,30 +307,50 @@ impl MixingRestreamer {
                         .args(["-f", "f32be"])
                         .args(["-sample_rate", "48000"])
                         .args(["-channels", "2"])
-                        .args(["-use_wallclock_as_timestamps", "true"])
-                        .arg("-i")
-                        .arg(mixin.get_fifo_path())
+                        .args(["-use_wallclock_as_timestamps", "true"]);
+                    mixin.pipe.setup_ffmpeg(cmd)?;
+                    cmd
                 }
 
-                "http" | "https"
-                    if Path::new(mixin.url.path()).extension()
-                        == Some("mp3".as_ref()) =>
-                {
+                "http" | "https" => {
+                    // Format is not forced here, so FFmpeg negotiates it by
+                    // the `Content-Type` of an Icecast mount (MP3, AAC or
+                    // OGG), and resamples it to the common rate afterwards.
                     extra_filters.push_str("aresample=48000,");
-                    cmd.args(["-i", mixin.url.as_str()])
+                    cmd.args(["-thread_queue_size", "512"])
+                        .args(["-reconnect", "1"])
+                        .args(["-reconnect_streamed", "1"])
+                        .args(["-reconnect_delay_max", "2"])
+                        .args(["-i", mixin.url.as_str()])
+                }
+
+                "ephyr" => {
+                    let url = mixin.resolve_restream_url(state)?;
+                    extra_filters.push_str("aresample=48000,");
+                    cmd.args(["-thread_queue_size", "512"])
+                        .args(["-i", url.as_str()])
                 }
 
                 _ => unimplemented!(),
             };
 
-            if !mixin.delay.is_zero() {
-                let _ = write!(
-                    extra_filters,
-                    "adelay@{mixin_id}=delays={delay}:all=1,",
-                    mixin_id = mixin.id,
-                    delay = mixin.delay.as_millis()
-                );
-            }
+            // `adelay` filter is always present (even with zero delay), so its
+            // value can be tuned in real-time via ZeroMQ without restarting
+            // the whole FFmpeg process.
+            let delay = output
+                .as_ref()
+                .and_then(|o| {
+                    o.mixins
+                        .iter()
+                        .find_map(|m| (m.id == mixin.id).then_some(m.delay))
+                })
+                .unwrap_or(mixin.delay);
+            let _ = write!(
+                extra_filters,
+                "adelay@{mixin_id}=delays={delay}:all=1,",
+                mixin_id = mixin.id,
+                delay = delay.as_millis()
+            );
 
             let volume = output
                 .as_ref()
@@ -243,12 +366,14 @@ impl MixingRestreamer {
                 "[{num}:a]\
                    volume@{mixin_id}={volume},\
                    {extra_filters}\
+                   {meter},\
                    azmq=bind_address=tcp\\\\\\://127.0.0.1\\\\\\:{port}\
                  [{mixin_id}]",
                 num = n + 1,
                 mixin_id = mixin.id,
                 volume = volume.display_as_fraction(),
                 extra_filters = extra_filters,
+                meter = audio_meter::filters(*mixin.levels_port),
                 port = mixin.zmq_port,
             ));
         }
@@ -265,15 +390,10 @@ impl MixingRestreamer {
         {
             let sidechain_mixin_id = sidechain_mixin.id.to_string();
             // Sidechain is mixing Origin Audio and selected Mixin Audio
+            let params = sidechain_mixin.sidechain_params.filter_options();
             filter_complex.push(format!(
                 "[{sidechain_mixin_id}]asplit=2[sc][mix];\
-                 [{orig_id}][sc]sidechaincompress=\
-                                    level_in=2\
-                                    :threshold=0.05\
-                                    :ratio=10\
-                                    :attack=10\
-                                    :knee=4\
-                                    :release=1500[compr]"
+                 [{orig_id}][sc]sidechaincompress={params}[compr]"
             ));
             // Replace Mixin Id for sidechain with `mix` value
             if let Some(elem) =
@@ -286,11 +406,28 @@ impl MixingRestreamer {
             orig_id = "compr".to_string();
         };
 
+        // `adelay` filter is always present (even with zero offset), so the
+        // audio can be delayed in real-time via ZeroMQ without restarting the
+        // whole FFmpeg process.
+        let av_offset = output.as_ref().map_or(self.av_offset, |o| o.av_offset);
+        // Loudness is normalized after mixing, so the measured levels reflect
+        // the normalized audio.
+        let loudnorm = self
+            .loudnorm
+            .map(|l| format!("{},", l.filters()))
+            .unwrap_or_default();
         filter_complex.push(format!(
-            "[{orig_id}][{mixin_ids}]amix=inputs={count}:duration=longest[out]",
+            "[{orig_id}][{mixin_ids}]amix=inputs={count}:duration=longest,\
+             {loudnorm}\
+             adelay@{my_id}=delays={audio_delay}:all=1,\
+             {meter}[out]",
             orig_id = orig_id,
             mixin_ids = mixin_ids.join("]["),
             count = self.mixins.len() + 1,
+            loudnorm = loudnorm,
+            my_id = self.id,
+            audio_delay = av_offset.max(0),
+            meter = audio_meter::filters(*self.levels_port),
         ));
 
         log::debug!("FFmpeg FILTER COMPLEX: {:?}", &filter_complex.join(";"));
@@ -299,20 +436,47 @@ impl MixingRestreamer {
             .args(["-map", "[out]"])
             .args(["-max_muxing_queue_size", "50000000"]);
 
+        // The video is copied as is, so can be delayed only by shifting its
+        // timestamps, which cannot be tuned in real-time.
+        if self.av_offset < 0 {
+            let secs = f64::from(-self.av_offset) / 1000.0;
+            let _ = cmd.args(["-bsf:v", &format!("setts=ts=TS+{secs}/TB")]);
+        }
+
+        if self.to_url.scheme() == "rtmps" {
+            if let Some(tls) = self.tls.as_ref() {
+                tls.setup_ffmpeg(cmd);
+            }
+        }
+        if let Some(reconnect) = self.reconnect.as_ref() {
+            reconnect.setup_ffmpeg(cmd, &self.to_url);
+        }
+        if self.to_url.scheme() == "icecast" {
+            if let Some(icecast) = self.icecast.as_ref() {
+                icecast.setup_ffmpeg(cmd);
+            }
+        }
+
+        if self.to_url.scheme() == "file" {
+            dvr::setup_segmenting(cmd, &self.to_url);
+        }
+
         let _ = match self.to_url.scheme() {
             "file" => match Path::new(self.to_url.path())
                 .extension()
                 .and_then(OsStr::to_str)
             {
-                Some("flv") => cmd
+                Some("flv" | "mp4") => cmd
                     .args(["-map", "0:v"])
                     .args(["-c:a", "libfdk_aac", "-c:v", "copy", "-shortest"])
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 Some("wav") => cmd
                     .arg("-vn")
                     .args(["-acodec", "pcm_s16le"])
                     .args(["-ar", "48000"])
                     .args(["-ac", "2"])
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 Some("mp3") => cmd
                     .arg("-vn")
@@ -320,6 +484,7 @@ impl MixingRestreamer {
                     .args(["-b:a", "64k"])
                     .args(["-ar", "48000"])
                     .args(["-ac", "2"])
+                    .args(&self.extra_args)
                     .arg(dvr::new_file_path(&self.to_url).await?),
                 _ => unimplemented!(),
             },
@@ -328,18 +493,21 @@ impl MixingRestreamer {
                 .arg("-vn")
                 .args(["-c:a", "libmp3lame", "-b:a", "64k"])
                 .args(["-f", "mp3", "-content_type", "audio/mpeg"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             "rtmp" | "rtmps" => cmd
                 .args(["-map", "0:v"])
                 .args(["-c:a", "libfdk_aac", "-c:v", "copy", "-shortest"])
                 .args(["-f", "flv"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             "srt" => cmd
                 .args(["-map", "0:v"])
                 .args(["-c:a", "libfdk_aac", "-c:v", "copy", "-shortest"])
                 .args(["-strict", "-2", "-y", "-f", "mpegts"])
+                .args(&self.extra_args)
                 .arg(self.to_url.as_str()),
 
             _ => unimplemented!(),
@@ -348,68 +516,73 @@ impl MixingRestreamer {
         Ok(())
     }
 
-    /// Copy data from [`Mixin.stdin`] to [FIFO].
-    ///
-    /// Each data copying is operated in separate thread.
-    /// [FIFO] should be fed before [FFmpeg].
-    ///
-    /// # Errors
+    /// Returns IDs of the elements in a [`State`] whose audio levels are
+    /// measured by this [`MixingRestreamer`], along with UDP ports the levels
+    /// are received on.
+    #[must_use]
+    pub(crate) fn audio_meters(&self) -> Vec<(Uuid, u16)> {
+        iter::once((self.id, *self.levels_port))
+            .chain(self.mixins.iter().map(|m| (m.id.into(), *m.levels_port)))
+            .collect()
+    }
+
+    /// Copy data from [`Mixin.stdin`] to its [`MixinPipe`].
     ///
-    /// If [FIFI] file failed to create.
-    /// We need it because [FFmpeg] cannot start if no [FIFO] file.
+    /// Each data copying is operated in separate thread, and lasts until the
+    /// given `kill_rx` signals, re-feeding the [`MixinPipe`] once [FFmpeg]
+    /// reopens it after a restart. So, it's started once per [`Mixin`] only.
     ///
-    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
     /// [FFmpeg]: https://ffmpeg.org
-    pub(crate) fn start_fed_mixins_fifo(
+    pub(crate) fn start_feeding_mixins(
         &self,
         kill_rx: &watch::Receiver<RestreamerStatus>,
     ) {
         async fn run_copy_and_stop_on_signal(
             input: Arc<Mutex<teamspeak::Input>>,
-            fifo_path: PathBuf,
+            pipe: Arc<MixinPipe>,
             mut kill_rx: watch::Receiver<RestreamerStatus>,
-        ) -> io::Result<()> {
+        ) {
             // To avoid instant resolve on await for `kill_rx`
             let _ = *kill_rx.borrow_and_update();
 
-            // Initialize copying future to fed it into select
             let mut src = input.lock().await;
-            let mut file = File::create(&fifo_path).await?;
-            let copying = io::copy(&mut *src, &mut file);
-            pin!(copying);
 
-            // Run copying to FIFO and stops if receive signal from `kill_rx`
+            // Run copying to pipe and stops if receive signal from `kill_rx`
             loop {
                 tokio::select! {
-                    r = &mut copying => {
-                        let _ = r.map_err(|e|
-                            log::error!("Failed to write into FIFO: {}", e)
-                        );
-                        break;
+                    r = pipe.feed(&mut *src) => {
+                        // Pipe is broken once FFmpeg exits, which is expected.
+                        match r {
+                            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                                log::error!("Failed to write into pipe: {}", e);
+                            }
+                            _ => {}
+                        }
                     }
-                   _ = kill_rx.changed() => {
-                        log::debug!("Signal for FIFO received");
+                    _ = kill_rx.changed() => {
+                        log::debug!("Signal for pipe received");
                         break;
                     }
                 }
+                // FFmpeg has closed pipe, so re-feed it once it's restarted.
+                time::sleep(PIPE_REFEED_DELAY).await;
             }
-            // Clean up FIFO file
-            let _ = std::fs::remove_file(fifo_path)
-                .map_err(|e| log::error!("Failed to remove FIFO: {}", e));
-
-            Ok(())
+            pipe.cleanup();
         }
 
+        /// Delay before re-feeding a [`MixinPipe`] closed by [FFmpeg].
+        ///
+        /// [FFmpeg]: https://ffmpeg.org
+        const PIPE_REFEED_DELAY: Duration = Duration::from_millis(500);
+
         for m in &self.mixins {
-            // FIFO should be created before open
-            if !m.get_fifo_path().exists() {
-                let _ = create_fifo(m.get_fifo_path(), 0o777)
-                    .map_err(|e| log::error!("Failed to create FIFO: {}", e));
-            }
             if let Some(i) = m.stdin.as_ref() {
+                if m.is_fed.swap(true, Ordering::SeqCst) {
+                    continue;
+                }
                 drop(tokio::spawn(run_copy_and_stop_on_signal(
                     Arc::clone(i),
-                    m.get_fifo_path(),
+                    Arc::clone(&m.pipe),
                     kill_rx.clone(),
                 )));
             }
@@ -432,25 +605,67 @@ pub struct Mixin {
     /// [`Volume`] rate to mix an audio of this [`Mixin`]'s live stream with.
     pub volume: Volume,
 
+    /// Duration to fade the audio of this [`Mixin`]'s live stream into the
+    /// [`Mixin::volume`] over, once it's tuned.
+    pub volume_fade: Delay,
+
     /// Apply [sidechain] audio filter of this [`Mixin`]'s with live stream.
     ///
     /// [sidechain]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
     pub sidechain: bool,
 
+    /// Parameters of the [sidechain] audio filter applied with this
+    /// [`Mixin`]'s live stream.
+    ///
+    /// [sidechain]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
+    pub sidechain_params: MixinSidechainParams,
+
     /// [ZeroMQ] port of a spawned [FFmpeg] process listening to a real-time
     /// filter updates of this [`Mixin`]'s live stream during mixing process.
     ///
     /// [FFmpeg]: https://ffmpeg.org
     /// [ZeroMQ]: https://zeromq.org
-    pub zmq_port: u16,
+    pub zmq_port: LocalPort,
+
+    /// UDP port to receive audio levels of this [`Mixin`]'s live stream on,
+    /// measured by a spawned [FFmpeg] process.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub levels_port: LocalPort,
 
     /// Actual live audio stream captured from the [TeamSpeak] server.
     ///
-    /// If present, it should be fed into [FIFO].
+    /// If present, it should be fed into [`Mixin::pipe`].
     ///
     /// [TeamSpeak]: https://teamspeak.com
-    /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
     stdin: Option<Arc<Mutex<teamspeak::Input>>>,
+
+    /// [`Status`] of the connection with the [TeamSpeak] server, if this
+    /// [`Mixin`] captures its audio from there.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    ts_status: Option<watch::Receiver<Status>>,
+
+    /// Indicator whether [`Mixin::ts_status`] is reported into the [`State`]
+    /// already.
+    is_status_reported: Arc<AtomicBool>,
+
+    /// [`MixinPipe`] feeding [`Mixin::stdin`] into [FFmpeg].
+    ///
+    /// Not shared with a `prev` [`Mixin`], as it's fed for the lifetime of
+    /// the [FFmpeg] process spawned for this [`Mixin`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pipe: Arc<MixinPipe>,
+
+    /// Indicator whether [`Mixin::stdin`] is being fed into [`Mixin::pipe`]
+    /// already.
+    ///
+    /// Not shared with a `prev` [`Mixin`], as it's fed for the lifetime of
+    /// the [FFmpeg] process spawned for this [`Mixin`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    is_fed: Arc<AtomicBool>,
 }
 
 impl Mixin {
@@ -470,6 +685,9 @@ impl Mixin {
         label: Option<&state::Label>,
         prev: Option<&Mixin>,
     ) -> Self {
+        // Resources of a `Mixin` with a changed source cannot be reused.
+        let prev = prev.filter(|m| m.url == state.src);
+
         let stdin = (state.src.scheme() == "ts")
             .then(|| {
                 prev.and_then(|m| m.stdin.clone()).or_else(|| {
@@ -512,17 +730,69 @@ impl Mixin {
             })
             .flatten();
 
+        let (ts_status, is_status_reported) = match prev {
+            Some(m) if stdin.is_some() && m.stdin.is_some() => {
+                (m.ts_status.clone(), m.is_status_reported.clone())
+            }
+            _ => (
+                stdin
+                    .as_ref()
+                    .and_then(|i| i.try_lock().ok())
+                    .map(|i| i.status()),
+                Arc::default(),
+            ),
+        };
+
         Self {
             id: state.id,
             url: state.src.clone(),
             delay: state.delay,
             sidechain: state.sidechain,
+            sidechain_params: state.sidechain_params,
             volume: state.volume.clone(),
-            zmq_port: new_unique_zmq_port(),
+            volume_fade: state.volume_fade,
+            zmq_port: LocalPort::allocate(),
+            levels_port: LocalPort::allocate(),
             stdin,
+            ts_status,
+            is_status_reported,
+            pipe: Arc::new(MixinPipe::new(Self::get_fifo_path(state.id))),
+            is_fed: Arc::default(),
         }
     }
 
+    /// Starts reporting [`Mixin::ts_status`] changes into the given [`State`],
+    /// unless it's reported already.
+    ///
+    /// Once the [TeamSpeak] connection is dropped, the [`Mixin`] is reported
+    /// as [`Status::Offline`].
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    fn report_status(&self, state: &State) {
+        let mut rx = match self.ts_status.clone() {
+            Some(rx) => rx,
+            None => return,
+        };
+        if self.is_status_reported.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let (id, state) = (self.id, state.clone());
+        drop(tokio::spawn(async move {
+            loop {
+                let status = *rx.borrow_and_update();
+                let _ = state.set_mixin_status(id, status, None);
+                if rx.changed().await.is_err() {
+                    break;
+                }
+            }
+            let _ = state.set_mixin_status(
+                id,
+                Status::Offline,
+                Some("TeamSpeak connection closed"),
+            );
+        }));
+    }
+
     /// Checks whether this [`Mixin`]'s [FFmpeg] process must be restarted, as
     /// cannot apply the new `actual` params on itself correctly, without
     /// interruptions.
@@ -531,10 +801,45 @@ impl Mixin {
     #[inline]
     #[must_use]
     pub fn needs_restart(&self, actual: &Self) -> bool {
-        self.url != actual.url || self.sidechain != actual.sidechain
+        self.url != actual.url
+            || self.sidechain != actual.sidechain
+            || self.sidechain_params != actual.sidechain_params
     }
 
-    /// [FIFO] path where stream captures from the [TeamSpeak] server.
+    /// Resolves an internal `ephyr://restream/<key>` [`Mixin::url`] into an
+    /// URL of the referred `Restream`'s main `Input` on the local [SRS]
+    /// server.
+    ///
+    /// # Errors
+    ///
+    /// If the referred `Restream` doesn't exist in the given [`State`], or
+    /// has no RTMP `Input` endpoint.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    fn resolve_restream_url(&self, state: &State) -> io::Result<Url> {
+        let key = self.url.restream_key().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not an internal Restream URL: {}", self.url),
+            )
+        })?;
+        state
+            .restreams
+            .lock_ref()
+            .iter()
+            .find(|r| r.key == key)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Restream '{key}' doesn't exist"),
+                )
+            })?
+            .main_input_rtmp_endpoint_url()
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))
+    }
+
+    /// [FIFO] path where stream captures from the [TeamSpeak] server for the
+    /// [`Mixin`] with the given `id`, if `MixinPipeKind::Fifo` is used.
     ///
     /// Should be fed into [FFmpeg]'s as file input.
     ///
@@ -543,42 +848,88 @@ impl Mixin {
     /// [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
     #[inline]
     #[must_use]
-    pub fn get_fifo_path(&self) -> PathBuf {
-        std::env::temp_dir().join(format!("ephyr_mixin_{}.pipe", self.id))
+    pub fn get_fifo_path(id: MixinId) -> PathBuf {
+        runtime_dir::path()
+            .join(format!("{}mixin_{id}.pipe", runtime_dir::PREFIX))
     }
 }
 
-/// Generates a new port for a [ZeroMQ] listener, which is highly unlikely to be
-/// used already.
+/// Interval between steps of fading a [`Volume`] of a [FFmpeg] track.
 ///
-/// [ZeroMQ]: https://zeromq.org
-#[must_use]
-fn new_unique_zmq_port() -> u16 {
-    use std::{
-        convert,
-        sync::atomic::{AtomicU16, Ordering},
-    };
-
-    static LATEST_PORT: AtomicU16 = AtomicU16::new(20000);
-
-    LATEST_PORT
-        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |p| {
-            Some(p.checked_add(1).unwrap_or(20000))
-        })
-        .unwrap_or_else(convert::identity)
-}
+/// [FFmpeg]: https://ffmpeg.org
+const VOLUME_FADE_STEP: Duration = Duration::from_millis(50);
+
+/// Maximum duration of fading a [`Volume`] of a [FFmpeg] track.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const MAX_VOLUME_FADE: Duration = Duration::from_secs(10);
+
+/// Handles to abort [`Volume`] fades being performed at the moment, by IDs of
+/// their [FFmpeg] tracks.
+///
+/// [FFmpeg]: https://ffmpeg.org
+static VOLUME_FADES: Lazy<std::sync::Mutex<HashMap<Uuid, AbortHandle>>> =
+    Lazy::new(std::sync::Mutex::default);
 
 /// Tunes [`Volume`] of the specified [FFmpeg] `track` by updating the `volume`
 /// [FFmpeg] filter in real-time via [ZeroMQ] protocol.
 ///
+/// If `fade` is not zero, then the `volume` filter is ramped from the `from`
+/// [`Volume`] to the `to` one in small steps over the `fade` duration (capped
+/// to [`MAX_VOLUME_FADE`]). Any fade of the same `track` still being
+/// performed is aborted.
+///
 /// [FFmpeg]: https://ffmpeg.org
 /// [ZeroMQ]: https://zeromq.org
-fn tune_volume(track: Uuid, port: u16, volume: Volume) {
-    tune_with_zmq(
-        port,
-        format!("volume@{track} volume {}", volume.display_as_fraction())
-            .into(),
+fn tune_volume(
+    track: Uuid,
+    port: u16,
+    from: &Volume,
+    to: &Volume,
+    fade: Delay,
+) {
+    let fade = Duration::from_millis(fade.as_millis().try_into().unwrap_or(0))
+        .min(MAX_VOLUME_FADE);
+    let steps = i64::try_from(fade.as_millis() / VOLUME_FADE_STEP.as_millis())
+        .unwrap_or(1)
+        .max(1);
+    let (from, to) = (i64::from(from.percents()), i64::from(to.percents()));
+    let levels: Vec<_> = (1..=steps)
+        .map(|i| {
+            Volume {
+                level: VolumeLevel::new(from + (to - from) * i / steps)
+                    .unwrap_or_default(),
+                muted: false,
+            }
+            .display_as_fraction()
+        })
+        .collect();
+
+    let (fading, abort) = future::abortable(
+        AssertUnwindSafe(async move {
+            for (i, level) in levels.into_iter().enumerate() {
+                if i > 0 {
+                    time::sleep(VOLUME_FADE_STEP).await;
+                }
+                let _ = send_with_zmq(
+                    port,
+                    format!("volume@{track} volume {level}").into(),
+                )
+                .await;
+            }
+        })
+        .catch_unwind()
+        .map_err(|p| {
+            log::crit!(
+                "Panicked while fading volume via ZeroMQ: {}",
+                display_panic(&p),
+            );
+        }),
     );
+    if let Some(prev) = VOLUME_FADES.lock().unwrap().insert(track, abort) {
+        prev.abort();
+    }
+    drop(tokio::spawn(fading));
 }
 
 /// Tunes [`Delay`] of the specified [FFmpeg] `track` by updating the `delay`
@@ -600,47 +951,49 @@ fn tune_delay(track: Uuid, port: u16, delay: Delay) {
 /// [FFmpeg]: https://ffmpeg.org
 /// [ZeroMQ]: https://zeromq.org
 fn tune_with_zmq(port: u16, command: ZmqMessage) {
-    use zeromq::{Socket as _, SocketRecv as _, SocketSend as _};
-
     drop(tokio::spawn(
-        AssertUnwindSafe(async move {
-            let addr = format!("tcp://127.0.0.1:{port}");
-
-            let mut socket = zeromq::ReqSocket::new();
-            socket.connect(&addr).await.map_err(|e| {
-                log::error!(
-                    "Failed to establish ZeroMQ connection with {addr} : {e}"
+        AssertUnwindSafe(send_with_zmq(port, command))
+            .catch_unwind()
+            .map_err(|p| {
+                log::crit!(
+                    "Panicked while sending ZeroMQ message: {}",
+                    display_panic(&p),
                 );
-            })?;
-            socket.send(command).await.map_err(|e| {
-                log::error!("Failed to send ZeroMQ message to {addr} : {e}");
-            })?;
-
-            let resp = socket.recv().await.map_err(|e| {
-                log::error!(
-                    "Failed to receive ZeroMQ response from {addr} : {e}"
-                );
-            })?;
-
-            let data = resp.into_vec().pop().unwrap();
-            if data.as_ref() != "0 Success".as_bytes() {
-                log::error!(
-                    "Received invalid ZeroMQ response from {addr} : {}",
-                    std::str::from_utf8(&data).map_or_else(
-                        |_| Cow::Owned(format!("{:?}", &data)),
-                        Cow::Borrowed,
-                    ),
-                );
-            }
-
-            <Result<_, ()>>::Ok(())
-        })
-        .catch_unwind()
-        .map_err(|p| {
-            log::crit!(
-                "Panicked while sending ZeroMQ message: {}",
-                display_panic(&p),
-            );
-        }),
+            }),
     ));
 }
+
+/// Sends [`ZmqMessage`] to the specified port on localhost, awaiting the
+/// response.
+///
+/// Any failure is logged.
+async fn send_with_zmq(port: u16, command: ZmqMessage) -> Result<(), ()> {
+    use zeromq::{Socket as _, SocketRecv as _, SocketSend as _};
+
+    let addr = format!("tcp://127.0.0.1:{port}");
+
+    let mut socket = zeromq::ReqSocket::new();
+    socket.connect(&addr).await.map_err(|e| {
+        log::error!("Failed to establish ZeroMQ connection with {addr} : {e}");
+    })?;
+    socket.send(command).await.map_err(|e| {
+        log::error!("Failed to send ZeroMQ message to {addr} : {e}");
+    })?;
+
+    let resp = socket.recv().await.map_err(|e| {
+        log::error!("Failed to receive ZeroMQ response from {addr} : {e}");
+    })?;
+
+    let data = resp.into_vec().pop().unwrap();
+    if data.as_ref() != "0 Success".as_bytes() {
+        log::error!(
+            "Received invalid ZeroMQ response from {addr} : {}",
+            std::str::from_utf8(&data).map_or_else(
+                |_| Cow::Owned(format!("{:?}", &data)),
+                Cow::Borrowed,
+            ),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! Short-lived [FFmpeg] process testing a downstream destination of an
+//! `Output` by pushing a generated test stream onto it.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{
+    collections::VecDeque,
+    convert::TryFrom as _,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use ephyr_log::log;
+use juniper::GraphQLObject;
+use tokio::{
+    io::{AsyncBufReadExt as _, BufReader},
+    time,
+};
+use url::Url;
+
+use crate::{ffmpeg::CommandFactory, state};
+
+/// Extra time given to [FFmpeg] process to connect and finish, in addition to
+/// the tested duration, before it's considered hung.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const GRACE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Maximum number of the last [FFmpeg] log lines reported on failure.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const MAX_ERROR_LINES: usize = 5;
+
+/// Result of testing a downstream destination of an `Output`.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct OutputTestResult {
+    /// Indicator whether the test stream has been pushed successfully for the
+    /// whole requested duration.
+    pub success: bool,
+
+    /// Time (in milliseconds) it took to connect to the destination and
+    /// perform the protocol handshake.
+    ///
+    /// `null` if the connection hasn't been established at all.
+    pub handshake_millis: Option<i32>,
+
+    /// Last lines of [FFmpeg] log describing the failure.
+    ///
+    /// `null` on success.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub error: Option<String>,
+}
+
+impl OutputTestResult {
+    /// Creates a failed [`OutputTestResult`] with the given `error`.
+    #[inline]
+    #[must_use]
+    fn failed(handshake_millis: Option<i32>, error: String) -> Self {
+        Self {
+            success: false,
+            handshake_millis,
+            error: Some(error),
+        }
+    }
+}
+
+/// Tests the downstream destination of the given [`state::Output`] by pushing
+/// a generated test pattern with silence onto it for the given `duration`.
+///
+/// Only live `rtmp://`, `rtmps://` and `srt://` destinations are supported.
+///
+/// [`state::Output`]'s TLS and reconnect options are respected, so the test
+/// behaves exactly as the real re-streaming would.
+pub async fn test_output(
+    ffmpeg: &dyn CommandFactory,
+    output: &state::Output,
+    duration: Duration,
+) -> OutputTestResult {
+    let dst: Url = output.dst.clone().into();
+    let format = match dst.scheme() {
+        "rtmp" | "rtmps" => "flv",
+        "srt" => "mpegts",
+        s => {
+            return OutputTestResult::failed(
+                None,
+                format!("Testing `{s}://` destinations is not supported"),
+            )
+        }
+    };
+
+    let mut cmd = ffmpeg.command();
+    let _ = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .args(["-hide_banner", "-loglevel", "info", "-nostats"])
+        .args(["-re", "-f", "lavfi"])
+        .args(["-i", "testsrc2=size=1280x720:rate=30"])
+        .args(["-re", "-f", "lavfi"])
+        .args(["-i", "anullsrc=channel_layout=stereo:sample_rate=48000"])
+        .args(["-t", &duration.as_secs().to_string()])
+        .args(["-c:v", "libx264", "-preset", "ultrafast"])
+        .args(["-pix_fmt", "yuv420p", "-g", "60"])
+        .args(["-c:a", "aac", "-b:a", "128k"]);
+    if dst.scheme() == "rtmps" {
+        if let Some(tls) = output.tls.as_ref() {
+            tls.setup_ffmpeg(&mut cmd);
+        }
+    }
+    if let Some(reconnect) = output.reconnect.as_ref() {
+        reconnect.setup_ffmpeg(&mut cmd, &dst);
+    }
+    let _ = cmd.args(["-f", format]).arg(dst.as_str());
+
+    let started = Instant::now();
+    let mut process = match cmd.spawn() {
+        Ok(p) => p,
+        Err(e) => {
+            return OutputTestResult::failed(
+                None,
+                format!("Failed to spawn FFmpeg: {e}"),
+            )
+        }
+    };
+    let mut lines = BufReader::new(process.stderr.take().unwrap()).lines();
+
+    let mut handshake = None;
+    let mut last_lines = VecDeque::with_capacity(MAX_ERROR_LINES);
+    let run = async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            // FFmpeg reports the output only once it has been opened, which
+            // means the connection is established and the handshake passed.
+            if handshake.is_none() && line.starts_with("Output #0") {
+                handshake = Some(started.elapsed());
+            }
+            if last_lines.len() == MAX_ERROR_LINES {
+                let _ = last_lines.pop_front();
+            }
+            last_lines.push_back(line);
+        }
+        process.wait().await
+    };
+    let status = time::timeout(duration + GRACE_TIMEOUT, run).await;
+
+    let handshake_millis =
+        handshake.map(|d| i32::try_from(d.as_millis()).unwrap_or(i32::MAX));
+    let logs = Vec::from(last_lines).join("\n");
+    match status {
+        Ok(Ok(s)) if s.success() => OutputTestResult {
+            success: true,
+            handshake_millis,
+            error: None,
+        },
+        Ok(Ok(s)) => OutputTestResult::failed(
+            handshake_millis,
+            format!("FFmpeg exited with {s}:\n{logs}"),
+        ),
+        Ok(Err(e)) => {
+            log::error!("Failed to await FFmpeg process: {}", e);
+            OutputTestResult::failed(
+                handshake_millis,
+                format!("Failed to await FFmpeg: {e}"),
+            )
+        }
+        Err(_) => OutputTestResult::failed(
+            handshake_millis,
+            format!("Timed out pushing onto destination:\n{logs}"),
+        ),
+    }
+}
@@ -0,0 +1,118 @@
+//! Kind of a [FFmpeg] re-streaming process that broadcasts a playlist of local
+//! pre-recorded files to a URL endpoint, optionally looping it.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use tokio::{fs, io, process::Command};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{runtime_dir, state::FfmpegLogLevel};
+
+/// Kind of a [FFmpeg] re-streaming process that broadcasts a playlist of local
+/// pre-recorded files to a URL endpoint, optionally looping it.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlaylistRestreamer {
+    /// ID of an element in a [`State`] this [`PlaylistRestreamer`] process is
+    /// related to.
+    ///
+    /// [`State`]: crate::state::State
+    pub id: Uuid,
+
+    /// `file:///` [`Url`]s of the files to be broadcast, in order.
+    pub files: Vec<Url>,
+
+    /// Indicator whether the playlist should be looped endlessly.
+    pub looped: bool,
+
+    /// [`Url`] to publish the broadcast live stream onto.
+    pub to_url: Url,
+
+    /// [`FfmpegLogLevel`] to run [FFmpeg] process with, overriding the
+    /// server's one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub log_level: Option<FfmpegLogLevel>,
+}
+
+impl PlaylistRestreamer {
+    /// Checks whether this [`PlaylistRestreamer`] process must be restarted,
+    /// as cannot apply the new `actual` params on itself correctly, without
+    /// interruptions.
+    #[inline]
+    #[must_use]
+    pub fn needs_restart(&self, actual: &Self) -> bool {
+        self != actual
+    }
+
+    /// Returns path to the [concat demuxer][1] list file of this
+    /// [`PlaylistRestreamer`].
+    ///
+    /// [1]: https://ffmpeg.org/ffmpeg-formats.html#concat-1
+    #[must_use]
+    pub fn list_path(&self) -> PathBuf {
+        runtime_dir::path().join(format!(
+            "{}playlist_{}.txt",
+            runtime_dir::PREFIX,
+            self.id,
+        ))
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] for this
+    /// [`PlaylistRestreamer`] before running it.
+    ///
+    /// Multiple files are played via [concat demuxer][1], so the list file is
+    /// written before running.
+    ///
+    /// # Errors
+    ///
+    /// If the list file fails to be written.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [1]: https://ffmpeg.org/ffmpeg-formats.html#concat-1
+    pub(crate) async fn setup_ffmpeg(
+        &self,
+        cmd: &mut Command,
+    ) -> io::Result<()> {
+        if let Some(level) = self.log_level {
+            level.setup_ffmpeg(cmd);
+        }
+
+        // Play files with their native frame rate, as a live stream is
+        // expected to be produced.
+        let _ = cmd.arg("-re");
+        if self.looped {
+            let _ = cmd.args(["-stream_loop", "-1"]);
+        }
+
+        let paths = self
+            .files
+            .iter()
+            .map(|f| f.to_file_path().unwrap_or_else(|()| f.path().into()));
+        if self.files.len() == 1 {
+            let _ = cmd.arg("-i").args(paths);
+        } else {
+            let mut list = String::new();
+            for path in paths {
+                // Single quotes should be escaped in the concat list.
+                let path = path.display().to_string().replace('\'', "'\\''");
+                let _ = writeln!(list, "file '{path}'");
+            }
+            let list_path = self.list_path();
+            fs::write(&list_path, list).await?;
+            let _ = cmd
+                .args(["-f", "concat", "-safe", "0", "-i"])
+                .arg(list_path);
+        }
+
+        let _ = cmd
+            .args(["-c", "copy"])
+            .args(["-f", "flv"])
+            .arg(self.to_url.as_str());
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+//! Tracking of `-progress` reports of [FFmpeg] re-streaming processes, and
+//! detection of the ones being frozen.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::future;
+use once_cell::sync::OnceCell;
+use tokio::{
+    io::{AsyncBufReadExt as _, BufReader},
+    process::ChildStdout,
+    sync::watch,
+    time,
+};
+use uuid::Uuid;
+
+use crate::ffmpeg::traffic;
+
+/// Duration of [FFmpeg] processes not advancing their output, after which
+/// they're considered frozen.
+///
+/// [FFmpeg]: https://ffmpeg.org
+static STALL_TIMEOUT: OnceCell<Duration> = OnceCell::new();
+
+/// Sets the global duration of [FFmpeg] processes not advancing their output,
+/// after which they're considered frozen and restarted.
+///
+/// Frozen processes are not detected, if not set.
+///
+/// # Errors
+///
+/// If the global duration has been set already.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub fn set_stall_timeout(timeout: Duration) -> anyhow::Result<()> {
+    STALL_TIMEOUT
+        .set(timeout)
+        .map_err(|_| anyhow!("FFmpeg stall timeout has been set already"))
+}
+
+/// Tracks `-progress` reports of the [FFmpeg] process related to the element
+/// with the given `id` in a [`State`], read from the given `stdout` until it
+/// closes.
+///
+/// Accounts the bytes pushed by the process, and returns the time (in
+/// microseconds) of its output, updated only once it advances.
+///
+/// [`State`]: crate::state::State
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) fn track(id: Uuid, stdout: ChildStdout) -> watch::Receiver<u64> {
+    let (out_time_tx, out_time_rx) = watch::channel(0);
+    drop(tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut last_size = 0;
+        while let Ok(Some(line)) = lines.next_line().await {
+            let (key, val) = match line.split_once('=') {
+                Some(kv) => kv,
+                None => continue,
+            };
+            // `N/A` is reported until the output is opened.
+            let val = match val.parse::<u64>() {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            match key {
+                "total_size" if val > last_size => {
+                    traffic::account(id, val - last_size);
+                    last_size = val;
+                }
+                // `out_time_ms` is reported in microseconds too, by older
+                // FFmpeg versions.
+                "out_time_us" | "out_time_ms" => {
+                    let _ = out_time_tx.send_if_modified(|t| {
+                        let advanced = val > *t;
+                        if advanced {
+                            *t = val;
+                        }
+                        advanced
+                    });
+                }
+                _ => {}
+            }
+        }
+    }));
+    out_time_rx
+}
+
+/// Resolves once the time of the [FFmpeg] process output, as returned by
+/// [`track()`], stops advancing for the global stall timeout.
+///
+/// Never resolves if no stall timeout has been set, or the process stops
+/// reporting its progress (being exited).
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(crate) async fn stalled(mut out_time: watch::Receiver<u64>) -> Duration {
+    let timeout = match STALL_TIMEOUT.get() {
+        Some(t) if !t.is_zero() => *t,
+        _ => return future::pending().await,
+    };
+    loop {
+        match time::timeout(timeout, out_time.changed()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => return future::pending().await,
+            Err(_) => return timeout,
+        }
+    }
+}
@@ -3,18 +3,17 @@
 //! [FFmpeg]: https://ffmpeg.org
 
 use chrono::{DateTime, Utc};
-use std::{
-    panic::AssertUnwindSafe, path::Path, process::Stdio, time::Duration,
-};
+use std::{io, panic::AssertUnwindSafe, process::Stdio, time::Duration};
 
 use ephyr_log::log;
 use futures::{future, pin_mut, FutureExt as _, TryFutureExt as _};
-use tokio::{process::Command, sync::watch, time};
+use tokio::{sync::watch, time};
+use tracing::{field, Instrument as _};
 
 use crate::{
     display_panic,
-    ffmpeg::restreamer_kind::RestreamerKind,
-    state::{State, Status},
+    ffmpeg::{restreamer_kind::RestreamerKind, CommandFactory},
+    state::{State, Status, StatusCounters},
 };
 
 /// Status of [Restreamer] process
@@ -62,10 +61,16 @@ impl Restreamer {
     /// background. Once this [`Restreamer`] is dropped, its [FFmpeg] process is
     /// killed with SIGTERM or aborted.
     ///
+    /// Each (re)spawn creates a new [`Command`] with the given
+    /// [`CommandFactory`], so picks up a replacement of the [FFmpeg]
+    /// [`Binary`].
+    ///
+    /// [`Binary`]: crate::ffmpeg::Binary
+    /// [`Command`]: tokio::process::Command
     /// [FFmpeg]: https://ffmpeg.org
     #[must_use]
-    pub fn run<P: AsRef<Path> + Send + 'static>(
-        ffmpeg_path: P,
+    pub fn run(
+        ffmpeg: &'static dyn CommandFactory,
         kind: RestreamerKind,
         state: State,
     ) -> Self {
@@ -73,14 +78,27 @@ impl Restreamer {
         let kind_for_spawn = kind.clone();
         let mut time_of_fail: Option<DateTime<Utc>> = None;
         let (kill_tx, kill_rx) = watch::channel(RestreamerStatus::Started);
+        let mut is_restart = false;
 
         let (spawner, abort_if_hanged) = future::abortable(async move {
             let kill_rx_for_loop = kill_rx.clone();
             loop {
                 let (kind, state) = (&kind_for_spawn, &state);
-                let mut cmd = Command::new(ffmpeg_path.as_ref());
+                let mut cmd = ffmpeg.command();
                 let kill_rx_for_ffmpeg = kill_rx.clone();
 
+                if is_restart {
+                    kind.renew_counters(state, StatusCounters::track_restart);
+                }
+                // Covers a single FFmpeg process lifetime, from its spawning
+                // till its exit.
+                let span = tracing::info_span!(
+                    "ffmpeg",
+                    id = %kind.id(),
+                    restart = is_restart,
+                    error = field::Empty,
+                );
+
                 let _ = AssertUnwindSafe(
                     async move {
                         Self::change_status(
@@ -88,12 +106,14 @@ impl Restreamer {
                             kind,
                             state,
                             Status::Initializing,
+                            None,
                         );
 
                         kind.setup_ffmpeg(
                             cmd.kill_on_drop(true)
+                                .args(["-progress", "pipe:1"])
                                 .stdin(Stdio::null())
-                                .stdout(Stdio::null())
+                                .stdout(Stdio::piped())
                                 .stderr(Stdio::piped()),
                             state,
                         )
@@ -102,17 +122,26 @@ impl Restreamer {
                                 "Failed to setup FFmpeg re-streamer: {}",
                                 e,
                             );
+                            (
+                                format!("Failed to setup FFmpeg: {e}"),
+                                Status::Offline,
+                            )
                         })
                         .await?;
 
-                        let running = kind.run_ffmpeg(cmd, kill_rx_for_ffmpeg);
+                        let running =
+                            kind.run_ffmpeg(cmd, kill_rx_for_ffmpeg, state);
                         pin_mut!(running);
 
                         let set_online = async move {
                             // If ffmpeg process does not fail within 10 sec
                             // than set `Online` status.
                             time::sleep(Duration::from_secs(10)).await;
-                            kind.renew_status(Status::Online, state);
+                            kind.renew_status(
+                                Status::Online,
+                                Some("FFmpeg is running without failures"),
+                                state,
+                            );
 
                             future::pending::<()>().await;
                             Ok(())
@@ -122,24 +151,39 @@ impl Restreamer {
                         future::try_select(running, set_online)
                             .await
                             .map_err(|e| {
+                                let e = e.factor_first().0;
                                 log::error!(
                                     "Failed to run FFmpeg re-streamer: {}",
-                                    e.factor_first().0,
+                                    e,
                                 );
+                                // Frozen process is killed by the watchdog,
+                                // so is restarted rather than gone offline.
+                                let status =
+                                    if e.kind() == io::ErrorKind::TimedOut {
+                                        Status::Unstable
+                                    } else {
+                                        Status::Offline
+                                    };
+                                (e.to_string(), status)
                             })
                             .map(|r| r.factor_first().0)
                     }
-                    .unwrap_or_else(|_| {
+                    .unwrap_or_else(|(e, status)| {
+                        let _ = tracing::Span::current()
+                            .record("error", e.as_str());
                         Self::change_status(
                             time_of_fail,
                             kind,
                             state,
-                            Status::Offline,
+                            status,
+                            Some(&e),
                         );
+                        kind.renew_counters(state, |c| c.track_error(&e));
                         time_of_fail = Some(Utc::now());
                     }),
                 )
                 .catch_unwind()
+                .instrument(span)
                 .await
                 .map_err(|p| {
                     log::crit!(
@@ -154,12 +198,17 @@ impl Restreamer {
                 }
 
                 time::sleep(Duration::from_secs(2)).await;
+                is_restart = true;
             }
         });
 
         // Spawn FFmpeg re-streamer manager as a child process.
         drop(tokio::spawn(spawner.map(move |_| {
-            kind_for_abort.renew_status(Status::Offline, &state_for_abort);
+            kind_for_abort.renew_status(
+                Status::Offline,
+                Some("Re-streaming stopped"),
+                &state_for_abort,
+            );
         })));
 
         Self {
@@ -179,6 +228,7 @@ impl Restreamer {
         kind: &RestreamerKind,
         state: &State,
         new_status: Status,
+        reason: Option<&str>,
     ) {
         match time_of_fail {
             Some(dt) => {
@@ -189,10 +239,10 @@ impl Restreamer {
                 } else {
                     new_status
                 };
-                kind.renew_status(status, state);
+                kind.renew_status(status, reason, state);
             }
             None => {
-                kind.renew_status(new_status, state);
+                kind.renew_status(new_status, reason, state);
             }
         }
     }
@@ -201,6 +251,9 @@ impl Restreamer {
 impl Drop for Restreamer {
     /// Send signal that [`Restreamer`] process is finished
     fn drop(&mut self) {
+        let _span =
+            tracing::info_span!("ffmpeg_kill", id = %self.kind.id()).entered();
+
         // Send notification to kill FFMPEG with SIGTERM
         log::debug!("Send signal to FFmpeg's");
         let _ = self.kill_tx.send(RestreamerStatus::Finished);
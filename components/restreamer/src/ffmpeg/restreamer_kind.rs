@@ -5,12 +5,8 @@
 
 use derive_more::From;
 use ephyr_log::log;
-use libc::pid_t;
-use nix::{
-    sys::{signal, signal::Signal},
-    unistd::Pid,
-};
-use std::{convert::TryInto, os::unix::process::ExitStatusExt, time::Duration};
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, path::Path, sync::Mutex, time::Duration};
 use tokio::{io, process::Command, sync::watch};
 use url::Url;
 use uuid::Uuid;
@@ -18,13 +14,25 @@ use uuid::Uuid;
 use crate::{
     dvr,
     ffmpeg::{
-        copy_restreamer::CopyRestreamer, mixing_restreamer::MixingRestreamer,
+        audio_meter, copy_restreamer::CopyRestreamer,
+        fallback_restreamer::FallbackRestreamer, hw_accel::HwAccel,
+        mixing_restreamer::MixingRestreamer,
+        playlist_restreamer::PlaylistRestreamer, progress,
         restreamer::RestreamerStatus,
         transcoding_restreamer::TranscodingRestreamer,
     },
-    state::{self, RestreamKey, State, Status},
+    process::{self, Exit, Pid},
+    srs,
+    state::{self, State, Status, StatusCounters},
 };
 
+/// PIDs of the currently running [FFmpeg] processes, identified by IDs of the
+/// elements in a [`State`] they're related to.
+///
+/// [FFmpeg]: https://ffmpeg.org
+static PIDS: Lazy<Mutex<HashMap<Uuid, Pid>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 /// Data of a concrete kind of a running [FFmpeg] process performing a
 /// re-streaming, that allows to spawn and re-spawn it at any time.
 ///
@@ -44,6 +52,13 @@ pub enum RestreamerKind {
     /// Mixing a live stream from one URL endpoint with additional live streams
     /// and re-streaming the result to another endpoint.
     Mixing(MixingRestreamer),
+
+    /// Broadcasting a playlist of local pre-recorded files to an endpoint.
+    Playlist(PlaylistRestreamer),
+
+    /// Broadcasting a fallback source of a `Restream` to an endpoint while
+    /// its `Input` is offline.
+    Fallback(FallbackRestreamer),
 }
 
 impl RestreamerKind {
@@ -57,6 +72,8 @@ impl RestreamerKind {
             Self::Copy(c) => c.id.into(),
             Self::Transcoding(c) => c.id.into(),
             Self::Mixing(m) => m.id.into(),
+            Self::Playlist(p) => p.id.into(),
+            Self::Fallback(f) => f.id.into(),
         }
     }
 
@@ -70,6 +87,8 @@ impl RestreamerKind {
             Self::Copy(c) => c.to_url.clone(),
             Self::Transcoding(t) => t.to_url.clone(),
             Self::Mixing(m) => m.to_url.clone(),
+            Self::Playlist(p) => p.to_url.clone(),
+            Self::Fallback(f) => f.to_url.clone(),
         }
     }
 
@@ -83,12 +102,58 @@ impl RestreamerKind {
             Self::Copy(c) => c.from_url.clone(),
             Self::Transcoding(t) => t.from_url.clone(),
             Self::Mixing(m) => m.from_url.clone(),
+            Self::Playlist(p) => p.files[0].clone(),
+            Self::Fallback(f) => f.src_url(),
         }
     }
 
+    /// Creates a new [FFmpeg] process transcoding a live stream of the given
+    /// [`state::Input`] into the given [`state::HlsRendition`] of its
+    /// [`state::InputEndpointKind::Hls`] endpoint.
+    ///
+    /// The given [`HwAccel`] is used for transcoding.
+    ///
+    /// Returns [`None`] if the [`state::Input`] is not ready to serve a live
+    /// stream yet.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn from_hls_rendition(
+        input: &state::Input,
+        endpoint: &state::InputEndpoint,
+        rendition: state::HlsRendition,
+        app: &str,
+        hw_accel: &HwAccel,
+    ) -> Option<Self> {
+        if !input.enabled || !input.is_ready_to_serve() {
+            return None;
+        }
+        Some(
+            TranscodingRestreamer {
+                id: rendition.process_id(endpoint.id),
+                from_url: state::InputEndpointKind::Rtmp
+                    .rtmp_url(app, &input.key),
+                to_url: rendition.rtmp_url(app, &input.key),
+                vcodec: Some("libx264".into()),
+                vprofile: Some("main".into()),
+                vpreset: Some("veryfast".into()),
+                acodec: Some("libfdk_aac".into()),
+                hw_accel: hw_accel.clone(),
+                ll_hls: None,
+                rendition: Some(rendition),
+                master_playlist: None,
+                log_level: input.log_level,
+            }
+            .into(),
+        )
+    }
+
     /// Creates a new [FFmpeg] process re-streaming a [`state::InputSrc`] to its
     /// [`state::Input`] endpoint.
     ///
+    /// The given [`HwAccel`] is used for transcoding, if the endpoint
+    /// requires it.
+    ///
     /// Returns [`None`] if a [FFmpeg] re-streaming process cannot not be
     /// created for the given [`state::Input`], or the later doesn't require it.
     ///
@@ -97,7 +162,8 @@ impl RestreamerKind {
     pub fn from_input(
         input: &state::Input,
         endpoint: &state::InputEndpoint,
-        key: &RestreamKey,
+        app: &str,
+        hw_accel: &HwAccel,
     ) -> Option<Self> {
         if !input.enabled {
             return None;
@@ -105,23 +171,76 @@ impl RestreamerKind {
 
         Some(match endpoint.kind {
             state::InputEndpointKind::Rtmp => {
+                let mut tuning = None;
+                let mut hls_variant = None;
                 let from_url = match input.src.as_ref()? {
+                    state::InputSrc::Playlist(p) => {
+                        let dvr = dvr::Storage::global();
+                        let files = p
+                            .files
+                            .iter()
+                            .map(|f| f.file_path(dvr).map(|_| f.clone().into()))
+                            .collect::<Option<Vec<_>>>();
+                        let files = match files {
+                            Some(files) => files,
+                            None => {
+                                log::error!(
+                                    "Playlist of {} Input contains files \
+                                     outside of DVR directory",
+                                    input.key,
+                                );
+                                return None;
+                            }
+                        };
+                        return Some(
+                            PlaylistRestreamer {
+                                id: endpoint.id.into(),
+                                files,
+                                looped: p.looped,
+                                to_url: endpoint.kind.rtmp_url(app, &input.key),
+                                log_level: input.log_level,
+                            }
+                            .into(),
+                        );
+                    }
                     state::InputSrc::Remote(remote) => {
+                        tuning = input.tuning.clone();
+                        hls_variant = remote.hls_variant;
                         remote.url.clone().into()
                     }
                     state::InputSrc::Failover(s) => {
-                        s.inputs.iter().find_map(|i| {
-                            i.endpoints.iter().find_map(|e| {
-                                (e.is_rtmp() && e.status == Status::Online)
-                                    .then(|| e.kind.rtmp_url(key, &i.key))
+                        let find_online = |ready_only: bool| {
+                            s.prioritized().into_iter().find_map(|i| {
+                                i.endpoints.iter().find_map(|e| {
+                                    (e.is_rtmp()
+                                        && e.status == Status::Online
+                                        && (!ready_only || e.is_failover_ready))
+                                        .then(|| e.kind.rtmp_url(app, &i.key))
+                                })
                             })
-                        })?
+                        };
+                        // With hysteresis, restored `Input`s are switched
+                        // back to only once they're ready, while any online
+                        // `Input` is still better than none.
+                        if s.hysteresis.is_some() {
+                            find_online(true).or_else(|| find_online(false))?
+                        } else {
+                            find_online(false)?
+                        }
                     }
                 };
                 CopyRestreamer {
                     id: endpoint.id.into(),
                     from_url,
-                    to_url: endpoint.kind.rtmp_url(key, &input.key),
+                    tuning,
+                    hls_variant,
+                    to_url: endpoint.kind.rtmp_url(app, &input.key),
+                    tls: None,
+                    reconnect: None,
+                    icecast: None,
+                    loudnorm: None,
+                    log_level: input.log_level,
+                    extra_args: Vec::new(),
                 }
                 .into()
             }
@@ -133,15 +252,72 @@ impl RestreamerKind {
                 TranscodingRestreamer {
                     id: endpoint.id.into(),
                     from_url: state::InputEndpointKind::Rtmp
-                        .rtmp_url(key, &input.key),
-                    to_url: endpoint.kind.rtmp_url(key, &input.key),
+                        .rtmp_url(app, &input.key),
+                    to_url: endpoint.kind.rtmp_url(app, &input.key),
                     vcodec: Some("libx264".into()),
                     vprofile: Some("baseline".into()),
                     vpreset: Some("superfast".into()),
                     acodec: Some("libfdk_aac".into()),
+                    hw_accel: hw_accel.clone(),
+                    ll_hls: endpoint.ll_hls.and_then(|ll_hls| {
+                        Some((ll_hls, srs::ll_hls_dir(app, &input.key)?))
+                    }),
+                    rendition: None,
+                    master_playlist: srs::hls_dir(app)
+                        .filter(|_| !endpoint.hls_renditions.is_empty())
+                        .map(|dir| {
+                            let name =
+                                state::HlsRendition::master_playlist_name(
+                                    &input.key,
+                                );
+                            let playlist = state::HlsRendition::master_playlist(
+                                &endpoint.hls_renditions,
+                                &input.key,
+                            );
+                            (playlist, dir.join(name))
+                        }),
+                    log_level: input.log_level,
+                }
+                .into()
+            }
+
+            state::InputEndpointKind::Whip => {
+                // Status of the endpoint is renewed by this re-streaming
+                // process itself, so only the actual publisher is considered.
+                endpoint.srs_publisher_id.as_ref()?;
+                CopyRestreamer {
+                    id: endpoint.id.into(),
+                    from_url: endpoint.kind.rtmp_url(app, &input.key),
+                    tuning: None,
+                    hls_variant: None,
+                    to_url: state::InputEndpointKind::Rtmp
+                        .rtmp_url(app, &input.key),
+                    tls: None,
+                    reconnect: None,
+                    icecast: None,
+                    loudnorm: None,
+                    log_level: input.log_level,
+                    extra_args: Vec::new(),
                 }
                 .into()
             }
+
+            state::InputEndpointKind::Srt => CopyRestreamer {
+                id: endpoint.id.into(),
+                // Not listened on until some port is assigned.
+                from_url: endpoint.srt_listener_url()?,
+                tuning: None,
+                hls_variant: None,
+                to_url: state::InputEndpointKind::Rtmp
+                    .rtmp_url(app, &input.key),
+                tls: None,
+                reconnect: None,
+                icecast: None,
+                loudnorm: None,
+                log_level: input.log_level,
+                extra_args: Vec::new(),
+            }
+            .into(),
         })
     }
 
@@ -161,7 +337,7 @@ impl RestreamerKind {
         from_url: &Url,
         prev: Option<&RestreamerKind>,
     ) -> Option<Self> {
-        if !output.enabled {
+        if !output.enabled || output.shed {
             return None;
         }
 
@@ -169,7 +345,15 @@ impl RestreamerKind {
             CopyRestreamer {
                 id: output.id.into(),
                 from_url: from_url.clone(),
+                tuning: None,
+                hls_variant: None,
                 to_url: Self::dst_url(output),
+                tls: output.tls.clone(),
+                reconnect: output.reconnect.clone(),
+                icecast: output.icecast.clone(),
+                loudnorm: output.loudnorm,
+                log_level: output.log_level,
+                extra_args: output.extra_ffmpeg_args.clone(),
             }
             .into()
         } else {
@@ -177,6 +361,46 @@ impl RestreamerKind {
         })
     }
 
+    /// Creates a new [FFmpeg] process broadcasting the given
+    /// [`state::RestreamFallback`] to the given [`state::Output::dst`]
+    /// endpoint, with its file resolved inside the given `media_dir`.
+    ///
+    /// Returns [`None`] if a [FFmpeg] re-streaming process cannot not be
+    /// created for the given [`state::Output`], or it's not a live one (like
+    /// a DVR recording), or the required file cannot be resolved.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn from_fallback(
+        output: &state::Output,
+        fallback: &state::RestreamFallback,
+        media_dir: &Path,
+    ) -> Option<Self> {
+        if !output.enabled || output.shed {
+            return None;
+        }
+        if !matches!(output.dst.scheme(), "rtmp" | "rtmps" | "srt") {
+            return None;
+        }
+        let file = fallback.file_path(media_dir);
+        if fallback.kind.requires_file() && file.is_none() {
+            return None;
+        }
+
+        Some(
+            FallbackRestreamer {
+                id: output.id.into(),
+                fallback: fallback.clone(),
+                file,
+                to_url: output.dst.clone().into(),
+                tls: output.tls.clone(),
+                log_level: output.log_level,
+                extra_args: output.extra_ffmpeg_args.clone(),
+            }
+            .into(),
+        )
+    }
+
     /// Extracts the correct [`Url`] acceptable by [FFmpeg] for sinking a live
     /// stream by the given [`state::Output`].
     ///
@@ -202,6 +426,12 @@ impl RestreamerKind {
                 old.needs_restart(new)
             }
             (Self::Mixing(old), Self::Mixing(new)) => old.needs_restart(new),
+            (Self::Playlist(old), Self::Playlist(new)) => {
+                old.needs_restart(new)
+            }
+            (Self::Fallback(old), Self::Fallback(new)) => {
+                old.needs_restart(new)
+            }
             _ => true,
         }
     }
@@ -227,6 +457,8 @@ impl RestreamerKind {
             Self::Copy(c) => c.setup_ffmpeg(cmd).await?,
             Self::Transcoding(c) => c.setup_ffmpeg(cmd),
             Self::Mixing(m) => m.setup_ffmpeg(cmd, state).await?,
+            Self::Playlist(p) => p.setup_ffmpeg(cmd).await?,
+            Self::Fallback(f) => f.setup_ffmpeg(cmd)?,
         };
         Ok(())
     }
@@ -236,9 +468,11 @@ impl RestreamerKind {
     /// Returns [`Ok`] if the [`kill_rx`] was sent and the ffmpeg process
     /// was stopped properly or if the entire input file was played to the end.
     ///
-    /// In case of [`Self::Mixin`] before starting [`Command`]
-    /// the FIFO files are created. For each pair of [`Mixin`] and FIFO the
-    /// new task are created and transfer data from [`Mixin.stdin`] to FIFO.
+    /// In case of [`Self::Mixin`] before starting [`Command`] a new task is
+    /// created for each [`Mixin`], transferring data from [`Mixin.stdin`] to
+    /// its pipe.
+    /// Audio levels measured by its [FFmpeg] process are reported into the
+    /// given [`State`] while it runs.
     ///
     /// # Errors
     ///
@@ -251,12 +485,42 @@ impl RestreamerKind {
         &self,
         cmd: Command,
         kill_rx: watch::Receiver<RestreamerStatus>,
+        state: &State,
     ) -> io::Result<()> {
         if let Self::Mixing(m) = self {
-            m.start_fed_mixins_fifo(&kill_rx);
+            m.start_feeding_mixins(&kill_rx);
         }
 
-        Self::run_ffmpeg_(cmd, kill_rx).await
+        // Once a non-looped playlist is played to the end, it shouldn't be
+        // re-spawned again, so we just wait for being stopped.
+        if let Self::Playlist(p) = self {
+            if !p.looped {
+                let mut wait_rx = kill_rx.clone();
+                let _ = *wait_rx.borrow_and_update();
+                Self::run_ffmpeg_(cmd, kill_rx, self.id()).await?;
+                let _ = wait_rx.changed().await;
+                return Ok(());
+            }
+        }
+
+        let running = Self::run_ffmpeg_(cmd, kill_rx, self.id());
+        let res = if let Self::Mixing(m) = self {
+            audio_meter::measure_while(running, &m.audio_meters(), state).await
+        } else {
+            running.await
+        };
+
+        let to_url = self.to_url();
+        if to_url.scheme() == "file" {
+            // Let the chained recording be continued by the next run, if it's
+            // restarted soon enough.
+            if dvr::is_chained(&to_url) {
+                dvr::stop_chain(&to_url);
+            }
+            dvr::Storage::global().generate_thumbnails_on_finish(self.id());
+        }
+
+        res
     }
 
     /// Properly runs the given [FFmpeg] [`Command`] awaiting its completion.
@@ -269,50 +533,85 @@ impl RestreamerKind {
     /// It can return an [`io::Error`] if something unexpected happened and the
     /// [FFmpeg] process was stopped.
     ///
+    /// Returns an [`io::ErrorKind::TimedOut`] error if the [FFmpeg] process
+    /// has been killed due to being frozen.
+    ///
     /// [FFmpeg]: https://ffmpeg.org
     async fn run_ffmpeg_(
         mut cmd: Command,
         mut kill_rx: watch::Receiver<RestreamerStatus>,
+        id: Uuid,
     ) -> io::Result<()> {
-        let process = cmd.spawn()?;
+        let mut process = cmd.spawn()?;
+        let out_time = process
+            .stdout
+            .take()
+            .map(|stdout| progress::track(id, stdout));
 
         // To avoid instant resolve on await for `kill_rx`
         let _ = *kill_rx.borrow_and_update();
 
-        let pid: pid_t = process
-            .id()
-            .expect("Failed to retrieve Process ID")
-            .try_into()
-            .expect("Failed to convert u32 to i32");
+        let pid = process.id().expect("Failed to retrieve Process ID");
 
-        // Task that sends SIGTERM if async stop of ffmpeg was invoked
+        // Task that stops ffmpeg gracefully if async stop was invoked
         let kill_task = tokio::spawn(async move {
             let _ = kill_rx.changed().await;
             log::debug!("Signal for FFmpeg received");
-            // It is necessary to send the signal two times and wait after
-            // sending the first one to correctly close all ffmpeg processes
-            signal::kill(Pid::from_raw(pid), Signal::SIGTERM)
-                .expect("Failed to kill process");
+            // It is necessary to request the stop two times and wait after
+            // the first one to correctly close all ffmpeg processes
+            if !process::stop(pid) {
+                log::error!("Failed to stop FFmpeg process {}", pid);
+            }
             tokio::time::sleep(Duration::from_millis(1)).await;
-            signal::kill(Pid::from_raw(pid), Signal::SIGTERM)
-                .expect("Failed to kill process");
+            let _ = process::stop(pid);
         });
 
-        let out = process.wait_with_output().await?;
-        kill_task.abort();
+        // Kills the FFmpeg process once it stops advancing its output, as it
+        // may stay alive without forwarding any frames.
+        let watchdog = tokio::spawn(async move {
+            let stall = match out_time {
+                Some(t) => progress::stalled(t).await,
+                None => return None,
+            };
+            log::warn!(
+                "FFmpeg re-streamer of {} is frozen for {}s, so killed",
+                id,
+                stall.as_secs(),
+            );
+            let _ = process::kill(pid);
+            Some(stall)
+        });
 
-        let status_code = out.status.code();
-        let signal_code = out.status.signal();
-        if out.status.success()
-            || status_code.and_then(|v| (v == 255).then_some(())).is_some()
-            || signal_code.and_then(|v| (v == 15).then_some(())).is_some()
+        let _ = PIDS.lock().unwrap().insert(id, pid);
+        let out = process.wait_with_output().await;
+        watchdog.abort();
         {
+            let mut pids = PIDS.lock().unwrap();
+            if pids.get(&id) == Some(&pid) {
+                let _ = pids.remove(&id);
+            }
+        }
+        let out = out?;
+        kill_task.abort();
+
+        if let Ok(Some(stall)) = watchdog.await {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "FFmpeg re-streamer stopped advancing its output for {}s",
+                    stall.as_secs(),
+                ),
+            ));
+        }
+
+        let exit = Exit::of(out.status);
+        if exit.is_ok() {
             log::debug!(
                 "FFmpeg re-streamer successfully stopped\n\
-                        \t exit code: {:?}\n\
-                        \t signal code: {:?}",
-                status_code,
-                signal_code
+                        \t exit: {:?}\n\
+                        \t exit status: {}",
+                exit,
+                out.status,
             );
             Ok(())
         } else {
@@ -328,17 +627,63 @@ impl RestreamerKind {
         }
     }
 
+    /// Lists IDs of the elements in a [`State`] along with PIDs of the
+    /// [FFmpeg] processes currently running for them.
+    ///
+    /// # Panics
+    ///
+    /// If the PIDs registry lock is poisoned.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn running_processes() -> Vec<(Uuid, Pid)> {
+        PIDS.lock()
+            .unwrap()
+            .iter()
+            .map(|(id, pid)| (*id, *pid))
+            .collect()
+    }
+
+    /// Abruptly kills the running [FFmpeg] process related to the element
+    /// with the given `id` in a [`State`], simulating its crash.
+    ///
+    /// Returns `false` if there is no such process running.
+    ///
+    /// # Panics
+    ///
+    /// If the PIDs registry lock is poisoned.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn kill_process(id: Uuid) -> bool {
+        let pid = if let Some(pid) = PIDS.lock().unwrap().get(&id) {
+            *pid
+        } else {
+            return false;
+        };
+        let _span =
+            tracing::info_span!("ffmpeg_kill", %id, pid, on_demand = true)
+                .entered();
+        log::warn!("Killing FFmpeg process {} of {} on demand", pid, id);
+        process::kill(pid)
+    }
+
     /// Renews [`Status`] of this [FFmpeg] re-streaming process in the `actual`
-    /// [`State`].
+    /// [`State`], tracking the given `reason` of the transition.
     ///
     /// [FFmpeg]: https://ffmpeg.org
-    pub fn renew_status(&self, status: Status, actual: &State) {
+    pub fn renew_status(
+        &self,
+        status: Status,
+        reason: Option<&str>,
+        actual: &State,
+    ) {
         for restream in actual.restreams.lock_mut().iter_mut() {
             if !restream.outputs.is_empty() {
                 let my_id = self.id();
                 for o in &mut restream.outputs {
                     if o.id == my_id {
-                        o.status = status;
+                        o.set_status(status, reason);
                         return;
                     }
                 }
@@ -348,10 +693,32 @@ impl RestreamerKind {
             if status != Status::Online {
                 if let Some(endpoint) = restream.input.find_endpoint(self.id())
                 {
-                    endpoint.status = status;
+                    endpoint.set_status(status, reason);
                     return;
                 }
             }
         }
     }
+
+    /// Renews [`StatusCounters`] of this [FFmpeg] re-streaming process in the
+    /// `actual` [`State`] with the given function.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn renew_counters<F>(&self, actual: &State, renew: F)
+    where
+        F: FnOnce(&mut StatusCounters),
+    {
+        for restream in actual.restreams.lock_mut().iter_mut() {
+            let my_id = self.id();
+            if let Some(o) = restream.outputs.iter_mut().find(|o| o.id == my_id)
+            {
+                renew(&mut o.counters);
+                return;
+            }
+            if let Some(endpoint) = restream.input.find_endpoint(self.id()) {
+                renew(&mut endpoint.counters);
+                return;
+            }
+        }
+    }
 }
@@ -9,7 +9,10 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    ffmpeg::{restreamer::Restreamer, restreamer_kind::RestreamerKind},
+    ffmpeg::{
+        restreamer::Restreamer, restreamer_kind::RestreamerKind,
+        CommandFactory, HwAccel,
+    },
     state::{self, State},
 };
 use std::result::Result::Err;
@@ -19,10 +22,18 @@ use std::result::Result::Err;
 /// [FFmpeg]: https://ffmpeg.org
 #[derive(Debug)]
 pub struct RestreamersPool {
-    /// Path to a [FFmpeg] binary used for spawning processes.
+    /// Factory of [FFmpeg] commands used for spawning processes.
     ///
     /// [FFmpeg]: https://ffmpeg.org
-    ffmpeg_path: PathBuf,
+    ffmpeg: &'static dyn CommandFactory,
+
+    /// [`HwAccel`] to be used by transcoding [FFmpeg] processes.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    hw_accel: HwAccel,
+
+    /// Path to the directory with media files of [`state::RestreamFallback`]s.
+    media_dir: PathBuf,
 
     /// Pool of currently running [FFmpeg] re-streaming processes identified by
     /// an ID of the correspondent element in a [`State`].
@@ -43,9 +54,16 @@ impl RestreamersPool {
     /// Creates a new [`RestreamersPool`] out of the given parameters.
     #[inline]
     #[must_use]
-    pub fn new<P: Into<PathBuf>>(ffmpeg_path: P, state: State) -> Self {
+    pub fn new(
+        ffmpeg: &'static dyn CommandFactory,
+        hw_accel: HwAccel,
+        media_dir: PathBuf,
+        state: State,
+    ) -> Self {
         Self {
-            ffmpeg_path: ffmpeg_path.into(),
+            ffmpeg,
+            hw_accel,
+            media_dir,
             pool: HashMap::new(),
             state,
         }
@@ -60,9 +78,17 @@ impl RestreamersPool {
         let mut new_pool = HashMap::with_capacity(self.pool.len() + 1);
 
         for r in restreams {
-            self.apply_input(&r.key, &r.input, &mut new_pool);
+            self.apply_input(&r.srs_app(), &r.input, &mut new_pool);
 
-            if !r.input.enabled || !r.input.is_ready_to_serve() {
+            if !r.input.enabled {
+                continue;
+            }
+            if !r.input.is_ready_to_serve() {
+                if let Some(fallback) = &r.fallback {
+                    for o in &r.outputs {
+                        let _ = self.apply_fallback(fallback, o, &mut new_pool);
+                    }
+                }
                 continue;
             }
 
@@ -84,6 +110,17 @@ impl RestreamersPool {
         self.pool = new_pool;
     }
 
+    /// Terminates all the [FFmpeg] re-streaming processes of this
+    /// [`RestreamersPool`].
+    ///
+    /// Each process is sent `SIGTERM` first, and is killed with `SIGKILL` if
+    /// it doesn't exit in time.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn terminate_all(&mut self) {
+        self.pool.clear();
+    }
+
     /// Traverses the given [`state::Input`] filling the `new_pool` with
     /// required [FFmpeg] re-streaming processes. Tries to preserve already
     /// running [FFmpeg] processes in its `pool` as much as possible.
@@ -91,17 +128,22 @@ impl RestreamersPool {
     /// [FFmpeg]: https://ffmpeg.org
     fn apply_input(
         &mut self,
-        key: &state::RestreamKey,
+        app: &str,
         input: &state::Input,
         new_pool: &mut HashMap<Uuid, Restreamer>,
     ) {
         if let Some(state::InputSrc::Failover(s)) = &input.src {
             for i in &s.inputs {
-                self.apply_input(key, i, new_pool);
+                self.apply_input(app, i, new_pool);
             }
         }
         for endpoint in &input.endpoints {
-            let _ = self.apply_input_endpoint(key, input, endpoint, new_pool);
+            let _ = self.apply_input_endpoint(app, input, endpoint, new_pool);
+            for rendition in &endpoint.hls_renditions {
+                let _ = self.apply_hls_rendition(
+                    app, input, endpoint, *rendition, new_pool,
+                );
+            }
         }
     }
 
@@ -112,25 +154,58 @@ impl RestreamersPool {
     /// [FFmpeg]: https://ffmpeg.org
     fn apply_input_endpoint(
         &mut self,
-        key: &state::RestreamKey,
+        app: &str,
         input: &state::Input,
         endpoint: &state::InputEndpoint,
         new_pool: &mut HashMap<Uuid, Restreamer>,
     ) -> Option<()> {
         let id = endpoint.id.into();
 
-        let new_kind = RestreamerKind::from_input(input, endpoint, key)?;
+        let new_kind =
+            RestreamerKind::from_input(input, endpoint, app, &self.hw_accel)?;
+
+        let process = self
+            .pool
+            .remove(&id)
+            .and_then(|mut p| (!p.kind.needs_restart(&new_kind)).then_some(p))
+            .unwrap_or_else(|| {
+                Restreamer::run(self.ffmpeg, new_kind, self.state.clone())
+            });
+
+        let old_process = new_pool.insert(id, process);
+        drop(old_process);
+        Some(())
+    }
+
+    /// Inspects the given [`state::HlsRendition`] filling the `new_pool` with
+    /// a required [FFmpeg] transcoding process. Tries to preserve already
+    /// running [FFmpeg] processes in its `pool` as much as possible.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    fn apply_hls_rendition(
+        &mut self,
+        app: &str,
+        input: &state::Input,
+        endpoint: &state::InputEndpoint,
+        rendition: state::HlsRendition,
+        new_pool: &mut HashMap<Uuid, Restreamer>,
+    ) -> Option<()> {
+        let id = rendition.process_id(endpoint.id);
+
+        let new_kind = RestreamerKind::from_hls_rendition(
+            input,
+            endpoint,
+            rendition,
+            app,
+            &self.hw_accel,
+        )?;
 
         let process = self
             .pool
             .remove(&id)
             .and_then(|mut p| (!p.kind.needs_restart(&new_kind)).then_some(p))
             .unwrap_or_else(|| {
-                Restreamer::run(
-                    self.ffmpeg_path.clone(),
-                    new_kind,
-                    self.state.clone(),
-                )
+                Restreamer::run(self.ffmpeg, new_kind, self.state.clone())
             });
 
         let old_process = new_pool.insert(id, process);
@@ -166,11 +241,37 @@ impl RestreamersPool {
             .remove(&id)
             .and_then(|mut p| (!p.kind.needs_restart(&new_kind)).then_some(p))
             .unwrap_or_else(|| {
-                Restreamer::run(
-                    self.ffmpeg_path.clone(),
-                    new_kind,
-                    self.state.clone(),
-                )
+                Restreamer::run(self.ffmpeg, new_kind, self.state.clone())
+            });
+
+        let old_process = new_pool.insert(id, process);
+        drop(old_process);
+        Some(())
+    }
+
+    /// Inspects the given [`state::Output`] filling the `new_pool` with a
+    /// [FFmpeg] process broadcasting the given [`state::RestreamFallback`]
+    /// into it. Tries to preserve already running [FFmpeg] processes in its
+    /// `pool` as much as possible.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    fn apply_fallback(
+        &mut self,
+        fallback: &state::RestreamFallback,
+        output: &state::Output,
+        new_pool: &mut HashMap<Uuid, Restreamer>,
+    ) -> Option<()> {
+        let id = output.id.into();
+
+        let new_kind =
+            RestreamerKind::from_fallback(output, fallback, &self.media_dir)?;
+
+        let process = self
+            .pool
+            .remove(&id)
+            .and_then(|mut p| (!p.kind.needs_restart(&new_kind)).then_some(p))
+            .unwrap_or_else(|| {
+                Restreamer::run(self.ffmpeg, new_kind, self.state.clone())
             });
 
         let old_process = new_pool.insert(id, process);
@@ -178,3 +279,179 @@ impl RestreamersPool {
         Some(())
     }
 }
+
+#[cfg(test)]
+mod restreamers_pool_spec {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::{
+        ffmpeg::{
+            fake::{eventually, FakeFfmpeg},
+            HwAccel, RestreamerKind,
+        },
+        process::{self, Pid},
+        spec,
+        state::{Label, OutputDstUrl, State, Status},
+    };
+
+    use super::RestreamersPool;
+
+    /// Creates a new [`State`] with a single online `Restream` re-streaming
+    /// to a single `Output` with the given `dst`.
+    fn state_with_output(dst: &str) -> State {
+        let state = State::default();
+        state
+            .add_restream(
+                serde_json::from_value::<spec::v1::Restream>(json!({
+                    "key": "test",
+                    "input": {
+                        "key": "primary",
+                        "endpoints": [{"kind": "rtmp"}],
+                        "enabled": true,
+                    },
+                    "outputs": [{"dst": dst, "enabled": true}],
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+        for r in state.restreams.lock_mut().iter_mut() {
+            r.input.endpoints[0].status = Status::Online;
+        }
+        state
+    }
+
+    /// Returns ID of the single `Output` in the given [`State`].
+    fn output_id(state: &State) -> Uuid {
+        state.restreams.lock_ref()[0].outputs[0].id.into()
+    }
+
+    /// Returns PID of the process currently running for the given `id`.
+    fn pid_of(id: Uuid) -> Option<Pid> {
+        RestreamerKind::running_processes()
+            .into_iter()
+            .find_map(|(i, pid)| (i == id).then_some(pid))
+    }
+
+    /// Applies the actual [`State`] to the given [`RestreamersPool`].
+    fn apply(pool: &mut RestreamersPool, state: &State) {
+        pool.apply(&state.restreams.get_cloned());
+    }
+
+    #[tokio::test]
+    async fn spawns_process_for_output() {
+        let ffmpeg = FakeFfmpeg::leaked();
+        let state = state_with_output("rtmp://example.com/live/a");
+        let mut pool = RestreamersPool::new(
+            ffmpeg,
+            HwAccel::None,
+            PathBuf::new(),
+            state.clone(),
+        );
+
+        apply(&mut pool, &state);
+
+        let id = output_id(&state);
+        assert!(eventually(|| pid_of(id).is_some()).await);
+        assert_eq!(ffmpeg.spawns(), 1);
+    }
+
+    #[tokio::test]
+    async fn preserves_process_if_nothing_changed() {
+        let ffmpeg = FakeFfmpeg::leaked();
+        let state = state_with_output("rtmp://example.com/live/a");
+        let mut pool = RestreamersPool::new(
+            ffmpeg,
+            HwAccel::None,
+            PathBuf::new(),
+            state.clone(),
+        );
+
+        apply(&mut pool, &state);
+        let id = output_id(&state);
+        assert!(eventually(|| pid_of(id).is_some()).await);
+        let pid = pid_of(id);
+
+        state.restreams.lock_mut()[0].outputs[0].label =
+            Some(Label::new("renamed").unwrap());
+        apply(&mut pool, &state);
+        apply(&mut pool, &state);
+
+        assert_eq!(pid_of(id), pid);
+        assert_eq!(ffmpeg.spawns(), 1);
+    }
+
+    #[tokio::test]
+    async fn restarts_process_on_dst_change() {
+        let ffmpeg = FakeFfmpeg::leaked();
+        let state = state_with_output("rtmp://example.com/live/a");
+        let mut pool = RestreamersPool::new(
+            ffmpeg,
+            HwAccel::None,
+            PathBuf::new(),
+            state.clone(),
+        );
+
+        apply(&mut pool, &state);
+        let id = output_id(&state);
+        assert!(eventually(|| pid_of(id).is_some()).await);
+        let old_pid = pid_of(id).unwrap();
+
+        state.restreams.lock_mut()[0].outputs[0].dst =
+            OutputDstUrl::new("rtmp://example.com/live/b".parse().unwrap())
+                .unwrap();
+        apply(&mut pool, &state);
+
+        assert!(
+            eventually(|| pid_of(id).map_or(false, |pid| pid != old_pid)).await
+        );
+        assert_eq!(ffmpeg.spawns(), 2);
+    }
+
+    #[tokio::test]
+    async fn kills_process_once_output_removed() {
+        let ffmpeg = FakeFfmpeg::leaked();
+        let state = state_with_output("rtmp://example.com/live/a");
+        let mut pool = RestreamersPool::new(
+            ffmpeg,
+            HwAccel::None,
+            PathBuf::new(),
+            state.clone(),
+        );
+
+        apply(&mut pool, &state);
+        let id = output_id(&state);
+        assert!(eventually(|| pid_of(id).is_some()).await);
+        let pid = pid_of(id).unwrap();
+
+        state.restreams.lock_mut()[0].outputs.clear();
+        apply(&mut pool, &state);
+
+        assert!(eventually(|| pid_of(id).is_none()).await);
+        assert!(eventually(|| !process::is_running(pid)).await);
+        assert_eq!(ffmpeg.spawns(), 1);
+    }
+
+    #[tokio::test]
+    async fn kills_processes_on_drop() {
+        let ffmpeg = FakeFfmpeg::leaked();
+        let state = state_with_output("rtmp://example.com/live/a");
+        let mut pool = RestreamersPool::new(
+            ffmpeg,
+            HwAccel::None,
+            PathBuf::new(),
+            state.clone(),
+        );
+
+        apply(&mut pool, &state);
+        let id = output_id(&state);
+        assert!(eventually(|| pid_of(id).is_some()).await);
+        let pid = pid_of(id).unwrap();
+
+        drop(pool);
+
+        assert!(eventually(|| !process::is_running(pid)).await);
+    }
+}
@@ -0,0 +1,50 @@
+//! Accounting of traffic pushed by [FFmpeg] re-streaming processes.
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{collections::HashMap, mem, sync::Mutex, time::Duration};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use tokio::time;
+use uuid::Uuid;
+
+use crate::State;
+
+/// Interval of flushing the accounted traffic into a [`State`].
+///
+/// Flushed rarely, as every flush persists the [`State`].
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bytes pushed by the running [FFmpeg] processes and not flushed into a
+/// [`State`] yet, identified by IDs of the elements in a [`State`] the
+/// processes are related to.
+///
+/// [FFmpeg]: https://ffmpeg.org
+static PUSHED: Lazy<Mutex<HashMap<Uuid, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Accounts the given number of `bytes` pushed by the [FFmpeg] process
+/// related to the element with the given `id` in a [`State`].
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(super) fn account(id: Uuid, bytes: u64) {
+    *PUSHED.lock().unwrap().entry(id).or_default() += bytes;
+}
+
+/// Runs flushing the traffic accounted for [FFmpeg] processes into `Output`s
+/// of the given [`State`] periodically.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub fn run_traffic_accounting(state: State) {
+    drop(tokio::spawn(async move {
+        let mut interval = time::interval(FLUSH_INTERVAL);
+        loop {
+            let _ = interval.tick().await;
+            let pushed = mem::take(&mut *PUSHED.lock().unwrap());
+            if !pushed.is_empty() {
+                state.add_outputs_traffic(&pushed, Utc::now());
+            }
+        }
+    }));
+}
@@ -4,12 +4,18 @@
 //!
 //! [FFmpeg]: https://ffmpeg.org
 
-use std::borrow::Cow;
+use std::{borrow::Cow, fs, path::PathBuf};
 
+use ephyr_log::log;
 use tokio::process::Command;
 use url::Url;
 use uuid::Uuid;
 
+use crate::{
+    ffmpeg::HwAccel,
+    state::{FfmpegLogLevel, HlsRendition, LlHls},
+};
+
 /// Kind of a [FFmpeg] re-streaming process that re-streams a live stream from
 /// one URL endpoint to another one transcoding it with desired settings, and
 /// optionally transmuxing it to the destination format.
@@ -48,6 +54,30 @@ pub struct TranscodingRestreamer {
     ///
     /// [1]: https://ffmpeg.org/ffmpeg-codecs.html#Audio-Encoders
     pub acodec: Option<Cow<'static, str>>,
+
+    /// [`HwAccel`] to encode the transcoded live stream with, replacing the
+    /// software `libx264` [`TranscodingRestreamer::vcodec`].
+    pub hw_accel: HwAccel,
+
+    /// [`LlHls`] options to additionally write the transcoded live stream
+    /// with into the given directory.
+    pub ll_hls: Option<(LlHls, PathBuf)>,
+
+    /// [`HlsRendition`] to scale the transcoded live stream to, and to
+    /// limit its bitrates with.
+    pub rendition: Option<HlsRendition>,
+
+    /// Contents of a [HLS] master playlist to be written into the given file
+    /// before running.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    pub master_playlist: Option<(String, PathBuf)>,
+
+    /// [`FfmpegLogLevel`] to run [FFmpeg] process with, overriding the
+    /// server's one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub log_level: Option<FfmpegLogLevel>,
 }
 
 impl TranscodingRestreamer {
@@ -65,26 +95,104 @@ impl TranscodingRestreamer {
     ///
     /// [FFmpeg]: https://ffmpeg.org
     pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) {
-        let _ = cmd.args(["-i", self.from_url.as_str()]);
-
-        if let Some(val) = self.vcodec.as_ref() {
-            let _ = cmd.args(["-c:v", val]);
+        if let Some(level) = self.log_level {
+            level.setup_ffmpeg(cmd);
         }
-        if let Some(val) = self.vpreset.as_ref() {
-            let _ = cmd.args(["-preset", val]);
+
+        let hw_encoder = self
+            .hw_accel
+            .h264_encoder()
+            .filter(|_| self.vcodec.as_deref() == Some("libx264"));
+        if hw_encoder.is_some() {
+            self.hw_accel.setup_ffmpeg_device(cmd);
         }
-        if let Some(val) = self.vprofile.as_ref() {
-            let _ = cmd.args(["-profile:v", val]);
+
+        let _ = cmd.args(["-i", self.from_url.as_str()]);
+
+        let scale = self.rendition.map(HlsRendition::ffmpeg_filter);
+        if let Some(encoder) = hw_encoder {
+            self.hw_accel.setup_ffmpeg_filter(cmd, scale.as_deref());
+            let _ = cmd.args(["-c:v", encoder]);
+            // Presets of software encoders are not supported by hardware
+            // ones.
+            if let Some(val) = self.vprofile.as_ref() {
+                let _ =
+                    cmd.args(["-profile:v", self.hw_accel.h264_profile(val)]);
+            }
+        } else {
+            if let Some(filter) = scale {
+                let _ = cmd.arg("-vf").arg(filter);
+            }
+            if let Some(val) = self.vcodec.as_ref() {
+                let _ = cmd.args(["-c:v", val]);
+            }
+            if let Some(val) = self.vpreset.as_ref() {
+                let _ = cmd.args(["-preset", val]);
+            }
+            if let Some(val) = self.vprofile.as_ref() {
+                let _ = cmd.args(["-profile:v", val]);
+            }
         }
 
         if let Some(val) = self.acodec.as_ref() {
             let _ = cmd.args(["-c:a", val]);
         }
 
-        let _ = match self.to_url.scheme() {
-            "rtmp" | "rtmps" => cmd.args(["-f", "flv"]),
+        if let Some(r) = self.rendition {
+            let _ = cmd
+                .arg("-b:v")
+                .arg(format!("{}k", r.video_bitrate))
+                .arg("-maxrate")
+                .arg(format!("{}k", r.video_bitrate))
+                .arg("-bufsize")
+                .arg(format!("{}k", 2 * r.video_bitrate))
+                .arg("-b:a")
+                .arg(format!("{}k", r.audio_bitrate));
+        }
+
+        if let Some((playlist, path)) = &self.master_playlist {
+            // SRS creates the directory only once the first live stream is
+            // published, so it may be missing yet.
+            let res = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(path, playlist));
+            if let Err(e) = res {
+                log::error!(
+                    "Failed to write HLS master playlist {}: {}",
+                    path.display(),
+                    e,
+                );
+            }
+        }
+
+        let format = match self.to_url.scheme() {
+            "rtmp" | "rtmps" => "flv",
             _ => unimplemented!(),
+        };
+        if let Some((ll_hls, dir)) = &self.ll_hls {
+            // Playlists and segments of the previous run are removed on
+            // exit, so the directory may be missing.
+            if let Err(e) = fs::create_dir_all(dir) {
+                log::error!(
+                    "Failed to create LL-HLS directory {}: {}",
+                    dir.display(),
+                    e,
+                );
+            }
+            // Codecs' extra data is required by both FLV and CMAF segments
+            // when they're written via `tee` muxer.
+            let _ = cmd
+                .args(["-flags", "+global_header"])
+                .args(["-map", "0:v?", "-map", "0:a?"])
+                .args(["-f", "tee"])
+                .arg(format!(
+                    "[f={format}]{}|{}",
+                    self.to_url,
+                    ll_hls.ffmpeg_tee_slave(dir),
+                ));
+        } else {
+            let _ = cmd.args(["-f", format]).arg(self.to_url.as_str());
         }
-        .arg(self.to_url.as_str());
     }
 }
@@ -0,0 +1,129 @@
+//! Automatic disabling of `Input`s staying offline for too long.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use ephyr_log::log;
+use serde_json::json;
+use tokio::time;
+
+use crate::{
+    audit,
+    notifier::{self, Event, Severity},
+    state::{InputId, RestreamId},
+    State,
+};
+
+/// Interval of checking `Input`s for being idle.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tracker of enabled `Input`s being offline, which disables them once they
+/// stay offline longer than their idle timeout, so [FFmpeg] processes don't
+/// retry pulling abandoned sources forever.
+///
+/// Idle timeout is taken from `Restream::idle_timeout`, falling back to the
+/// `Settings::idle_timeout`.
+///
+/// Offline duration is counted since the server start at most, as it's not
+/// persisted.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Debug, Default)]
+pub struct IdleTracker {
+    /// Moments since when the tracked `Input`s are offline.
+    offline_since: HashMap<InputId, Instant>,
+}
+
+impl IdleTracker {
+    /// Runs this [`IdleTracker`] checking `Input`s of the given [`State`]
+    /// periodically.
+    pub fn run(mut self, state: State) {
+        drop(tokio::spawn(async move {
+            let mut interval = time::interval(CHECK_INTERVAL);
+            loop {
+                let _ = interval.tick().await;
+                self.check(&state);
+            }
+        }));
+    }
+
+    /// Checks `Input`s of the given [`State`], disabling the ones being
+    /// offline longer than their idle timeout.
+    fn check(&mut self, state: &State) {
+        let default = state.settings.get_cloned().idle_timeout;
+        let now = Instant::now();
+
+        let mut idle = vec![];
+        {
+            let restreams = state.restreams.lock_ref();
+            self.offline_since
+                .retain(|id, _| restreams.iter().any(|r| r.input.id == *id));
+            for r in restreams.iter() {
+                let timeout = match r.idle_timeout.or(default) {
+                    Some(t) if t > 0 => {
+                        Duration::from_secs(60 * u64::from(t.unsigned_abs()))
+                    }
+                    _ => {
+                        drop(self.offline_since.remove(&r.input.id));
+                        continue;
+                    }
+                };
+                if !r.input.enabled || r.input.is_ready_to_serve() {
+                    drop(self.offline_since.remove(&r.input.id));
+                    continue;
+                }
+                let since =
+                    *self.offline_since.entry(r.input.id).or_insert(now);
+                if now.duration_since(since) >= timeout {
+                    idle.push((r.id, r.key.to_string(), timeout));
+                }
+            }
+        }
+
+        // Inputs are disabled once the maintenance mode is over.
+        if state.maintenance_mode.get() {
+            return;
+        }
+        for (id, key, timeout) in idle {
+            Self::disable(state, id, &key, timeout);
+        }
+    }
+
+    /// Disables the `Input` of the `Restream` with the given `id` and `key`
+    /// in the [`State`], being offline for the given `timeout`.
+    fn disable(state: &State, id: RestreamId, key: &str, timeout: Duration) {
+        let input_id = match state
+            .restreams
+            .lock_ref()
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.input.id)
+        {
+            Some(i) => i,
+            None => return,
+        };
+        if state.disable_input(input_id, id) != Ok(true) {
+            return;
+        }
+
+        let mins = timeout.as_secs() / 60;
+        let msg = format!(
+            "Input of Restream '{key}' has been offline for {mins} minutes, \
+             so disabled",
+        );
+        log::warn!("{msg}");
+        audit::record_internal(
+            state,
+            "disableInput",
+            json!({
+                "id": input_id,
+                "restreamId": id,
+                "idleMinutes": mins,
+            })
+            .to_string(),
+        );
+        notifier::notify(Event::new(Severity::Warning, "Input disabled", msg));
+    }
+}
@@ -0,0 +1,203 @@
+//! Minimal generator of [JSON Patch] documents, describing changes between
+//! two JSON values.
+//!
+//! [JSON Patch]: https://datatracker.ietf.org/doc/html/rfc6902
+
+use serde::Serialize;
+use serde_json::Value as Json;
+
+/// Single operation of a [JSON Patch] document.
+///
+/// Only `add`, `remove` and `replace` operations are ever generated.
+///
+/// [JSON Patch]: https://datatracker.ietf.org/doc/html/rfc6902
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase", tag = "op")]
+pub enum Operation {
+    /// Adds the `value` at the `path`.
+    Add {
+        /// [JSON Pointer] to add the `value` at.
+        ///
+        /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+        path: String,
+
+        /// Value to be added.
+        value: Json,
+    },
+
+    /// Removes the value at the `path`.
+    Remove {
+        /// [JSON Pointer] to remove the value at.
+        ///
+        /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+        path: String,
+    },
+
+    /// Replaces the value at the `path` with the given `value`.
+    Replace {
+        /// [JSON Pointer] to replace the value at.
+        ///
+        /// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+        path: String,
+
+        /// Value to be placed instead.
+        value: Json,
+    },
+}
+
+/// Generates [JSON Patch] [`Operation`]s transforming the `old` value into
+/// the `new` one.
+///
+/// Arrays are compared index-wise, so inserting into the middle of an array
+/// results in replacing of all its following elements. This is fine for
+/// rarely reordered collections.
+///
+/// [JSON Patch]: https://datatracker.ietf.org/doc/html/rfc6902
+#[must_use]
+pub fn diff(old: &Json, new: &Json) -> Vec<Operation> {
+    let mut ops = Vec::new();
+    diff_at(&mut String::new(), old, new, &mut ops);
+    ops
+}
+
+/// Generates [`Operation`]s transforming the `old` value into the `new` one,
+/// both located at the given `path`, into the provided `ops`.
+fn diff_at(
+    path: &mut String,
+    old: &Json,
+    new: &Json,
+    ops: &mut Vec<Operation>,
+) {
+    match (old, new) {
+        (Json::Object(old), Json::Object(new)) => {
+            for (key, old_val) in old {
+                let len = path.len();
+                push_token(path, key);
+                match new.get(key) {
+                    Some(new_val) => diff_at(path, old_val, new_val, ops),
+                    None => ops.push(Operation::Remove { path: path.clone() }),
+                }
+                path.truncate(len);
+            }
+            for (key, new_val) in new {
+                if !old.contains_key(key) {
+                    let len = path.len();
+                    push_token(path, key);
+                    ops.push(Operation::Add {
+                        path: path.clone(),
+                        value: new_val.clone(),
+                    });
+                    path.truncate(len);
+                }
+            }
+        }
+        (Json::Array(old), Json::Array(new)) => {
+            for (n, (old_val, new_val)) in old.iter().zip(new).enumerate() {
+                let len = path.len();
+                push_token(path, &n.to_string());
+                diff_at(path, old_val, new_val, ops);
+                path.truncate(len);
+            }
+            // Removing from the end, so the indices stay valid.
+            for n in (new.len()..old.len()).rev() {
+                ops.push(Operation::Remove {
+                    path: format!("{path}/{n}"),
+                });
+            }
+            for val in new.iter().skip(old.len()) {
+                ops.push(Operation::Add {
+                    path: format!("{path}/-"),
+                    value: val.clone(),
+                });
+            }
+        }
+        (old, new) if old == new => {}
+        (_, new) => ops.push(Operation::Replace {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+    }
+}
+
+/// Appends the given reference `token` to the given [JSON Pointer] `path`,
+/// escaping it properly.
+///
+/// [JSON Pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+fn push_token(path: &mut String, token: &str) {
+    path.push('/');
+    path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+#[cfg(test)]
+mod json_patch_spec {
+    use serde_json::json;
+
+    use super::{diff, Operation};
+
+    #[test]
+    fn produces_no_operations_for_equal_values() {
+        let val = json!({"a": [1, {"b": null}], "c": "d"});
+
+        assert!(diff(&val, &val).is_empty());
+    }
+
+    #[test]
+    fn diffs_objects() {
+        let old = json!({"a": 1, "b": {"c": true}, "d/e": "x"});
+        let new = json!({"a": 2, "b": {"c": true, "f~": []}});
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Operation::Replace {
+                    path: "/a".into(),
+                    value: json!(2),
+                },
+                Operation::Add {
+                    path: "/b/f~0".into(),
+                    value: json!([]),
+                },
+                Operation::Remove {
+                    path: "/d~1e".into(),
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn diffs_arrays() {
+        let old = json!([{"id": 1}, {"id": 2}, {"id": 3}]);
+
+        assert_eq!(
+            diff(&old, &json!([{"id": 1}])),
+            vec![
+                Operation::Remove { path: "/2".into() },
+                Operation::Remove { path: "/1".into() },
+            ],
+        );
+        assert_eq!(
+            diff(&old, &json!([{"id": 1}, {"id": 5}, {"id": 3}, 4])),
+            vec![
+                Operation::Replace {
+                    path: "/1/id".into(),
+                    value: json!(5),
+                },
+                Operation::Add {
+                    path: "/-".into(),
+                    value: json!(4),
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn replaces_whole_value_of_different_type() {
+        assert_eq!(
+            diff(&json!(null), &json!({"a": 1})),
+            vec![Operation::Replace {
+                path: String::new(),
+                value: json!({"a": 1}),
+            }],
+        );
+    }
+}
@@ -27,20 +27,35 @@
 )]
 
 pub mod api;
+pub mod audit;
+pub mod backup;
 pub mod cli;
 pub mod client_stat;
 pub mod dvr;
+pub mod failover;
+pub mod federation;
 pub mod ffmpeg;
+pub mod idle;
+pub mod json_patch;
+pub mod m3u8;
+pub mod notifier;
+pub mod preview;
+pub mod process;
+pub mod runtime_dir;
 pub mod serde;
 pub mod server;
+pub mod shedding;
+pub mod shutdown;
 pub mod spec;
 pub mod srs;
+pub mod srt;
 pub mod state;
 pub mod stream_probe;
 pub mod teamspeak;
 pub mod types;
+pub mod webhook;
 
-use std::{any::Any, mem};
+use std::{any::Any, fs, mem, path::Path};
 
 use ephyr_log::slog;
 
@@ -64,9 +79,48 @@ pub fn run() -> Result<(), cli::Failure> {
 
     // This guard should be held till the end of the program for the logger
     // to present in global context.
-    mem::forget(ephyr_log::init(cfg.verbose));
+    mem::forget(ephyr_log::init_with(&ephyr_log::LogConfig {
+        level: cfg.verbose,
+        stdout: !cfg.no_log_stdout,
+        file: cfg.log_file.clone(),
+        rotation: cfg.log_rotation,
+        syslog: cfg.log_syslog,
+        otlp_endpoint: cfg.otlp_endpoint.as_ref().map(ToString::to_string),
+    }));
 
-    server::run(cfg)
+    match cfg.cmd.take() {
+        Some(cli::Command::Validate { spec, expand_env }) => {
+            validate(&spec, expand_env)
+        }
+        None => server::run(cfg),
+    }
+}
+
+/// Validates the [`Spec`] file at the given `path`, printing the found errors.
+///
+/// If `expand_env` is `true`, then `${ENV_VAR}` placeholders in the [`Spec`]
+/// are expanded before validating.
+///
+/// # Errors
+///
+/// If the file cannot be read or contains an invalid [`Spec`].
+pub fn validate(path: &Path, expand_env: bool) -> Result<(), cli::Failure> {
+    let json = fs::read_to_string(path).map_err(|e| {
+        eprintln!("Failed to read {}: {}", path.display(), e);
+    })?;
+    match Spec::from_json(&json, expand_env) {
+        Ok(_) => {
+            println!("{} is a valid spec", path.display());
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{} is an invalid spec:", path.display());
+            for err in e.0 {
+                eprintln!("  {err}");
+            }
+            Err(cli::Failure)
+        }
+    }
 }
 
 /// Interprets given [panic payload][1] as displayable message.
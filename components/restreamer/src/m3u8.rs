@@ -0,0 +1,232 @@
+//! Minimal parser of [HLS] master playlists (`.m3u8`), allowing to select a
+//! variant stream out of them.
+//!
+//! [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+
+use std::{convert::TryFrom as _, time::Duration};
+
+use anyhow::anyhow;
+use url::Url;
+
+use crate::state::HlsVariantSelector;
+
+/// Timeout of fetching a [HLS] playlist.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Variant stream declared in a [HLS] master playlist.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Variant {
+    /// Absolute [`Url`] of the media playlist of this [`Variant`].
+    pub url: Url,
+
+    /// Peak bandwidth of this [`Variant`] (in bits per second).
+    pub bandwidth: u64,
+
+    /// Height of this [`Variant`]'s video (in pixels), if declared.
+    pub height: Option<u64>,
+}
+
+/// Parses [`Variant`]s out of the given [HLS] master playlist `contents`,
+/// resolving their URIs against the given `base` [`Url`] of the playlist.
+///
+/// Returns an empty [`Vec`] if the playlist is a media playlist rather than a
+/// master one.
+///
+/// # Errors
+///
+/// If the `contents` is not a valid [HLS] playlist.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+pub fn parse_variants(
+    contents: &str,
+    base: &Url,
+) -> anyhow::Result<Vec<Variant>> {
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty());
+    if lines.next() != Some("#EXTM3U") {
+        return Err(anyhow!("Playlist doesn't start with #EXTM3U tag"));
+    }
+
+    let mut variants = Vec::new();
+    let mut pending: Option<(u64, Option<u64>)> = None;
+    for line in lines {
+        if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let mut bandwidth = None;
+            let mut height = None;
+            for (name, val) in parse_attributes(attrs) {
+                match name {
+                    "BANDWIDTH" => bandwidth = val.parse().ok(),
+                    "RESOLUTION" => {
+                        height = val
+                            .split_once('x')
+                            .and_then(|(_, h)| h.parse().ok());
+                    }
+                    _ => {}
+                }
+            }
+            let bandwidth = bandwidth.ok_or_else(|| {
+                anyhow!("#EXT-X-STREAM-INF tag has no valid BANDWIDTH")
+            })?;
+            pending = Some((bandwidth, height));
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some((bandwidth, height)) = pending.take() {
+            variants.push(Variant {
+                url: base.join(line).map_err(|e| {
+                    anyhow!("Invalid variant URI '{}': {}", line, e)
+                })?,
+                bandwidth,
+                height,
+            });
+        }
+    }
+    Ok(variants)
+}
+
+/// Parses the given attribute list of a [HLS] tag into name-value pairs,
+/// unquoting quoted values.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+fn parse_attributes(attrs: &str) -> Vec<(&str, &str)> {
+    let mut pairs = Vec::new();
+    let mut rest = attrs;
+    while let Some((name, tail)) = rest.split_once('=') {
+        let (val, tail) = if let Some(quoted) = tail.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let tail = quoted.get(end + 1..).unwrap_or_default();
+            (&quoted[..end], tail.strip_prefix(',').unwrap_or(tail))
+        } else {
+            tail.split_once(',').unwrap_or((tail, ""))
+        };
+        pairs.push((name.trim(), val));
+        rest = tail;
+    }
+    pairs
+}
+
+/// Selects the [`Variant`] matching the given [`HlsVariantSelector`] best.
+///
+/// The [`Variant`] with the highest bandwidth not exceeding the selector's
+/// limits is preferred. If none fits the limits, then the one with the lowest
+/// bandwidth is selected.
+#[must_use]
+pub fn select_variant<'v>(
+    variants: &'v [Variant],
+    selector: &HlsVariantSelector,
+) -> Option<&'v Variant> {
+    let fits = |v: &&Variant| {
+        selector.max_bandwidth.map_or(true, |max| {
+            u64::try_from(max).map_or(false, |max| v.bandwidth <= max)
+        }) && selector.max_height.map_or(true, |max| {
+            v.height.map_or(true, |h| {
+                u64::try_from(max).map_or(false, |max| h <= max)
+            })
+        })
+    };
+    variants
+        .iter()
+        .filter(fits)
+        .max_by_key(|v| v.bandwidth)
+        .or_else(|| variants.iter().min_by_key(|v| v.bandwidth))
+}
+
+/// Resolves the [`Url`] of the media playlist to be pulled out of the given
+/// [HLS] playlist `url` according to the given [`HlsVariantSelector`].
+///
+/// Returns the given `url` as is, if it's a media playlist already.
+///
+/// # Errors
+///
+/// If the playlist fails to be fetched or parsed.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+pub async fn resolve_variant(
+    url: &Url,
+    selector: &HlsVariantSelector,
+) -> anyhow::Result<Url> {
+    let contents = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()?
+        .get(url.clone())
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let variants = parse_variants(&contents, url)?;
+    Ok(select_variant(&variants, selector)
+        .map_or_else(|| url.clone(), |v| v.url.clone()))
+}
+
+#[cfg(test)]
+mod m3u8_spec {
+    use url::Url;
+
+    use crate::state::HlsVariantSelector;
+
+    use super::{parse_variants, select_variant};
+
+    const MASTER: &str = concat!(
+        "#EXTM3U\n",
+        "#EXT-X-VERSION:3\n",
+        "#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,",
+        "CODECS=\"avc1.4d401e,mp4a.40.2\"\n",
+        "360p/index.m3u8\n",
+        "#EXT-X-STREAM-INF:BANDWIDTH=2800000,RESOLUTION=1280x720\n",
+        "720p/index.m3u8\n",
+        "#EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080\n",
+        "https://cdn.example.com/1080p.m3u8\n",
+    );
+
+    fn base() -> Url {
+        Url::parse("https://example.com/live/master.m3u8").unwrap()
+    }
+
+    #[test]
+    fn parses_master_playlist() {
+        let variants = parse_variants(MASTER, &base()).unwrap();
+
+        assert_eq!(variants.len(), 3);
+        assert_eq!(
+            variants[0].url.as_str(),
+            "https://example.com/live/360p/index.m3u8",
+        );
+        assert_eq!(variants[0].bandwidth, 800_000);
+        assert_eq!(variants[0].height, Some(360));
+        assert_eq!(
+            variants[2].url.as_str(),
+            "https://cdn.example.com/1080p.m3u8",
+        );
+    }
+
+    #[test]
+    fn parses_no_variants_of_media_playlist() {
+        let media = "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXTINF:4.0,\n0.ts\n";
+
+        assert!(parse_variants(media, &base()).unwrap().is_empty());
+        assert!(parse_variants("0.ts", &base()).is_err());
+    }
+
+    #[test]
+    fn selects_best_fitting_variant() {
+        let variants = parse_variants(MASTER, &base()).unwrap();
+        let select = |max_height, max_bandwidth| {
+            select_variant(
+                &variants,
+                &HlsVariantSelector {
+                    max_height,
+                    max_bandwidth,
+                },
+            )
+            .map(|v| v.bandwidth)
+        };
+
+        assert_eq!(select(None, None), Some(5_000_000));
+        assert_eq!(select(Some(720), None), Some(2_800_000));
+        assert_eq!(select(None, Some(1_000_000)), Some(800_000));
+        assert_eq!(select(Some(240), None), Some(800_000));
+    }
+}
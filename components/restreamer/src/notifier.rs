@@ -0,0 +1,247 @@
+//! Notifications about important server events sent to external chat
+//! services.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use url::Url;
+
+use crate::cli::Opts;
+
+/// Global instance of a [`Notifier`] used by this application.
+static NOTIFIER: OnceCell<Notifier> = OnceCell::new();
+
+/// Timeout of delivering a single notification to a [`Backend`].
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sender of [`Event`] notifications to all the configured [`Backend`]s.
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    /// [`Backend`]s to send notifications to, along with the minimal
+    /// [`Severity`] of [`Event`]s to be sent to each of them.
+    pub backends: Vec<(Backend, Severity)>,
+
+    /// Template of a notification message.
+    ///
+    /// `{severity}`, `{host}`, `{title}` and `{message}` placeholders are
+    /// substituted with the appropriate [`Event`] fields.
+    pub template: String,
+
+    /// Host of this server, used for distinguishing notifications sent by
+    /// different servers.
+    pub host: String,
+}
+
+impl Notifier {
+    /// Creates a new [`Notifier`] out of the given CLI [`Opts`].
+    ///
+    /// Returns [`None`] if no [`Backend`]s are configured.
+    #[must_use]
+    pub fn new(cfg: &Opts) -> Option<Self> {
+        let backends = cfg
+            .slack_webhook_url
+            .clone()
+            .map(|url| (Backend::Slack(url), cfg.slack_min_severity))
+            .into_iter()
+            .chain(
+                cfg.teams_webhook_url
+                    .clone()
+                    .map(|url| (Backend::Teams(url), cfg.teams_min_severity)),
+            )
+            .collect::<Vec<_>>();
+        (!backends.is_empty()).then(|| Self {
+            backends,
+            template: cfg.notification_template.clone(),
+            host: cfg.public_host.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Sets the global instance of [`Notifier`].
+    ///
+    /// # Errors
+    ///
+    /// If the global instance has been set already.
+    pub fn set_global(self) -> anyhow::Result<()> {
+        NOTIFIER
+            .set(self)
+            .map_err(|_| anyhow!("Notifier has been initialized already"))
+    }
+
+    /// Renders the message of the given [`Event`] with the
+    /// [`Notifier::template`].
+    #[must_use]
+    pub fn render(&self, event: &Event) -> String {
+        self.template
+            .replace("{severity}", &event.severity.to_string())
+            .replace("{host}", &self.host)
+            .replace("{title}", &event.title)
+            .replace("{message}", &event.message)
+    }
+
+    /// Sends the given [`Event`] to all the [`Backend`]s accepting its
+    /// [`Severity`].
+    ///
+    /// Sending is performed in background, so never blocks.
+    pub fn send(&self, event: &Event) {
+        let text = self.render(event);
+        for (backend, min) in &self.backends {
+            if event.severity < *min {
+                continue;
+            }
+            let backend = backend.clone();
+            let (title, text, severity) =
+                (event.title.clone(), text.clone(), event.severity);
+            drop(tokio::spawn(async move {
+                if let Err(e) = backend.send(&title, &text, severity).await {
+                    log::error!("Failed to send {backend} notification: {e}");
+                }
+            }));
+        }
+    }
+}
+
+/// Sends the given [`Event`] via the global [`Notifier`], if any is
+/// configured.
+pub fn notify(event: Event) {
+    if let Some(n) = NOTIFIER.get() {
+        n.send(&event);
+    }
+}
+
+/// Event of the server to notify about.
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// [`Severity`] of this [`Event`].
+    pub severity: Severity,
+
+    /// Short title of this [`Event`].
+    pub title: String,
+
+    /// Detailed description of this [`Event`].
+    pub message: String,
+}
+
+impl Event {
+    /// Creates a new [`Event`] with the given [`Severity`], `title` and
+    /// `message`.
+    #[inline]
+    #[must_use]
+    pub fn new<T, M>(severity: Severity, title: T, message: M) -> Self
+    where
+        T: Into<String>,
+        M: Into<String>,
+    {
+        Self {
+            severity,
+            title: title.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Severity of an [`Event`].
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// Informational [`Event`], not requiring any actions.
+    Info,
+
+    /// [`Event`] indicating a possible problem.
+    Warning,
+
+    /// [`Event`] indicating a problem requiring immediate actions.
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Critical => "CRITICAL",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "warning" | "warn" => Ok(Self::Warning),
+            "critical" | "crit" => Ok(Self::Critical),
+            _ => Err(anyhow!(
+                "'{s}' is invalid severity, allowed are: \
+                 info | warning | critical",
+            )),
+        }
+    }
+}
+
+/// Chat service to send notifications to via its incoming webhook.
+#[derive(Clone, Debug)]
+pub enum Backend {
+    /// [Slack] incoming webhook.
+    ///
+    /// [Slack]: https://api.slack.com/messaging/webhooks
+    Slack(Url),
+
+    /// [Microsoft Teams] incoming webhook.
+    ///
+    /// [Microsoft Teams]: https://learn.microsoft.com/en-us/microsoftteams/platform/webhooks-and-connectors/how-to/add-incoming-webhook
+    Teams(Url),
+}
+
+impl Backend {
+    /// Sends the given notification `text` to this [`Backend`].
+    ///
+    /// # Errors
+    ///
+    /// If the request to the webhook fails or responds with non-success
+    /// status.
+    pub async fn send(
+        &self,
+        title: &str,
+        text: &str,
+        severity: Severity,
+    ) -> Result<(), reqwest::Error> {
+        let (url, body) = match self {
+            Self::Slack(url) => (url, json!({ "text": text })),
+            Self::Teams(url) => (
+                url,
+                json!({
+                    "@type": "MessageCard",
+                    "@context": "https://schema.org/extensions",
+                    "summary": title,
+                    "title": title,
+                    "themeColor": match severity {
+                        Severity::Info => "2EB886",
+                        Severity::Warning => "DAA038",
+                        Severity::Critical => "A30200",
+                    },
+                    "text": text,
+                }),
+            ),
+        };
+        let _ = reqwest::Client::new()
+            .post(url.clone())
+            .timeout(TIMEOUT)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Slack(_) => "Slack",
+            Self::Teams(_) => "Teams",
+        })
+    }
+}
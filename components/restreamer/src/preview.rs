@@ -0,0 +1,194 @@
+//! Live preview images of `InputEndpoint`s, captured periodically with
+//! [FFmpeg].
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use ephyr_log::log;
+use futures::{stream, StreamExt as _};
+use tokio::{fs, process::Command, time};
+use url::Url;
+
+use crate::{
+    ffmpeg, runtime_dir,
+    state::{EndpointId, Input, InputSrc, Status},
+    State,
+};
+
+/// Path prefix of the client HTTP server's route serving preview images.
+pub const ROUTE: &str = "/preview/";
+
+/// Maximum number of [FFmpeg] processes capturing preview images
+/// concurrently.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const CONCURRENCY: usize = 4;
+
+/// Maximum duration of capturing a single preview image, after which its
+/// [FFmpeg] process is killed.
+///
+/// [FFmpeg]: https://ffmpeg.org
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Returns directory where preview images are stored.
+fn dir() -> PathBuf {
+    runtime_dir::path().join(format!("{}previews", runtime_dir::PREFIX))
+}
+
+/// Returns path of the preview image of the `InputEndpoint` with the given
+/// `id`.
+#[must_use]
+pub fn path(id: EndpointId) -> PathBuf {
+    dir().join(format!("{id}.jpg"))
+}
+
+/// Returns relative URL of the preview image of the `InputEndpoint` with the
+/// given `id`, served by the client HTTP server.
+#[must_use]
+pub fn url(id: EndpointId) -> String {
+    format!("{ROUTE}{id}.jpg")
+}
+
+/// Runs capturing preview images of `Online` `InputEndpoint`s of the given
+/// [`State`] every `interval`.
+///
+/// No-op if the `interval` is zero.
+pub fn run(state: State, interval: Duration) {
+    if interval.is_zero() {
+        return;
+    }
+    drop(tokio::spawn(async move {
+        let mut interval = time::interval(interval);
+        loop {
+            let _ = interval.tick().await;
+            capture_all(&state).await;
+        }
+    }));
+}
+
+/// Captures preview images of all `Online` `InputEndpoint`s of the given
+/// [`State`], removing the ones of the `InputEndpoint`s not being `Online`
+/// anymore.
+async fn capture_all(state: &State) {
+    let mut targets = vec![];
+    for r in state.restreams.lock_ref().iter() {
+        collect_targets(&r.input, &r.srs_app(), &mut targets);
+    }
+
+    if let Err(e) = fs::create_dir_all(dir()).await {
+        log::error!("Failed to create previews directory: {e}");
+        return;
+    }
+    let actual = targets
+        .iter()
+        .map(|(id, _)| format!("{id}.jpg"))
+        .collect::<HashSet<_>>();
+    if let Err(e) = remove_stale(&actual).await {
+        log::warn!("Failed to remove stale previews: {e}");
+    }
+
+    let ffmpeg_path = ffmpeg::Binary::global().path();
+    stream::iter(targets)
+        .for_each_concurrent(CONCURRENCY, |(id, url)| {
+            let ffmpeg_path = &ffmpeg_path;
+            async move {
+                if let Err(e) = capture(ffmpeg_path, id, &url).await {
+                    log::debug!("Failed to capture preview of {url}: {e}");
+                }
+            }
+        })
+        .await;
+}
+
+/// Collects IDs and local [SRS] URLs of `Online` `InputEndpoint`s of the
+/// given [`Input`] (and its failover `Input`s) into the given `targets`.
+///
+/// [SRS]: https://github.com/ossrs/srs
+fn collect_targets(
+    input: &Input,
+    app: &str,
+    targets: &mut Vec<(EndpointId, Url)>,
+) {
+    if let Some(InputSrc::Failover(s)) = &input.src {
+        for i in &s.inputs {
+            collect_targets(i, app, targets);
+        }
+    }
+    for e in &input.endpoints {
+        if e.status == Status::Online {
+            targets.push((e.id, e.kind.rtmp_url(app, &input.key)));
+        }
+    }
+}
+
+/// Removes preview images not contained in the given `actual` file names.
+///
+/// # Errors
+///
+/// If the previews directory cannot be read.
+async fn remove_stale(actual: &HashSet<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir()).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        if !actual.contains(&*name.to_string_lossy()) {
+            // File may be removed concurrently, so we're not interested in
+            // the result.
+            let _ = fs::remove_file(entry.path()).await;
+        }
+    }
+    Ok(())
+}
+
+/// Captures a preview image of the live stream at the given `url` with
+/// [FFmpeg], replacing the one of the `InputEndpoint` with the given `id`.
+///
+/// The image is written into a temporary file first, so the served one is
+/// never partial.
+///
+/// # Errors
+///
+/// If [FFmpeg] process fails or times out, or the image cannot be replaced.
+///
+/// [FFmpeg]: https://ffmpeg.org
+async fn capture(
+    ffmpeg_path: &Path,
+    id: EndpointId,
+    url: &Url,
+) -> io::Result<()> {
+    let tmp = dir().join(format!("{id}.tmp.jpg"));
+
+    let out = Command::new(ffmpeg_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(url.as_str())
+        .args(["-frames:v", "1"])
+        .args(["-vf", "scale=320:-2"])
+        .arg("-y")
+        .arg(&tmp)
+        .output();
+    let out = time::timeout(CAPTURE_TIMEOUT, out).await.map_err(|_| {
+        io::Error::new(io::ErrorKind::TimedOut, "FFmpeg timed out")
+    })??;
+    if !out.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "FFmpeg exited with code: {}\n{}",
+                out.status,
+                String::from_utf8_lossy(&out.stderr),
+            ),
+        ));
+    }
+    fs::rename(&tmp, path(id)).await
+}
@@ -0,0 +1,97 @@
+//! Platform-independent control over spawned child processes.
+//!
+//! Each supported platform provides its own implementation of stopping,
+//! killing and classifying exits of processes, so the rest of the
+//! application doesn't depend on platform-specific APIs directly.
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+use std::process::ExitStatus;
+
+#[cfg(unix)]
+use self::unix as platform;
+#[cfg(windows)]
+use self::windows as platform;
+
+/// ID of a process in the operating system.
+pub type Pid = u32;
+
+/// Way a process has exited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Exit {
+    /// Process has completed successfully on its own.
+    Completed,
+
+    /// Process has been stopped gracefully via [`stop()`].
+    Stopped,
+
+    /// Process has failed or has been killed abruptly.
+    Failed,
+}
+
+impl Exit {
+    /// Classifies the given [`ExitStatus`] of a process.
+    ///
+    /// [FFmpeg] exits with `255` code once stopped gracefully, so such exit
+    /// is considered as [`Exit::Stopped`] too.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn of(status: ExitStatus) -> Self {
+        if status.success() {
+            Self::Completed
+        } else if status.code() == Some(255) || platform::is_stopped(status) {
+            Self::Stopped
+        } else {
+            Self::Failed
+        }
+    }
+
+    /// Indicates whether this [`Exit`] is not a failure.
+    #[inline]
+    #[must_use]
+    pub fn is_ok(self) -> bool {
+        self != Self::Failed
+    }
+}
+
+/// Prepares the given [`Command`] to spawn a process being able to be stopped
+/// gracefully via [`stop()`].
+///
+/// [`Command`]: tokio::process::Command
+#[inline]
+pub fn setup(cmd: &mut tokio::process::Command) {
+    platform::setup(cmd);
+}
+
+/// Requests the process with the given `pid` to stop gracefully, allowing it
+/// to finalize its outputs.
+///
+/// Returns `false` if the request cannot be delivered (the process doesn't
+/// exist anymore, for example).
+#[inline]
+#[must_use]
+pub fn stop(pid: Pid) -> bool {
+    platform::stop(pid)
+}
+
+/// Kills the process with the given `pid` abruptly, without allowing it to
+/// finalize anything.
+///
+/// Returns `false` if the process cannot be killed (it doesn't exist anymore,
+/// for example).
+#[inline]
+#[must_use]
+pub fn kill(pid: Pid) -> bool {
+    platform::kill(pid)
+}
+
+/// Indicates whether the process with the given `pid` is still running.
+#[inline]
+#[must_use]
+pub fn is_running(pid: Pid) -> bool {
+    platform::is_running(pid)
+}
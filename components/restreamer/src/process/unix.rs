@@ -0,0 +1,45 @@
+//! Control over child processes on Unix platforms via signals.
+
+use std::{
+    convert::TryFrom, os::unix::process::ExitStatusExt as _,
+    process::ExitStatus,
+};
+
+use nix::{
+    sys::signal::{self, Signal},
+    unistd,
+};
+
+use super::Pid;
+
+/// Converts the given [`Pid`] into a [`unistd::Pid`].
+///
+/// Returns [`None`] if the given [`Pid`] doesn't fit, so cannot represent
+/// any process.
+fn to_nix(pid: Pid) -> Option<unistd::Pid> {
+    i32::try_from(pid).ok().map(unistd::Pid::from_raw)
+}
+
+/// Processes are stopped via signals, so need no preparations.
+pub(super) fn setup(_: &mut tokio::process::Command) {}
+
+/// Sends `SIGTERM` to the process with the given `pid`.
+pub(super) fn stop(pid: Pid) -> bool {
+    to_nix(pid).map_or(false, |p| signal::kill(p, Signal::SIGTERM).is_ok())
+}
+
+/// Sends `SIGKILL` to the process with the given `pid`.
+pub(super) fn kill(pid: Pid) -> bool {
+    to_nix(pid).map_or(false, |p| signal::kill(p, Signal::SIGKILL).is_ok())
+}
+
+/// Checks the process with the given `pid` to exist by sending no signal to
+/// it.
+pub(super) fn is_running(pid: Pid) -> bool {
+    to_nix(pid).map_or(false, |p| signal::kill(p, None).is_ok())
+}
+
+/// Indicates whether the given [`ExitStatus`] is caused by `SIGTERM`.
+pub(super) fn is_stopped(status: ExitStatus) -> bool {
+    status.signal() == Some(Signal::SIGTERM as i32)
+}
@@ -0,0 +1,83 @@
+//! Control over child processes on Windows platforms via console control
+//! events, and `taskkill` and `tasklist` utilities, shipped with every
+//! Windows installation.
+
+use std::process::{Command, ExitStatus, Stdio};
+
+use super::Pid;
+
+/// [Process creation flag][1] making the spawned process the root of a new
+/// process group, so console control events may be sent to it only.
+///
+/// [1]: https://learn.microsoft.com/windows/win32/procthread/process-creation-flags
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+/// [`CTRL_BREAK_EVENT`][1] console control event, handled by [FFmpeg] the
+/// same way as `SIGTERM` on Unix.
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [1]: https://learn.microsoft.com/windows/console/generateconsolectrlevent
+const CTRL_BREAK_EVENT: u32 = 1;
+
+#[link(name = "kernel32")]
+extern "system" {
+    /// [`GenerateConsoleCtrlEvent`][1] function of Windows API.
+    ///
+    /// [1]: https://learn.microsoft.com/windows/console/generateconsolectrlevent
+    fn GenerateConsoleCtrlEvent(ctrl_event: u32, process_group_id: u32) -> i32;
+}
+
+/// Runs the given system utility with the given `args`, returning its
+/// output if it has succeeded.
+fn run(program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| out.stdout)
+}
+
+/// Prepares the given [`tokio::process::Command`] to spawn a process in its
+/// own process group, so it can be stopped via [`stop()`] alone.
+pub(super) fn setup(cmd: &mut tokio::process::Command) {
+    let _ = cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+/// Sends `CTRL_BREAK_EVENT` to the process group of the process with the
+/// given `pid`, which is the process itself if it has been spawned via
+/// [`setup()`].
+///
+/// Console processes (like [FFmpeg]) finalize their outputs and exit on it,
+/// unlike `taskkill` without `/F`, which only asks windows to close.
+///
+/// [FFmpeg]: https://ffmpeg.org
+pub(super) fn stop(pid: Pid) -> bool {
+    // Function has no preconditions, and fails gracefully if there is no
+    // such process group.
+    #[allow(unsafe_code)]
+    let res = unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) };
+    res != 0
+}
+
+/// Terminates the process with the given `pid` forcibly.
+pub(super) fn kill(pid: Pid) -> bool {
+    run("taskkill", &["/F", "/PID", &pid.to_string()]).is_some()
+}
+
+/// Checks the process with the given `pid` to be listed by `tasklist`.
+pub(super) fn is_running(pid: Pid) -> bool {
+    let filter = format!("PID eq {pid}");
+    run("tasklist", &["/NH", "/FO", "CSV", "/FI", &filter])
+        .map_or(false, |out| {
+            String::from_utf8_lossy(&out).contains(&format!("\"{pid}\""))
+        })
+}
+
+/// Windows has no signals, so gracefully stopped processes can be detected
+/// by their exit code only.
+pub(super) fn is_stopped(_: ExitStatus) -> bool {
+    false
+}
@@ -0,0 +1,84 @@
+//! Directory for runtime artifacts of this application, like [FIFO]s, playlist
+//! files and preview images, not required to survive its restart.
+//!
+//! [FIFO]: https://www.unix.com/man-page/linux/7/fifo/
+
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use once_cell::sync::OnceCell;
+use tokio::fs;
+
+/// Prefix of names of all the runtime artifacts of this application.
+pub const PREFIX: &str = "ephyr_";
+
+/// Configured directory for runtime artifacts.
+static DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Returns the directory for runtime artifacts of this application.
+///
+/// Falls back to [`std::env::temp_dir()`], if not configured.
+#[must_use]
+pub fn path() -> PathBuf {
+    DIR.get().cloned().unwrap_or_else(std::env::temp_dir)
+}
+
+/// Sets the given `dir` as the global directory for runtime artifacts,
+/// creating it if not exists.
+///
+/// Artifacts left by previous runs are removed from the `dir`. They're not
+/// removed from the [`std::env::temp_dir()`] (used if no `dir` is specified),
+/// as it may be shared with other servers running on the same host.
+///
+/// # Errors
+///
+/// - If the `dir` cannot be created or is not writable.
+/// - If the global directory has been set already.
+pub async fn init(dir: Option<&Path>) -> anyhow::Result<()> {
+    let dir = match dir {
+        Some(d) => d,
+        None => return Ok(()),
+    };
+
+    fs::create_dir_all(dir).await.map_err(|e| {
+        anyhow!("Failed to create `{}` directory: {e}", dir.display())
+    })?;
+    let probe = dir.join(format!("{PREFIX}probe"));
+    fs::write(&probe, b"")
+        .await
+        .map_err(|e| anyhow!("`{}` is not writable: {e}", dir.display()))?;
+    let _ = fs::remove_file(&probe).await;
+
+    remove_stale(dir).await;
+
+    DIR.set(dir.to_owned())
+        .map_err(|_| anyhow!("Runtime directory has been set already"))
+}
+
+/// Removes runtime artifacts left by previous runs in the given `dir`.
+async fn remove_stale(dir: &Path) {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(e) => e,
+        Err(e) => {
+            log::warn!("Failed to read `{}` directory: {e}", dir.display());
+            return;
+        }
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if !entry.file_name().to_string_lossy().starts_with(PREFIX) {
+            continue;
+        }
+        let path = entry.path();
+        let res = match entry.file_type().await {
+            Ok(t) if t.is_dir() => fs::remove_dir_all(&path).await,
+            _ => fs::remove_file(&path).await,
+        };
+        match res {
+            Ok(()) => log::debug!("Removed stale `{}`", path.display()),
+            Err(e) => {
+                log::warn!("Failed to remove stale `{}`: {e}", path.display());
+            }
+        }
+    }
+}
@@ -12,3 +12,15 @@
 pub fn is_false(val: &bool) -> bool {
     !*val
 }
+
+/// Indicates whether the given value equals to its [`Default`] one.
+///
+/// # Purpose
+///
+/// Signature of this function matches for a convenient use in a
+/// `skip_serializing_if` [`serde`]'s attribute.
+#[inline]
+#[must_use]
+pub fn is_default<T: Default + PartialEq>(val: &T) -> bool {
+    *val == T::default()
+}
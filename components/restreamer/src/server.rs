@@ -1,18 +1,35 @@
 //! HTTP servers.
 
+pub mod auth_limiter;
 pub mod client;
+pub mod public_stats;
+pub mod rest;
 pub mod srs_callback;
 pub mod statistics;
+pub mod tls;
+pub mod whip;
 
-use std::{net::IpAddr, time::Duration};
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use ephyr_log::log;
-use futures::future;
-use tokio::{fs, time};
+use futures::{future, future::Either, pin_mut};
+use tokio::time;
 
 use crate::{
+    audit, backup,
     cli::{Failure, Opts},
-    client_stat, dvr, ffmpeg, srs, teamspeak, State,
+    client_stat, dvr, failover, ffmpeg,
+    idle::IdleTracker,
+    notifier::Notifier,
+    preview, runtime_dir,
+    shedding::Shedder,
+    shutdown, srs, srt, state, teamspeak,
+    webhook::Dispatcher,
+    State,
 };
 
 /// Initializes and runs all application's HTTP servers.
@@ -36,25 +53,79 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         );
     }
 
-    let ffmpeg_path =
-        fs::canonicalize(&cfg.ffmpeg_path).await.map_err(|e| {
+    runtime_dir::init(cfg.runtime_dir.as_deref())
+        .await
+        .map_err(|e| {
+            log::error!("Failed to initialize runtime directory: {e}");
+        })?;
+
+    ffmpeg::Binary::try_new(&cfg.ffmpeg_path)
+        .await
+        .and_then(ffmpeg::Binary::set_global)
+        .map_err(|e| {
             log::error!("Failed to resolve FFmpeg binary path: {e}");
         })?;
+    let ffmpeg = ffmpeg::Binary::global();
+    ffmpeg::set_stall_timeout(cfg.ffmpeg_stall_timeout)
+        .map_err(|e| log::error!("Failed to set FFmpeg stall timeout: {e}"))?;
+    ffmpeg::set_mixin_pipe_kind(cfg.mixin_pipe)
+        .map_err(|e| log::error!("Failed to set mixin pipe kind: {e}"))?;
+    ffmpeg.watch(cfg.ffmpeg_check_interval, cfg.ffmpeg_restart_window);
+    let hw_accel = match cfg.hw_accel.clone() {
+        Some(accel) => accel,
+        None => ffmpeg::HwAccel::detect(ffmpeg).await,
+    };
 
-    let state = State::try_new(&cfg.state_path)
+    let storage = state::Storage::open(
+        cfg.state_backend,
+        &cfg.state_path,
+        &cfg.state_db_path,
+    )
+    .await
+    .map_err(|e| log::error!("Failed to open server state storage: {e}"))?;
+    let state = State::try_new(&storage, cfg.state_persist_debounce)
         .await
         .map_err(|e| log::error!("Failed to initialize server state: {e}"))?;
+    state.maintenance_mode.set(cfg.maintenance_mode);
+    audit::init(&state, &cfg.audit_log_path)
+        .await
+        .map_err(|e| log::error!("Failed to initialize audit log: {e}"))?;
 
-    let srs = srs::Server::try_new(
-        &cfg.srs_path,
-        &srs::Config {
-            callback_port: cfg.callback_http_port,
-            http_server_dir: cfg.srs_http_dir.clone().into(),
-            log_level: cfg.verbose.map(Into::into).unwrap_or_default(),
+    let mut srs_cfg = srs::Config {
+        rtmp_port: cfg.srs_rtmp_port,
+        http_port: cfg.srs_http_port,
+        rtc_port: cfg.srs_rtc_port,
+        api_port: cfg.srs_api_port,
+        callback_port: cfg.callback_http_port,
+        http_server_dir: cfg.srs_http_dir.clone().into(),
+        dvr_dir: cfg.dvr_dir.clone().into(),
+        rtc_candidate: cfg.public_host.clone().unwrap_or_else(|| "*".into()),
+        log_level: cfg.verbose.map(Into::into).unwrap_or_default(),
+        restreams: srs::RestreamConfig::collect(&state.restreams.get_cloned()),
+    };
+    let srs = srs::Server::try_new(&cfg.srs_path, &srs_cfg)
+        .await
+        .map_err(|e| log::error!("Failed to initialize SRS server: {e}"))?;
+    let srs_conf = srs.config_file();
+    State::on_change(
+        "refresh_srs_config",
+        &state.restreams,
+        move |restreams| {
+            let restreams = srs::RestreamConfig::collect(&restreams);
+            let changed = srs_cfg.restreams != restreams;
+            if changed {
+                srs_cfg.restreams = restreams;
+            }
+            let (conf, srs_cfg) = (srs_conf.clone(), srs_cfg.clone());
+            async move {
+                if changed {
+                    if let Err(e) = conf.reload(&srs_cfg).await {
+                        log::error!("Failed to refresh SRS config: {e}");
+                    }
+                }
+            }
         },
-    )
-    .await
-    .map_err(|e| log::error!("Failed to initialize SRS server: {e}"))?;
+    );
     State::on_change(
         "cleanup_dvr_files",
         &state.restreams,
@@ -65,12 +136,63 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         },
     );
 
-    let mut restreamers =
-        ffmpeg::RestreamersPool::new(ffmpeg_path, state.clone());
+    if let Some(ports) = cfg.srt_listen_ports {
+        ports
+            .set_global()
+            .map_err(|e| log::error!("Failed to set SRT port range: {e}"))?;
+        srt::run(&state);
+    }
+
+    let restreamers = Arc::new(Mutex::new(ffmpeg::RestreamersPool::new(
+        ffmpeg,
+        hw_accel,
+        cfg.media_dir.clone(),
+        state.clone(),
+    )));
+    let pool = Arc::clone(&restreamers);
     State::on_change("spawn_restreamers", &state.restreams, move |restreams| {
-        restreamers.apply(&restreams);
+        if !shutdown::is_started() {
+            pool.lock().unwrap().apply(&restreams);
+        }
         future::ready(())
     });
+    let coordinator =
+        shutdown::Coordinator::new(restreamers, state.clone(), storage);
+
+    Dispatcher::run(state.clone());
+
+    if let Some(notifier) = Notifier::new(&cfg) {
+        notifier
+            .set_global()
+            .map_err(|e| log::error!("Failed to initialize Notifier: {e}"))?;
+    }
+
+    if let Some(threshold) = cfg.cpu_shedding_threshold {
+        Shedder::new(threshold, cfg.cpu_shedding_period).run(state.clone());
+    }
+
+    client_stat::AlertsEvaluator::default().run(state.clone());
+
+    client_stat::run_stale_detection(state.clone());
+
+    if let Some(reporter) = client_stat::Reporter::new(&cfg)
+        .map_err(|e| log::error!("Failed to initialize Reporter: {e}"))?
+    {
+        reporter.run(state.clone());
+    }
+
+    IdleTracker::default().run(state.clone());
+
+    failover::run(state.clone());
+
+    ffmpeg::run_traffic_accounting(state.clone());
+
+    preview::run(state.clone(), cfg.preview_interval);
+
+    backup::Storage::new(cfg.backups_dir.clone(), cfg.backups_max)
+        .set_global()
+        .map_err(|e| log::error!("Failed to initialize backups: {e}"))?;
+    backup::run(state.clone(), cfg.backup_interval, cfg.backup_every_changes);
 
     let mut client_jobs = client_stat::ClientJobsPool::new(state.clone());
     State::on_change("spawn_client_jobs", &state.clients, move |clients| {
@@ -78,12 +200,20 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         future::ready(())
     });
 
-    future::try_join3(
+    let servers = future::try_join3(
         self::client::run(&cfg, state.clone()),
-        self::statistics::run(state.clone()),
+        self::statistics::run(
+            state.clone(),
+            cfg.statistics_interval,
+            cfg.ffmpeg_rss_limit,
+        ),
         self::srs_callback::run(&cfg, state),
-    )
-    .await?;
+    );
+    let shutdown = coordinator.run();
+    pin_mut!(servers, shutdown);
+    if let Either::Left((res, _)) = future::select(servers, shutdown).await {
+        let _ = res?;
+    }
 
     drop(srs);
     // Wait for all the async `Drop`s to proceed well.
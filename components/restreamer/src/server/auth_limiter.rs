@@ -0,0 +1,215 @@
+//! Rate limiting of failed authorization attempts to client HTTP server,
+//! preventing brute-forcing of passwords.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ephyr_log::log;
+
+use crate::cli::Opts;
+
+/// Tracker of failed authorization attempts per source IP address, locking
+/// out the ones exceeding the allowed number of failures.
+///
+/// Every next lockout of the same IP address lasts twice longer than the
+/// previous one.
+#[derive(Debug)]
+pub struct AuthLimiter {
+    /// Number of failed attempts, after which an IP address is locked out.
+    ///
+    /// Zero disables the lockout.
+    max_failures: u32,
+
+    /// Duration of the first lockout of an IP address.
+    lockout: Duration,
+
+    /// Maximum duration of a lockout of an IP address.
+    ///
+    /// IP addresses having no failed attempts during it are forgotten.
+    max_lockout: Duration,
+
+    /// Failed attempts of IP addresses.
+    clients: Mutex<HashMap<IpAddr, Attempts>>,
+}
+
+/// Failed authorization attempts of a single IP address.
+#[derive(Clone, Copy, Debug)]
+struct Attempts {
+    /// Number of failed attempts since the last lockout.
+    failures: u32,
+
+    /// Number of lockouts happened already.
+    lockouts: u32,
+
+    /// Moment the current lockout ends at, if any.
+    locked_until: Option<Instant>,
+
+    /// Moment of the last failed attempt.
+    last_failure: Instant,
+}
+
+impl AuthLimiter {
+    /// Creates a new [`AuthLimiter`] with the given thresholds.
+    #[inline]
+    #[must_use]
+    pub fn new(
+        max_failures: u32,
+        lockout: Duration,
+        max_lockout: Duration,
+    ) -> Self {
+        Self {
+            max_failures,
+            lockout,
+            max_lockout,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a new [`AuthLimiter`] with the thresholds of the given
+    /// [`Opts`].
+    #[inline]
+    #[must_use]
+    pub fn from_opts(cfg: &Opts) -> Self {
+        Self::new(
+            cfg.auth_max_failures,
+            cfg.auth_lockout,
+            cfg.auth_max_lockout,
+        )
+    }
+
+    /// Checks whether the given `ip` address is allowed to attempt
+    /// authorization.
+    ///
+    /// # Errors
+    ///
+    /// With the remaining duration of the lockout, if the `ip` address is
+    /// locked out.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let clients = self.clients.lock().unwrap();
+        let until = clients.get(&ip).and_then(|a| a.locked_until);
+        match until.map(|t| t.saturating_duration_since(Instant::now())) {
+            Some(left) if !left.is_zero() => Err(left),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tracks a failed authorization attempt from the given `ip` address.
+    ///
+    /// Returns the duration of the lockout, if the `ip` address has been
+    /// locked out by this attempt.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn fail(&self, ip: IpAddr) -> Option<Duration> {
+        if self.max_failures == 0 {
+            return None;
+        }
+        let now = Instant::now();
+
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|_, a| now - a.last_failure < self.max_lockout);
+        let attempts = clients.entry(ip).or_insert(Attempts {
+            failures: 0,
+            lockouts: 0,
+            locked_until: None,
+            last_failure: now,
+        });
+        attempts.failures += 1;
+        attempts.last_failure = now;
+        if attempts.failures < self.max_failures {
+            return None;
+        }
+
+        let lockout = self
+            .lockout
+            .checked_mul(2_u32.saturating_pow(attempts.lockouts))
+            .map_or(self.max_lockout, |d| d.min(self.max_lockout));
+        attempts.failures = 0;
+        attempts.lockouts = attempts.lockouts.saturating_add(1);
+        attempts.locked_until = Some(now + lockout);
+        log::warn!(
+            "Locked out {ip} for {} after {} failed authorization attempts",
+            humantime::format_duration(lockout),
+            self.max_failures,
+        );
+        Some(lockout)
+    }
+
+    /// Tracks a successful authorization from the given `ip` address,
+    /// forgetting its failed attempts.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn succeed(&self, ip: IpAddr) {
+        let _ = self.clients.lock().unwrap().remove(&ip);
+    }
+}
+
+#[cfg(test)]
+mod auth_limiter_spec {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    use super::AuthLimiter;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn doubles_lockouts_up_to_max() {
+        let limiter = AuthLimiter::new(
+            2,
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+        );
+        assert!(limiter.check(IP).is_ok());
+
+        assert_eq!(limiter.fail(IP), None);
+        assert_eq!(limiter.fail(IP), Some(Duration::from_secs(10)));
+        assert!(limiter.check(IP).is_err());
+
+        assert_eq!(limiter.fail(IP), None);
+        assert_eq!(limiter.fail(IP), Some(Duration::from_secs(20)));
+        assert_eq!(limiter.fail(IP), None);
+        assert_eq!(limiter.fail(IP), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn forgets_failures_on_success() {
+        let limiter = AuthLimiter::new(
+            2,
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+        );
+        assert_eq!(limiter.fail(IP), None);
+        assert_eq!(limiter.fail(IP), Some(Duration::from_secs(10)));
+
+        limiter.succeed(IP);
+        assert!(limiter.check(IP).is_ok());
+        assert_eq!(limiter.fail(IP), None);
+    }
+
+    #[test]
+    fn never_locks_out_if_disabled() {
+        let limiter = AuthLimiter::new(
+            0,
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+        );
+        for _ in 0..10 {
+            assert_eq!(limiter.fail(IP), None);
+        }
+        assert!(limiter.check(IP).is_ok());
+    }
+}
@@ -1,9 +1,14 @@
 //! Client HTTP server responding to client requests.
-use std::time::Duration;
+use std::{io, path::Path, time::Duration};
 
+use actix_files::NamedFile;
+use actix_multipart::Multipart;
 use actix_service::Service as _;
 use actix_web::{
-    dev::ServiceRequest, get, middleware, route, web, App, Error, HttpRequest,
+    dev::{self, ServiceRequest},
+    error, get,
+    http::{header, Method, StatusCode},
+    middleware, post, route, web, App, Error, FromRequest as _, HttpRequest,
     HttpResponse, HttpServer,
 };
 use actix_web_httpauth::extractors::{
@@ -12,23 +17,50 @@ use actix_web_httpauth::extractors::{
 };
 use actix_web_static_files::ResourceFiles;
 use ephyr_log::log;
-use futures::{future, FutureExt as _};
+use futures::{future, FutureExt as _, StreamExt as _, TryStreamExt as _};
+use futures_signals::signal::SignalExt as _;
 use juniper::http::playground::playground_source;
 use juniper_actix::{graphql_handler, subscriptions::subscriptions_handler};
 use juniper_graphql_ws::ConnectionConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use tokio::fs;
+use tracing_actix_web::TracingLogger;
+use uuid::Uuid;
 
 use crate::{
-    api,
+    api::{self, graphql::report::ReportToken},
+    audit,
     cli::{Failure, Opts},
-    State,
+    dvr, preview,
+    server::{
+        auth_limiter::AuthLimiter,
+        public_stats::{PublicStats, REFRESH_INTERVAL},
+        rest, tls, whip,
+    },
+    shutdown,
+    state::{EndpointId, PasswordKind, RestreamId, StorageKind, TenantKey},
+    Spec, State,
 };
 use std::fmt;
 
+const CLIENT_ROUTE_API: &str = "/api";
 const MIX_ROUTE: &str = "/mix";
 const MIX_ROUTE_API: &str = "/api-mix";
 const STATISTICS_ROUTE_API: &str = "/api-statistics";
+const REPORT_ROUTE_API: &str = "/api-report";
+const HEALTH_ROUTE: &str = "/healthz";
+const READY_ROUTE: &str = "/readyz";
+const PUBLIC_STATS_ROUTE: &str = "/api-public-stats";
 const INDEX_FILE: &str = "index.html";
 
+/// Maximum size (in bytes) of a GraphQL request body, matching the default
+/// limit of JSON payloads in [`actix_web`].
+const MAX_GRAPHQL_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maximum size (in bytes) of a spec file accepted by [`import`] endpoint.
+const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+
 pub mod public_dir {
     #![allow(clippy::must_use_candidate, unused_results)]
     #![doc(hidden)]
@@ -54,12 +86,22 @@ pub mod public_dashboard_dir {
 ///
 /// Client HTTP server serves [`api::graphql::client`] on `/` endpoint.
 ///
+/// # REST
+///
+/// Additionally serves [`rest`] endpoints on `/v1/` path for automations not
+/// able to speak GraphQL, requiring the same authorization.
+///
 /// # Playground
 ///
 /// If [`cli::Opts::debug`] is specified then additionally serves
 /// [GraphQL Playground][2] on `/api/playground` endpoint with no
 /// authorization required.
 ///
+/// # TLS
+///
+/// If [`tls::config()`] is provided, then serves everything over HTTPS (and
+/// WSS for subscriptions) only.
+///
 /// # Errors
 ///
 /// If [`HttpServer`] cannot run due to already used port, etc.
@@ -71,8 +113,13 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
     let in_debug_mode = cfg.debug;
 
     let stored_cfg = cfg.clone();
+    let auth_limiter = web::Data::new(AuthLimiter::from_opts(cfg));
+
+    let tls = tls::config(cfg).await.map_err(|e| {
+        log::error!("Failed to configure TLS of client HTTP server: {e}");
+    })?;
 
-    Ok(HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let root_dir_files = public_dir::generate();
         let mix_dir_files = public_mix_dir::generate();
         let dashboard_dir_files = public_dashboard_dir::generate();
@@ -80,12 +127,16 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
         let mut app = App::new()
             .app_data(stored_cfg.clone())
             .app_data(state.clone())
+            .app_data(auth_limiter.clone())
             .app_data(basic::Config::default().realm("Any login is allowed"))
             .app_data(web::Data::new(api::graphql::client::schema()))
+            .app_data(web::Data::new(api::graphql::client::tenant_schema()))
             .app_data(web::Data::new(api::graphql::mix::schema()))
             .app_data(web::Data::new(api::graphql::dashboard::schema()))
             .app_data(web::Data::new(api::graphql::statistics::schema()))
+            .app_data(web::Data::new(api::graphql::report::schema()))
             .wrap(middleware::Logger::default())
+            .wrap(TracingLogger::default())
             .wrap_fn(|req, srv| match authorize(req) {
                 Ok(req) => srv.call(req).left_future(),
                 Err(e) => future::err(e).right_future(),
@@ -93,7 +144,23 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
             .service(graphql_client)
             .service(graphql_mix)
             .service(graphql_statistics)
-            .service(graphql_dashboard);
+            .service(graphql_dashboard)
+            .service(graphql_report)
+            .service(import)
+            .service(public_stats)
+            .service(whip::publish)
+            .service(whip::stop)
+            .service(whip::preflight)
+            .service(dvr_file)
+            .service(preview_image)
+            .service(rest::openapi)
+            .service(rest::restreams)
+            .service(rest::restream)
+            .service(rest::toggle_restream)
+            .service(rest::toggle_all_outputs)
+            .service(rest::toggle_output)
+            .service(healthz)
+            .service(readyz);
         if in_debug_mode {
             app = app
                 .service(playground_client)
@@ -110,9 +177,16 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
                 .resolve_not_found_to(INDEX_FILE),
         )
         .service(ResourceFiles::new("/", root_dir_files))
-    })
-    .bind((cfg.client_http_ip, cfg.client_http_port))
+    });
+    let addr = (cfg.client_http_ip, cfg.client_http_port);
+    // Both HTTP and WebSocket endpoints are served over TLS, if configured.
+    Ok(match tls {
+        Some(tls) => server.bind_rustls(addr, tls),
+        None => server.bind(addr),
+    }
     .map_err(|e| log::error!("Failed to bind client HTTP server: {e}"))?
+    // Signals are handled by `shutdown::Coordinator`.
+    .disable_signals()
     .run()
     .await
     .map_err(|e| log::error!("Failed to run client HTTP server: {e}"))?)
@@ -123,6 +197,9 @@ pub enum SchemaKind {
     /// Full schema
     Schema(web::Data<api::graphql::client::Schema>),
 
+    /// Full schema without mutations, served to tenants
+    SchemaTenant(web::Data<api::graphql::client::TenantSchema>),
+
     /// Single output schema for mixing
     SchemaMix(web::Data<api::graphql::mix::Schema>),
 
@@ -131,6 +208,9 @@ pub enum SchemaKind {
 
     /// Statistics schema
     SchemaStatistics(web::Data<api::graphql::statistics::Schema>),
+
+    /// Schema for reporting statistics by remote clients
+    SchemaReport(web::Data<api::graphql::report::Schema>),
 }
 
 impl fmt::Debug for SchemaKind {
@@ -160,6 +240,17 @@ async fn graphql_dashboard(
     graphql(req, payload, SchemaKind::SchemaDashboard(schema)).await
 }
 
+/// Endpoint serving [`api::`graphql`::report`] for remote clients reporting
+/// their statistics
+#[route("/api-report", method = "POST")]
+async fn graphql_report(
+    req: HttpRequest,
+    payload: web::Payload,
+    schema: web::Data<api::graphql::report::Schema>,
+) -> Result<HttpResponse, Error> {
+    graphql(req, payload, SchemaKind::SchemaReport(schema)).await
+}
+
 /// Endpoint serving [`api::`graphql`::mix`] for single output
 /// application
 #[route("/api-mix", method = "GET", method = "POST")]
@@ -172,13 +263,23 @@ async fn graphql_mix(
 }
 
 /// Endpoint serving [`api::`graphql`::client`] for main application
+///
+/// Requests authorized with [`TenantKey`] are executed against the schema
+/// without mutations, as tenants have read-only access.
 #[route("/api", method = "GET", method = "POST")]
 async fn graphql_client(
     req: HttpRequest,
     payload: web::Payload,
     schema: web::Data<api::graphql::client::Schema>,
+    tenant_schema: web::Data<api::graphql::client::TenantSchema>,
 ) -> Result<HttpResponse, Error> {
-    graphql(req, payload, SchemaKind::Schema(schema)).await
+    let is_tenant = req.extensions().get::<TenantKey>().is_some();
+    let kind = if is_tenant {
+        SchemaKind::SchemaTenant(tenant_schema)
+    } else {
+        SchemaKind::Schema(schema)
+    };
+    graphql(req, payload, kind).await
 }
 
 async fn graphql(
@@ -188,6 +289,7 @@ async fn graphql(
 ) -> Result<HttpResponse, Error> {
     let ctx = api::graphql::Context::new(req.clone());
     if req.head().upgrade() {
+        let payload = end_on_reauth(&req, payload).await?;
         let cfg = ConnectionConfig::new(ctx)
             .with_keep_alive_interval(Duration::from_secs(5));
 
@@ -195,6 +297,9 @@ async fn graphql(
             SchemaKind::Schema(s) => {
                 subscriptions_handler(req, payload, s.into_inner(), cfg).await
             }
+            SchemaKind::SchemaTenant(s) => {
+                subscriptions_handler(req, payload, s.into_inner(), cfg).await
+            }
             SchemaKind::SchemaMix(s) => {
                 subscriptions_handler(req, payload, s.into_inner(), cfg).await
             }
@@ -204,12 +309,32 @@ async fn graphql(
             SchemaKind::SchemaStatistics(s) => {
                 subscriptions_handler(req, payload, s.into_inner(), cfg).await
             }
+            SchemaKind::SchemaReport(s) => {
+                subscriptions_handler(req, payload, s.into_inner(), cfg).await
+            }
         }
     } else {
+        // Statistics are polled and reported by other servers regularly, and
+        // are available without any credentials, so are not audited at all.
+        let is_audited = !matches!(
+            schema_kind,
+            SchemaKind::SchemaStatistics(_) | SchemaKind::SchemaReport(_),
+        );
+        let payload = if is_audited {
+            match audit(&req, payload).await? {
+                Some(p) => p,
+                None => return Ok(HttpResponse::PayloadTooLarge().finish()),
+            }
+        } else {
+            payload
+        };
         match schema_kind {
             SchemaKind::Schema(s) => {
                 graphql_handler(&s, &ctx, req, payload).await
             }
+            SchemaKind::SchemaTenant(s) => {
+                graphql_handler(&s, &ctx, req, payload).await
+            }
             SchemaKind::SchemaMix(s) => {
                 graphql_handler(&s, &ctx, req, payload).await
             }
@@ -219,10 +344,438 @@ async fn graphql(
             SchemaKind::SchemaStatistics(s) => {
                 graphql_handler(&s, &ctx, req, payload).await
             }
+            SchemaKind::SchemaReport(s) => {
+                graphql_handler(&s, &ctx, req, payload).await
+            }
         }
     }
 }
 
+/// Wraps the given WebSocket `payload` of the [`HttpRequest`] to end once
+/// [`Settings::auth_generation`] changes since the [`HttpRequest`] has been
+/// authorized, so the connection is closed and should be re-established with
+/// the actual credentials.
+///
+/// [`Settings::auth_generation`]: crate::state::Settings::auth_generation
+async fn end_on_reauth(
+    req: &HttpRequest,
+    payload: web::Payload,
+) -> Result<web::Payload, Error> {
+    let generation = match req.extensions().get::<AuthGeneration>() {
+        Some(g) => g.0,
+        None => return Ok(payload),
+    };
+    let reauth = Box::pin(
+        req.app_data::<State>()
+            .unwrap()
+            .settings
+            .signal_ref(|s| s.auth_generation)
+            .to_stream()
+            .filter(move |g| future::ready(*g != generation)),
+    )
+    .into_future();
+    let mut payload = dev::Payload::Stream {
+        payload: Box::pin(payload.take_until(reauth)),
+    };
+    web::Payload::from_request(req, &mut payload).await
+}
+
+/// Records the mutations requested by the given [`HttpRequest`] into the
+/// audit log.
+///
+/// Returns the `payload` to execute the [`HttpRequest`] with, or [`None`] if
+/// it exceeds [`MAX_GRAPHQL_SIZE`].
+async fn audit(
+    req: &HttpRequest,
+    mut payload: web::Payload,
+) -> Result<Option<web::Payload>, Error> {
+    let requests = if req.method() == Method::POST {
+        let mut body = web::BytesMut::new();
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk?;
+            if body.len() + chunk.len() > MAX_GRAPHQL_SIZE {
+                return Ok(None);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let body = body.freeze();
+        let requests = audit::Request::parse_batch(&body);
+        payload =
+            web::Payload::from_request(req, &mut dev::Payload::from(body))
+                .await?;
+        requests
+    } else {
+        web::Query::<audit::Request>::from_query(req.query_string())
+            .map(|q| {
+                let mut r = q.into_inner();
+                // Variables are passed as a JSON string in a query string.
+                if let Some(Json::String(vars)) = r.variables.as_ref() {
+                    r.variables = serde_json::from_str(vars).ok();
+                }
+                vec![r]
+            })
+            .unwrap_or_default()
+    };
+
+    let state = req.app_data::<State>().unwrap();
+    let route = req.uri().path();
+    let settings = state.settings.get_cloned();
+    let auth = if route.starts_with(MIX_ROUTE_API) {
+        settings.password_output_hash.map(|_| PasswordKind::Output)
+    } else {
+        settings.password_hash.map(|_| PasswordKind::Main)
+    };
+    let requester = audit::Requester {
+        client_ip: req.peer_addr().map(|a| a.ip().to_string()),
+        auth,
+        api: route.to_owned(),
+    };
+    audit::record(state, &requester, &requests);
+
+    Ok(Some(payload))
+}
+
+/// Query parameters of [`import`] endpoint.
+#[derive(Clone, Debug, Deserialize)]
+struct ImportParams {
+    /// Indicator whether the imported spec should replace existing
+    /// definitions.
+    #[serde(default)]
+    replace: bool,
+
+    /// Optional ID of a concrete `Restream` to apply the imported spec to
+    /// without touching other `Restream`s.
+    restream_id: Option<RestreamId>,
+}
+
+/// Endpoint importing a JSON or YAML spec file into this server, with the
+/// same semantics as `import` mutation of [`api::graphql::client`].
+///
+/// Accepts the spec file either as a raw request body, or as the first field
+/// of a `multipart/form-data` request. YAML is detected by the request (or
+/// field) `Content-Type` or by `.yaml`/`.yml` file name extension, otherwise
+/// JSON is assumed.
+///
+/// Records the import into the audit log (without the spec itself, as it may
+/// contain secrets), just like the mutations of [`rest`] endpoints.
+///
+/// Responds with `404 Not Found` if a `Restream` with the given `restream_id`
+/// doesn't exist, and with `400 Bad Request` if the spec is invalid.
+#[post("/api/import")]
+async fn import(
+    req: HttpRequest,
+    params: web::Query<ImportParams>,
+    payload: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let content_type = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+
+    let (body, is_yaml) = if content_type.starts_with("multipart/form-data") {
+        let mut multipart = Multipart::new(req.headers(), payload);
+        let mut field = if let Some(field) = multipart.try_next().await? {
+            field
+        } else {
+            return Ok(HttpResponse::BadRequest().body("No spec file provided"));
+        };
+        let is_yaml = field
+            .content_type()
+            .map_or(false, |m| is_yaml_mime(m.essence_str()))
+            || field
+                .content_disposition()
+                .get_filename()
+                .map_or(false, |f| f.ends_with(".yaml") || f.ends_with(".yml"));
+        let mut body = Vec::new();
+        while let Some(chunk) = field.try_next().await? {
+            if body.len() + chunk.len() > MAX_IMPORT_SIZE {
+                return Ok(HttpResponse::PayloadTooLarge().finish());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        (body, is_yaml)
+    } else {
+        let mut payload = payload;
+        let mut body = Vec::new();
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk?;
+            if body.len() + chunk.len() > MAX_IMPORT_SIZE {
+                return Ok(HttpResponse::PayloadTooLarge().finish());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        (body, is_yaml_mime(mime))
+    };
+
+    let body = match String::from_utf8(body) {
+        Ok(b) => b,
+        Err(_) => {
+            return Ok(HttpResponse::BadRequest()
+                .body("Spec file should be UTF-8 encoded"))
+        }
+    };
+    let expand_env = req.app_data::<Opts>().unwrap().spec_expand_env;
+    let spec = if is_yaml {
+        Spec::from_yaml(&body, expand_env)
+    } else {
+        Spec::from_json(&body, expand_env)
+    };
+    let spec = match spec {
+        Ok(s) => s.into_v1(),
+        Err(e) => {
+            return Ok(
+                HttpResponse::BadRequest().body(format!("Invalid spec: {e}"))
+            )
+        }
+    };
+
+    let state = req.app_data::<State>().unwrap();
+    if state.maintenance_mode.get() {
+        return Ok(HttpResponse::ServiceUnavailable()
+            .body("Server runs in maintenance mode"));
+    }
+
+    let params = params.into_inner();
+    let requester = audit::Requester {
+        client_ip: req.peer_addr().map(|a| a.ip().to_string()),
+        auth: state
+            .settings
+            .get_cloned()
+            .password_hash
+            .map(|_| PasswordKind::Main),
+        api: req.uri().path().to_owned(),
+    };
+    audit::record_mutation(
+        state,
+        &requester,
+        "import",
+        json!({
+            "replace": params.replace,
+            "restreamId": params.restream_id,
+        })
+        .to_string(),
+    );
+
+    Ok(
+        match state.import(spec, params.replace, params.restream_id) {
+            Ok(Some(())) => HttpResponse::Ok().json(true),
+            Ok(None) => HttpResponse::NotFound().body("Restream not found"),
+            Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+        },
+    )
+}
+
+/// Indicates whether the given MIME type denotes a YAML file.
+fn is_yaml_mime(mime: &str) -> bool {
+    matches!(
+        mime,
+        "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml"
+    )
+}
+
+/// Result of a single check performed by [`healthz`] and [`readyz`]
+/// endpoints.
+#[derive(Clone, Debug, Serialize)]
+struct HealthCheck {
+    /// Indicator whether this check has passed.
+    ok: bool,
+
+    /// Error describing why this check has failed, if it has.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl<E: fmt::Display> From<Result<(), E>> for HealthCheck {
+    #[inline]
+    fn from(res: Result<(), E>) -> Self {
+        Self {
+            ok: res.is_ok(),
+            error: res.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Structured JSON report returned by [`healthz`] and [`readyz`] endpoints.
+#[derive(Clone, Debug, Serialize)]
+struct HealthReport {
+    /// Overall status of the server: `ok` or `fail`.
+    status: &'static str,
+
+    /// Whether the spawned [SRS] server responds to its HTTP API.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    srs: HealthCheck,
+
+    /// Whether the state file (or database) is writable.
+    state_file: HealthCheck,
+
+    /// Whether the [FFmpeg] binary is resolvable.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    ffmpeg: HealthCheck,
+}
+
+impl HealthReport {
+    /// Probes all the dependencies of this server described by the given
+    /// [`Opts`].
+    async fn probe(cfg: &Opts) -> Self {
+        let (srs, state_file, ffmpeg) = future::join3(
+            api::srs::Client::ping(),
+            check_writable(match cfg.state_backend {
+                StorageKind::Json => &cfg.state_path,
+                StorageKind::Sqlite => &cfg.state_db_path,
+            }),
+            fs::canonicalize(&cfg.ffmpeg_path),
+        )
+        .await;
+        Self {
+            status: "ok",
+            srs: srs.into(),
+            state_file: state_file.into(),
+            ffmpeg: ffmpeg.map(drop).into(),
+        }
+    }
+
+    /// Converts this [`HealthReport`] into a [`HttpResponse`], considering it
+    /// failed if any of the given `checks` of it has failed.
+    fn into_response<F>(mut self, checks: F) -> HttpResponse
+    where
+        F: FnOnce(&Self) -> bool,
+    {
+        let status = if checks(&self) {
+            StatusCode::OK
+        } else {
+            self.status = "fail";
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        HttpResponse::build(status).json(self)
+    }
+}
+
+/// Checks whether the file on the given `path` is writable, or may be created
+/// if it doesn't exist yet (on a fresh install, for example).
+///
+/// # Errors
+///
+/// If the file cannot be opened for writing, or its directory doesn't allow
+/// creating files in it.
+async fn check_writable(path: &Path) -> io::Result<()> {
+    match fs::OpenOptions::new().append(true).open(path).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let dir = path
+                .parent()
+                .filter(|d| !d.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."));
+            let probe = dir.join(format!(".{}.probe", Uuid::new_v4()));
+            drop(
+                fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&probe)
+                    .await?,
+            );
+            fs::remove_file(probe).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Endpoint serving liveness probe of this server.
+///
+/// Fails if [SRS] server is not alive, or [FFmpeg] binary cannot be resolved,
+/// as this server cannot operate without them. Requires no authorization.
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [SRS]: https://github.com/ossrs/srs
+#[get("/healthz")]
+async fn healthz(req: HttpRequest) -> HttpResponse {
+    HealthReport::probe(req.app_data::<Opts>().unwrap())
+        .await
+        .into_response(|r| r.srs.ok && r.ffmpeg.ok)
+}
+
+/// Endpoint serving readiness probe of this server.
+///
+/// Fails if any of its dependencies is not operational, including the state
+/// file not being writable. Requires no authorization.
+#[get("/readyz")]
+async fn readyz(req: HttpRequest) -> HttpResponse {
+    if shutdown::is_started() {
+        return HttpResponse::ServiceUnavailable().body("Shutting down");
+    }
+    HealthReport::probe(req.app_data::<Opts>().unwrap())
+        .await
+        .into_response(|r| r.srs.ok && r.state_file.ok && r.ffmpeg.ok)
+}
+
+/// Endpoint serving a recorded [DVR] file by its `path`, as returned by
+/// `Query.dvrFiles`.
+///
+/// Supports partial downloads via HTTP `Range` requests, so recordings may be
+/// seeked while being played. Requires the main or the output password (if
+/// any).
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[get("/dvr/{path:.*}")]
+async fn dvr_file(
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let not_found = || error::ErrorNotFound("DVR file not found");
+
+    let full = dvr::Storage::global()
+        .file_path(path.into_inner())
+        .ok_or_else(not_found)?;
+    let file = NamedFile::open_async(full).await.map_err(|_| not_found())?;
+    Ok(file.into_response(&req))
+}
+
+/// Endpoint serving the latest preview image of an `InputEndpoint` by its
+/// `id`, as returned by `InputEndpoint.previewUrl`.
+///
+/// Responds with `404 Not Found` if no preview has been captured yet.
+#[get("/preview/{id}.jpg")]
+async fn preview_image(
+    req: HttpRequest,
+    id: web::Path<EndpointId>,
+) -> Result<HttpResponse, Error> {
+    let file = NamedFile::open_async(preview::path(id.into_inner()))
+        .await
+        .map_err(|_| error::ErrorNotFound("Preview not found"))?;
+    let mut res = file.into_response(&req);
+    // Previews are replaced periodically, so should be re-validated.
+    let _ = res.headers_mut().insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static("no-cache"),
+    );
+    Ok(res)
+}
+
+/// Endpoint serving [`PublicStats`] as JSON for embedding into public
+/// websites.
+///
+/// Responds with `404 Not Found` unless enabled by [`Opts::public_stats`].
+/// Requires no authorization and allows cross-origin requests.
+#[get("/api-public-stats")]
+async fn public_stats(req: HttpRequest) -> HttpResponse {
+    if !req.app_data::<Opts>().unwrap().public_stats {
+        return HttpResponse::NotFound().finish();
+    }
+    let json = PublicStats::cached_json(req.app_data::<State>().unwrap());
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((
+            "Cache-Control",
+            format!("public, max-age={}", REFRESH_INTERVAL.as_secs()),
+        ))
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .body(json)
+}
+
 /// Endpoint serving [GraphQL Playground][1] for exploring
 /// [`api::graphql::client`].
 ///
@@ -275,30 +828,95 @@ async fn playground() -> HttpResponse {
 /// Performs [`HttpRequest`] [Basic authorization][1] as middleware against
 /// [`State::password_hash`]. Doesn't consider username anyhow.
 ///
+/// [DVR] files are allowed to be accessed with
+/// [`State::password_output_hash`] as well.
+///
 /// No-op if [`State::password_hash`] is [`None`].
 ///
+/// [`CLIENT_ROUTE_API`] is allowed to be accessed with tenant credentials as
+/// well, having the [`TenantKey`] as username and checked against
+/// [`Settings::tenant_password_hashes`]. The authorized [`TenantKey`] is
+/// put into the request extensions.
+///
+/// [`REPORT_ROUTE_API`] requires a [Bearer token][2] matching the report
+/// token of any `Client`, which is put into the request extensions as a
+/// [`ReportToken`]. Its schema allows to report statistics of remote
+/// `Client`s only.
+///
+/// IP addresses failing authorization too often are locked out by the
+/// [`AuthLimiter`], responding with `429 Too Many Requests`.
+///
+/// [`Settings::tenant_password_hashes`]:
+///     crate::state::Settings::tenant_password_hashes
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+///
 /// [1]: https://en.wikipedia.org/wiki/Basic_access_authentication
+/// [2]: https://datatracker.ietf.org/doc/html/rfc6750
 fn authorize(req: ServiceRequest) -> Result<ServiceRequest, Error> {
     let route = req.uri().path();
     log::debug!("authorize URI PATH: {}", route);
 
-    if route.starts_with(STATISTICS_ROUTE_API) {
+    // No changes should be accepted once a graceful shutdown has started, so
+    // API requests, which may contain mutations, are rejected (subscriptions
+    // are opened via `GET`, so keep working till the very end).
+    if shutdown::is_started()
+        && req.method() == Method::POST
+        && (route.starts_with("/api") || route.starts_with(rest::ROUTE))
+    {
+        return Err(error::ErrorServiceUnavailable("Server is shutting down"));
+    }
+
+    if route.starts_with(STATISTICS_ROUTE_API)
+        || route == HEALTH_ROUTE
+        || route == READY_ROUTE
+        || route == PUBLIC_STATS_ROUTE
+        || route.starts_with(whip::ROUTE)
+    {
+        return Ok(req);
+    }
+
+    if route == REPORT_ROUTE_API {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|t| {
+                req.app_data::<State>()
+                    .unwrap()
+                    .clients
+                    .lock_ref()
+                    .iter()
+                    .any(|c| c.report_token.as_deref() == Some(*t))
+            })
+            .map(|t| ReportToken(t.to_owned()))
+            .ok_or_else(|| {
+                error::ErrorUnauthorized("Valid report token is required")
+            })?;
+        let _ = req.extensions_mut().insert(token);
         return Ok(req);
     }
 
     let is_mix_auth =
         route.starts_with(MIX_ROUTE) || route.starts_with(MIX_ROUTE_API);
     let settings = req.app_data::<State>().unwrap().settings.get_cloned();
+    let generation = AuthGeneration(settings.auth_generation);
 
-    let hash = if is_mix_auth {
-        settings.password_output_hash
+    let (hash, alt_hash) = if is_mix_auth {
+        (settings.password_output_hash, None)
+    } else if route.starts_with(dvr::ROUTE) {
+        (settings.password_hash, settings.password_output_hash)
     } else {
-        settings.password_hash
+        (settings.password_hash, None)
     };
 
     let hash = match hash {
         Some(h) => h,
-        None => return Ok(req),
+        None => {
+            let _ = req.extensions_mut().insert(generation);
+            return Ok(req);
+        }
     };
 
     let err = || {
@@ -310,11 +928,59 @@ fn authorize(req: ServiceRequest) -> Result<ServiceRequest, Error> {
         )
     };
 
+    let limiter = req.app_data::<web::Data<AuthLimiter>>().unwrap();
+    let ip = req.peer_addr().map(|a| a.ip());
+    if let Some(left) = ip.and_then(|ip| limiter.check(ip).err()) {
+        return Err(error::ErrorTooManyRequests(format!(
+            "Too many failed authorization attempts, retry in {}s",
+            left.as_secs() + 1,
+        )));
+    }
+
+    // Requests without credentials are not counted as failed attempts, as
+    // browsers send them to be challenged for credentials.
     let auth = BasicAuth::from_service_request(&req).into_inner()?;
-    let pass = auth.password().ok_or_else(err)?;
-    if argon2::verify_encoded(hash.as_str(), pass.as_bytes()) != Ok(true) {
-        return Err(err().into());
+    let verify = |h: &str| {
+        auth.password().map_or(false, |p| {
+            argon2::verify_encoded(h, p.as_bytes()) == Ok(true)
+        })
+    };
+    let tenant = if verify(&hash) || alt_hash.as_deref().map_or(false, verify) {
+        None
+    } else {
+        // Tenant credentials grant access to the main GraphQL API only, with
+        // the username being the tenant.
+        let tenant = (route == CLIENT_ROUTE_API)
+            .then(|| TenantKey::new(&**auth.user_id()))
+            .flatten()
+            .filter(|t| {
+                settings
+                    .tenant_password_hashes
+                    .get(t)
+                    .map_or(false, |h| verify(h))
+            });
+        if tenant.is_none() {
+            if let Some(ip) = ip {
+                let _ = limiter.fail(ip);
+            }
+            return Err(err().into());
+        }
+        tenant
+    };
+    if let Some(ip) = ip {
+        limiter.succeed(ip);
+    }
+    if let Some(tenant) = tenant {
+        let _ = req.extensions_mut().insert(tenant);
     }
+    let _ = req.extensions_mut().insert(generation);
 
     Ok(req)
 }
+
+/// [`Settings::auth_generation`] an [`HttpRequest`] has been authorized
+/// under.
+///
+/// [`Settings::auth_generation`]: crate::state::Settings::auth_generation
+#[derive(Clone, Copy, Debug)]
+struct AuthGeneration(u64);
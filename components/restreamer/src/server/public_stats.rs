@@ -0,0 +1,134 @@
+//! Downsampled viewer statistics of [`Restream`]s, safe to be served publicly.
+//!
+//! [`Restream`]: crate::state::Restream
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{
+    state::{Input, Label, RestreamId},
+    State,
+};
+
+/// Interval to refresh the cached [`PublicStats`] with.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Granularity to round viewer counts down with, so the exact audience is not
+/// disclosed.
+///
+/// Counts less than it are reported as is.
+const VIEWERS_GRANULARITY: usize = 10;
+
+/// Cached JSON representation of [`PublicStats`], along with the moment it
+/// has been collected at.
+static CACHE: Lazy<Mutex<Option<(Instant, String)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Downsampled viewer statistics of all [`Restream`]s.
+///
+/// Neither keys nor URLs of [`Restream`]s are exposed, so these statistics
+/// cannot be used for publishing or playing live streams.
+///
+/// [`Restream`]: crate::state::Restream
+#[derive(Clone, Debug, Serialize)]
+pub struct PublicStats {
+    /// Time these [`PublicStats`] have been collected at.
+    pub updated_at: DateTime<Utc>,
+
+    /// Statistics of every [`Restream`].
+    ///
+    /// [`Restream`]: crate::state::Restream
+    pub restreams: Vec<PublicRestreamStats>,
+}
+
+/// Downsampled viewer statistics of a single [`Restream`].
+///
+/// [`Restream`]: crate::state::Restream
+#[derive(Clone, Debug, Serialize)]
+pub struct PublicRestreamStats {
+    /// ID of the [`Restream`].
+    ///
+    /// [`Restream`]: crate::state::Restream
+    pub id: RestreamId,
+
+    /// Label of the [`Restream`], if any.
+    ///
+    /// [`Restream`]: crate::state::Restream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+
+    /// Indicator whether the [`Restream`] receives a live stream at the
+    /// moment.
+    ///
+    /// [`Restream`]: crate::state::Restream
+    pub online: bool,
+
+    /// Coarse number of remote clients playing the [`Restream`].
+    ///
+    /// [`Restream`]: crate::state::Restream
+    pub viewers: usize,
+}
+
+impl PublicStats {
+    /// Collects actual [`PublicStats`] from the given [`State`].
+    #[must_use]
+    pub fn collect(state: &State) -> Self {
+        let restreams = state
+            .restreams
+            .lock_ref()
+            .iter()
+            .map(|r| PublicRestreamStats {
+                id: r.id,
+                label: r.label.clone(),
+                online: r.input.is_ready_to_serve(),
+                viewers: downsample(count_viewers(&r.input)),
+            })
+            .collect();
+        Self {
+            updated_at: Utc::now(),
+            restreams,
+        }
+    }
+
+    /// Returns JSON representation of [`PublicStats`] collected from the given
+    /// [`State`], re-collecting them not more often than once per
+    /// [`REFRESH_INTERVAL`].
+    ///
+    /// # Panics
+    ///
+    /// If the cache lock is poisoned.
+    #[must_use]
+    pub fn cached_json(state: &State) -> String {
+        let mut cache = CACHE.lock().unwrap();
+        if let Some((at, json)) = cache.as_ref() {
+            if at.elapsed() < REFRESH_INTERVAL {
+                return json.clone();
+            }
+        }
+        let json = serde_json::to_string(&Self::collect(state))
+            .expect("PublicStats are always serializable");
+        *cache = Some((Instant::now(), json.clone()));
+        json
+    }
+}
+
+/// Counts remote clients playing the given [`Input`] via any of its
+/// endpoints.
+fn count_viewers(input: &Input) -> usize {
+    input.endpoints.iter().map(|e| e.srs_viewer_ids.len()).sum()
+}
+
+/// Rounds the given viewers `count` down to [`VIEWERS_GRANULARITY`].
+fn downsample(count: usize) -> usize {
+    if count < VIEWERS_GRANULARITY {
+        count
+    } else {
+        count - count % VIEWERS_GRANULARITY
+    }
+}
@@ -0,0 +1,400 @@
+//! REST endpoints of client HTTP server, providing a small subset of
+//! `api::graphql::client` for simple automations not able to speak GraphQL.
+//!
+//! Endpoints require the same authorization as `api::graphql::client` does,
+//! and are described by [OpenAPI] specification served on `/v1/openapi.json`
+//! endpoint.
+//!
+//! [OpenAPI]: https://spec.openapis.org/oas/v3.0.3
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+
+use crate::{
+    audit,
+    state::{
+        Changed, Label, Output, OutputDstUrl, OutputId, PasswordKind, Restream,
+        RestreamId, RestreamKey, StateError, Status,
+    },
+    State,
+};
+
+/// Path prefix of REST endpoints.
+pub const ROUTE: &str = "/v1/";
+
+/// Representation of a [`Restream`] returned by REST endpoints.
+#[derive(Clone, Debug, Serialize)]
+pub struct RestreamView {
+    /// ID of the [`Restream`].
+    pub id: RestreamId,
+
+    /// Key of the [`Restream`].
+    pub key: RestreamKey,
+
+    /// Label of the [`Restream`], if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+
+    /// Indicator whether the [`Restream`] is enabled.
+    pub enabled: bool,
+
+    /// Indicator whether the [`Restream`] receives a live stream at the
+    /// moment.
+    pub online: bool,
+
+    /// [`Output`]s of the [`Restream`].
+    pub outputs: Vec<OutputView>,
+}
+
+impl From<&Restream> for RestreamView {
+    fn from(r: &Restream) -> Self {
+        Self {
+            id: r.id,
+            key: r.key.clone(),
+            label: r.label.clone(),
+            enabled: r.input.enabled,
+            online: r.input.is_ready_to_serve(),
+            outputs: r.outputs.iter().map(OutputView::from).collect(),
+        }
+    }
+}
+
+/// Representation of an [`Output`] returned by REST endpoints.
+#[derive(Clone, Debug, Serialize)]
+pub struct OutputView {
+    /// ID of the [`Output`].
+    pub id: OutputId,
+
+    /// Downstream URL of the [`Output`].
+    pub dst: OutputDstUrl,
+
+    /// Label of the [`Output`], if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+
+    /// Indicator whether the [`Output`] is enabled.
+    pub enabled: bool,
+
+    /// [`Status`] of the [`Output`].
+    pub status: Status,
+}
+
+impl From<&Output> for OutputView {
+    fn from(o: &Output) -> Self {
+        Self {
+            id: o.id,
+            dst: o.dst.clone(),
+            label: o.label.clone(),
+            enabled: o.enabled,
+            status: o.status,
+        }
+    }
+}
+
+/// Action to be performed by REST endpoints toggling `Restream`s and
+/// `Output`s.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Toggle {
+    /// Enabling.
+    Enable,
+
+    /// Disabling.
+    Disable,
+}
+
+impl Toggle {
+    /// Returns name of the `api::graphql::client` mutation performing this
+    /// [`Toggle`] on the given `target`, for recording into the audit log.
+    fn mutation(self, target: &str) -> String {
+        let action = match self {
+            Self::Enable => "enable",
+            Self::Disable => "disable",
+        };
+        format!("{action}{target}")
+    }
+}
+
+/// Endpoint listing all `Restream`s along with their `Output`s.
+#[get("/v1/restreams")]
+pub async fn restreams(req: HttpRequest) -> HttpResponse {
+    let state = req.app_data::<State>().unwrap();
+    let restreams = state
+        .restreams
+        .lock_ref()
+        .iter()
+        .map(RestreamView::from)
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(restreams)
+}
+
+/// Endpoint returning a single `Restream` along with its `Output`s.
+///
+/// Responds with `404 Not Found` if the `Restream` doesn't exist.
+#[get("/v1/restreams/{restream_id}")]
+pub async fn restream(
+    req: HttpRequest,
+    path: web::Path<RestreamId>,
+) -> HttpResponse {
+    let id = path.into_inner();
+    let state = req.app_data::<State>().unwrap();
+    let restream = state
+        .restreams
+        .lock_ref()
+        .iter()
+        .find(|r| r.id == id)
+        .map(RestreamView::from);
+    match restream {
+        Some(r) => HttpResponse::Ok().json(r),
+        None => HttpResponse::NotFound().body("Restream not found"),
+    }
+}
+
+/// Endpoint enabling or disabling a `Restream`, with the same semantics as
+/// `enableRestream` and `disableRestream` mutations.
+#[post("/v1/restreams/{restream_id}/{toggle}")]
+pub async fn toggle_restream(
+    req: HttpRequest,
+    path: web::Path<(RestreamId, Toggle)>,
+) -> HttpResponse {
+    let (id, toggle) = path.into_inner();
+    perform(
+        &req,
+        &toggle.mutation("Restream"),
+        json!({ "id": id }),
+        |state| match toggle {
+            Toggle::Enable => state.enable_restream(id),
+            Toggle::Disable => state.disable_restream(id),
+        },
+    )
+}
+
+/// Endpoint enabling or disabling all `Output`s of a `Restream`, with the
+/// same semantics as `enableAllOutputs` and `disableAllOutputs` mutations.
+#[post("/v1/restreams/{restream_id}/outputs/{toggle}")]
+pub async fn toggle_all_outputs(
+    req: HttpRequest,
+    path: web::Path<(RestreamId, Toggle)>,
+) -> HttpResponse {
+    let (restream_id, toggle) = path.into_inner();
+    perform(
+        &req,
+        &toggle.mutation("AllOutputs"),
+        json!({ "restreamId": restream_id }),
+        |state| match toggle {
+            Toggle::Enable => state.enable_all_outputs(restream_id),
+            Toggle::Disable => state.disable_all_outputs(restream_id),
+        },
+    )
+}
+
+/// Endpoint enabling or disabling an `Output`, with the same semantics as
+/// `enableOutput` and `disableOutput` mutations.
+#[post("/v1/restreams/{restream_id}/outputs/{output_id}/{toggle}")]
+pub async fn toggle_output(
+    req: HttpRequest,
+    path: web::Path<(RestreamId, OutputId, Toggle)>,
+) -> HttpResponse {
+    let (restream_id, id, toggle) = path.into_inner();
+    perform(
+        &req,
+        &toggle.mutation("Output"),
+        json!({ "restreamId": restream_id, "id": id }),
+        |state| match toggle {
+            Toggle::Enable => state.enable_output(id, restream_id),
+            Toggle::Disable => state.disable_output(id, restream_id),
+        },
+    )
+}
+
+/// Endpoint serving [OpenAPI] specification of REST endpoints.
+///
+/// [OpenAPI]: https://spec.openapis.org/oas/v3.0.3
+#[get("/v1/openapi.json")]
+pub async fn openapi() -> HttpResponse {
+    HttpResponse::Ok().json(openapi_spec())
+}
+
+/// Performs the given `mutation` of the [`State`] requested by the given
+/// [`HttpRequest`], recording it into the audit log with the given `args`.
+///
+/// Responds with `true` if the [`State`] has been changed, with `false` if
+/// it's unchanged (including being enabled or disabled already), with
+/// `404 Not Found` if the mutated element doesn't exist, and with
+/// `409 Conflict` if the mutation conflicts with its current state.
+fn perform<F>(
+    req: &HttpRequest,
+    mutation: &str,
+    args: Json,
+    f: F,
+) -> HttpResponse
+where
+    F: FnOnce(&State) -> Result<Changed, StateError>,
+{
+    let state = req.app_data::<State>().unwrap();
+    if state.maintenance_mode.get() {
+        return HttpResponse::ServiceUnavailable()
+            .body("Server runs in maintenance mode");
+    }
+
+    let requester = audit::Requester {
+        client_ip: req.peer_addr().map(|a| a.ip().to_string()),
+        auth: state
+            .settings
+            .get_cloned()
+            .password_hash
+            .map(|_| PasswordKind::Main),
+        api: req.uri().path().to_owned(),
+    };
+    audit::record_mutation(state, &requester, mutation, args.to_string());
+
+    match f(state) {
+        Ok(changed) => HttpResponse::Ok().json(changed),
+        Err(StateError::NotFound(_)) => {
+            HttpResponse::NotFound().body("Not found")
+        }
+        Err(e @ StateError::Conflict(_)) => {
+            HttpResponse::Conflict().body(e.to_string())
+        }
+    }
+}
+
+/// Builds [OpenAPI] specification of REST endpoints.
+///
+/// [OpenAPI]: https://spec.openapis.org/oas/v3.0.3
+#[must_use]
+pub fn openapi_spec() -> Json {
+    let toggle = |summary: &str, params: &[&str]| {
+        let mut parameters = params
+            .iter()
+            .map(|p| json!({ "$ref": format!("#/components/parameters/{p}") }))
+            .collect::<Vec<_>>();
+        parameters.push(json!({ "$ref": "#/components/parameters/toggle" }));
+        json!({
+            "post": {
+                "summary": summary,
+                "parameters": parameters,
+                "responses": {
+                    "200": {
+                        "description": "`true` if changed, `false` if \
+                                        nothing to change",
+                        "content": {"application/json": {
+                            "schema": {"type": "boolean"},
+                        }},
+                    },
+                    "404": {"description": "Not found"},
+                    "503": {"description": "Server runs in maintenance mode"},
+                },
+            },
+        })
+    };
+    let uuid_param = |name: &str| {
+        json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": {"type": "string", "format": "uuid"},
+        })
+    };
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Ephyr re-streamer REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "security": [{"basic": []}],
+        "paths": {
+            "/v1/restreams": {
+                "get": {
+                    "summary": "Lists all Restreams with their Outputs",
+                    "responses": {"200": {
+                        "description": "List of Restreams",
+                        "content": {"application/json": {"schema": {
+                            "type": "array",
+                            "items": {"$ref": "#/components/schemas/Restream"},
+                        }}},
+                    }},
+                },
+            },
+            "/v1/restreams/{restream_id}": {
+                "get": {
+                    "summary": "Returns a single Restream with its Outputs",
+                    "parameters": [
+                        {"$ref": "#/components/parameters/restream_id"},
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Restream",
+                            "content": {"application/json": {"schema": {
+                                "$ref": "#/components/schemas/Restream",
+                            }}},
+                        },
+                        "404": {"description": "Not found"},
+                    },
+                },
+            },
+            "/v1/restreams/{restream_id}/{toggle}": toggle(
+                "Enables or disables a Restream",
+                &["restream_id"],
+            ),
+            "/v1/restreams/{restream_id}/outputs/{toggle}": toggle(
+                "Enables or disables all Outputs of a Restream",
+                &["restream_id"],
+            ),
+            "/v1/restreams/{restream_id}/outputs/{output_id}/{toggle}": toggle(
+                "Enables or disables an Output",
+                &["restream_id", "output_id"],
+            ),
+        },
+        "components": {
+            "securitySchemes": {
+                "basic": {"type": "http", "scheme": "basic"},
+            },
+            "parameters": {
+                "restream_id": uuid_param("restream_id"),
+                "output_id": uuid_param("output_id"),
+                "toggle": {
+                    "name": "toggle",
+                    "in": "path",
+                    "required": true,
+                    "schema": {"type": "string", "enum": ["enable", "disable"]},
+                },
+            },
+            "schemas": {
+                "Restream": {
+                    "type": "object",
+                    "required": ["id", "key", "enabled", "online", "outputs"],
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"},
+                        "key": {"type": "string"},
+                        "label": {"type": "string"},
+                        "enabled": {"type": "boolean"},
+                        "online": {"type": "boolean"},
+                        "outputs": {
+                            "type": "array",
+                            "items": {"$ref": "#/components/schemas/Output"},
+                        },
+                    },
+                },
+                "Output": {
+                    "type": "object",
+                    "required": ["id", "dst", "enabled", "status"],
+                    "properties": {
+                        "id": {"type": "string", "format": "uuid"},
+                        "dst": {"type": "string", "format": "uri"},
+                        "label": {"type": "string"},
+                        "enabled": {"type": "boolean"},
+                        "status": {
+                            "type": "string",
+                            "enum": [
+                                "offline", "initializing", "online", "unstable",
+                            ],
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
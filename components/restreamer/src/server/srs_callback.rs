@@ -7,6 +7,7 @@ use actix_web::{
     error, middleware, post, web, web::Data, App, Error, HttpServer,
 };
 use futures::{FutureExt, TryFutureExt};
+use tracing_actix_web::TracingLogger;
 use url::Url;
 
 use ephyr_log::log;
@@ -14,9 +15,12 @@ use ephyr_log::log;
 use crate::{
     api::srs::callback,
     cli::{Failure, Opts},
-    display_panic,
-    state::{EndpointId, Input, InputEndpointKind, InputSrc, State, Status},
-    stream_probe::stream_probe,
+    display_panic, srs,
+    state::{
+        EndpointId, HlsRendition, Input, InputEndpointKind, InputSrc, State,
+        Status,
+    },
+    stream_probe::stream_probe_once,
 };
 
 /// Runs HTTP server for exposing [SRS] [HTTP Callback API][1] on `/`
@@ -34,10 +38,13 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
         App::new()
             .app_data(Data::new(state.clone()))
             .wrap(middleware::Logger::default())
+            .wrap(TracingLogger::default())
             .service(on_callback)
     })
     .bind((cfg.callback_http_ip, cfg.callback_http_port))
     .map_err(|e| log::error!("Failed to bind callback HTTP server: {e}"))?
+    // Signals are handled by `shutdown::Coordinator`.
+    .disable_signals()
     .run()
     .await
     .map_err(|e| {
@@ -55,6 +62,11 @@ pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
 /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPCallback
 #[allow(clippy::unused_async)]
 #[post("/")]
+#[tracing::instrument(
+    name = "srs_callback",
+    skip_all,
+    fields(action = ?req.action, app = %req.app, stream = ?req.stream),
+)]
 async fn on_callback(
     req: web::Json<callback::Request>,
     state: Data<State>,
@@ -72,22 +84,31 @@ async fn on_callback(
 
 /// Handles [`callback::Event::OnConnect`].
 ///
-/// Only checks whether the appropriate [`state::Restream`] exists and its
-/// [`Input`] is enabled.
+/// Only checks whether the appropriate [`state::Restream`] exists, its
+/// [`Input`] is enabled, and the client's IP is not denied by its
+/// [`RestreamIpFilter`].
 ///
 /// # Errors
 ///
-/// If [`callback::Request::app`] matches no existing [`state::Restream`].
+/// - If [`callback::Request::app`] matches no existing [`state::Restream`].
+/// - If [`callback::Request::ip`] is denied by [`RestreamIpFilter`].
 ///
+/// [`RestreamIpFilter`]: crate::state::RestreamIpFilter
 /// [`state::Restream`]: crate::state::Restream
 fn on_connect(req: &callback::Request, state: &State) -> Result<(), Error> {
-    state
-        .restreams
-        .get_cloned()
+    let restreams = state.restreams.lock_ref();
+    let restream = restreams
         .iter()
-        .find(|r| r.input.enabled && r.key == *req.app)
-        .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))
-        .map(|_| ())
+        .find(|r| r.input.enabled && r.srs_app() == req.app)
+        .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
+    if restream
+        .ip_filter
+        .as_ref()
+        .map_or(false, |f| f.is_denied(req.ip))
+    {
+        return Err(error::ErrorForbidden("Such `ip` is denied"));
+    }
+    Ok(())
 }
 
 /// Handles [`callback::Event::OnPublish`] and [`callback::Event::OnPlay`].
@@ -103,8 +124,17 @@ fn on_connect(req: &callback::Request, state: &State) -> Result<(), Error> {
 ///   [`InputEndpoint`].
 /// - If [`InputEndpoint`] is not allowed to be published by external
 ///   client.
+/// - If [`callback::Request::param`] doesn't contain a valid secret key of
+///   [`InputEndpoint`] being published by external client.
+/// - If [`callback::Request::param`] doesn't contain a valid
+///   [`PublishToken`] of [`state::Restream`] being published by external
+///   client.
+/// - If [`callback::Request::ip`] is not allowed to publish by
+///   [`RestreamIpFilter`].
 ///
 /// [`InputEndpoint`]: crate::state::InputEndpoint
+/// [`PublishToken`]: crate::state::PublishToken
+/// [`RestreamIpFilter`]: crate::state::RestreamIpFilter
 /// [`state::Restream`]: crate::state::Restream
 ///
 /// [SRS]: https://github.com/ossrs/srs
@@ -132,14 +162,20 @@ fn on_start(
 
     let stream = req.stream.as_deref().unwrap_or_default();
     let kind = match req.vhost.as_str() {
-        "hls" => InputEndpointKind::Hls,
+        v if srs::is_hls_vhost(v) => InputEndpointKind::Hls,
+        "whip" => InputEndpointKind::Whip,
         _ => InputEndpointKind::Rtmp,
     };
+    // Only HLS streams have renditions, published as separate streams.
+    let (stream, rendition) = match kind {
+        InputEndpointKind::Hls => HlsRendition::split_stream(stream),
+        _ => (stream, None),
+    };
 
     let mut restreams = state.restreams.lock_mut();
     let restream = restreams
         .iter_mut()
-        .find(|r| r.input.enabled && r.key == *req.app)
+        .find(|r| r.input.enabled && r.srs_app() == req.app)
         .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
 
     let input = lookup_input(&mut restream.input, stream)
@@ -150,14 +186,49 @@ fn on_start(
         .iter_mut()
         .find(|e| e.kind == kind)
         .ok_or_else(|| error::ErrorForbidden("Such `vhost` is not allowed"))?;
+    if rendition.map_or(false, |r| !endpoint.has_hls_rendition(r)) {
+        return Err(error::ErrorNotFound("Such `stream` doesn't exist"));
+    }
 
     if publishing {
-        if !req.ip.is_loopback() && (input.src.is_some() || !endpoint.is_rtmp())
+        // Renditions are published by local transcoding processes only, and
+        // don't affect the status of their endpoint.
+        if rendition.is_some() {
+            return if req.ip.is_loopback() {
+                Ok(())
+            } else {
+                Err(error::ErrorForbidden(
+                    "Such `stream` is allowed only locally",
+                ))
+            };
+        }
+        if let Some(filter) = &restream.ip_filter {
+            if !filter.is_allowed(req.ip) {
+                return Err(error::ErrorForbidden(
+                    "Such `ip` is not allowed to publish",
+                ));
+            }
+        }
+        if !req.ip.is_loopback()
+            && (input.src.is_some() || !endpoint.is_publishable())
         {
             return Err(error::ErrorForbidden(
                 "Such `stream` is allowed only locally",
             ));
         }
+        if !req.ip.is_loopback()
+            && !endpoint.is_publish_allowed(req.param.as_deref())
+        {
+            return Err(error::ErrorForbidden("Invalid secret `key`"));
+        }
+        if !req.ip.is_loopback()
+            && !restream
+                .publish_token
+                .as_ref()
+                .map_or(true, |t| t.is_passed(req.param.as_deref()))
+        {
+            return Err(error::ErrorForbidden("Invalid publish `token`"));
+        }
 
         let publisher_id = match endpoint.srs_publisher_id.clone() {
             Some(id) => id.get_value(),
@@ -168,10 +239,10 @@ fn on_start(
             endpoint.srs_publisher_id = Some(req.client_id.clone().into());
         }
 
-        endpoint.status = Status::Online;
+        endpoint.set_status(Status::Online, Some("Publishing started"));
 
         let url = InputEndpointKind::get_rtmp_url(
-            &restream.key,
+            &restream.srs_app(),
             &input.key,
             InputEndpointKind::Rtmp,
         );
@@ -187,6 +258,9 @@ fn on_start(
             let _ =
                 endpoint.srs_player_ids.insert(req.client_id.clone().into());
         }
+        if !req.ip.is_loopback() {
+            let _ = endpoint.srs_viewer_ids.insert(req.client_id.clone());
+        }
     }
     Ok(())
 }
@@ -227,14 +301,20 @@ fn on_stop(
 
     let stream = req.stream.as_deref().unwrap_or_default();
     let kind = match req.vhost.as_str() {
-        "hls" => InputEndpointKind::Hls,
+        v if srs::is_hls_vhost(v) => InputEndpointKind::Hls,
+        "whip" => InputEndpointKind::Whip,
         _ => InputEndpointKind::Rtmp,
     };
+    // Only HLS streams have renditions, published as separate streams.
+    let (stream, rendition) = match kind {
+        InputEndpointKind::Hls => HlsRendition::split_stream(stream),
+        _ => (stream, None),
+    };
 
     let mut restreams = state.restreams.lock_mut();
     let restream = restreams
         .iter_mut()
-        .find(|r| r.key == *req.app)
+        .find(|r| r.srs_app() == req.app)
         .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
 
     let input = lookup_input(&mut restream.input, stream)
@@ -245,12 +325,19 @@ fn on_stop(
         .iter_mut()
         .find(|e| e.kind == kind)
         .ok_or_else(|| error::ErrorForbidden("Such `vhost` is not allowed"))?;
+    if rendition.map_or(false, |r| !endpoint.has_hls_rendition(r)) {
+        return Err(error::ErrorNotFound("Such `stream` doesn't exist"));
+    }
 
     if publishing {
+        if rendition.is_some() {
+            return Ok(());
+        }
         endpoint.srs_publisher_id = None;
-        endpoint.status = Status::Offline;
+        endpoint.set_status(Status::Offline, Some("Publishing stopped"));
     } else {
         let _ = endpoint.srs_player_ids.remove(&req.client_id);
+        let _ = endpoint.srs_viewer_ids.remove(&req.client_id);
     }
     Ok(())
 }
@@ -287,14 +374,15 @@ fn on_hls(req: &callback::Request, state: &State) -> Result<(), Error> {
     }
 
     let stream = req.stream.as_deref().unwrap_or_default();
-    let kind = (req.vhost.as_str() == "hls")
+    let kind = srs::is_hls_vhost(&req.vhost)
         .then_some(InputEndpointKind::Hls)
         .ok_or_else(|| error::ErrorForbidden("Such `vhost` is not allowed"))?;
+    let (stream, rendition) = HlsRendition::split_stream(stream);
 
     let mut restreams = state.restreams.lock_mut();
     let restream = restreams
         .iter_mut()
-        .find(|r| r.input.enabled && r.key == *req.app)
+        .find(|r| r.input.enabled && r.srs_app() == req.app)
         .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
 
     let endpoint = lookup_input(&mut restream.input, stream)
@@ -303,6 +391,9 @@ fn on_hls(req: &callback::Request, state: &State) -> Result<(), Error> {
         .iter_mut()
         .find(|e| e.kind == kind)
         .ok_or_else(|| error::ErrorNotFound("Such `stream` doesn't exist"))?;
+    if rendition.map_or(false, |r| !endpoint.has_hls_rendition(r)) {
+        return Err(error::ErrorNotFound("Such `stream` doesn't exist"));
+    }
 
     if endpoint.status != Status::Online {
         return Err(error::ErrorImATeapot("Not ready to serve"));
@@ -314,16 +405,21 @@ fn on_hls(req: &callback::Request, state: &State) -> Result<(), Error> {
     if !endpoint.srs_player_ids.contains(&req.client_id) {
         let _ = endpoint.srs_player_ids.insert(req.client_id.clone().into());
     }
+    if !req.ip.is_loopback() {
+        let _ = endpoint.srs_viewer_ids.insert(req.client_id.clone());
+    }
     Ok(())
 }
 
 fn update_stream_info(id: EndpointId, url: Url, state: State) {
     drop(tokio::spawn(
         AssertUnwindSafe(async move {
-            let result = stream_probe(url).await;
-            state
-                .set_stream_info(id, result)
-                .unwrap_or_else(|e| log::error!("{}", e));
+            // Stream being probed already will be cached by its prober.
+            if let Some(result) = stream_probe_once(url).await {
+                state
+                    .set_stream_info(id, result)
+                    .unwrap_or_else(|e| log::error!("{}", e));
+            }
         })
         .catch_unwind()
         .map_err(move |p| {
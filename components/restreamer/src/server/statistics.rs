@@ -1,15 +1,171 @@
-//! Module which collects server statistics and updates them every second
-use std::time::Duration;
+//! Module which collects server statistics and updates them periodically
+use std::{
+    collections::HashMap,
+    convert::TryFrom as _,
+    fs, io,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 use systemstat::{Platform, System};
-use tokio::time;
+use tokio::{task, time};
 
-use crate::{cli::Failure, display_panic, state::ServerInfo, State};
+use crate::{
+    cli::Failure,
+    display_panic, dvr,
+    ffmpeg::RestreamerKind,
+    process::{self, Pid},
+    state::{ProcessUsage, ServerInfo},
+    State,
+};
 use ephyr_log::log;
-use futures::FutureExt;
+use futures::{future, FutureExt};
+#[cfg(unix)]
+use nix::{
+    sys::statvfs,
+    unistd::{sysconf, SysconfVar},
+};
 use num_cpus;
+use once_cell::sync::Lazy;
 use std::panic::AssertUnwindSafe;
+use uuid::Uuid;
 
-/// Runs statistics monitoring
+/// Fake CPU usage (in percents) reported instead of the real one, along with
+/// the moment it expires at.
+static FAKE_CPU_USAGE: Lazy<Mutex<Option<(f64, Instant)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Injects the given fake CPU `usage` (in percents) to be reported instead of
+/// the real one during the given `period`, simulating a high load.
+///
+/// Removes the injected fake CPU usage if [`None`] is given.
+///
+/// # Panics
+///
+/// If the fake CPU usage lock is poisoned.
+pub fn inject_cpu_usage(usage: Option<f64>, period: Duration) {
+    *FAKE_CPU_USAGE.lock().unwrap() =
+        usage.map(|u| (u, Instant::now() + period));
+}
+
+/// Returns the fake CPU usage (in percents) injected via
+/// [`inject_cpu_usage()`], if it hasn't expired yet.
+fn fake_cpu_usage() -> Option<f64> {
+    FAKE_CPU_USAGE
+        .lock()
+        .unwrap()
+        .filter(|(_, until)| *until > Instant::now())
+        .map(|(usage, _)| usage)
+}
+
+/// Samples [`ProcessUsage`]s of the running [FFmpeg] processes, identified
+/// by IDs of the elements in a [`State`] they serve.
+///
+/// `last` contains CPU times (in clock ticks) of the processes sampled
+/// previously, `elapsed` time ago, and is updated with the new ones.
+///
+/// Processes exceeding the given `rss_limit` (in megabytes) are terminated,
+/// so restarted afresh.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_precision_loss)]
+fn sample_processes(
+    last: &mut HashMap<Pid, u64>,
+    elapsed: Duration,
+    rss_limit: Option<u64>,
+) -> HashMap<Uuid, ProcessUsage> {
+    let (ticks_per_sec, page_size) = ticks_and_page_size();
+
+    let mut ticks = HashMap::new();
+    let mut usages = HashMap::new();
+    for (id, pid) in RestreamerKind::running_processes() {
+        let (cpu_ticks, rss_pages) = match read_proc_stat(pid) {
+            Some(stat) => stat,
+            None => continue,
+        };
+        let cpu = last.get(&pid).map_or(0.0, |prev| {
+            cpu_ticks.saturating_sub(*prev) as f64
+                / ticks_per_sec as f64
+                / elapsed.as_secs_f64().max(f64::EPSILON)
+                * 100.0
+        });
+        let rss = rss_pages * page_size / 1024 / 1024;
+        let _ = ticks.insert(pid, cpu_ticks);
+
+        if let Some(limit) = rss_limit.filter(|l| rss > *l) {
+            log::warn!(
+                "FFmpeg process {} of {} exceeded memory limit: {} MB > {} \
+                 MB, terminating",
+                pid,
+                id,
+                rss,
+                limit,
+            );
+            let _ = process::stop(pid);
+        }
+
+        let _ = usages.insert(
+            id,
+            ProcessUsage {
+                cpu: cpu.round() as i32,
+                rss: i32::try_from(rss).unwrap_or(i32::MAX),
+            },
+        );
+    }
+    *last = ticks;
+    usages
+}
+
+/// Returns the number of clock ticks per second and the size of a memory page
+/// (in bytes) of the system.
+#[cfg(unix)]
+fn ticks_and_page_size() -> (u64, u64) {
+    let ticks_per_sec = sysconf(SysconfVar::CLK_TCK)
+        .ok()
+        .flatten()
+        .map_or(100, |t| t as u64);
+    let page_size = sysconf(SysconfVar::PAGE_SIZE)
+        .ok()
+        .flatten()
+        .map_or(4096, |s| s as u64);
+    (ticks_per_sec, page_size)
+}
+
+/// Returns the conventional number of clock ticks per second and size of a
+/// memory page (in bytes), as there is no `/proc` to read them for anyway.
+#[cfg(not(unix))]
+fn ticks_and_page_size() -> (u64, u64) {
+    (100, 4096)
+}
+
+/// Reads the total CPU time (in clock ticks) and the resident memory (in
+/// pages) of the process with the given `pid` from its `/proc/<pid>/stat`.
+///
+/// Returns [`None`] if the process doesn't exist anymore.
+fn read_proc_stat(pid: Pid) -> Option<(u64, u64)> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Process name may contain spaces, so the fields are counted from its
+    // closing parenthesis, starting from the 3rd one (`state`).
+    let mut fields = stat.get(stat.rfind(')')? + 1..)?.split_whitespace();
+    let utime: u64 = fields.nth(11)?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    let rss: u64 = fields.nth(8)?.parse().ok()?;
+    Some((utime + stime, rss))
+}
+
+/// Runs statistics monitoring, sampling the server resources every
+/// `interval`.
+///
+/// CPU, RAM, network and disk (of the [DVR] storage) usages are sampled by
+/// independent samplers running concurrently, and joined into a single
+/// [`ServerInfo`] per tick.
+///
+/// Also samples resources usage of the running [FFmpeg] processes,
+/// terminating the ones exceeding the given `rss_limit` (in megabytes).
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
 ///
 /// # Panics
 /// Panic is captured to log. Could be panicked during getting server
@@ -18,112 +174,101 @@ use std::panic::AssertUnwindSafe;
 /// # Errors
 /// No return errors expected. Preserved return signature in order to
 /// run in `future::try_join3`
-#[allow(clippy::cast_possible_truncation)]
-#[allow(clippy::cast_precision_loss)]
-#[allow(clippy::cast_possible_wrap)]
-pub async fn run(state: State) -> Result<(), Failure> {
-    // we use tx_last and rx_last to compute the delta
-    // (send/receive bytes last second)
-    let mut tx_last: f64 = 0.0;
-    let mut rx_last: f64 = 0.0;
+pub async fn run(
+    state: State,
+    interval: Duration,
+    rss_limit: Option<u64>,
+) -> Result<(), Failure> {
+    // Traffic sampled last time, to compute its delta.
+    let mut traffic_last: Option<(f64, f64)> = None;
+    // CPU times of FFmpeg processes sampled last time, to compute their CPU
+    // usage
+    let mut ticks_last = HashMap::new();
+    let mut sampled_at = Instant::now();
+    // CPU load is measured between ticks, so sampling doesn't wait for it.
+    let mut cpu_load = System::new().cpu_load_aggregate().ok();
 
     let spawner = async move {
+        let mut ticker = time::interval(interval);
+        ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        let _ = ticker.tick().await;
         loop {
+            let _ = ticker.tick().await;
             let state = &state;
+            let elapsed = sampled_at.elapsed();
+            sampled_at = Instant::now();
 
             let _ = AssertUnwindSafe(async {
-                let sys = System::new();
+                let cpu = cpu_load.take();
+                let dvr_dir = dvr::Storage::global().root_path.clone();
+                let (cpu, ram, traffic, disk) = future::join4(
+                    sample(move || match cpu {
+                        Some(m) => m.done(),
+                        None => Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            "CPU load measurement is not started",
+                        )),
+                    }),
+                    sample(|| System::new().memory()),
+                    sample(sample_traffic),
+                    sample(move || sample_disk(&dvr_dir)),
+                )
+                .await;
+                cpu_load = System::new().cpu_load_aggregate().ok();
 
                 let mut info = ServerInfo::default();
+                let mut fail = |metric: &str, e: io::Error| {
+                    info.set_error(Some(e.to_string()));
+                    log::error!("Statistics. {}: error: {}", metric, e);
+                };
 
-                // Update cpu usage
-                match sys.cpu_load_aggregate() {
+                match cpu {
+                    // in percents
                     Ok(cpu) => {
-                        // Need to wait some time to let the library compute
-                        // CPU usage.
-                        // Do not change delay time, since it is also used
-                        // further to compute network statistics
-                        // (bytes sent/received last second)
-                        time::sleep(Duration::from_secs(1)).await;
-                        let cpu = cpu.done().unwrap();
-
-                        // in percents
-                        info.update_cpu(Some(
-                            f64::from(1.0 - cpu.idle) * 100.0,
-                        ));
-
-                        let cpus_usize = num_cpus::get();
-                        let cpus: i32 = cpus_usize as i32;
-
-                        info.update_cores(Some(cpus));
-                    }
-                    Err(x) => {
-                        info.set_error(Some(x.to_string()));
-                        log::error!("Statistics. CPU load: error: {}", x);
+                        info.update_cpu(Some(fake_cpu_usage().unwrap_or_else(
+                            || f64::from(1.0 - cpu.idle) * 100.0,
+                        )))
                     }
+                    Err(e) => fail("CPU load", e),
                 }
+                info.update_cores(i32::try_from(num_cpus::get()).ok());
 
-                // Update ram usage
-                match sys.memory() {
-                    Ok(mem) => {
-                        // in megabytes
-                        let mem_total = mem.total.as_u64() / 1024 / 1024;
-                        // in megabytes
-                        let mem_free = mem.free.as_u64() / 1024 / 1024;
-                        info.update_ram(
-                            Some(mem_total as f64),
-                            Some(mem_free as f64),
-                        );
-                    }
-                    Err(x) => {
-                        info.set_error(Some(x.to_string()));
-                        log::error!("Statistics. Memory: error: {}", x);
-                    }
+                match ram {
+                    Ok(mem) => info.update_ram(
+                        Some(to_megabytes(mem.total.as_u64())),
+                        Some(to_megabytes(mem.free.as_u64())),
+                    ),
+                    Err(e) => fail("Memory", e),
                 }
 
-                // Update network usage
-                match sys.networks() {
-                    Ok(netifs) => {
-                        // Sum up along network interfaces
-                        let mut tx: f64 = 0.0;
-                        let mut rx: f64 = 0.0;
-
-                        // Note that the sum of sent/received bytes are
-                        // computed among all the available network
-                        // interfaces
-                        for netif in netifs.values() {
-                            let netstats =
-                                sys.network_stats(&netif.name).unwrap();
-                            // in megabytes
-                            tx += netstats.tx_bytes.as_u64() as f64
-                                / 1024.0
-                                / 1024.0;
-                            // in megabytes
-                            rx += netstats.rx_bytes.as_u64() as f64
-                                / 1024.0
-                                / 1024.0;
+                match traffic {
+                    Ok((tx, rx)) => {
+                        // Deltas are normalized to a second, regardless of
+                        // the sampling interval.
+                        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+                        if let Some((tx_last, rx_last)) = traffic_last {
+                            info.update_traffic_usage(
+                                Some((tx - tx_last) / secs),
+                                Some((rx - rx_last) / secs),
+                            );
                         }
-
-                        // Compute delta
-                        let tx_delta = tx - tx_last;
-                        let rx_delta = rx - rx_last;
-
-                        // Update server info
-                        info.update_traffic_usage(
-                            Some(tx_delta),
-                            Some(rx_delta),
-                        );
-
-                        tx_last = tx;
-                        rx_last = rx;
+                        traffic_last = Some((tx, rx));
                     }
-                    Err(x) => {
-                        info.set_error(Some(x.to_string()));
-                        log::error!("Statistics. Networks: error: {}", x);
+                    Err(e) => fail("Networks", e),
+                }
+
+                match disk {
+                    Ok((total, free)) => {
+                        info.update_disk(Some(total), Some(free));
                     }
+                    Err(e) => fail("Disk", e),
                 }
 
-                *state.server_info.lock_mut() = info;
+                state.record_server_info(info);
+
+                let usages =
+                    sample_processes(&mut ticks_last, elapsed, rss_limit);
+                state.set_process_usages(&usages);
 
                 // Try to clean up stream info
                 state.cleanup_stream_info();
@@ -143,3 +288,69 @@ pub async fn run(state: State) -> Result<(), Failure> {
 
     Ok(())
 }
+
+/// Runs the given blocking `sampler` on a dedicated thread, so the samplers
+/// don't block each other.
+async fn sample<T, F>(sampler: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    task::spawn_blocking(sampler)
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+}
+
+/// Samples the total traffic (in megabytes) sent and received via all the
+/// network interfaces of the server.
+#[allow(clippy::cast_precision_loss)]
+fn sample_traffic() -> io::Result<(f64, f64)> {
+    let sys = System::new();
+    let (mut tx, mut rx) = (0, 0);
+    for netif in sys.networks()?.values() {
+        let stats = sys.network_stats(&netif.name)?;
+        tx += stats.tx_bytes.as_u64();
+        rx += stats.rx_bytes.as_u64();
+    }
+    Ok((to_megabytes(tx), to_megabytes(rx)))
+}
+
+/// Samples the total and the available space (in megabytes) of the
+/// filesystem containing the given `path`.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)]
+fn sample_disk(path: &Path) -> io::Result<(f64, f64)> {
+    let stat = statvfs::statvfs(path)?;
+    let fragment = u64::from(stat.fragment_size());
+    Ok((
+        to_megabytes(u64::from(stat.blocks()) * fragment),
+        to_megabytes(u64::from(stat.blocks_available()) * fragment),
+    ))
+}
+
+/// Samples the total and the available space (in megabytes) of the
+/// filesystem mounted at the closest ancestor of the given `path`.
+#[cfg(not(unix))]
+fn sample_disk(path: &Path) -> io::Result<(f64, f64)> {
+    let fs = System::new()
+        .mounts()?
+        .into_iter()
+        .filter(|fs| path.starts_with(&fs.fs_mounted_on))
+        .max_by_key(|fs| fs.fs_mounted_on.len())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No filesystem is mounted for {}", path.display()),
+            )
+        })?;
+    Ok((
+        to_megabytes(fs.total.as_u64()),
+        to_megabytes(fs.avail.as_u64()),
+    ))
+}
+
+/// Converts the given `bytes` into megabytes.
+#[allow(clippy::cast_precision_loss)]
+fn to_megabytes(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0
+}
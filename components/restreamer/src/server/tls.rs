@@ -0,0 +1,135 @@
+//! [TLS] configuration of HTTP servers.
+//!
+//! [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use tokio::{fs, io::AsyncWriteExt as _};
+
+use crate::cli::Opts;
+
+/// Builds a [`ServerConfig`] of the client HTTP server out of the given
+/// [`Opts`].
+///
+/// If [`Opts::tls_self_signed`] is specified, then a self-signed certificate
+/// is generated and written into [`Opts::tls_cert_path`] and
+/// [`Opts::tls_key_path`] (if they're specified and don't exist yet), so it
+/// stays the same across restarts.
+///
+/// Returns [`None`] if [TLS] is not configured.
+///
+/// # Errors
+///
+/// If the certificate or its private key fails to be read, parsed or
+/// generated.
+///
+/// [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+pub async fn config(cfg: &Opts) -> anyhow::Result<Option<ServerConfig>> {
+    let (cert_pem, key_pem) = match (&cfg.tls_cert_path, &cfg.tls_key_path) {
+        (Some(cert), Some(key))
+            if !cfg.tls_self_signed || (cert.exists() && key.exists()) =>
+        {
+            (read(cert).await?, read(key).await?)
+        }
+        (None, None) if !cfg.tls_self_signed => return Ok(None),
+        (Some(_), None) | (None, Some(_)) => {
+            return Err(anyhow!(
+                "Both TLS certificate and private key should be specified",
+            ))
+        }
+        (cert, key) => {
+            let (cert_pem, key_pem) = self_signed(cfg)?;
+            if let (Some(cert), Some(key)) = (cert, key) {
+                write(cert, &cert_pem, false).await?;
+                write(key, &key_pem, true).await?;
+            }
+            log::warn!(
+                "Using self-signed TLS certificate, which is not trusted by \
+                 clients",
+            );
+            (cert_pem.into_bytes(), key_pem.into_bytes())
+        }
+    };
+
+    let certs = rustls_pemfile::certs(&mut cert_pem.as_slice())
+        .map_err(|e| anyhow!("Failed to parse TLS certificate: {e}"))?
+        .into_iter()
+        .map(Certificate)
+        .collect::<Vec<_>>();
+    if certs.is_empty() {
+        return Err(anyhow!("No TLS certificate found"));
+    }
+    let key = rustls_pemfile::read_all(&mut key_pem.as_slice())
+        .map_err(|e| anyhow!("Failed to parse TLS private key: {e}"))?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(k) | Item::PKCS8Key(k) | Item::ECKey(k) => {
+                Some(PrivateKey(k))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No TLS private key found"))?;
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map(Some)
+        .map_err(|e| anyhow!("Invalid TLS certificate or private key: {e}"))
+}
+
+/// Generates a new self-signed certificate in PEM format for the hosts
+/// described by the given [`Opts`], along with its private key.
+///
+/// # Errors
+///
+/// If the certificate fails to be generated.
+fn self_signed(cfg: &Opts) -> anyhow::Result<(String, String)> {
+    let mut hosts =
+        vec!["localhost".to_owned(), cfg.client_http_ip.to_string()];
+    hosts.extend(cfg.public_host.clone());
+    hosts.sort();
+    hosts.dedup();
+    let cert = rcgen::generate_simple_self_signed(hosts).map_err(|e| {
+        anyhow!("Failed to generate self-signed TLS certificate: {e}")
+    })?;
+    let cert_pem = cert.serialize_pem().map_err(|e| {
+        anyhow!("Failed to serialize self-signed TLS certificate: {e}")
+    })?;
+    Ok((cert_pem, cert.serialize_private_key_pem()))
+}
+
+/// Reads the whole PEM file at the given `path`.
+async fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    fs::read(path)
+        .await
+        .map_err(|e| anyhow!("Failed to read '{}' file: {}", path.display(), e))
+}
+
+/// Writes the given PEM `contents` into a new file at the given `path`.
+///
+/// If `private`, then the created file is readable and writable by its owner
+/// only (on Unix platforms).
+async fn write(
+    path: &Path,
+    contents: &str,
+    private: bool,
+) -> anyhow::Result<()> {
+    let mut opts = fs::OpenOptions::new();
+    let _ = opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    let _ = opts.mode(if private { 0o600 } else { 0o644 });
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut file = opts.open(path).await.map_err(|e| {
+        anyhow!("Failed to create '{}' file: {}", path.display(), e)
+    })?;
+    file.write_all(contents.as_bytes()).await.map_err(|e| {
+        anyhow!("Failed to write '{}' file: {}", path.display(), e)
+    })
+}
@@ -0,0 +1,215 @@
+//! [WHIP] endpoints of client HTTP server, accepting [WebRTC] live streams
+//! into `InputEndpointKind::Whip` endpoints.
+//!
+//! [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+//! [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+
+use actix_web::{
+    delete, http::header, post, route, web, HttpRequest, HttpResponse,
+};
+use ephyr_log::log;
+
+use crate::{
+    api::srs,
+    state::{
+        Input, InputEndpoint, InputEndpointKind, InputId, InputSrc, RestreamId,
+    },
+    State,
+};
+
+/// Path prefix of [WHIP] endpoints.
+///
+/// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+pub const ROUTE: &str = "/whip/";
+
+/// [MIME type][1] of [SDP] payloads.
+///
+/// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+/// [1]: https://en.wikipedia.org/wiki/Media_type
+const SDP_MIME: &str = "application/sdp";
+
+/// Endpoint starting publishing of a [WebRTC] live stream to the
+/// `InputEndpointKind::Whip` endpoint of the specified `Input`, by responding
+/// the answer [SDP] to the offer one of the client.
+///
+/// The endpoint's secret key (if any) should be passed as a bearer token in
+/// `Authorization` header, while the `Restream`'s publish token (if any)
+/// should be passed as `token` query parameter.
+///
+/// [SDP]: https://en.wikipedia.org/wiki/Session_Description_Protocol
+/// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+#[post("/whip/{restream}/{input}")]
+pub async fn publish(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    offer: String,
+) -> HttpResponse {
+    let (restream, input) = path.into_inner();
+
+    let is_sdp = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.starts_with(SDP_MIME));
+    if !is_sdp {
+        return HttpResponse::UnsupportedMediaType()
+            .body(format!("Request body should be `{SDP_MIME}`"));
+    }
+
+    let state = req.app_data::<State>().unwrap();
+    if lookup_endpoint(state, &restream, &input).is_none() {
+        return HttpResponse::NotFound().body("WHIP endpoint not found");
+    }
+
+    // Authorization is checked by SRS callback along with all the other
+    // publishing restrictions of the `Restream`.
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    let _ = query.append_pair("vhost", "whip");
+    if let Some(key) = bearer_token(&req) {
+        let _ = query.append_pair("key", &key);
+    }
+    if let Some(token) =
+        url::form_urlencoded::parse(req.query_string().as_bytes())
+            .find_map(|(k, v)| (k == "token").then(|| v.into_owned()))
+    {
+        let _ = query.append_pair("token", &token);
+    }
+    let stream_url =
+        format!("webrtc://127.0.0.1/{restream}/{input}?{}", query.finish(),);
+
+    let client_ip = match req.peer_addr() {
+        Some(addr) => addr.ip(),
+        None => return HttpResponse::BadRequest().finish(),
+    };
+    match srs::Client::rtc_publish(stream_url, client_ip, offer).await {
+        Ok(session) => {
+            log::info!(
+                "Started WHIP session {} of {}/{} from {}",
+                session.id,
+                restream,
+                input,
+                client_ip,
+            );
+            with_cors(HttpResponse::Created())
+                .content_type(SDP_MIME)
+                .insert_header((
+                    header::LOCATION,
+                    format!("{ROUTE}{restream}/{input}"),
+                ))
+                .body(session.answer)
+        }
+        Err(srs::Error::Rejected(code)) => with_cors(HttpResponse::Forbidden())
+            .body(format!("Publishing rejected with code {code}")),
+        Err(e) => {
+            log::error!("Failed to start WHIP session: {}", e);
+            with_cors(HttpResponse::BadGateway()).finish()
+        }
+    }
+}
+
+/// Endpoint stopping publishing of a [WebRTC] live stream to the
+/// `InputEndpointKind::Whip` endpoint of the specified `Input`, by kicking its
+/// publisher.
+///
+/// Requires the same bearer token as [`publish`] endpoint.
+///
+/// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+#[delete("/whip/{restream}/{input}")]
+pub async fn stop(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (restream, input) = path.into_inner();
+
+    let state = req.app_data::<State>().unwrap();
+    let (restream_id, input_id, endpoint) =
+        match lookup_endpoint(state, &restream, &input) {
+            Some(found) => found,
+            None => {
+                return HttpResponse::NotFound().body("WHIP endpoint not found")
+            }
+        };
+    let key = bearer_token(&req).map(|t| format!("key={t}"));
+    if !endpoint.is_publish_allowed(key.as_deref()) {
+        return with_cors(HttpResponse::Unauthorized()).finish();
+    }
+
+    let _ = state.kick_publisher(endpoint.id, input_id, restream_id);
+    with_cors(HttpResponse::Ok()).finish()
+}
+
+/// Endpoint responding to [CORS] preflight requests of [WHIP] endpoints, so
+/// browsers are allowed to publish from any origin.
+///
+/// [CORS]: https://en.wikipedia.org/wiki/Cross-origin_resource_sharing
+/// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+#[route("/whip/{restream}/{input}", method = "OPTIONS")]
+pub async fn preflight() -> HttpResponse {
+    with_cors(HttpResponse::NoContent())
+        .insert_header((
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            "POST, DELETE, OPTIONS",
+        ))
+        .insert_header((
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            "Authorization, Content-Type",
+        ))
+        .finish()
+}
+
+/// Looks up the `InputEndpointKind::Whip` endpoint of the `Input` with the
+/// given `input` key in the `Restream` with the given `restream` key.
+///
+/// Returns IDs of the found `Restream` and `Input`, along with the found
+/// [`InputEndpoint`] itself.
+fn lookup_endpoint(
+    state: &State,
+    restream: &str,
+    input: &str,
+) -> Option<(RestreamId, InputId, InputEndpoint)> {
+    /// Traverses the given [`Input`] and all its failover [`Input`]s looking
+    /// for the one with the specified `key`.
+    fn lookup_input<'i>(input: &'i Input, key: &str) -> Option<&'i Input> {
+        if input.key == *key {
+            return Some(input);
+        }
+        if let Some(InputSrc::Failover(s)) = input.src.as_ref() {
+            s.inputs.iter().find_map(|i| lookup_input(i, key))
+        } else {
+            None
+        }
+    }
+
+    let restreams = state.restreams.lock_ref();
+    let r = restreams.iter().find(|r| r.srs_app() == *restream)?;
+    let i = lookup_input(&r.input, input)?;
+    let e = i
+        .endpoints
+        .iter()
+        .find(|e| e.kind == InputEndpointKind::Whip)?;
+    Some((r.id, i.id, e.clone()))
+}
+
+/// Extracts a bearer token from `Authorization` header of the given
+/// [`HttpRequest`], if any.
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|t| t.trim().to_owned())
+}
+
+/// Adds [CORS] headers allowing requests from any origin to the given
+/// [`HttpResponse`] builder.
+///
+/// [CORS]: https://en.wikipedia.org/wiki/Cross-origin_resource_sharing
+fn with_cors(
+    mut resp: actix_web::HttpResponseBuilder,
+) -> actix_web::HttpResponseBuilder {
+    let _ = resp
+        .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"))
+        .insert_header((header::ACCESS_CONTROL_EXPOSE_HEADERS, "Location"));
+    resp
+}
@@ -0,0 +1,118 @@
+//! Priority-based shedding of `Output`s when the server is overloaded.
+
+use std::time::{Duration, Instant};
+
+use ephyr_log::log;
+use futures::future;
+
+use crate::{
+    notifier::{self, Event, Severity},
+    state::ServerInfo,
+    State,
+};
+
+/// Gap (in percents) below the CPU usage threshold, which the CPU usage should
+/// fall under to consider the server's load as normalized.
+///
+/// Prevents shed `Output`s from flapping when the CPU usage hovers around the
+/// threshold.
+const HYSTERESIS: f64 = 10.0;
+
+/// Tracker of the server's CPU load, which stops the lowest-priority `Output`s
+/// when the server is overloaded for too long, and resumes them once its load
+/// normalizes.
+///
+/// `Output`s are stopped and resumed one by one per each `period`, so the
+/// smallest possible number of them is affected.
+#[derive(Clone, Debug)]
+pub struct Shedder {
+    /// CPU usage (in percents) considered as overload.
+    threshold: f64,
+
+    /// Duration the overload (or the normal load) should last for, before
+    /// shedding (or resuming) an `Output`.
+    period: Duration,
+
+    /// Moment since when the server is overloaded.
+    overloaded_since: Option<Instant>,
+
+    /// Moment since when the server's load is normal.
+    normal_since: Option<Instant>,
+}
+
+impl Shedder {
+    /// Creates a new [`Shedder`] with the given CPU usage `threshold` and
+    /// `period`.
+    #[inline]
+    #[must_use]
+    pub fn new(threshold: f64, period: Duration) -> Self {
+        Self {
+            threshold,
+            period,
+            overloaded_since: None,
+            normal_since: None,
+        }
+    }
+
+    /// Runs this [`Shedder`] tracking [`ServerInfo`] changes of the given
+    /// [`State`].
+    pub fn run(mut self, state: State) {
+        let st = state.clone();
+        State::on_change("shed_outputs", &state.server_info, move |info| {
+            self.track(&info, &st);
+            future::ready(())
+        });
+    }
+
+    /// Tracks the given [`ServerInfo`] sample, shedding or resuming `Output`s
+    /// of the [`State`] if required.
+    fn track(&mut self, info: &ServerInfo, state: &State) {
+        let cpu = if let Some(cpu) = info.cpu_usage {
+            cpu
+        } else {
+            return;
+        };
+        let now = Instant::now();
+
+        if cpu > self.threshold {
+            self.normal_since = None;
+            let since = *self.overloaded_since.get_or_insert(now);
+            if now.duration_since(since) >= self.period {
+                if let Some(id) = state.shed_output() {
+                    let msg = format!(
+                        "Server is overloaded with {cpu:.0}% CPU usage, \
+                         shed Output {id}",
+                    );
+                    log::warn!("{msg}");
+                    notifier::notify(Event::new(
+                        Severity::Warning,
+                        "Output shed",
+                        msg,
+                    ));
+                }
+                self.overloaded_since = Some(now);
+            }
+        } else if cpu < self.threshold - HYSTERESIS {
+            self.overloaded_since = None;
+            let since = *self.normal_since.get_or_insert(now);
+            if now.duration_since(since) >= self.period {
+                if let Some(id) = state.resume_shed_output() {
+                    let msg = format!(
+                        "Server load normalized with {cpu:.0}% CPU usage, \
+                         resumed Output {id}",
+                    );
+                    log::info!("{msg}");
+                    notifier::notify(Event::new(
+                        Severity::Info,
+                        "Output resumed",
+                        msg,
+                    ));
+                }
+                self.normal_since = Some(now);
+            }
+        } else {
+            self.overloaded_since = None;
+            self.normal_since = None;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Graceful shutdown of the application.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ephyr_log::log;
+use tokio::time;
+
+use crate::{
+    ffmpeg::{RestreamerKind, RestreamersPool},
+    state::Storage,
+    State,
+};
+
+/// Maximum duration to wait for [FFmpeg] processes to exit during a graceful
+/// shutdown.
+///
+/// Should exceed the time a [`Restreamer`] waits for its process to exit on
+/// `SIGTERM` before killing it with `SIGKILL`.
+///
+/// [`Restreamer`]: crate::ffmpeg::Restreamer
+/// [FFmpeg]: https://ffmpeg.org
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Indicator whether a graceful shutdown has been started.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Indicates whether a graceful shutdown has been started, so no new changes
+/// should be accepted.
+#[inline]
+#[must_use]
+pub fn is_started() -> bool {
+    STARTED.load(Ordering::SeqCst)
+}
+
+/// Coordinator of a graceful shutdown, performing it once `SIGTERM` or
+/// `SIGINT` is received.
+#[derive(Debug)]
+pub struct Coordinator {
+    /// [`RestreamersPool`] to terminate [FFmpeg] processes of.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    restreamers: Arc<Mutex<RestreamersPool>>,
+
+    /// [`State`] to be flushed into the [`Coordinator::storage`].
+    state: State,
+
+    /// [`Storage`] to flush the [`State`] into.
+    storage: Storage,
+}
+
+impl Coordinator {
+    /// Creates a new [`Coordinator`] out of the given parameters.
+    #[inline]
+    #[must_use]
+    pub fn new(
+        restreamers: Arc<Mutex<RestreamersPool>>,
+        state: State,
+        storage: Storage,
+    ) -> Self {
+        Self {
+            restreamers,
+            state,
+            storage,
+        }
+    }
+
+    /// Awaits `SIGTERM` or `SIGINT`, and then performs a graceful shutdown:
+    /// 1. Stops accepting any changes (see [`is_started()`]).
+    /// 2. Terminates all [FFmpeg] processes, awaiting them to exit.
+    /// 3. Flushes the [`State`] into its [`Storage`].
+    ///
+    /// Resolves once the application may exit.
+    ///
+    /// # Panics
+    ///
+    /// If signal handlers cannot be installed, or the [`RestreamersPool`]
+    /// lock is poisoned.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub async fn run(self) {
+        let name = wait_signal().await;
+        log::info!("Received {}, shutting down gracefully", name);
+        STARTED.store(true, Ordering::SeqCst);
+
+        self.restreamers.lock().unwrap().terminate_all();
+        let started_at = Instant::now();
+        while !RestreamerKind::running_processes().is_empty() {
+            if started_at.elapsed() > DRAIN_TIMEOUT {
+                log::warn!("Some FFmpeg processes haven't exited in time");
+                break;
+            }
+            time::sleep(Duration::from_millis(100)).await;
+        }
+        if RestreamerKind::running_processes().is_empty() {
+            log::info!("All FFmpeg processes have been terminated");
+        }
+
+        match self.state.flush(&self.storage).await {
+            Ok(()) => log::info!("Server state has been flushed"),
+            Err(e) => log::error!("Failed to flush server state: {e}"),
+        }
+    }
+}
+
+/// Awaits `SIGTERM` or `SIGINT`, returning the name of the received one.
+///
+/// # Panics
+///
+/// If signal handlers cannot be installed.
+#[cfg(unix)]
+async fn wait_signal() -> &'static str {
+    use futures::{
+        future::{self, Either},
+        pin_mut,
+    };
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())
+        .expect("Failed to install SIGTERM handler");
+    let mut sigint = signal(SignalKind::interrupt())
+        .expect("Failed to install SIGINT handler");
+    let (term, int) = (sigterm.recv(), sigint.recv());
+    pin_mut!(term, int);
+    match future::select(term, int).await {
+        Either::Left(_) => "SIGTERM",
+        Either::Right(_) => "SIGINT",
+    }
+}
+
+/// Awaits `Ctrl+C`, as there are no Unix signals on other platforms.
+///
+/// # Panics
+///
+/// If the handler cannot be installed.
+#[cfg(not(unix))]
+async fn wait_signal() -> &'static str {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to install Ctrl+C handler");
+    "Ctrl+C"
+}
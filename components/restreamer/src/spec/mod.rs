@@ -5,8 +5,27 @@
 
 pub mod v1;
 
-use derive_more::From;
-use serde::{Deserialize, Serialize};
+use std::{env, fmt};
+
+use derive_more::{Display, Error, From};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+/// Prefix of the environment variables allowed to be expanded in a [`Spec`],
+/// so no other environment variables of the server may be exfiltrated via
+/// it.
+pub const ENV_PREFIX: &str = "EPHYR_SPEC_";
+
+/// Names of the [`Spec`] fields (containing URLs or labels) whose string
+/// values are allowed to have `${ENV_VAR}` placeholders expanded.
+const ENV_EXPANDABLE_FIELDS: &[&str] = &[
+    "dst",
+    "files",
+    "label",
+    "preview_url",
+    "remote_url",
+    "src",
+    "url",
+];
 
 /// All supported versions of shareable (exportable and importable)
 /// specifications of application's [`State`].
@@ -28,4 +47,423 @@ impl Spec {
             Self::V1(s) => s,
         }
     }
+
+    /// Parses a [`Spec`] out of the given JSON string, checking all its
+    /// invariants.
+    ///
+    /// If `expand_env` is `true`, then `${ENV_VAR}` placeholders in URLs and
+    /// labels of the spec are replaced with the values of the corresponding
+    /// environment variables (see [`expand_env()`]).
+    ///
+    /// # Errors
+    ///
+    /// If the given JSON is not a valid [`Spec`], or it refers to unset
+    /// environment variables. The returned [`SpecErrors`] contain all the
+    /// errors found, each pointing to the JSON path it has occurred at.
+    pub fn from_json(json: &str, expand_env: bool) -> Result<Self, SpecErrors> {
+        let mut de = serde_json::Deserializer::from_str(json);
+        if expand_env {
+            Self::deserialize_expanding_env(&mut de)
+        } else {
+            Self::deserialize_tracking_path(&mut de).map_err(|e| {
+                Self::collect_errors(serde_json::from_str(json).ok(), e)
+            })
+        }
+    }
+
+    /// Parses a [`Spec`] out of the given YAML string, checking all its
+    /// invariants.
+    ///
+    /// If `expand_env` is `true`, then `${ENV_VAR}` placeholders in URLs and
+    /// labels of the spec are replaced with the values of the corresponding
+    /// environment variables (see [`expand_env()`]).
+    ///
+    /// # Errors
+    ///
+    /// If the given YAML is not a valid [`Spec`], or it refers to unset
+    /// environment variables. The returned [`SpecErrors`] contain all the
+    /// errors found, each pointing to the path it has occurred at.
+    pub fn from_yaml(yaml: &str, expand_env: bool) -> Result<Self, SpecErrors> {
+        let de = serde_yaml::Deserializer::from_str(yaml);
+        if expand_env {
+            Self::deserialize_expanding_env(de)
+        } else {
+            Self::deserialize_tracking_path(de).map_err(|e| {
+                Self::collect_errors(serde_yaml::from_str(yaml).ok(), e)
+            })
+        }
+    }
+
+    /// Deserializes a [`Spec`] with the given [`Deserializer`], tracking the
+    /// path an error occurs at.
+    fn deserialize_tracking_path<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<Self, SpecError> {
+        serde_path_to_error::deserialize(de).map_err(|e| SpecError {
+            path: e.path().to_string(),
+            message: e.into_inner().to_string(),
+        })
+    }
+
+    /// Deserializes a [`Spec`] with the given [`Deserializer`], expanding
+    /// `${ENV_VAR}` placeholders in its URLs and labels beforehand.
+    fn deserialize_expanding_env<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<Self, SpecErrors> {
+        let mut val =
+            serde_json::Value::deserialize(de).map_err(|e| SpecError {
+                path: ".".into(),
+                message: e.to_string(),
+            })?;
+
+        let mut unresolved = Vec::new();
+        expand_env_in_value(&mut val, false, &mut unresolved);
+        if !unresolved.is_empty() {
+            return Err(SpecError {
+                path: ".".into(),
+                message: format!(
+                    "Unresolved environment variables: {}",
+                    unresolved.join(", "),
+                ),
+            }
+            .into());
+        }
+
+        Self::deserialize_tracking_path(&val)
+            .map_err(|e| Self::collect_errors(Some(val), e))
+    }
+
+    /// Collects all the errors of the given invalid [`Spec`] `val`, by
+    /// checking its [`v1::Settings`], [`v1::Restream`]s and their
+    /// [`v1::Output`]s separately, so an error in one of them doesn't hide
+    /// the errors in the others.
+    ///
+    /// Falls back to the given `first` error if no separate errors are found
+    /// (e.g. the spec is malformed as a whole, or the [`v1::Restream`]s
+    /// conflict with each other).
+    fn collect_errors(
+        val: Option<serde_json::Value>,
+        first: SpecError,
+    ) -> SpecErrors {
+        let mut errors = vec![];
+        if let Some(val) = &val {
+            if let Some(settings) = val.get("settings") {
+                errors.extend(check::<Option<v1::Settings>>(
+                    settings, "settings",
+                ));
+            }
+            let restreams = val
+                .get("restreams")
+                .and_then(serde_json::Value::as_array)
+                .into_iter()
+                .flatten();
+            for (i, restream) in restreams.enumerate() {
+                let path = format!("restreams[{i}]");
+                let output_errors: Vec<_> = restream
+                    .get("outputs")
+                    .and_then(serde_json::Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .enumerate()
+                    .filter_map(|(n, o)| {
+                        check::<v1::Output>(o, &format!("{path}.outputs[{n}]"))
+                    })
+                    .collect();
+                if output_errors.is_empty() {
+                    errors.extend(check::<v1::Restream>(restream, &path));
+                } else {
+                    // Check the rest of the `Restream` without its invalid
+                    // `Output`s, so they are not reported twice.
+                    let mut restream = restream.clone();
+                    restream["outputs"] = serde_json::Value::Array(vec![]);
+                    errors.extend(check::<v1::Restream>(&restream, &path));
+                    errors.extend(output_errors);
+                }
+            }
+        }
+        if errors.is_empty() {
+            errors.push(first);
+        }
+        SpecErrors(errors)
+    }
+}
+
+/// Checks the given [`serde_json::Value`] to be deserializable as `T`,
+/// returning the [`SpecError`] with its path prefixed with the given `prefix`
+/// one, if it's not.
+fn check<T: DeserializeOwned>(
+    val: &serde_json::Value,
+    prefix: &str,
+) -> Option<SpecError> {
+    serde_path_to_error::deserialize::<_, T>(val)
+        .err()
+        .map(|e| {
+            let path = e.path().to_string();
+            SpecError {
+                path: if path == "." {
+                    prefix.to_owned()
+                } else {
+                    format!("{prefix}.{path}")
+                },
+                message: e.into_inner().to_string(),
+            }
+        })
+}
+
+/// Replaces `${ENV_VAR}` placeholders in the given string with the values of
+/// the corresponding environment variables.
+///
+/// Only variables prefixed with [`ENV_PREFIX`] are expanded. Placeholders of
+/// any other variables, or the ones not consisting of ASCII letters, digits
+/// and underscores (like a sole `$` or an unclosed `${`), are left as is.
+///
+/// # Errors
+///
+/// With the names of all the unset variables, if any.
+pub fn expand_env(s: &str) -> Result<String, Vec<String>> {
+    let mut unresolved = Vec::new();
+    let expanded = expand_env_collecting(s, &mut unresolved);
+    if unresolved.is_empty() {
+        Ok(expanded)
+    } else {
+        Err(unresolved)
+    }
+}
+
+/// Replaces `${ENV_VAR}` placeholders in the given string, collecting the
+/// names of unset variables into the `unresolved` ones (without duplicates).
+fn expand_env_collecting(s: &str, unresolved: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let name = tail.find('}').map(|end| &tail[..end]).filter(|name| {
+            name.starts_with(ENV_PREFIX)
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if let Some(name) = name {
+            if let Ok(val) = env::var(name) {
+                out.push_str(&val);
+            } else if !unresolved.iter().any(|n| n == name) {
+                unresolved.push(name.to_owned());
+            }
+            rest = &tail[name.len() + 1..];
+        } else {
+            out.push_str("${");
+            rest = tail;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Replaces `${ENV_VAR}` placeholders in the string values of the
+/// [`ENV_EXPANDABLE_FIELDS`] of the given [`serde_json::Value`] recursively.
+///
+/// `expandable` indicates whether the given [`serde_json::Value`] is the one
+/// of an [`ENV_EXPANDABLE_FIELDS`] itself.
+fn expand_env_in_value(
+    val: &mut serde_json::Value,
+    expandable: bool,
+    unresolved: &mut Vec<String>,
+) {
+    use serde_json::Value as V;
+
+    match val {
+        V::String(s) if expandable => {
+            *s = expand_env_collecting(s, unresolved);
+        }
+        V::Array(vals) => {
+            for v in vals {
+                expand_env_in_value(v, expandable, unresolved);
+            }
+        }
+        V::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                let expandable = ENV_EXPANDABLE_FIELDS.contains(&k.as_str());
+                expand_env_in_value(v, expandable, unresolved);
+            }
+        }
+        V::String(_) | V::Null | V::Bool(_) | V::Number(_) => {}
+    }
+}
+
+/// Error of parsing a [`Spec`] out of JSON or YAML.
+#[derive(Clone, Debug, Display, Error)]
+#[display(fmt = "{}: {}", path, message)]
+pub struct SpecError {
+    /// Path in the parsed [`Spec`] the error has occurred at.
+    pub path: String,
+
+    /// Human-readable description of the error.
+    pub message: String,
+}
+
+/// All the errors of parsing a [`Spec`] out of JSON or YAML.
+#[derive(Clone, Debug, Error)]
+pub struct SpecErrors(#[error(not(source))] pub Vec<SpecError>);
+
+impl From<SpecError> for SpecErrors {
+    #[inline]
+    fn from(err: SpecError) -> Self {
+        Self(vec![err])
+    }
+}
+
+impl fmt::Display for SpecErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, err) in self.0.iter().enumerate() {
+            if n > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{err}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod spec_spec {
+    use std::env;
+
+    use super::{expand_env, v1::InputSrc, Spec};
+
+    #[test]
+    fn expands_env_vars() {
+        env::set_var("EPHYR_SPEC_TEST_KEY", "prod");
+
+        assert_eq!(
+            expand_env("rtmp://a/${EPHYR_SPEC_TEST_KEY}/b").unwrap(),
+            "rtmp://a/prod/b",
+        );
+        assert_eq!(expand_env("$ ${ 1A} ${x").unwrap(), "$ ${ 1A} ${x");
+        assert_eq!(expand_env("${HOME}/${PATH}").unwrap(), "${HOME}/${PATH}");
+        assert_eq!(
+            expand_env("${EPHYR_SPEC_TEST_NONE}${EPHYR_SPEC_TEST_NONE}")
+                .unwrap_err(),
+            vec!["EPHYR_SPEC_TEST_NONE".to_owned()],
+        );
+    }
+
+    #[test]
+    fn expands_env_vars_only_in_urls_and_labels() {
+        env::set_var("EPHYR_SPEC_TEST_HOST", "example.com");
+        env::set_var("EPHYR_SPEC_TEST_LABEL", "Main");
+
+        let json = r#"{"version":"v1","restreams":[{
+            "key":"origin",
+            "label":"${EPHYR_SPEC_TEST_LABEL}",
+            "input":{
+                "key":"origin",
+                "src":{"remote_url":"rtmp://${EPHYR_SPEC_TEST_HOST}/a/b"},
+                "enabled":true
+            }
+        }]}"#;
+        let spec = Spec::from_json(json, true).unwrap().into_v1();
+        let restream = &spec.restreams[0];
+        assert_eq!(restream.label.as_ref().unwrap().to_string(), "Main");
+        match &restream.input.src {
+            Some(InputSrc::Remote { url, .. }) => {
+                assert_eq!(url.as_str(), "rtmp://example.com/a/b");
+            }
+            src => panic!("Unexpected input source: {src:?}"),
+        }
+
+        let json = r#"{"version":"v1","restreams":[{
+            "key":"${EPHYR_SPEC_TEST_HOST}",
+            "input":{"key":"origin","enabled":true}
+        }]}"#;
+        assert!(Spec::from_json(json, true).is_err());
+    }
+
+    #[test]
+    fn lists_unresolved_env_vars_of_spec() {
+        let json = r#"{"version":"v1","restreams":[{
+            "input":{
+                "key":"origin",
+                "src":{"remote_url":"rtmp://${EPHYR_SPEC_TEST_A}/a/b"},
+                "enabled":true
+            },
+            "key":"origin",
+            "label":"${EPHYR_SPEC_TEST_B}"
+        }]}"#;
+
+        let err = Spec::from_json(json, true).unwrap_err();
+        assert_eq!(
+            err.0[0].message,
+            "Unresolved environment variables: \
+             EPHYR_SPEC_TEST_A, EPHYR_SPEC_TEST_B",
+        );
+        assert!(Spec::from_json(json, false).is_err());
+    }
+
+    #[test]
+    fn migrates_legacy_input_srcs() {
+        let json = r#"{"version":"v1","restreams":[{
+            "key":"origin",
+            "input":{
+                "key":"playback",
+                "endpoints":[{"kind":"rtmp"}],
+                "src":{"failover_inputs":[{
+                    "key":"primary",
+                    "endpoints":[{"kind":"rtmp"}],
+                    "src":{"remote_url":"rtmp://example.com/a/b"}
+                }]}
+            }
+        }]}"#;
+        let spec = Spec::from_json(json, false).unwrap();
+
+        let inputs = match &spec.clone().into_v1().restreams[0].input.src {
+            Some(InputSrc::Failover {
+                inputs,
+                hysteresis: None,
+            }) => inputs.clone(),
+            src => panic!("Unexpected input source: {src:?}"),
+        };
+        match &inputs[0].src {
+            Some(InputSrc::Remote {
+                url,
+                hls_variant: None,
+            }) => {
+                assert_eq!(url.as_str(), "rtmp://example.com/a/b");
+            }
+            src => panic!("Unexpected input source: {src:?}"),
+        }
+
+        let exported = serde_json::to_string(&spec).unwrap();
+        assert!(!exported.contains("failover_inputs"), "{exported}");
+        assert!(!exported.contains("remote_url"), "{exported}");
+    }
+
+    #[test]
+    fn collects_all_errors_of_spec() {
+        let json = r#"{"version":"v1","restreams":[{
+            "key":"bad key",
+            "input":{"key":"origin","enabled":true},
+            "outputs":[{"dst":"not a url"}]
+        }, {
+            "key":"valid",
+            "input":{"key":"origin","enabled":true},
+            "outputs":[
+                {"dst":"rtmp://example.com/live/stream"},
+                {"dst":"not a url either"}
+            ]
+        }]}"#;
+
+        for err in [
+            Spec::from_json(json, false).unwrap_err(),
+            Spec::from_json(json, true).unwrap_err(),
+        ] {
+            let paths: Vec<_> = err.0.iter().map(|e| e.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                [
+                    "restreams[0].key",
+                    "restreams[0].outputs[0].dst",
+                    "restreams[1].outputs[1].dst",
+                ],
+            );
+        }
+    }
 }
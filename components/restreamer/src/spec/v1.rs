@@ -3,9 +3,12 @@
 //!
 //! [`State`]: state::State
 
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::RangeInclusive};
 
-use crate::{serde::is_false, state};
+use crate::{
+    serde::{is_default, is_false},
+    state,
+};
 use juniper::GraphQLInputObject;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use url::Url;
@@ -31,6 +34,7 @@ impl Spec {
 
         if !restreams.is_empty() {
             let mut unique = HashSet::with_capacity(restreams.len());
+            let mut unique_labels = HashSet::with_capacity(restreams.len());
             for r in &restreams {
                 if let Some(key) = unique.replace(&r.key) {
                     return Err(D::Error::custom(format!(
@@ -38,6 +42,15 @@ impl Spec {
                         key,
                     )));
                 }
+                if let Some(label) = &r.label {
+                    if !unique_labels.insert(label.to_lowercase()) {
+                        return Err(D::Error::custom(format!(
+                            "Duplicate Restream.label in Spec.restreams \
+                             (ignoring case): {}",
+                            label,
+                        )));
+                    }
+                }
             }
         }
 
@@ -53,7 +66,8 @@ pub struct Settings {
     /// Title for the server
     /// It is used for differentiating servers on UI side if multiple servers
     /// are used.
-    pub title: Option<String>,
+    #[serde(default, deserialize_with = "state::Title::deserialize_opt")]
+    pub title: Option<state::Title>,
 
     /// Whether do we need to confirm deletion of inputs and outputs
     /// If `true` we should confirm deletion, `false` - do not confirm
@@ -62,6 +76,89 @@ pub struct Settings {
     /// Whether do we need to confirm enabling/disabling of inputs \
     /// or outputs
     pub enable_confirmation: Option<bool>,
+
+    /// Duration (in minutes) an enabled `Input` may stay offline, before it's
+    /// disabled automatically.
+    ///
+    /// If not specified, then `Input`s are never disabled automatically,
+    /// unless [`Restream::idle_timeout`] says otherwise.
+    #[serde(
+        default,
+        deserialize_with = "Settings::deserialize_idle_timeout",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_timeout: Option<i32>,
+
+    /// [`state::RestreamTemplate`]s stored on the server.
+    #[serde(
+        default,
+        deserialize_with = "Settings::deserialize_templates",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub templates: Vec<state::RestreamTemplate>,
+
+    /// [`state::MixinLimits`] of [`Output`]s on the server.
+    #[serde(
+        default,
+        deserialize_with = "Settings::deserialize_mixin_limits",
+        skip_serializing_if = "is_default"
+    )]
+    pub mixin_limits: state::MixinLimits,
+}
+
+impl Settings {
+    /// Deserializes [`Settings::templates`] ensuring their names are unique.
+    fn deserialize_templates<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<state::RestreamTemplate>, D::Error> {
+        let templates =
+            <Vec<state::RestreamTemplate>>::deserialize(deserializer)?;
+
+        let mut unique = HashSet::with_capacity(templates.len());
+        for t in &templates {
+            if let Some(name) = unique.replace(&t.name) {
+                return Err(D::Error::custom(format!(
+                    "Duplicate RestreamTemplate.name in Settings.templates: {}",
+                    name,
+                )));
+            }
+        }
+
+        Ok(templates)
+    }
+
+    /// Deserializes [`Settings::idle_timeout`] ensuring it's within the
+    /// allowed range.
+    fn deserialize_idle_timeout<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<i32>, D::Error> {
+        let timeout = <Option<i32>>::deserialize(deserializer)?;
+        if let Some(t) = timeout {
+            if t == 0 || !state::Restream::is_valid_idle_timeout(t) {
+                return Err(D::Error::custom(format!(
+                    "Settings.idle_timeout should be in 1..={} range",
+                    state::Restream::MAX_IDLE_TIMEOUT,
+                )));
+            }
+        }
+        Ok(timeout)
+    }
+
+    /// Deserializes [`Settings::mixin_limits`] ensuring they're valid.
+    fn deserialize_mixin_limits<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<state::MixinLimits, D::Error> {
+        let limits = state::MixinLimits::deserialize(deserializer)?;
+        if !limits.is_valid() {
+            return Err(D::Error::custom(format!(
+                "Settings.mixin_limits should not exceed {} mixins and {} \
+                 TeamSpeak ones, with the latter not exceeding the former",
+                state::MixinLimits::CEILING.max_mixins,
+                state::MixinLimits::CEILING.max_teamspeak_mixins,
+            )));
+        }
+        Ok(limits)
+    }
 }
 
 /// Shareable (exportable and importable) specification of a
@@ -75,6 +172,13 @@ pub struct Restream {
     /// endpoints URLs.
     pub key: state::RestreamKey,
 
+    /// Tenant this [`Restream`] belongs to, isolating its endpoints under a
+    /// separate [SRS] app.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<state::TenantKey>,
+
     /// Optional label of this [`Restream`].
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<state::Label>,
@@ -89,9 +193,80 @@ pub struct Restream {
         skip_serializing_if = "Vec::is_empty"
     )]
     pub outputs: Vec<Output>,
+
+    /// [`RestreamWebhook`] to notify about [`Restream`]'s events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<RestreamWebhook>,
+
+    /// [`RestreamFallback`] to broadcast while [`Restream::input`] is offline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<RestreamFallback>,
+
+    /// [`RestreamIpFilter`] of publishers allowed to [`Restream::input`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_filter: Option<RestreamIpFilter>,
+
+    /// [`RestreamSrsTuning`] of the SRS server for this [`Restream`].
+    #[serde(
+        default,
+        deserialize_with = "Restream::deserialize_srs_tuning",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub srs_tuning: Option<RestreamSrsTuning>,
+
+    /// [`state::PublishToken`] required from external publishers of
+    /// [`Restream::input`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_token: Option<state::PublishToken>,
+
+    /// Duration (in minutes) [`Restream::input`] may stay offline while being
+    /// enabled, before it's disabled automatically.
+    #[serde(
+        default,
+        deserialize_with = "Restream::deserialize_idle_timeout",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_timeout: Option<i32>,
 }
 
 impl Restream {
+    /// Deserializes [`Restream::idle_timeout`] ensuring it's within the
+    /// allowed range.
+    fn deserialize_idle_timeout<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<i32>, D::Error> {
+        let timeout = <Option<i32>>::deserialize(deserializer)?;
+        if let Some(t) = timeout {
+            if !state::Restream::is_valid_idle_timeout(t) {
+                return Err(D::Error::custom(format!(
+                    "Restream.idle_timeout should be in 0..={} range",
+                    state::Restream::MAX_IDLE_TIMEOUT,
+                )));
+            }
+        }
+        Ok(timeout)
+    }
+
+    /// Deserializes [`Restream::srs_tuning`] ensuring its
+    /// [`RestreamSrsTuning::hls_fragment`] is within the allowed range.
+    fn deserialize_srs_tuning<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<RestreamSrsTuning>, D::Error> {
+        let tuning = <Option<RestreamSrsTuning>>::deserialize(deserializer)?;
+        if let Some(f) = tuning.as_ref().and_then(|t| t.hls_fragment) {
+            let range = state::RestreamSrsTuning::HLS_FRAGMENT_RANGE;
+            if !range.contains(&f) {
+                return Err(D::Error::custom(format!(
+                    "Restream.srs_tuning.hls_fragment should be in {}..={} \
+                     range",
+                    range.start(),
+                    range.end(),
+                )));
+            }
+        }
+        Ok(tuning)
+    }
+
     /// Deserializes [`Restream::outputs`] ensuring its invariants preserved.
     fn deserialize_outputs<'de, D: Deserializer<'de>>(
         deserializer: D,
@@ -114,6 +289,117 @@ impl Restream {
     }
 }
 
+/// Partial specification of a [`Restream`], stored as a
+/// [`state::RestreamTemplate`] to be merged into existing [`Restream`]s.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamTemplate {
+    /// [`Output`]s to be merged into [`Restream::outputs`] by their `dst`.
+    #[serde(
+        default,
+        deserialize_with = "Restream::deserialize_outputs",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub outputs: Vec<Output>,
+
+    /// [`RestreamFallback`] to be set as [`Restream::fallback`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<RestreamFallback>,
+
+    /// [`RestreamIpFilter`] to be set as [`Restream::ip_filter`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_filter: Option<RestreamIpFilter>,
+}
+
+impl RestreamTemplate {
+    /// Indicates whether this [`RestreamTemplate`] defines nothing to be
+    /// merged.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+            && self.fallback.is_none()
+            && self.ip_filter.is_none()
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::RestreamFallback`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamFallback {
+    /// Kind of the fallback source.
+    pub kind: state::FallbackKind,
+
+    /// Path to a local file to be broadcast, relative to the server's media
+    /// directory.
+    #[serde(
+        default,
+        deserialize_with = "RestreamFallback::deserialize_file",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub file: Option<String>,
+}
+
+impl RestreamFallback {
+    /// Deserializes [`RestreamFallback::file`] ensuring it cannot escape the
+    /// server's media directory.
+    fn deserialize_file<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        let file = <Option<String>>::deserialize(deserializer)?;
+        if let Some(f) = &file {
+            if !state::RestreamFallback::is_valid_file(f) {
+                return Err(D::Error::custom(
+                    "RestreamFallback.file should be a relative path inside \
+                     the media directory",
+                ));
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::RestreamIpFilter`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamIpFilter {
+    /// Networks allowed to publish a live stream.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<state::IpCidr>,
+
+    /// Networks denied to connect at all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<state::IpCidr>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::RestreamSrsTuning`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamSrsTuning {
+    /// Indicator whether live streams should be recorded by SRS.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dvr: bool,
+
+    /// Duration (in seconds) of HLS fragments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_fragment: Option<i32>,
+
+    /// Indicator whether the latest GOP is cached for HLS endpoints.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop_cache: Option<bool>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::RestreamWebhook`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamWebhook {
+    /// URL to send `POST` requests with events to.
+    pub url: Url,
+
+    /// Secret to sign requests with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
 /// Shareable (exportable and importable) specification of a [`state::Input`].
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct Input {
@@ -140,6 +426,21 @@ pub struct Input {
     /// live stream from its upstream sources.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
+
+    /// [`InputTuning`] options of pulling a live stream from a remote `src`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<InputTuning>,
+
+    /// Verbosity level of [FFmpeg] processes serving this [`Input`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<state::FfmpegLogLevel>,
+
+    /// Priority of this [`Input`] among other [`Input`]s of its failover
+    /// [`InputSrc`].
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: i32,
 }
 
 impl Input {
@@ -154,11 +455,17 @@ impl Input {
         let mut endpoints = vec![InputEndpoint {
             kind: state::InputEndpointKind::Rtmp,
             label: None,
+            secret_key: None,
+            ll_hls: None,
+            hls_renditions: vec![],
         }];
         if with_hls {
             endpoints.push(InputEndpoint {
                 kind: state::InputEndpointKind::Hls,
                 label: None,
+                secret_key: None,
+                ll_hls: None,
+                hls_renditions: vec![],
             });
         }
 
@@ -168,6 +475,9 @@ impl Input {
             endpoints,
             src: input_src,
             enabled: true,
+            tuning: None,
+            log_level: None,
+            priority: 0,
         }
     }
 }
@@ -184,10 +494,20 @@ impl<'de> Deserialize<'de> for Input {
             src: Option<InputSrc>,
             #[serde(default)]
             enabled: bool,
+            #[serde(default)]
+            tuning: Option<InputTuning>,
+            #[serde(default)]
+            log_level: Option<state::FfmpegLogLevel>,
+            #[serde(default)]
+            priority: i32,
         }
 
         let raw = RawInput::deserialize(deserializer)?;
 
+        if let Some(tuning) = &raw.tuning {
+            tuning.validate().map_err(D::Error::custom)?;
+        }
+
         let mut unique_endpoints = HashSet::with_capacity(raw.endpoints.len());
         for e in &raw.endpoints {
             if let Some(kind) = unique_endpoints.replace(e.kind) {
@@ -197,6 +517,48 @@ impl<'de> Deserialize<'de> for Input {
                 )));
             }
         }
+        for e in &raw.endpoints {
+            if e.kind == state::InputEndpointKind::Srt
+                && e.secret_key.as_ref().map_or(false, |k| k.len() < 10)
+            {
+                return Err(D::Error::custom(format!(
+                    "InputEndpoint.secret_key of {} endpoint should be at \
+                     least 10 characters long",
+                    state::InputEndpointKind::Srt,
+                )));
+            }
+            if let Some(ll_hls) = &e.ll_hls {
+                if e.kind != state::InputEndpointKind::Hls {
+                    return Err(D::Error::custom(format!(
+                        "InputEndpoint.ll_hls is allowed for {} endpoint only",
+                        state::InputEndpointKind::Hls,
+                    )));
+                }
+                if !ll_hls.is_valid() {
+                    return Err(D::Error::custom(format!(
+                        "InputEndpoint.ll_hls.target_latency should be in \
+                         {}..={} range",
+                        LlHls::MIN_TARGET_LATENCY,
+                        LlHls::MAX_TARGET_LATENCY,
+                    )));
+                }
+            }
+            if !e.hls_renditions.is_empty() {
+                if e.kind != state::InputEndpointKind::Hls {
+                    return Err(D::Error::custom(format!(
+                        "InputEndpoint.hls_renditions is allowed for {} \
+                         endpoint only",
+                        state::InputEndpointKind::Hls,
+                    )));
+                }
+                if let Err(e) = HlsRendition::validate_ladder(&e.hls_renditions)
+                {
+                    return Err(D::Error::custom(format!(
+                        "InputEndpoint.hls_renditions {e}",
+                    )));
+                }
+            }
+        }
         if !unique_endpoints.contains(&state::InputEndpointKind::Rtmp) {
             return Err(D::Error::custom(format!(
                 "Input.endpoints should contain at least one {} endpoint",
@@ -204,14 +566,47 @@ impl<'de> Deserialize<'de> for Input {
             )));
         }
 
+        if raw.src.is_some()
+            && unique_endpoints.contains(&state::InputEndpointKind::Whip)
+        {
+            return Err(D::Error::custom(format!(
+                "Input.endpoints should not contain {} endpoint along with \
+                 Input.src",
+                state::InputEndpointKind::Whip,
+            )));
+        }
+        if raw.src.is_some()
+            && unique_endpoints.contains(&state::InputEndpointKind::Srt)
+        {
+            return Err(D::Error::custom(format!(
+                "Input.endpoints should not contain {} endpoint along with \
+                 Input.src",
+                state::InputEndpointKind::Srt,
+            )));
+        }
+
         if let Some(src) = &raw.src {
             fn ensure_srcs_unique<'i>(
                 src: &'i InputSrc,
                 unique_urls: &mut HashSet<&'i state::InputSrcUrl>,
                 unique_keys: &mut HashSet<&'i state::InputKey>,
             ) -> Result<(), String> {
+                if let InputSrc::Failover {
+                    hysteresis: Some(h),
+                    ..
+                } = src
+                {
+                    if !h.is_valid() {
+                        return Err(format!(
+                            "Input.src.failover.hysteresis.stable_secs \
+                             should be in {}..={} range",
+                            state::FailoverHysteresis::MIN_STABLE_SECS,
+                            state::FailoverHysteresis::MAX_STABLE_SECS,
+                        ));
+                    }
+                }
                 match src {
-                    InputSrc::RemoteUrl(url) => {
+                    InputSrc::Remote { url, .. } => {
                         if let Some(url) = unique_urls.replace(url) {
                             return Err(format!(
                                 "Duplicate RemoteInputSrc.url in Input.src: {}",
@@ -219,7 +614,14 @@ impl<'de> Deserialize<'de> for Input {
                             ));
                         }
                     }
-                    InputSrc::FailoverInputs(inputs) => {
+                    InputSrc::Playlist { files, .. } => {
+                        if files.is_empty() {
+                            return Err("Input.src.playlist.files should not \
+                                        be empty"
+                                .into());
+                        }
+                    }
+                    InputSrc::Failover { inputs, .. } => {
                         for i in inputs {
                             if let Some(key) = unique_keys.replace(&i.key) {
                                 return Err(format!(
@@ -253,10 +655,72 @@ impl<'de> Deserialize<'de> for Input {
             endpoints: raw.endpoints,
             src: raw.src,
             enabled: raw.enabled,
+            tuning: raw.tuning,
+            log_level: raw.log_level,
+            priority: raw.priority,
         })
     }
 }
 
+/// Shareable (exportable and importable) specification of a
+/// [`state::InputTuning`].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "InputTuningInput")]
+pub struct InputTuning {
+    /// Size of the data (in bytes) to analyze for getting the live stream
+    /// information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_size: Option<i32>,
+
+    /// Duration (in milliseconds) of the data to analyze for getting the live
+    /// stream information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyze_duration: Option<i32>,
+
+    /// Size of the buffer (in bytes) for buffering the pulled live stream in
+    /// real-time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_size: Option<i32>,
+
+    /// Timeout (in milliseconds) of network read/write operations, after which
+    /// the pulling is considered failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rw_timeout: Option<i32>,
+}
+
+impl InputTuning {
+    /// Validates all the specified values of this [`InputTuning`] to be
+    /// positive, as [FFmpeg] either rejects zeros or treats them specially.
+    ///
+    /// # Errors
+    ///
+    /// With a description of the first invalid value.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, val) in [
+            ("probe_size", self.probe_size),
+            ("analyze_duration", self.analyze_duration),
+            ("buffer_size", self.buffer_size),
+            ("rw_timeout", self.rw_timeout),
+        ] {
+            if val.map_or(false, |v| v <= 0) {
+                return Err(format!("InputTuning.{name} should be positive"));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Shareable (exportable and importable) specification of a
 /// [`state::InputEndpoint`].
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -267,18 +731,265 @@ pub struct InputEndpoint {
     /// Label for this input
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<state::Label>,
+
+    /// Secret key required to publish a live stream to this
+    /// [`InputEndpoint`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<state::EndpointKey>,
+
+    /// [`LlHls`] options to additionally serve a live stream with.
+    ///
+    /// Allowed for [`state::InputEndpointKind::Hls`] only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ll_hls: Option<LlHls>,
+
+    /// [`HlsRendition`]s to additionally serve a live stream with, forming a
+    /// ladder listed in a master playlist.
+    ///
+    /// Allowed for [`state::InputEndpointKind::Hls`] only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hls_renditions: Vec<HlsRendition>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::LlHls`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LlHls {
+    /// Target latency (in milliseconds) of a live stream on the client side.
+    #[serde(default = "LlHls::default_target_latency")]
+    pub target_latency: i32,
+}
+
+impl LlHls {
+    /// Default [`LlHls::target_latency`] (in milliseconds).
+    pub const DEFAULT_TARGET_LATENCY: i32 = 3000;
+
+    /// Minimum allowed [`LlHls::target_latency`] (in milliseconds).
+    pub const MIN_TARGET_LATENCY: i32 = 1000;
+
+    /// Maximum allowed [`LlHls::target_latency`] (in milliseconds).
+    pub const MAX_TARGET_LATENCY: i32 = 30000;
+
+    /// Returns [`LlHls::DEFAULT_TARGET_LATENCY`].
+    #[inline]
+    #[must_use]
+    fn default_target_latency() -> i32 {
+        Self::DEFAULT_TARGET_LATENCY
+    }
+
+    /// Indicates whether [`LlHls::target_latency`] is within the allowed
+    /// range.
+    #[inline]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN_TARGET_LATENCY..=Self::MAX_TARGET_LATENCY)
+            .contains(&self.target_latency)
+    }
+}
+
+impl Default for LlHls {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            target_latency: Self::DEFAULT_TARGET_LATENCY,
+        }
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::HlsRendition`].
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "HlsRenditionInput")]
+pub struct HlsRendition {
+    /// Height (in pixels) of the video of this rendition.
+    pub height: i32,
+
+    /// Bitrate (in kbps) of the video of this rendition.
+    pub video_bitrate: i32,
+
+    /// Bitrate (in kbps) of the audio of this rendition.
+    #[serde(default = "HlsRendition::default_audio_bitrate")]
+    #[graphql(default = HlsRendition::DEFAULT_AUDIO_BITRATE)]
+    pub audio_bitrate: i32,
+}
+
+impl HlsRendition {
+    /// Default [`HlsRendition::audio_bitrate`] (in kbps).
+    pub const DEFAULT_AUDIO_BITRATE: i32 = 128;
+
+    /// Minimum allowed [`HlsRendition::height`] (in pixels).
+    pub const MIN_HEIGHT: i32 = 144;
+
+    /// Maximum allowed [`HlsRendition::height`] (in pixels).
+    pub const MAX_HEIGHT: i32 = 2160;
+
+    /// Minimum allowed [`HlsRendition::video_bitrate`] and
+    /// [`HlsRendition::audio_bitrate`] (in kbps).
+    pub const MIN_BITRATE: i32 = 32;
+
+    /// Maximum allowed [`HlsRendition::video_bitrate`] and
+    /// [`HlsRendition::audio_bitrate`] (in kbps).
+    pub const MAX_BITRATE: i32 = 50000;
+
+    /// Maximum allowed number of [`HlsRendition`]s in a ladder.
+    pub const MAX_LADDER_LEN: usize = 5;
+
+    /// Returns [`HlsRendition::DEFAULT_AUDIO_BITRATE`].
+    #[inline]
+    #[must_use]
+    fn default_audio_bitrate() -> i32 {
+        Self::DEFAULT_AUDIO_BITRATE
+    }
+
+    /// Indicates whether [`HlsRendition::height`] is even and it and the
+    /// bitrates are within the allowed ranges.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        let bitrates = Self::MIN_BITRATE..=Self::MAX_BITRATE;
+        (Self::MIN_HEIGHT..=Self::MAX_HEIGHT).contains(&self.height)
+            && self.height % 2 == 0
+            && bitrates.contains(&self.video_bitrate)
+            && bitrates.contains(&self.audio_bitrate)
+    }
+
+    /// Validates the given ladder of [`HlsRendition`]s to be served by a
+    /// single [`state::InputEndpointKind::Hls`] endpoint.
+    ///
+    /// # Errors
+    ///
+    /// With a description of the violated invariant, if any:
+    /// - the ladder is longer than [`HlsRendition::MAX_LADDER_LEN`];
+    /// - any [`HlsRendition`] is not [`HlsRendition::is_valid()`];
+    /// - [`HlsRendition::height`]s are not unique.
+    pub fn validate_ladder(ladder: &[Self]) -> Result<(), String> {
+        if ladder.len() > Self::MAX_LADDER_LEN {
+            return Err(format!(
+                "should contain at most {} renditions",
+                Self::MAX_LADDER_LEN,
+            ));
+        }
+        if !ladder.iter().all(Self::is_valid) {
+            return Err(format!(
+                "should have even height in {}..={} range and bitrates in \
+                 {}..={} range",
+                Self::MIN_HEIGHT,
+                Self::MAX_HEIGHT,
+                Self::MIN_BITRATE,
+                Self::MAX_BITRATE,
+            ));
+        }
+        let mut heights = HashSet::with_capacity(ladder.len());
+        if let Some(r) = ladder.iter().find(|r| !heights.insert(r.height)) {
+            return Err(format!("has duplicate height: {}", r.height));
+        }
+        Ok(())
+    }
 }
 
 /// Shareable (exportable and importable) specification of a
 /// [`state::InputSrc`].
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+///
+/// Legacy `remote_url` and `failover_inputs` shapes are still accepted, being
+/// migrated into [`InputSrc::Remote`] and [`InputSrc::Failover`] ones on
+/// deserialization.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum InputSrc {
-    /// Remote endpoint represented by its URL.
-    RemoteUrl(state::InputSrcUrl),
+    /// Remote endpoint represented by its URL along with the options of
+    /// pulling it.
+    Remote {
+        /// URL of the remote endpoint.
+        url: state::InputSrcUrl,
+
+        /// [`state::HlsVariantSelector`] to choose a variant stream of a
+        /// [HLS] master playlist with.
+        ///
+        /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hls_variant: Option<state::HlsVariantSelector>,
+    },
+
+    /// Multiple [`Input`]s forming a failover source along with the options
+    /// of switching between them.
+    Failover {
+        /// [`Input`]s forming the failover source, in the order of their
+        /// priority.
+        inputs: Vec<Input>,
+
+        /// [`state::FailoverHysteresis`] of switching back to the restored
+        /// [`Input`]s.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hysteresis: Option<state::FailoverHysteresis>,
+    },
 
-    /// Multiple [`Input`]s forming a failover source.
-    FailoverInputs(Vec<Input>),
+    /// Playlist of local files to be broadcast in order.
+    Playlist {
+        /// `file:///` URLs of the files inside the DVR directory to be
+        /// broadcast.
+        files: Vec<state::PlaylistFileUrl>,
+
+        /// Indicator whether the playlist should be looped endlessly.
+        #[serde(rename = "loop", default, skip_serializing_if = "is_false")]
+        looped: bool,
+    },
+}
+
+impl<'de> Deserialize<'de> for InputSrc {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        enum RawInputSrc {
+            RemoteUrl(state::InputSrcUrl),
+            Remote {
+                url: state::InputSrcUrl,
+                #[serde(default)]
+                hls_variant: Option<state::HlsVariantSelector>,
+            },
+            FailoverInputs(Vec<Input>),
+            Failover {
+                inputs: Vec<Input>,
+                #[serde(default)]
+                hysteresis: Option<state::FailoverHysteresis>,
+            },
+            Playlist {
+                files: Vec<state::PlaylistFileUrl>,
+                #[serde(rename = "loop", default)]
+                looped: bool,
+            },
+        }
+
+        Ok(match RawInputSrc::deserialize(deserializer)? {
+            RawInputSrc::RemoteUrl(url) => Self::Remote {
+                url,
+                hls_variant: None,
+            },
+            RawInputSrc::Remote { url, hls_variant } => {
+                Self::Remote { url, hls_variant }
+            }
+            RawInputSrc::FailoverInputs(inputs) => Self::Failover {
+                inputs,
+                hysteresis: None,
+            },
+            RawInputSrc::Failover { inputs, hysteresis } => {
+                Self::Failover { inputs, hysteresis }
+            }
+            RawInputSrc::Playlist { files, looped } => {
+                Self::Playlist { files, looped }
+            }
+        })
+    }
 }
 
 /// Shareable (exportable and importable) specification of a [`state::Output`].
@@ -316,13 +1027,107 @@ pub struct Output {
     )]
     pub mixins: Vec<Mixin>,
 
+    /// Offset (in milliseconds) of this [`Output`]'s audio relatively to its
+    /// video.
+    #[serde(
+        default,
+        deserialize_with = "Output::deserialize_av_offset",
+        skip_serializing_if = "is_default"
+    )]
+    pub av_offset: i32,
+
     /// Indicator whether this [`Output`]  is enabled, so is allowed to perform
     /// a live stream re-streaming to its downstream destination.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
+
+    /// Priority of this [`Output`] to be kept running when the server is
+    /// overloaded.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: i32,
+
+    /// Verbosity level of [FFmpeg] process serving this [`Output`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<state::FfmpegLogLevel>,
+
+    /// [`OutputTls`] options to publish onto an encrypted downstream
+    /// destination with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutputTls>,
+
+    /// [`OutputReconnect`] options to survive short network failures of the
+    /// downstream destination with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<OutputReconnect>,
+
+    /// [`OutputIcecast`] metadata to describe the stream with on an
+    /// `icecast://` destination.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icecast: Option<OutputIcecast>,
+
+    /// [`OutputLoudnorm`] options to normalize loudness of the audio with.
+    #[serde(
+        default,
+        deserialize_with = "Output::deserialize_loudnorm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub loudnorm: Option<OutputLoudnorm>,
+
+    /// Extra arguments to pass to [FFmpeg] process serving this [`Output`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(
+        default,
+        deserialize_with = "Output::deserialize_extra_ffmpeg_args",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub extra_ffmpeg_args: Vec<String>,
 }
 
 impl Output {
+    /// Deserializes [`Output::loudnorm`] ensuring its values are within the
+    /// allowed ranges.
+    fn deserialize_loudnorm<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OutputLoudnorm>, D::Error> {
+        let loudnorm = Option::<OutputLoudnorm>::deserialize(deserializer)?;
+        if loudnorm.map_or(false, |l| !l.is_valid()) {
+            return Err(D::Error::custom(format!(
+                "Output.loudnorm.target_lufs should be in {}..={} range",
+                OutputLoudnorm::MIN_TARGET_LUFS,
+                OutputLoudnorm::MAX_TARGET_LUFS,
+            )));
+        }
+        Ok(loudnorm)
+    }
+
+    /// Deserializes [`Output::av_offset`] ensuring it's within the allowed
+    /// range.
+    fn deserialize_av_offset<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<i32, D::Error> {
+        let offset = i32::deserialize(deserializer)?;
+        if !state::Output::is_valid_av_offset(offset) {
+            return Err(D::Error::custom(format!(
+                "Output.av_offset should be in -{max}..={max} range",
+                max = state::Output::MAX_AV_OFFSET,
+            )));
+        }
+        Ok(offset)
+    }
+
+    /// Deserializes [`Output::extra_ffmpeg_args`] ensuring they're valid.
+    fn deserialize_extra_ffmpeg_args<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<String>, D::Error> {
+        let args = <Vec<String>>::deserialize(deserializer)?;
+        state::Output::validate_extra_ffmpeg_args(&args)
+            .map_err(D::Error::custom)?;
+        Ok(args)
+    }
+
     /// Deserializes [`Output::mixins`] ensuring its invariants preserved.
     fn deserialize_mixins<'de, D: Deserializer<'de>>(
         deserializer: D,
@@ -330,8 +1135,15 @@ impl Output {
         let mixins = <Vec<Mixin>>::deserialize(deserializer)?;
 
         if !mixins.is_empty() {
+            // Actual `state::Settings::mixin_limits` are checked on import,
+            // so only the absolute ceiling is checked here.
+            state::MixinLimits::CEILING
+                .check(mixins.iter().map(|m| &m.src))
+                .map_err(|e| {
+                    D::Error::custom(format!("Invalid Output.mixins: {e}"))
+                })?;
+
             let mut unique = HashSet::with_capacity(mixins.len());
-            let mut ts_count: u8 = 0;
             let mut has_sidechain = false;
             for m in &mixins {
                 if let Some(src) = unique.replace(&m.src) {
@@ -340,15 +1152,11 @@ impl Output {
                         src,
                     )));
                 }
-                if m.src.scheme() == "ts" {
-                    ts_count += 1;
-                    if ts_count > 3 {
-                        return Err(D::Error::custom(format!(
-                            "Maximum 3 TeamSpeak Mixin.src allowed \
-                            in Output.mixins: {}",
-                            m.src,
-                        )));
-                    }
+                if !m.sidechain_params.is_valid() {
+                    return Err(D::Error::custom(format!(
+                        "Invalid Mixin.sidechain_params in Output.mixins: {}",
+                        m.src,
+                    )));
                 }
                 if m.sidechain {
                     if has_sidechain {
@@ -386,6 +1194,333 @@ pub struct Mixin {
     /// [`Output`].
     #[serde(default, skip_serializing_if = "is_false")]
     pub sidechain: bool,
+
+    /// Parameters of side-chaining this [`Mixin`] with an [`Output`].
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sidechain_params: MixinSidechainParams,
+}
+
+/// Shareable (exportable and importable) specification of
+/// [`state::MixinSidechainParams`].
+#[derive(
+    Clone, Copy, Debug, Deserialize, PartialEq, Serialize, GraphQLInputObject,
+)]
+#[graphql(name = "MixinSidechainParamsInput")]
+pub struct MixinSidechainParams {
+    /// Level of a [`Mixin`]'s audio above which an [`Output`]'s audio starts
+    /// being compressed.
+    #[serde(default = "MixinSidechainParams::default_threshold")]
+    #[graphql(default = MixinSidechainParams::DEFAULT_THRESHOLD)]
+    pub threshold: f64,
+
+    /// Ratio by which an [`Output`]'s audio is compressed.
+    #[serde(default = "MixinSidechainParams::default_ratio")]
+    #[graphql(default = MixinSidechainParams::DEFAULT_RATIO)]
+    pub ratio: f64,
+
+    /// Time (in milliseconds) before the compression starts.
+    #[serde(default = "MixinSidechainParams::default_attack")]
+    #[graphql(default = MixinSidechainParams::DEFAULT_ATTACK)]
+    pub attack: f64,
+
+    /// Time (in milliseconds) before the compression stops.
+    #[serde(default = "MixinSidechainParams::default_release")]
+    #[graphql(default = MixinSidechainParams::DEFAULT_RELEASE)]
+    pub release: f64,
+
+    /// Amplification of an [`Output`]'s audio after compression.
+    #[serde(default = "MixinSidechainParams::default_makeup")]
+    #[graphql(default = MixinSidechainParams::DEFAULT_MAKEUP)]
+    pub makeup: f64,
+}
+
+// Parameters are validated to be finite numbers, so never are `NaN`.
+impl Eq for MixinSidechainParams {}
+
+impl MixinSidechainParams {
+    /// Default [`MixinSidechainParams::threshold`].
+    pub const DEFAULT_THRESHOLD: f64 = 0.05;
+
+    /// Default [`MixinSidechainParams::ratio`].
+    pub const DEFAULT_RATIO: f64 = 10.0;
+
+    /// Default [`MixinSidechainParams::attack`].
+    pub const DEFAULT_ATTACK: f64 = 10.0;
+
+    /// Default [`MixinSidechainParams::release`].
+    pub const DEFAULT_RELEASE: f64 = 1500.0;
+
+    /// Default [`MixinSidechainParams::makeup`].
+    pub const DEFAULT_MAKEUP: f64 = 1.0;
+
+    /// Allowed range of [`MixinSidechainParams::threshold`], as accepted by
+    /// [FFmpeg].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub const THRESHOLD_RANGE: RangeInclusive<f64> = 0.000_976_563..=1.0;
+
+    /// Allowed range of [`MixinSidechainParams::ratio`].
+    pub const RATIO_RANGE: RangeInclusive<f64> = 1.0..=20.0;
+
+    /// Allowed range of [`MixinSidechainParams::attack`].
+    pub const ATTACK_RANGE: RangeInclusive<f64> = 0.01..=2000.0;
+
+    /// Allowed range of [`MixinSidechainParams::release`].
+    pub const RELEASE_RANGE: RangeInclusive<f64> = 0.01..=9000.0;
+
+    /// Allowed range of [`MixinSidechainParams::makeup`].
+    pub const MAKEUP_RANGE: RangeInclusive<f64> = 1.0..=64.0;
+
+    /// Returns [`MixinSidechainParams::DEFAULT_THRESHOLD`].
+    #[inline]
+    #[must_use]
+    fn default_threshold() -> f64 {
+        Self::DEFAULT_THRESHOLD
+    }
+
+    /// Returns [`MixinSidechainParams::DEFAULT_RATIO`].
+    #[inline]
+    #[must_use]
+    fn default_ratio() -> f64 {
+        Self::DEFAULT_RATIO
+    }
+
+    /// Returns [`MixinSidechainParams::DEFAULT_ATTACK`].
+    #[inline]
+    #[must_use]
+    fn default_attack() -> f64 {
+        Self::DEFAULT_ATTACK
+    }
+
+    /// Returns [`MixinSidechainParams::DEFAULT_RELEASE`].
+    #[inline]
+    #[must_use]
+    fn default_release() -> f64 {
+        Self::DEFAULT_RELEASE
+    }
+
+    /// Returns [`MixinSidechainParams::DEFAULT_MAKEUP`].
+    #[inline]
+    #[must_use]
+    fn default_makeup() -> f64 {
+        Self::DEFAULT_MAKEUP
+    }
+
+    /// Indicates whether all these [`MixinSidechainParams`] are within their
+    /// allowed ranges.
+    #[inline]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        Self::THRESHOLD_RANGE.contains(&self.threshold)
+            && Self::RATIO_RANGE.contains(&self.ratio)
+            && Self::ATTACK_RANGE.contains(&self.attack)
+            && Self::RELEASE_RANGE.contains(&self.release)
+            && Self::MAKEUP_RANGE.contains(&self.makeup)
+    }
+}
+
+impl Default for MixinSidechainParams {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            threshold: Self::DEFAULT_THRESHOLD,
+            ratio: Self::DEFAULT_RATIO,
+            attack: Self::DEFAULT_ATTACK,
+            release: Self::DEFAULT_RELEASE,
+            makeup: Self::DEFAULT_MAKEUP,
+        }
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::OutputTls`].
+///
+/// Secret values are masked with [`state::OutputTls::MASK`] on export, and
+/// such masked values preserve the existing secrets on import.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "OutputTlsInput")]
+pub struct OutputTls {
+    /// Path to a file with CA certificates to verify the destination server
+    /// with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+
+    /// Path to a file with a client certificate to authenticate with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_file: Option<String>,
+
+    /// Path to a file with a private key of the client certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+
+    /// Indicator whether verification of the destination server certificate
+    /// should be skipped.
+    ///
+    /// Allows publishing onto a destination with a self-signed certificate,
+    /// unless its certificate is specified as the `ca_file` instead.
+    #[serde(default, skip_serializing_if = "is_false")]
+    #[graphql(default)]
+    pub skip_verify: bool,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::OutputReconnect`].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "OutputReconnectInput")]
+pub struct OutputReconnect {
+    /// Indicator whether the connection should be re-established on its
+    /// failure.
+    #[serde(default, skip_serializing_if = "is_false")]
+    #[graphql(default)]
+    pub http_reconnect: bool,
+
+    /// Timeout (in milliseconds) of network read/write operations, after which
+    /// the publishing is considered failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rw_timeout: Option<i32>,
+
+    /// Latency (in milliseconds) of a SRT connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_latency: Option<i32>,
+
+    /// Maximum bandwidth (in bytes per second) of a SRT connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_max_bandwidth: Option<i32>,
+}
+
+impl OutputReconnect {
+    /// Indicates whether all the values of this [`OutputReconnect`] are
+    /// non-negative.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        [self.rw_timeout, self.srt_latency, self.srt_max_bandwidth]
+            .iter()
+            .flatten()
+            .all(|v| *v >= 0)
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::OutputIcecast`].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "OutputIcecastInput")]
+pub struct OutputIcecast {
+    /// Name of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Description of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Genre of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+
+    /// URL of a website related to the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Indicator whether the stream should be listed in public directories.
+    #[serde(default, skip_serializing_if = "is_false")]
+    #[graphql(default)]
+    pub public: bool,
+}
+
+impl OutputIcecast {
+    /// Maximum number of characters in a single [`OutputIcecast`] value.
+    pub const MAX_LEN: usize = 256;
+
+    /// Indicates whether all the values of this [`OutputIcecast`] fit into
+    /// [`OutputIcecast::MAX_LEN`] and contain no control characters, as
+    /// they're sent in HTTP headers.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        [&self.name, &self.description, &self.genre, &self.url]
+            .iter()
+            .filter_map(|v| v.as_deref())
+            .all(|v| {
+                v.chars().count() <= Self::MAX_LEN
+                    && !v.chars().any(char::is_control)
+            })
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::OutputLoudnorm`].
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    GraphQLInputObject,
+)]
+#[graphql(name = "OutputLoudnormInput")]
+pub struct OutputLoudnorm {
+    /// Target integrated loudness (in LUFS) to normalize the audio to.
+    #[serde(default = "OutputLoudnorm::default_target_lufs")]
+    #[graphql(default = OutputLoudnorm::DEFAULT_TARGET_LUFS)]
+    pub target_lufs: i32,
+}
+
+impl OutputLoudnorm {
+    /// Default [`OutputLoudnorm::target_lufs`], as recommended by
+    /// [EBU R128].
+    ///
+    /// [EBU R128]: https://en.wikipedia.org/wiki/EBU_R_128
+    pub const DEFAULT_TARGET_LUFS: i32 = -23;
+
+    /// Minimum allowed [`OutputLoudnorm::target_lufs`].
+    pub const MIN_TARGET_LUFS: i32 = -70;
+
+    /// Maximum allowed [`OutputLoudnorm::target_lufs`].
+    pub const MAX_TARGET_LUFS: i32 = -5;
+
+    /// Returns [`OutputLoudnorm::DEFAULT_TARGET_LUFS`].
+    #[inline]
+    #[must_use]
+    fn default_target_lufs() -> i32 {
+        Self::DEFAULT_TARGET_LUFS
+    }
+
+    /// Indicates whether [`OutputLoudnorm::target_lufs`] is within the
+    /// allowed range.
+    #[inline]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN_TARGET_LUFS..=Self::MAX_TARGET_LUFS)
+            .contains(&self.target_lufs)
+    }
 }
 
 /// Shareable specification of [`state::Volume`].
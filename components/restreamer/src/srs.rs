@@ -4,11 +4,12 @@
 
 use std::{
     borrow::Borrow,
+    collections::HashSet,
     ops::Deref,
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     process::Stdio,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use anyhow::anyhow;
@@ -16,10 +17,171 @@ use askama::Template;
 use derive_more::{AsRef, Deref, Display, From, Into};
 use ephyr_log::{log, slog};
 use futures::future::{self, FutureExt as _, TryFutureExt as _};
+use once_cell::sync::{Lazy, OnceCell};
 use smart_default::SmartDefault;
 use tokio::{fs, process::Command};
 
-use crate::{api, display_panic, dvr};
+use crate::{api, display_panic, dvr, state};
+
+/// Default port of [SRS] server to accept [RTMP] connections on.
+///
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRS]: https://github.com/ossrs/srs
+pub const DEFAULT_RTMP_PORT: u16 = 1935;
+
+/// Default port of [SRS] server to serve [HLS] live streams on.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+pub const DEFAULT_HTTP_PORT: u16 = 8000;
+
+/// Default port of [SRS] server to expose its [HTTP API][1] on `127.0.0.1`.
+///
+/// [SRS]: https://github.com/ossrs/srs
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+pub const DEFAULT_API_PORT: u16 = 8002;
+
+/// Port of the spawned [SRS] server serving [HLS] live streams.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+static HTTP_PORT: OnceCell<u16> = OnceCell::new();
+
+/// Returns port of the spawned [SRS] server serving [HLS] live streams.
+///
+/// Returns [`DEFAULT_HTTP_PORT`] if no [`Server`] has been spawned yet.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+#[inline]
+#[must_use]
+pub fn http_port() -> u16 {
+    HTTP_PORT.get().copied().unwrap_or(DEFAULT_HTTP_PORT)
+}
+
+/// Port of the spawned [SRS] server exposing its [HTTP API][1].
+///
+/// [SRS]: https://github.com/ossrs/srs
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+static API_PORT: OnceCell<u16> = OnceCell::new();
+
+/// Returns port of the spawned [SRS] server exposing its [HTTP API][1] on
+/// `127.0.0.1`.
+///
+/// Returns [`DEFAULT_API_PORT`] if no [`Server`] has been spawned yet.
+///
+/// [SRS]: https://github.com/ossrs/srs
+/// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+#[inline]
+#[must_use]
+pub fn api_port() -> u16 {
+    API_PORT.get().copied().unwrap_or(DEFAULT_API_PORT)
+}
+
+/// Port of the spawned [SRS] server accepting [RTMP] connections.
+///
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRS]: https://github.com/ossrs/srs
+static RTMP_PORT: OnceCell<u16> = OnceCell::new();
+
+/// Returns port of the spawned [SRS] server accepting [RTMP] connections.
+///
+/// Returns [`DEFAULT_RTMP_PORT`] if no [`Server`] has been spawned yet.
+///
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRS]: https://github.com/ossrs/srs
+#[inline]
+#[must_use]
+pub fn rtmp_port() -> u16 {
+    RTMP_PORT.get().copied().unwrap_or(DEFAULT_RTMP_PORT)
+}
+
+/// Directory of the spawned [SRS] server's HTTP server, where it writes [HLS]
+/// playlists and segments into.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+static HLS_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Returns directory of the spawned [SRS] server's HTTP server, where it writes
+/// [HLS] playlists and segments of the given `restream` into.
+///
+/// Returns [`None`] if no [`Server`] has been spawned yet.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+#[inline]
+#[must_use]
+pub fn hls_dir(restream: &str) -> Option<PathBuf> {
+    HLS_DIR.get().map(|dir| dir.join(restream))
+}
+
+/// Directory of the spawned [SRS] server's HTTP server to write [LL-HLS]
+/// playlists and segments into.
+///
+/// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+/// [SRS]: https://github.com/ossrs/srs
+static LL_HLS_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Returns directory of the spawned [SRS] server's HTTP server to write
+/// [LL-HLS] playlists and segments of the given `restream` and `input` into.
+///
+/// Returns [`None`] if no [`Server`] has been spawned yet.
+///
+/// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+/// [SRS]: https://github.com/ossrs/srs
+#[inline]
+#[must_use]
+pub fn ll_hls_dir(restream: &str, input: &str) -> Option<PathBuf> {
+    LL_HLS_DIR.get().map(|dir| dir.join(restream).join(input))
+}
+
+/// [SRS] apps of `Restream`s having a dedicated [HLS] vhost in the current
+/// configuration of the spawned [SRS] server.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+static HLS_VHOST_APPS: Lazy<RwLock<HashSet<String>>> =
+    Lazy::new(RwLock::default);
+
+/// Returns [SRS] vhost to publish [HLS] live streams of the given `restream`
+/// app into.
+///
+/// `Restream`s with [`RestreamSrsTuning`] of their [HLS] endpoints have a
+/// dedicated vhost, while all others share the `hls` one.
+///
+/// # Panics
+///
+/// If the lock is poisoned.
+///
+/// [`RestreamSrsTuning`]: state::RestreamSrsTuning
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+#[must_use]
+pub fn hls_vhost(restream: &str) -> String {
+    if HLS_VHOST_APPS.read().unwrap().contains(restream) {
+        format!("{}{}", HLS_VHOST_PREFIX, restream)
+    } else {
+        "hls".into()
+    }
+}
+
+/// Prefix of dedicated [SRS] vhosts of `Restream`s for their [HLS] live
+/// streams.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+pub const HLS_VHOST_PREFIX: &str = "hls.";
+
+/// Indicates whether the given [SRS] `vhost` serves [HLS] live streams.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+/// [SRS]: https://github.com/ossrs/srs
+#[inline]
+#[must_use]
+pub fn is_hls_vhost(vhost: &str) -> bool {
+    vhost == "hls" || vhost.starts_with(HLS_VHOST_PREFIX)
+}
 
 /// [SRS] server spawnable as a separate process.
 ///
@@ -75,11 +237,51 @@ impl Server {
             )
         })?;
 
-        // Set directory for dvr::Storage served by this SRS instance.
-        let mut dvr_dir = http_dir.clone();
-        dvr_dir.push("dvr");
+        // Pre-create directory for LL-HLS.
+        let mut ll_hls_dir = http_dir.clone();
+        ll_hls_dir.push(state::LlHls::DIR);
+        fs::create_dir_all(&ll_hls_dir).await.map_err(|e| {
+            anyhow!(
+                "Failed to pre-create LL-HLS directory {} : {}",
+                ll_hls_dir.display(),
+                e,
+            )
+        })?;
+        LL_HLS_DIR.set(ll_hls_dir).map_err(|_| {
+            anyhow!("SRS LL-HLS directory has been set already")
+        })?;
+
+        HLS_DIR
+            .set(http_dir.clone())
+            .map_err(|_| anyhow!("SRS HLS directory has been set already"))?;
+
+        // Set directory for dvr::Storage outside the directory served by
+        // this SRS instance, so recordings are not exposed without
+        // authorization.
+        let dvr_dir = if cfg.dvr_dir.is_relative() {
+            workdir.join(&cfg.dvr_dir)
+        } else {
+            cfg.dvr_dir.clone().into()
+        };
+        if dvr_dir.starts_with(&http_dir) {
+            return Err(anyhow!(
+                "DVR directory {} should be outside of SRS HTTP directory {}",
+                dvr_dir.display(),
+                http_dir.display(),
+            ));
+        }
         dvr::Storage { root_path: dvr_dir }.set_global()?;
 
+        RTMP_PORT
+            .set(cfg.rtmp_port)
+            .map_err(|_| anyhow!("SRS RTMP port has been set already"))?;
+        HTTP_PORT
+            .set(cfg.http_port)
+            .map_err(|_| anyhow!("SRS HTTP port has been set already"))?;
+        API_PORT
+            .set(cfg.api_port)
+            .map_err(|_| anyhow!("SRS API port has been set already"))?;
+
         let mut cmd = Command::new(bin_path);
         let _ = cmd
             .stdin(Stdio::null())
@@ -142,15 +344,73 @@ impl Server {
     ///
     /// [SRS]: https://github.com/ossrs/srs
     pub async fn refresh(&self, cfg: &Config) -> anyhow::Result<()> {
-        // SRS server reloads automatically on its conf file changes.
+        self.config_file().refresh(cfg).await
+    }
+
+    /// Returns [`ConfigFile`] of this [`Server`].
+    #[inline]
+    #[must_use]
+    pub fn config_file(&self) -> ConfigFile {
+        ConfigFile(self.conf_path.clone())
+    }
+}
+
+/// Configuration file of a spawned [SRS] [`Server`], allowing to reload it
+/// without keeping the [`Server`] process alive.
+///
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(Clone, Debug)]
+pub struct ConfigFile(PathBuf);
+
+impl ConfigFile {
+    /// Updates this [SRS] configuration file with the given [`Config`].
+    ///
+    /// The spawned [SRS] server reloads automatically on its configuration
+    /// file changes, if it's able to watch them.
+    ///
+    /// # Errors
+    ///
+    /// If [SRS] configuration file fails to be created.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub async fn refresh(&self, cfg: &Config) -> anyhow::Result<()> {
         fs::write(
-            &self.conf_path,
+            &self.0,
             cfg.render().map_err(|e| {
                 anyhow!("Failed to render SRS config from template: {e}")
             })?,
         )
         .await
-        .map_err(|e| anyhow!("Failed to write SRS config file: {e}"))
+        .map_err(|e| anyhow!("Failed to write SRS config file: {e}"))?;
+
+        *HLS_VHOST_APPS.write().unwrap() = cfg
+            .restreams
+            .iter()
+            .filter(|r| r.tuning.has_hls_vhost())
+            .map(|r| r.app.clone())
+            .collect();
+        Ok(())
+    }
+
+    /// Updates this [SRS] configuration file with the given [`Config`], and
+    /// reloads the spawned [SRS] server explicitly via its [HTTP API][1].
+    ///
+    /// # Errors
+    ///
+    /// If [SRS] configuration file fails to be created, or [SRS] server fails
+    /// to be reloaded.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+    pub async fn reload(&self, cfg: &Config) -> anyhow::Result<()> {
+        self.refresh(cfg).await?;
+        api::srs::Client::reload()
+            .await
+            .map_err(|e| anyhow!("Failed to reload SRS server: {e}"))
     }
 }
 
@@ -235,6 +495,27 @@ impl Drop for ClientId {
 #[derive(Clone, Debug, Template)]
 #[template(path = "restreamer.srs.conf.j2", escape = "none")]
 pub struct Config {
+    /// Port to accept [RTMP] connections on.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    pub rtmp_port: u16,
+
+    /// Port to serve [HLS] live streams and other files of
+    /// [`Config::http_server_dir`] on.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    pub http_port: u16,
+
+    /// UDP port to accept [WebRTC] media on.
+    ///
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    pub rtc_port: u16,
+
+    /// Port to expose [HTTP API][2] on `127.0.0.1`.
+    ///
+    /// [2]: https://github.com/ossrs/srs/wiki/v4_EN_HTTPApi
+    pub api_port: u16,
+
     /// Port that [HTTP Callback API][1] is exposed on.
     ///
     /// [1]: https://en.wikipedia.org/wiki/Basic_access_authentication
@@ -245,10 +526,140 @@ pub struct Config {
     /// [SRS]: https://github.com/ossrs/srs
     pub http_server_dir: DisplayablePath,
 
+    /// Path to the directory to store [DVR] recordings in, being not served
+    /// by [SRS] HTTP server.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    /// [SRS]: https://github.com/ossrs/srs
+    pub dvr_dir: DisplayablePath,
+
+    /// Host (IP address or domain name) announced to [WebRTC] clients as an
+    /// [ICE candidate][1] to send media onto.
+    ///
+    /// `*` makes [SRS] to announce all its local IP addresses.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [1]: https://github.com/ossrs/srs/wiki/v4_EN_WebRTC#config-candidate
+    pub rtc_candidate: String,
+
     /// Severity of [SRS] server logs.
     ///
     /// [SRS]: https://github.com/ossrs/srs
     pub log_level: LogLevel,
+
+    /// Configurations of `Restream`s tuning the [SRS] server.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub restreams: Vec<RestreamConfig>,
+}
+
+impl Config {
+    /// Returns `app/stream` pairs of live streams to be recorded by the [SRS]
+    /// server, separated by spaces.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn dvr_apply(&self) -> String {
+        self.restreams
+            .iter()
+            .filter(|r| r.tuning.dvr)
+            .flat_map(|r| {
+                r.streams.iter().map(move |s| format!("{}/{s}", r.app))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// [SRS] configuration of a `Restream` having [`RestreamSrsTuning`].
+///
+/// [`RestreamSrsTuning`]: state::RestreamSrsTuning
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RestreamConfig {
+    /// [SRS] app of the `Restream`.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub app: String,
+
+    /// Keys of all `Input`s of the `Restream`, being [SRS] streams.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub streams: Vec<String>,
+
+    /// [`RestreamSrsTuning`] of the `Restream`.
+    ///
+    /// [`RestreamSrsTuning`]: state::RestreamSrsTuning
+    pub tuning: state::RestreamSrsTuning,
+}
+
+impl RestreamConfig {
+    /// Collects [`RestreamConfig`]s of the given `Restream`s having
+    /// [`RestreamSrsTuning`].
+    ///
+    /// [`RestreamSrsTuning`]: state::RestreamSrsTuning
+    #[must_use]
+    pub fn collect(restreams: &[state::Restream]) -> Vec<Self> {
+        fn keys(input: &state::Input, out: &mut Vec<String>) {
+            out.push(input.key.to_string());
+            if let Some(state::InputSrc::Failover(s)) = &input.src {
+                for i in &s.inputs {
+                    keys(i, out);
+                }
+            }
+        }
+
+        restreams
+            .iter()
+            .filter_map(|r| {
+                let tuning = r.srs_tuning.clone()?;
+                let mut streams = vec![];
+                keys(&r.input, &mut streams);
+                Some(Self {
+                    app: r.srs_app(),
+                    streams,
+                    tuning,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns name of the dedicated [SRS] vhost for [HLS] live streams of
+    /// the `Restream`.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn hls_vhost(&self) -> String {
+        format!("{}{}", HLS_VHOST_PREFIX, self.app)
+    }
+
+    /// Returns duration (in seconds) of [HLS] fragments in the dedicated
+    /// [SRS] vhost of the `Restream`.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn hls_fragment(&self) -> i32 {
+        self.tuning.hls_fragment.unwrap_or(10)
+    }
+
+    /// Returns `on` or `off` value of [SRS] GOP cache in the dedicated [SRS]
+    /// vhost of the `Restream`.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn gop_cache(&self) -> &'static str {
+        if self.tuning.gop_cache.unwrap_or(true) {
+            "on"
+        } else {
+            "off"
+        }
+    }
 }
 
 /// Severity of [SRS] [server logs][1].
@@ -0,0 +1,159 @@
+//! [SRT] ingest of live streams, accepting them by listening on ports assigned
+//! to `InputEndpointKind::Srt` endpoints.
+//!
+//! [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+
+use std::{collections::HashSet, fmt, ops::RangeInclusive, str::FromStr};
+
+use anyhow::anyhow;
+use futures::future;
+use once_cell::sync::OnceCell;
+use url::Url;
+
+use crate::{
+    state::{Input, InputEndpoint, InputEndpointKind, InputSrc},
+    State,
+};
+
+/// Range of ports to be assigned to `InputEndpointKind::Srt` endpoints.
+static PORTS: OnceCell<PortRange> = OnceCell::new();
+
+/// Range of UDP ports to listen on for [SRT] connections.
+///
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PortRange {
+    /// First port of this [`PortRange`].
+    start: u16,
+
+    /// Last port of this [`PortRange`] (inclusive).
+    end: u16,
+}
+
+impl PortRange {
+    /// Returns all the ports of this [`PortRange`].
+    #[inline]
+    #[must_use]
+    pub fn ports(self) -> RangeInclusive<u16> {
+        self.start..=self.end
+    }
+
+    /// Sets this [`PortRange`] as the global one, which ports are assigned to
+    /// `InputEndpointKind::Srt` endpoints from.
+    ///
+    /// # Errors
+    ///
+    /// If the global [`PortRange`] has been set already.
+    #[inline]
+    pub fn set_global(self) -> anyhow::Result<()> {
+        PORTS
+            .set(self)
+            .map_err(|_| anyhow!("SRT port range has been set already"))
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let parse = |p: &str| {
+            p.trim()
+                .parse::<u16>()
+                .map_err(|e| anyhow!("'{p}' is invalid port: {e}"))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == 0 || start > end {
+            return Err(anyhow!(
+                "'{s}' is invalid port range, expected: <start>-<end>",
+            ));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Returns [FFmpeg] URL listening for a [SRT] connection on the given `port`,
+/// requiring the given `passphrase` (if any).
+///
+/// [FFmpeg]: https://ffmpeg.org
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+#[must_use]
+pub fn listener_url(port: u16, passphrase: Option<&str>) -> Url {
+    let mut url =
+        Url::parse(&format!("srt://0.0.0.0:{port}?mode=listener")).unwrap();
+    if let Some(pass) = passphrase {
+        let _ = url.query_pairs_mut().append_pair("passphrase", pass);
+    }
+    url
+}
+
+/// Runs assigning ports from the global [`PortRange`] to
+/// `InputEndpointKind::Srt` endpoints of the given [`State`] once they
+/// change.
+///
+/// Already assigned ports are kept as long as they stay in the range, so
+/// encoders don't need to be reconfigured. Does nothing if no global
+/// [`PortRange`] has been set.
+pub fn run(state: &State) {
+    let range = match PORTS.get() {
+        Some(r) => *r,
+        None => return,
+    };
+    let st = state.clone();
+    State::on_change("assign_srt_ports", &state.restreams, move |_| {
+        assign_ports(&st, range);
+        future::ready(())
+    });
+}
+
+/// Assigns free ports from the given [`PortRange`] to
+/// `InputEndpointKind::Srt` endpoints of the given [`State`] not having
+/// valid ones.
+fn assign_ports(state: &State, range: PortRange) {
+    /// Collects all `InputEndpointKind::Srt` endpoints of the given [`Input`]
+    /// and its failover [`Input`]s.
+    fn srt_endpoints<'i>(
+        input: &'i mut Input,
+        out: &mut Vec<&'i mut InputEndpoint>,
+    ) {
+        for e in &mut input.endpoints {
+            if e.kind == InputEndpointKind::Srt {
+                out.push(e);
+            }
+        }
+        if let Some(InputSrc::Failover(s)) = input.src.as_mut() {
+            for i in &mut s.inputs {
+                srt_endpoints(i, out);
+            }
+        }
+    }
+
+    // Unchanged `State::restreams` are deduplicated by `State::on_change()`
+    // hooks, so locking them for writing here doesn't loop.
+    let mut restreams = state.restreams.lock_mut();
+    let mut endpoints = vec![];
+    for r in restreams.iter_mut() {
+        srt_endpoints(&mut r.input, &mut endpoints);
+    }
+
+    let mut used = HashSet::with_capacity(endpoints.len());
+    let mut unassigned = vec![];
+    for e in endpoints {
+        match e.srt_port {
+            Some(p) if range.ports().contains(&p) && used.insert(p) => {}
+            _ => unassigned.push(e),
+        }
+    }
+    let mut free = range.ports().filter(|p| !used.contains(p));
+    for e in unassigned {
+        // Endpoints left without a port are not listened on, until some port
+        // is freed.
+        e.srt_port = free.next();
+    }
+}
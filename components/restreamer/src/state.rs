@@ -1,51 +1,93 @@
 //! Application state.
 #![allow(clippy::module_name_repetitions)]
 
+mod alert;
+mod audio_level;
 mod client_statistics;
+mod counters;
+mod error;
 mod input;
 mod label;
 mod output;
+mod process_usage;
 mod restream;
 mod settings;
+mod status_history;
+mod storage;
+mod template;
+mod watchers;
 
 pub use self::{
+    alert::{Alert, AlertEvent, AlertEventKind, AlertKind, AlertRule},
+    audio_level::{AudioLevel, MixinAudioLevel, OutputAudioLevels},
     client_statistics::{
         Client, ClientId, ClientStatistics, ClientStatisticsResponse,
-        ServerInfo, StatusStatistics,
+        ClientStatus, EndpointsUsage, FlappingOutput, ServerInfo,
+        ServerInfoSample, StatusStatistics, StreamStatistics,
     },
+    counters::StatusCounters,
+    error::{Changed, StateError},
     input::{
-        EndpointId, FailoverInputSrc, Input, InputEndpoint, InputEndpointKind,
-        InputId, InputKey, InputSrc, InputSrcUrl, RemoteInputSrc,
+        EndpointId, EndpointKey, FailoverHysteresis, FailoverInputSrc,
+        HlsVariantSelector, Input, InputEndpoint, InputEndpointKind, InputId,
+        InputKey, InputSrc, InputSrcUrl, InputTuning, LlHls, PlaylistFileUrl,
+        PlaylistInputSrc, RemoteInputSrc,
     },
-    label::Label,
+    label::{Label, TextError, Title},
     output::{
-        Delay, Mixin, MixinId, MixinSrcUrl, Output, OutputDstUrl, OutputId,
-        Volume, VolumeLevel,
+        Delay, Mixin, MixinId, MixinSidechainParams, MixinSrcUrl, Output,
+        OutputDstUrl, OutputIcecast, OutputId, OutputLoudnorm, OutputReconnect,
+        OutputTls, OutputTraffic, Volume, VolumeLevel,
     },
-    restream::{Restream, RestreamId, RestreamKey},
-    settings::Settings,
+    process_usage::ProcessUsage,
+    restream::{
+        FallbackKind, IpCidr, PublishToken, Restream, RestreamFallback,
+        RestreamId, RestreamIpFilter, RestreamKey, RestreamSrsTuning,
+        RestreamWebhook, TenantKey, WebhookDelivery, WebhookEvent,
+    },
+    settings::{MixinLimits, MixinLimitsError, Settings},
+    status_history::StatusEvent,
+    storage::{SqliteStorage, Storage, StorageKind},
+    template::RestreamTemplate,
+    watchers::RestreamWatchers,
 };
 
-use std::{future::Future, mem, panic::AssertUnwindSafe, path::Path};
+use std::{
+    convert::TryFrom, future::Future, mem, panic::AssertUnwindSafe, sync::Arc,
+    time::Duration,
+};
 
 use anyhow::anyhow;
+use backoff::{future::retry_notify, ExponentialBackoff};
+use chrono::{DateTime, Utc};
 use ephyr_log::log;
 use futures::{
-    future::TryFutureExt as _,
+    future::{self, TryFutureExt as _},
     sink,
     stream::{StreamExt as _, TryStreamExt as _},
 };
-use futures_signals::signal::{Mutable, SignalExt as _};
+use futures_signals::signal::{Mutable, Signal, SignalExt as _};
 use juniper::GraphQLEnum;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
-use tokio::{fs, io::AsyncReadExt as _};
+use tokio::{sync::Notify, time};
+use url::Url;
+use uuid::Uuid;
 
 use crate::{
-    display_panic, spec, state::client_statistics::StreamStatistics,
-    stream_probe::StreamInfo, Spec,
+    audit::AuditEntry,
+    display_panic,
+    notifier::{self, Event, Severity},
+    spec, srs,
+    stream_probe::StreamInfo,
+    Spec,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+/// Maximum delay of persisting changes of a [`State`], even if they keep
+/// coming more often than the debounce interval.
+const MAX_PERSIST_DELAY: Duration = Duration::from_secs(5);
 
 /// Reactive application's state.
 ///
@@ -58,61 +100,89 @@ pub struct State {
     /// All [`Restream`]s performed by this application.
     pub restreams: Mutable<Vec<Restream>>,
 
+    /// Per-[`Restream`] mirrors of [`State::restreams`] for subscribing to
+    /// a single [`Restream`].
+    ///
+    /// Use [`State::restream_signal()`] to subscribe.
+    #[serde(skip)]
+    pub restream_watchers: RestreamWatchers,
+
     /// All [`Client`]s for monitoring
     pub clients: Mutable<Vec<Client>>,
 
     /// Global [`ServerInfo`] of the server
     pub server_info: Mutable<ServerInfo>,
+
+    /// [`ServerInfoSample`]s of the last
+    /// [`ServerInfoSample::HISTORY_PERIOD`], ordered from the oldest to the
+    /// newest.
+    #[serde(skip)]
+    pub server_info_history: Mutable<VecDeque<ServerInfoSample>>,
+
+    /// Active [`Alert`]s about monitored [`Client`]s.
+    #[serde(skip)]
+    pub alerts: Mutable<Vec<Alert>>,
+
+    /// Live [`AudioLevel`]s of [`Output`]s and [`Mixin`]s being measured at
+    /// the moment, identified by their IDs.
+    #[serde(skip)]
+    pub audio_levels: Mutable<HashMap<Uuid, AudioLevel>>,
+
+    /// Audit log of the mutations requested via GraphQL API, persisted
+    /// separately from the rest of the [`State`].
+    #[serde(skip)]
+    pub audit_log: Mutable<VecDeque<AuditEntry>>,
+
+    /// Indicator whether the server runs in maintenance mode, rejecting any
+    /// changes requested via API.
+    ///
+    /// Not persisted, so is reset on restart.
+    #[serde(skip)]
+    pub maintenance_mode: Mutable<bool>,
 }
 
 impl State {
-    /// Instantiates a new [`State`] reading it from a `file` (if any) and
-    /// performing all the required inner subscriptions.
+    /// Instantiates a new [`State`] loading it from the given [`Storage`] (if
+    /// any) and performing all the required inner subscriptions.
+    ///
+    /// Changes of the [`State`] are persisted into the [`Storage`] once no
+    /// more changes happen during the `debounce` interval, so bursts of
+    /// changes are written at once. Still, persisting is never delayed for
+    /// more than [`MAX_PERSIST_DELAY`] (or the `debounce` interval, if it's
+    /// greater), so constantly coming changes are not lost.
     ///
     /// # Errors
     ///
-    /// If [`State`] file exists, but fails to be parsed.
-    pub async fn try_new<P: AsRef<Path>>(
-        file: P,
+    /// If [`Storage`] contains a [`State`], but it fails to be loaded.
+    pub async fn try_new(
+        storage: &Storage,
+        debounce: Duration,
     ) -> Result<Self, anyhow::Error> {
-        let file = file.as_ref();
-
-        let mut contents = vec![];
-        let _ = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(&file)
-            .await
-            .map_err(|e| {
-                anyhow!("Failed to open '{}' file: {}", file.display(), e)
-            })?
-            .read_to_end(&mut contents)
-            .await
-            .map_err(|e| {
-                anyhow!("Failed to read '{}' file: {}", file.display(), e)
-            })?;
-
-        let state = if contents.is_empty() {
-            State::default()
-        } else {
-            serde_json::from_slice(&contents).map_err(|e| {
-                anyhow!(
-                    "Failed to deserialize state from '{}' file: {}",
-                    file.display(),
-                    e,
-                )
-            })?
-        };
-
-        let (file, persisted_state) = (file.to_owned(), state.clone());
+        let state = storage.load().await?.unwrap_or_default();
+
+        let changed = Arc::new(Notify::new());
+        let (storage, persisted_state) = (storage.clone(), state.clone());
+        let persisting = Arc::clone(&changed);
+        drop(tokio::spawn(async move {
+            loop {
+                persisting.notified().await;
+                // Wait until changes stop coming, but not endlessly.
+                let deadline =
+                    time::Instant::now() + debounce.max(MAX_PERSIST_DELAY);
+                while time::Instant::now() < deadline
+                    && time::timeout_at(
+                        deadline.min(time::Instant::now() + debounce),
+                        persisting.notified(),
+                    )
+                    .await
+                    .is_ok()
+                {}
+                Self::persist(storage.clone(), persisted_state.clone()).await;
+            }
+        }));
         let persist_state1 = move || {
-            fs::write(
-                file.clone(),
-                serde_json::to_vec(&persisted_state)
-                    .expect("Failed to serialize server state"),
-            )
-            .map_err(|e| log::error!("Failed to persist server state: {e}"))
+            changed.notify_one();
+            future::ready(())
         };
         let persist_state2 = persist_state1.clone();
         let persist_state3 = persist_state1.clone();
@@ -127,9 +197,81 @@ impl State {
             persist_state3()
         });
 
+        let watchers = state.restream_watchers.clone();
+        drop(tokio::spawn(
+            state
+                .restreams
+                .signal_ref(move |restreams| watchers.sync(restreams))
+                .to_stream()
+                .for_each(|()| future::ready(())),
+        ));
+
         Ok(state)
     }
 
+    /// Persists the given [`State`] into the [`Storage`], retrying it
+    /// endlessly with an [`ExponentialBackoff`] on failures.
+    ///
+    /// While the [`State`] fails to be persisted, the failure is reported in
+    /// its [`ServerInfo::state_persistence_error`].
+    async fn persist(storage: Storage, state: State) {
+        if crate::shutdown::is_started() {
+            // The state is flushed by `shutdown::Coordinator` instead.
+            return;
+        }
+        let server_info = state.server_info.clone();
+        retry_notify(
+            ExponentialBackoff {
+                max_elapsed_time: None,
+                max_interval: Duration::from_secs(60),
+                ..ExponentialBackoff::default()
+            },
+            move || {
+                let (storage, state) = (storage.clone(), state.clone());
+                async move {
+                    storage
+                        .save(&state)
+                        .await
+                        .map_err(backoff::Error::transient)
+                }
+            },
+            |err, dur| {
+                log::error!(
+                    "Failed to persist server state, retrying in {}: {}",
+                    humantime::format_duration(dur),
+                    err,
+                );
+                let mut info = server_info.lock_mut();
+                if info.state_persistence_error.is_none() {
+                    notifier::notify(Event::new(
+                        Severity::Critical,
+                        "Failed to persist server state",
+                        err.to_string(),
+                    ));
+                }
+                info.set_state_persistence_error(Some(err.to_string()));
+            },
+        )
+        .await
+        .map_or_else(
+            |e| log::error!("Failed to persist server state: {e}"),
+            |()| {
+                if server_info.lock_ref().state_persistence_error.is_some() {
+                    server_info.lock_mut().set_state_persistence_error(None);
+                }
+            },
+        );
+    }
+
+    /// Writes this [`State`] into the given [`Storage`] at once.
+    ///
+    /// # Errors
+    ///
+    /// If the [`Storage`] fails to be written.
+    pub async fn flush(&self, storage: &Storage) -> anyhow::Result<()> {
+        storage.save(self).await
+    }
+
     /// Applies the given [`Spec`] to this [`State`].
     ///
     /// If `replace` is `true` then all the [`Restream`]s, [`Restream::outputs`]
@@ -175,6 +317,60 @@ impl State {
         }
     }
 
+    /// Imports the given [`Spec`] into this [`State`], or into its
+    /// [`Restream`] with the given `restream_id` only (if specified).
+    ///
+    /// See [`State::apply()`] and [`Restream::apply()`] for `replace`
+    /// semantics.
+    ///
+    /// Returns [`None`] if there is no [`Restream`] with such `restream_id` in
+    /// this [`State`].
+    ///
+    /// # Errors
+    ///
+    /// - If `restream_id` is specified, but the given [`Spec`] doesn't contain
+    ///   exactly one [`Restream`].
+    /// - If any [`Output`] of the given [`Spec`] exceeds [`MixinLimits`] of
+    ///   its [`Settings`] (or of this [`State`], if it has none).
+    pub fn import(
+        &self,
+        spec: spec::v1::Spec,
+        replace: bool,
+        restream_id: Option<RestreamId>,
+    ) -> anyhow::Result<Option<()>> {
+        let limits = match spec.settings.as_ref() {
+            Some(s) if restream_id.is_none() => s.mixin_limits,
+            _ if replace && restream_id.is_none() => MixinLimits::default(),
+            _ => self.settings.lock_ref().mixin_limits,
+        };
+        for o in spec.restreams.iter().flat_map(|r| &r.outputs) {
+            limits
+                .check(o.mixins.iter().map(|m| &m.src))
+                .map_err(|e| anyhow!("Output '{}': {e}", o.dst))?;
+        }
+
+        let id = if let Some(id) = restream_id {
+            id
+        } else {
+            self.apply(spec, replace);
+            return Ok(Some(()));
+        };
+
+        let spec = (spec.restreams.len() == 1)
+            .then(|| spec.restreams.into_iter().next())
+            .flatten()
+            .ok_or_else(|| {
+                anyhow!("Spec should contain exactly one Restream")
+            })?;
+        #[allow(clippy::manual_find_map)] // due to moving `spec` inside closure
+        Ok(self
+            .restreams
+            .lock_mut()
+            .iter_mut()
+            .find(|r| r.id == id)
+            .map(|r| r.apply(spec, replace)))
+    }
+
     /// Exports this [`State`] as a [`spec::v1::Spec`].
     #[inline]
     #[must_use]
@@ -191,6 +387,19 @@ impl State {
         .into()
     }
 
+    /// Returns a [`Signal`] of the [`Restream`] with the given `id`, emitting
+    /// [`None`] while there is no such [`Restream`].
+    ///
+    /// Unlike subscribing to the whole [`State::restreams`], emits only once
+    /// this [`Restream`] changes.
+    pub fn restream_signal(
+        &self,
+        id: RestreamId,
+    ) -> impl Signal<Item = Option<Restream>> {
+        let restreams = self.restreams.lock_ref();
+        self.restream_watchers.signal(id, &restreams)
+    }
+
     /// Subscribes the specified `hook` to changes of the [`Mutable`] `val`ue.
     ///
     /// `name` is just a convenience for describing the `hook` in logs.
@@ -234,34 +443,242 @@ impl State {
         Ok(())
     }
 
+    /// Sets or removes (if [`None`]) the password to authorize on the client
+    /// API of the [`Client`] with the given `id` in this [`State`].
+    ///
+    /// Returns `false` if nothing changed.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Client`] with such `id`.
+    pub fn set_client_password(
+        &self,
+        client_id: &ClientId,
+        password: Option<String>,
+    ) -> Result<Changed, StateError> {
+        let mut clients = self.clients.lock_mut();
+        let client = clients
+            .iter_mut()
+            .find(|c| c.id == *client_id)
+            .ok_or(StateError::NotFound("Client"))?;
+        if client.password == password {
+            return Ok(false);
+        }
+        client.password = password;
+        Ok(true)
+    }
+
+    /// Generates a new token for the [`Client`] with the given `id` to report
+    /// its statistics with, switching it to reporting them by itself.
+    ///
+    /// Returns [`None`] if there is no [`Client`] with such `id`.
+    #[must_use]
+    pub fn rotate_client_report_token(
+        &self,
+        client_id: &ClientId,
+    ) -> Option<String> {
+        let mut clients = self.clients.lock_mut();
+        let client = clients.iter_mut().find(|c| c.id == *client_id)?;
+        let token = Uuid::new_v4().simple().to_string();
+        client.report_token = Some(token.clone());
+        Some(token)
+    }
+
+    /// Removes the report token of the [`Client`] with the given `id`,
+    /// switching it back to being polled for its statistics.
+    ///
+    /// Returns `false` if nothing changed.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Client`] with such `id`.
+    pub fn remove_client_report_token(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<Changed, StateError> {
+        let mut clients = self.clients.lock_mut();
+        let client = clients
+            .iter_mut()
+            .find(|c| c.id == *client_id)
+            .ok_or(StateError::NotFound("Client"))?;
+        if client.report_token.take().is_none() {
+            return Ok(false);
+        }
+        client.statistics = None;
+        client.last_seen_at = None;
+        Ok(true)
+    }
+
+    /// Saves the given [`ClientStatistics`] reported by the [`Client`] with
+    /// the given `id`, if the given `token` matches its report token.
+    ///
+    /// Returns `false` if the `token` doesn't match.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Client`] with such `id`.
+    pub fn report_client_statistics(
+        &self,
+        client_id: &ClientId,
+        token: &str,
+        statistics: ClientStatistics,
+        api_version: i32,
+    ) -> Result<Changed, StateError> {
+        let mut clients = self.clients.lock_mut();
+        let client = clients
+            .iter_mut()
+            .find(|c| c.id == *client_id)
+            .ok_or(StateError::NotFound("Client"))?;
+        if client.report_token.as_deref() != Some(token) {
+            return Ok(false);
+        }
+        client.statistics = Some(ClientStatisticsResponse {
+            data: Some(statistics),
+            errors: None,
+            status: ClientStatus::Ok,
+            api_version: Some(api_version),
+        });
+        client.last_seen_at = Some(Utc::now());
+        Ok(true)
+    }
+
     /// Removes a [`Client`] with the given `id` from this [`State`].
     ///
-    /// Returns [`None`] if there is no [`Client`] with such `id` in this
-    /// [`State`].
-    #[allow(clippy::must_use_candidate)]
-    pub fn remove_client(&self, client_id: &ClientId) -> Option<()> {
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Client`] with such `id`
+    /// in this [`State`].
+    pub fn remove_client(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<(), StateError> {
         let mut clients = self.clients.lock_mut();
         let prev_len = clients.len();
         clients.retain(|r| r.id != *client_id);
-        (clients.len() != prev_len).then_some(())
+        (clients.len() != prev_len)
+            .then_some(())
+            .ok_or(StateError::NotFound("Client"))
+    }
+
+    /// Sets the [`AlertRule`] of the given `kind` in [`Settings`] of this
+    /// [`State`], replacing the existing one, if any.
+    ///
+    /// Returns `false` if nothing changed.
+    #[must_use]
+    pub fn set_alert_rule(&self, rule: AlertRule) -> bool {
+        let mut settings = self.settings.lock_mut();
+        let rules = &mut settings.alert_rules;
+        if let Some(r) = rules.iter_mut().find(|r| r.kind == rule.kind) {
+            if *r == rule {
+                return false;
+            }
+            *r = rule;
+        } else {
+            rules.push(rule);
+        }
+        true
+    }
+
+    /// Removes the [`AlertRule`] of the given `kind` from [`Settings`] of this
+    /// [`State`].
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`AlertRule`] of such
+    /// `kind`.
+    pub fn remove_alert_rule(&self, kind: AlertKind) -> Result<(), StateError> {
+        let mut settings = self.settings.lock_mut();
+        let prev_len = settings.alert_rules.len();
+        settings.alert_rules.retain(|r| r.kind != kind);
+        (settings.alert_rules.len() != prev_len)
+            .then_some(())
+            .ok_or(StateError::NotFound("AlertRule"))
+    }
+
+    /// Sets the given [`MixinLimits`] in [`Settings`] of this [`State`].
+    ///
+    /// Returns `false` if nothing changed.
+    ///
+    /// # Errors
+    ///
+    /// If any existing [`Output`] exceeds the given [`MixinLimits`], so should
+    /// be reduced first.
+    pub fn set_mixin_limits(
+        &self,
+        limits: MixinLimits,
+    ) -> anyhow::Result<bool> {
+        let restreams = self.restreams.lock_ref();
+        let mut settings = self.settings.lock_mut();
+        if settings.mixin_limits == limits {
+            return Ok(false);
+        }
+        for o in restreams.iter().flat_map(|r| &r.outputs) {
+            limits
+                .check(o.mixins.iter().map(|m| &m.src))
+                .map_err(|e| anyhow!("Output '{}': {e}", o.dst))?;
+        }
+        settings.mixin_limits = limits;
+        Ok(true)
     }
 
     /// Adds a new [`Restream`] by the given `spec` to this [`State`].
     ///
     /// # Errors
     ///
-    /// If this [`State`] has a [`Restream`] with such `key` already.
+    /// If this [`State`] has a [`Restream`] with such `key` or `label`
+    /// already.
     pub fn add_restream(&self, spec: spec::v1::Restream) -> anyhow::Result<()> {
         let mut restreams = self.restreams.lock_mut();
 
         if restreams.iter().any(|r| r.key == spec.key) {
             return Err(anyhow!("Restream.key '{}' is used already", spec.key));
         }
+        Self::ensure_unique_label(&restreams, spec.label.as_ref(), None)?;
 
         restreams.push(Restream::new(spec));
         Ok(())
     }
 
+    /// Clones a [`Restream`] with the given `id` in this [`State`] under the
+    /// given `key` and `label`, with all its [`Input`]s and [`Output`]s
+    /// getting new IDs.
+    ///
+    /// Returns ID of the newly created [`Restream`], or [`None`] if there is
+    /// no [`Restream`] with such `id` in this [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If this [`State`] has a [`Restream`] with such `key` or `label`
+    /// already.
+    pub fn clone_restream(
+        &self,
+        id: RestreamId,
+        key: RestreamKey,
+        label: Option<Label>,
+    ) -> anyhow::Result<Option<RestreamId>> {
+        let mut restreams = self.restreams.lock_mut();
+
+        if restreams.iter().any(|r| r.key == key) {
+            return Err(anyhow!("Restream.key '{}' is used already", key));
+        }
+        Self::ensure_unique_label(&restreams, label.as_ref(), None)?;
+
+        let orig = match restreams.iter().find(|r| r.id == id) {
+            Some(r) => r.clone(),
+            None => return Ok(None),
+        };
+        let mut spec = orig.export();
+        spec.id = None;
+        spec.key = key;
+        spec.label = label;
+
+        let mut restream = Restream::new(spec);
+        restream.copy_secrets(&orig);
+        let new_id = restream.id;
+        restreams.push(restream);
+        Ok(Some(new_id))
+    }
+
     /// Edits a [`Restream`] with the given `spec` identified by the given `id`
     /// in this [`State`].
     ///
@@ -270,7 +687,8 @@ impl State {
     ///
     /// # Errors
     ///
-    /// If this [`State`] has a [`Restream`] with such `key` already.
+    /// If this [`State`] has another [`Restream`] with such `key` or `label`
+    /// already.
     pub fn edit_restream(
         &self,
         id: RestreamId,
@@ -281,6 +699,7 @@ impl State {
         if restreams.iter().any(|r| r.key == spec.key && r.id != id) {
             return Err(anyhow!("Restream.key '{}' is used already", spec.key));
         }
+        Self::ensure_unique_label(&restreams, spec.label.as_ref(), Some(id))?;
 
         #[allow(clippy::manual_find_map)] // due to consuming `spec`
         Ok(restreams
@@ -289,126 +708,892 @@ impl State {
             .map(|r| r.apply(spec, false)))
     }
 
-    /// Removes a [`Restream`] with the given `id` from this [`State`].
+    /// Sets or removes (if [`None`]) the [`Label`] of the [`Restream`] with
+    /// the given `id` in this [`State`], not touching anything else of it.
     ///
-    /// Returns [`None`] if there is no [`Restream`] with such `id` in this
-    /// [`State`].
-    #[allow(clippy::must_use_candidate)]
-    pub fn remove_restream(&self, id: RestreamId) -> Option<()> {
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// - With [`StateError::NotFound`] if there is no such [`Restream`].
+    /// - With [`StateError::Conflict`] if another [`Restream`] has such
+    ///   `label` already.
+    pub fn rename_restream(
+        &self,
+        id: RestreamId,
+        label: Option<Label>,
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
-        let prev_len = restreams.len();
-        restreams.retain(|r| r.id != id);
-        (restreams.len() != prev_len).then_some(())
+        Self::ensure_unique_label(&restreams, label.as_ref(), Some(id))?;
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        if restream.label == label {
+            return Ok(false);
+        }
+        restream.label = label;
+        Ok(true)
     }
 
-    /// Enables a [`Restream`] with the given `id` in this [`State`].
+    /// Sets the given [`RestreamKey`] on the [`Restream`] with the given `id`
+    /// in this [`State`], kicking its current external publishers (if any),
+    /// as they publish under the previous key.
     ///
-    /// Returns `true` if it has been enabled, or `false` if it already has been
-    /// enabled, or [`None`] if it doesn't exist.
-    #[must_use]
-    pub fn enable_restream(&self, id: RestreamId) -> Option<bool> {
-        self.restreams
-            .lock_mut()
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// - With [`StateError::NotFound`] if there is no such [`Restream`].
+    /// - With [`StateError::Conflict`] if this [`State`] has another
+    ///   [`Restream`] with such `key` already.
+    pub fn change_restream_key(
+        &self,
+        id: RestreamId,
+        key: RestreamKey,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        if restreams.iter().any(|r| r.key == key && r.id != id) {
+            return Err(StateError::Conflict(format!(
+                "Restream.key '{key}' is used already",
+            )));
+        }
+        let restream = restreams
             .iter_mut()
-            .find_map(|r| (r.id == id).then(|| r.input.enable()))
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        if restream.key == key {
+            return Ok(false);
+        }
+        restream.key = key;
+        Self::kick_external_publishers(&mut restream.input);
+        Ok(true)
     }
 
-    /// Disables a [`Restream`] with the given `id` in this [`State`].
+    /// Sets or removes (if [`None`]) the [`RestreamWebhook`] of the
+    /// [`Restream`] with the given `id` in this [`State`].
     ///
-    /// Returns `true` if it has been disabled, or `false` if it already has
-    /// been disabled, or [`None`] if it doesn't exist.
-    #[must_use]
-    pub fn disable_restream(&self, id: RestreamId) -> Option<bool> {
-        self.restreams
-            .lock_mut()
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_webhook(
+        &self,
+        id: RestreamId,
+        webhook: Option<spec::v1::RestreamWebhook>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
             .iter_mut()
-            .find_map(|r| (r.id == id).then(|| r.input.disable()))
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        let webhook =
+            webhook.map(|w| RestreamWebhook::new(w, restream.webhook.as_ref()));
+        if restream.webhook == webhook {
+            return Ok(false);
+        }
+        restream.webhook = webhook;
+        Ok(true)
     }
 
-    /// Enables an [`Input`] with the given `id` in the specified [`Restream`]
-    /// of this [`State`].
+    /// Sets or removes (if [`None`]) the [`RestreamFallback`] of the
+    /// [`Restream`] with the given `id` in this [`State`].
     ///
-    /// Returns `true` if it has been enabled, or `false` if it already has been
-    /// enabled, or [`None`] if it doesn't exist.
-    #[must_use]
-    pub fn enable_input(
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_fallback(
         &self,
-        id: InputId,
-        restream_id: RestreamId,
-    ) -> Option<bool> {
-        self.restreams
-            .lock_mut()
+        id: RestreamId,
+        fallback: Option<spec::v1::RestreamFallback>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
             .iter_mut()
-            .find(|r| r.id == restream_id)?
-            .input
-            .find_mut(id)
-            .map(Input::enable)
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        let fallback = fallback.map(RestreamFallback::new);
+        if restream.fallback == fallback {
+            return Ok(false);
+        }
+        restream.fallback = fallback;
+        Ok(true)
     }
 
-    /// Disables an [`Input`] with the given `id` in the specified [`Restream`]
-    /// of this [`State`].
+    /// Sets or removes (if [`None`]) the [`RestreamIpFilter`] of the
+    /// [`Restream`] with the given `id` in this [`State`].
     ///
-    /// Returns `true` if it has been disabled, or `false` if it already has
-    /// been disabled, or [`None`] if it doesn't exist.
-    #[must_use]
-    pub fn disable_input(
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_ip_filter(
         &self,
-        id: InputId,
-        restream_id: RestreamId,
-    ) -> Option<bool> {
-        self.restreams
-            .lock_mut()
+        id: RestreamId,
+        filter: Option<spec::v1::RestreamIpFilter>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
             .iter_mut()
-            .find(|r| r.id == restream_id)?
-            .input
-            .find_mut(id)
-            .map(Input::disable)
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        let filter = filter.map(RestreamIpFilter::new);
+        if restream.ip_filter == filter {
+            return Ok(false);
+        }
+        restream.ip_filter = filter;
+        Ok(true)
     }
 
-    /// Sets label on [`Input`] with the given `id` in
-    /// the specified [`Restream`] of this [`State`].
+    /// Sets or removes (if [`None`]) the [`RestreamSrsTuning`] of the
+    /// [`Restream`] with the given `id` in this [`State`].
     ///
-    /// Returns `true` if it has been set, or `false` if it already has
-    /// been set, or [`None`] if it doesn't exist.
-    #[must_use]
-    pub fn set_endpoint_label(
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_srs_tuning(
         &self,
-        id: InputId,
-        restream_id: RestreamId,
-        endpoint_id: EndpointId,
-        label: Option<Label>,
-    ) -> Option<bool> {
-        self.restreams
-            .lock_mut()
-            .iter_mut()
-            .find(|r| r.id == restream_id)?
-            .input
-            .find_mut(id)?
-            .endpoints
+        id: RestreamId,
+        tuning: Option<spec::v1::RestreamSrsTuning>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
             .iter_mut()
-            .find(|endpoint| endpoint.id == endpoint_id)
-            .map(|mut ie| {
-                if ie.label == label {
-                    false
-                } else {
-                    ie.label = label;
-                    true
-                }
-            })
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        let tuning = tuning.map(RestreamSrsTuning::new);
+        if restream.srs_tuning == tuning {
+            return Ok(false);
+        }
+        restream.srs_tuning = tuning;
+        Ok(true)
     }
 
-    /// Adds a new [`Output`] to the specified [`Restream`] of this [`State`].
+    /// Sets or removes (if [`None`]) the [`PublishToken`] of the [`Restream`]
+    /// with the given `id` in this [`State`], kicking its current external
+    /// publishers (if any).
     ///
-    /// Returns [`None`] if there is no [`Restream`] with such `id` in this
-    /// [`State`].
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
     ///
     /// # Errors
     ///
-    /// If the [`Restream`] has an [`Output`] with such `dst` already.
-    pub fn add_output(
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_publish_token(
         &self,
-        restream_id: RestreamId,
-        spec: spec::v1::Output,
+        id: RestreamId,
+        token: Option<PublishToken>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        if restream.publish_token == token {
+            return Ok(false);
+        }
+        restream.publish_token = token;
+        Self::kick_external_publishers(&mut restream.input);
+        Ok(true)
+    }
+
+    /// Saves the given [`RestreamTemplate`] in this [`State`], replacing the
+    /// existing one with the same name (if any, ignoring case).
+    ///
+    /// Returns `true` if a new [`RestreamTemplate`] has been added, or
+    /// `false` if the existing one has been replaced.
+    pub fn save_template(&self, template: RestreamTemplate) -> bool {
+        let mut settings = self.settings.lock_mut();
+        if let Some(t) = settings
+            .templates
+            .iter_mut()
+            .find(|t| t.name.eq_ignore_case(&template.name))
+        {
+            *t = template;
+            return false;
+        }
+        settings.templates.push(template);
+        true
+    }
+
+    /// Removes the [`RestreamTemplate`] with the given `name` from this
+    /// [`State`].
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`RestreamTemplate`].
+    pub fn delete_template(&self, name: &Label) -> Result<(), StateError> {
+        let mut settings = self.settings.lock_mut();
+        let prev_len = settings.templates.len();
+        settings.templates.retain(|t| !t.name.eq_ignore_case(name));
+        (settings.templates.len() != prev_len)
+            .then_some(())
+            .ok_or(StateError::NotFound("RestreamTemplate"))
+    }
+
+    /// Merges the [`RestreamTemplate`] with the given `name` into the
+    /// [`Restream`] with the given `id` in this [`State`].
+    ///
+    /// [`Output`]s of the [`RestreamTemplate`] are merged with the existing
+    /// ones by their `dst`, while the rest of the existing [`Output`]s are
+    /// left untouched.
+    ///
+    /// Returns [`None`] if there is no such [`Restream`].
+    ///
+    /// # Errors
+    ///
+    /// If there is no such [`RestreamTemplate`].
+    pub fn apply_template(
+        &self,
+        id: RestreamId,
+        name: &Label,
+    ) -> anyhow::Result<Option<()>> {
+        let template = self
+            .settings
+            .lock_ref()
+            .templates
+            .iter()
+            .find(|t| t.name.eq_ignore_case(name))
+            .map(|t| t.spec.clone())
+            .ok_or_else(|| {
+                anyhow!("RestreamTemplate '{name}' doesn't exist")
+            })?;
+
+        let mut restreams = self.restreams.lock_mut();
+        let restream = match restreams.iter_mut().find(|r| r.id == id) {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+        let mut spec = restream.export();
+        spec.outputs = template.outputs;
+        spec.fallback = template.fallback;
+        spec.ip_filter = template.ip_filter;
+        restream.apply(spec, false);
+        Ok(Some(()))
+    }
+
+    /// Sets or removes (if [`None`]) the [`Restream::idle_timeout`] of the
+    /// [`Restream`] with the given `id` in this [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no such [`Restream`].
+    pub fn set_restream_idle_timeout(
+        &self,
+        id: RestreamId,
+        timeout: Option<i32>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        if restream.idle_timeout == timeout {
+            return Ok(false);
+        }
+        restream.idle_timeout = timeout;
+        Ok(true)
+    }
+
+    /// Generates a new random [`PublishToken`] for the [`Restream`] with the
+    /// given `id` in this [`State`], kicking its current external publishers
+    /// (if any).
+    ///
+    /// Returns the new [`PublishToken`], or [`None`] if there is no such
+    /// [`Restream`].
+    #[must_use]
+    pub fn rotate_restream_publish_token(
+        &self,
+        id: RestreamId,
+    ) -> Option<PublishToken> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == id)?;
+        let token = PublishToken::random();
+        restream.publish_token = Some(token.clone());
+        Self::kick_external_publishers(&mut restream.input);
+        Some(token)
+    }
+
+    /// Checks the given `label` not to be used (ignoring case) by any of the
+    /// given [`Restream`]s, except the one with the given `id`.
+    ///
+    /// The [`Restream`] with the given `id` keeping its current `label`
+    /// always passes, so legacy duplicates don't prevent editing it.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::Conflict`] if the `label` is used already.
+    fn ensure_unique_label(
+        restreams: &[Restream],
+        label: Option<&Label>,
+        id: Option<RestreamId>,
+    ) -> Result<(), StateError> {
+        let label = match label {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        let has_label = |r: &Restream| {
+            r.label.as_ref().map_or(false, |l| l.eq_ignore_case(label))
+        };
+
+        let keeps_label = id
+            .and_then(|id| restreams.iter().find(|r| r.id == id))
+            .map_or(false, &has_label);
+        if keeps_label
+            || !restreams.iter().any(|r| Some(r.id) != id && has_label(r))
+        {
+            return Ok(());
+        }
+        Err(StateError::Conflict(format!(
+            "Restream.label '{label}' is used already",
+        )))
+    }
+
+    /// Kicks publishers of the given [`Input`] and all its failover
+    /// [`Input`]s, which are pushed by external clients.
+    fn kick_external_publishers(input: &mut Input) {
+        match input.src.as_mut() {
+            None => {
+                for e in &mut input.endpoints {
+                    // `srs::ClientId` kicks the client when `Drop`ped.
+                    e.srs_publisher_id = None;
+                }
+            }
+            Some(InputSrc::Failover(s)) => {
+                for i in &mut s.inputs {
+                    Self::kick_external_publishers(i);
+                }
+            }
+            Some(InputSrc::Remote(_) | InputSrc::Playlist(_)) => {}
+        }
+    }
+
+    /// Removes a [`Restream`] with the given `id` from this [`State`].
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Restream`] with such `id`
+    /// in this [`State`].
+    pub fn remove_restream(&self, id: RestreamId) -> Result<(), StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let prev_len = restreams.len();
+        restreams.retain(|r| r.id != id);
+        (restreams.len() != prev_len)
+            .then_some(())
+            .ok_or(StateError::NotFound("Restream"))
+    }
+
+    /// Enables a [`Restream`] with the given `id` in this [`State`].
+    ///
+    /// Returns `true` if it has been enabled, or `false` if it has been
+    /// enabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn enable_restream(
+        &self,
+        id: RestreamId,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        Ok(restream.input.enable())
+    }
+
+    /// Disables a [`Restream`] with the given `id` in this [`State`].
+    ///
+    /// Returns `true` if it has been disabled, or `false` if it has been
+    /// disabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn disable_restream(
+        &self,
+        id: RestreamId,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        Ok(restream.input.disable())
+    }
+
+    /// Enables an [`Input`] with the given `id` in the specified [`Restream`]
+    /// of this [`State`].
+    ///
+    /// Returns `true` if it has been enabled, or `false` if it has been
+    /// enabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn enable_input(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+        Ok(input.enable())
+    }
+
+    /// Sets [`InputTuning`] options of an [`Input`] with the given `id` in the
+    /// specified [`Restream`] of this [`State`].
+    ///
+    /// Returns `true` if they have been changed, or `false` if they are the
+    /// same already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if the [`Input`] doesn't exist.
+    pub fn set_input_tuning(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        tuning: Option<InputTuning>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+
+        if input.tuning == tuning {
+            return Ok(false);
+        }
+
+        input.tuning = tuning;
+        Ok(true)
+    }
+
+    /// Sets the [`Input::priority`] of an [`Input`] with the given `id` in the
+    /// specified [`Restream`] of this [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it is the same
+    /// already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if the [`Input`] doesn't exist.
+    pub fn set_input_priority(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        priority: i32,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+
+        if input.priority == priority {
+            return Ok(false);
+        }
+
+        input.priority = priority;
+        Ok(true)
+    }
+
+    /// Moves an [`Input`] with the given `id` to the given `position` among
+    /// [`FailoverInputSrc::inputs`] of the specified [`Restream`] of this
+    /// [`State`].
+    ///
+    /// The `position` exceeding the number of [`FailoverInputSrc::inputs`]
+    /// moves the [`Input`] to the end.
+    ///
+    /// Returns `true` if it has been moved, or `false` if it is at the given
+    /// `position` already.
+    ///
+    /// # Errors
+    ///
+    /// - With [`StateError::NotFound`] if the [`Input`] doesn't exist.
+    /// - With [`StateError::Conflict`] if the [`Restream`] doesn't pull from
+    ///   a [`FailoverInputSrc`].
+    pub fn move_failover_input(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        position: usize,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?;
+        let inputs = match restream.input.src.as_mut() {
+            Some(InputSrc::Failover(s)) => &mut s.inputs,
+            _ => {
+                return Err(StateError::Conflict(
+                    "Restream doesn't pull from a failover source".into(),
+                ))
+            }
+        };
+        let from = inputs
+            .iter()
+            .position(|i| i.id == id)
+            .ok_or(StateError::NotFound("Input"))?;
+
+        let to = position.min(inputs.len() - 1);
+        if from == to {
+            return Ok(false);
+        }
+
+        let input = inputs.remove(from);
+        inputs.insert(to, input);
+        Ok(true)
+    }
+
+    /// Sets a [`HlsVariantSelector`] of the [`RemoteInputSrc`] of an [`Input`]
+    /// with the given `id` in the specified [`Restream`] of this [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// - With [`StateError::NotFound`] if the [`Input`] doesn't exist.
+    /// - With [`StateError::Conflict`] if the [`Input`] doesn't pull a live
+    ///   stream from a [`RemoteInputSrc`].
+    pub fn set_input_hls_variant(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        selector: Option<HlsVariantSelector>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+        let remote = match input.src.as_mut() {
+            Some(InputSrc::Remote(remote)) => remote,
+            _ => {
+                return Err(StateError::Conflict(
+                    "Input doesn't pull from a remote source".into(),
+                ))
+            }
+        };
+
+        if remote.hls_variant == selector {
+            return Ok(false);
+        }
+
+        remote.hls_variant = selector;
+        Ok(true)
+    }
+
+    /// Sets an [`FfmpegLogLevel`] of the specified [`Input`] in this
+    /// [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`Input`] exists.
+    pub fn set_input_log_level(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        log_level: Option<FfmpegLogLevel>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+
+        if input.log_level == log_level {
+            return Ok(false);
+        }
+
+        input.log_level = log_level;
+        Ok(true)
+    }
+
+    /// Sets an [`FfmpegLogLevel`] of the specified [`Output`] in this
+    /// [`State`].
+    ///
+    /// Returns `true` if it has been changed, or `false` if it has the same
+    /// value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`Output`] exists.
+    pub fn set_output_log_level(
+        &self,
+        id: OutputId,
+        restream_id: RestreamId,
+        log_level: Option<FfmpegLogLevel>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == id)
+            .ok_or(StateError::NotFound("Output"))?;
+
+        if output.log_level == log_level {
+            return Ok(false);
+        }
+
+        output.log_level = log_level;
+        Ok(true)
+    }
+
+    /// Disables an [`Input`] with the given `id` in the specified [`Restream`]
+    /// of this [`State`].
+    ///
+    /// Returns `true` if it has been disabled, or `false` if it has been
+    /// disabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn disable_input(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?;
+        Ok(input.disable())
+    }
+
+    /// Kicks the publisher of the [`InputEndpoint`] with the given
+    /// `endpoint_id` of the specified [`Input`] in the specified [`Restream`]
+    /// of this [`State`], along with all its players, without disabling the
+    /// [`Input`].
+    ///
+    /// Returns `true` if the publisher has been kicked, or `false` if there is
+    /// no publisher.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if the [`InputEndpoint`] doesn't exist.
+    pub fn kick_publisher(
+        &self,
+        endpoint_id: EndpointId,
+        input_id: InputId,
+        restream_id: RestreamId,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let endpoint = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(input_id)
+            .ok_or(StateError::NotFound("Input"))?
+            .endpoints
+            .iter_mut()
+            .find(|e| e.id == endpoint_id)
+            .ok_or(StateError::NotFound("InputEndpoint"))?;
+        if endpoint.srs_publisher_id.is_none() {
+            return Ok(false);
+        }
+        // `srs::ClientId` kicks the client when `Drop`ped.
+        endpoint.srs_publisher_id = None;
+        endpoint.srs_player_ids.clear();
+        // Do not rely only on SRS to set status, as it sporadically races.
+        endpoint.set_status(Status::Offline, Some("Publisher kicked"));
+        Ok(true)
+    }
+
+    /// Forcibly sets the given `status` of the [`InputEndpoint`] with the given
+    /// `id` in the specified [`Restream`] of this [`State`].
+    ///
+    /// Intended for simulating failures only, as the status is overwritten
+    /// once the real one changes.
+    ///
+    /// Returns `true` if it has been set, or `false` if it already has been
+    /// set.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn set_endpoint_status(
+        &self,
+        id: EndpointId,
+        restream_id: RestreamId,
+        status: Status,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let endpoint = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_endpoint(id)
+            .ok_or(StateError::NotFound("InputEndpoint"))?;
+        if endpoint.status == status {
+            return Ok(false);
+        }
+        endpoint.set_status(status, Some("Set manually"));
+        Ok(true)
+    }
+
+    /// Sets label on [`Input`] with the given `id` in
+    /// the specified [`Restream`] of this [`State`].
+    ///
+    /// Returns `true` if it has been set, or `false` if it already has been
+    /// set.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
+    pub fn set_endpoint_label(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        endpoint_id: EndpointId,
+        label: Option<Label>,
+    ) -> Result<Changed, StateError> {
+        self.restreams
+            .lock_mut()
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?
+            .endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.id == endpoint_id)
+            .ok_or(StateError::NotFound("InputEndpoint"))
+            .map(|mut ie| {
+                if ie.label == label {
+                    false
+                } else {
+                    ie.label = label;
+                    true
+                }
+            })
+    }
+
+    /// Sets a new random [`EndpointKey`] on the [`InputEndpoint`] with the
+    /// given `endpoint_id` in the specified [`Input`] of this [`State`],
+    /// kicking its current external publisher (if any).
+    ///
+    /// Returns the new [`EndpointKey`], or [`None`] if no such
+    /// [`InputEndpoint`] exists.
+    #[must_use]
+    pub fn rotate_endpoint_key(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        endpoint_id: EndpointId,
+    ) -> Option<EndpointKey> {
+        let mut restreams = self.restreams.lock_mut();
+        let input = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)?
+            .input
+            .find_mut(id)?;
+        let is_push = input.src.is_none();
+        let endpoint =
+            input.endpoints.iter_mut().find(|e| e.id == endpoint_id)?;
+
+        let key = EndpointKey::random();
+        endpoint.secret_key = Some(key.clone());
+        if is_push {
+            // `srs::ClientId` kicks the client when `Drop`ped.
+            endpoint.srs_publisher_id = None;
+        }
+        Some(key)
+    }
+
+    /// Removes [`EndpointKey`] from the [`InputEndpoint`] with the given
+    /// `endpoint_id` in the specified [`Input`] of this [`State`], so a live
+    /// stream may be published to it without any key.
+    ///
+    /// Returns `true` if it has been removed, or `false` if it has been removed
+    /// already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`InputEndpoint`] exists.
+    pub fn remove_endpoint_key(
+        &self,
+        id: InputId,
+        restream_id: RestreamId,
+        endpoint_id: EndpointId,
+    ) -> Result<Changed, StateError> {
+        self.restreams
+            .lock_mut()
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .input
+            .find_mut(id)
+            .ok_or(StateError::NotFound("Input"))?
+            .endpoints
+            .iter_mut()
+            .find(|e| e.id == endpoint_id)
+            .ok_or(StateError::NotFound("InputEndpoint"))
+            .map(|e| e.secret_key.take().is_some())
+    }
+
+    /// Adds a new [`Output`] to the specified [`Restream`] of this [`State`].
+    ///
+    /// Returns [`None`] if there is no [`Restream`] with such `id` in this
+    /// [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`Restream`] has an [`Output`] with such `dst` already.
+    pub fn add_output(
+        &self,
+        restream_id: RestreamId,
+        spec: spec::v1::Output,
     ) -> anyhow::Result<Option<()>> {
         let mut restreams = self.restreams.lock_mut();
 
@@ -428,6 +1613,52 @@ impl State {
         Ok(Some(()))
     }
 
+    /// Clones an [`Output`] with the given `id` in the specified [`Restream`]
+    /// of this [`State`] to re-stream onto the given `dst`, with all its
+    /// [`Mixin`]s getting new IDs.
+    ///
+    /// Returns ID of the newly created [`Output`], or [`None`] if there is no
+    /// [`Restream`] with such `restream_id` in this [`State`], or there is no
+    /// [`Output`] with such `id`.
+    ///
+    /// # Errors
+    ///
+    /// If the [`Restream`] has an [`Output`] with such `dst` already.
+    pub fn clone_output(
+        &self,
+        restream_id: RestreamId,
+        id: OutputId,
+        dst: OutputDstUrl,
+    ) -> anyhow::Result<Option<OutputId>> {
+        let mut restreams = self.restreams.lock_mut();
+
+        let outputs = if let Some(r) =
+            restreams.iter_mut().find(|r| r.id == restream_id)
+        {
+            &mut r.outputs
+        } else {
+            return Ok(None);
+        };
+
+        if outputs.iter().any(|o| o.dst == dst) {
+            return Err(anyhow!("Output.dst '{}' is used already", dst));
+        }
+
+        let orig = match outputs.iter().find(|o| o.id == id) {
+            Some(o) => o.clone(),
+            None => return Ok(None),
+        };
+        let mut spec = orig.export();
+        spec.id = None;
+        spec.dst = dst;
+
+        let mut output = Output::new(spec);
+        output.copy_secrets(&orig);
+        let new_id = output.id;
+        outputs.push(output);
+        Ok(Some(new_id))
+    }
+
     /// Edits an [`Output`] with the given `spec` identified by the given `id`
     /// in the specified [`Restream`] of this [`State`].
     ///
@@ -467,75 +1698,101 @@ impl State {
     /// Removes an [`Output`] with the given `id` from the specified
     /// [`Restream`] of this [`State`].
     ///
-    /// Returns [`None`] if there is no [`Restream`] with such `restream_id` or
-    /// no [`Output`] with such `id` in this [`State`].
-    #[must_use]
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if there is no [`Restream`] with such
+    /// `restream_id` or no [`Output`] with such `id` in this [`State`].
     pub fn remove_output(
         &self,
         id: OutputId,
         restream_id: RestreamId,
-    ) -> Option<()> {
+    ) -> Result<(), StateError> {
         let mut restreams = self.restreams.lock_mut();
-        let outputs =
-            &mut restreams.iter_mut().find(|r| r.id == restream_id)?.outputs;
+        let outputs = &mut restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs;
 
         let prev_len = outputs.len();
         outputs.retain(|o| o.id != id);
-        (outputs.len() != prev_len).then_some(())
+        (outputs.len() != prev_len)
+            .then_some(())
+            .ok_or(StateError::NotFound("Output"))
     }
 
     /// Enables an [`Output`] with the given `id` in the specified [`Restream`]
     /// of this [`State`].
     ///
-    /// Returns `true` if it has been enabled, or `false` if it already has been
-    /// enabled, or [`None`] if it doesn't exist.
-    #[must_use]
+    /// Returns `true` if it has been enabled, or `false` if it has been
+    /// enabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
     pub fn enable_output(
         &self,
         id: OutputId,
         restream_id: RestreamId,
-    ) -> Option<bool> {
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
         let output = restreams
             .iter_mut()
-            .find(|r| r.id == restream_id)?
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
             .outputs
             .iter_mut()
-            .find(|o| o.id == id)?;
+            .find(|o| o.id == id)
+            .ok_or(StateError::NotFound("Output"))?;
 
         if output.enabled {
-            return Some(false);
+            return Ok(false);
         }
 
         output.enabled = true;
-        Some(true)
+        Ok(true)
     }
 
     /// Disables an [`Output`] with the given `id` in the specified [`Restream`]
     /// of this [`State`].
     ///
-    /// Returns `true` if it has been disabled, or `false` if it already has
-    /// been disabled, or [`None`] if it doesn't exist.
-    #[must_use]
+    /// Returns `true` if it has been disabled, or `false` if it has been
+    /// disabled already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if it doesn't exist.
     pub fn disable_output(
         &self,
         id: OutputId,
         restream_id: RestreamId,
-    ) -> Option<bool> {
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
         let output = restreams
             .iter_mut()
-            .find(|r| r.id == restream_id)?
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
             .outputs
             .iter_mut()
-            .find(|o| o.id == id)?;
+            .find(|o| o.id == id)
+            .ok_or(StateError::NotFound("Output"))?;
 
         if !output.enabled {
-            return Some(false);
+            return Ok(false);
         }
 
         output.enabled = false;
-        Some(true)
+        Ok(true)
+    }
+
+    /// Get [Restream] by its `id`
+    #[must_use]
+    pub fn get_restream(&self, id: RestreamId) -> Option<Restream> {
+        self.restreams
+            .lock_ref()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned()
     }
 
     /// Get [Output] from [Restream] by `restream_id` and `output_id`
@@ -554,13 +1811,64 @@ impl State {
             .find(|o| o.id == output_id)
     }
 
+    /// Returns an [`Output`] with the given `output_id` from any [`Restream`]
+    /// of this [`State`].
+    #[must_use]
+    pub fn find_output(&self, output_id: OutputId) -> Option<Output> {
+        self.restreams
+            .lock_ref()
+            .iter()
+            .find_map(|r| r.outputs.iter().find(|o| o.id == output_id))
+            .cloned()
+    }
+
+    /// Sheds (temporarily stops) an enabled [`Output`] with the lowest
+    /// [`Output::priority`] among all [`Restream`]s of this [`State`].
+    ///
+    /// Returns ID of the shed [`Output`], or [`None`] if there is nothing to
+    /// shed.
+    #[must_use]
+    pub fn shed_output(&self) -> Option<OutputId> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .flat_map(|r| r.outputs.iter_mut())
+            .filter(|o| o.enabled && !o.shed)
+            .min_by_key(|o| o.priority)?;
+        output.shed = true;
+        Some(output.id)
+    }
+
+    /// Resumes a shed [`Output`] with the highest [`Output::priority`] among
+    /// all [`Restream`]s of this [`State`].
+    ///
+    /// Returns ID of the resumed [`Output`], or [`None`] if there is nothing
+    /// to resume.
+    #[must_use]
+    pub fn resume_shed_output(&self) -> Option<OutputId> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .flat_map(|r| r.outputs.iter_mut())
+            .filter(|o| o.shed)
+            .max_by_key(|o| o.priority)?;
+        output.shed = false;
+        Some(output.id)
+    }
+
     /// Enables all [`Output`]s in the specified [`Restream`] of this [`State`].
     ///
     /// Returns `true` if at least one [`Output`] has been enabled, or `false`
-    /// if all of them already have been enabled, or [`None`] if no [`Restream`]
-    /// with such `restream_id` exists.
-    #[must_use]
-    pub fn enable_all_outputs(&self, restream_id: RestreamId) -> Option<bool> {
+    /// if all of them already have been enabled.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no [`Restream`] with such `restream_id`
+    /// exists.
+    pub fn enable_all_outputs(
+        &self,
+        restream_id: RestreamId,
+    ) -> Result<Changed, StateError> {
         self.set_state_of_all_outputs(restream_id, true)
     }
 
@@ -568,10 +1876,16 @@ impl State {
     /// [`State`].
     ///
     /// Returns `true` if at least one [`Output`] has been disabled, or `false`
-    /// if all of them already have been disabled, or [`None`] if no
-    /// [`Restream`] with such `restream_id` exists.
-    #[must_use]
-    pub fn disable_all_outputs(&self, restream_id: RestreamId) -> Option<bool> {
+    /// if all of them already have been disabled.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no [`Restream`] with such `restream_id`
+    /// exists.
+    pub fn disable_all_outputs(
+        &self,
+        restream_id: RestreamId,
+    ) -> Result<Changed, StateError> {
         self.set_state_of_all_outputs(restream_id, false)
     }
 
@@ -596,38 +1910,134 @@ impl State {
     /// Tunes a [`Volume`] rate of the specified [`Output`] or its [`Mixin`] in
     /// this [`State`].
     ///
+    /// The change is applied gradually over the given `fade` duration, if
+    /// it's not zero.
+    ///
     /// Returns `true` if a [`Volume`] rate has been changed, or `false` if it
     /// has the same value already.
     ///
-    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
-    #[must_use]
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such
+    /// [`Restream`]/[`Output`]/[`Mixin`] exists.
     pub fn tune_volume(
         &self,
         restream_id: RestreamId,
         output_id: OutputId,
         mixin_id: Option<MixinId>,
         volume: Volume,
-    ) -> Option<bool> {
+        fade: Delay,
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
         let output = restreams
             .iter_mut()
-            .find(|r| r.id == restream_id)?
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
             .outputs
             .iter_mut()
-            .find(|o| o.id == output_id)?;
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?;
 
-        let curr_volume = if let Some(id) = mixin_id {
-            &mut output.mixins.iter_mut().find(|m| m.id == id)?.volume
+        let (curr_volume, curr_fade) = if let Some(id) = mixin_id {
+            let mixin = output
+                .mixins
+                .iter_mut()
+                .find(|m| m.id == id)
+                .ok_or(StateError::NotFound("Mixin"))?;
+            (&mut mixin.volume, &mut mixin.volume_fade)
         } else {
-            &mut output.volume
+            (&mut output.volume, &mut output.volume_fade)
         };
 
         if *curr_volume == volume {
-            return Some(false);
+            return Ok(false);
         }
 
         *curr_volume = volume;
-        Some(true)
+        *curr_fade = fade;
+        output.mix_revision += 1;
+        Ok(true)
+    }
+
+    /// Tunes [`Volume`]s of all the [`Mixin`]s, whose [`Mixin::src`] matches
+    /// the given `pattern`, of all the [`Output`]s of the specified
+    /// [`Restream`] in this [`State`] at once.
+    ///
+    /// If no `pattern` is given, then tunes [`Output::volume`]s instead.
+    ///
+    /// Returns the number of [`Output`]s/[`Mixin`]s whose [`Volume`] has been
+    /// changed.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`Restream`] exists.
+    pub fn tune_volume_all(
+        &self,
+        restream_id: RestreamId,
+        pattern: Option<&Regex>,
+        volume: &Volume,
+        fade: Delay,
+    ) -> Result<i32, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?;
+
+        let mut changed = 0;
+        for output in &mut restream.outputs {
+            let mut is_changed = false;
+            let volumes: Vec<_> = if let Some(p) = pattern {
+                output
+                    .mixins
+                    .iter_mut()
+                    .filter(|m| p.is_match(m.src.as_str()))
+                    .map(|m| (&mut m.volume, &mut m.volume_fade))
+                    .collect()
+            } else {
+                vec![(&mut output.volume, &mut output.volume_fade)]
+            };
+            for (curr_volume, curr_fade) in volumes {
+                if *curr_volume != *volume {
+                    *curr_volume = volume.clone();
+                    *curr_fade = fade;
+                    changed += 1;
+                    is_changed = true;
+                }
+            }
+            if is_changed {
+                output.mix_revision += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Sets a [`Status`] of the specified [`Mixin`] in this [`State`].
+    ///
+    /// Returns `true` if the [`Status`] has been changed, or `false` if it has
+    /// the same value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`Mixin`] exists.
+    pub fn set_mixin_status(
+        &self,
+        mixin_id: MixinId,
+        status: Status,
+        reason: Option<&str>,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let mixin = restreams
+            .iter_mut()
+            .flat_map(|r| r.outputs.iter_mut())
+            .flat_map(|o| o.mixins.iter_mut())
+            .find(|m| m.id == mixin_id)
+            .ok_or(StateError::NotFound("Mixin"))?;
+        if mixin.status == status {
+            return Ok(false);
+        }
+        mixin.set_status(status, reason);
+        Ok(true)
     }
 
     /// Tunes a [`Delay`] of the specified [`Mixin`] in this [`State`].
@@ -635,32 +2045,78 @@ impl State {
     /// Returns `true` if a [`Delay`] has been changed, or `false` if it has the
     /// same value already.
     ///
-    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
-    #[must_use]
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such
+    /// [`Restream`]/[`Output`]/[`Mixin`] exists.
     pub fn tune_delay(
         &self,
         input_id: RestreamId,
         output_id: OutputId,
         mixin_id: MixinId,
         delay: Delay,
-    ) -> Option<bool> {
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
-        let mixin = restreams
+        let output = restreams
             .iter_mut()
-            .find(|r| r.id == input_id)?
+            .find(|r| r.id == input_id)
+            .ok_or(StateError::NotFound("Restream"))?
             .outputs
             .iter_mut()
-            .find(|o| o.id == output_id)?
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?;
+        let mixin = output
             .mixins
             .iter_mut()
-            .find(|m| m.id == mixin_id)?;
+            .find(|m| m.id == mixin_id)
+            .ok_or(StateError::NotFound("Mixin"))?;
 
         if mixin.delay == delay {
-            return Some(false);
+            return Ok(false);
         }
 
         mixin.delay = delay;
-        Some(true)
+        output.mix_revision += 1;
+        Ok(true)
+    }
+
+    /// Sets a new [`Mixin::src`] of the specified [`Mixin`] in this [`State`],
+    /// preserving all its other settings.
+    ///
+    /// Returns `true` if the [`Mixin::src`] has been changed, or `false` if it
+    /// has the same value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such
+    /// [`Restream`]/[`Output`]/[`Mixin`] exists.
+    pub fn set_mixin_src(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        src: MixinSrcUrl,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let mixin = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?
+            .mixins
+            .iter_mut()
+            .find(|m| m.id == mixin_id)
+            .ok_or(StateError::NotFound("Mixin"))?;
+
+        if mixin.src == src {
+            return Ok(false);
+        }
+
+        mixin.src = src;
+        Ok(true)
     }
 
     /// Tunes a the specified [`Mixin.sidechain`] in this [`State`].
@@ -668,32 +2124,199 @@ impl State {
     /// Returns `true` if a [`Mixin.sidechain`] has been changed, or `false`
     /// if it has the same value already.
     ///
-    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
-    #[must_use]
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such
+    /// [`Restream`]/[`Output`]/[`Mixin`] exists.
     pub fn tune_sidechain(
         &self,
         input_id: RestreamId,
         output_id: OutputId,
         mixin_id: MixinId,
         sidechain: bool,
-    ) -> Option<bool> {
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
-        let mixin = restreams
+        let output = restreams
             .iter_mut()
-            .find(|r| r.id == input_id)?
+            .find(|r| r.id == input_id)
+            .ok_or(StateError::NotFound("Restream"))?
             .outputs
             .iter_mut()
-            .find(|o| o.id == output_id)?
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?;
+        let mixin = output
             .mixins
             .iter_mut()
-            .find(|m| m.id == mixin_id)?;
+            .find(|m| m.id == mixin_id)
+            .ok_or(StateError::NotFound("Mixin"))?;
 
         if mixin.sidechain == sidechain {
-            return Some(false);
+            return Ok(false);
         }
 
         mixin.sidechain = sidechain;
-        Some(true)
+        output.mix_revision += 1;
+        Ok(true)
+    }
+
+    /// Tunes [`Mixin::sidechain_params`] of the specified [`Mixin`] in this
+    /// [`State`].
+    ///
+    /// Returns `true` if [`Mixin::sidechain_params`] have been changed, or
+    /// `false` if they have the same values already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such
+    /// [`Restream`]/[`Output`]/[`Mixin`] exists.
+    pub fn tune_sidechain_params(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        params: MixinSidechainParams,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?;
+        let mixin = output
+            .mixins
+            .iter_mut()
+            .find(|m| m.id == mixin_id)
+            .ok_or(StateError::NotFound("Mixin"))?;
+
+        if mixin.sidechain_params == params {
+            return Ok(false);
+        }
+
+        mixin.sidechain_params = params;
+        output.mix_revision += 1;
+        Ok(true)
+    }
+
+    /// Tunes [`Output::av_offset`] of the specified [`Output`] in this
+    /// [`State`].
+    ///
+    /// Returns `true` if [`Output::av_offset`] has been changed, or `false` if
+    /// it has the same value already.
+    ///
+    /// # Errors
+    ///
+    /// With [`StateError::NotFound`] if no such [`Restream`]/[`Output`] exists.
+    pub fn tune_av_offset(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        av_offset: i32,
+    ) -> Result<Changed, StateError> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)
+            .ok_or(StateError::NotFound("Output"))?;
+
+        if output.av_offset == av_offset {
+            return Ok(false);
+        }
+
+        output.av_offset = av_offset;
+        output.mix_revision += 1;
+        Ok(true)
+    }
+
+    /// Sets the given [`ServerInfo`] as the current one, recording it into
+    /// the [`State::server_info_history`].
+    ///
+    /// Samples older than [`ServerInfoSample::HISTORY_PERIOD`] are evicted.
+    pub fn record_server_info(&self, info: ServerInfo) {
+        let now = Utc::now();
+        let since = now
+            - chrono::Duration::from_std(ServerInfoSample::HISTORY_PERIOD)
+                .unwrap_or_else(|_| chrono::Duration::zero());
+        {
+            let mut history = self.server_info_history.lock_mut();
+            while history.front().map_or(false, |s| s.at < since) {
+                let _ = history.pop_front();
+            }
+            history.push_back(ServerInfoSample {
+                at: now,
+                info: info.clone(),
+            });
+        }
+        *self.server_info.lock_mut() = info;
+    }
+
+    /// Returns [`ServerInfoSample`]s taken during the given latest `period`,
+    /// ordered from the oldest to the newest.
+    #[must_use]
+    pub fn server_info_since(&self, period: Duration) -> Vec<ServerInfoSample> {
+        let since = Utc::now()
+            - chrono::Duration::from_std(period)
+                .unwrap_or_else(|_| chrono::Duration::max_value());
+        self.server_info_history
+            .lock_ref()
+            .iter()
+            .filter(|s| s.at >= since)
+            .cloned()
+            .collect()
+    }
+
+    /// Attaches the given [`ProcessUsage`]s (identified by IDs of the
+    /// [`InputEndpoint`]s and [`Output`]s served by the processes) to the
+    /// [`Restream`]s of this [`State`].
+    ///
+    /// [`InputEndpoint`]s and [`Output`]s missing in the given `usages` have
+    /// their [`ProcessUsage`] removed.
+    pub fn set_process_usages(&self, usages: &HashMap<Uuid, ProcessUsage>) {
+        /// Visits all the [`InputEndpoint`]s of the given [`Input`] and its
+        /// failover [`Input`]s.
+        fn visit_endpoints<F: FnMut(&mut InputEndpoint)>(
+            input: &mut Input,
+            f: &mut F,
+        ) {
+            input.endpoints.iter_mut().for_each(&mut *f);
+            if let Some(InputSrc::Failover(s)) = &mut input.src {
+                for i in &mut s.inputs {
+                    visit_endpoints(i, f);
+                }
+            }
+        }
+
+        let mut restreams = self.restreams.lock_mut();
+        for r in restreams.iter_mut() {
+            visit_endpoints(&mut r.input, &mut |e| {
+                e.process_usage = usages.get(&Uuid::from(e.id)).copied();
+            });
+            for o in &mut r.outputs {
+                o.process_usage = usages.get(&Uuid::from(o.id)).copied();
+            }
+        }
+    }
+
+    /// Accumulates the given numbers of bytes pushed (identified by IDs of
+    /// the [`Output`]s they're pushed to) at the given moment (`now`) into
+    /// the [`OutputTraffic`] of the [`Output`]s in this [`State`].
+    pub fn add_outputs_traffic(
+        &self,
+        pushed: &HashMap<Uuid, u64>,
+        now: DateTime<Utc>,
+    ) {
+        let mut restreams = self.restreams.lock_mut();
+        for o in restreams.iter_mut().flat_map(|r| r.outputs.iter_mut()) {
+            if let Some(bytes) = pushed.get(&Uuid::from(o.id)) {
+                o.traffic.add(*bytes, now);
+            }
+        }
     }
 
     /// Clean up stream statistics info
@@ -732,6 +2355,67 @@ impl State {
         Ok(())
     }
 
+    /// Looks up the [`InputEndpoint`] identified by the given IDs in a
+    /// [`Restream`] visible to the given `tenant`, and being published at the
+    /// moment.
+    ///
+    /// Returns its [`StreamStatistics`] cached for the current publishing
+    /// session (if any), along with an [URL] to probe its live stream and the
+    /// [SRS] client publishing it.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [URL]: https://en.wikipedia.org/wiki/URL
+    #[must_use]
+    pub fn stream_info_of(
+        &self,
+        restream_id: RestreamId,
+        input_id: InputId,
+        endpoint_id: EndpointId,
+        tenant: Option<&TenantKey>,
+    ) -> Option<(Option<StreamStatistics>, Url, srs::ClientId)> {
+        let restreams = self.restreams.lock_ref();
+        let restream = restreams
+            .iter()
+            .find(|r| r.id == restream_id && r.is_visible_to(tenant))?;
+        let input = restream.input.find(input_id)?;
+        let endpoint = input.endpoints.iter().find(|e| e.id == endpoint_id)?;
+        if endpoint.status != Status::Online {
+            return None;
+        }
+        let publisher = endpoint.srs_publisher_id.clone()?;
+        let url = InputEndpointKind::get_rtmp_url(
+            &restream.srs_app(),
+            &input.key,
+            endpoint.kind,
+        );
+        Some((endpoint.stream_stat.clone(), url, publisher))
+    }
+
+    /// Caches the given [`StreamStatistics`] for the [`InputEndpoint`] with
+    /// the given `id`, if it's still published by the given [SRS] client.
+    ///
+    /// Returns `false` if the publishing session has changed meanwhile, so
+    /// the [`StreamStatistics`] are outdated.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn cache_stream_info(
+        &self,
+        id: EndpointId,
+        publisher: &srs::ClientId,
+        stat: StreamStatistics,
+    ) -> bool {
+        let mut restreams = self.restreams.lock_mut();
+        let endpoint = match restreams
+            .iter_mut()
+            .find_map(|r| r.input.find_endpoint(id))
+        {
+            Some(e) if e.srs_publisher_id.as_ref() == Some(publisher) => e,
+            _ => return false,
+        };
+        endpoint.stream_stat = Some(stat);
+        true
+    }
+
     /// Gather statistics about [`Input`]s statuses
     #[must_use]
     pub fn get_inputs_statistics(&self) -> Vec<StatusStatistics> {
@@ -780,14 +2464,41 @@ impl State {
             .collect()
     }
 
+    /// Gather [`Output`]s restarted within the last 24 hours, the most
+    /// restarted first
+    #[must_use]
+    pub fn get_flapping_outputs(&self) -> Vec<FlappingOutput> {
+        let mut flapping: Vec<_> = self
+            .restreams
+            .get_cloned()
+            .into_iter()
+            .flat_map(|r| r.outputs.into_iter())
+            .filter_map(|o| {
+                let restarts = o.counters.restarts_count();
+                // Statistics are exposed without authentication, so no
+                // secrets of the destination should leak there.
+                let dst = o.dst.masked();
+                let last_error = o
+                    .counters
+                    .last_error
+                    .map(|e| e.replace(o.dst.as_str(), &dst));
+                (restarts > 0).then(|| FlappingOutput {
+                    label: o.label.map(Into::into),
+                    dst,
+                    restarts: i32::try_from(restarts).unwrap_or(i32::MAX),
+                    last_error,
+                })
+            })
+            .collect();
+        flapping.sort_by(|a, b| b.restarts.cmp(&a.restarts));
+        flapping
+    }
+
     /// Statistics for statuses of this [`Client`]
     #[must_use]
     pub fn get_statistics(&self) -> ClientStatistics {
         let settings = self.settings.get_cloned();
-        let title = match settings.title {
-            Some(t) => t,
-            None => String::new(),
-        };
+        let title = settings.title.map(Into::into).unwrap_or_default();
 
         let inputs_stat = self.get_inputs_statistics();
         let outputs_stat = self.get_outputs_statistics();
@@ -796,9 +2507,40 @@ impl State {
             inputs_stat,
             outputs_stat,
             self.server_info.lock_mut().clone(),
+            self.get_flapping_outputs(),
+            self.get_endpoints_usage(),
         )
     }
 
+    /// Gather usage of all [`InputEndpoint`]s by [SRS] publishers and players
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn get_endpoints_usage(&self) -> EndpointsUsage {
+        fn collect(input: &Input, usage: &mut EndpointsUsage) {
+            for e in &input.endpoints {
+                let players =
+                    i32::try_from(e.srs_player_ids.len()).unwrap_or(i32::MAX);
+                usage.publishers_count = usage
+                    .publishers_count
+                    .saturating_add(i32::from(e.srs_publisher_id.is_some()));
+                usage.players_count =
+                    usage.players_count.saturating_add(players);
+            }
+            if let Some(InputSrc::Failover(s)) = &input.src {
+                for i in &s.inputs {
+                    collect(i, usage);
+                }
+            }
+        }
+
+        let mut usage = EndpointsUsage::default();
+        for r in self.restreams.lock_ref().iter() {
+            collect(&r.input, &mut usage);
+        }
+        usage
+    }
+
     fn update_stat(stat: &mut HashMap<Status, i32>, status: Status) {
         if let Some(x) = stat.get_mut(&status) {
             *x += 1;
@@ -809,25 +2551,23 @@ impl State {
 
     /// Disables/Enables all [`Output`]s in the specified [`Restream`] of this
     /// [`State`].
-    #[must_use]
     fn set_state_of_all_outputs(
         &self,
         restream_id: RestreamId,
         enabled: bool,
-    ) -> Option<bool> {
+    ) -> Result<Changed, StateError> {
         let mut restreams = self.restreams.lock_mut();
-        Some(
-            restreams
-                .iter_mut()
-                .find(|r| r.id == restream_id)?
-                .outputs
-                .iter_mut()
-                .filter(|o| o.enabled != enabled)
-                .fold(false, |_, o| {
-                    o.enabled = enabled;
-                    true
-                }),
-        )
+        Ok(restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .ok_or(StateError::NotFound("Restream"))?
+            .outputs
+            .iter_mut()
+            .filter(|o| o.enabled != enabled)
+            .fold(false, |_, o| {
+                o.enabled = enabled;
+                true
+            }))
     }
 
     /// Disables/Enables all [`Output`]s in ALL [`Restream`]s of this [`State`].
@@ -846,7 +2586,10 @@ impl State {
 }
 
 /// Specifies kind of password
-#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum PasswordKind {
     /// Password for main application
     Main,
@@ -857,8 +2600,18 @@ pub enum PasswordKind {
 
 /// Status indicating availability of an `Input`, `Output`, or a `Mixin`.
 #[derive(
-    Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq, SmartDefault, Hash,
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    GraphQLEnum,
+    PartialEq,
+    Serialize,
+    SmartDefault,
+    Hash,
 )]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     /// Inactive, no operations are performed and no media traffic is flowed.
     #[default]
@@ -874,3 +2627,64 @@ pub enum Status {
     /// Failed recently
     Unstable,
 }
+
+/// Verbosity level of [FFmpeg] process logs.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FfmpegLogLevel {
+    /// Nothing is logged.
+    Quiet,
+
+    /// Only fatal errors are logged.
+    Fatal,
+
+    /// Errors are logged.
+    Error,
+
+    /// Warnings and errors are logged.
+    Warning,
+
+    /// Informational messages, warnings and errors are logged.
+    Info,
+
+    /// Everything including debugging information is logged.
+    Debug,
+
+    /// Extremely verbose debugging information is logged.
+    Trace,
+}
+
+impl FfmpegLogLevel {
+    /// Returns the value of [FFmpeg]'s `-loglevel` option representing this
+    /// [`FfmpegLogLevel`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn as_ffmpeg_str(self) -> &'static str {
+        match self {
+            Self::Quiet => "quiet",
+            Self::Fatal => "fatal",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] to log with this
+    /// [`FfmpegLogLevel`].
+    ///
+    /// The [`Command`]'s stderr is left as is, so the logs are still captured
+    /// for reporting errors of the [FFmpeg] process.
+    ///
+    /// [`Command`]: tokio::process::Command
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg(self, cmd: &mut tokio::process::Command) {
+        let _ = cmd.args(["-loglevel", self.as_ffmpeg_str()]);
+    }
+}
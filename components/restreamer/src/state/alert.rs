@@ -0,0 +1,179 @@
+//! Alerts raised on the dashboard about monitored [`Client`]s.
+//!
+//! [`Client`]: crate::state::Client
+
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    ClientId, ClientStatistics, ClientStatus, Status, StatusStatistics,
+};
+
+/// Rule of raising an `Alert` about a `Client`.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct AlertRule {
+    /// Kind of the condition checked by this `AlertRule`.
+    pub kind: AlertKind,
+
+    /// Value which should be exceeded to satisfy the condition.
+    ///
+    /// It's a number of items for `OFFLINE_INPUTS` and `OFFLINE_OUTPUTS`
+    /// kinds, CPU usage in percents for `HIGH_CPU` kind, and is ignored for
+    /// `UNREACHABLE` kind.
+    pub threshold: i32,
+
+    /// Duration (in seconds) the condition should last for before raising an
+    /// `Alert`.
+    pub duration: i32,
+}
+
+impl AlertRule {
+    /// Returns [`AlertRule`]s applied by default.
+    #[must_use]
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                kind: AlertKind::OfflineInputs,
+                threshold: 0,
+                duration: 120,
+            },
+            Self {
+                kind: AlertKind::Unreachable,
+                threshold: 0,
+                duration: 60,
+            },
+        ]
+    }
+
+    /// Evaluates the condition of this [`AlertRule`] against the given
+    /// [`ClientStatistics`].
+    ///
+    /// Returns the observed value if the condition is satisfied.
+    #[must_use]
+    pub fn check(
+        &self,
+        status: ClientStatus,
+        stats: Option<&ClientStatistics>,
+    ) -> Option<f64> {
+        let value = match self.kind {
+            AlertKind::Unreachable => {
+                return (status != ClientStatus::Ok).then_some(1.0)
+            }
+            AlertKind::OfflineInputs => {
+                f64::from(count_offline(&stats?.inputs))
+            }
+            AlertKind::OfflineOutputs => {
+                f64::from(count_offline(&stats?.outputs))
+            }
+            AlertKind::HighCpu => stats?.server_info.cpu_usage?,
+        };
+        (value > f64::from(self.threshold)).then_some(value)
+    }
+}
+
+/// Counts offline items in the given [`StatusStatistics`].
+fn count_offline(stats: &[StatusStatistics]) -> i32 {
+    stats
+        .iter()
+        .filter(|s| s.status == Status::Offline)
+        .map(|s| s.count)
+        .sum()
+}
+
+/// Possible kinds of an `AlertRule` condition.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    GraphQLEnum,
+    Hash,
+    PartialEq,
+    Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// `Client` has more offline `Input`s than the threshold.
+    #[display(fmt = "Offline inputs")]
+    OfflineInputs,
+
+    /// `Client` has more offline `Output`s than the threshold.
+    #[display(fmt = "Offline outputs")]
+    OfflineOutputs,
+
+    /// `Client` statistics cannot be retrieved.
+    #[display(fmt = "Unreachable")]
+    Unreachable,
+
+    /// `Client` CPU usage exceeds the threshold.
+    #[display(fmt = "High CPU usage")]
+    HighCpu,
+}
+
+/// Alert raised about a `Client` by an `AlertRule`.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct Alert {
+    /// ID of the `Client` this `Alert` is raised about.
+    pub client_id: ClientId,
+
+    /// Kind of the `AlertRule` condition raised this `Alert`.
+    pub kind: AlertKind,
+
+    /// Value observed when this `Alert` has been raised.
+    pub value: f64,
+
+    /// Time this `Alert` has been raised at.
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Event of raising or clearing an `Alert`.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct AlertEvent {
+    /// Kind of this `AlertEvent`.
+    pub kind: AlertEventKind,
+
+    /// `Alert` this `AlertEvent` is about.
+    pub alert: Alert,
+}
+
+impl AlertEvent {
+    /// Computes [`AlertEvent`]s happened on transition from the `prev` active
+    /// [`Alert`]s to the `actual` ones.
+    #[must_use]
+    pub fn diff(prev: &[Alert], actual: &[Alert]) -> Vec<Self> {
+        let is_same = |a: &Alert, b: &Alert| {
+            a.client_id == b.client_id && a.kind == b.kind
+        };
+        let cleared = prev
+            .iter()
+            .filter(|p| !actual.iter().any(|a| is_same(a, p)))
+            .map(|p| Self {
+                kind: AlertEventKind::Cleared,
+                alert: p.clone(),
+            });
+        let raised = actual
+            .iter()
+            .filter(|a| !prev.iter().any(|p| is_same(a, p)))
+            .map(|a| Self {
+                kind: AlertEventKind::Raised,
+                alert: a.clone(),
+            });
+        cleared.chain(raised).collect()
+    }
+}
+
+/// Possible kinds of an `AlertEvent`.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum AlertEventKind {
+    /// `Alert` has been raised.
+    Raised,
+
+    /// `Alert` has been cleared.
+    Cleared,
+}
@@ -0,0 +1,113 @@
+//! Live audio levels of `Output`s and `Mixin`s measured by [FFmpeg].
+//!
+//! [FFmpeg]: https://ffmpeg.org
+
+use juniper::GraphQLObject;
+
+use crate::state::{MixinId, OutputId, RestreamId, State};
+
+/// Live audio level of an `Output` or a `Mixin`, measured by [FFmpeg].
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Copy, Debug, Default, GraphQLObject, PartialEq)]
+pub struct AudioLevel {
+    /// RMS level of the audio (in dBFS), averaged over a short period.
+    pub rms: f64,
+
+    /// Peak level of the audio (in dBFS) within a short period.
+    pub peak: f64,
+}
+
+impl AudioLevel {
+    /// Lowest level (in dBFS) reported by an [`AudioLevel`], representing
+    /// silence.
+    pub const MIN_DB: f64 = -90.0;
+
+    /// Applies the given metadata `line` printed by [FFmpeg] `astats` filter
+    /// to this [`AudioLevel`].
+    ///
+    /// Returns `false` if the `line` doesn't contain any known level.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn apply_metadata(&mut self, line: &str) -> bool {
+        let (key, val) = match line.trim().split_once('=') {
+            Some(kv) => kv,
+            None => return false,
+        };
+        let field = match key {
+            "lavfi.astats.Overall.RMS_level" => &mut self.rms,
+            "lavfi.astats.Overall.Peak_level" => &mut self.peak,
+            _ => return false,
+        };
+        // Silence is reported as `-inf`, which is not representable in JSON.
+        *field = match val.parse::<f64>() {
+            Ok(v) => v.max(Self::MIN_DB),
+            Err(_) => return false,
+        };
+        true
+    }
+}
+
+/// Live audio levels of an `Output` and its `Mixin`s.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct OutputAudioLevels {
+    /// `AudioLevel` of the mixed audio re-streamed by the `Output`.
+    ///
+    /// `null` if not measured at the moment (the `Output` is offline or has
+    /// no `Mixin`s).
+    pub output: Option<AudioLevel>,
+
+    /// `AudioLevel`s of the `Mixin`s of the `Output`, measured after applying
+    /// their volumes.
+    ///
+    /// `Mixin`s not being measured at the moment are omitted.
+    pub mixins: Vec<MixinAudioLevel>,
+}
+
+impl OutputAudioLevels {
+    /// Collects the actual [`OutputAudioLevels`] of the specified `Output`
+    /// from the given [`State`].
+    ///
+    /// Returns [`None`] if no such `Output` exists.
+    #[must_use]
+    pub fn collect(
+        state: &State,
+        restream_id: RestreamId,
+        output_id: OutputId,
+    ) -> Option<Self> {
+        let mixin_ids: Vec<MixinId> = {
+            let restreams = state.restreams.lock_ref();
+            restreams
+                .iter()
+                .find(|r| r.id == restream_id)?
+                .outputs
+                .iter()
+                .find(|o| o.id == output_id)?
+                .mixins
+                .iter()
+                .map(|m| m.id)
+                .collect()
+        };
+        let levels = state.audio_levels.lock_ref();
+        Some(Self {
+            output: levels.get(&output_id.into()).copied(),
+            mixins: mixin_ids
+                .into_iter()
+                .filter_map(|mixin_id| {
+                    let level = *levels.get(&mixin_id.into())?;
+                    Some(MixinAudioLevel { mixin_id, level })
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Live audio level of a `Mixin`.
+#[derive(Clone, Copy, Debug, GraphQLObject, PartialEq)]
+pub struct MixinAudioLevel {
+    /// ID of the `Mixin`.
+    pub mixin_id: MixinId,
+
+    /// Measured `AudioLevel` of the `Mixin`.
+    pub level: AudioLevel,
+}
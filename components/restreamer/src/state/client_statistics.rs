@@ -3,13 +3,15 @@
 //!
 //! [`Input`]: crate::state::Input
 //! [`Output`]: crate::state::Output
+use std::time::Duration;
+
 use crate::state::Status;
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 
 use derive_more::{Deref, Display, Into};
 use juniper::{
-    GraphQLObject, GraphQLScalar, InputValue, ParseScalarResult,
+    GraphQLEnum, GraphQLObject, GraphQLScalar, InputValue, ParseScalarResult,
     ParseScalarValue, ScalarToken, ScalarValue, Value,
 };
 
@@ -21,7 +23,9 @@ use url::Url;
 ///
 /// [`Input`]: crate::state::Input
 /// [`Output`]: crate::state::Output
-#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
 pub struct StatusStatistics {
     /// Status of [`Input`]s or [`Output`]
     ///
@@ -39,7 +43,7 @@ pub struct StatusStatistics {
 ///
 /// [`Input`]: crate::state::Input
 /// [`Output`]: crate::state::Output
-#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+#[derive(Clone, Debug, Deserialize, GraphQLObject, PartialEq, Serialize)]
 pub struct ClientStatistics {
     /// Client title
     pub client_title: String,
@@ -55,6 +59,14 @@ pub struct ClientStatistics {
 
     /// Info about server info (CPU, Memory, Network)
     pub server_info: ServerInfo,
+
+    /// Outputs restarted within the last 24 hours, the most restarted first
+    #[serde(default)]
+    pub flapping_outputs: Vec<FlappingOutput>,
+
+    /// Usage of all input endpoints by publishers and players
+    #[serde(default)]
+    pub endpoints_usage: EndpointsUsage,
 }
 
 impl ClientStatistics {
@@ -69,6 +81,8 @@ impl ClientStatistics {
         inputs: Vec<StatusStatistics>,
         outputs: Vec<StatusStatistics>,
         server_info: ServerInfo,
+        flapping_outputs: Vec<FlappingOutput>,
+        endpoints_usage: EndpointsUsage,
     ) -> Self {
         Self {
             client_title,
@@ -76,10 +90,61 @@ impl ClientStatistics {
             inputs,
             outputs,
             server_info,
+            flapping_outputs,
+            endpoints_usage,
         }
     }
 }
 
+/// Usage of [`InputEndpoint`]s of [`Client`] by [SRS] clients
+///
+/// [`InputEndpoint`]: crate::state::InputEndpoint
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    GraphQLObject,
+    PartialEq,
+    Serialize,
+)]
+pub struct EndpointsUsage {
+    /// Total count of publishers of all input endpoints
+    pub publishers_count: i32,
+
+    /// Total count of players (local processes and external viewers) of all
+    /// input endpoints
+    pub players_count: i32,
+}
+
+/// [`Output`] being restarted repeatedly, so requiring attention.
+///
+/// [`Output`]: crate::state::Output
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct FlappingOutput {
+    /// Label of the [`Output`], if any
+    ///
+    /// [`Output`]: crate::state::Output
+    pub label: Option<String>,
+
+    /// Downstream URL of the [`Output`], with its credentials, path and
+    /// query masked
+    ///
+    /// [`Output`]: crate::state::Output
+    pub dst: String,
+
+    /// Number of restarts within the last 24 hours
+    pub restarts: i32,
+
+    /// Message of the last error happened
+    pub last_error: Option<String>,
+}
+
 /// Current state of [`ClientStatistics`] request
 #[derive(Clone, Debug, GraphQLObject, PartialEq)]
 pub struct ClientStatisticsResponse {
@@ -88,6 +153,32 @@ pub struct ClientStatisticsResponse {
 
     /// The top-level errors returned by the server.
     pub errors: Option<Vec<String>>,
+
+    /// Status of the request.
+    pub status: ClientStatus,
+
+    /// Version of the statistics API reported by the client.
+    ///
+    /// `null` if the client doesn't report its API version, which means it
+    /// runs an old version of the server.
+    pub api_version: Option<i32>,
+}
+
+/// Status of a [`ClientStatistics`] request.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum ClientStatus {
+    /// Statistics has been retrieved successfully.
+    Ok,
+
+    /// Statistics has failed to be retrieved.
+    Failed,
+
+    /// Client's statistics API version is incompatible with this server, so
+    /// no statistics can be retrieved.
+    Incompatible,
+
+    /// Client reporting its statistics by itself hasn't done it for too long.
+    Stale,
 }
 
 /// Server's info
@@ -113,8 +204,21 @@ pub struct ServerInfo {
     /// Network traffic, received last second
     pub rx_delta: Option<f64>,
 
+    /// Total space of the disk storing DVR files, MB
+    pub disk_total: Option<f64>,
+
+    /// Free (available) space of the disk storing DVR files, MB
+    pub disk_free: Option<f64>,
+
     /// Error message
     pub error_msg: Option<String>,
+
+    /// Error of the last failed attempt to persist the server state.
+    ///
+    /// Non-`null` value means that changes are not being saved at the moment
+    /// and will be lost on restart.
+    #[serde(skip)]
+    pub state_persistence_error: Option<String>,
 }
 
 impl ServerInfo {
@@ -133,6 +237,11 @@ impl ServerInfo {
         self.error_msg = msg;
     }
 
+    /// Sets error of persisting the server state
+    pub fn set_state_persistence_error(&mut self, msg: Option<String>) {
+        self.state_persistence_error = msg;
+    }
+
     /// Updates ram usage
     pub fn update_ram(
         &mut self,
@@ -152,6 +261,31 @@ impl ServerInfo {
         self.tx_delta = tx_delta;
         self.rx_delta = rx_delta;
     }
+
+    /// Updates disk usage
+    pub fn update_disk(
+        &mut self,
+        disk_total: Option<f64>,
+        disk_free: Option<f64>,
+    ) {
+        self.disk_total = disk_total;
+        self.disk_free = disk_free;
+    }
+}
+
+/// [`ServerInfo`] sampled at some moment, kept in a history of the server.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct ServerInfoSample {
+    /// Time when this sample has been taken.
+    pub at: DateTime<Utc>,
+
+    /// `ServerInfo` of the server at that time.
+    pub info: ServerInfo,
+}
+
+impl ServerInfoSample {
+    /// Period of the latest [`ServerInfoSample`]s kept in a history.
+    pub const HISTORY_PERIOD: Duration = Duration::from_secs(15 * 60);
 }
 
 /// Client represents server with running `ephyr` app and can return some
@@ -167,6 +301,24 @@ pub struct Client {
     /// Statistics for this [`Client`].
     #[serde(skip)]
     pub statistics: Option<ClientStatisticsResponse>,
+
+    /// Password to authorize on the client API of this [`Client`] with, for
+    /// operating it remotely.
+    #[graphql(ignore)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Token this [`Client`] reports its statistics with by itself.
+    ///
+    /// If set, then this [`Client`] is not polled for its statistics, but
+    /// is expected to report them periodically instead.
+    #[graphql(ignore)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_token: Option<String>,
+
+    /// Time when statistics of this [`Client`] have been received last time.
+    #[serde(skip)]
+    pub last_seen_at: Option<DateTime<Utc>>,
 }
 
 impl Client {
@@ -176,8 +328,19 @@ impl Client {
         Self {
             id: client_id.clone(),
             statistics: None,
+            password: None,
+            report_token: None,
+            last_seen_at: None,
         }
     }
+
+    /// Indicates whether this [`Client`] reports its statistics by itself,
+    /// rather than being polled for them.
+    #[inline]
+    #[must_use]
+    pub fn is_reporting(&self) -> bool {
+        self.report_token.is_some()
+    }
 }
 
 /// ID of a [`Client`].
@@ -203,6 +366,19 @@ impl ClientId {
         Self(url)
     }
 
+    /// Indicates whether this [`ClientId`] is a valid URL of a remote
+    /// [`Client`] to be monitored, i.e. an HTTP(S) URL of a host without any
+    /// credentials, query or fragment.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        matches!(self.0.scheme(), "http" | "https")
+            && self.0.host_str().map_or(false, |h| !h.is_empty())
+            && self.0.username().is_empty()
+            && self.0.password().is_none()
+            && self.0.query().is_none()
+            && self.0.fragment().is_none()
+    }
+
     #[allow(clippy::wrong_self_convention)]
     fn to_output<S: ScalarValue>(&self) -> Value<S> {
         Value::scalar(self.0.as_str().to_owned())
@@ -0,0 +1,101 @@
+//! Counters of a re-streaming process health, allowing to spot flapping
+//! `Input`s and `Output`s.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Utc};
+use juniper::graphql_object;
+
+use crate::state::Status;
+
+/// Counters of a re-streaming process health, allowing to spot flapping
+/// `Input`s and `Output`s.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StatusCounters {
+    /// Time of the last transition into `Status::Online`.
+    ///
+    /// [`None`] if not `Status::Online` at the moment.
+    pub online_since: Option<DateTime<Utc>>,
+
+    /// Times of restarts happened within the last [`StatusCounters::PERIOD`].
+    pub restarts: VecDeque<DateTime<Utc>>,
+
+    /// Message of the last error happened.
+    pub last_error: Option<String>,
+}
+
+impl StatusCounters {
+    /// Period to count restarts within, in hours.
+    pub const PERIOD: i64 = 24;
+
+    /// Maximum number of characters kept in a
+    /// [`StatusCounters::last_error`].
+    pub const MAX_ERROR_LEN: usize = 1024;
+
+    /// Tracks the transition from the `prev` [`Status`] into the `new` one.
+    pub fn track_status(&mut self, prev: Status, new: Status) {
+        if new == Status::Online {
+            if prev != Status::Online {
+                self.online_since = Some(Utc::now());
+            }
+        } else {
+            self.online_since = None;
+        }
+    }
+
+    /// Tracks a restart happened right now.
+    pub fn track_restart(&mut self) {
+        let now = Utc::now();
+        self.prune(now);
+        self.restarts.push_back(now);
+    }
+
+    /// Tracks the given error happened right now.
+    ///
+    /// Only the tail of the error message is kept, as [FFmpeg] reports the
+    /// actual failure reason at the end of its output.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn track_error(&mut self, err: &str) {
+        let err = err.trim();
+        let skip = err.chars().count().saturating_sub(Self::MAX_ERROR_LEN);
+        self.last_error = Some(err.chars().skip(skip).collect());
+    }
+
+    /// Returns number of restarts happened within the last
+    /// [`StatusCounters::PERIOD`].
+    #[must_use]
+    pub fn restarts_count(&self) -> usize {
+        let since = Utc::now() - Duration::hours(Self::PERIOD);
+        self.restarts.iter().filter(|at| **at > since).count()
+    }
+
+    /// Removes restarts happened before the last [`StatusCounters::PERIOD`].
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let since = now - Duration::hours(Self::PERIOD);
+        while self.restarts.front().map_or(false, |at| *at <= since) {
+            let _ = self.restarts.pop_front();
+        }
+    }
+}
+
+#[graphql_object]
+impl StatusCounters {
+    /// Time of the last transition into `Status::Online`.
+    ///
+    /// `null` if not `Status::Online` at the moment.
+    fn online_since(&self) -> Option<DateTime<Utc>> {
+        self.online_since
+    }
+
+    /// Number of restarts happened within the last 24 hours.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn restarts(&self) -> i32 {
+        self.restarts_count() as i32
+    }
+
+    /// Message of the last error happened.
+    fn last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+}
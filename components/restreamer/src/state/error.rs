@@ -0,0 +1,24 @@
+//! Errors of mutating a `State`.
+
+use derive_more::{Display, Error};
+
+/// Indicator whether a `State` has been changed by a mutation (`true`), or
+/// has had the required values already (`false`).
+pub type Changed = bool;
+
+/// Error of mutating a `State`, precisely describing why the mutation cannot
+/// be performed.
+#[derive(Clone, Debug, Display, Eq, Error, PartialEq)]
+pub enum StateError {
+    /// Mutated element (or one of its parents) doesn't exist.
+    ///
+    /// Holds the name of the missing element.
+    #[display(fmt = "{_0} doesn't exist")]
+    NotFound(#[error(not(source))] &'static str),
+
+    /// Mutation conflicts with the current state of the mutated element.
+    ///
+    /// Holds the description of the conflict.
+    #[display(fmt = "{_0}")]
+    Conflict(#[error(not(source))] String),
+}
@@ -1,9 +1,20 @@
+mod hls_rendition;
 mod input_endpoint;
 mod input_src;
+mod input_tuning;
+mod ll_hls;
 
 pub use self::{
-    input_endpoint::{EndpointId, InputEndpoint, InputEndpointKind},
-    input_src::{FailoverInputSrc, InputSrc, InputSrcUrl, RemoteInputSrc},
+    hls_rendition::HlsRendition,
+    input_endpoint::{
+        EndpointId, EndpointKey, InputEndpoint, InputEndpointKind,
+    },
+    input_src::{
+        FailoverHysteresis, FailoverInputSrc, HlsVariantSelector, InputSrc,
+        InputSrcUrl, PlaylistFileUrl, PlaylistInputSrc, RemoteInputSrc,
+    },
+    input_tuning::InputTuning,
+    ll_hls::LlHls,
 };
 
 use std::{borrow::Cow, mem};
@@ -15,12 +26,18 @@ use regex::Regex;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
-use crate::{serde::is_false, spec, state::Status};
+use crate::{
+    api::graphql::Context,
+    serde::{is_default, is_false},
+    spec,
+    state::{FfmpegLogLevel, Status},
+};
 
 /// Upstream source that a `Restream` receives a live stream from.
 #[derive(
     Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
 )]
+#[graphql(context = Context)]
 pub struct Input {
     /// Unique ID of this `Input`.
     ///
@@ -47,6 +64,31 @@ pub struct Input {
     /// live stream from its upstream sources.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
+
+    /// Tuning options of pulling a live stream from a remote `src`.
+    ///
+    /// Has no effect if this `Input` doesn't pull a live stream from a remote
+    /// source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<InputTuning>,
+
+    /// Verbosity level of [FFmpeg] processes serving this `Input`.
+    ///
+    /// If not set, then the verbosity is defined by the server's log level.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<FfmpegLogLevel>,
+
+    /// Priority of this `Input` among other `Input`s of its
+    /// `FailoverInputSrc`.
+    ///
+    /// `Input`s with higher priority are preferred, while the ones with the
+    /// same priority are preferred in their order in the `FailoverInputSrc`.
+    ///
+    /// Has no effect if this `Input` is not a failover one.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: i32,
 }
 
 impl Input {
@@ -63,11 +105,22 @@ impl Input {
                 .collect(),
             src: spec.src.map(InputSrc::new),
             enabled: spec.enabled,
+            tuning: spec.tuning.map(InputTuning::new),
+            log_level: spec.log_level,
+            priority: spec.priority,
         }
     }
 
     /// Applies the given [`spec::v1::Input`] to this [`Input`].
-    pub fn apply(&mut self, new: spec::v1::Input) {
+    ///
+    /// Existing [`Input::log_level`] is preserved if the given
+    /// [`spec::v1::Input`] doesn't specify any, as well as existing
+    /// [`Input::tuning`] and [`Input::priority`], unless `replace` is `true`.
+    ///
+    /// `replace` is passed down to [`InputEndpoint::apply()`], so its
+    /// optional fields are cleared if the given [`spec::v1::Input`] doesn't
+    /// specify them.
+    pub fn apply(&mut self, new: spec::v1::Input, replace: bool) {
         if self.key != new.key
             || !new.enabled
             || (self.src.is_none() && new.src.is_some())
@@ -78,10 +131,20 @@ impl Input {
             for e in &mut self.endpoints {
                 e.srs_publisher_id = None;
                 e.srs_player_ids.clear();
+                e.srs_viewer_ids.clear();
             }
         }
 
         self.key = new.key;
+        if replace || new.tuning.is_some() {
+            self.tuning = new.tuning.map(InputTuning::new);
+        }
+        if new.log_level.is_some() {
+            self.log_level = new.log_level;
+        }
+        if replace {
+            self.priority = new.priority;
+        }
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
@@ -97,7 +160,7 @@ impl Input {
                 .find_map(|(n, o)| (o.kind == new.kind).then_some(n))
                 .map(|n| olds.swap_remove(n))
             {
-                old.apply(new);
+                old.apply(new, replace);
                 self.endpoints.push(old);
             } else {
                 self.endpoints.push(InputEndpoint::new(new));
@@ -105,7 +168,7 @@ impl Input {
         }
 
         match (self.src.as_mut(), new.src) {
-            (Some(old), Some(new)) => old.apply(new),
+            (Some(old), Some(new)) => old.apply(new, replace),
             (None, Some(new)) => self.src = Some(InputSrc::new(new)),
             _ => self.src = None,
         }
@@ -124,6 +187,9 @@ impl Input {
                 .collect(),
             src: self.src.as_ref().map(InputSrc::export),
             enabled: self.enabled,
+            tuning: self.tuning.as_ref().map(InputTuning::export),
+            log_level: self.log_level,
+            priority: self.priority,
         }
     }
 
@@ -157,8 +223,9 @@ impl Input {
         for e in &mut self.endpoints {
             e.srs_publisher_id = None;
             e.srs_player_ids.clear();
+            e.srs_viewer_ids.clear();
             // Do not rely only on SRS to set status, as it sporadically races.
-            e.status = Status::Offline;
+            e.set_status(Status::Offline, Some("Input disabled"));
         }
 
         if let Some(InputSrc::Failover(s)) = self.src.as_mut() {
@@ -170,6 +237,20 @@ impl Input {
         changed
     }
 
+    /// Lookups for an [`Input`] with the given `id` inside this [`Input`] or
+    /// its [`FailoverInputSrc::inputs`].
+    #[must_use]
+    pub fn find(&self, id: InputId) -> Option<&Self> {
+        if self.id == id {
+            return Some(self);
+        }
+        if let Some(InputSrc::Failover(s)) = &self.src {
+            s.inputs.iter().find_map(|i| i.find(id))
+        } else {
+            None
+        }
+    }
+
     /// Lookups for an [`Input`] with the given `id` inside this [`Input`] or
     /// its [`FailoverInputSrc::inputs`].
     #[must_use]
@@ -184,6 +265,20 @@ impl Input {
         }
     }
 
+    /// Lookups for an [`Input`] owning the [`InputEndpoint`] with the given
+    /// `id` among this [`Input`] and its [`FailoverInputSrc::inputs`].
+    #[must_use]
+    pub fn find_endpoint_owner(&self, id: EndpointId) -> Option<&Self> {
+        if self.endpoints.iter().any(|e| e.id == id) {
+            return Some(self);
+        }
+        if let Some(InputSrc::Failover(s)) = &self.src {
+            s.inputs.iter().find_map(|i| i.find_endpoint_owner(id))
+        } else {
+            None
+        }
+    }
+
     /// Lookups for an [`InputEndpoint`] with the given `id` inside
     /// [`Input`] or its [`FailoverInputSrc::inputs`].
     pub fn find_endpoint(
@@ -241,6 +336,7 @@ impl Input {
     Eq,
     From,
     GraphQLScalar,
+    Hash,
     Into,
     PartialEq,
     Serialize,
@@ -303,3 +399,53 @@ impl PartialEq<str> for InputKey {
         self.0 == other
     }
 }
+
+#[cfg(test)]
+mod input_spec {
+    use serde_json::json;
+
+    use crate::{spec, State};
+
+    use super::InputSrc;
+
+    /// Deserializes a [`spec::v1::Restream`] out of the given JSON.
+    fn to_spec(json: serde_json::Value) -> spec::v1::Restream {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn keeps_priorities_on_restream_edit() {
+        let json = json!({
+            "key": "origin",
+            "input": {
+                "key": "playback",
+                "endpoints": [{"kind": "rtmp"}],
+                "src": {"failover": {"inputs": [
+                    {"key": "primary", "endpoints": [{"kind": "rtmp"}]},
+                    {"key": "backup", "endpoints": [{"kind": "rtmp"}]},
+                ]}},
+            },
+        });
+        let state = State::default();
+        state.add_restream(to_spec(json.clone())).unwrap();
+
+        let backup_priority = |state: &State| {
+            let restreams = state.restreams.lock_ref();
+            match &restreams[0].input.src {
+                Some(InputSrc::Failover(s)) => s
+                    .inputs
+                    .iter()
+                    .find(|i| i.key == *"backup")
+                    .map(|i| (restreams[0].id, i.id, i.priority))
+                    .unwrap(),
+                src => panic!("Unexpected input source: {src:?}"),
+            }
+        };
+        let (restream_id, input_id, _) = backup_priority(&state);
+        assert!(state.set_input_priority(input_id, restream_id, 5).unwrap());
+
+        let _ = state.edit_restream(restream_id, to_spec(json)).unwrap();
+
+        assert_eq!(backup_priority(&state).2, 5);
+    }
+}
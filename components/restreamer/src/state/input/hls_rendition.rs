@@ -0,0 +1,179 @@
+//! Rendition of a live stream served by an `InputEndpointKind::Hls` endpoint
+//! additionally to the source one, forming an [HLS] ladder.
+//!
+//! [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+
+use std::fmt::Write as _;
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    spec, srs,
+    state::{EndpointId, InputEndpointKind, InputKey},
+};
+
+/// Rendition of a live stream served by an `InputEndpointKind::Hls` endpoint
+/// additionally to the source one, being transcoded into the lower
+/// resolution and bitrates.
+///
+/// All the renditions of an `InputEndpoint` are listed in its [HLS] master
+/// playlist, so players switch between them adaptively.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct HlsRendition {
+    /// Height (in pixels) of the video of this rendition.
+    ///
+    /// Width is scaled proportionally.
+    pub height: i32,
+
+    /// Bitrate (in kbps) of the video of this rendition.
+    pub video_bitrate: i32,
+
+    /// Bitrate (in kbps) of the audio of this rendition.
+    pub audio_bitrate: i32,
+}
+
+impl HlsRendition {
+    /// Name of the [HLS] master playlist file suffix, listing all the
+    /// renditions of an `Input`.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    const MASTER_PLAYLIST: &'static str = "master.m3u8";
+
+    /// Creates a new [`HlsRendition`] out of the given
+    /// [`spec::v1::HlsRendition`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::HlsRendition) -> Self {
+        Self {
+            height: spec.height,
+            video_bitrate: spec.video_bitrate,
+            audio_bitrate: spec.audio_bitrate,
+        }
+    }
+
+    /// Exports this [`HlsRendition`] as a [`spec::v1::HlsRendition`].
+    #[inline]
+    #[must_use]
+    pub fn export(self) -> spec::v1::HlsRendition {
+        spec::v1::HlsRendition {
+            height: self.height,
+            video_bitrate: self.video_bitrate,
+            audio_bitrate: self.audio_bitrate,
+        }
+    }
+
+    /// Returns name of this [`HlsRendition`] (like `720p`), suffixing the
+    /// stream name of the `Input` it belongs to.
+    #[inline]
+    #[must_use]
+    pub fn name(self) -> String {
+        format!("{}p", self.height)
+    }
+
+    /// Splits the given [SRS] stream name into the key of an `Input` and the
+    /// name of its [`HlsRendition`], if any.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn split_stream(stream: &str) -> (&str, Option<&str>) {
+        match stream.split_once('.') {
+            Some((input, rendition)) => (input, Some(rendition)),
+            None => (stream, None),
+        }
+    }
+
+    /// Returns ID of the [FFmpeg] process transcoding this [`HlsRendition`]
+    /// of the `InputEndpoint` with the given `id`.
+    ///
+    /// Derived from the `InputEndpoint`'s ID, as renditions have no own ones,
+    /// while their heights are unique within an `InputEndpoint`.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn process_id(self, endpoint: EndpointId) -> Uuid {
+        let id: Uuid = endpoint.into();
+        #[allow(clippy::cast_sign_loss)]
+        Uuid::from_u128(id.as_u128() ^ u128::from(self.height as u32))
+    }
+
+    /// Returns local [RTMP] URL to publish this [`HlsRendition`] of the given
+    /// `restream` and `input` onto.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    #[must_use]
+    pub fn rtmp_url(self, restream: &str, input: &InputKey) -> Url {
+        Url::parse(&format!(
+            "{}.{}",
+            InputEndpointKind::Hls.rtmp_url(restream, input),
+            self.name(),
+        ))
+        .unwrap()
+    }
+
+    /// Returns [FFmpeg] video filter scaling a live stream to this
+    /// [`HlsRendition`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub(crate) fn ffmpeg_filter(self) -> String {
+        format!("scale=-2:{}", self.height)
+    }
+
+    /// Returns contents of the [HLS] master playlist listing the given
+    /// `renditions` of the given `input`.
+    ///
+    /// Renditions are referred relatively, as the master playlist is written
+    /// along with their own ones.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[must_use]
+    pub fn master_playlist(renditions: &[Self], input: &InputKey) -> String {
+        let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n");
+        for r in renditions {
+            let bandwidth = (r.video_bitrate + r.audio_bitrate) * 1000;
+            let _ = write!(
+                playlist,
+                "#EXT-X-STREAM-INF:BANDWIDTH={bandwidth},NAME=\"{name}\"\n\
+                 {input}.{name}.m3u8?vHost=hls\n",
+                name = r.name(),
+            );
+        }
+        playlist
+    }
+
+    /// Returns name of the [HLS] master playlist file of the given `input`.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[inline]
+    #[must_use]
+    pub fn master_playlist_name(input: &InputKey) -> String {
+        format!("{input}.{}", Self::MASTER_PLAYLIST)
+    }
+
+    /// Returns public URL of the [HLS] master playlist for the given
+    /// `restream` and `input`, reachable via the given public `host`.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[must_use]
+    pub fn master_public_url(
+        host: &str,
+        restream: &str,
+        input: &InputKey,
+    ) -> String {
+        format!(
+            "http://{host}:{}/{restream}/{}",
+            srs::http_port(),
+            Self::master_playlist_name(input),
+        )
+    }
+}
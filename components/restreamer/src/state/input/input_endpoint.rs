@@ -1,23 +1,24 @@
-use std::collections::HashSet;
+use std::{borrow::Cow, collections::HashSet, convert::TryFrom};
 
-use derive_more::{Display, From, Into};
-use juniper::{GraphQLEnum, GraphQLObject, GraphQLScalar};
-use serde::{Deserialize, Serialize};
-use url::Url;
+use derive_more::{Deref, Display, From, Into};
+use juniper::{graphql_object, GraphQLEnum, GraphQLScalar};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use url::{form_urlencoded, Url};
 use uuid::Uuid;
 
 use crate::{
-    spec, srs,
+    api::graphql::Context,
+    preview, spec, srs, srt,
     state::{
-        client_statistics::StreamStatistics, InputKey, Label, RestreamKey,
-        Status,
+        client_statistics::StreamStatistics, HlsRendition, InputKey, Label,
+        LlHls, ProcessUsage, Status, StatusCounters, StatusEvent,
     },
 };
 
 /// Endpoint of an `Input` serving a live stream for `Output`s and clients.
-#[derive(
-    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
-)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct InputEndpoint {
     /// Unique ID of this `InputEndpoint`.
     ///
@@ -28,19 +29,71 @@ pub struct InputEndpoint {
     pub kind: InputEndpointKind,
 
     /// User defined label for each Endpoint
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "Label::deserialize_sanitized_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub label: Option<Label>,
 
+    /// Secret key required to publish a live stream to this `InputEndpoint`
+    /// by an external client.
+    ///
+    /// If set, then the publishing URL should contain it as `?key=<value>`
+    /// query parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<EndpointKey>,
+
+    /// [`LlHls`] options to additionally serve a live stream with by this
+    /// [`InputEndpointKind::Hls`] endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ll_hls: Option<LlHls>,
+
+    /// [`HlsRendition`]s to additionally serve a live stream with by this
+    /// [`InputEndpointKind::Hls`] endpoint, listed in its master playlist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hls_renditions: Vec<HlsRendition>,
+
+    /// Port assigned to this [`InputEndpointKind::Srt`] endpoint to listen
+    /// for [SRT] connections on.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_port: Option<u16>,
+
     /// `Status` of this `InputEndpoint` indicating whether it actually serves a
     /// live stream ready to be consumed by `Output`s and clients.
     #[serde(skip)]
     pub status: Status,
 
+    /// `StatusCounters` of this `InputEndpoint` allowing to spot its flapping.
+    #[serde(skip)]
+    pub counters: StatusCounters,
+
+    /// Indicator whether this [`InputEndpoint`] has been [`Status::Online`]
+    /// long enough (and has passed probing, if required) to be switched back
+    /// to by a [`FailoverHysteresis`].
+    ///
+    /// [`FailoverHysteresis`]: crate::state::FailoverHysteresis
+    #[serde(skip)]
+    pub is_failover_ready: bool,
+
+    /// Last [`Status`] transitions of this [`InputEndpoint`] (the oldest
+    /// first), capped to [`StatusEvent::MAX_HISTORY_LEN`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_history: Vec<StatusEvent>,
+
+    /// [`ProcessUsage`] of the [FFmpeg] process serving this
+    /// [`InputEndpoint`], if any is running at the moment.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub process_usage: Option<ProcessUsage>,
+
     /// ID of [SRS] client who publishes a live stream to this [`InputEndpoint`]
     /// (either an external client or a local process).
     ///
     /// [SRS]: https://github.com/ossrs/srs
-    #[graphql(skip)]
     #[serde(skip)]
     pub srs_publisher_id: Option<srs::ClientId>,
 
@@ -48,10 +101,16 @@ pub struct InputEndpoint {
     /// (either an external clients or a local processes).
     ///
     /// [SRS]: https://github.com/ossrs/srs
-    #[graphql(skip)]
     #[serde(skip)]
     pub srs_player_ids: HashSet<srs::ClientId>,
 
+    /// IDs of [SRS] clients who play a live stream from this [`InputEndpoint`]
+    /// remotely (not local processes), so are considered as its viewers.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(skip)]
+    pub srs_viewer_ids: HashSet<String>,
+
     /// Corresponding stream info
     pub stream_stat: Option<StreamStatistics>,
 }
@@ -66,9 +125,22 @@ impl InputEndpoint {
             id: EndpointId::random(),
             kind: spec.kind,
             status: Status::Offline,
+            counters: StatusCounters::default(),
+            is_failover_ready: false,
+            status_history: Vec::new(),
+            process_usage: None,
             label: spec.label,
+            secret_key: spec.secret_key,
+            ll_hls: spec.ll_hls.map(LlHls::new),
+            hls_renditions: spec
+                .hls_renditions
+                .into_iter()
+                .map(HlsRendition::new)
+                .collect(),
+            srt_port: None,
             srs_publisher_id: None,
             srs_player_ids: HashSet::new(),
+            srs_viewer_ids: HashSet::new(),
             stream_stat: None,
         }
     }
@@ -76,14 +148,47 @@ impl InputEndpoint {
     /// Applies the given [`spec::v1::InputEndpoint`] to
     /// this [`InputEndpoint`].
     ///
-    /// If `label` is not `None` than we already
-    /// set the value and the update is not required.
+    /// [`InputEndpoint::label`] and [`InputEndpoint::secret_key`] are left
+    /// untouched if the given spec has none and `replace` is `false`.
+    ///
+    /// The allocated [`InputEndpoint::srt_port`] is preserved as long as this
+    /// [`InputEndpoint`] remains an SRT one.
     #[inline]
-    pub fn apply(&mut self, new: spec::v1::InputEndpoint) {
+    pub fn apply(&mut self, new: spec::v1::InputEndpoint, replace: bool) {
+        if self.kind != new.kind {
+            self.srt_port = None;
+        }
         self.kind = new.kind;
-        if new.label.is_some() {
+        if replace || new.label.is_some() {
             self.label = new.label;
         };
+        if replace || new.secret_key.is_some() {
+            self.secret_key = new.secret_key;
+        };
+        self.ll_hls = new.ll_hls.map(LlHls::new);
+        self.hls_renditions = new
+            .hls_renditions
+            .into_iter()
+            .map(HlsRendition::new)
+            .collect();
+    }
+
+    /// Sets the given [`Status`] of this [`InputEndpoint`], tracking its
+    /// transition (with the given `reason`) in [`InputEndpoint::counters`] and
+    /// [`InputEndpoint::status_history`].
+    #[inline]
+    pub fn set_status(&mut self, status: Status, reason: Option<&str>) {
+        self.counters.track_status(self.status, status);
+        if status != Status::Online {
+            self.is_failover_ready = false;
+        }
+        StatusEvent::track(
+            &mut self.status_history,
+            self.status,
+            status,
+            reason,
+        );
+        self.status = status;
     }
 
     /// Exports this [`InputEndpoint`] as a [`spec::v1::InputEndpoint`].
@@ -93,9 +198,34 @@ impl InputEndpoint {
         spec::v1::InputEndpoint {
             kind: self.kind,
             label: self.label.clone(),
+            secret_key: self.secret_key.clone(),
+            ll_hls: self.ll_hls.map(LlHls::export),
+            hls_renditions: self
+                .hls_renditions
+                .iter()
+                .copied()
+                .map(HlsRendition::export)
+                .collect(),
         }
     }
 
+    /// Indicates whether the given `param` of an [SRS] callback authorizes
+    /// publishing a live stream to this [`InputEndpoint`].
+    ///
+    /// Always authorizes if no [`InputEndpoint::secret_key`] is set.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn is_publish_allowed(&self, param: Option<&str>) -> bool {
+        let secret = match &self.secret_key {
+            Some(key) => key,
+            None => return true,
+        };
+        let query = param.unwrap_or_default().trim_start_matches('?');
+        url::form_urlencoded::parse(query.as_bytes())
+            .any(|(k, v)| k == "key" && *secret == *v)
+    }
+
     /// Indicates whether this [`InputEndpoint`] is an
     /// [`InputEndpointKind::Rtmp`].
     #[inline]
@@ -103,6 +233,219 @@ impl InputEndpoint {
     pub fn is_rtmp(&self) -> bool {
         matches!(self.kind, InputEndpointKind::Rtmp)
     }
+
+    /// Returns [FFmpeg] URL listening for [SRT] connections to this
+    /// [`InputEndpointKind::Srt`] endpoint, requiring its
+    /// [`InputEndpoint::secret_key`] (if any) as a passphrase.
+    ///
+    /// Returns [`None`] if no port has been assigned to this [`InputEndpoint`]
+    /// yet.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[must_use]
+    pub fn srt_listener_url(&self) -> Option<Url> {
+        Some(srt::listener_url(
+            self.srt_port?,
+            self.secret_key.as_deref().map(String::as_str),
+        ))
+    }
+
+    /// Indicates whether this [`InputEndpoint`] is allowed to accept a live
+    /// stream published by an external client.
+    #[inline]
+    #[must_use]
+    pub fn is_publishable(&self) -> bool {
+        matches!(self.kind, InputEndpointKind::Rtmp | InputEndpointKind::Whip)
+    }
+
+    /// Indicates whether this [`InputEndpoint`] serves the [`HlsRendition`]
+    /// with the given `name`.
+    #[inline]
+    #[must_use]
+    pub fn has_hls_rendition(&self, name: &str) -> bool {
+        self.hls_renditions.iter().any(|r| r.name() == name)
+    }
+}
+
+#[graphql_object(context = Context)]
+impl InputEndpoint {
+    /// Unique ID of this `InputEndpoint`.
+    ///
+    /// Once assigned, it never changes.
+    fn id(&self) -> EndpointId {
+        self.id
+    }
+
+    /// Kind of this `InputEndpoint`.
+    fn kind(&self) -> InputEndpointKind {
+        self.kind
+    }
+
+    /// User defined label for each Endpoint
+    fn label(&self) -> Option<&Label> {
+        self.label.as_ref()
+    }
+
+    /// Secret key required to publish a live stream to this `InputEndpoint`
+    /// by an external client.
+    ///
+    /// If set, then the publishing URL should contain it as `?key=<value>`
+    /// query parameter.
+    fn secret_key(&self) -> Option<&EndpointKey> {
+        self.secret_key.as_ref()
+    }
+
+    /// `LlHls` options this `InputEndpoint` additionally serves a live stream
+    /// with.
+    ///
+    /// Always `null` for non-`HLS` endpoints.
+    fn ll_hls(&self) -> Option<LlHls> {
+        self.ll_hls
+    }
+
+    /// `HlsRendition`s this `InputEndpoint` additionally serves a live stream
+    /// with, listed in its master playlist.
+    ///
+    /// Always empty for non-`HLS` endpoints.
+    fn hls_renditions(&self) -> &[HlsRendition] {
+        &self.hls_renditions
+    }
+
+    /// Port assigned to this `InputEndpoint` to listen for SRT connections
+    /// on.
+    ///
+    /// Always `null` for non-`SRT` endpoints, or if no free port is left in
+    /// the configured range.
+    fn srt_port(&self) -> Option<i32> {
+        self.srt_port.map(i32::from)
+    }
+
+    /// `Status` of this `InputEndpoint` indicating whether it actually serves a
+    /// live stream ready to be consumed by `Output`s and clients.
+    fn status(&self) -> Status {
+        self.status
+    }
+
+    /// `StatusCounters` of this `InputEndpoint` allowing to spot its flapping.
+    fn counters(&self) -> &StatusCounters {
+        &self.counters
+    }
+
+    /// Last `Status` transitions of this `InputEndpoint` (the oldest first),
+    /// capped to the last 50 ones.
+    fn status_history(&self) -> &[StatusEvent] {
+        &self.status_history
+    }
+
+    /// `ProcessUsage` of the FFmpeg process serving this `InputEndpoint`.
+    ///
+    /// `null` if no process is running at the moment (a live stream is
+    /// published by an external client, for example).
+    fn process_usage(&self) -> Option<ProcessUsage> {
+        self.process_usage
+    }
+
+    /// Number of SRS clients publishing a live stream to this
+    /// `InputEndpoint` at the moment (either an external client or a local
+    /// process).
+    fn publishers_count(&self) -> i32 {
+        i32::from(self.srs_publisher_id.is_some())
+    }
+
+    /// Number of SRS clients playing a live stream from this `InputEndpoint`
+    /// at the moment, both external viewers and local processes.
+    fn players_count(&self) -> i32 {
+        i32::try_from(self.srs_player_ids.len()).unwrap_or(i32::MAX)
+    }
+
+    /// Corresponding stream info
+    fn stream_stat(&self) -> Option<&StreamStatistics> {
+        self.stream_stat.as_ref()
+    }
+
+    /// Relative URL of the latest preview image of the live stream of this
+    /// `InputEndpoint`, requiring the same authorization as this API does.
+    ///
+    /// Preview images are captured periodically, so the URL should be
+    /// re-fetched to refresh the image.
+    ///
+    /// `null` if this `InputEndpoint` is not `ONLINE`, or capturing preview
+    /// images is disabled on this server.
+    fn preview_url(&self, context: &Context) -> Option<String> {
+        (self.status == Status::Online
+            && !context.config().preview_interval.is_zero())
+        .then(|| preview::url(self.id))
+    }
+
+    /// Public URLs to publish a live stream to or play it from this
+    /// `InputEndpoint`, ready to be copied as is.
+    ///
+    /// Built with `Info.publicHost` and the keys of the `Restream` and the
+    /// `Input` this `InputEndpoint` belongs to. URLs to publish a live stream
+    /// with include the `secretKey` (if any).
+    fn public_urls(&self, context: &Context) -> Vec<String> {
+        let config = context.config();
+        let host = match config.public_host.as_deref() {
+            Some(host) => host,
+            None => return vec![],
+        };
+        let rtmp_port = config.public_rtmp_port.unwrap_or(config.srs_rtmp_port);
+        let client_origin = format!(
+            "{}://{host}:{}",
+            if config.tls_cert_path.is_some() || config.tls_self_signed {
+                "https"
+            } else {
+                "http"
+            },
+            config.client_http_port,
+        );
+        context
+            .state()
+            .restreams
+            .lock_ref()
+            .iter()
+            .find_map(|r| {
+                r.input.find_endpoint_owner(self.id).map(|i| {
+                    let app = r.srs_app();
+                    let mut url = self.kind.public_url(
+                        host,
+                        rtmp_port,
+                        &client_origin,
+                        self.srt_port,
+                        &app,
+                        &i.key,
+                    );
+                    let secret = self
+                        .secret_key
+                        .as_ref()
+                        .filter(|_| self.kind != InputEndpointKind::Hls);
+                    if let Some(key) = secret {
+                        let param = match self.kind {
+                            InputEndpointKind::Srt => "passphrase",
+                            _ => "key",
+                        };
+                        let query =
+                            form_urlencoded::Serializer::new(String::new())
+                                .append_pair(param, key)
+                                .finish();
+                        let sep = if url.contains('?') { '&' } else { '?' };
+                        url = format!("{url}{sep}{query}");
+                    }
+                    let mut urls = vec![url];
+                    if self.ll_hls.is_some() {
+                        urls.push(LlHls::public_url(host, &app, &i.key));
+                    }
+                    if !self.hls_renditions.is_empty() {
+                        urls.push(HlsRendition::master_public_url(
+                            host, &app, &i.key,
+                        ));
+                    }
+                    urls
+                })
+            })
+            .unwrap_or_default()
+    }
 }
 
 /// Possible kinds of an `InputEndpoint`.
@@ -136,35 +479,103 @@ pub enum InputEndpointKind {
     /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
     #[display(fmt = "HLS")]
     Hls,
+
+    /// [WHIP] endpoint.
+    ///
+    /// Accepts a live stream over [WebRTC] (from browsers, for example) and
+    /// republishes it to the [RTMP] endpoint of the same `Input`.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    #[display(fmt = "WHIP")]
+    Whip,
+
+    /// [SRT] endpoint.
+    ///
+    /// Accepts a live stream from an encoder connecting in caller mode to the
+    /// port assigned to this endpoint, and republishes it to the [RTMP]
+    /// endpoint of the same `Input`.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[display(fmt = "SRT")]
+    Srt,
 }
 
 impl InputEndpointKind {
     /// Returns RTMP URL on a local [SRS] server of this [`InputEndpointKind`]
     /// for the given `restream` and `input`.
     ///
+    /// Uses the port the local [SRS] server has been spawned with.
+    ///
     /// [SRS]: https://github.com/ossrs/srs
     #[must_use]
-    pub fn rtmp_url(self, restream: &RestreamKey, input: &InputKey) -> Url {
+    pub fn rtmp_url(self, restream: &str, input: &InputKey) -> Url {
         Self::get_rtmp_url(restream, input, self)
     }
 
-    /// Create RTMP URL for specific [`RestreamKey`] and [`InputKey`]
+    /// Returns public URL of this [`InputEndpointKind`] for the given
+    /// `restream` and `input`, reachable via the given public `host`.
+    ///
+    /// The `rtmp_port` is omitted if it's the [`srs::DEFAULT_RTMP_PORT`].
+    ///
+    /// [`InputEndpointKind::Whip`] is served by the client HTTP server, so
+    /// its URL is built upon the given `client_origin` instead, while
+    /// [`InputEndpointKind::Srt`] is listened on the given `srt_port`.
+    #[must_use]
+    pub fn public_url(
+        self,
+        host: &str,
+        rtmp_port: u16,
+        client_origin: &str,
+        srt_port: Option<u16>,
+        restream: &str,
+        input: &InputKey,
+    ) -> String {
+        match self {
+            Self::Rtmp if rtmp_port == srs::DEFAULT_RTMP_PORT => {
+                format!("rtmp://{host}/{restream}/{input}")
+            }
+            Self::Rtmp => {
+                format!("rtmp://{host}:{rtmp_port}/{restream}/{input}")
+            }
+            Self::Hls => format!(
+                "http://{host}:{}/{restream}/{input}.m3u8?vHost=hls",
+                srs::http_port(),
+            ),
+            Self::Whip => format!("{client_origin}/whip/{restream}/{input}"),
+            Self::Srt => match srt_port {
+                Some(port) => format!("srt://{host}:{port}"),
+                None => format!("srt://{host}"),
+            },
+        }
+    }
+
+    /// Create RTMP URL for specific [SRS] app of a `Restream`, [`InputKey`]
     /// and [`InputEndpointKind`]
     ///
     /// # Panics
-    /// No panics, because [`RestreamKey`] and [`InputKey`] are validated.
+    /// No panics, because `Restream` and [`InputKey`] keys are validated.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
     #[must_use]
     pub fn get_rtmp_url(
-        restream: &RestreamKey,
+        restream: &str,
         input: &InputKey,
         kind: InputEndpointKind,
     ) -> Url {
         Url::parse(&format!(
-            "rtmp://127.0.0.1:1935/{}{}/{}",
+            "rtmp://127.0.0.1:{}/{}{}/{}",
+            srs::rtmp_port(),
             restream,
             match kind {
-                InputEndpointKind::Rtmp => "",
-                InputEndpointKind::Hls => "?vhost=hls",
+                // SRT endpoint republishes into the RTMP one.
+                InputEndpointKind::Rtmp | InputEndpointKind::Srt => "".into(),
+                InputEndpointKind::Hls => {
+                    format!("?vhost={}", srs::hls_vhost(restream))
+                }
+                InputEndpointKind::Whip => "?vhost=whip".into(),
             },
             input,
         ))
@@ -197,3 +608,47 @@ impl EndpointId {
         Self(Uuid::new_v4())
     }
 }
+
+/// Secret key of an `InputEndpoint` required to publish a live stream to it.
+#[derive(
+    Clone, Debug, Deref, Display, Eq, GraphQLScalar, Into, PartialEq, Serialize,
+)]
+#[graphql(transparent)]
+pub struct EndpointKey(String);
+
+impl EndpointKey {
+    /// Creates a new [`EndpointKey`] if the given value meets its invariants.
+    #[must_use]
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
+        static REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new("^[A-Za-z0-9_-]{8,64}$").unwrap());
+
+        let val = val.into();
+        REGEX.is_match(&val).then(|| Self(val.into_owned()))
+    }
+
+    /// Generates a new random [`EndpointKey`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for EndpointKey {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("Not a valid InputEndpoint.key"))
+    }
+}
+
+impl PartialEq<str> for EndpointKey {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
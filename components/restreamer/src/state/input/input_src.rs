@@ -1,4 +1,8 @@
-use std::{mem, path::Path};
+use std::{
+    cmp::Reverse,
+    mem,
+    path::{Component, Path, PathBuf},
+};
 
 use derive_more::{Deref, Display, From, Into};
 use juniper::{GraphQLObject, GraphQLScalar, GraphQLUnion};
@@ -6,6 +10,9 @@ use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use url::Url;
 
 use crate::{
+    api::graphql::Context,
+    dvr,
+    serde::is_false,
     spec,
     state::{Input, Label},
 };
@@ -14,6 +21,7 @@ use crate::{
 #[derive(
     Clone, Debug, Deserialize, Eq, From, GraphQLUnion, PartialEq, Serialize,
 )]
+#[graphql(context = Context)]
 #[serde(rename_all = "lowercase")]
 pub enum InputSrc {
     /// Remote endpoint.
@@ -21,6 +29,9 @@ pub enum InputSrc {
 
     /// Multiple local endpoints forming a failover source.
     Failover(FailoverInputSrc),
+
+    /// Playlist of local files.
+    Playlist(PlaylistInputSrc),
 }
 
 impl InputSrc {
@@ -29,43 +40,43 @@ impl InputSrc {
     #[must_use]
     pub fn new(spec: spec::v1::InputSrc) -> Self {
         match spec {
-            spec::v1::InputSrc::RemoteUrl(url) => {
-                Self::Remote(RemoteInputSrc { url, label: None })
+            spec::v1::InputSrc::Remote { url, hls_variant } => {
+                Self::Remote(RemoteInputSrc {
+                    url,
+                    label: None,
+                    hls_variant,
+                })
             }
-            spec::v1::InputSrc::FailoverInputs(inputs) => {
+            spec::v1::InputSrc::Failover { inputs, hysteresis } => {
                 Self::Failover(FailoverInputSrc {
                     inputs: inputs.into_iter().map(Input::new).collect(),
+                    hysteresis,
                 })
             }
+            spec::v1::InputSrc::Playlist { files, looped } => {
+                Self::Playlist(PlaylistInputSrc { files, looped })
+            }
         }
     }
 
     /// Applies the given [`spec::v1::InputSrc`] to this [`InputSrc`].
     ///
-    /// Replaces all the [`FailoverInputSrc::inputs`] with new ones.
-    pub fn apply(&mut self, new: spec::v1::InputSrc) {
+    /// Replaces all the [`FailoverInputSrc::inputs`] with new ones, applying
+    /// the given `replace` to the preserved ones.
+    pub fn apply(&mut self, new: spec::v1::InputSrc, replace: bool) {
         match (self, new) {
-            (Self::Remote(old), spec::v1::InputSrc::RemoteUrl(new_url)) => {
-                old.url = new_url;
+            (
+                Self::Remote(old),
+                spec::v1::InputSrc::Remote { url, hls_variant },
+            ) => {
+                old.url = url;
+                old.hls_variant = hls_variant;
             }
-            (Self::Failover(src), spec::v1::InputSrc::FailoverInputs(news)) => {
-                let mut olds = mem::replace(
-                    &mut src.inputs,
-                    Vec::with_capacity(news.len()),
-                );
-                for new in news {
-                    if let Some(mut old) = olds
-                        .iter()
-                        .enumerate()
-                        .find_map(|(n, o)| (o.key == new.key).then_some(n))
-                        .map(|n| olds.swap_remove(n))
-                    {
-                        old.apply(new);
-                        src.inputs.push(old);
-                    } else {
-                        src.inputs.push(Input::new(new));
-                    }
-                }
+            (
+                Self::Failover(src),
+                spec::v1::InputSrc::Failover { inputs, hysteresis },
+            ) => {
+                src.apply(inputs, hysteresis, replace);
             }
             (old, new) => *old = Self::new(new),
         }
@@ -76,10 +87,18 @@ impl InputSrc {
     #[must_use]
     pub fn export(&self) -> spec::v1::InputSrc {
         match self {
-            Self::Remote(i) => spec::v1::InputSrc::RemoteUrl(i.url.clone()),
-            Self::Failover(src) => spec::v1::InputSrc::FailoverInputs(
-                src.inputs.iter().map(Input::export).collect(),
-            ),
+            Self::Remote(i) => spec::v1::InputSrc::Remote {
+                url: i.url.clone(),
+                hls_variant: i.hls_variant,
+            },
+            Self::Failover(src) => spec::v1::InputSrc::Failover {
+                inputs: src.inputs.iter().map(Input::export).collect(),
+                hysteresis: src.hysteresis,
+            },
+            Self::Playlist(src) => spec::v1::InputSrc::Playlist {
+                files: src.files.clone(),
+                looped: src.looped,
+            },
         }
     }
 }
@@ -88,13 +107,52 @@ impl InputSrc {
 #[derive(
     Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
 )]
+#[graphql(context = Context)]
 pub struct RemoteInputSrc {
     /// URL of this `RemoteInputSrc`.
     pub url: InputSrcUrl,
 
     /// Label for this Endpoint
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "Label::deserialize_sanitized_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub label: Option<Label>,
+
+    /// `HlsVariantSelector` to choose a variant stream with, if the `url` is
+    /// a HLS master playlist.
+    ///
+    /// If not set, then the variant is chosen by FFmpeg.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_variant: Option<HlsVariantSelector>,
+}
+
+/// Limits to select a variant stream of a [HLS] master playlist with.
+///
+/// The variant with the highest bandwidth fitting the limits is selected, or
+/// the one with the lowest bandwidth if none fits.
+///
+/// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    GraphQLObject,
+    PartialEq,
+    Serialize,
+)]
+pub struct HlsVariantSelector {
+    /// Maximum height (in pixels) of the variant's video.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<i32>,
+
+    /// Maximum bandwidth (in bits per second) of the variant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bandwidth: Option<i32>,
 }
 
 /// Failover source of multiple `Input`s to pull a live stream by an `Input`
@@ -102,13 +160,118 @@ pub struct RemoteInputSrc {
 #[derive(
     Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
 )]
+#[graphql(context = Context)]
 pub struct FailoverInputSrc {
     /// `Input`s forming this `FailoverInputSrc`.
     ///
     /// Failover is implemented by attempting to pull the first `Input` falling
     /// back to the second one, and so on. Once the first source is restored,
     /// we pool from it once again.
+    ///
+    /// `Input`s with higher `Input.priority` are attempted before the ones
+    /// with lower priority, regardless of their order here.
     pub inputs: Vec<Input>,
+
+    /// `FailoverHysteresis` of switching back to the restored `Input`s.
+    ///
+    /// If not set, then the restored `Input` is switched back to as soon as it
+    /// goes online.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hysteresis: Option<FailoverHysteresis>,
+}
+
+impl FailoverInputSrc {
+    /// Returns [`FailoverInputSrc::inputs`] in the order they should be
+    /// attempted to be pulled: by descending [`Input::priority`], preserving
+    /// their order for the same priority.
+    #[must_use]
+    pub fn prioritized(&self) -> Vec<&Input> {
+        let mut inputs = self.inputs.iter().collect::<Vec<_>>();
+        inputs.sort_by_key(|i| Reverse(i.priority));
+        inputs
+    }
+
+    /// Applies the given [`spec::v1::Input`]s and [`FailoverHysteresis`] to
+    /// this [`FailoverInputSrc`].
+    ///
+    /// Replaces all the [`FailoverInputSrc::inputs`] with new ones, preserving
+    /// the ones with the same [`Input::key`] (see [`Input::apply()`] for the
+    /// meaning of `replace`).
+    fn apply(
+        &mut self,
+        news: Vec<spec::v1::Input>,
+        hysteresis: Option<FailoverHysteresis>,
+        replace: bool,
+    ) {
+        let mut olds =
+            mem::replace(&mut self.inputs, Vec::with_capacity(news.len()));
+        for new in news {
+            if let Some(mut old) = olds
+                .iter()
+                .enumerate()
+                .find_map(|(n, o)| (o.key == new.key).then_some(n))
+                .map(|n| olds.swap_remove(n))
+            {
+                old.apply(new, replace);
+                self.inputs.push(old);
+            } else {
+                self.inputs.push(Input::new(new));
+            }
+        }
+        self.hysteresis = hysteresis;
+    }
+}
+
+/// Hysteresis of switching a `FailoverInputSrc` back to a restored `Input`,
+/// preventing it from flapping when the `Input` goes online only briefly.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct FailoverHysteresis {
+    /// Number of seconds a restored `Input` should stay online before being
+    /// switched back to.
+    pub stable_secs: i32,
+
+    /// Indicator whether a restored `Input` should be probed with FFprobe for
+    /// a valid video stream before being switched back to.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub probe_video: bool,
+}
+
+impl FailoverHysteresis {
+    /// Minimum allowed [`FailoverHysteresis::stable_secs`].
+    pub const MIN_STABLE_SECS: i32 = 1;
+
+    /// Maximum allowed [`FailoverHysteresis::stable_secs`].
+    pub const MAX_STABLE_SECS: i32 = 3600;
+
+    /// Indicates whether [`FailoverHysteresis::stable_secs`] is within the
+    /// allowed range.
+    #[inline]
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN_STABLE_SECS..=Self::MAX_STABLE_SECS)
+            .contains(&self.stable_secs)
+    }
+}
+
+/// Playlist of local files to broadcast a live stream by an `Input` from.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+#[graphql(context = Context)]
+pub struct PlaylistInputSrc {
+    /// URLs of the files forming this `PlaylistInputSrc`, in the order they
+    /// are broadcast.
+    pub files: Vec<PlaylistFileUrl>,
+
+    /// Indicator whether this `PlaylistInputSrc` is looped endlessly.
+    ///
+    /// Once a non-looped playlist is played to the end, the `Input` goes
+    /// offline.
+    #[graphql(name = "loop")]
+    #[serde(rename = "loop", default, skip_serializing_if = "is_false")]
+    pub looped: bool,
 }
 
 /// [`Url`] of a [`RemoteInputSrc`].
@@ -178,3 +341,91 @@ impl<'de> Deserialize<'de> for InputSrcUrl {
         })
     }
 }
+
+/// [`Url`] of a file in a [`PlaylistInputSrc`].
+///
+/// Only `file:///` URLs of [MP4] or [FLV] files are allowed (having `.mp4` or
+/// `.flv` extension in its path), without any `.` or `..` segments in their
+/// paths. Only files inside the [`dvr::Storage`] directory are broadcast
+/// (see [`PlaylistFileUrl::file_path()`]).
+///
+/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [MP4]: https://en.wikipedia.org/wiki/MPEG-4_Part_14
+#[derive(
+    Clone,
+    Debug,
+    Deref,
+    Display,
+    Eq,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+    GraphQLScalar,
+)]
+#[graphql(transparent)]
+pub struct PlaylistFileUrl(Url);
+
+impl PlaylistFileUrl {
+    /// Creates a new [`PlaylistFileUrl`] if the given [`Url`] is suitable for
+    /// that.
+    ///
+    /// # Errors
+    ///
+    /// Returns the given [`Url`] back if it doesn't represent a valid
+    /// [`PlaylistFileUrl`].
+    #[inline]
+    pub fn new(url: Url) -> Result<Self, Url> {
+        if Self::validate(&url) {
+            Ok(Self(url))
+        } else {
+            Err(url)
+        }
+    }
+
+    /// Validates the given [`Url`] to represent a valid [`PlaylistFileUrl`].
+    #[must_use]
+    pub fn validate(url: &Url) -> bool {
+        url.scheme() == "file"
+            && matches!(
+                Path::new(url.path()).extension().and_then(|e| e.to_str()),
+                Some("mp4" | "flv"),
+            )
+            && url.to_file_path().map_or(false, |path| {
+                path.components().all(|c| {
+                    matches!(
+                        c,
+                        Component::Prefix(_)
+                            | Component::RootDir
+                            | Component::Normal(_),
+                    )
+                })
+            })
+    }
+
+    /// Resolves this [`PlaylistFileUrl`] into a path of the file inside the
+    /// given [`dvr::Storage`].
+    ///
+    /// Returns [`None`] if the file is located outside of the
+    /// [`dvr::Storage::root_path`], the same way as
+    /// [`dvr::Storage::file_path()`] does.
+    #[must_use]
+    pub fn file_path(&self, storage: &dvr::Storage) -> Option<PathBuf> {
+        let path = self.0.to_file_path().ok()?;
+        storage.file_path(path.strip_prefix(&storage.root_path).ok()?)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlaylistFileUrl {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(Url::deserialize(deserializer)?).map_err(|url| {
+            D::Error::custom(format!(
+                "Not a valid PlaylistInputSrc.files: {url}"
+            ))
+        })
+    }
+}
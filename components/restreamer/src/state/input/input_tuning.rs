@@ -0,0 +1,89 @@
+//! Tuning options of pulling a live stream by an `Input` from its remote
+//! source.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::spec;
+
+/// Tuning options of pulling a live stream by an `Input` from its remote
+/// source.
+///
+/// Useful for distant or unstable sources requiring bigger buffers to be
+/// pulled reliably.
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct InputTuning {
+    /// Size of the data (in bytes) to analyze for getting the live stream
+    /// information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_size: Option<i32>,
+
+    /// Duration (in milliseconds) of the data to analyze for getting the live
+    /// stream information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyze_duration: Option<i32>,
+
+    /// Size of the buffer (in bytes) for buffering the pulled live stream in
+    /// real-time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_size: Option<i32>,
+
+    /// Timeout (in milliseconds) of network read/write operations, after which
+    /// the pulling is considered failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rw_timeout: Option<i32>,
+}
+
+impl InputTuning {
+    /// Creates a new [`InputTuning`] out of the given
+    /// [`spec::v1::InputTuning`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::InputTuning) -> Self {
+        Self {
+            probe_size: spec.probe_size,
+            analyze_duration: spec.analyze_duration,
+            buffer_size: spec.buffer_size,
+            rw_timeout: spec.rw_timeout,
+        }
+    }
+
+    /// Exports this [`InputTuning`] as a [`spec::v1::InputTuning`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::InputTuning {
+        spec::v1::InputTuning {
+            probe_size: self.probe_size,
+            analyze_duration: self.analyze_duration,
+            buffer_size: self.buffer_size,
+            rw_timeout: self.rw_timeout,
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] with these tuning
+    /// options.
+    ///
+    /// Must be called right before specifying the input URL, as [FFmpeg]
+    /// applies these options to the following input only.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) {
+        if let Some(val) = self.probe_size {
+            let _ = cmd.args(["-probesize", &val.to_string()]);
+        }
+        if let Some(val) = self.analyze_duration {
+            let micros = i64::from(val) * 1000;
+            let _ = cmd.args(["-analyzeduration", &micros.to_string()]);
+        }
+        if let Some(val) = self.buffer_size {
+            let _ = cmd.args(["-rtbufsize", &val.to_string()]);
+        }
+        if let Some(val) = self.rw_timeout {
+            let micros = i64::from(val) * 1000;
+            let _ = cmd.args(["-rw_timeout", &micros.to_string()]);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Options of serving a live stream by an `InputEndpointKind::Hls` endpoint
+//! additionally as [LL-HLS].
+//!
+//! [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+
+use std::path::Path;
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, srs, state::InputKey};
+
+/// Options of serving a live stream by an `InputEndpointKind::Hls` endpoint
+/// additionally as [LL-HLS], with playlists split into partial segments.
+///
+/// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct LlHls {
+    /// Target latency (in milliseconds) of the live stream on the client
+    /// side.
+    ///
+    /// Durations of segments and partial segments are derived from it.
+    pub target_latency: i32,
+}
+
+impl LlHls {
+    /// Name of the directory (relative to the [SRS] HTTP server's one) to
+    /// write [LL-HLS] playlists and segments into.
+    ///
+    /// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+    /// [SRS]: https://github.com/ossrs/srs
+    pub const DIR: &'static str = "ll-hls";
+
+    /// Name of the [LL-HLS] master playlist file.
+    ///
+    /// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+    const PLAYLIST: &'static str = "master.m3u8";
+
+    /// Creates a new [`LlHls`] out of the given [`spec::v1::LlHls`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::LlHls) -> Self {
+        Self {
+            target_latency: spec.target_latency,
+        }
+    }
+
+    /// Exports this [`LlHls`] as a [`spec::v1::LlHls`].
+    #[inline]
+    #[must_use]
+    pub fn export(self) -> spec::v1::LlHls {
+        spec::v1::LlHls {
+            target_latency: self.target_latency,
+        }
+    }
+
+    /// Returns public URL of the [LL-HLS] master playlist for the given
+    /// `restream` and `input`, reachable via the given public `host`.
+    ///
+    /// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+    #[must_use]
+    pub fn public_url(host: &str, restream: &str, input: &InputKey) -> String {
+        format!(
+            "http://{host}:{}/{}/{restream}/{input}/{}",
+            srs::http_port(),
+            Self::DIR,
+            Self::PLAYLIST,
+        )
+    }
+
+    /// Returns specification of a [FFmpeg] [tee] muxer's slave writing
+    /// [LL-HLS] playlists and segments into the given `dir`.
+    ///
+    /// [LL-HLS] is produced by [DASH muxer][1] with CMAF segments, so failures
+    /// of this slave are ignored, not to break the rest of the outputs.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [LL-HLS]: https://datatracker.ietf.org/doc/html/rfc8216bis
+    /// [tee]: https://ffmpeg.org/ffmpeg-formats.html#tee-1
+    /// [1]: https://ffmpeg.org/ffmpeg-formats.html#dash-2
+    #[must_use]
+    pub(crate) fn ffmpeg_tee_slave(self, dir: &Path) -> String {
+        let target = f64::from(self.target_latency) / 1000.0;
+        // Segments fit into the target latency twice, while partial ones
+        // split each segment into 4 parts, as recommended by Apple.
+        let segment = (target / 2.0).max(1.0);
+        let part = segment / 4.0;
+
+        format!(
+            "[f=dash:onfail=ignore:streaming=1:ldash=1:lhls=1:\
+              hls_playlist=1:hls_master_name={}:seg_duration={segment}:\
+              frag_type=duration:frag_duration={part}:\
+              target_latency={target}:window_size=6:remove_at_exit=1]{}",
+            Self::PLAYLIST,
+            dir.join("manifest.mpd").display(),
+        )
+    }
+}
@@ -1,12 +1,19 @@
 use derive_more::{Deref, Display, Into};
-use juniper::GraphQLScalar;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use juniper::{
+    GraphQLScalar, InputValue, ParseScalarResult, ParseScalarValue,
+    ScalarToken, ScalarValue, Value,
+};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use std::borrow::Cow;
 
 /// Label of a [`Restream`] or an [`Output`].
 ///
+/// Surrounding whitespaces are trimmed. Should contain from 1 to
+/// [`Label::MAX_LEN`] unicode characters (emoji included), and no control
+/// characters or commas.
+///
+/// [`Label`]s of [`Restream`]s are unique case-insensitively.
+///
 /// [`Restream`]: crate::state::Restream
 /// [`Output`]: crate::state::Output
 #[derive(
@@ -21,19 +28,79 @@ use std::borrow::Cow;
     Serialize,
     GraphQLScalar,
 )]
-#[graphql(transparent)]
+#[graphql(with = Self)]
 pub struct Label(String);
 
 impl Label {
+    /// Maximum allowed number of characters in a [`Label`].
+    pub const MAX_LEN: usize = 70;
+
     /// Creates a new [`Label`] if the given value meets its invariants.
+    ///
+    /// # Errors
+    ///
+    /// With [`TextError`] if the given value doesn't meet [`Label`]
+    /// invariants.
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Result<Self, TextError> {
+        let val = val.into();
+        let val = val.trim();
+        validate(val, Self::MAX_LEN, &[','])?;
+        Ok(Self(val.to_owned()))
+    }
+
+    /// Creates a new [`Label`] out of the given value, sanitizing it to meet
+    /// [`Label`] invariants rather than failing.
+    ///
+    /// Returns [`None`] if nothing remains of the given value.
     #[must_use]
-    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
-        static REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new(r"^[^,\n\t\r\f\v]{1,70}$").unwrap());
+    pub fn sanitized(val: &str) -> Option<Self> {
+        sanitize(val, Self::MAX_LEN, &[',']).map(Self)
+    }
 
-        let val = val.into();
-        (!val.is_empty() && REGEX.is_match(&val))
-            .then(|| Self(val.into_owned()))
+    /// Checks whether this [`Label`] equals to the `other` one, ignoring case
+    /// of their characters.
+    #[must_use]
+    pub fn eq_ignore_case(&self, other: &Self) -> bool {
+        self.0 == other.0 || self.0.to_lowercase() == other.0.to_lowercase()
+    }
+
+    /// Deserializes an optional [`Label`] of a previously persisted state,
+    /// sanitizing it (see [`Label::sanitized()`]), so legacy values not
+    /// meeting the current invariants don't prevent the state from loading.
+    ///
+    /// # Errors
+    ///
+    /// If the deserialized value is not a string.
+    pub fn deserialize_sanitized_opt<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Self>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(<Option<Cow<'_, str>>>::deserialize(deserializer)?
+            .and_then(|v| Self::sanitized(&v)))
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn to_output<S: ScalarValue>(&self) -> Value<S> {
+        Value::scalar(self.0.clone())
+    }
+
+    fn from_input<S>(v: &InputValue<S>) -> Result<Self, String>
+    where
+        S: ScalarValue,
+    {
+        let v = v
+            .as_string_value()
+            .ok_or_else(|| "Label should be a string".to_owned())?;
+        Self::new(v).map_err(|e| format!("Not a valid Label: {e}"))
+    }
+
+    fn parse_token<S>(value: ScalarToken<'_>) -> ParseScalarResult<S>
+    where
+        S: ScalarValue,
+    {
+        <String as ParseScalarValue<S>>::from_str(value)
     }
 }
 
@@ -44,6 +111,284 @@ impl<'de> Deserialize<'de> for Label {
         D: Deserializer<'de>,
     {
         Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
-            .ok_or_else(|| D::Error::custom("Not a valid Label"))
+            .map_err(|e| D::Error::custom(format!("Not a valid Label: {e}")))
+    }
+}
+
+/// Title of this server, used for differentiating servers on UI side if
+/// multiple servers are used.
+///
+/// Surrounding whitespaces are trimmed. Should contain from 1 to
+/// [`Title::MAX_LEN`] unicode characters (emoji included), and no control
+/// characters.
+#[derive(
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+)]
+pub struct Title(String);
+
+impl Title {
+    /// Maximum allowed number of characters in a [`Title`].
+    pub const MAX_LEN: usize = 70;
+
+    /// Creates a new [`Title`] if the given value meets its invariants.
+    ///
+    /// # Errors
+    ///
+    /// With [`TextError`] if the given value doesn't meet [`Title`]
+    /// invariants.
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Result<Self, TextError> {
+        let val = val.into();
+        let val = val.trim();
+        validate(val, Self::MAX_LEN, &[])?;
+        Ok(Self(val.to_owned()))
+    }
+
+    /// Creates a new [`Title`] out of the given value, sanitizing it to meet
+    /// [`Title`] invariants rather than failing.
+    ///
+    /// Returns [`None`] if nothing remains of the given value.
+    #[must_use]
+    pub fn sanitized(val: &str) -> Option<Self> {
+        sanitize(val, Self::MAX_LEN, &[]).map(Self)
+    }
+
+    /// Deserializes an optional [`Title`] of a previously persisted state,
+    /// sanitizing it (see [`Title::sanitized()`]), so legacy values not
+    /// meeting the current invariants don't prevent the state from loading.
+    ///
+    /// # Errors
+    ///
+    /// If the deserialized value is not a string.
+    pub fn deserialize_sanitized_opt<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Self>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(<Option<Cow<'_, str>>>::deserialize(deserializer)?
+            .and_then(|v| Self::sanitized(&v)))
+    }
+
+    /// Deserializes an optional [`Title`], considering an empty string as
+    /// [`None`] for compatibility with previously persisted states.
+    ///
+    /// # Errors
+    ///
+    /// If the deserialized value is not a valid [`Title`].
+    pub fn deserialize_opt<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Self>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Option<Cow<'_, str>>>::deserialize(deserializer)?
+            .filter(|v| !v.trim().is_empty())
+            .map(Self::new)
+            .transpose()
+            .map_err(|e| D::Error::custom(format!("Not a valid Title: {e}")))
+    }
+}
+
+impl<'de> Deserialize<'de> for Title {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("Not a valid Title: {e}")))
+    }
+}
+
+/// Error of validating a human-readable text, like a [`Label`] or a [`Title`].
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum TextError {
+    /// Text is empty.
+    #[display(fmt = "should not be empty")]
+    Empty,
+
+    /// Text exceeds the maximum allowed number of characters.
+    #[display(fmt = "exceeds max allowed length of {} characters", _0)]
+    TooLong(usize),
+
+    /// Text contains a forbidden character.
+    #[display(fmt = "contains forbidden character {:?}", _0)]
+    ForbiddenChar(char),
+}
+
+/// Validates the given already trimmed text to be non-empty, having no more
+/// than `max_len` characters, and containing no control characters or the
+/// given `forbidden` ones.
+///
+/// Characters are counted as unicode scalar values rather than bytes, so
+/// non-latin texts and emoji are measured fairly.
+fn validate(
+    val: &str,
+    max_len: usize,
+    forbidden: &[char],
+) -> Result<(), TextError> {
+    if val.is_empty() {
+        return Err(TextError::Empty);
+    }
+    if val.chars().count() > max_len {
+        return Err(TextError::TooLong(max_len));
+    }
+    if let Some(c) = val
+        .chars()
+        .find(|c| c.is_control() || forbidden.contains(c))
+    {
+        return Err(TextError::ForbiddenChar(c));
+    }
+    Ok(())
+}
+
+/// Sanitizes the given text to pass [`validate()`], replacing control and
+/// the given `forbidden` characters with spaces, trimming it and truncating
+/// to `max_len` characters.
+///
+/// Returns [`None`] if the sanitized text is empty.
+fn sanitize(val: &str, max_len: usize, forbidden: &[char]) -> Option<String> {
+    let val = val
+        .chars()
+        .map(|c| {
+            if c.is_control() || forbidden.contains(&c) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    let val = val.trim().chars().take(max_len).collect::<String>();
+    let val = val.trim_end();
+    (!val.is_empty()).then(|| val.to_owned())
+}
+
+#[cfg(test)]
+mod label_spec {
+    use super::{Label, TextError, Title};
+
+    #[test]
+    fn trims_surrounding_whitespaces() {
+        assert_eq!(Label::new("  my label \t").unwrap().as_str(), "my label");
+        assert_eq!(Title::new("\n My server ").unwrap().as_str(), "My server");
+    }
+
+    #[test]
+    fn rejects_empty() {
+        for val in ["", "   ", "\t\n"] {
+            assert_eq!(Label::new(val), Err(TextError::Empty), "{val:?}");
+            assert_eq!(Title::new(val), Err(TextError::Empty), "{val:?}");
+        }
+    }
+
+    #[test]
+    fn counts_length_in_chars() {
+        let max = "я".repeat(Label::MAX_LEN);
+        assert!(Label::new(max.as_str()).is_ok());
+        assert_eq!(
+            Label::new(format!("{max}я")),
+            Err(TextError::TooLong(Label::MAX_LEN)),
+        );
+
+        let emoji = "🎥".repeat(Title::MAX_LEN);
+        assert!(Title::new(emoji.as_str()).is_ok());
+        assert_eq!(
+            Title::new(format!("{emoji}🎥")),
+            Err(TextError::TooLong(Title::MAX_LEN)),
+        );
+    }
+
+    #[test]
+    fn rejects_forbidden_chars() {
+        assert_eq!(
+            Label::new("a\u{7}b"),
+            Err(TextError::ForbiddenChar('\u{7}')),
+        );
+        assert_eq!(Title::new("a\nb"), Err(TextError::ForbiddenChar('\n')),);
+        assert_eq!(Label::new("a,b"), Err(TextError::ForbiddenChar(',')));
+        assert!(Title::new("a,b").is_ok());
+    }
+
+    #[test]
+    fn displays_errors() {
+        assert_eq!(TextError::Empty.to_string(), "should not be empty");
+        assert_eq!(
+            TextError::TooLong(70).to_string(),
+            "exceeds max allowed length of 70 characters",
+        );
+        assert_eq!(
+            TextError::ForbiddenChar(',').to_string(),
+            "contains forbidden character ','",
+        );
+    }
+
+    #[test]
+    fn compares_case_insensitively() {
+        let label = Label::new("Main Stream").unwrap();
+
+        assert!(label.eq_ignore_case(&Label::new("main stream").unwrap()));
+        assert!(label.eq_ignore_case(&Label::new("MAIN STREAM").unwrap()));
+        assert!(!label.eq_ignore_case(&Label::new("Main Stream 2").unwrap()));
+        assert!(Label::new("Поток")
+            .unwrap()
+            .eq_ignore_case(&Label::new("ПОТОК").unwrap()));
+    }
+
+    #[test]
+    fn sanitizes_invalid_values() {
+        assert_eq!(Label::sanitized(" a,b\nc ").unwrap().as_str(), "a b c",);
+        assert_eq!(Title::sanitized("a,b").unwrap().as_str(), "a,b");
+        assert_eq!(
+            Label::sanitized(&"x".repeat(Label::MAX_LEN + 10))
+                .unwrap()
+                .chars()
+                .count(),
+            Label::MAX_LEN,
+        );
+        assert_eq!(Label::sanitized(" , \n"), None);
+        assert_eq!(Title::sanitized(""), None);
+    }
+
+    #[test]
+    fn deserializes_legacy_values_sanitized() {
+        #[derive(serde::Deserialize)]
+        struct Persisted {
+            #[serde(
+                default,
+                deserialize_with = "Label::deserialize_sanitized_opt"
+            )]
+            label: Option<Label>,
+            #[serde(
+                default,
+                deserialize_with = "Title::deserialize_sanitized_opt"
+            )]
+            title: Option<Title>,
+        }
+
+        let persisted: Persisted = serde_json::from_str(&format!(
+            r#"{{"label": "one, two", "title": "{}"}}"#,
+            "t".repeat(Title::MAX_LEN + 1),
+        ))
+        .unwrap();
+        assert_eq!(persisted.label.unwrap().as_str(), "one  two");
+        assert_eq!(persisted.title.unwrap().len(), Title::MAX_LEN);
+
+        let persisted: Persisted =
+            serde_json::from_str(r#"{"label": ",", "title": null}"#).unwrap();
+        assert_eq!(persisted.label, None);
+        assert_eq!(persisted.title, None);
+
+        let persisted: Persisted = serde_json::from_str("{}").unwrap();
+        assert_eq!(persisted.label, None);
+    }
+
+    #[test]
+    fn rejects_invalid_values_on_strict_deserialization() {
+        assert!(serde_json::from_str::<Label>(r#""a,b""#).is_err());
+        assert!(serde_json::from_str::<Title>(r#""""#).is_err());
+        assert_eq!(
+            serde_json::from_str::<Label>(r#"" ok ""#).unwrap().as_str(),
+            "ok",
+        );
     }
 }
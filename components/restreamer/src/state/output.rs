@@ -1,23 +1,43 @@
+mod icecast;
+mod loudnorm;
 mod mixin;
+mod reconnect;
+mod sidechain;
+mod tls;
+mod traffic;
 mod volume;
 
 pub use self::{
+    icecast::OutputIcecast,
+    loudnorm::OutputLoudnorm,
     mixin::{Delay, Mixin, MixinId, MixinSrcUrl},
+    reconnect::OutputReconnect,
+    sidechain::MixinSidechainParams,
+    tls::OutputTls,
+    traffic::OutputTraffic,
     volume::{Volume, VolumeLevel},
 };
 
-use std::{mem, path::Path};
+use std::{mem, path::Path, time::Duration};
 
+use anyhow::anyhow;
 use derive_more::{Deref, Display, From, Into};
 use juniper::{GraphQLObject, GraphQLScalar};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use tokio::{
+    net::{self, TcpStream},
+    time,
+};
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    serde::is_false,
+    serde::{is_default, is_false},
     spec,
-    state::{Label, Status},
+    state::{
+        FfmpegLogLevel, Label, ProcessUsage, PublishToken, RestreamKey, Status,
+        StatusCounters, StatusEvent,
+    },
 };
 
 /// Downstream destination that a `Restream` re-streams a live stream to.
@@ -39,7 +59,11 @@ pub struct Output {
     pub dst: OutputDstUrl,
 
     /// Optional label of this `Output`.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "Label::deserialize_sanitized_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub label: Option<Label>,
 
     /// Url of stream preview.
@@ -53,6 +77,12 @@ pub struct Output {
     #[serde(default, skip_serializing_if = "Volume::is_origin")]
     pub volume: Volume,
 
+    /// Duration to fade the audio of this `Output` into its latest tuned
+    /// `Output.volume` over.
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub volume_fade: Delay,
+
     /// `Mixin`s to mix this `Output` with before re-streaming it to its
     /// downstream destination.
     ///
@@ -61,18 +91,120 @@ pub struct Output {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mixins: Vec<Mixin>,
 
+    /// Offset (in milliseconds) of this `Output`'s audio relatively to its
+    /// video, correcting their desynchronization introduced by mixing.
+    ///
+    /// Positive value delays the audio, while negative one delays the video.
+    ///
+    /// Has no effect when there is no `Output.mixins`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub av_offset: i32,
+
     /// Indicator whether this `Output` is enabled, so is allowed to perform a
     /// live stream re-streaming to its downstream destination.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
 
+    /// Priority of this `Output` to be kept running when the server is
+    /// overloaded.
+    ///
+    /// `Output`s with the lowest priority are stopped first.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: i32,
+
+    /// Verbosity level of [FFmpeg] process serving this `Output`.
+    ///
+    /// If not set, then the verbosity is defined by the server's log level.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<FfmpegLogLevel>,
+
+    /// Indicator whether this `Output` has been temporarily stopped due to
+    /// the server being overloaded.
+    ///
+    /// It's resumed automatically once the server load normalizes.
+    #[serde(skip)]
+    pub shed: bool,
+
+    /// Revision of this `Output`'s mixing parameters (`Volume`s, `Delay`s and
+    /// side-chains of its `Mixin`s).
+    ///
+    /// Monotonically increases on each change of them, allowing clients to
+    /// ensure whether their changes have been applied.
+    #[serde(default)]
+    pub mix_revision: i32,
+
+    /// TLS options to publish onto an encrypted downstream destination with.
+    ///
+    /// Has effect for `rtmps://` destinations only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutputTls>,
+
+    /// Options allowing to survive short network failures of the downstream
+    /// destination without restarting the re-streaming process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<OutputReconnect>,
+
+    /// Metadata describing the stream to listeners.
+    ///
+    /// Has effect for `icecast://` destinations only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icecast: Option<OutputIcecast>,
+
+    /// Options normalizing loudness of this `Output`'s audio.
+    ///
+    /// Makes the audio being re-encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudnorm: Option<OutputLoudnorm>,
+
+    /// Extra arguments to pass to [FFmpeg] process serving this `Output`,
+    /// right before its downstream destination.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_ffmpeg_args: Vec<String>,
+
     /// `Status` of this `Output` indicating whether it actually re-streams a
     /// live stream to its downstream destination.
     #[serde(skip)]
     pub status: Status,
+
+    /// `StatusCounters` of this `Output` allowing to spot its flapping.
+    #[serde(skip)]
+    pub counters: StatusCounters,
+
+    /// Last `Status` transitions of this `Output` (the oldest first), capped
+    /// to the last 50 ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_history: Vec<StatusEvent>,
+
+    /// `ProcessUsage` of the [FFmpeg] process serving this `Output`.
+    ///
+    /// `null` if no process is running at the moment.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub process_usage: Option<ProcessUsage>,
+
+    /// `OutputTraffic` pushed to the downstream destination of this
+    /// `Output`, allowing to estimate its egress costs.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub traffic: OutputTraffic,
 }
 
 impl Output {
+    /// Maximum absolute value (in milliseconds) of [`Output::av_offset`].
+    pub const MAX_AV_OFFSET: i32 = 10_000;
+
+    /// Indicates whether the given [`Output::av_offset`] value is within the
+    /// allowed range.
+    #[inline]
+    #[must_use]
+    pub fn is_valid_av_offset(offset: i32) -> bool {
+        (-Self::MAX_AV_OFFSET..=Self::MAX_AV_OFFSET).contains(&offset)
+    }
+
     /// Creates a new [`Output`] out of the given [`spec::v1::Output`].
     #[inline]
     #[must_use]
@@ -83,9 +215,24 @@ impl Output {
             label: spec.label,
             preview_url: spec.preview_url,
             volume: Volume::new(&spec.volume),
+            volume_fade: Delay::default(),
             mixins: spec.mixins.into_iter().map(Mixin::new).collect(),
+            av_offset: spec.av_offset,
             enabled: spec.enabled,
+            tls: spec.tls.map(|t| OutputTls::new(t, None)),
+            reconnect: spec.reconnect.map(OutputReconnect::new),
+            icecast: spec.icecast.map(OutputIcecast::new),
+            loudnorm: spec.loudnorm.map(OutputLoudnorm::new),
+            priority: spec.priority,
+            log_level: spec.log_level,
+            extra_ffmpeg_args: spec.extra_ffmpeg_args,
+            shed: false,
+            mix_revision: 0,
             status: Status::Offline,
+            counters: StatusCounters::default(),
+            status_history: Vec::new(),
+            process_usage: None,
+            traffic: OutputTraffic::default(),
         }
     }
 
@@ -94,11 +241,26 @@ impl Output {
     /// If `replace` is `true` then all the [`Output::mixins`] will be replaced
     /// with new ones, otherwise new ones will be merged with already existing
     /// [`Output::mixins`].
+    ///
+    /// [`Output::mix_revision`] is bumped only if the mixing parameters have
+    /// been changed.
     pub fn apply(&mut self, new: spec::v1::Output, replace: bool) {
+        let (prev_volume, prev_av_offset, prev_mixins) =
+            (self.volume.clone(), self.av_offset, self.mixins.clone());
+
         self.dst = new.dst;
         self.label = new.label;
         self.preview_url = new.preview_url;
         self.volume = Volume::new(&new.volume);
+        self.volume_fade = Delay::default();
+        self.av_offset = new.av_offset;
+        self.priority = new.priority;
+        self.log_level = new.log_level;
+        self.tls = new.tls.map(|t| OutputTls::new(t, self.tls.as_ref()));
+        self.reconnect = new.reconnect.map(OutputReconnect::new);
+        self.icecast = new.icecast.map(OutputIcecast::new);
+        self.loudnorm = new.loudnorm.map(OutputLoudnorm::new);
+        self.extra_ffmpeg_args = new.extra_ffmpeg_args;
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
@@ -131,6 +293,28 @@ impl Output {
                 }
             }
         }
+
+        if self.volume != prev_volume
+            || self.av_offset != prev_av_offset
+            || self.mixins != prev_mixins
+        {
+            self.mix_revision += 1;
+        }
+    }
+
+    /// Sets the given [`Status`] of this [`Output`], tracking its transition
+    /// (with the given `reason`) in [`Output::counters`] and
+    /// [`Output::status_history`].
+    #[inline]
+    pub fn set_status(&mut self, status: Status, reason: Option<&str>) {
+        self.counters.track_status(self.status, status);
+        StatusEvent::track(
+            &mut self.status_history,
+            self.status,
+            status,
+            reason,
+        );
+        self.status = status;
     }
 
     /// Exports this [`Output`] as a [`spec::v1::Output`].
@@ -144,8 +328,139 @@ impl Output {
             preview_url: self.preview_url.clone(),
             volume: self.volume.export(),
             mixins: self.mixins.iter().map(Mixin::export).collect(),
+            av_offset: self.av_offset,
             enabled: self.enabled,
+            priority: self.priority,
+            log_level: self.log_level,
+            tls: self.tls.as_ref().map(OutputTls::export),
+            reconnect: self.reconnect.as_ref().map(OutputReconnect::export),
+            icecast: self.icecast.as_ref().map(OutputIcecast::export),
+            loudnorm: self.loudnorm.map(OutputLoudnorm::export),
+            extra_ffmpeg_args: self.extra_ffmpeg_args.clone(),
+        }
+    }
+
+    /// Copies the secrets (masked in an exported [`spec::v1::Output`]) of the
+    /// given `orig`inal [`Output`] into this one created from its exported
+    /// spec.
+    pub fn copy_secrets(&mut self, orig: &Self) {
+        if let (Some(tls), Some(orig)) = (self.tls.as_mut(), orig.tls.as_ref())
+        {
+            tls.key_file = orig.key_file.clone();
+        }
+    }
+
+    /// Maximum number of [`Output::extra_ffmpeg_args`].
+    pub const MAX_EXTRA_FFMPEG_ARGS: usize = 32;
+
+    /// [FFmpeg] options allowed in [`Output::extra_ffmpeg_args`], along with
+    /// the number of values each of them takes.
+    ///
+    /// Only options tuning encoding and muxing of the output are allowed, so
+    /// no additional inputs or outputs may be specified, nor local files may
+    /// be touched. Options may be suffixed with a stream specifier (like
+    /// `-b:v`).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub const ALLOWED_FFMPEG_ARGS: &'static [(&'static str, usize)] = &[
+        ("-an", 0),
+        ("-ac", 1),
+        ("-ar", 1),
+        ("-aspect", 1),
+        ("-avoid_negative_ts", 1),
+        ("-b", 1),
+        ("-bf", 1),
+        ("-bsf", 1),
+        ("-bufsize", 1),
+        ("-c", 1),
+        ("-codec", 1),
+        ("-copyts", 0),
+        ("-crf", 1),
+        ("-dn", 0),
+        ("-fflags", 1),
+        ("-flags", 1),
+        ("-flvflags", 1),
+        ("-force_key_frames", 1),
+        ("-fps_mode", 1),
+        ("-g", 1),
+        ("-keyint_min", 1),
+        ("-level", 1),
+        ("-max_interleave_delta", 1),
+        ("-max_muxing_queue_size", 1),
+        ("-maxrate", 1),
+        ("-metadata", 1),
+        ("-minrate", 1),
+        ("-movflags", 1),
+        ("-muxdelay", 1),
+        ("-muxpreload", 1),
+        ("-pix_fmt", 1),
+        ("-preset", 1),
+        ("-profile", 1),
+        ("-qp", 1),
+        ("-r", 1),
+        ("-refs", 1),
+        ("-s", 1),
+        ("-sample_fmt", 1),
+        ("-sc_threshold", 1),
+        ("-sn", 0),
+        ("-start_at_zero", 0),
+        ("-strict", 1),
+        ("-tag", 1),
+        ("-threads", 1),
+        ("-tune", 1),
+        ("-vn", 0),
+        ("-vsync", 1),
+    ];
+
+    /// Validates the given [`Output::extra_ffmpeg_args`].
+    ///
+    /// Every argument should be either one of
+    /// [`Output::ALLOWED_FFMPEG_ARGS`], or a value it takes. Values looking
+    /// like URLs or paths are not allowed.
+    ///
+    /// # Errors
+    ///
+    /// With a description of the first invalid argument.
+    pub fn validate_extra_ffmpeg_args(args: &[String]) -> Result<(), String> {
+        if args.len() > Self::MAX_EXTRA_FFMPEG_ARGS {
+            return Err(format!(
+                "Maximum {} extra FFmpeg arguments are allowed",
+                Self::MAX_EXTRA_FFMPEG_ARGS,
+            ));
+        }
+        let is_invalid = |arg: &str| {
+            arg.trim().is_empty() || arg.chars().any(char::is_control)
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if is_invalid(arg) {
+                return Err(format!("Invalid FFmpeg argument: {arg:?}"));
+            }
+            let name = arg.split(':').next().unwrap_or_default();
+            let arity = Self::ALLOWED_FFMPEG_ARGS
+                .iter()
+                .find_map(|(n, arity)| (*n == name).then(|| *arity))
+                .ok_or_else(|| {
+                    format!("FFmpeg argument `{arg}` is not an allowed option")
+                })?;
+            for _ in 0..arity {
+                let val = args.next().ok_or_else(|| {
+                    format!("FFmpeg option `{arg}` requires a value")
+                })?;
+                if is_invalid(val) {
+                    return Err(format!("Invalid FFmpeg argument: {val:?}"));
+                }
+                if val.contains("://")
+                    || val.contains("..")
+                    || val.starts_with(['/', '~'])
+                {
+                    return Err(format!(
+                        "FFmpeg argument `{val}` looks like a file or URL",
+                    ));
+                }
+            }
         }
+        Ok(())
     }
 }
 
@@ -182,11 +497,14 @@ impl OutputId {
 ///   host);
 /// - [SRT] URL (starting with `srt://` scheme and having a host);
 /// - [Icecast] URL (starting with `icecast://` scheme and having a host);
-/// - [FLV]|[WAV]|[MP3] file URL (starting with `file:///` scheme,
-///   without host and subdirectories, and with `.flv`|`.wav`|`.mp3`
-///    extension in its path).
+/// - [FLV]|[MP4]|[WAV]|[MP3] file URL (starting with `file:///` scheme,
+///   without host and subdirectories, and with `.flv`|`.mp4`|`.wav`|`.mp3`
+///   extension in its path), optionally having `segment` query parameter
+///   with a positive number of seconds to split the recording into segments
+///   of (like `file:///name.mp4?segment=600`).
 ///
 /// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [MP4]: https://en.wikipedia.org/wiki/MP4_file_format
 /// [WAV]: https://en.wikipedia.org/wiki/WAV
 /// [MP3]: https://en.wikipedia.org/wiki/MP3
 /// [Icecast]: https://icecast.org
@@ -208,6 +526,10 @@ impl OutputId {
 pub struct OutputDstUrl(Url);
 
 impl OutputDstUrl {
+    /// Key of the `Input` accepting a live stream pushed into a `Restream`
+    /// from outside.
+    pub const RELAY_INPUT_KEY: &'static str = "primary";
+
     /// Creates a new [`OutputDstUrl`] if the given [`Url`] is suitable for
     /// that.
     ///
@@ -224,6 +546,50 @@ impl OutputDstUrl {
         }
     }
 
+    /// Creates a new [`OutputDstUrl`] relaying a live stream to the
+    /// `Restream` with the given `key` on another server (ephyr or plain
+    /// [SRS]) reachable via the given `host` and RTMP `port`.
+    ///
+    /// The `token` is passed along, if the downstream `Restream` requires
+    /// one for publishing.
+    ///
+    /// # Errors
+    ///
+    /// If the given `host` cannot be used in an RTMP URL.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn relay(
+        host: &str,
+        port: u16,
+        key: &RestreamKey,
+        token: Option<&PublishToken>,
+    ) -> Result<Self, url::ParseError> {
+        let mut url = Url::parse(&format!(
+            "rtmp://{host}:{port}/{key}/{}",
+            Self::RELAY_INPUT_KEY,
+        ))?;
+        if let Some(t) = token {
+            let _ = url.query_pairs_mut().append_pair("token", t);
+        }
+        Self::new(url).map_err(|_| url::ParseError::EmptyHost)
+    }
+
+    /// Returns this [`OutputDstUrl`] having only its scheme, host and port,
+    /// while its credentials, path and query (which may contain stream keys
+    /// or tokens) are masked.
+    #[must_use]
+    pub fn masked(&self) -> String {
+        let mut masked = format!("{}://", self.0.scheme());
+        if let Some(host) = self.0.host_str() {
+            masked.push_str(host);
+        }
+        if let Some(port) = self.0.port() {
+            masked.push_str(&format!(":{port}"));
+        }
+        masked.push_str("/***");
+        masked
+    }
+
     /// Validates the given [`Url`] to represent a valid [`OutputDstUrl`].
     #[must_use]
     pub fn validate(url: &Url) -> bool {
@@ -233,15 +599,63 @@ impl OutputDstUrl {
                 let path = Path::new(url.path());
                 !url.has_host()
                     && path.is_absolute()
-                    && (path.extension() == Some("flv".as_ref())
-                        || path.extension() == Some("wav".as_ref())
-                        || path.extension() == Some("mp3".as_ref()))
+                    && path.extension().map_or(false, |ext| {
+                        ["flv", "mp4", "wav", "mp3"].iter().any(|e| ext == *e)
+                    })
                     && path.parent() == Some("/".as_ref())
                     && !url.path().contains("/../")
+                    && url.query_pairs().all(|(k, v)| match k.as_ref() {
+                        "segment" => v.parse::<u32>().map_or(false, |n| n > 0),
+                        "chain" => v == "1" || v == "true",
+                        _ => false,
+                    })
             }
             _ => false,
         }
     }
+
+    /// Checks whether this [`OutputDstUrl`] is reachable, by resolving its
+    /// host via DNS and connecting to it via TCP, within the given `timeout`.
+    ///
+    /// [SRT] destinations are only resolved, as [SRT] is UDP-based. Local
+    /// `file://` destinations are not checked at all.
+    ///
+    /// # Errors
+    ///
+    /// If the host cannot be resolved, or none of its addresses accepts a TCP
+    /// connection in time.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    pub async fn preflight(&self, timeout: Duration) -> anyhow::Result<()> {
+        let port = match self.0.scheme() {
+            "file" => return Ok(()),
+            "rtmp" => self.0.port().unwrap_or(1935),
+            "rtmps" => self.0.port().unwrap_or(443),
+            "icecast" => self.0.port().unwrap_or(8000),
+            // SRT URLs always have the port specified.
+            _ => self.0.port().unwrap_or_default(),
+        };
+        let host = self.0.host_str().unwrap_or_default();
+
+        let addrs: Vec<_> =
+            time::timeout(timeout, net::lookup_host((host, port)))
+                .await
+                .map_err(|_| anyhow!("Resolving `{host}` timed out"))?
+                .map_err(|e| anyhow!("Failed to resolve `{host}`: {e}"))?
+                .collect();
+        if addrs.is_empty() {
+            return Err(anyhow!("`{host}` resolves to no addresses"));
+        }
+        if self.0.scheme() == "srt" {
+            return Ok(());
+        }
+
+        time::timeout(timeout, TcpStream::connect(addrs.as_slice()))
+            .await
+            .map_err(|_| anyhow!("Connecting to `{host}:{port}` timed out"))?
+            .map(drop)
+            .map_err(|e| anyhow!("Failed to connect to `{host}:{port}`: {e}"))
+    }
 }
 
 impl<'de> Deserialize<'de> for OutputDstUrl {
@@ -255,3 +669,103 @@ impl<'de> Deserialize<'de> for OutputDstUrl {
         })
     }
 }
+
+#[cfg(test)]
+mod output_spec {
+    use serde_json::json;
+    use url::Url;
+
+    use crate::{spec, State};
+
+    use super::{Output, OutputDstUrl};
+
+    /// Deserializes a [`spec::v1::Output`] out of the given JSON.
+    fn to_spec(json: serde_json::Value) -> spec::v1::Output {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn validate(args: &[&str]) -> Result<(), String> {
+        Output::validate_extra_ffmpeg_args(
+            &args.iter().map(|&a| a.to_owned()).collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn allows_known_options_with_values() {
+        for args in [
+            &["-bufsize", "6000k", "-tune", "zerolatency"][..],
+            &["-b:v", "4M", "-an", "-g", "-1"],
+            &["-metadata:s:a:0", "language=eng", "-vn"],
+        ] {
+            assert_eq!(validate(args), Ok(()), "args: {args:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_files() {
+        for args in [
+            &["-shortest", "out.mp4"][..],
+            &["-an", "out.mp4"],
+            &["-i", "/etc/passwd"],
+            &["-f", "flv"],
+            &["-filter_complex", "movie=x"],
+            &["-metadata", "../x"],
+            &["-preset", "/tmp/x"],
+            &["-tune", "file:///x"],
+            &["-bufsize"],
+            &["6000k"],
+        ] {
+            assert!(validate(args).is_err(), "args: {args:?}");
+        }
+    }
+
+    #[test]
+    fn bumps_mix_revision_only_on_mixing_changes() {
+        let dst = "rtmp://example.com/live/a";
+        let mut output = Output::new(to_spec(json!({"dst": dst})));
+
+        output.apply(to_spec(json!({"dst": dst, "label": "renamed"})), true);
+        assert_eq!(output.mix_revision, 0);
+
+        output.apply(to_spec(json!({"dst": dst, "av_offset": 100})), true);
+        assert_eq!(output.mix_revision, 1);
+
+        output.apply(to_spec(json!({"dst": dst, "av_offset": 100})), true);
+        assert_eq!(output.mix_revision, 1);
+    }
+
+    #[test]
+    fn keeps_tls_secrets_on_clone() {
+        let state = State::default();
+        state
+            .add_restream(
+                serde_json::from_value(json!({
+                    "key": "origin",
+                    "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+                    "outputs": [{
+                        "dst": "rtmps://example.com/live/a",
+                        "tls": {
+                            "cert_file": "/certs/client.pem",
+                            "key_file": "/certs/client.key",
+                        },
+                    }],
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+        let (restream_id, id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+
+        let dst = Url::parse("rtmps://example.com/live/b").unwrap();
+        let new_id = state
+            .clone_output(restream_id, id, OutputDstUrl::new(dst).unwrap())
+            .unwrap()
+            .unwrap();
+
+        let tls = state.get_output(restream_id, new_id).unwrap().tls.unwrap();
+        assert_eq!(tls.cert_file.as_deref(), Some("/certs/client.pem"));
+        assert_eq!(tls.key_file.as_deref(), Some("/certs/client.key"));
+    }
+}
@@ -0,0 +1,90 @@
+//! [Icecast] metadata of an `Output` describing its stream to listeners.
+//!
+//! [Icecast]: https://icecast.org
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::{serde::is_false, spec};
+
+/// [Icecast] metadata of an `Output` describing its stream to listeners.
+///
+/// Has effect for `icecast://` destinations only.
+///
+/// [Icecast]: https://icecast.org
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct OutputIcecast {
+    /// Name of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Description of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Genre of the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+
+    /// URL of a website related to the stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Indicator whether the stream should be listed in public directories.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub public: bool,
+}
+
+impl OutputIcecast {
+    /// Creates a new [`OutputIcecast`] out of the given
+    /// [`spec::v1::OutputIcecast`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::OutputIcecast) -> Self {
+        Self {
+            name: spec.name,
+            description: spec.description,
+            genre: spec.genre,
+            url: spec.url,
+            public: spec.public,
+        }
+    }
+
+    /// Exports this [`OutputIcecast`] as a [`spec::v1::OutputIcecast`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::OutputIcecast {
+        spec::v1::OutputIcecast {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            genre: self.genre.clone(),
+            url: self.url.clone(),
+            public: self.public,
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] with this metadata.
+    ///
+    /// Must be called right before specifying the output URL, as [FFmpeg]
+    /// applies protocol options to the following URL only.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) {
+        let opts = [
+            ("-ice_name", &self.name),
+            ("-ice_description", &self.description),
+            ("-ice_genre", &self.genre),
+            ("-ice_url", &self.url),
+        ];
+        for (opt, val) in opts
+            .iter()
+            .filter_map(|(opt, val)| Some((*opt, val.as_deref()?)))
+        {
+            let _ = cmd.args([opt, val]);
+        }
+        let _ = cmd.args(["-ice_public", if self.public { "1" } else { "0" }]);
+    }
+}
@@ -0,0 +1,56 @@
+//! Options of an `Output` normalizing loudness of its audio.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::spec;
+
+/// Options of an `Output` normalizing loudness of its audio according to the
+/// [EBU R128] standard.
+///
+/// Useful for evening out `Input`s arriving at wildly different loudness.
+/// Requires the audio to be re-encoded.
+///
+/// [EBU R128]: https://en.wikipedia.org/wiki/EBU_R_128
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct OutputLoudnorm {
+    /// Target integrated loudness (in LUFS) to normalize the audio to.
+    pub target_lufs: i32,
+}
+
+impl OutputLoudnorm {
+    /// Creates a new [`OutputLoudnorm`] out of the given
+    /// [`spec::v1::OutputLoudnorm`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::OutputLoudnorm) -> Self {
+        Self {
+            target_lufs: spec.target_lufs,
+        }
+    }
+
+    /// Exports this [`OutputLoudnorm`] as a [`spec::v1::OutputLoudnorm`].
+    #[inline]
+    #[must_use]
+    pub fn export(self) -> spec::v1::OutputLoudnorm {
+        spec::v1::OutputLoudnorm {
+            target_lufs: self.target_lufs,
+        }
+    }
+
+    /// Returns [FFmpeg] audio filters normalizing loudness with these options.
+    ///
+    /// `loudnorm` filter upsamples the audio to 192 kHz, so it's resampled
+    /// back afterwards.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub(crate) fn filters(self) -> String {
+        format!(
+            "loudnorm=I={}:TP=-1.5:LRA=11,aresample=48000",
+            self.target_lufs,
+        )
+    }
+}
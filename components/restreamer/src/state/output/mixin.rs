@@ -2,9 +2,12 @@
 //! the destination.
 
 use crate::{
-    serde::is_false,
+    serde::{is_default, is_false},
     spec,
-    state::{output::Volume, Status},
+    state::{
+        output::{MixinSidechainParams, Volume},
+        RestreamKey, Status, StatusEvent,
+    },
 };
 use derive_more::{Deref, Display, From, Into};
 use juniper::{
@@ -29,7 +32,8 @@ pub struct Mixin {
 
     /// URL of the source to be mixed with an `Output`.
     ///
-    /// At the moment, only [TeamSpeak] is supported.
+    /// At the moment, [TeamSpeak], HTTP and `ephyr://restream/<key>` URLs are
+    /// supported.
     ///
     /// [TeamSpeak]: https://teamspeak.com
     pub src: MixinSrcUrl,
@@ -38,6 +42,12 @@ pub struct Mixin {
     #[serde(default, skip_serializing_if = "Volume::is_origin")]
     pub volume: Volume,
 
+    /// Duration to fade the audio of this `Mixin` into its latest tuned
+    /// `Mixin.volume` over.
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub volume_fade: Delay,
+
     /// Delay that this `Mixin` should wait before being mixed with an `Output`.
     ///
     /// Very useful to fix de-synchronization issues and correct timings between
@@ -50,12 +60,21 @@ pub struct Mixin {
     #[serde(skip)]
     pub status: Status,
 
+    /// Last `Status` transitions of this `Mixin` (the oldest first), capped
+    /// to the last 50 ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_history: Vec<StatusEvent>,
+
     /// Side-chain audio of `Output` with this `Mixin`.
     ///
     /// Helps to automatically control audio level of `Mixin`
     /// based on level of `Output`.
     #[serde(default, skip_serializing_if = "is_false")]
     pub sidechain: bool,
+
+    /// Parameters of side-chaining the audio of `Output` with this `Mixin`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sidechain_params: MixinSidechainParams,
 }
 
 impl Mixin {
@@ -67,9 +86,12 @@ impl Mixin {
             id: MixinId::random(),
             src: spec.src,
             volume: Volume::new(&spec.volume),
+            volume_fade: Delay::default(),
             delay: spec.delay,
             status: Status::Offline,
+            status_history: Vec::new(),
             sidechain: spec.sidechain,
+            sidechain_params: MixinSidechainParams::new(spec.sidechain_params),
         }
     }
 
@@ -78,8 +100,23 @@ impl Mixin {
     pub fn apply(&mut self, new: spec::v1::Mixin) {
         self.src = new.src;
         self.volume = Volume::new(&new.volume);
+        self.volume_fade = Delay::default();
         self.delay = new.delay;
         self.sidechain = new.sidechain;
+        self.sidechain_params = MixinSidechainParams::new(new.sidechain_params);
+    }
+
+    /// Sets the given [`Status`] of this [`Mixin`], tracking its transition
+    /// (with the given `reason`) in [`Mixin::status_history`].
+    #[inline]
+    pub fn set_status(&mut self, status: Status, reason: Option<&str>) {
+        StatusEvent::track(
+            &mut self.status_history,
+            self.status,
+            status,
+            reason,
+        );
+        self.status = status;
     }
 
     /// Exports this [`Mixin`] as a [`spec::v1::Mixin`].
@@ -91,6 +128,7 @@ impl Mixin {
             volume: self.volume.export(),
             delay: self.delay,
             sidechain: self.sidechain,
+            sidechain_params: self.sidechain_params.export(),
         }
     }
 }
@@ -125,10 +163,17 @@ impl MixinId {
 ///
 /// Only the following URLs are allowed at the moment:
 /// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
-/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
-///   host and `.mp3` extension in its path).
+/// - HTTP URL (starting with `http://` or `https://` scheme, having a host
+///   and either `.mp3`, `.aac`, `.ogg` or no extension at all in its path, so
+///   standard [Icecast] mounts are supported, with their format being
+///   negotiated by [FFmpeg]);
+/// - internal URL of another `Restream` on this server (in a form of
+///   `ephyr://restream/<key>`), so its live stream is pulled from the local
+///   [SRS] server.
 ///
-/// [MP3]: https://en.wikipedia.org/wiki/MP3
+/// [FFmpeg]: https://ffmpeg.org
+/// [Icecast]: https://icecast.org
+/// [SRS]: https://github.com/ossrs/srs
 /// [TeamSpeak]: https://teamspeak.com
 #[derive(
     Clone,
@@ -168,11 +213,36 @@ impl MixinSrcUrl {
             && match url.scheme() {
                 "ts" => true,
                 "http" | "https" => {
-                    Path::new(url.path()).extension() == Some("mp3".as_ref())
+                    Path::new(url.path()).extension().map_or(true, |ext| {
+                        ["mp3", "aac", "ogg"].iter().any(|e| ext == *e)
+                    })
                 }
+                "ephyr" => Self::parse_restream_key(url).is_some(),
                 _ => false,
             }
     }
+
+    /// Returns [`RestreamKey`] of the `Restream` referred by this
+    /// [`MixinSrcUrl`], if it's an internal `ephyr://restream/<key>` one.
+    #[inline]
+    #[must_use]
+    pub fn restream_key(&self) -> Option<RestreamKey> {
+        Self::parse_restream_key(&self.0)
+    }
+
+    /// Parses [`RestreamKey`] out of the given internal
+    /// `ephyr://restream/<key>` [`Url`].
+    fn parse_restream_key(url: &Url) -> Option<RestreamKey> {
+        if url.scheme() != "ephyr"
+            || url.host_str() != Some("restream")
+            || url.port().is_some()
+            || url.query().is_some()
+            || url.fragment().is_some()
+        {
+            return None;
+        }
+        RestreamKey::new(url.path().strip_prefix('/')?)
+    }
 }
 
 impl<'de> Deserialize<'de> for MixinSrcUrl {
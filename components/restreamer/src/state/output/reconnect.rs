@@ -0,0 +1,104 @@
+//! Options of an `Output` allowing it to survive short network failures of
+//! its downstream destination without restarting.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use url::Url;
+
+use crate::{serde::is_false, spec};
+
+/// Options of an `Output` allowing it to survive short network failures of
+/// its downstream destination without restarting.
+///
+/// Useful for destinations suffering from short network blips, which
+/// otherwise cause the whole re-streaming process to be restarted.
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct OutputReconnect {
+    /// Indicator whether the connection should be re-established on its
+    /// failure.
+    ///
+    /// Has effect for HTTP-based destinations (like `icecast://`) only.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub http_reconnect: bool,
+
+    /// Timeout (in milliseconds) of network read/write operations, after which
+    /// the publishing is considered failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rw_timeout: Option<i32>,
+
+    /// Latency (in milliseconds) of a SRT connection, allowing to recover lost
+    /// packets within.
+    ///
+    /// Has effect for `srt://` destinations only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_latency: Option<i32>,
+
+    /// Maximum bandwidth (in bytes per second) of a SRT connection, including
+    /// retransmissions of lost packets.
+    ///
+    /// Has effect for `srt://` destinations only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_max_bandwidth: Option<i32>,
+}
+
+impl OutputReconnect {
+    /// Creates a new [`OutputReconnect`] out of the given
+    /// [`spec::v1::OutputReconnect`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::OutputReconnect) -> Self {
+        Self {
+            http_reconnect: spec.http_reconnect,
+            rw_timeout: spec.rw_timeout,
+            srt_latency: spec.srt_latency,
+            srt_max_bandwidth: spec.srt_max_bandwidth,
+        }
+    }
+
+    /// Exports this [`OutputReconnect`] as a [`spec::v1::OutputReconnect`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::OutputReconnect {
+        spec::v1::OutputReconnect {
+            http_reconnect: self.http_reconnect,
+            rw_timeout: self.rw_timeout,
+            srt_latency: self.srt_latency,
+            srt_max_bandwidth: self.srt_max_bandwidth,
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] with these options for
+    /// publishing onto the given `dst` [`Url`].
+    ///
+    /// Must be called right before specifying the output URL, as [FFmpeg]
+    /// applies protocol options to the following URL only.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command, dst: &Url) {
+        if let Some(val) = self.rw_timeout {
+            let micros = i64::from(val) * 1000;
+            let _ = cmd.args(["-rw_timeout", &micros.to_string()]);
+        }
+        match dst.scheme() {
+            "icecast" | "http" | "https" if self.http_reconnect => {
+                let _ = cmd
+                    .args(["-reconnect", "1"])
+                    .args(["-reconnect_streamed", "1"])
+                    .args(["-reconnect_on_network_error", "1"]);
+            }
+            "srt" => {
+                if let Some(val) = self.srt_latency {
+                    let micros = i64::from(val) * 1000;
+                    let _ = cmd.args(["-latency", &micros.to_string()]);
+                }
+                if let Some(val) = self.srt_max_bandwidth {
+                    let _ = cmd.args(["-maxbw", &val.to_string()]);
+                }
+            }
+            _ => {}
+        }
+    }
+}
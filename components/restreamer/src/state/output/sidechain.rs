@@ -0,0 +1,87 @@
+//! Parameters of side-chaining a `Mixin` with its `Output`.
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::spec;
+
+/// Parameters of the [sidechaincompress][1] filter compressing the audio of
+/// an `Output` by the audio of its side-chained `Mixin`.
+///
+/// [1]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
+#[derive(
+    Clone, Copy, Debug, Deserialize, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct MixinSidechainParams {
+    /// Level of the `Mixin`'s audio above which the `Output`'s audio starts
+    /// being compressed.
+    pub threshold: f64,
+
+    /// Ratio by which the `Output`'s audio is compressed.
+    pub ratio: f64,
+
+    /// Time (in milliseconds) the `Mixin`'s audio should rise above the
+    /// `threshold` before the compression starts.
+    pub attack: f64,
+
+    /// Time (in milliseconds) the `Mixin`'s audio should fall below the
+    /// `threshold` before the compression stops.
+    pub release: f64,
+
+    /// Amplification of the `Output`'s audio after it has been compressed.
+    pub makeup: f64,
+}
+
+// Parameters are validated to be finite numbers, so never are `NaN`.
+impl Eq for MixinSidechainParams {}
+
+impl MixinSidechainParams {
+    /// Creates new [`MixinSidechainParams`] out of the given
+    /// [`spec::v1::MixinSidechainParams`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::MixinSidechainParams) -> Self {
+        Self {
+            threshold: spec.threshold,
+            ratio: spec.ratio,
+            attack: spec.attack,
+            release: spec.release,
+            makeup: spec.makeup,
+        }
+    }
+
+    /// Exports these [`MixinSidechainParams`] as
+    /// [`spec::v1::MixinSidechainParams`].
+    #[inline]
+    #[must_use]
+    pub fn export(self) -> spec::v1::MixinSidechainParams {
+        spec::v1::MixinSidechainParams {
+            threshold: self.threshold,
+            ratio: self.ratio,
+            attack: self.attack,
+            release: self.release,
+            makeup: self.makeup,
+        }
+    }
+
+    /// Returns options of [FFmpeg]'s [sidechaincompress][1] filter with these
+    /// parameters.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [1]: https://ffmpeg.org/ffmpeg-filters.html#sidechaincompress
+    #[must_use]
+    pub(crate) fn filter_options(self) -> String {
+        format!(
+            "level_in=2:threshold={}:ratio={}:attack={}:knee=4:release={}\
+             :makeup={}",
+            self.threshold, self.ratio, self.attack, self.release, self.makeup,
+        )
+    }
+}
+
+impl Default for MixinSidechainParams {
+    #[inline]
+    fn default() -> Self {
+        Self::new(spec::v1::MixinSidechainParams::default())
+    }
+}
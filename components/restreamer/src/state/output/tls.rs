@@ -0,0 +1,197 @@
+//! [TLS] options of an `Output` used for publishing onto an encrypted
+//! destination (like `rtmps://`).
+//!
+//! [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+
+use juniper::graphql_object;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::{serde::is_false, spec};
+
+/// [TLS] options of an `Output` used for publishing onto an encrypted
+/// destination (like `rtmps://`).
+///
+/// Paths to the client key are considered secret, so are never exposed via
+/// GraphQL API and are masked in exported specs.
+///
+/// [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OutputTls {
+    /// Path to a file with CA certificates to verify the destination server
+    /// with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+
+    /// Path to a file with a client certificate to authenticate with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_file: Option<String>,
+
+    /// Path to a file with a private key of the client certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+
+    /// Indicator whether verification of the destination server certificate
+    /// should be skipped.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub skip_verify: bool,
+}
+
+impl OutputTls {
+    /// Placeholder used instead of secret values in exported specs.
+    pub const MASK: &'static str = "********";
+
+    /// Creates a new [`OutputTls`] out of the given [`spec::v1::OutputTls`].
+    ///
+    /// Masked secrets are taken from the `prev` [`OutputTls`], if any.
+    #[must_use]
+    pub fn new(spec: spec::v1::OutputTls, prev: Option<&Self>) -> Self {
+        let key_file = if spec.key_file.as_deref() == Some(Self::MASK) {
+            prev.and_then(|p| p.key_file.clone())
+        } else {
+            spec.key_file
+        };
+        Self {
+            ca_file: spec.ca_file,
+            cert_file: spec.cert_file,
+            key_file,
+            skip_verify: spec.skip_verify,
+        }
+    }
+
+    /// Exports this [`OutputTls`] as a [`spec::v1::OutputTls`], masking its
+    /// secrets.
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::OutputTls {
+        spec::v1::OutputTls {
+            ca_file: self.ca_file.clone(),
+            cert_file: self.cert_file.clone(),
+            key_file: self.key_file.as_ref().map(|_| Self::MASK.to_owned()),
+            skip_verify: self.skip_verify,
+        }
+    }
+
+    /// Properly setups the given [FFmpeg] [`Command`] with these [TLS]
+    /// options.
+    ///
+    /// Must be called right before specifying the output URL, as [FFmpeg]
+    /// applies protocol options to the following URL only.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [TLS]: https://en.wikipedia.org/wiki/Transport_Layer_Security
+    pub(crate) fn setup_ffmpeg(&self, cmd: &mut Command) {
+        let _ =
+            cmd.args(["-tls_verify", if self.skip_verify { "0" } else { "1" }]);
+        if let Some(path) = self.ca_file.as_ref() {
+            let _ = cmd.args(["-ca_file", path]);
+        }
+        if let Some(path) = self.cert_file.as_ref() {
+            let _ = cmd.args(["-cert_file", path]);
+        }
+        if let Some(path) = self.key_file.as_ref() {
+            let _ = cmd.args(["-key_file", path]);
+        }
+    }
+}
+
+#[graphql_object]
+impl OutputTls {
+    /// Path to a file with CA certificates to verify the destination server
+    /// with.
+    fn ca_file(&self) -> Option<String> {
+        self.ca_file.clone()
+    }
+
+    /// Path to a file with a client certificate to authenticate with.
+    fn cert_file(&self) -> Option<String> {
+        self.cert_file.clone()
+    }
+
+    /// Indicator whether a private key of the client certificate is set.
+    ///
+    /// The path to the key itself is never exposed.
+    fn has_key(&self) -> bool {
+        self.key_file.is_some()
+    }
+
+    /// Indicator whether verification of the destination server certificate
+    /// is skipped.
+    fn skip_verify(&self) -> bool {
+        self.skip_verify
+    }
+}
+
+#[cfg(test)]
+mod tls_spec {
+    use tokio::process::Command;
+
+    use crate::spec;
+
+    use super::OutputTls;
+
+    fn ffmpeg_args(tls: &OutputTls) -> Vec<String> {
+        let mut cmd = Command::new("ffmpeg");
+        tls.setup_ffmpeg(&mut cmd);
+        cmd.as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn masks_key_file_on_export() {
+        let tls = OutputTls {
+            cert_file: Some("/certs/client.pem".into()),
+            key_file: Some("/certs/client.key".into()),
+            ..OutputTls::default()
+        };
+
+        let spec = tls.export();
+        assert_eq!(spec.cert_file.as_deref(), Some("/certs/client.pem"));
+        assert_eq!(spec.key_file.as_deref(), Some(OutputTls::MASK));
+
+        assert_eq!(OutputTls::new(spec.clone(), Some(&tls)), tls);
+        assert_eq!(OutputTls::new(spec, None).key_file, None);
+    }
+
+    #[test]
+    fn does_not_mask_absent_key_file() {
+        let spec = OutputTls::default().export();
+        assert_eq!(spec, spec::v1::OutputTls::default());
+    }
+
+    #[test]
+    fn verifies_destination_by_default() {
+        let tls = OutputTls {
+            ca_file: Some("/certs/ca.pem".into()),
+            cert_file: Some("/certs/client.pem".into()),
+            key_file: Some("/certs/client.key".into()),
+            skip_verify: false,
+        };
+
+        assert_eq!(
+            ffmpeg_args(&tls),
+            [
+                "-tls_verify",
+                "1",
+                "-ca_file",
+                "/certs/ca.pem",
+                "-cert_file",
+                "/certs/client.pem",
+                "-key_file",
+                "/certs/client.key",
+            ],
+        );
+    }
+
+    #[test]
+    fn skips_verification_of_self_signed_destination() {
+        let tls = OutputTls {
+            skip_verify: true,
+            ..OutputTls::default()
+        };
+
+        assert_eq!(ffmpeg_args(&tls), ["-tls_verify", "0"]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Traffic pushed to a downstream destination of an `Output`.
+
+use chrono::{DateTime, Datelike as _, NaiveDate, Utc};
+use juniper::graphql_object;
+use serde::{Deserialize, Serialize};
+
+/// Amount of data pushed to a downstream destination of an `Output`,
+/// accumulated per UTC day and month.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OutputTraffic {
+    /// UTC day the [`OutputTraffic::today`] and [`OutputTraffic::month`]
+    /// counters are accumulated till.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<NaiveDate>,
+
+    /// Bytes pushed during the [`OutputTraffic::day`].
+    #[serde(default)]
+    pub today: u64,
+
+    /// Bytes pushed during the month of the [`OutputTraffic::day`].
+    #[serde(default)]
+    pub month: u64,
+
+    /// Bytes pushed overall.
+    #[serde(default)]
+    pub total: u64,
+}
+
+impl OutputTraffic {
+    /// Accumulates the given number of `bytes` pushed at the given moment
+    /// (`now`), resetting the counters of the passed day and month.
+    pub fn add(&mut self, bytes: u64, now: DateTime<Utc>) {
+        let day = now.date_naive();
+        self.reset_till(day);
+        self.day = Some(day);
+        self.today = self.today.saturating_add(bytes);
+        self.month = self.month.saturating_add(bytes);
+        self.total = self.total.saturating_add(bytes);
+    }
+
+    /// Resets the counters not related to the given UTC `day` anymore.
+    fn reset_till(&mut self, day: NaiveDate) {
+        match self.day {
+            Some(d) if d == day => {}
+            Some(d) if (d.year(), d.month()) == (day.year(), day.month()) => {
+                self.today = 0;
+            }
+            _ => {
+                self.today = 0;
+                self.month = 0;
+            }
+        }
+    }
+
+    /// Returns this [`OutputTraffic`] as of the given UTC `day`.
+    fn actual(&self, day: NaiveDate) -> Self {
+        let mut actual = self.clone();
+        actual.reset_till(day);
+        actual
+    }
+}
+
+/// Amount of data pushed to a downstream destination of an `Output`.
+///
+/// Values are in bytes, updated once a minute.
+#[graphql_object]
+impl OutputTraffic {
+    /// Bytes pushed today (UTC).
+    #[allow(clippy::cast_precision_loss)]
+    fn today(&self) -> f64 {
+        self.actual(Utc::now().date_naive()).today as f64
+    }
+
+    /// Bytes pushed this month (UTC).
+    #[allow(clippy::cast_precision_loss)]
+    fn month(&self) -> f64 {
+        self.actual(Utc::now().date_naive()).month as f64
+    }
+
+    /// Bytes pushed overall.
+    #[allow(clippy::cast_precision_loss)]
+    fn total(&self) -> f64 {
+        self.total as f64
+    }
+}
+
+#[cfg(test)]
+mod traffic_spec {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::OutputTraffic;
+
+    #[test]
+    fn resets_passed_day_and_month() {
+        let mut traffic = OutputTraffic::default();
+        traffic.add(10, Utc.with_ymd_and_hms(2023, 1, 31, 23, 0, 0).unwrap());
+        traffic.add(5, Utc.with_ymd_and_hms(2023, 1, 31, 23, 59, 0).unwrap());
+        assert_eq!((traffic.today, traffic.month, traffic.total), (15, 15, 15));
+
+        traffic.add(7, Utc.with_ymd_and_hms(2023, 2, 1, 0, 1, 0).unwrap());
+        assert_eq!((traffic.today, traffic.month, traffic.total), (7, 7, 22));
+
+        traffic.add(3, Utc.with_ymd_and_hms(2023, 2, 2, 0, 0, 0).unwrap());
+        assert_eq!((traffic.today, traffic.month, traffic.total), (3, 10, 25));
+    }
+}
@@ -51,6 +51,18 @@ impl Volume {
         }
     }
 
+    /// Returns the effective level of this [`Volume`] in percents, being `0`
+    /// if it's muted.
+    #[inline]
+    #[must_use]
+    pub fn percents(&self) -> u16 {
+        if self.muted {
+            0
+        } else {
+            self.level.0
+        }
+    }
+
     /// Indicates whether this [`Volume`] rate value corresponds is the
     /// [`Volume::ORIGIN`]al one.
     #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
@@ -0,0 +1,17 @@
+//! Resources usage of re-streaming processes serving `Input`s and `Output`s.
+
+use juniper::GraphQLObject;
+
+/// Resources usage of a [FFmpeg] process serving an `InputEndpoint` or an
+/// `Output`.
+///
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(Clone, Copy, Debug, Default, Eq, GraphQLObject, Hash, PartialEq)]
+pub struct ProcessUsage {
+    /// CPU usage of the process (in percents of a single core), so may exceed
+    /// `100` if the process utilizes multiple cores.
+    pub cpu: i32,
+
+    /// Resident memory of the process (in megabytes).
+    pub rss: i32,
+}
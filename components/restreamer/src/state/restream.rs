@@ -1,3 +1,15 @@
+mod fallback;
+mod ip_filter;
+mod srs_tuning;
+mod webhook;
+
+pub use self::{
+    fallback::{FallbackKind, RestreamFallback},
+    ip_filter::{IpCidr, RestreamIpFilter},
+    srs_tuning::RestreamSrsTuning,
+    webhook::{RestreamWebhook, WebhookDelivery, WebhookEvent},
+};
+
 use std::{borrow::Cow, mem};
 
 use anyhow::anyhow;
@@ -10,6 +22,7 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
+    api::graphql::Context,
     spec,
     state::{Input, Label, Output},
 };
@@ -18,6 +31,7 @@ use crate::{
 #[derive(
     Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
 )]
+#[graphql(context = Context)]
 pub struct Restream {
     /// Unique ID of this `Input`.
     ///
@@ -28,8 +42,17 @@ pub struct Restream {
     /// endpoints URLs.
     pub key: RestreamKey,
 
-    /// Optional label of this `Restream`.
+    /// Tenant this `Restream` belongs to, isolating its endpoints under a
+    /// separate SRS app, and making it visible to the tenant's credentials.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<TenantKey>,
+
+    /// Optional label of this `Restream`.
+    #[serde(
+        default,
+        deserialize_with = "Label::deserialize_sanitized_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub label: Option<Label>,
 
     /// `Input` that a live stream is received from.
@@ -38,9 +61,46 @@ pub struct Restream {
     /// `Output`s that a live stream is re-streamed to.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub outputs: Vec<Output>,
+
+    /// Outbound webhook to notify about this `Restream`'s events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<RestreamWebhook>,
+
+    /// Fallback source to be broadcast to `Output`s while the `Input` is
+    /// offline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<RestreamFallback>,
+
+    /// Filter of IP addresses allowed to publish a live stream to this
+    /// `Restream`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_filter: Option<RestreamIpFilter>,
+
+    /// Tuning of the SRS server for this `Restream`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srs_tuning: Option<RestreamSrsTuning>,
+
+    /// Token required to publish a live stream to this `Restream` by an
+    /// external client.
+    ///
+    /// If set, then the publishing URL should contain it as `?token=<value>`
+    /// query parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_token: Option<PublishToken>,
+
+    /// Duration (in minutes) this `Restream`'s `Input` may stay offline while
+    /// being enabled, before it's disabled automatically.
+    ///
+    /// `0` means that this `Restream`'s `Input` is never disabled
+    /// automatically, while `null` means that the server-wide setting is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<i32>,
 }
 
 impl Restream {
+    /// Maximum allowed [`Restream::idle_timeout`] (in minutes).
+    pub const MAX_IDLE_TIMEOUT: i32 = 30 * 24 * 60;
+
     /// Creates a new [`Restream`] out of the given [`spec::v1::Restream`].
     #[inline]
     #[must_use]
@@ -48,9 +108,16 @@ impl Restream {
         Self {
             id: RestreamId::random(),
             key: spec.key,
+            tenant: spec.tenant,
             label: spec.label,
             input: Input::new(spec.input),
             outputs: spec.outputs.into_iter().map(Output::new).collect(),
+            webhook: spec.webhook.map(|w| RestreamWebhook::new(w, None)),
+            fallback: spec.fallback.map(RestreamFallback::new),
+            ip_filter: spec.ip_filter.map(RestreamIpFilter::new),
+            srs_tuning: spec.srs_tuning.map(RestreamSrsTuning::new),
+            publish_token: spec.publish_token,
+            idle_timeout: spec.idle_timeout,
         }
     }
 
@@ -59,10 +126,36 @@ impl Restream {
     /// If `replace` is `true` then all the [`Restream::outputs`] will be
     /// replaced with new ones, otherwise new ones will be merged with already
     /// existing [`Restream::outputs`].
+    ///
+    /// [`Restream::webhook`], [`Restream::fallback`], [`Restream::ip_filter`],
+    /// [`Restream::srs_tuning`], [`Restream::publish_token`] and
+    /// [`Restream::idle_timeout`] are left untouched if the given spec has
+    /// none and `replace` is `false`.
     pub fn apply(&mut self, new: spec::v1::Restream, replace: bool) {
         self.key = new.key;
+        self.tenant = new.tenant;
         self.label = new.label;
-        self.input.apply(new.input);
+        self.input.apply(new.input, replace);
+        if replace || new.webhook.is_some() {
+            self.webhook = new
+                .webhook
+                .map(|w| RestreamWebhook::new(w, self.webhook.as_ref()));
+        }
+        if replace || new.fallback.is_some() {
+            self.fallback = new.fallback.map(RestreamFallback::new);
+        }
+        if replace || new.ip_filter.is_some() {
+            self.ip_filter = new.ip_filter.map(RestreamIpFilter::new);
+        }
+        if replace || new.srs_tuning.is_some() {
+            self.srs_tuning = new.srs_tuning.map(RestreamSrsTuning::new);
+        }
+        if replace || new.publish_token.is_some() {
+            self.publish_token = new.publish_token;
+        }
+        if replace || new.idle_timeout.is_some() {
+            self.idle_timeout = new.idle_timeout;
+        }
         if replace {
             let mut olds = mem::replace(
                 &mut self.outputs,
@@ -101,12 +194,63 @@ impl Restream {
         spec::v1::Restream {
             id: Some(self.id),
             key: self.key.clone(),
+            tenant: self.tenant.clone(),
             label: self.label.clone(),
             input: self.input.export(),
             outputs: self.outputs.iter().map(Output::export).collect(),
+            webhook: self.webhook.as_ref().map(RestreamWebhook::export),
+            fallback: self.fallback.as_ref().map(RestreamFallback::export),
+            ip_filter: self.ip_filter.as_ref().map(RestreamIpFilter::export),
+            srs_tuning: self.srs_tuning.as_ref().map(RestreamSrsTuning::export),
+            publish_token: self.publish_token.clone(),
+            idle_timeout: self.idle_timeout,
         }
     }
 
+    /// Copies the secrets (masked in an exported [`spec::v1::Restream`]) of
+    /// the given `orig`inal [`Restream`] into this one created from its
+    /// exported spec.
+    pub fn copy_secrets(&mut self, orig: &Self) {
+        if let (Some(webhook), Some(orig)) =
+            (self.webhook.as_mut(), orig.webhook.as_ref())
+        {
+            webhook.secret = orig.secret.clone();
+        }
+        for (output, orig) in self.outputs.iter_mut().zip(&orig.outputs) {
+            output.copy_secrets(orig);
+        }
+    }
+
+    /// Indicates whether the given `timeout` (in minutes) is allowed to be a
+    /// [`Restream::idle_timeout`].
+    #[inline]
+    #[must_use]
+    pub fn is_valid_idle_timeout(timeout: i32) -> bool {
+        (0..=Self::MAX_IDLE_TIMEOUT).contains(&timeout)
+    }
+
+    /// Returns name of the [SRS] app serving endpoints of this [`Restream`],
+    /// prefixed with its [`Restream::tenant`] (if any).
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn srs_app(&self) -> String {
+        match &self.tenant {
+            Some(tenant) => format!("{tenant}.{}", self.key),
+            None => self.key.to_string(),
+        }
+    }
+
+    /// Indicates whether this [`Restream`] is visible to the given `tenant`
+    /// credentials.
+    ///
+    /// All [`Restream`]s are visible if no `tenant` is specified.
+    #[inline]
+    #[must_use]
+    pub fn is_visible_to(&self, tenant: Option<&TenantKey>) -> bool {
+        tenant.map_or(true, |t| self.tenant.as_ref() == Some(t))
+    }
+
     /// Returns an URL on a local [SRS] server of the endpoint representing a
     /// main [`Input`] in this [`Restream`].
     ///
@@ -117,7 +261,9 @@ impl Restream {
     /// [SRS]: https://github.com/ossrs/srs
     pub fn main_input_rtmp_endpoint_url(&self) -> anyhow::Result<Url> {
         match self.input.endpoints.iter().find(|e| e.is_rtmp()) {
-            Some(main) => Ok(main.kind.rtmp_url(&self.key, &self.input.key)),
+            Some(main) => {
+                Ok(main.kind.rtmp_url(&self.srs_app(), &self.input.key))
+            }
             None => Err(anyhow!("Not found any RTMP endpoint")),
         }
     }
@@ -133,6 +279,7 @@ impl Restream {
     Eq,
     From,
     GraphQLScalar,
+    Hash,
     Into,
     PartialEq,
     Serialize,
@@ -195,3 +342,94 @@ impl PartialEq<str> for RestreamKey {
         self.0 == other
     }
 }
+
+/// Key of a tenant owning `Restream`s.
+#[derive(
+    Clone,
+    Debug,
+    Deref,
+    Display,
+    Eq,
+    GraphQLScalar,
+    Hash,
+    Into,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+#[graphql(transparent)]
+pub struct TenantKey(String);
+
+impl TenantKey {
+    /// Creates a new [`TenantKey`] if the given value meets its invariants.
+    #[must_use]
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
+        static REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new("^[a-z0-9_-]{1,20}$").unwrap());
+
+        let val = val.into();
+        REGEX.is_match(&val).then(|| Self(val.into_owned()))
+    }
+}
+
+impl<'de> Deserialize<'de> for TenantKey {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("Not a valid Restream.tenant"))
+    }
+}
+
+/// Token of a [`Restream`] required to publish a live stream to it by an
+/// external client.
+#[derive(
+    Clone, Debug, Deref, Display, Eq, GraphQLScalar, Into, PartialEq, Serialize,
+)]
+#[graphql(transparent)]
+pub struct PublishToken(String);
+
+impl PublishToken {
+    /// Creates a new [`PublishToken`] if the given value meets its invariants.
+    #[must_use]
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
+        static REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new("^[A-Za-z0-9_-]{8,64}$").unwrap());
+
+        let val = val.into();
+        REGEX.is_match(&val).then(|| Self(val.into_owned()))
+    }
+
+    /// Generates a new random [`PublishToken`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+
+    /// Indicates whether the given `param` of an [SRS] callback contains this
+    /// [`PublishToken`] as `token` query parameter.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn is_passed(&self, param: Option<&str>) -> bool {
+        let query = param.unwrap_or_default().trim_start_matches('?');
+        url::form_urlencoded::parse(query.as_bytes())
+            .any(|(k, v)| k == "token" && self.0 == v)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublishToken {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?).ok_or_else(|| {
+            D::Error::custom("Not a valid Restream.publish_token")
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! Fallback source of a `Restream` to be broadcast while its `Input` is
+//! offline.
+
+use std::path::{Component, Path, PathBuf};
+
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+
+use crate::spec;
+
+/// Fallback source (slate) of a `Restream` to be broadcast to its `Output`s
+/// while its `Input` is offline, so downstream platforms don't show a black
+/// screen.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct RestreamFallback {
+    /// Kind of this `RestreamFallback`.
+    pub kind: FallbackKind,
+
+    /// Path to a local file to be broadcast, relative to the server's media
+    /// directory.
+    ///
+    /// Required for `IMAGE` and `FILE` kinds, and ignored for `COLOR_BARS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl RestreamFallback {
+    /// Creates a new [`RestreamFallback`] out of the given
+    /// [`spec::v1::RestreamFallback`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::RestreamFallback) -> Self {
+        Self {
+            kind: spec.kind,
+            file: spec.file,
+        }
+    }
+
+    /// Exports this [`RestreamFallback`] as a [`spec::v1::RestreamFallback`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::RestreamFallback {
+        spec::v1::RestreamFallback {
+            kind: self.kind,
+            file: self.file.clone(),
+        }
+    }
+
+    /// Checks whether the given `file` is a valid [`RestreamFallback::file`],
+    /// being a relative path which cannot escape the media directory.
+    #[must_use]
+    pub fn is_valid_file(file: &str) -> bool {
+        let path = Path::new(file);
+        path.components().next().is_some()
+            && path.components().all(|c| matches!(c, Component::Normal(_)))
+    }
+
+    /// Resolves the [`RestreamFallback::file`] into its path inside the given
+    /// media `dir`.
+    ///
+    /// Returns [`None`] if there is no [`RestreamFallback::file`], or it's not
+    /// a valid one (see [`RestreamFallback::is_valid_file()`]).
+    #[must_use]
+    pub fn file_path(&self, dir: &Path) -> Option<PathBuf> {
+        self.file
+            .as_deref()
+            .filter(|f| Self::is_valid_file(f))
+            .map(|f| dir.join(f))
+    }
+}
+
+/// Kind of a `RestreamFallback`.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackKind {
+    /// Static image looped endlessly along with a silent audio.
+    Image,
+
+    /// Pre-recorded video file looped endlessly.
+    File,
+
+    /// [SMPTE color bars][1] generated along with a silent audio.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/SMPTE_color_bars
+    ColorBars,
+}
+
+impl FallbackKind {
+    /// Indicates whether this [`FallbackKind`] requires a
+    /// [`RestreamFallback::file`] to be specified.
+    #[inline]
+    #[must_use]
+    pub fn requires_file(self) -> bool {
+        matches!(self, Self::Image | Self::File)
+    }
+}
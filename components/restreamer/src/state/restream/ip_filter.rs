@@ -0,0 +1,151 @@
+//! Filter of IP addresses allowed to publish a live stream to a `Restream`.
+
+use std::{borrow::Cow, net::IpAddr, str::FromStr};
+
+use derive_more::Display;
+use ipnet::IpNet;
+use juniper::{
+    GraphQLObject, GraphQLScalar, InputValue, ParseScalarResult,
+    ParseScalarValue, ScalarToken, ScalarValue, Value,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::spec;
+
+/// Filter of IP addresses allowed to publish a live stream to a `Restream`.
+///
+/// Loopback addresses are always allowed, as local processes use them.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct RestreamIpFilter {
+    /// Networks allowed to publish a live stream.
+    ///
+    /// If empty, then any network not listed in `deny` is allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<IpCidr>,
+
+    /// Networks denied to connect to a `Restream` at all.
+    ///
+    /// Takes precedence over `allow`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<IpCidr>,
+}
+
+impl RestreamIpFilter {
+    /// Creates a new [`RestreamIpFilter`] out of the given
+    /// [`spec::v1::RestreamIpFilter`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::RestreamIpFilter) -> Self {
+        Self {
+            allow: spec.allow,
+            deny: spec.deny,
+        }
+    }
+
+    /// Exports this [`RestreamIpFilter`] as a [`spec::v1::RestreamIpFilter`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::RestreamIpFilter {
+        spec::v1::RestreamIpFilter {
+            allow: self.allow.clone(),
+            deny: self.deny.clone(),
+        }
+    }
+
+    /// Indicates whether the given `ip` is listed in
+    /// [`RestreamIpFilter::deny`].
+    #[must_use]
+    pub fn is_denied(&self, ip: IpAddr) -> bool {
+        !ip.is_loopback() && self.deny.iter().any(|n| n.contains(ip))
+    }
+
+    /// Indicates whether the given `ip` is allowed to publish a live stream.
+    #[must_use]
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        if ip.is_loopback() {
+            return true;
+        }
+        !self.is_denied(ip)
+            && (self.allow.is_empty()
+                || self.allow.iter().any(|n| n.contains(ip)))
+    }
+}
+
+/// Network of IP addresses in [CIDR notation][1] (like `192.168.0.0/16`).
+///
+/// A single IP address (without a prefix length) is accepted as well.
+///
+/// [1]: https://en.wikipedia.org/wiki/Classless_Inter-Domain_Routing
+#[derive(Clone, Copy, Debug, Display, Eq, GraphQLScalar, Hash, PartialEq)]
+#[graphql(with = Self)]
+pub struct IpCidr(IpNet);
+
+impl IpCidr {
+    /// Indicates whether the given `ip` belongs to this [`IpCidr`].
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.contains(&ip)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn to_output<S: ScalarValue>(&self) -> Value<S> {
+        Value::scalar(self.0.to_string())
+    }
+
+    fn from_input<S>(v: &InputValue<S>) -> Result<Self, String>
+    where
+        S: ScalarValue,
+    {
+        v.as_string_value()
+            .ok_or_else(|| "IpCidr should be a string".to_owned())?
+            .parse()
+            .map_err(|e| format!("Not a valid IpCidr: {e}"))
+    }
+
+    fn parse_token<S>(value: ScalarToken<'_>) -> ParseScalarResult<S>
+    where
+        S: ScalarValue,
+    {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = ipnet::AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let net = s.parse::<IpNet>().or_else(|e| {
+            let ip = s.parse::<IpAddr>().map_err(|_| e)?;
+            let prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+            Ok(IpNet::new(ip, prefix_len)
+                .expect("Max prefix length is always valid"))
+        })?;
+        Ok(Self(net.trunc()))
+    }
+}
+
+impl Serialize for IpCidr {
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Cow<'_, str>>::deserialize(deserializer)?
+            .parse()
+            .map_err(|e| D::Error::custom(format!("Not a valid IpCidr: {e}")))
+    }
+}
@@ -0,0 +1,78 @@
+//! Tuning of the [SRS] server for a `Restream`.
+//!
+//! [SRS]: https://github.com/ossrs/srs
+
+use std::ops::RangeInclusive;
+
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::{serde::is_false, spec};
+
+/// Tuning of the [SRS] server for a `Restream`, overriding its server-wide
+/// configuration.
+///
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct RestreamSrsTuning {
+    /// Indicator whether live streams received by `Input`s of a `Restream`
+    /// should be recorded by SRS into FLV files.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dvr: bool,
+
+    /// Duration (in seconds) of HLS fragments produced by HLS endpoints of a
+    /// `Restream`.
+    ///
+    /// `null` means the SRS default (10 seconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_fragment: Option<i32>,
+
+    /// Indicator whether the latest GOP of live streams is cached for HLS
+    /// endpoints of a `Restream`, so their players (and transcoding
+    /// processes) start instantly.
+    ///
+    /// `null` means the SRS default (enabled).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gop_cache: Option<bool>,
+}
+
+impl RestreamSrsTuning {
+    /// Allowed range of [`RestreamSrsTuning::hls_fragment`] (in seconds).
+    pub const HLS_FRAGMENT_RANGE: RangeInclusive<i32> = 1..=60;
+
+    /// Creates a new [`RestreamSrsTuning`] out of the given
+    /// [`spec::v1::RestreamSrsTuning`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::RestreamSrsTuning) -> Self {
+        Self {
+            dvr: spec.dvr,
+            hls_fragment: spec.hls_fragment,
+            gop_cache: spec.gop_cache,
+        }
+    }
+
+    /// Exports this [`RestreamSrsTuning`] as a
+    /// [`spec::v1::RestreamSrsTuning`].
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::RestreamSrsTuning {
+        spec::v1::RestreamSrsTuning {
+            dvr: self.dvr,
+            hls_fragment: self.hls_fragment,
+            gop_cache: self.gop_cache,
+        }
+    }
+
+    /// Indicates whether this [`RestreamSrsTuning`] requires a dedicated
+    /// [SRS] vhost for HLS endpoints of its `Restream`.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn has_hls_vhost(&self) -> bool {
+        self.hls_fragment.is_some() || self.gop_cache.is_some()
+    }
+}
@@ -0,0 +1,133 @@
+//! Outbound webhook of a `Restream` notifying third-party automations about
+//! its live stream events.
+
+use chrono::{DateTime, Utc};
+use juniper::{graphql_object, GraphQLEnum, GraphQLObject};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::spec;
+
+/// Outbound webhook of a `Restream`, called when its `Input` goes online or
+/// offline.
+///
+/// Requests are signed with [HMAC]-SHA256 of their body, if the `secret` is
+/// specified. The secret is never exposed via GraphQL API and is masked in
+/// exported specs.
+///
+/// [HMAC]: https://en.wikipedia.org/wiki/HMAC
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamWebhook {
+    /// URL to send `POST` requests with [`WebhookEvent`]s to.
+    pub url: Url,
+
+    /// Secret to sign requests with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Log of the latest deliveries of this [`RestreamWebhook`].
+    #[serde(skip)]
+    pub deliveries: Vec<WebhookDelivery>,
+}
+
+impl RestreamWebhook {
+    /// Placeholder used instead of secret values in exported specs.
+    pub const MASK: &'static str = "********";
+
+    /// Maximum number of [`WebhookDelivery`]s kept in
+    /// [`RestreamWebhook::deliveries`].
+    pub const MAX_DELIVERIES: usize = 20;
+
+    /// Creates a new [`RestreamWebhook`] out of the given
+    /// [`spec::v1::RestreamWebhook`].
+    ///
+    /// Masked secret is taken from the `prev` [`RestreamWebhook`], if any.
+    #[must_use]
+    pub fn new(spec: spec::v1::RestreamWebhook, prev: Option<&Self>) -> Self {
+        let secret = if spec.secret.as_deref() == Some(Self::MASK) {
+            prev.and_then(|p| p.secret.clone())
+        } else {
+            spec.secret
+        };
+        Self {
+            url: spec.url,
+            secret,
+            deliveries: prev.map(|p| p.deliveries.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Exports this [`RestreamWebhook`] as a [`spec::v1::RestreamWebhook`],
+    /// masking its secret.
+    #[inline]
+    #[must_use]
+    pub fn export(&self) -> spec::v1::RestreamWebhook {
+        spec::v1::RestreamWebhook {
+            url: self.url.clone(),
+            secret: self.secret.as_ref().map(|_| Self::MASK.to_owned()),
+        }
+    }
+
+    /// Records the given [`WebhookDelivery`] into the
+    /// [`RestreamWebhook::deliveries`] log, evicting the oldest ones.
+    pub fn record(&mut self, delivery: WebhookDelivery) {
+        self.deliveries.push(delivery);
+        if self.deliveries.len() > Self::MAX_DELIVERIES {
+            drop(
+                self.deliveries
+                    .drain(..self.deliveries.len() - Self::MAX_DELIVERIES),
+            );
+        }
+    }
+}
+
+#[graphql_object]
+impl RestreamWebhook {
+    /// URL to send `POST` requests with events to.
+    fn url(&self) -> String {
+        self.url.to_string()
+    }
+
+    /// Indicator whether requests are signed with a secret.
+    ///
+    /// The secret itself is never exposed.
+    fn has_secret(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Log of the latest deliveries of this webhook, the newest last.
+    fn deliveries(&self) -> &[WebhookDelivery] {
+        &self.deliveries
+    }
+}
+
+/// Event of a `Restream` sent via its [`RestreamWebhook`].
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// `Input` of the `Restream` went online.
+    InputOnline,
+
+    /// `Input` of the `Restream` went offline.
+    InputOffline,
+}
+
+/// Result of delivering a [`WebhookEvent`] via a [`RestreamWebhook`].
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct WebhookDelivery {
+    /// Delivered [`WebhookEvent`].
+    pub event: WebhookEvent,
+
+    /// Time when the [`WebhookEvent`] has happened.
+    pub at: DateTime<Utc>,
+
+    /// Number of delivery attempts performed.
+    pub attempts: i32,
+
+    /// HTTP status code of the last response, if any was received.
+    pub status_code: Option<i32>,
+
+    /// Error of the last delivery attempt, if it has failed.
+    pub error: Option<String>,
+}
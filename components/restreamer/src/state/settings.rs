@@ -1,5 +1,13 @@
 //! Server's settings.
-use crate::spec;
+use std::collections::BTreeMap;
+
+use crate::{
+    serde::is_default,
+    spec,
+    state::{AlertRule, MixinSrcUrl, RestreamTemplate, TenantKey, Title},
+};
+use derive_more::Display;
+use juniper::GraphQLObject;
 use serde::{Deserialize, Serialize};
 
 /// Server's settings.
@@ -15,10 +23,16 @@ pub struct Settings {
     /// application's public APIs.
     pub password_output_hash: Option<String>,
 
+    /// [`argon2`] hashes of passwords granting read-only access to this
+    /// application's public APIs, limited to `Restream`s of the tenants.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tenant_password_hashes: BTreeMap<TenantKey, String>,
+
     /// Title for the server
     /// It is used for differentiating servers on UI side if multiple servers
     /// are used.
-    pub title: Option<String>,
+    #[serde(default, deserialize_with = "Title::deserialize_sanitized_opt")]
+    pub title: Option<Title>,
 
     /// Whether do we need to confirm deletion of inputs and outputs
     /// If `true` we should confirm deletion, `false` - do not confirm
@@ -27,6 +41,35 @@ pub struct Settings {
     /// Whether do we need to confirm enabling/disabling of inputs or outputs
     /// If `true` we should confirm, `false` - do not confirm
     pub enable_confirmation: Option<bool>,
+
+    /// [`AlertRule`]s of raising alerts about monitored `Client`s on the
+    /// dashboard.
+    #[serde(default = "AlertRule::defaults")]
+    pub alert_rules: Vec<AlertRule>,
+
+    /// Duration (in minutes) an enabled `Input` may stay offline, before it's
+    /// disabled automatically.
+    ///
+    /// `Restream`'s own idle timeout takes precedence over this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<i32>,
+
+    /// [`RestreamTemplate`]s to be merged into `Restream`s.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<RestreamTemplate>,
+
+    /// [`MixinLimits`] of `Output`s on this server.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub mixin_limits: MixinLimits,
+
+    /// Generation of the passwords protecting this application's public APIs,
+    /// bumped on each their change.
+    ///
+    /// Long-living connections (like GraphQL subscriptions) authorized under
+    /// an older generation are terminated, so should be re-established with
+    /// the actual credentials.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub auth_generation: u64,
 }
 
 impl Settings {
@@ -38,6 +81,9 @@ impl Settings {
             delete_confirmation: self.delete_confirmation,
             enable_confirmation: self.enable_confirmation,
             title: self.title.clone(),
+            idle_timeout: self.idle_timeout,
+            templates: self.templates.clone(),
+            mixin_limits: self.mixin_limits,
         }
     }
 
@@ -47,6 +93,9 @@ impl Settings {
         self.title = new.title;
         self.delete_confirmation = new.delete_confirmation;
         self.enable_confirmation = new.enable_confirmation;
+        self.idle_timeout = new.idle_timeout;
+        self.templates = new.templates;
+        self.mixin_limits = new.mixin_limits;
     }
 }
 
@@ -55,9 +104,105 @@ impl Default for Settings {
         Settings {
             password_hash: None,
             password_output_hash: None,
+            tenant_password_hashes: BTreeMap::new(),
             title: None,
             delete_confirmation: Some(true),
             enable_confirmation: Some(true),
+            alert_rules: AlertRule::defaults(),
+            idle_timeout: None,
+            templates: vec![],
+            mixin_limits: MixinLimits::default(),
+            auth_generation: 0,
+        }
+    }
+}
+
+/// Limits of `Mixin`s an `Output` may have.
+///
+/// Mixing is quite CPU-consuming, so these limits may be raised only on
+/// servers powerful enough, up to [`MixinLimits::CEILING`].
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+#[serde(default)]
+pub struct MixinLimits {
+    /// Maximum number of `Mixin`s of a single `Output`.
+    pub max_mixins: i32,
+
+    /// Maximum number of [TeamSpeak] `Mixin`s of a single `Output`.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub max_teamspeak_mixins: i32,
+}
+
+impl MixinLimits {
+    /// Default [`MixinLimits`], suitable for most servers.
+    pub const DEFAULT: Self = Self {
+        max_mixins: 5,
+        max_teamspeak_mixins: 3,
+    };
+
+    /// Absolute ceiling of [`MixinLimits`], which cannot be exceeded on any
+    /// server.
+    pub const CEILING: Self = Self {
+        max_mixins: 16,
+        max_teamspeak_mixins: 8,
+    };
+
+    /// Indicates whether these [`MixinLimits`] are non-negative, consistent
+    /// and don't exceed the [`MixinLimits::CEILING`].
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        (0..=Self::CEILING.max_mixins).contains(&self.max_mixins)
+            && (0..=Self::CEILING.max_teamspeak_mixins)
+                .contains(&self.max_teamspeak_mixins)
+            && self.max_teamspeak_mixins <= self.max_mixins
+    }
+
+    /// Checks whether the given `Mixin` sources of a single `Output` fit into
+    /// these [`MixinLimits`].
+    ///
+    /// # Errors
+    ///
+    /// With [`MixinLimitsError`] if any of these [`MixinLimits`] is exceeded.
+    pub fn check<'a, I>(&self, srcs: I) -> Result<(), MixinLimitsError>
+    where
+        I: IntoIterator<Item = &'a MixinSrcUrl>,
+    {
+        let (mut total, mut ts) = (0, 0);
+        for src in srcs {
+            total += 1;
+            if src.scheme() == "ts" {
+                ts += 1;
+            }
+        }
+        if total > self.max_mixins {
+            return Err(MixinLimitsError::TooMuchMixins(self.max_mixins));
         }
+        if ts > self.max_teamspeak_mixins {
+            return Err(MixinLimitsError::TooMuchTeamspeakMixins(
+                self.max_teamspeak_mixins,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MixinLimits {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
     }
 }
+
+/// Error of exceeding [`MixinLimits`].
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum MixinLimitsError {
+    /// [`MixinLimits::max_mixins`] is exceeded.
+    #[display(fmt = "Maximum {_0} mixing URLs are allowed")]
+    TooMuchMixins(i32),
+
+    /// [`MixinLimits::max_teamspeak_mixins`] is exceeded.
+    #[display(fmt = "Maximum {_0} TeamSpeak URLs are allowed")]
+    TooMuchTeamspeakMixins(i32),
+}
@@ -0,0 +1,64 @@
+//! History of `Status` transitions of `Input`s, `Output`s and `Mixin`s,
+//! allowing to see when and why they went offline.
+
+use chrono::{DateTime, Utc};
+use juniper::GraphQLObject;
+use serde::{Deserialize, Serialize};
+
+use crate::state::Status;
+
+/// Single `Status` transition of an `InputEndpoint`, an `Output` or a
+/// `Mixin`.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct StatusEvent {
+    /// Time when the transition happened.
+    pub at: DateTime<Utc>,
+
+    /// `Status` transitioned into.
+    pub status: Status,
+
+    /// Human-readable reason of the transition, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl StatusEvent {
+    /// Maximum number of [`StatusEvent`]s kept in a history.
+    pub const MAX_HISTORY_LEN: usize = 50;
+
+    /// Maximum number of characters kept in a [`StatusEvent::reason`].
+    pub const MAX_REASON_LEN: usize = 256;
+
+    /// Tracks the transition from the `prev` [`Status`] into the `new` one
+    /// with the given `reason` in the given `history`, evicting the oldest
+    /// [`StatusEvent`]s beyond [`StatusEvent::MAX_HISTORY_LEN`].
+    ///
+    /// Only the first line of the `reason` is kept, as the following ones
+    /// usually contain verbose [FFmpeg] output.
+    ///
+    /// Does nothing if the [`Status`] hasn't changed.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub fn track(
+        history: &mut Vec<Self>,
+        prev: Status,
+        new: Status,
+        reason: Option<&str>,
+    ) {
+        if prev == new {
+            return;
+        }
+        if history.len() >= Self::MAX_HISTORY_LEN {
+            let _ = history.drain(..=history.len() - Self::MAX_HISTORY_LEN);
+        }
+        history.push(Self {
+            at: Utc::now(),
+            status: new,
+            reason: reason
+                .and_then(|r| r.trim().lines().next())
+                .map(|r| r.chars().take(Self::MAX_REASON_LEN).collect()),
+        });
+    }
+}
@@ -0,0 +1,504 @@
+//! Persistence backends of a [`State`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use ephyr_log::log;
+use rusqlite::{params, Connection};
+use serde_json::{Map, Value as Json};
+use tokio::{fs, io::AsyncWriteExt as _, task};
+
+use crate::State;
+
+/// Kind of a [`Storage`] to persist a [`State`] with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StorageKind {
+    /// Whole [`State`] is written into a single JSON file on every change.
+    Json,
+
+    /// [`State`] is written into a [SQLite] database incrementally, only
+    /// updating its changed parts.
+    ///
+    /// [SQLite]: https://sqlite.org
+    Sqlite,
+}
+
+impl fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Sqlite => "sqlite",
+        })
+    }
+}
+
+impl FromStr for StorageKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(anyhow!(
+                "'{s}' is invalid state backend, allowed are: json | sqlite",
+            )),
+        }
+    }
+}
+
+/// Storage persisting a [`State`].
+#[derive(Clone, Debug)]
+pub enum Storage {
+    /// JSON file the whole [`State`] is written into.
+    ///
+    /// The file is written atomically, via a temporary file synced to disk
+    /// and renamed into it afterwards, so it's never left half-written, even
+    /// on a power loss.
+    Json(PathBuf),
+
+    /// [SQLite] database the [`State`] is written into incrementally.
+    ///
+    /// [SQLite]: https://sqlite.org
+    Sqlite(Arc<SqliteStorage>),
+}
+
+impl Storage {
+    /// Opens a new [`Storage`] of the given [`StorageKind`].
+    ///
+    /// For [`StorageKind::Sqlite`], the [`State`] stored in the `json_file`
+    /// is migrated into the `db_file`, unless the latter contains any
+    /// [`State`] already. The migrated `json_file` is renamed to have a
+    /// `.migrated` extension, so it's not migrated again.
+    ///
+    /// # Errors
+    ///
+    /// If the [SQLite] database fails to be opened, or the migration fails.
+    ///
+    /// [SQLite]: https://sqlite.org
+    pub async fn open(
+        kind: StorageKind,
+        json_file: &Path,
+        db_file: &Path,
+    ) -> anyhow::Result<Self> {
+        let storage = match kind {
+            StorageKind::Json => {
+                // Pre-create the file, so its writability may be checked.
+                let _ = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .open(json_file)
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to open '{}' file: {}",
+                            json_file.display(),
+                            e,
+                        )
+                    })?;
+                return Ok(Self::Json(json_file.to_owned()));
+            }
+            StorageKind::Sqlite => {
+                let db_file = db_file.to_owned();
+                let db =
+                    task::spawn_blocking(move || SqliteStorage::open(&db_file))
+                        .await??;
+                Self::Sqlite(Arc::new(db))
+            }
+        };
+
+        if storage.load().await?.is_none() {
+            if let Some(state) = Self::Json(json_file.to_owned()).load().await?
+            {
+                storage.save(&state).await?;
+                let mut migrated = json_file.as_os_str().to_owned();
+                migrated.push(".migrated");
+                fs::rename(json_file, &migrated).await.map_err(|e| {
+                    anyhow!(
+                        "Failed to rename migrated '{}' file: {}",
+                        json_file.display(),
+                        e,
+                    )
+                })?;
+                log::info!(
+                    "Migrated server state from '{}' into '{}'",
+                    json_file.display(),
+                    db_file.display(),
+                );
+            }
+        }
+
+        Ok(storage)
+    }
+
+    /// Loads a [`State`] from this [`Storage`].
+    ///
+    /// Returns [`None`] if this [`Storage`] contains no [`State`] yet.
+    ///
+    /// # Errors
+    ///
+    /// If this [`Storage`] fails to be read, or its contents fail to be
+    /// deserialized.
+    pub async fn load(&self) -> anyhow::Result<Option<State>> {
+        match self {
+            Self::Json(file) => {
+                let contents = match fs::read(file).await {
+                    Ok(c) => c,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        return Ok(None)
+                    }
+                    Err(e) => {
+                        return Err(anyhow!(
+                            "Failed to read '{}' file: {}",
+                            file.display(),
+                            e,
+                        ))
+                    }
+                };
+                if contents.is_empty() {
+                    return Ok(None);
+                }
+                serde_json::from_slice(&contents).map(Some).map_err(|e| {
+                    anyhow!(
+                        "Failed to deserialize state from '{}' file: {}",
+                        file.display(),
+                        e,
+                    )
+                })
+            }
+            Self::Sqlite(db) => {
+                let db = Arc::clone(db);
+                task::spawn_blocking(move || db.load()).await?
+            }
+        }
+    }
+
+    /// Saves the given [`State`] into this [`Storage`].
+    ///
+    /// # Errors
+    ///
+    /// If this [`Storage`] fails to be written.
+    pub async fn save(&self, state: &State) -> anyhow::Result<()> {
+        let contents = serde_json::to_value(state)
+            .expect("Failed to serialize server state");
+        match self {
+            Self::Json(file) => {
+                let mut tmp = file.as_os_str().to_owned();
+                tmp.push(".tmp");
+                write_synced(Path::new(&tmp), contents.to_string().as_bytes())
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to write '{}' file: {}",
+                            Path::new(&tmp).display(),
+                            e,
+                        )
+                    })?;
+                fs::rename(&tmp, file).await.map_err(|e| {
+                    anyhow!(
+                        "Failed to replace '{}' file: {}",
+                        file.display(),
+                        e
+                    )
+                })?;
+                sync_dir(file).await.map_err(|e| {
+                    anyhow!(
+                        "Failed to sync directory of '{}' file: {}",
+                        file.display(),
+                        e
+                    )
+                })
+            }
+            Self::Sqlite(db) => {
+                let db = Arc::clone(db);
+                task::spawn_blocking(move || db.save(contents)).await?
+            }
+        }
+    }
+
+    /// Returns path to the file backing this [`Storage`].
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Json(file) => file,
+            Self::Sqlite(db) => &db.path,
+        }
+    }
+}
+
+/// Writes the given `contents` into the given `file`, and syncs it to disk.
+async fn write_synced(file: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut f = fs::File::create(file).await?;
+    f.write_all(contents).await?;
+    f.sync_all().await
+}
+
+/// Syncs to disk the directory containing the given `file`, so its renaming
+/// is persisted.
+#[cfg(unix)]
+async fn sync_dir(file: &Path) -> std::io::Result<()> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir).await?.sync_all().await
+}
+
+/// Does nothing, as directories cannot be opened for syncing on non-Unix
+/// platforms.
+#[cfg(not(unix))]
+#[allow(clippy::unused_async)]
+async fn sync_dir(_: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// [SQLite] database persisting a [`State`].
+///
+/// Every `Restream` is stored in a separate row, while the rest top-level
+/// fields of a [`State`] are stored in a row per each. Only the rows changed
+/// since the last save are written.
+///
+/// [SQLite]: https://sqlite.org
+#[derive(Debug)]
+pub struct SqliteStorage {
+    /// Path to the [SQLite] database file.
+    ///
+    /// [SQLite]: https://sqlite.org
+    path: PathBuf,
+
+    /// Connection to the [SQLite] database along with the rows written into
+    /// it by the last save.
+    ///
+    /// [SQLite]: https://sqlite.org
+    inner: Mutex<(Connection, Rows)>,
+}
+
+/// Rows of a [`SqliteStorage`] identified by their table and key.
+type Rows = HashMap<(&'static str, String), String>;
+
+impl SqliteStorage {
+    /// Opens the [SQLite] database at the given `path`, creating it and its
+    /// schema if not exists.
+    ///
+    /// # Errors
+    ///
+    /// If the [SQLite] database fails to be opened or initialized.
+    ///
+    /// [SQLite]: https://sqlite.org
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path).map_err(|e| {
+            anyhow!("Failed to open '{}' database: {}", path.display(), e)
+        })?;
+        // WAL keeps the database consistent on a power loss, while `NORMAL`
+        // synchronization is safe enough in this mode.
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS state (
+                 field TEXT PRIMARY KEY NOT NULL,
+                 value TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS restreams (
+                 id TEXT PRIMARY KEY NOT NULL,
+                 position INTEGER NOT NULL,
+                 value TEXT NOT NULL
+             );",
+        )
+        .map_err(|e| {
+            anyhow!("Failed to initialize '{}' database: {}", path.display(), e)
+        })?;
+        Ok(Self {
+            path: path.to_owned(),
+            inner: Mutex::new((conn, Rows::new())),
+        })
+    }
+
+    /// Loads a [`State`] from this [`SqliteStorage`], remembering its rows as
+    /// written ones.
+    ///
+    /// Returns [`None`] if the database contains no [`State`] yet.
+    ///
+    /// # Errors
+    ///
+    /// If the database fails to be read, or its rows fail to be deserialized.
+    fn load(&self) -> anyhow::Result<Option<State>> {
+        let mut inner = self.inner.lock().unwrap();
+        let (conn, written) = &mut *inner;
+
+        let mut rows = Rows::new();
+        let mut fields = Map::new();
+        let mut stmt = conn.prepare("SELECT field, value FROM state")?;
+        for row in stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))? {
+            let (field, value): (String, String) = row?;
+            let _ = fields.insert(field.clone(), serde_json::from_str(&value)?);
+            let _ = rows.insert(("state", field), value);
+        }
+        drop(stmt);
+
+        let mut restreams = Vec::new();
+        let mut stmt = conn.prepare(
+            "SELECT id, position, value FROM restreams ORDER BY position",
+        )?;
+        let query =
+            stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+        for row in query {
+            let (id, position, value): (String, i64, String) = row?;
+            restreams.push(serde_json::from_str::<Json>(&value)?);
+            let _ =
+                rows.insert(("restreams", id), format!("{position}:{value}"));
+        }
+        drop(stmt);
+
+        if fields.is_empty() && restreams.is_empty() {
+            return Ok(None);
+        }
+        let _ = fields.insert("restreams".into(), Json::Array(restreams));
+
+        let state =
+            serde_json::from_value(Json::Object(fields)).map_err(|e| {
+                anyhow!(
+                    "Failed to deserialize state from '{}' database: {}",
+                    self.path.display(),
+                    e,
+                )
+            })?;
+        *written = rows;
+        Ok(Some(state))
+    }
+
+    /// Saves the given serialized [`State`] into this [`SqliteStorage`] in a
+    /// single transaction, writing only the rows changed since the last save.
+    ///
+    /// # Errors
+    ///
+    /// If the database fails to be written.
+    fn save(&self, state: Json) -> anyhow::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let (conn, written) = &mut *inner;
+
+        let mut fields = match state {
+            Json::Object(fields) => fields,
+            _ => return Err(anyhow!("Server state is not a JSON object")),
+        };
+        let restreams = match fields.remove("restreams") {
+            Some(Json::Array(restreams)) => restreams,
+            _ => Vec::new(),
+        };
+
+        let mut rows = Rows::with_capacity(fields.len() + restreams.len());
+        let tx = conn.transaction()?;
+        for (field, value) in fields {
+            let value = value.to_string();
+            let key = ("state", field);
+            if written.get(&key) != Some(&value) {
+                let _ = tx.execute(
+                    "INSERT OR REPLACE INTO state (field, value)
+                     VALUES (?1, ?2)",
+                    params![key.1, value],
+                )?;
+            }
+            let _ = rows.insert(key, value);
+        }
+        for (n, restream) in restreams.into_iter().enumerate() {
+            let id = restream["id"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Restream has no ID"))?
+                .to_owned();
+            // Position is stored along, so reordering rewrites the row too.
+            let value = format!("{n}:{restream}");
+            let key = ("restreams", id);
+            if written.get(&key) != Some(&value) {
+                let _ = tx.execute(
+                    "INSERT OR REPLACE INTO restreams (id, position, value)
+                     VALUES (?1, ?2, ?3)",
+                    params![key.1, n, restream.to_string()],
+                )?;
+            }
+            let _ = rows.insert(key, value);
+        }
+        for (table, key) in written.keys().filter(|k| !rows.contains_key(k)) {
+            let sql = if *table == "state" {
+                "DELETE FROM state WHERE field = ?1"
+            } else {
+                "DELETE FROM restreams WHERE id = ?1"
+            };
+            let _ = tx.execute(sql, params![key])?;
+        }
+        tx.commit()?;
+
+        *written = rows;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod storage_spec {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::{spec, State};
+
+    use super::{Storage, StorageKind};
+
+    /// Creates a new [`State`] with `Restream`s of the given `keys`.
+    fn state_with_restreams(keys: &[&str]) -> State {
+        let state = State::default();
+        for key in keys {
+            state
+                .add_restream(
+                    serde_json::from_value::<spec::v1::Restream>(json!({
+                        "key": key,
+                        "input": {
+                            "key": "primary",
+                            "endpoints": [{"kind": "rtmp"}],
+                        },
+                    }))
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+        state
+    }
+
+    #[tokio::test]
+    async fn migrates_json_and_saves_incrementally() {
+        let dir =
+            std::env::temp_dir().join(format!("ephyr_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (json_file, db_file) = (dir.join("state.json"), dir.join("db"));
+
+        let state = state_with_restreams(&["first", "second"]);
+        Storage::Json(json_file.clone()).save(&state).await.unwrap();
+
+        let storage = Storage::open(StorageKind::Sqlite, &json_file, &db_file)
+            .await
+            .unwrap();
+        assert!(!json_file.exists(), "JSON state is not migrated");
+        let loaded = storage.load().await.unwrap().unwrap();
+        let keys = |s: &State| {
+            s.restreams
+                .lock_ref()
+                .iter()
+                .map(|r| r.key.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&loaded), ["first", "second"]);
+
+        let first = loaded.restreams.lock_ref()[0].id;
+        let _ = loaded.remove_restream(first);
+        storage.save(&loaded).await.unwrap();
+
+        let reopened = Storage::open(StorageKind::Sqlite, &json_file, &db_file)
+            .await
+            .unwrap();
+        let reloaded = reopened.load().await.unwrap().unwrap();
+        assert_eq!(keys(&reloaded), ["second"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+//! Named partial specs of `Restream`s stored on the server, to be merged into
+//! existing `Restream`s.
+
+use juniper::graphql_object;
+use serde::{Deserialize, Serialize};
+
+use crate::{spec, state::Label};
+
+/// Named partial spec of a `Restream` (like a set of `Output`s with standard
+/// `Mixin`s), to be merged into existing `Restream`s.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestreamTemplate {
+    /// Unique name of this [`RestreamTemplate`].
+    pub name: Label,
+
+    /// Partial spec of a `Restream` defined by this [`RestreamTemplate`].
+    pub spec: spec::v1::RestreamTemplate,
+}
+
+#[graphql_object]
+impl RestreamTemplate {
+    /// Unique name of this `RestreamTemplate`.
+    fn name(&self) -> &Label {
+        &self.name
+    }
+
+    /// Partial JSON spec of a `Restream` defined by this `RestreamTemplate`,
+    /// in the same format as `saveTemplate` mutation accepts it.
+    fn spec(&self) -> String {
+        serde_json::to_string(&self.spec)
+            .expect("Failed to serialize RestreamTemplate.spec")
+    }
+}
@@ -0,0 +1,51 @@
+//! Fine-grained watching of `Restream`s.
+
+use std::collections::HashMap;
+
+use futures_signals::signal::{Mutable, Signal};
+
+use super::{Restream, RestreamId};
+
+/// Per-[`Restream`] [`Mutable`]s mirroring `State::restreams`, so subscribers
+/// of a single [`Restream`] are woken up only once it changes, rather than on
+/// any change of any [`Restream`].
+///
+/// [`Mutable`]s are created lazily, on the first subscription to the
+/// [`Restream`], and are kept (emitting [`None`]) after the [`Restream`] is
+/// removed.
+#[derive(Clone, Debug, Default)]
+pub struct RestreamWatchers(
+    Mutable<HashMap<RestreamId, Mutable<Option<Restream>>>>,
+);
+
+impl RestreamWatchers {
+    /// Returns a [`Signal`] of the [`Restream`] with the given `id`, emitting
+    /// [`None`] while there is no such [`Restream`].
+    ///
+    /// The given `restreams` should be the actual `State::restreams`, used to
+    /// initialize the [`Mutable`] of the [`Restream`], if there is none yet.
+    pub fn signal(
+        &self,
+        id: RestreamId,
+        restreams: &[Restream],
+    ) -> impl Signal<Item = Option<Restream>> {
+        self.0
+            .lock_mut()
+            .entry(id)
+            .or_insert_with(|| {
+                Mutable::new(restreams.iter().find(|r| r.id == id).cloned())
+            })
+            .signal_cloned()
+    }
+
+    /// Synchronizes the watched [`Restream`]s with the given actual
+    /// `restreams`, touching only the changed ones.
+    pub fn sync(&self, restreams: &[Restream]) {
+        for (id, watched) in self.0.lock_ref().iter() {
+            let actual = restreams.iter().find(|r| r.id == *id);
+            if watched.lock_ref().as_ref() != actual {
+                watched.set(actual.cloned());
+            }
+        }
+    }
+}
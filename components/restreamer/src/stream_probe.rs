@@ -3,10 +3,46 @@
 //! [FFprobe]: https://ffmpeg.org/ffprobe.html
 
 use anyhow::anyhow;
-use std::process::Stdio;
+use once_cell::sync::Lazy;
+use std::{collections::HashSet, process::Stdio, sync::Mutex};
 use tokio::process::Command;
 use url::Url;
 
+/// [URL]s of live streams being probed at the moment.
+///
+/// [URL]: https://en.wikipedia.org/wiki/URL
+static PROBING: Lazy<Mutex<HashSet<Url>>> = Lazy::new(Mutex::default);
+
+/// Gather information about `rtmp` stream, unless it's being gathered
+/// already by another caller.
+///
+/// Returns [`None`] if the stream is being probed at the moment, so no
+/// additional `ffprobe` process is spawned for it.
+///
+/// # Errors
+///
+/// See [`stream_probe()`].
+///
+/// # Panics
+///
+/// If the lock is poisoned.
+pub async fn stream_probe_once(url: Url) -> Option<anyhow::Result<StreamInfo>> {
+    /// Removes the probed [`Url`] from [`PROBING`] once dropped.
+    struct Guard(Url);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let _ = PROBING.lock().unwrap().remove(&self.0);
+        }
+    }
+
+    if !PROBING.lock().unwrap().insert(url.clone()) {
+        return None;
+    }
+    let guard = Guard(url);
+    Some(stream_probe(guard.0.clone()).await)
+}
+
 /// Gather information about `rtmp` stream
 ///
 /// # Errors
@@ -9,32 +9,29 @@ use std::{
     mem::ManuallyDrop,
     pin::Pin,
     str,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
-    },
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::Duration,
 };
 
-use backoff::{future::retry_notify, ExponentialBackoff};
+use backoff::{backoff::Backoff as _, ExponentialBackoff};
 use byteorder::{BigEndian, ByteOrder as _};
 use derive_more::{Display, Error};
 use ephyr_log::log;
-use futures::{
-    future, ready, sink, FutureExt as _, Stream, StreamExt as _,
-    TryFutureExt as _,
-};
+use futures::{future, ready, sink, FutureExt as _, Stream, StreamExt as _};
 use once_cell::sync::Lazy;
 use rand::Rng as _;
 use tokio::{
     io::{self, AsyncRead},
+    sync::watch,
     task::JoinHandle,
     time,
 };
 use tsclientlib::{DisconnectOptions, StreamItem};
 use tsproto_packets::packets::AudioData;
 
+use crate::state::Status;
+
 pub use tsclientlib::{ConnectOptions as Config, Connection};
 
 /// Handler responsible for decoding, tracking and mixing audio of all
@@ -92,9 +89,10 @@ pub struct Input {
     /// [TeamSpeak]: https://teamspeak.com
     pub conn: Option<(future::AbortHandle, JoinHandle<()>)>,
 
-    /// Indicator whether the spawned [`AudioCapture`] is unable to recover from
-    /// its last error, and so this [`Input`] should return an error too.
-    is_conn_unrecoverable: Arc<AtomicBool>,
+    /// [`Status`] of the connection with [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    status: Arc<watch::Sender<Status>>,
 }
 
 impl Input {
@@ -112,6 +110,11 @@ impl Input {
     pub const FRAME_SIZE: usize =
         Self::SAMPLE_RATE / 1000 * Self::FREQUENCY_MILLIS * Self::CHANNELS;
 
+    /// Maximum delay before reconnecting to [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
     /// Creates a new [`Input`] with the provided [`Config`].
     #[must_use]
     pub fn new<C: Into<Config>>(cfg: C) -> Self {
@@ -149,44 +152,88 @@ impl Input {
             cursor: 0,
             audio: Arc::new(Mutex::new(AudioHandler::new(lgr))),
             conn: None,
-            is_conn_unrecoverable: Arc::new(AtomicBool::default()),
+            status: Arc::new(watch::channel(Status::Offline).0),
         }
     }
 
-    /// Spawns an [`AudioCapture`] associated with this [`Input`], retrying it
-    /// endlessly with an [`ExponentialBackoff`] if it fails in a recoverable
-    /// way.
+    /// Subscribes to [`Status`] changes of the connection with [TeamSpeak]
+    /// server.
+    ///
+    /// The returned receiver is closed once this [`Input`] is dropped.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn status(&self) -> watch::Receiver<Status> {
+        self.status.subscribe()
+    }
+
+    /// Spawns an [`AudioCapture`] associated with this [`Input`], reconnecting
+    /// it endlessly with an [`ExponentialBackoff`] once it fails.
+    ///
+    /// The backoff is reset once the [`AudioCapture`] goes [`Status::Online`],
+    /// so a dropped long-living connection is re-established quickly. Errors
+    /// not being recoverable by a reconnection (like an unsupported codec) are
+    /// reported as [`Status::Offline`] and retried each
+    /// [`Input::MAX_RECONNECT_DELAY`] only.
+    ///
+    /// Silence is emitted while reconnecting, so the [FFmpeg] process mixing
+    /// this [`Input`] keeps running.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
     fn spawn_audio_capturing(&mut self) {
         let cfg = self.cfg.clone();
         let audio = self.audio.clone();
-        let is_conn_unrecoverable = self.is_conn_unrecoverable.clone();
+        let status = self.status.clone();
 
-        let capturing = retry_notify(
-            ExponentialBackoff {
+        let capturing = async move {
+            let mut backoff = ExponentialBackoff {
+                max_interval: Self::MAX_RECONNECT_DELAY,
                 max_elapsed_time: None,
                 ..ExponentialBackoff::default()
-            },
-            move || {
-                AudioCapture::run(cfg.clone(), audio.clone())
-                    .map_err(AudioCaptureError::into_backoff)
-            },
-            |err, dur| {
+            };
+            let _ = status.send_replace(Status::Initializing);
+            loop {
+                let err = AudioCapture::run(
+                    cfg.clone(),
+                    audio.clone(),
+                    status.clone(),
+                )
+                .await
+                .err()
+                .unwrap_or(AudioCaptureError::UnexpectedFinish);
+
+                // Don't replay audio queued before the failure.
+                audio.lock().unwrap().reset();
+                if *status.borrow() == Status::Online {
+                    backoff.reset();
+                }
+                let (delay, new_status) = if err.is_permanent() {
+                    (Self::MAX_RECONNECT_DELAY, Status::Offline)
+                } else {
+                    (
+                        backoff
+                            .next_backoff()
+                            .unwrap_or(Self::MAX_RECONNECT_DELAY),
+                        Status::Unstable,
+                    )
+                };
                 log::error!(
-                    "Backoff TeamSpeak server audio capturing for {} due to \
-                     error: {}",
-                    humantime::format_duration(dur),
+                    "Reconnect to TeamSpeak server in {} due to error: {}",
+                    humantime::format_duration(delay),
                     err,
                 );
-            },
-        )
-        .map_err(move |e| {
-            log::error!("Cannot capture audio from TeamSpeak server: {e}");
-            is_conn_unrecoverable.store(true, Ordering::SeqCst);
-        });
+                let _ = status.send_replace(new_status);
+                time::sleep(delay).await;
+            }
+        };
 
         let (abort, on_abort) = future::AbortHandle::new_pair();
+        let status = self.status.clone();
         let waiter = tokio::spawn(
-            future::Abortable::new(capturing, on_abort).map(|_| ()),
+            future::Abortable::new(capturing, on_abort).map(move |_| {
+                let _ = status.send_replace(Status::Offline);
+            }),
         );
 
         self.conn = Some((abort, waiter));
@@ -208,9 +255,6 @@ impl AsyncRead for Input {
         if self.conn.is_none() {
             self.spawn_audio_capturing();
         }
-        if self.is_conn_unrecoverable.load(Ordering::SeqCst) {
-            return Poll::Ready(Err(InputError::NoData.into()));
-        }
 
         if self.cursor >= self.frame.len() {
             // `time::Interval` stream never returns `None`, so we can omit
@@ -263,7 +307,7 @@ impl fmt::Debug for Input {
             .field("cursor", &self.cursor)
             .field("audio", &"Arc<Mutex<AudioHandler>>")
             .field("conn", &self.conn)
-            .field("is_conn_unrecoverable", &self.is_conn_unrecoverable)
+            .field("status", &self.status)
             .finish()
     }
 }
@@ -288,12 +332,6 @@ impl Drop for Input {
 /// Possible errors of reading [`Input`].
 #[derive(Debug, Display, Error)]
 pub enum InputError {
-    /// No data can be received from [TeamSpeak] server.
-    ///
-    /// [TeamSpeak]: https://teamspeak.com
-    #[display(fmt = "Unable to receive data from TeamSpeak server")]
-    NoData,
-
     /// Input buffer provided to read [`Input`] is too small to read any data.
     #[display(fmt = "Input buffer is too small")]
     TooSmallBuffer,
@@ -304,7 +342,6 @@ impl From<InputError> for io::Error {
         use InputError as E;
 
         let kind = match e {
-            E::NoData => io::ErrorKind::NotConnected,
             E::TooSmallBuffer => io::ErrorKind::InvalidData,
         };
         io::Error::new(kind, e)
@@ -326,19 +363,30 @@ pub struct AudioCapture {
     ///
     /// [TeamSpeak]: https://teamspeak.com
     audio: Arc<Mutex<AudioHandler>>,
+
+    /// [`Status`] of the [`AudioCapture::conn`] to be reported as
+    /// [`Status::Online`] once any event is received from [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    status: Option<Arc<watch::Sender<Status>>>,
 }
 
 impl AudioCapture {
     /// Creates new [`AudioCapture`] from the given [`Connection`] and for
-    /// the given [`AudioHandler`].
+    /// the given [`AudioHandler`], optionally reporting its [`Status`].
     #[inline]
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
-    pub fn new(conn: Connection, audio: Arc<Mutex<AudioHandler>>) -> Self {
+    pub fn new(
+        conn: Connection,
+        audio: Arc<Mutex<AudioHandler>>,
+        status: Option<Arc<watch::Sender<Status>>>,
+    ) -> Self {
         audio.lock().unwrap().reset();
         Self {
             conn: ManuallyDrop::new(conn),
             audio,
+            status,
         }
     }
 
@@ -374,7 +422,7 @@ impl AudioCapture {
     }
 
     /// Creates a new [`AudioCapture`] using the given [`Config`] for the given
-    /// [`AudioHandler`] and awaits its completion.
+    /// [`AudioHandler`] and awaits its completion, reporting its [`Status`].
     ///
     /// Generates new HWID (hardware identification string) to uniquely
     /// distinguish this [`AudioCapture`] for [TeamSpeak] server.
@@ -390,6 +438,7 @@ impl AudioCapture {
     pub async fn run(
         cfg: Config,
         audio: Arc<Mutex<AudioHandler>>,
+        status: Arc<watch::Sender<Status>>,
     ) -> Result<(), AudioCaptureError> {
         log::debug!(
             "Connecting to TeamSpeak server: {}/{:?}",
@@ -400,7 +449,7 @@ impl AudioCapture {
             .hardware_id(Self::new_hwid())
             .connect()
             .map_err(AudioCaptureError::InitializationFailed)?;
-        AudioCapture::new(conn, audio).await
+        AudioCapture::new(conn, audio, Some(status)).await
     }
 }
 
@@ -417,14 +466,16 @@ impl Future for AudioCapture {
         use AudioCaptureError as E;
 
         loop {
-            let audio_packet =
-                match ready!(Pin::new(&mut self.conn.events()).poll_next(cx))
-                    .ok_or_else(|| E::UnexpectedFinish)?
-                    .map_err(E::ConnectionFailed)?
-                {
-                    StreamItem::Audio(packet) => packet,
-                    _ => continue,
-                };
+            let item = ready!(Pin::new(&mut self.conn.events()).poll_next(cx))
+                .ok_or_else(|| E::UnexpectedFinish)?
+                .map_err(E::ConnectionFailed)?;
+            if let Some(status) = self.status.take() {
+                let _ = status.send_replace(Status::Online);
+            }
+            let audio_packet = match item {
+                StreamItem::Audio(packet) => packet,
+                _ => continue,
+            };
 
             let member_id = match audio_packet.data().data() {
                 AudioData::S2C { from, .. }
@@ -511,13 +562,15 @@ pub enum AudioCaptureError {
 }
 
 impl AudioCaptureError {
-    /// Wraps this [`AudioCaptureError`] into a [`backoff::Error`] carefully
-    /// distinguishing transient and permanent errors.
+    /// Indicates whether this [`AudioCaptureError`] is permanent, so cannot be
+    /// recovered by reconnecting to [TeamSpeak] server quickly.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
     #[must_use]
-    pub fn into_backoff(self) -> backoff::Error<Self> {
+    pub fn is_permanent(&self) -> bool {
         use tsclientlib::audio::Error as E;
 
-        let is_permanent = match &self {
+        match self {
             Self::InitializationFailed(_) => true,
             Self::ConnectionFailed(_)
             | Self::UnexpectedFinish
@@ -525,11 +578,6 @@ impl AudioCaptureError {
             Self::DecodingFailed(err) => {
                 matches!(err, E::CreateDecoder(_) | E::UnsupportedCodec(_))
             }
-        };
-        if is_permanent {
-            backoff::Error::Permanent(self)
-        } else {
-            backoff::Error::transient(self)
         }
     }
 }
@@ -0,0 +1,195 @@
+//! Delivering of `Restream` events via their outbound webhooks.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use backoff::{future::retry_notify, ExponentialBackoff};
+use chrono::{DateTime, Utc};
+use ephyr_log::log;
+use futures::future;
+use hmac::{Hmac, Mac as _};
+use serde_json::json;
+use sha2::Sha256;
+use tokio::sync::Mutex;
+
+use crate::{
+    state::{
+        Restream, RestreamId, RestreamWebhook, WebhookDelivery, WebhookEvent,
+    },
+    State,
+};
+
+/// Name of the HTTP header carrying [HMAC]-SHA256 signature of a request body.
+///
+/// [HMAC]: https://en.wikipedia.org/wiki/HMAC
+pub const SIGNATURE_HEADER: &str = "X-Ephyr-Signature";
+
+/// Timeout of a single delivery attempt.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum duration of retrying a single delivery.
+const MAX_RETRYING: Duration = Duration::from_secs(5 * 60);
+
+/// Dispatcher of [`WebhookEvent`]s to [`RestreamWebhook`]s, tracking changes
+/// of `Restream`s' `Input`s.
+#[derive(Debug, Default)]
+pub struct Dispatcher {
+    /// Last known online status of `Restream`s' `Input`s.
+    online: HashMap<RestreamId, bool>,
+}
+
+impl Dispatcher {
+    /// Runs a new [`Dispatcher`] tracking `Restream`s of the given [`State`].
+    pub fn run(state: State) {
+        let mut dispatcher = Self::default();
+        let st = state.clone();
+        State::on_change("dispatch_webhooks", &state.restreams, move |rs| {
+            dispatcher.track(&rs, &st);
+            future::ready(())
+        });
+    }
+
+    /// Tracks the given `restreams` and dispatches [`WebhookEvent`]s for the
+    /// ones, whose `Input`s' status has changed.
+    fn track(&mut self, restreams: &[Restream], state: &State) {
+        let mut online = HashMap::with_capacity(restreams.len());
+        for r in restreams {
+            let is_online = r.input.is_ready_to_serve();
+            let was_online = self.online.get(&r.id).copied().unwrap_or(false);
+            drop(online.insert(r.id, is_online));
+
+            if is_online == was_online {
+                continue;
+            }
+            if let Some(hook) = &r.webhook {
+                let event = if is_online {
+                    WebhookEvent::InputOnline
+                } else {
+                    WebhookEvent::InputOffline
+                };
+                deliver(hook.clone(), event, r, state.clone());
+            }
+        }
+        self.online = online;
+    }
+}
+
+/// Delivers the given [`WebhookEvent`] of the given [`Restream`] via the
+/// given [`RestreamWebhook`] in background, retrying on failures, and records
+/// the result into the [`RestreamWebhook::deliveries`] log.
+fn deliver(
+    hook: RestreamWebhook,
+    event: WebhookEvent,
+    restream: &Restream,
+    state: State,
+) {
+    let at = Utc::now();
+    let body = payload(event, at, restream).to_string();
+    let signature = hook.secret.as_ref().map(|s| sign(s, &body));
+    let id = restream.id;
+
+    drop(tokio::spawn(async move {
+        let attempts = Arc::new(AtomicI32::new(0));
+        let status_code = Arc::new(Mutex::new(None));
+
+        let res = retry_notify(
+            ExponentialBackoff {
+                max_elapsed_time: Some(MAX_RETRYING),
+                ..ExponentialBackoff::default()
+            },
+            || {
+                let (url, body) = (hook.url.clone(), body.clone());
+                let signature = signature.clone();
+                let (attempts, status_code) =
+                    (attempts.clone(), status_code.clone());
+                async move {
+                    let _ = attempts.fetch_add(1, Ordering::SeqCst);
+                    let mut req = reqwest::Client::new()
+                        .post(url)
+                        .timeout(TIMEOUT)
+                        .header("Content-Type", "application/json")
+                        .body(body);
+                    if let Some(sig) = signature {
+                        req = req.header(SIGNATURE_HEADER, sig);
+                    }
+                    let resp = req.send().await.map_err(|e| {
+                        backoff::Error::transient(e.to_string())
+                    })?;
+                    *status_code.lock().await =
+                        Some(i32::from(resp.status().as_u16()));
+                    let _ = resp.error_for_status().map_err(|e| {
+                        backoff::Error::transient(e.to_string())
+                    })?;
+                    Ok(())
+                }
+            },
+            |e, dur| {
+                log::warn!(
+                    "Failed to deliver webhook of Restream {}, retrying in \
+                     {}: {}",
+                    id,
+                    humantime::format_duration(dur),
+                    e,
+                );
+            },
+        )
+        .await;
+        if let Err(e) = &res {
+            log::error!("Failed to deliver webhook of Restream {id}: {e}");
+        }
+
+        let delivery = WebhookDelivery {
+            event,
+            at,
+            attempts: attempts.load(Ordering::SeqCst),
+            status_code: *status_code.lock().await,
+            error: res.err(),
+        };
+        if let Some(hook) = state
+            .restreams
+            .lock_mut()
+            .iter_mut()
+            .find(|r| r.id == id)
+            .and_then(|r| r.webhook.as_mut())
+        {
+            hook.record(delivery);
+        }
+    }));
+}
+
+/// Forms a JSON payload of the given [`WebhookEvent`] of the given
+/// [`Restream`].
+fn payload(
+    event: WebhookEvent,
+    at: DateTime<Utc>,
+    restream: &Restream,
+) -> serde_json::Value {
+    json!({
+        "event": event,
+        "timestamp": at,
+        "restream": {
+            "id": restream.id,
+            "key": restream.key,
+            "label": restream.label,
+        },
+    })
+}
+
+/// Signs the given `body` with [HMAC]-SHA256 using the given `secret`.
+///
+/// Returns the signature in a `sha256=<hex>` form.
+///
+/// [HMAC]: https://en.wikipedia.org/wiki/HMAC
+#[must_use]
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = <Hmac<Sha256>>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}